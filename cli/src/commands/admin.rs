@@ -1,4 +1,7 @@
 use std::fs::remove_file;
+use std::path::Path;
+
+use colored::Colorize;
 
 use env_defs::{CloudProvider, ExtraData};
 use env_utils::{
@@ -6,7 +9,18 @@ use env_utils::{
     store_tf_vars_json, unzip_file,
 };
 
-use crate::current_region_handler;
+use env_common::{
+    interface::GenericCloudHandler,
+    logic::{
+        apply_platform_config, get_audit_events, plan_platform_config, prune_history,
+        read_platform_config, run_break_glass_module, sign_audit_log, verify_audit_log,
+        ConfigAction, ConfigChange, PruneOptions, AUDIT_SIGNING_KEY_ENV,
+    },
+};
+use serde_json::Value;
+
+use super::exit_on_err;
+use crate::{current_region_handler, follow_execution, ClaimJobStruct};
 
 pub async fn handle_setup_workspace(deployment_id: &str, environment_id: &str) {
     let (deployment, _) = current_region_handler()
@@ -84,6 +98,10 @@ pub async fn handle_setup_workspace(deployment_id: &str, environment_id: &str) {
         &deployment.module_track,
         &deployment.drift_detection,
         &ExtraData::None,
+        &handler
+            .get_environment_variables()
+            .await
+            .unwrap_or_default(),
     );
 
     // Filter out git-related variables
@@ -146,3 +164,245 @@ pub async fn handle_get_state(deployment_id: &str, environment_id: &str, output:
         std::process::exit(1);
     }
 }
+
+pub async fn handle_run_module(
+    module: &str,
+    environment: &str,
+    name: &str,
+    vars_file: &str,
+    plan_only: bool,
+    follow: bool,
+) {
+    let (module, version) = match module.split_once('@') {
+        Some((module, version)) if !module.is_empty() && !version.is_empty() => (module, version),
+        _ => {
+            eprintln!("Error: Module must be given as <module>@<version>, e.g. s3bucket@0.1.0");
+            std::process::exit(1);
+        }
+    };
+    let variables: Value = exit_on_err(
+        std::fs::read_to_string(vars_file)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_str(&content)?))
+            .map_err(|e| anyhow::anyhow!("Failed to read variables from {}: {}", vars_file, e)),
+    );
+    let origin = hostname::get()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "cli".to_string());
+    let command = if plan_only { "plan" } else { "apply" };
+
+    println!(
+        "{}",
+        format!(
+            "Break-glass {} of {} version {} in {}, this is recorded for audit",
+            command, module, version, environment
+        )
+        .yellow()
+        .bold()
+    );
+
+    let handler = current_region_handler().await;
+    let (job_id, deployment_id, _) = exit_on_err(
+        run_break_glass_module(
+            &handler,
+            module,
+            version,
+            environment,
+            name,
+            &variables,
+            command,
+            &origin,
+        )
+        .await,
+    );
+    println!(
+        "Started {} job: {} in {} (job id: {})",
+        command, deployment_id, environment, job_id
+    );
+
+    if follow {
+        let job = ClaimJobStruct {
+            job_id,
+            deployment_id,
+            environment: environment.to_string(),
+            region: handler.get_region().to_string(),
+        };
+        if let Err(e) = follow_execution(&[job], command).await {
+            eprintln!("Failed to follow {}: {}", command, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+pub async fn handle_apply_config(file: &str, plan_only: bool) {
+    let manifest = exit_on_err(read_platform_config(file));
+    let base_dir = Path::new(file).parent().unwrap_or(Path::new("."));
+
+    let handler = current_region_handler().await;
+    let central_handler = GenericCloudHandler::central().await;
+
+    let changes =
+        exit_on_err(plan_platform_config(&handler, &central_handler, &manifest, base_dir).await);
+
+    if changes.is_empty() {
+        println!("Platform configuration is up to date, no changes needed");
+        return;
+    }
+
+    println!("Platform configuration changes:\n");
+    for change in &changes {
+        print_config_change(change);
+    }
+    let count = |action| changes.iter().filter(|c| c.action == action).count();
+    println!(
+        "\n{} to create, {} to update, {} to delete",
+        count(ConfigAction::Create),
+        count(ConfigAction::Update),
+        count(ConfigAction::Delete)
+    );
+
+    if plan_only {
+        return;
+    }
+
+    exit_on_err(apply_platform_config(&handler, &central_handler, &changes).await);
+    println!("Platform configuration applied");
+}
+
+pub async fn handle_prune(days: Option<u32>, archive: bool, dry_run: bool) {
+    let handler = current_region_handler().await;
+    let options = PruneOptions {
+        retention_days: days,
+        // The project settings decide unless archiving is turned off explicitly
+        archive: if archive { None } else { Some(false) },
+        dry_run,
+    };
+    let summary = exit_on_err(prune_history(&handler, &options).await);
+
+    let cutoff = env_utils::epoch_to_timestamp(summary.cutoff_epoch);
+    if dry_run {
+        println!(
+            "Would prune {} events and {} change records from before {} in {}",
+            summary.events,
+            summary.change_records,
+            cutoff,
+            handler.get_region()
+        );
+        return;
+    }
+    println!(
+        "Pruned {} events and {} change records from before {} in {}",
+        summary.events,
+        summary.change_records,
+        cutoff,
+        handler.get_region()
+    );
+    if let Some(key) = summary.archive_key {
+        println!("Archived to {}", key);
+    }
+}
+
+pub async fn handle_audit_export(from: &str, to: Option<&str>, output_file: Option<&str>) {
+    let key = exit_on_err(audit_signing_key());
+    let start_epoch = exit_on_err(parse_audit_time(from, false));
+    let end_epoch = match to {
+        Some(to) => exit_on_err(parse_audit_time(to, true)),
+        None => env_utils::get_epoch(),
+    };
+    if start_epoch >= end_epoch {
+        eprintln!("{}", "Error: --from must be before --to".red());
+        std::process::exit(1);
+    }
+
+    let handler = current_region_handler().await;
+    let events = exit_on_err(get_audit_events(&handler, start_epoch, end_epoch).await);
+    let log = exit_on_err(sign_audit_log(&events, &key));
+    match output_file {
+        Some(path) => {
+            exit_on_err(std::fs::write(path, log).map_err(anyhow::Error::from));
+            println!("Exported {} audit events to {}", events.len(), path);
+        }
+        None => print!("{}", log),
+    }
+}
+
+pub fn handle_audit_verify(file: &str) {
+    let key = exit_on_err(audit_signing_key());
+    let log = exit_on_err(std::fs::read_to_string(file).map_err(anyhow::Error::from));
+    let count = exit_on_err(verify_audit_log(&log, &key));
+    println!(
+        "The signatures of all {} audit events in {} are valid",
+        count, file
+    );
+}
+
+fn audit_signing_key() -> anyhow::Result<String> {
+    std::env::var(AUDIT_SIGNING_KEY_ENV)
+        .ok()
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Set {} to the secret the audit log is signed with",
+                AUDIT_SIGNING_KEY_ENV
+            )
+        })
+}
+
+// Epoch in milliseconds of a date (midnight UTC, or the end of the day for `end_of_day`) or of
+// an RFC 3339 timestamp
+fn parse_audit_time(value: &str, end_of_day: bool) -> anyhow::Result<u128> {
+    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.timestamp_millis() as u128);
+    }
+    let date = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        anyhow::anyhow!(
+            "Invalid time '{}', use a date such as 2024-01-31 or an RFC 3339 timestamp",
+            value
+        )
+    })?;
+    let date = if end_of_day {
+        date.succ_opt().unwrap_or(date)
+    } else {
+        date
+    };
+    Ok(date
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp_millis() as u128)
+}
+
+fn print_config_change(change: &ConfigChange) {
+    match change.action {
+        ConfigAction::Create => {
+            println!("{}", format!("+ {} {}", change.kind, change.name).green())
+        }
+        ConfigAction::Delete => {
+            println!("{}", format!("- {} {}", change.kind, change.name).red())
+        }
+        ConfigAction::Update => {
+            println!("{}", format!("~ {} {}", change.kind, change.name).yellow());
+            let empty = serde_json::Map::new();
+            let current = change
+                .current
+                .as_ref()
+                .and_then(Value::as_object)
+                .unwrap_or(&empty);
+            let desired = change
+                .desired
+                .as_ref()
+                .and_then(Value::as_object)
+                .unwrap_or(&empty);
+            let mut fields: Vec<&String> = current.keys().chain(desired.keys()).collect();
+            fields.sort();
+            fields.dedup();
+            for field in fields {
+                let before = current.get(field).unwrap_or(&Value::Null);
+                let after = desired.get(field).unwrap_or(&Value::Null);
+                if before != after {
+                    println!("    {}: {} -> {}", field, before, after);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::Result;
+use colored::Colorize;
+use env_common::logic::{
+    decide_approval, get_pending_approvals, is_queued_job_id, start_approved_job, ApprovalOutcome,
+};
+use env_defs::{ApprovalRequest, CloudProvider};
+use http_client::{http_decide_approval, http_get_pending_approvals, is_http_mode_enabled};
+
+use super::{exit_on_err, print_structured, OutputFormat};
+use crate::current_region_handler;
+
+async fn fetch_pending_approvals(environment: Option<&str>) -> Result<Vec<ApprovalRequest>> {
+    let handler = current_region_handler().await;
+    if is_http_mode_enabled() {
+        let value =
+            http_get_pending_approvals(handler.get_project_id(), handler.get_region(), environment)
+                .await?;
+        Ok(serde_json::from_value(value)?)
+    } else {
+        get_pending_approvals(&handler, environment.unwrap_or_default()).await
+    }
+}
+
+pub async fn handle_list(environment: Option<&str>, output: OutputFormat) {
+    let requests = exit_on_err(fetch_pending_approvals(environment).await);
+    if print_structured(&requests, output) {
+        return;
+    }
+
+    println!(
+        "{:<40} {:<50} {:<10} {:<20} {:<25} {:<10}",
+        "Environment", "Deployment ID", "Command", "Module", "Initiated By", "Approvals",
+    );
+    for request in &requests {
+        println!(
+            "{:<40} {:<50} {:<10} {:<20} {:<25} {:<10}",
+            request.environment,
+            request.deployment_id,
+            request.command,
+            request.module,
+            request.initiated_by,
+            format!(
+                "{}/{}",
+                request.approvers().len(),
+                request.required_approvals
+            ),
+        );
+    }
+}
+
+/// Approves or rejects the job pending approval of the deployment as the current user,
+/// starting it when this was the last approval it needed
+pub async fn handle_decide(environment: &str, deployment_id: &str, comment: &str, approve: bool) {
+    let handler = current_region_handler().await;
+    if is_http_mode_enabled() {
+        let resp = exit_on_err(
+            http_decide_approval(
+                handler.get_project_id(),
+                handler.get_region(),
+                environment,
+                deployment_id,
+                comment,
+                approve,
+            )
+            .await,
+        );
+        let job_id = resp["job_id"].as_str().unwrap_or_default();
+        match resp["status"].as_str().unwrap_or_default() {
+            "approved" if is_queued_job_id(job_id) => println!(
+                "Job {} was approved and queued, the project runs as many jobs as it allows",
+                job_id
+            ),
+            "approved" => println!("Job {} was approved and started", job_id),
+            "rejected" => println!("Job {} was rejected", job_id),
+            _ => println!(
+                "Approved job {}, {} of {} approval(s)",
+                job_id, resp["approvals"], resp["required_approvals"]
+            ),
+        }
+        return;
+    }
+
+    let approver = exit_on_err(handler.get_user_id().await);
+    let outcome = exit_on_err(
+        decide_approval(
+            &handler,
+            deployment_id,
+            environment,
+            &approver,
+            approve,
+            comment,
+        )
+        .await,
+    );
+    match outcome {
+        ApprovalOutcome::Approved(submission) => {
+            let job_id = exit_on_err(start_approved_job(&handler, &submission).await);
+            println!(
+                "{}",
+                format!(
+                    "Approved by {}, {} job {}",
+                    submission.payload.approved_by.join(", "),
+                    if is_queued_job_id(&job_id) {
+                        "queued"
+                    } else {
+                        "started"
+                    },
+                    job_id
+                )
+                .green()
+            );
+        }
+        ApprovalOutcome::Pending(request) => println!(
+            "Approved job {}, {} of {} approval(s)",
+            request.job_id,
+            request.approvers().len(),
+            request.required_approvals
+        ),
+        ApprovalOutcome::Rejected(request) => println!("Job {} was rejected", request.job_id),
+    }
+}
@@ -1,22 +1,66 @@
 use colored::Colorize;
 use env_common::interface::GenericCloudHandler;
-use env_common::logic::{destroy_infra, driftcheck_infra};
-use env_defs::{pretty_print_resource_changes, CloudProvider, DeploymentManifest, ExtraData};
+use env_common::logic::{
+    apply_plan_infra, destroy_infra, driftcheck_infra, get_cascade_destroy_order,
+};
+use env_defs::{
+    environment_matches, pretty_print_resource_changes, CloudProvider, DeploymentManifest,
+    DeploymentResp, DeploymentStatus, ExtraData,
+};
+use http_client::{change_record_url, http_get_deployments, is_http_mode_enabled};
 use log::{error, info};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+use super::{confirm, exit_code_for_error, exit_on_err, print_structured, OutputFormat};
 use crate::run::run_claim_file;
 use crate::utils::current_region_handler;
-use crate::{follow_driftcheck, follow_execution, ClaimJobStruct};
+use crate::{follow_driftcheck, follow_execution, follow_job_status, ClaimJobStruct, JobChanges};
 
-pub async fn handle_plan(environment: &str, claim: &str, store_files: bool, destroy: bool) {
-    match run_claim_file(environment, claim, "plan", store_files, destroy, true).await {
-        Ok(_) => {}
+/// Exit code of `plan --detailed-exitcode` when the plan has changes, the same as Terraform's
+const PLAN_CHANGES_PRESENT_EXIT_CODE: i32 = 2;
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_plan(
+    environment: &str,
+    claim: &str,
+    store_files: bool,
+    destroy: bool,
+    var_file: Option<&str>,
+    vars: &[String],
+    detailed_exitcode: bool,
+    json: bool,
+) {
+    let changes = match run_claim_file(
+        environment,
+        claim,
+        "plan",
+        store_files,
+        destroy,
+        true,
+        var_file,
+        vars,
+        json,
+    )
+    .await
+    {
+        Ok(changes) => changes,
         Err(e) => {
             eprintln!("Plan failed: {}", e);
             std::process::exit(1);
         }
+    };
+
+    let changes_present = changes.iter().any(JobChanges::has_changes);
+    if json {
+        let summary = serde_json::json!({
+            "changes_present": changes_present,
+            "deployments": changes,
+        });
+        println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+    }
+    if detailed_exitcode && changes_present {
+        std::process::exit(PLAN_CHANGES_PRESENT_EXIT_CODE);
     }
 }
 
@@ -33,7 +77,7 @@ pub async fn handle_driftcheck(deployment_id: &str, environment: &str, remediate
         Ok(result) => result,
         Err(e) => {
             error!("Failed to request drift check: {}", e);
-            std::process::exit(1);
+            std::process::exit(exit_code_for_error(&e));
         }
     };
     info!("Successfully requested drift check (job id: {})", job_id);
@@ -87,8 +131,244 @@ pub async fn handle_driftcheck(deployment_id: &str, environment: &str, remediate
     }
 }
 
-pub async fn handle_apply(environment: &str, claim: &str, store_files: bool, follow: bool) {
-    match run_claim_file(environment, claim, "apply", store_files, false, follow).await {
+/// Drift check result of one deployment in `driftcheck --all`
+#[derive(Debug, Serialize)]
+struct DriftReportEntry {
+    deployment_id: String,
+    environment: String,
+    module: String,
+    job_id: String,
+    status: DriftCheckStatus,
+    drifted_resources: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    change_record: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum DriftCheckStatus {
+    InSync,
+    Drifted,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+struct DriftReport {
+    checked: usize,
+    drifted: usize,
+    failed: usize,
+    deployments: Vec<DriftReportEntry>,
+}
+
+/// Deployments checked by `driftcheck --all`, optionally only those of a module or environment
+fn select_deployments(
+    deployments: Vec<DeploymentResp>,
+    module: Option<&str>,
+    environment: Option<&str>,
+) -> Vec<DeploymentResp> {
+    let mut selected: Vec<DeploymentResp> = deployments
+        .into_iter()
+        .filter(|d| !d.deleted)
+        .filter(|d| module.is_none_or(|module| d.module == module))
+        .filter(|d| environment.is_none_or(|pattern| environment_matches(pattern, &d.environment)))
+        .collect();
+    selected.sort_by(|a, b| {
+        (&a.environment, &a.deployment_id).cmp(&(&b.environment, &b.deployment_id))
+    });
+    selected
+}
+
+async fn fetch_region_deployments(
+    handler: &GenericCloudHandler,
+) -> anyhow::Result<Vec<DeploymentResp>> {
+    if is_http_mode_enabled() {
+        http_get_deployments(handler.get_project_id(), handler.get_region())
+            .await?
+            .into_iter()
+            .map(|v| serde_json::from_value(v).map_err(Into::into))
+            .collect()
+    } else {
+        handler.get_all_deployments("", false).await
+    }
+}
+
+/// Checks all deployments of the region for drift, or those of a module or environment. At
+/// most `concurrency` checks are submitted at a time, and the project's job queue further limits
+/// how many of them run at once. Exits with 2 when any deployment drifted and 1 when any check
+/// failed
+pub async fn handle_driftcheck_all(
+    module: Option<&str>,
+    environment: Option<&str>,
+    remediate: bool,
+    concurrency: usize,
+    output: OutputFormat,
+) {
+    let handler = current_region_handler().await;
+    let deployments = select_deployments(
+        exit_on_err(fetch_region_deployments(&handler).await),
+        module,
+        environment,
+    );
+    if deployments.is_empty() {
+        println!("No deployments to check for drift");
+        return;
+    }
+    if output == OutputFormat::Table {
+        println!(
+            "Checking drift for {} deployment(s), {} at a time...",
+            deployments.len(),
+            concurrency
+        );
+    }
+
+    let operation = if remediate { "APPLY" } else { "PLAN" };
+    let mut entries = Vec::with_capacity(deployments.len());
+    for batch in deployments.chunks(concurrency.max(1)) {
+        // Submit the whole batch first so its checks run side by side
+        let mut submitted = Vec::new();
+        for deployment in batch {
+            let submission = driftcheck_infra(
+                &handler,
+                &deployment.deployment_id,
+                &deployment.environment,
+                remediate,
+                ExtraData::None,
+            )
+            .await;
+            submitted.push((deployment, submission));
+        }
+
+        for (deployment, submission) in submitted {
+            let mut entry = DriftReportEntry {
+                deployment_id: deployment.deployment_id.clone(),
+                environment: deployment.environment.clone(),
+                module: deployment.module.clone(),
+                job_id: String::new(),
+                status: DriftCheckStatus::Failed,
+                drifted_resources: 0,
+                change_record: None,
+                error: None,
+            };
+            let outcome = match submission {
+                Ok((job_id, region)) => {
+                    entry.job_id = job_id.clone();
+                    let job = ClaimJobStruct {
+                        job_id,
+                        deployment_id: deployment.deployment_id.clone(),
+                        environment: deployment.environment.clone(),
+                        region,
+                    };
+                    follow_driftcheck(&job, remediate).await
+                }
+                Err(e) => Err(e),
+            };
+            match outcome {
+                Ok(outcome) => {
+                    entry.drifted_resources = outcome
+                        .resource_changes
+                        .iter()
+                        .filter(|c| c.action != env_defs::ResourceAction::NoOp)
+                        .count();
+                    entry.status = if entry.drifted_resources > 0 {
+                        DriftCheckStatus::Drifted
+                    } else {
+                        DriftCheckStatus::InSync
+                    };
+                    entry.change_record = Some(change_record_url(
+                        handler.get_project_id(),
+                        handler.get_region(),
+                        &deployment.environment,
+                        &deployment.deployment_id,
+                        &outcome.job_id,
+                        operation,
+                    ));
+                    entry.job_id = outcome.job_id;
+                }
+                Err(e) => entry.error = Some(e.to_string()),
+            }
+            entries.push(entry);
+        }
+    }
+
+    let count = |status| entries.iter().filter(|e| e.status == status).count();
+    let report = DriftReport {
+        checked: entries.len(),
+        drifted: count(DriftCheckStatus::Drifted),
+        failed: count(DriftCheckStatus::Failed),
+        deployments: entries,
+    };
+    if !print_structured(&report, output) {
+        print_drift_report(&report);
+    }
+    if report.failed > 0 {
+        std::process::exit(1);
+    }
+    if report.drifted > 0 {
+        std::process::exit(2);
+    }
+}
+
+fn print_drift_report(report: &DriftReport) {
+    for entry in &report.deployments {
+        match entry.status {
+            DriftCheckStatus::InSync => {}
+            DriftCheckStatus::Drifted => {
+                println!(
+                    "\n{} {} in {}: {} drifted resource(s)",
+                    "!".yellow().bold(),
+                    entry.deployment_id.bold(),
+                    entry.environment,
+                    entry.drifted_resources
+                );
+                if let Some(change_record) = &entry.change_record {
+                    println!("  {}", change_record.dimmed());
+                }
+            }
+            DriftCheckStatus::Failed => {
+                println!(
+                    "\n{} {} in {}: check failed",
+                    "✗".red().bold(),
+                    entry.deployment_id.bold(),
+                    entry.environment
+                );
+                if let Some(error) = &entry.error {
+                    println!("  {}", error.red());
+                }
+            }
+        }
+    }
+    println!(
+        "\nChecked {} deployment(s): {} in sync, {} drifted, {} failed",
+        report.checked,
+        report.checked - report.drifted - report.failed,
+        report.drifted.to_string().yellow().bold(),
+        report.failed.to_string().red().bold()
+    );
+}
+
+pub async fn handle_apply(
+    environment: &str,
+    claim: &str,
+    store_files: bool,
+    follow: bool,
+    var_file: Option<&str>,
+    vars: &[String],
+) {
+    match run_claim_file(
+        environment,
+        claim,
+        "apply",
+        store_files,
+        false,
+        follow,
+        var_file,
+        vars,
+        false,
+    )
+    .await
+    {
         Ok(_) => {
             info!("Successfully applied claim");
         }
@@ -99,6 +379,50 @@ pub async fn handle_apply(environment: &str, claim: &str, store_files: bool, fol
     };
 }
 
+pub async fn handle_apply_plan(
+    deployment_id: &str,
+    environment: &str,
+    plan_job_id: &str,
+    follow: bool,
+) {
+    let (job_id, region) = match apply_plan_infra(
+        &current_region_handler().await,
+        deployment_id,
+        environment,
+        plan_job_id,
+        ExtraData::None,
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(e) => {
+            error!("Failed to request applying plan {}: {}", plan_job_id, e);
+            std::process::exit(exit_code_for_error(&e));
+        }
+    };
+    info!(
+        "Successfully requested applying plan {} (job id: {})",
+        plan_job_id, job_id
+    );
+    println!(
+        "Started apply job: {} in {} (job id: {})",
+        deployment_id, environment, job_id
+    );
+
+    if follow {
+        let job = ClaimJobStruct {
+            job_id,
+            deployment_id: deployment_id.to_string(),
+            environment: environment.to_string(),
+            region,
+        };
+        if let Err(e) = follow_execution(&[job], "apply").await {
+            error!("Failed to follow apply operation: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 pub async fn handle_destroy(
     deployment_id_or_path: &str,
     environment: &str,
@@ -135,6 +459,12 @@ pub async fn handle_destroy(
                 })
                 .collect();
 
+            let docs = docs.iter().flat_map(|doc| {
+                env_utils::expand_claim_regions(doc).unwrap_or_else(|e| {
+                    log::warn!("Skipping claim with invalid region list: {}", e);
+                    vec![]
+                })
+            });
             for doc in docs {
                 if let Ok(manifest) = serde_yaml::from_value::<DeploymentManifest>(doc) {
                     targets.push(DeploymentTarget {
@@ -183,7 +513,7 @@ pub async fn handle_destroy(
                     "Failed to request destroying deployment {}: {}",
                     target.id, e
                 );
-                std::process::exit(1);
+                std::process::exit(exit_code_for_error(&e));
             }
         };
         job_ids.push(job_id);
@@ -222,3 +552,183 @@ pub async fn handle_destroy(
         }
     }
 }
+
+/// Destroys a deployment together with everything depending on it, one deployment at a time with
+/// dependents first. The version override only applies to the requested deployment
+pub async fn handle_destroy_cascade(
+    deployment_id: &str,
+    environment: &str,
+    version: Option<&str>,
+    yes: bool,
+) {
+    if http_client::is_http_mode_enabled() {
+        eprintln!(
+            "{}",
+            "Error: 'destroy --cascade' is not supported in HTTP mode".red()
+        );
+        std::process::exit(1);
+    }
+
+    let region_handler = current_region_handler().await;
+    let order = match get_cascade_destroy_order(&region_handler, deployment_id, environment).await {
+        Ok(order) => order,
+        Err(e) => {
+            error!("Failed to resolve dependents of {}: {}", deployment_id, e);
+            std::process::exit(exit_code_for_error(&e));
+        }
+    };
+
+    println!("The following deployments will be destroyed, in this order:");
+    for (i, deployment) in order.iter().enumerate() {
+        println!(
+            "  {}. {} in {} ({}/{})",
+            i + 1,
+            deployment.dependent_id,
+            deployment.environment,
+            deployment.project_id,
+            deployment.region
+        );
+    }
+    if !yes && !confirm(&format!("Destroy {} deployment(s)?", order.len())) {
+        println!("Aborted");
+        return;
+    }
+
+    for (i, deployment) in order.iter().enumerate() {
+        let is_root = i + 1 == order.len();
+        println!(
+            "{}",
+            format!(
+                "[{}/{}] Destroying {} in {}",
+                i + 1,
+                order.len(),
+                deployment.dependent_id,
+                deployment.environment
+            )
+            .bold()
+        );
+        let handler =
+            GenericCloudHandler::workload(&deployment.project_id, &deployment.region).await;
+        let job_id = match destroy_infra(
+            &handler,
+            &deployment.dependent_id,
+            &deployment.environment,
+            ExtraData::None,
+            if is_root { version } else { None },
+        )
+        .await
+        {
+            Ok(job_id) => job_id,
+            Err(e) => {
+                error!(
+                    "Failed to request destroying deployment {}: {}",
+                    deployment.dependent_id, e
+                );
+                std::process::exit(exit_code_for_error(&e));
+            }
+        };
+
+        let job = ClaimJobStruct {
+            job_id,
+            deployment_id: deployment.dependent_id.clone(),
+            environment: deployment.environment.clone(),
+            region: deployment.region.clone(),
+        };
+        // Each deployment must be gone before what it depends on can be destroyed
+        match follow_job_status(&job, "destroy").await {
+            Ok(Some(DeploymentStatus::PendingApproval)) => {
+                eprintln!(
+                    "Destroying {} is pending approval, stopping the cascade. Rerun it once approved",
+                    deployment.dependent_id
+                );
+                std::process::exit(1);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                error!(
+                    "Failed to destroy {}, stopping the cascade with {} deployment(s) left: {}",
+                    deployment.dependent_id,
+                    order.len() - i,
+                    e
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+    info!("Destroyed {} deployment(s)", order.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deployment(id: &str, environment: &str, module: &str, deleted: bool) -> DeploymentResp {
+        serde_json::from_value(serde_json::json!({
+            "epoch": 0,
+            "deployment_id": id,
+            "status": "successful",
+            "job_id": "",
+            "environment": environment,
+            "project_id": "123456789012",
+            "region": "eu-west-1",
+            "module": module,
+            "module_version": "0.1.0",
+            "module_type": "module",
+            "module_track": "stable",
+            "drift_detection": {},
+            "next_drift_check_epoch": 0,
+            "has_drifted": false,
+            "variables": {},
+            "output": {},
+            "policy_results": [],
+            "error_text": "",
+            "deleted": deleted,
+            "dependencies": [],
+            "initiated_by": "",
+            "cpu": "",
+            "memory": "",
+            "reference": "",
+            "tf_resources": null,
+        }))
+        .unwrap()
+    }
+
+    fn ids(deployments: &[DeploymentResp]) -> Vec<&str> {
+        deployments
+            .iter()
+            .map(|d| d.deployment_id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_select_deployments() {
+        let deployments = vec![
+            deployment("s3bucket/logs", "github-org-repo/prod", "s3bucket", false),
+            deployment("s3bucket/data", "cli/dev", "s3bucket", false),
+            deployment("network/main", "github-org-repo/prod", "network", false),
+            deployment("s3bucket/old", "cli/dev", "s3bucket", true),
+        ];
+
+        assert_eq!(
+            ids(&select_deployments(deployments.clone(), None, None)),
+            vec!["s3bucket/data", "network/main", "s3bucket/logs"]
+        );
+        assert_eq!(
+            ids(&select_deployments(
+                deployments.clone(),
+                Some("s3bucket"),
+                None
+            )),
+            vec!["s3bucket/data", "s3bucket/logs"]
+        );
+        // Environments match on their namespace too
+        assert_eq!(
+            ids(&select_deployments(
+                deployments,
+                Some("s3bucket"),
+                Some("prod")
+            )),
+            vec!["s3bucket/logs"]
+        );
+    }
+}
@@ -1,13 +1,37 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::Read;
+
 use anyhow::Result;
+use colored::Colorize;
+use graph::{
+    edge_id, OutputEdge, OutputGraph, OutputNode, OutputNodeData, OutputNodePosition,
+    OutputNodeStyle,
+};
 use http_client::{
-    http_describe_deployment, http_get_deployments, http_get_logs, http_get_module_version,
+    http_describe_deployment, http_get_deployment_outputs, http_get_deployments,
+    http_get_job_status, http_get_logs, http_get_module_version, http_get_stack_version,
     is_http_mode_enabled,
 };
-use log::error;
+use log::{error, warn};
 
-use super::{exit_on_err, exit_on_none, fetch_all_projects};
-use crate::current_region_handler;
-use env_defs::{CloudProvider, CloudProviderCommon, DeploymentResp, ModuleResp};
+use super::module::download_module_zip;
+use super::{
+    confirm, exit_on_err, exit_on_none, fetch_all_projects, print_structured, OutputFormat,
+};
+use crate::run::{parse_references, read_single_claim};
+use crate::utils::{with_drift_report, with_stack_instance};
+use crate::{current_region_handler, follow_driftcheck, follow_job_status, ClaimJobStruct};
+use env_common::interface::GenericCloudHandler;
+use env_common::logic::{
+    force_unlock_deployment, get_deployment_details, import_deployment, parse_state_lock,
+    run_claim, FORCE_UNLOCK_COMMAND,
+};
+use env_defs::{
+    get_deployment_identifier, pretty_print_resource_changes, CloudProvider, CloudProviderCommon,
+    Dependent, DeploymentManifest, DeploymentResp, DeploymentStatus, EventData, ExtraData, LogData,
+    ModuleResp, ModuleVersionDiff, ResourceAction, StackInstanceModule,
+};
+use env_utils::{get_epoch, is_region_group_member, to_snake_case};
 
 async fn fetch_deployment(
     deployment_id: &str,
@@ -35,6 +59,27 @@ async fn fetch_deployment(
     }
 }
 
+async fn fetch_deployment_outputs(
+    deployment_id: &str,
+    environment: &str,
+    unmask: bool,
+) -> Result<serde_json::Value> {
+    let handler = current_region_handler().await;
+    if is_http_mode_enabled() {
+        Ok(http_get_deployment_outputs(
+            handler.get_project_id(),
+            handler.get_region(),
+            environment,
+            deployment_id,
+            unmask,
+        )
+        .await?)
+    } else {
+        env_common::logic::get_deployment_outputs(&handler, deployment_id, environment, unmask)
+            .await
+    }
+}
+
 async fn fetch_module_version(module: &str, track: &str, version: &str) -> Result<ModuleResp> {
     if is_http_mode_enabled() {
         Ok(http_get_module_version(track, module, version).await?)
@@ -78,7 +123,7 @@ async fn fetch_deployments(project: &str, region: &str) -> Result<Vec<Deployment
     }
 }
 
-async fn fetch_deployments_across_projects(
+pub(super) async fn fetch_deployments_across_projects(
     filter_project: Option<&str>,
     filter_region: Option<&str>,
 ) -> Result<Vec<DeploymentResp>> {
@@ -108,20 +153,152 @@ async fn fetch_deployments_across_projects(
     Ok(all)
 }
 
-pub async fn handle_describe(deployment_id: &str, environment: &str) {
-    let d = exit_on_none(
-        exit_on_err(fetch_deployment(deployment_id, environment).await),
-        &format!("Deployment not found: {}", deployment_id),
-    );
+pub async fn handle_describe(deployment_id: &str, environment: &str, output: OutputFormat) {
+    let d = match exit_on_err(fetch_deployment(deployment_id, environment).await) {
+        Some(d) => d,
+        None => {
+            // The id may name a claim fanned out to several regions
+            let group = exit_on_none(
+                Some(exit_on_err(
+                    fetch_region_group(deployment_id, environment).await,
+                ))
+                .filter(|group| !group.is_empty()),
+                &format!("Deployment not found: {}", deployment_id),
+            );
+            describe_region_group(deployment_id, &group, output);
+            return;
+        }
+    };
+    let d = with_stack_instance(with_drift_report(d).await).await;
+    if print_structured(&d, output) {
+        return;
+    }
     println!("Deployment: {}", serde_json::to_string_pretty(&d).unwrap());
+    if let Some(stack_instance) = &d.stack_instance {
+        println!("\nStack modules:");
+        print_stack_instance(stack_instance);
+    }
+    if let Some(drift_report) = &d.drift_report {
+        println!("\nDrift:\n{}", pretty_print_resource_changes(drift_report));
+    }
+}
+
+pub async fn handle_outputs(
+    deployment_id: &str,
+    environment: &str,
+    unmask: bool,
+    output: OutputFormat,
+) {
+    let outputs = exit_on_err(fetch_deployment_outputs(deployment_id, environment, unmask).await);
+    if print_structured(&outputs, output) {
+        return;
+    }
+    let Some(outputs) = outputs.as_object().filter(|o| !o.is_empty()) else {
+        println!("No outputs found for {}", deployment_id);
+        return;
+    };
+    println!("{:<30} {:<10} {}", "Name", "Sensitive", "Value");
+    for (name, value) in outputs {
+        let sensitive = value
+            .get("sensitive")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let value = match value.get("value") {
+            Some(serde_json::Value::String(s)) => s.clone(),
+            Some(v) => v.to_string(),
+            None => String::new(),
+        };
+        println!(
+            "{:<30} {:<10} {}",
+            name,
+            if sensitive { "yes" } else { "no" },
+            value
+        );
+    }
+}
+
+fn print_stack_instance(stack_instance: &[StackInstanceModule]) {
+    println!(
+        "{:<25} {:<25} {:<15} {:<10}",
+        "Claim", "Module", "Version", "Track"
+    );
+    for module in stack_instance {
+        println!(
+            "{:<25} {:<25} {:<15} {:<10}",
+            module.claim, module.module, module.version, module.track
+        );
+        for output in &module.outputs {
+            println!(
+                "    {} <- {}: {}",
+                output.name, output.module_output, output.value
+            );
+        }
+    }
+}
+
+/// Deployments fanned out from a multi-region claim with the original deployment id, across the
+/// regions of the current project
+async fn fetch_region_group(group_id: &str, environment: &str) -> Result<Vec<DeploymentResp>> {
+    let handler = current_region_handler().await;
+    let deployments =
+        fetch_deployments_across_projects(Some(handler.get_project_id()), None).await?;
+    Ok(deployments
+        .into_iter()
+        .filter(|d| {
+            d.environment == environment
+                && is_region_group_member(group_id, &d.deployment_id, &d.region)
+        })
+        .collect())
+}
+
+/// Overall status of a region group: a failure if any region failed, otherwise the status of the
+/// first region that has not succeeded yet
+fn aggregate_region_group_status(group: &[DeploymentResp]) -> Option<DeploymentStatus> {
+    group
+        .iter()
+        .find(|d| d.status.is_failure())
+        .or_else(|| {
+            group
+                .iter()
+                .find(|d| d.status != DeploymentStatus::Successful)
+        })
+        .or(group.first())
+        .map(|d| d.status.clone())
+}
+
+fn describe_region_group(group_id: &str, group: &[DeploymentResp], output: OutputFormat) {
+    if print_structured(&group, output) {
+        return;
+    }
+    if let Some(status) = aggregate_region_group_status(group) {
+        println!(
+            "Region group: {} ({} regions, status: {})",
+            group_id,
+            group.len(),
+            status
+        );
+    }
+    println!(
+        "{:<15} {:<15} {:<50} {:<25}",
+        "Status", "Region", "Deployment ID", "Version"
+    );
+    for d in group {
+        println!(
+            "{:<15} {:<15} {:<50} {:<25}",
+            d.status, d.region, d.deployment_id, d.module_version
+        );
+    }
 }
 
-pub async fn handle_list(project: Option<&str>, region: Option<&str>) {
+pub async fn handle_list(project: Option<&str>, region: Option<&str>, output: OutputFormat) {
     let all_deployments = if let (Some(p), Some(r)) = (project, region) {
         exit_on_err(fetch_deployments(p, r).await)
     } else {
         exit_on_err(fetch_deployments_across_projects(project, region).await)
     };
+    if print_structured(&all_deployments, output) {
+        return;
+    }
 
     println!(
         "{:<15} {:<30} {:<15} {:<50} {:<20} {:<25} {:<40}",
@@ -149,6 +326,399 @@ pub async fn handle_list(project: Option<&str>, region: Option<&str>) {
     }
 }
 
+/// Layout of `deployments graph` when the output format is `table`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GraphFormat {
+    #[default]
+    Mermaid,
+    Dot,
+}
+
+pub async fn handle_graph(environment: &str, format: GraphFormat, output: OutputFormat) {
+    let handler = current_region_handler().await;
+    let deployments =
+        exit_on_err(fetch_deployments(handler.get_project_id(), handler.get_region()).await);
+
+    // Dependents can live in other projects and regions, only their records point at them
+    let mut dependents = vec![];
+    if !is_http_mode_enabled() {
+        for deployment in deployments.iter().filter(|d| d.environment == environment) {
+            let records = exit_on_err(
+                handler
+                    .get_dependents(&deployment.deployment_id, &deployment.environment)
+                    .await,
+            );
+            dependents.extend(records.into_iter().map(|record| (deployment, record)));
+        }
+    }
+
+    let graph = deployment_graph(environment, &deployments, &dependents);
+    if print_structured(&graph, output) {
+        return;
+    }
+    match format {
+        GraphFormat::Mermaid => println!("{}", graph.to_mermaid()),
+        GraphFormat::Dot => println!("{}", graph.to_dot()),
+    }
+}
+
+fn deployment_node_id(deployment: &DeploymentResp) -> String {
+    get_deployment_identifier(
+        &deployment.project_id,
+        &deployment.region,
+        &deployment.deployment_id,
+        &deployment.environment,
+    )
+}
+
+/// Graph of the deployments in `environment` together with the deployments they depend on and the
+/// ones depending on them. Each deployment is a group, and edges point from a dependency to its
+/// dependent, starting at the outputs of the dependency the dependent consumes when those are known
+fn deployment_graph(
+    environment: &str,
+    deployments: &[DeploymentResp],
+    dependents: &[(&DeploymentResp, Dependent)],
+) -> OutputGraph {
+    let by_id: HashMap<String, &DeploymentResp> = deployments
+        .iter()
+        .map(|d| (deployment_node_id(d), d))
+        .collect();
+    let in_environment = |id: &str| by_id.get(id).is_some_and(|d| d.environment == environment);
+
+    // (dependency, dependent) pairs, from both sides of the relation
+    let mut links: BTreeSet<(String, String)> = BTreeSet::new();
+    for deployment in deployments {
+        for dependency in &deployment.dependencies {
+            links.insert((
+                get_deployment_identifier(
+                    &dependency.project_id,
+                    &dependency.region,
+                    &dependency.deployment_id,
+                    &dependency.environment,
+                ),
+                deployment_node_id(deployment),
+            ));
+        }
+    }
+    for (deployment, dependent) in dependents {
+        links.insert((
+            deployment_node_id(deployment),
+            get_deployment_identifier(
+                &dependent.project_id,
+                &dependent.region,
+                &dependent.dependent_id,
+                &dependent.environment,
+            ),
+        ));
+    }
+    links.retain(|(dependency, dependent)| in_environment(dependency) || in_environment(dependent));
+
+    let mut groups: BTreeSet<&str> = by_id
+        .keys()
+        .map(String::as_str)
+        .filter(|id| in_environment(id))
+        .collect();
+    for (dependency, dependent) in &links {
+        groups.insert(dependency);
+        groups.insert(dependent);
+    }
+    let mut nodes: Vec<OutputNode> = groups
+        .iter()
+        .map(|id| deployment_node(id, by_id.get(*id).copied(), environment))
+        .collect();
+
+    let mut output_nodes = HashSet::new();
+    let mut edges = vec![];
+    for (dependency_id, dependent_id) in &links {
+        let consumed = match (by_id.get(dependency_id), by_id.get(dependent_id)) {
+            (Some(dependency), Some(dependent)) => consumed_outputs(dependency, dependent),
+            _ => BTreeMap::new(),
+        };
+        if consumed.is_empty() {
+            edges.push(OutputEdge {
+                id: edge_id(dependency_id, dependent_id),
+                source: dependency_id.clone(),
+                target: dependent_id.clone(),
+                attributes: None,
+            });
+        }
+        for (output, variables) in consumed {
+            let output_id = format!("{}::output.{}", dependency_id, output);
+            if output_nodes.insert(output_id.clone()) {
+                nodes.push(OutputNode::Resource {
+                    id: output_id.clone(),
+                    parent_id: Some(dependency_id.clone()),
+                    data: OutputNodeData {
+                        label: output,
+                        node_type: "output".to_string(),
+                        action: None,
+                        count: None,
+                        hcl: None,
+                        values: None,
+                        provider: None,
+                    },
+                    position: OutputNodePosition { x: 0, y: 0 },
+                });
+            }
+            edges.push(OutputEdge {
+                id: edge_id(&output_id, dependent_id),
+                source: output_id,
+                target: dependent_id.clone(),
+                attributes: Some(variables),
+            });
+        }
+    }
+    OutputGraph::new(nodes, edges)
+}
+
+/// Group of a deployment, labelled with its environment when it's outside the graphed one. A
+/// dependency that isn't among the fetched deployments is labelled with its identifier
+fn deployment_node(id: &str, deployment: Option<&DeploymentResp>, environment: &str) -> OutputNode {
+    let (label, values) = match deployment {
+        Some(d) => (
+            if d.environment == environment {
+                d.deployment_id.clone()
+            } else {
+                format!("{} ({})", d.deployment_id, d.environment)
+            },
+            Some(serde_json::json!({
+                "project_id": d.project_id,
+                "region": d.region,
+                "environment": d.environment,
+                "module": d.module,
+                "module_version": d.module_version,
+                "status": d.status.to_string(),
+            })),
+        ),
+        None => (id.to_string(), None),
+    };
+    let border = if deployment.is_some_and(|d| d.environment == environment) {
+        "1px dashed #388bfd"
+    } else {
+        "1px dashed #cccccc"
+    };
+    OutputNode::Group {
+        id: id.to_string(),
+        data: OutputNodeData {
+            label,
+            node_type: "deployment".to_string(),
+            action: None,
+            count: None,
+            hcl: None,
+            values,
+            provider: None,
+        },
+        position: OutputNodePosition { x: 0, y: 0 },
+        style: OutputNodeStyle {
+            background_color: "rgba(56, 139, 253, 0.05)".to_string(),
+            border: border.to_string(),
+            z_index: -1,
+        },
+        parent_id: None,
+    }
+}
+
+/// Outputs of `dependency` consumed by `dependent`, with the variables consuming each of them.
+/// References are usually resolved before a claim is submitted, so a variable consumes an output
+/// when it holds a `{{ Kind::name::field }}` reference to it or holds its value
+fn consumed_outputs(
+    dependency: &DeploymentResp,
+    dependent: &DeploymentResp,
+) -> BTreeMap<String, Vec<String>> {
+    let mut consumed: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let (Some(outputs), Some(variables)) = (
+        dependency.output.as_object(),
+        dependent.variables.as_object(),
+    ) else {
+        return consumed;
+    };
+    for (variable, value) in variables {
+        let mut referenced = vec![];
+        visit_json_strings(value, &mut |s| {
+            referenced.extend(
+                parse_references(s)
+                    .into_iter()
+                    .filter(|(_, reference)| reference.deployment_id() == dependency.deployment_id)
+                    .map(|(_, reference)| to_snake_case(&reference.field)),
+            )
+        });
+        for (output, output_value) in outputs {
+            let output_value = &output_value["value"];
+            // Only values unlikely to be shared by chance count as consumed
+            let distinctive = match output_value {
+                serde_json::Value::String(s) => !s.is_empty(),
+                serde_json::Value::Array(items) => !items.is_empty(),
+                serde_json::Value::Object(fields) => !fields.is_empty(),
+                _ => false,
+            };
+            if referenced.contains(output) || (distinctive && output_value == value) {
+                consumed
+                    .entry(output.clone())
+                    .or_default()
+                    .push(variable.clone());
+            }
+        }
+    }
+    consumed
+}
+
+fn visit_json_strings(value: &serde_json::Value, visit: &mut impl FnMut(&str)) {
+    match value {
+        serde_json::Value::String(s) => visit(s),
+        serde_json::Value::Array(items) => items.iter().for_each(|v| visit_json_strings(v, visit)),
+        serde_json::Value::Object(fields) => {
+            fields.values().for_each(|v| visit_json_strings(v, visit))
+        }
+        _ => {}
+    }
+}
+
+/// Reads the state to import from a file, stdin for `-`, or an address in the storage of the
+/// cloud provider such as `s3://bucket/key`
+async fn read_import_state(handler: &GenericCloudHandler, state: &str) -> Result<Vec<u8>> {
+    if state == "-" {
+        let mut data = vec![];
+        std::io::stdin().read_to_end(&mut data)?;
+        return Ok(data);
+    }
+    if state.contains("://") {
+        return handler.read_state_file_at(state).await;
+    }
+    std::fs::read(state).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", state, e))
+}
+
+async fn import(environment: &str, claim: &str, state: &str, verify: bool) -> Result<()> {
+    if is_http_mode_enabled() {
+        return Err(anyhow::anyhow!(
+            "Importing deployments is not supported in HTTP mode"
+        ));
+    }
+    let (handler, yaml) = read_single_claim(environment, claim).await?;
+    let state = read_import_state(&handler, state).await?;
+    let reference_fallback = hostname::get()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "cli".to_string());
+
+    let (deployment_id, _) =
+        import_deployment(&handler, &yaml, environment, &state, &reference_fallback).await?;
+    println!("Imported {} into {}", deployment_id, environment);
+
+    if !verify {
+        return Ok(());
+    }
+    let (job_id, deployment_id, _) = run_claim(
+        &handler,
+        &yaml,
+        environment,
+        "plan",
+        vec![],
+        ExtraData::None,
+        &reference_fallback,
+    )
+    .await?;
+    println!(
+        "Verifying {} against the imported state (job id: {})...",
+        deployment_id, job_id
+    );
+    let job = ClaimJobStruct {
+        job_id,
+        deployment_id: deployment_id.clone(),
+        environment: environment.to_string(),
+        region: handler.get_region().to_string(),
+    };
+    // A plan of the claim shows where the module would change the imported resources
+    let outcome = follow_driftcheck(&job, false).await?;
+    if outcome.deployment_status != DeploymentStatus::Successful {
+        return Err(anyhow::anyhow!(
+            "Verification plan of {} finished as {}",
+            deployment_id,
+            outcome.deployment_status
+        ));
+    }
+    let divergent: Vec<_> = outcome
+        .resource_changes
+        .into_iter()
+        .filter(|c| c.action != ResourceAction::NoOp)
+        .collect();
+    if divergent.is_empty() {
+        println!("The claim matches the imported state, no changes needed");
+    } else {
+        println!(
+            "The claim diverges from the imported state, applying it would make these changes:\n"
+        );
+        println!("{}", pretty_print_resource_changes(&divergent));
+        std::process::exit(2);
+    }
+    Ok(())
+}
+
+pub async fn handle_import(environment: &str, claim: &str, state: &str, verify: bool) {
+    exit_on_err(import(environment, claim, state, verify).await);
+}
+
+pub async fn handle_unlock(deployment_id: &str, environment: &str, lock_id: &str, yes: bool) {
+    exit_on_err(unlock(deployment_id, environment, lock_id, yes).await);
+}
+
+/// Releases a state lock left behind by a job that stopped without releasing it, in a runner job
+/// of the deployment
+async fn unlock(deployment_id: &str, environment: &str, lock_id: &str, yes: bool) -> Result<()> {
+    if is_http_mode_enabled() {
+        return Err(anyhow::anyhow!(
+            "Unlocking deployments is not supported in HTTP mode"
+        ));
+    }
+    let handler = current_region_handler().await;
+    let deployment = handler
+        .get_deployment(deployment_id, environment, false)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Deployment not found: {}", deployment_id))?;
+
+    // The lock the last job of the deployment failed to acquire, if that is why it failed
+    match parse_state_lock(&deployment.error_text) {
+        Some(lock) if lock.id == lock_id => println!(
+            "Lock {} is held by {} since {} ({})",
+            lock.id, lock.who, lock.created, lock.operation
+        ),
+        Some(lock) => warn!(
+            "The last job of {} failed on lock {} held by {}, not on {}",
+            deployment_id, lock.id, lock.who, lock_id
+        ),
+        None => warn!(
+            "The last job of {} did not fail on a state lock, make sure {} is the lock to release",
+            deployment_id, lock_id
+        ),
+    }
+    println!(
+        "Releasing a lock that is still held lets two processes write the state at the same time and can corrupt it. Only continue if the job holding it is no longer running"
+    );
+    if !yes && !confirm(&format!("Release lock {} of {}?", lock_id, deployment_id)) {
+        println!("Aborted");
+        return Ok(());
+    }
+
+    let job_id = force_unlock_deployment(&handler, &deployment, lock_id).await?;
+    println!("Releasing the lock (job id: {})...", job_id);
+    let job = ClaimJobStruct {
+        job_id,
+        deployment_id: deployment_id.to_string(),
+        environment: environment.to_string(),
+        region: handler.get_region().to_string(),
+    };
+    match follow_job_status(&job, FORCE_UNLOCK_COMMAND).await? {
+        Some(DeploymentStatus::Successful) => {
+            println!("Released lock {} of {}", lock_id, deployment_id);
+            Ok(())
+        }
+        Some(status) => Err(anyhow::anyhow!(
+            "Releasing lock {} finished as {}",
+            lock_id,
+            status
+        )),
+        None => Err(anyhow::anyhow!("No status returned for the unlock job")),
+    }
+}
+
 pub async fn handle_get_claim(deployment_id: &str, environment: &str) {
     let deployment = exit_on_none(
         exit_on_err(fetch_deployment(deployment_id, environment).await),
@@ -164,12 +734,340 @@ pub async fn handle_get_claim(deployment_id: &str, environment: &str) {
         .await,
     );
 
+    if deployment.module_type == "stack" {
+        println!(
+            "{}",
+            exit_on_err(generate_stack_member_claims(&deployment, &module).await)
+        );
+        return;
+    }
+
     println!(
         "{}",
         env_utils::generate_deployment_claim(&deployment, &module)
     );
 }
 
+/// The claims of the stack with the variables of the deployment, as a multi-document YAML
+async fn generate_stack_member_claims(
+    deployment: &DeploymentResp,
+    stack: &ModuleResp,
+) -> Result<String> {
+    let stack_data = stack.stack_data.as_ref().ok_or_else(|| {
+        anyhow::anyhow!("Stack {} {} has no modules", stack.module, stack.version)
+    })?;
+    let mut members = vec![];
+    for stack_module in &stack_data.modules {
+        if stack_module.claim.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Stack {} {} was published before the claim names of its modules were recorded, publish it again to get its claims",
+                stack.module,
+                stack.version
+            ));
+        }
+        let track = match stack_module.track.as_str() {
+            "" => env_utils::get_version_track(&stack_module.version)?,
+            track => track.to_string(),
+        };
+        let module =
+            fetch_module_version(&stack_module.module, &track, &stack_module.version).await?;
+        members.push((stack_module.claim.clone(), module));
+    }
+
+    // References between the claims only exist in the generated main.tf of the stack
+    let main_tf = match download_module_zip(&stack.s3_key).await {
+        Ok(zip) => env_utils::read_files_from_zip(&zip, "tf")?
+            .into_iter()
+            .find(|(name, _)| name == "main.tf")
+            .map(|(_, content)| content),
+        Err(e) => {
+            warn!(
+                "Failed to download stack {} {}, references between its claims are left out: {}",
+                stack.module, stack.version, e
+            );
+            None
+        }
+    };
+
+    let claims = env_utils::generate_stack_member_claims(
+        &deployment.variables,
+        &members,
+        main_tf.as_deref(),
+    )?;
+    Ok(format!(
+        "# Claims of stack {} version {}, deployed as {} in {} ({})\n{}",
+        stack.module_name,
+        stack.version,
+        deployment.deployment_id,
+        deployment.environment,
+        deployment.region,
+        claims
+    ))
+}
+
+/// What applying a claim would change compared to the deployment it was last applied as
+#[derive(serde::Serialize)]
+struct ClaimDiff {
+    deployment_id: String,
+    environment: String,
+    /// The claim has not been deployed to the environment yet
+    new_deployment: bool,
+    variables: Vec<VariableChange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deployed_version: Option<String>,
+    version: String,
+    /// Changes to the Terraform code from the deployed version to the version of the claim
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version_diff: Option<ModuleVersionDiff>,
+    has_drifted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_drift_check_epoch: Option<u128>,
+}
+
+/// A variable added to, changed in or removed from the claim, named as in the claim
+#[derive(serde::Serialize, Debug, PartialEq)]
+struct VariableChange {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deployed: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    claim: Option<serde_json::Value>,
+}
+
+/// Variables that differ between the deployed variables and the variables of the claim, both in
+/// snake_case as they are stored with the deployment
+fn diff_variables(deployed: &serde_json::Value, claim: &serde_json::Value) -> Vec<VariableChange> {
+    let empty = serde_json::Map::new();
+    let deployed = deployed.as_object().unwrap_or(&empty);
+    let claim = claim.as_object().unwrap_or(&empty);
+    deployed
+        .keys()
+        .chain(claim.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|name| deployed.get(*name) != claim.get(*name))
+        .map(|name| VariableChange {
+            name: env_utils::to_camel_case(name),
+            deployed: deployed.get(name).cloned(),
+            claim: claim.get(name).cloned(),
+        })
+        .collect()
+}
+
+async fn fetch_stack_version(stack: &str, track: &str, version: &str) -> Result<ModuleResp> {
+    if is_http_mode_enabled() {
+        Ok(http_get_stack_version(track, stack, version).await?)
+    } else {
+        current_region_handler()
+            .await
+            .get_stack_version(stack, track, version)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Stack {stack} {track} {version} not found"))
+    }
+}
+
+/// Changes to the Terraform code between the deployed version and the version of the claim,
+/// taken from the version diff stored with the claim version when it was published right after
+/// the deployed one
+async fn version_diff(
+    deployment: &DeploymentResp,
+    module: &ModuleResp,
+) -> Result<ModuleVersionDiff> {
+    if let Some(diff) = &module.version_diff {
+        if diff.previous_version == deployment.module_version {
+            return Ok(diff.clone());
+        }
+    }
+    let deployed_module = if deployment.module_type == "stack" {
+        fetch_stack_version(
+            &deployment.module,
+            &deployment.module_track,
+            &deployment.module_version,
+        )
+        .await?
+    } else {
+        fetch_module_version(
+            &deployment.module,
+            &deployment.module_track,
+            &deployment.module_version,
+        )
+        .await?
+    };
+    let deployed_tf =
+        env_utils::read_tf_from_zip(&download_module_zip(&deployed_module.s3_key).await?)?;
+    let tf = env_utils::read_tf_from_zip(&download_module_zip(&module.s3_key).await?)?;
+    let (added, changed, removed) = env_utils::diff_modules(&deployed_tf, &tf);
+    Ok(ModuleVersionDiff {
+        added,
+        changed,
+        removed,
+        previous_version: deployment.module_version.clone(),
+    })
+}
+
+async fn diff_claim(environment: &str, claim: &str) -> Result<ClaimDiff> {
+    let (_, yaml) = read_single_claim(environment, claim).await?;
+    let manifest: DeploymentManifest = serde_yaml::from_value(yaml)?;
+    let (_, environment, deployment_id, module_name, _) =
+        get_deployment_details(environment, manifest.clone())?;
+
+    let (version, is_stack) = match (&manifest.spec.module_version, &manifest.spec.stack_version) {
+        (Some(version), None) => (version.clone(), false),
+        (None, Some(version)) => (version.clone(), true),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "The claim must set exactly one of moduleVersion and stackVersion"
+            ))
+        }
+    };
+    let track = env_utils::get_version_track(&version)?;
+    let module = if is_stack {
+        fetch_stack_version(&module_name, &track, &version).await?
+    } else {
+        fetch_module_version(&module_name, &track, &version).await?
+    };
+
+    // The variables as they would be stored with the deployment, see validate_and_prepare_claim
+    let provided_variables = serde_json::to_value(&manifest.spec.variables)?;
+    let variables = if is_stack {
+        let dont_flatten: Vec<&String> = module
+            .tf_providers
+            .iter()
+            .flat_map(|p| p.tf_variables.iter().map(|v| &v.name))
+            .collect();
+        env_utils::flatten_and_convert_first_level_keys_to_snake_case(
+            &provided_variables,
+            "",
+            dont_flatten,
+        )
+    } else {
+        env_utils::convert_first_level_keys_to_snake_case(&provided_variables)
+    };
+
+    let Some(deployment) = fetch_deployment(&deployment_id, &environment).await? else {
+        return Ok(ClaimDiff {
+            deployment_id,
+            environment,
+            new_deployment: true,
+            variables: diff_variables(&serde_json::json!({}), &variables),
+            deployed_version: None,
+            version,
+            version_diff: None,
+            has_drifted: false,
+            last_drift_check_epoch: None,
+        });
+    };
+
+    let version_diff = if deployment.module_version != version {
+        Some(version_diff(&deployment, &module).await?)
+    } else {
+        None
+    };
+    Ok(ClaimDiff {
+        variables: diff_variables(&deployment.variables, &variables),
+        new_deployment: false,
+        deployed_version: Some(deployment.module_version),
+        version_diff,
+        has_drifted: deployment.has_drifted,
+        last_drift_check_epoch: deployment.last_drift_check_epoch,
+        deployment_id,
+        environment,
+        version,
+    })
+}
+
+pub async fn handle_diff(environment: &str, claim: &str, output: OutputFormat) {
+    let diff = exit_on_err(diff_claim(environment, claim).await);
+    if print_structured(&diff, output) {
+        return;
+    }
+
+    if diff.new_deployment {
+        println!(
+            "{} is not deployed in {}, applying the claim creates it with version {}",
+            diff.deployment_id, diff.environment, diff.version
+        );
+    } else {
+        println!("{} in {}", diff.deployment_id, diff.environment);
+    }
+
+    println!("\nVariables:");
+    if diff.variables.is_empty() {
+        println!("No changes to the variables");
+    }
+    let show = |value: &serde_json::Value| serde_json::to_string(value).unwrap_or_default();
+    for change in &diff.variables {
+        let line = match (&change.deployed, &change.claim) {
+            (None, Some(claim)) => format!("+ {} = {}", change.name, show(claim)).green(),
+            (Some(deployed), None) => format!("- {} = {}", change.name, show(deployed)).red(),
+            (Some(deployed), Some(claim)) => {
+                format!("~ {}: {} -> {}", change.name, show(deployed), show(claim)).yellow()
+            }
+            (None, None) => continue,
+        };
+        println!("{}", line);
+    }
+
+    if let Some(deployed_version) = &diff.deployed_version {
+        println!("\nVersion:");
+        match &diff.version_diff {
+            None => println!("No change from the deployed version {}", deployed_version),
+            Some(version_diff) => {
+                println!("{} -> {}", deployed_version, diff.version);
+                if version_diff.added.is_empty()
+                    && version_diff.changed.is_empty()
+                    && version_diff.removed.is_empty()
+                {
+                    println!("No changes to the Terraform code");
+                }
+                for addition in &version_diff.added {
+                    println!(
+                        "{}",
+                        format!("+ {} = {}", addition.path, addition.value).green()
+                    );
+                }
+                for change in &version_diff.changed {
+                    println!(
+                        "{}",
+                        format!(
+                            "~ {}: {} -> {}",
+                            change.path, change.old_value, change.new_value
+                        )
+                        .yellow()
+                    );
+                }
+                for removal in &version_diff.removed {
+                    println!(
+                        "{}",
+                        format!("- {} = {}", removal.path, removal.value).red()
+                    );
+                }
+            }
+        }
+
+        println!("\nDrift:");
+        match (diff.has_drifted, diff.last_drift_check_epoch) {
+            (true, Some(epoch)) => println!(
+                "{}",
+                format!(
+                    "Drifted from its configuration at the last check ({}), run a plan to see the changes",
+                    env_utils::epoch_to_timestamp(epoch)
+                )
+                .red()
+            ),
+            (true, None) => println!(
+                "{}",
+                "Drifted from its configuration, run a plan to see the changes".red()
+            ),
+            (false, Some(epoch)) => println!(
+                "No drift at the last check ({})",
+                env_utils::epoch_to_timestamp(epoch)
+            ),
+            (false, None) => println!("Not checked for drift yet"),
+        }
+    }
+}
+
 pub async fn handle_get_logs(job_id: &str, output_path: Option<&str>) {
     let log_content = exit_on_err(fetch_logs(job_id).await);
 
@@ -183,3 +1081,415 @@ pub async fn handle_get_logs(job_id: &str, output_path: Option<&str>) {
         println!("{}", log_content);
     }
 }
+
+const LOGS_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+/// How far before the first log line the events of a followed job are looked up
+const JOB_EVENTS_MARGIN: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+async fn fetch_log_lines(job_id: &str) -> Result<Vec<LogData>> {
+    if is_http_mode_enabled() {
+        Ok(fetch_logs(job_id)
+            .await?
+            .lines()
+            .map(|line| LogData {
+                message: line.to_string(),
+                timestamp: None,
+            })
+            .collect())
+    } else {
+        current_region_handler().await.read_logs(job_id).await
+    }
+}
+
+/// Returns whether the job is still running and, once it has stopped, the task exit code if known
+async fn fetch_job_state(job_id: &str, since_epoch: u128) -> Result<(bool, Option<i64>)> {
+    let handler = current_region_handler().await;
+    if is_http_mode_enabled() {
+        let job_status =
+            http_get_job_status(handler.get_project_id(), handler.get_region(), job_id).await?;
+        let status = job_status
+            .get("status")
+            .and_then(|v| v.as_str())
+            .unwrap_or("UNKNOWN");
+        let running = !matches!(status, "STOPPED" | "DEPROVISIONING" | "UNKNOWN");
+        Ok((
+            running,
+            job_status.get("exit_code").and_then(|v| v.as_i64()),
+        ))
+    } else {
+        let running = handler
+            .get_job_status(job_id)
+            .await?
+            .map(|s| s.is_running)
+            .unwrap_or(false);
+        if running {
+            return Ok((true, None));
+        }
+        // Only the HTTP API reports the task exit code, so fall back to the last event the job recorded
+        let events = handler
+            .get_all_events_between(since_epoch, get_epoch())
+            .await?;
+        Ok((false, job_exit_code(&events, job_id)))
+    }
+}
+
+/// Maps the latest event recorded by the job to an exit code, 1 if it ended in a failure status
+fn job_exit_code(events: &[EventData], job_id: &str) -> Option<i64> {
+    // Job ids are ARNs on some backends, so compare the trailing segment as well
+    let short_id = |id: &str| id.rsplit('/').next().unwrap_or(id).to_string();
+    events
+        .iter()
+        .filter(|e| e.job_id == job_id || short_id(&e.job_id) == short_id(job_id))
+        .max_by_key(|e| e.epoch)
+        .map(|e| if e.status.is_failure() { 1 } else { 0 })
+}
+
+/// Drops lines older than `since_ms` (lines without a timestamp are kept) and keeps the last `tail` lines
+fn select_log_lines(logs: &[LogData], since_ms: Option<i64>, tail: Option<usize>) -> Vec<&LogData> {
+    let selected: Vec<&LogData> = logs
+        .iter()
+        .filter(|l| match (since_ms, l.timestamp) {
+            (Some(since), Some(ts)) => ts >= since,
+            _ => true,
+        })
+        .collect();
+    match tail {
+        Some(n) if n < selected.len() => selected[selected.len() - n..].to_vec(),
+        _ => selected,
+    }
+}
+
+pub async fn handle_logs(
+    job_id: &str,
+    follow: bool,
+    since: Option<&str>,
+    tail: Option<usize>,
+    deployment: Option<(&str, &str)>,
+) {
+    let since_ms = since.map(|s| {
+        let duration = exit_on_err(
+            humantime::parse_duration(s)
+                .map_err(|e| anyhow::anyhow!("Invalid --since value '{}': {}", s, e)),
+        );
+        chrono::Utc::now().timestamp_millis() - duration.as_millis() as i64
+    });
+
+    let logs = exit_on_err(fetch_log_lines(job_id).await);
+    for line in select_log_lines(&logs, since_ms, tail) {
+        println!("{}", line.message);
+    }
+
+    if !follow {
+        return;
+    }
+
+    // Backends return the full log stream on every read, so only print what was appended since the last poll
+    let mut seen = logs.len();
+    // Events are looked up from shortly before the first log line, as the job started no later than that
+    let since_epoch = logs
+        .iter()
+        .filter_map(|l| l.timestamp)
+        .min()
+        .map_or_else(get_epoch, |ts| ts.max(0) as u128)
+        .saturating_sub(JOB_EVENTS_MARGIN.as_millis());
+    let exit_code = loop {
+        let (running, exit_code) = exit_on_err(fetch_job_state(job_id, since_epoch).await);
+        let logs = exit_on_err(fetch_log_lines(job_id).await);
+        if logs.len() > seen {
+            for line in select_log_lines(&logs[seen..], since_ms, None) {
+                println!("{}", line.message);
+            }
+            seen = logs.len();
+        }
+        if !running {
+            break exit_code;
+        }
+        tokio::time::sleep(LOGS_POLL_INTERVAL).await;
+    };
+
+    let failed = if let Some((deployment_id, environment)) = deployment {
+        let d = exit_on_none(
+            exit_on_err(fetch_deployment(deployment_id, environment).await),
+            &format!("Deployment not found: {}", deployment_id),
+        );
+        if d.status.is_failure() {
+            eprintln!("Job {} finished with status {}", job_id, d.status);
+        }
+        d.status.is_failure()
+    } else {
+        exit_code.is_some_and(|c| c != 0)
+    };
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(message: &str, timestamp: Option<i64>) -> LogData {
+        LogData {
+            message: message.to_string(),
+            timestamp,
+        }
+    }
+
+    fn messages(lines: Vec<&LogData>) -> Vec<&str> {
+        lines.iter().map(|l| l.message.as_str()).collect()
+    }
+
+    fn deployment(
+        id: &str,
+        environment: &str,
+        variables: serde_json::Value,
+        output: serde_json::Value,
+        dependencies: serde_json::Value,
+    ) -> DeploymentResp {
+        serde_json::from_value(serde_json::json!({
+            "epoch": 0,
+            "deployment_id": id,
+            "status": "successful",
+            "job_id": "",
+            "environment": environment,
+            "project_id": "123456789012",
+            "region": "eu-west-1",
+            "module": "s3bucket",
+            "module_version": "0.1.0",
+            "module_type": "module",
+            "module_track": "stable",
+            "drift_detection": {},
+            "next_drift_check_epoch": 0,
+            "has_drifted": false,
+            "variables": variables,
+            "output": output,
+            "policy_results": [],
+            "error_text": "",
+            "deleted": false,
+            "dependencies": dependencies,
+            "initiated_by": "",
+            "cpu": "",
+            "memory": "",
+            "reference": "",
+            "tf_resources": null,
+        }))
+        .unwrap()
+    }
+
+    fn node_ids(graph: &OutputGraph) -> Vec<&str> {
+        graph
+            .nodes
+            .iter()
+            .map(|n| match n {
+                OutputNode::Group { id, .. } | OutputNode::Resource { id, .. } => id.as_str(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_deployment_graph() {
+        let dependency = |id: &str| {
+            serde_json::json!([{
+                "project_id": "123456789012",
+                "region": "eu-west-1",
+                "deployment_id": id,
+                "environment": "cli/dev",
+            }])
+        };
+        let deployments = vec![
+            deployment(
+                "s3bucket/logs",
+                "cli/dev",
+                serde_json::json!({ "bucket_name": "logs" }),
+                serde_json::json!({
+                    "bucket_arn": { "value": "arn:aws:s3:::logs", "sensitive": false },
+                    "versioned": { "value": true, "sensitive": false },
+                }),
+                serde_json::json!([]),
+            ),
+            deployment(
+                "lambda/processor",
+                "cli/dev",
+                serde_json::json!({
+                    "source_bucket_arn": "arn:aws:s3:::logs",
+                    "tags": { "Versioned": "{{ S3Bucket::logs::versioned }}" },
+                    "enabled": true,
+                }),
+                serde_json::json!({}),
+                dependency("s3bucket/logs"),
+            ),
+            deployment(
+                "sqs/alerts",
+                "cli/dev",
+                serde_json::json!({}),
+                serde_json::json!({}),
+                dependency("lambda/processor"),
+            ),
+            deployment(
+                "s3bucket/other",
+                "cli/prod",
+                serde_json::json!({}),
+                serde_json::json!({}),
+                serde_json::json!([]),
+            ),
+        ];
+        let dependent: Dependent = serde_json::from_value(serde_json::json!({
+            "project_id": "210987654321",
+            "region": "us-east-1",
+            "dependent_id": "athena/queries",
+            "environment": "cli/analytics",
+        }))
+        .unwrap();
+        let dependents = vec![(&deployments[0], dependent)];
+
+        let graph = deployment_graph("cli/dev", &deployments, &dependents);
+        let logs = "123456789012::eu-west-1::cli/dev::s3bucket/logs";
+        let processor = "123456789012::eu-west-1::cli/dev::lambda/processor";
+        let alerts = "123456789012::eu-west-1::cli/dev::sqs/alerts";
+        let queries = "210987654321::us-east-1::cli/analytics::athena/queries";
+        assert_eq!(
+            node_ids(&graph),
+            vec![
+                "123456789012::eu-west-1::cli/dev::lambda/processor",
+                "123456789012::eu-west-1::cli/dev::s3bucket/logs",
+                "123456789012::eu-west-1::cli/dev::sqs/alerts",
+                "210987654321::us-east-1::cli/analytics::athena/queries",
+                "123456789012::eu-west-1::cli/dev::s3bucket/logs::output.bucket_arn",
+                "123456789012::eu-west-1::cli/dev::s3bucket/logs::output.versioned",
+            ]
+        );
+
+        let edges: Vec<(&str, &str, Option<Vec<String>>)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.source.as_str(), e.target.as_str(), e.attributes.clone()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                (processor, alerts, None),
+                (logs, queries, None),
+                (
+                    "123456789012::eu-west-1::cli/dev::s3bucket/logs::output.bucket_arn",
+                    processor,
+                    Some(vec!["source_bucket_arn".to_string()])
+                ),
+                (
+                    "123456789012::eu-west-1::cli/dev::s3bucket/logs::output.versioned",
+                    processor,
+                    Some(vec!["tags".to_string()])
+                ),
+            ]
+        );
+
+        let mermaid = graph.to_mermaid();
+        assert!(mermaid.contains("subgraph n1 [\"s3bucket/logs\"]"));
+        assert!(mermaid.contains(&format!("    n5[\"{}\"]", queries)));
+        assert!(mermaid.contains("    n2 -->|\"source_bucket_arn\"| n0"));
+    }
+
+    #[test]
+    fn test_select_log_lines_since_and_tail() {
+        let logs = vec![
+            log("a", Some(100)),
+            log("b", Some(200)),
+            log("c", None),
+            log("d", Some(300)),
+        ];
+        assert_eq!(
+            messages(select_log_lines(&logs, None, None)),
+            vec!["a", "b", "c", "d"]
+        );
+        assert_eq!(
+            messages(select_log_lines(&logs, Some(200), None)),
+            vec!["b", "c", "d"]
+        );
+        assert_eq!(
+            messages(select_log_lines(&logs, Some(200), Some(2))),
+            vec!["c", "d"]
+        );
+        assert_eq!(
+            messages(select_log_lines(&logs, None, Some(10))),
+            vec!["a", "b", "c", "d"]
+        );
+    }
+
+    #[test]
+    fn test_diff_variables() {
+        let deployed = serde_json::json!({
+            "bucket_name": "logs",
+            "versioning": false,
+            "tags": {"team": "data"},
+        });
+        let claim = serde_json::json!({
+            "bucket_name": "logs",
+            "versioning": true,
+            "retention_days": 30,
+        });
+        assert_eq!(
+            diff_variables(&deployed, &claim),
+            vec![
+                VariableChange {
+                    name: "retentionDays".to_string(),
+                    deployed: None,
+                    claim: Some(serde_json::json!(30)),
+                },
+                VariableChange {
+                    name: "tags".to_string(),
+                    deployed: Some(serde_json::json!({"team": "data"})),
+                    claim: None,
+                },
+                VariableChange {
+                    name: "versioning".to_string(),
+                    deployed: Some(serde_json::json!(false)),
+                    claim: Some(serde_json::json!(true)),
+                },
+            ]
+        );
+        assert!(diff_variables(&deployed, &deployed).is_empty());
+    }
+
+    fn job_event(job_id: &str, epoch: u128, status: &str) -> EventData {
+        serde_json::from_value(serde_json::json!({
+            "deployment_id": "s3bucket/my-bucket",
+            "project_id": "123456789012",
+            "region": "eu-west-1",
+            "environment": "cli/prod",
+            "event": "apply",
+            "epoch": epoch,
+            "error_text": "",
+            "id": "",
+            "job_id": job_id,
+            "metadata": {},
+            "drift_detection": {},
+            "next_drift_check_epoch": -1,
+            "has_drifted": false,
+            "module": "s3bucket",
+            "name": "my-bucket",
+            "status": status,
+            "timestamp": "",
+            "output": {},
+            "policy_results": [],
+            "initiated_by": "",
+            "event_duration": 0,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_job_exit_code_uses_latest_event_of_the_job() {
+        let events = vec![
+            job_event(
+                "arn:aws:ecs:eu-west-1:123:task/cluster/job-1",
+                3,
+                "failed_plan",
+            ),
+            job_event("job-1", 1, "initiated"),
+            job_event("job-2", 5, "successful"),
+        ];
+        assert_eq!(job_exit_code(&events, "job-1"), Some(1));
+        assert_eq!(job_exit_code(&events, "job-2"), Some(0));
+        assert_eq!(job_exit_code(&events, "job-3"), None);
+    }
+}
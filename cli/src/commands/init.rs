@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use colored::Colorize;
+use env_defs::{ModuleResp, TfVariable};
+use env_utils::{
+    claim_variables, generate_claim_scaffold, is_required_variable, module_example_value,
+    to_camel_case,
+};
+use inquire::{Select, Text};
+
+use super::exit_on_err;
+use super::module::{fetch_all_latest_modules, fetch_all_module_versions};
+
+async fn select_module(track: &str, module: Option<String>) -> Result<ModuleResp> {
+    let modules = fetch_all_latest_modules(track).await?;
+    if modules.is_empty() {
+        anyhow::bail!("No modules found on track {}", track);
+    }
+
+    let module = match module {
+        Some(module) => module,
+        None => {
+            let mut names: Vec<String> = modules.iter().map(|m| m.module.clone()).collect();
+            names.sort();
+            Select::new("Select a module:", names)
+                .prompt()
+                .map_err(|e| anyhow::anyhow!("Failed to select module: {}", e))?
+        }
+    };
+    modules
+        .into_iter()
+        .find(|m| m.module == module)
+        .ok_or_else(|| anyhow::anyhow!("Module {} not found on track {}", module, track))
+}
+
+async fn select_version(latest: ModuleResp, version: Option<String>) -> Result<ModuleResp> {
+    if version.as_deref() == Some(latest.version.as_str()) {
+        return Ok(latest);
+    }
+
+    let versions: Vec<ModuleResp> = fetch_all_module_versions(&latest.track, &latest.module)
+        .await?
+        .into_iter()
+        .filter(|m| !m.deprecated || version.as_deref() == Some(m.version.as_str()))
+        .collect();
+    let version = match version {
+        Some(version) => version,
+        None => {
+            let labels: Vec<String> = versions.iter().map(|m| m.version.clone()).collect();
+            let cursor = labels
+                .iter()
+                .position(|v| *v == latest.version)
+                .unwrap_or(0);
+            Select::new("Select a version:", labels)
+                .with_starting_cursor(cursor)
+                .prompt()
+                .map_err(|e| anyhow::anyhow!("Failed to select version: {}", e))?
+        }
+    };
+    versions
+        .into_iter()
+        .find(|m| m.version == version)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Version {} of module {} not found on track {}",
+                version,
+                latest.module,
+                latest.track
+            )
+        })
+}
+
+fn prompt_text(message: &str, default: Option<&str>, help: Option<&str>) -> Result<String> {
+    let mut prompt = Text::new(message);
+    if let Some(default) = default {
+        prompt = prompt.with_default(default);
+    }
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
+    prompt
+        .prompt()
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", message.trim_end_matches(':'), e))
+}
+
+/// Prompts for a value of `variable`, parsed as YAML so lists, maps and numbers can be entered
+fn prompt_variable(module: &ModuleResp, variable: &TfVariable) -> Result<serde_yaml::Value> {
+    let example = module_example_value(module, &variable.name).map(|value| {
+        serde_yaml::to_string(&value)
+            .unwrap()
+            .trim_start_matches("---\n")
+            .trim_end()
+            .to_string()
+    });
+    let help = (!variable.description.is_empty()).then_some(variable.description.as_str());
+    let input = prompt_text(
+        &format!("{}:", to_camel_case(&variable.name)),
+        example.as_deref(),
+        help,
+    )?;
+    if input.trim().is_empty() {
+        return Ok(serde_yaml::Value::Null);
+    }
+    serde_yaml::from_str(&input)
+        .map_err(|e| anyhow::anyhow!("Invalid value for {}: {}", variable.name, e))
+}
+
+pub async fn handle_init(
+    track: &str,
+    module: Option<String>,
+    version: Option<String>,
+    name: Option<String>,
+    region: Option<String>,
+    file: Option<String>,
+    force: bool,
+) {
+    let module = exit_on_err(select_module(track, module).await);
+    let module = exit_on_err(select_version(module, version).await);
+
+    let name = match name {
+        Some(name) => name,
+        None => exit_on_err(prompt_text(
+            "Deployment name:",
+            Some(&format!("my-{}", module.module)),
+            Some("Used as metadata.name, the deployment id becomes <module>/<name>"),
+        )),
+    };
+    let region = match region {
+        Some(region) => region,
+        None => exit_on_err(prompt_text("Region:", None, None)),
+    };
+
+    let mut values = BTreeMap::new();
+    for variable in claim_variables(&module) {
+        if is_required_variable(variable) {
+            let value = exit_on_err(prompt_variable(&module, variable));
+            values.insert(variable.name.clone(), value);
+        }
+    }
+
+    let claim = generate_claim_scaffold(&module, &name, &region, &values);
+    let file = file.unwrap_or_else(|| format!("{}.yaml", name));
+    if Path::new(&file).exists() && !force {
+        eprintln!(
+            "{}",
+            format!(
+                "Error: {} already exists, use --force to overwrite it",
+                file
+            )
+            .red()
+        );
+        std::process::exit(1);
+    }
+    exit_on_err(std::fs::write(&file, claim).map_err(anyhow::Error::from));
+
+    println!(
+        "Wrote claim for {} version {} to {}",
+        module.module, module.version, file
+    );
+    println!("Plan it with: infraweave plan {}", file);
+}
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+use colored::Colorize;
+use env_utils::{
+    fix_claim, get_version_track, lint_claim, lint_claim_against_module, LintFinding, LintSeverity,
+    CLAIM_LINT_RULES,
+};
+use serde::{Deserialize, Serialize};
+
+use super::module::fetch_module_version;
+use super::stack::fetch_stack_version;
+use super::{exit_on_err, print_structured, OutputFormat};
+use crate::run::collect_claim_files;
+
+/// Finding of `infraweave lint` together with where it was found
+#[derive(Serialize)]
+struct ClaimLintResult {
+    file: String,
+    /// Index of the YAML document in the file, starting at 0
+    document: usize,
+    /// Line of the offending field, or of the start of the document if it can't be located
+    line: usize,
+    #[serde(flatten)]
+    finding: LintFinding,
+}
+
+pub async fn handle_lint(
+    paths: &[String],
+    fix: bool,
+    sarif: bool,
+    region: Option<&str>,
+    offline: bool,
+    output: OutputFormat,
+) {
+    let mut files = vec![];
+    for path in paths {
+        exit_on_err(collect_claim_files(Path::new(path), &mut files));
+    }
+
+    let mut modules = HashMap::new();
+    let mut results = vec![];
+    for file in &files {
+        let findings = exit_on_err(lint_file(file, fix, region, offline, &mut modules).await);
+        results.extend(findings);
+    }
+
+    let has_errors = results
+        .iter()
+        .any(|r| r.finding.severity == LintSeverity::Error);
+
+    if sarif {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&sarif_report(&results)).unwrap()
+        );
+    } else if !print_structured(&results, output) {
+        for result in &results {
+            let severity = match result.finding.severity {
+                LintSeverity::Error => "error".red().bold(),
+                LintSeverity::Warning => "warning".yellow().bold(),
+            };
+            println!(
+                "{}:{}: {} [{}] {}: {}{}",
+                result.file,
+                result.line,
+                severity,
+                result.finding.rule,
+                result.finding.path,
+                result.finding.message,
+                if result.finding.fixable {
+                    " (fixable with --fix)".dimmed().to_string()
+                } else {
+                    String::new()
+                }
+            );
+        }
+        if results.is_empty() {
+            println!(
+                "{} No problems found in {} claim file(s)",
+                "✓".green().bold(),
+                files.len()
+            );
+        }
+    }
+
+    if has_errors {
+        std::process::exit(1);
+    }
+}
+
+async fn lint_file(
+    file: &Path,
+    fix: bool,
+    region: Option<&str>,
+    offline: bool,
+    modules: &mut HashMap<(String, String, String), Option<env_defs::ModuleResp>>,
+) -> Result<Vec<ClaimLintResult>> {
+    let mut content = std::fs::read_to_string(file)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file.display(), e))?;
+    let mut documents = parse_documents(&content)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", file.display(), e))?;
+
+    if fix {
+        let fixed: usize = documents
+            .iter_mut()
+            .filter(|document| !document.is_null())
+            .map(fix_claim)
+            .sum();
+        if fixed > 0 {
+            content = documents
+                .iter()
+                .map(serde_yaml::to_string)
+                .collect::<Result<Vec<_>, _>>()?
+                .join("---\n");
+            std::fs::write(file, &content)?;
+            eprintln!("Fixed {} field(s) in {}", fixed, file.display());
+        }
+    }
+
+    let document_starts = document_start_lines(&content);
+    let mut results = vec![];
+    for (index, document) in documents.iter().enumerate() {
+        if document.is_null() {
+            continue;
+        }
+        let mut findings = lint_claim(document, region);
+        if !offline {
+            findings.extend(lint_against_module(document, modules).await?);
+        }
+        let document_start = document_starts.get(index).copied().unwrap_or(1);
+        results.extend(findings.into_iter().map(|finding| ClaimLintResult {
+            file: file.display().to_string(),
+            document: index,
+            line: finding_line(&content, document_start, &finding.path),
+            finding,
+        }));
+    }
+    Ok(results)
+}
+
+fn parse_documents(content: &str) -> Result<Vec<serde_yaml::Value>> {
+    serde_yaml::Deserializer::from_str(content)
+        .map(|document| Ok(serde_yaml::Value::deserialize(document)?))
+        .collect()
+}
+
+/// Runs the rules needing the module or stack of the claim, fetching each version once
+async fn lint_against_module(
+    claim: &serde_yaml::Value,
+    modules: &mut HashMap<(String, String, String), Option<env_defs::ModuleResp>>,
+) -> Result<Vec<LintFinding>> {
+    let (version, is_stack) = match (
+        claim["spec"]["moduleVersion"].as_str(),
+        claim["spec"]["stackVersion"].as_str(),
+    ) {
+        (Some(version), None) => (version, false),
+        (None, Some(version)) => (version, true),
+        // Reported by the missing-version rule
+        _ => return Ok(vec![]),
+    };
+    let Some(kind) = claim["kind"].as_str() else {
+        return Ok(vec![]);
+    };
+    let module = kind.to_lowercase();
+    let Ok(track) = get_version_track(version) else {
+        return Ok(vec![unknown_version(is_stack, &module, version)]);
+    };
+
+    let key = (module.clone(), track.clone(), version.to_string());
+    if !modules.contains_key(&key) {
+        let module_resp = if is_stack {
+            fetch_stack_version(&track, &module, version).await?
+        } else {
+            fetch_module_version(&track, &module, version).await?
+        };
+        modules.insert(key.clone(), module_resp);
+    }
+    Ok(match &modules[&key] {
+        Some(module_resp) => lint_claim_against_module(claim, module_resp),
+        None => vec![unknown_version(is_stack, &module, version)],
+    })
+}
+
+fn unknown_version(is_stack: bool, module: &str, version: &str) -> LintFinding {
+    let (label, path) = if is_stack {
+        ("Stack", "spec.stackVersion")
+    } else {
+        ("Module", "spec.moduleVersion")
+    };
+    LintFinding {
+        rule: "unknown-version".to_string(),
+        severity: LintSeverity::Error,
+        path: path.to_string(),
+        message: format!("{} {} has no version {}", label, module, version),
+        fixable: false,
+    }
+}
+
+/// First line of each document of a multi-document YAML file, starting at 1
+fn document_start_lines(content: &str) -> Vec<usize> {
+    let mut starts = vec![1];
+    let mut has_content = false;
+    for (i, line) in content.lines().enumerate() {
+        if line == "---" || line.starts_with("--- ") {
+            if has_content {
+                starts.push(i + 2);
+            } else if let Some(start) = starts.last_mut() {
+                // A separator before the first content doesn't start a new document
+                *start = i + 2;
+            }
+            has_content = false;
+        } else if !line.trim().is_empty() && !line.trim_start().starts_with('#') {
+            has_content = true;
+        }
+    }
+    starts
+}
+
+/// Line of the last key of `path` in the document starting at `document_start`
+fn finding_line(content: &str, document_start: usize, path: &str) -> usize {
+    let key = path.rsplit('.').next().unwrap_or(path);
+    content
+        .lines()
+        .enumerate()
+        .skip(document_start - 1)
+        .take_while(|(i, line)| *i + 1 == document_start || *line != "---")
+        .find(|(_, line)| {
+            line.trim_start()
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with(':'))
+        })
+        .map(|(i, _)| i + 1)
+        .unwrap_or(document_start)
+}
+
+/// Findings as a SARIF 2.1.0 log, the format read by CI code scanning annotations
+fn sarif_report(results: &[ClaimLintResult]) -> serde_json::Value {
+    let rules: Vec<serde_json::Value> = CLAIM_LINT_RULES
+        .iter()
+        .map(|(id, description)| {
+            serde_json::json!({
+                "id": id,
+                "shortDescription": { "text": description },
+            })
+        })
+        .collect();
+    let sarif_results: Vec<serde_json::Value> = results
+        .iter()
+        .map(|result| {
+            serde_json::json!({
+                "ruleId": result.finding.rule,
+                "level": match result.finding.severity {
+                    LintSeverity::Error => "error",
+                    LintSeverity::Warning => "warning",
+                },
+                "message": { "text": format!("{}: {}", result.finding.path, result.finding.message) },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": result.file },
+                        "region": { "startLine": result.line },
+                    }
+                }],
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "infraweave",
+                    "version": env!("APP_VERSION"),
+                    "informationUri": "https://github.com/infraweave-io/infraweave",
+                    "rules": rules,
+                }
+            },
+            "results": sarif_results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLAIMS: &str = r#"---
+# First claim
+apiVersion: infraweave.io/v1
+kind: S3Bucket
+metadata:
+  name: bucket
+spec:
+  moduleVersion: 0.1.0
+  region: eu-west-1
+  variables:
+    bucket_name: my-bucket
+---
+apiVersion: infraweave.io/v1
+kind: S3Bucket
+metadata:
+  name: other
+spec:
+  region: eu-west-1
+  variables:
+    bucket_name: other-bucket
+"#;
+
+    #[test]
+    fn test_finding_lines() {
+        let starts = document_start_lines(CLAIMS);
+        assert_eq!(starts, vec![2, 13]);
+        assert_eq!(finding_line(CLAIMS, 2, "spec.variables.bucket_name"), 11);
+        assert_eq!(finding_line(CLAIMS, 13, "spec.variables.bucket_name"), 20);
+        assert_eq!(finding_line(CLAIMS, 13, "spec"), 17);
+        assert_eq!(finding_line(CLAIMS, 13, "spec.moduleVersion"), 13);
+    }
+
+    #[test]
+    fn test_sarif_report() {
+        let results = vec![ClaimLintResult {
+            file: "claims/bucket.yaml".to_string(),
+            document: 0,
+            line: 11,
+            finding: LintFinding {
+                rule: "variable-casing".to_string(),
+                severity: LintSeverity::Error,
+                path: "spec.variables.bucket_name".to_string(),
+                message: "Variable \"bucket_name\" should be written as \"bucketName\"".to_string(),
+                fixable: true,
+            },
+        }];
+        let report = sarif_report(&results);
+        let result = &report["runs"][0]["results"][0];
+        assert_eq!(result["ruleId"], "variable-casing");
+        assert_eq!(result["level"], "error");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "claims/bucket.yaml"
+        );
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["region"]["startLine"],
+            11
+        );
+        assert_eq!(
+            report["runs"][0]["tool"]["driver"]["rules"]
+                .as_array()
+                .unwrap()
+                .len(),
+            CLAIM_LINT_RULES.len()
+        );
+    }
+}
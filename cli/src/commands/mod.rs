@@ -1,8 +1,11 @@
 pub mod admin;
+pub mod approval;
 pub mod auth;
 pub mod claim;
 pub mod deployment;
 pub mod gitops;
+pub mod init;
+pub mod lint;
 pub mod mcp;
 pub mod module;
 pub mod policy;
@@ -13,8 +16,9 @@ pub mod upgrade;
 
 use anyhow::Result;
 use colored::Colorize;
-use env_defs::CloudProvider;
+use env_defs::{CloudHandlerError, CloudProvider};
 use http_client::{http_get_all_projects, is_http_mode_enabled};
+use serde::Serialize;
 
 use crate::current_region_handler;
 
@@ -30,12 +34,71 @@ pub async fn fetch_all_projects() -> Result<Vec<env_defs::ProjectData>> {
     }
 }
 
+/// Output format of read commands
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Yaml,
+}
+
+/// Serializes `value` for the structured formats, returns None for `Table`
+pub fn render_structured<T: Serialize>(value: &T, output: OutputFormat) -> Result<Option<String>> {
+    Ok(match output {
+        OutputFormat::Table => None,
+        OutputFormat::Json => Some(serde_json::to_string_pretty(value)?),
+        OutputFormat::Yaml => Some(serde_yaml::to_string(value)?.trim_end().to_string()),
+    })
+}
+
+/// Prints `value` as JSON or YAML and returns true, or returns false for `Table`
+/// so the caller prints its human readable output instead
+pub fn print_structured<T: Serialize>(value: &T, output: OutputFormat) -> bool {
+    match exit_on_err(render_structured(value, output)) {
+        Some(rendered) => {
+            println!("{}", rendered);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Exit code of a failed command, telling scripts whether it is worth retrying: 3 when something
+/// was not found, 4 for missing credentials or permissions, 5 for conflicts, 6 for invalid
+/// requests, 75 (EX_TEMPFAIL) when throttled or temporarily unavailable and 1 otherwise
+pub fn exit_code_for_error(error: &anyhow::Error) -> i32 {
+    let Some(cloud_error) = error
+        .chain()
+        .find_map(|e| e.downcast_ref::<CloudHandlerError>())
+    else {
+        return 1;
+    };
+    match cloud_error {
+        _ if cloud_error.is_retryable() => 75,
+        _ if cloud_error.is_permission_error() => 4,
+        CloudHandlerError::NotFound(_) => 3,
+        CloudHandlerError::Conflict(_) | CloudHandlerError::JobAlreadyInProgress(_) => 5,
+        CloudHandlerError::Validation(_) => 6,
+        _ => 1,
+    }
+}
+
+/// Asks the user a yes or no question, anything but yes is a no
+pub fn confirm(prompt: &str) -> bool {
+    print!("{} [y/N] ", prompt);
+    let _ = std::io::Write::flush(&mut std::io::stdout());
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
 pub fn exit_on_err<T>(result: anyhow::Result<T>) -> T {
     match result {
         Ok(v) => v,
         Err(e) => {
             eprintln!("{}", format!("Error: {}", e).red());
-            std::process::exit(1);
+            std::process::exit(exit_code_for_error(&e));
         }
     }
 }
@@ -49,3 +112,47 @@ pub fn exit_on_none<T>(option: Option<T>, message: &str) -> T {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code_for_error() {
+        let throttled: anyhow::Error = CloudHandlerError::Throttled("slow down".to_string()).into();
+        assert_eq!(exit_code_for_error(&throttled), 75);
+        let denied = anyhow::Error::from(CloudHandlerError::AccessDenied("no".to_string()))
+            .context("Failed to list modules");
+        assert_eq!(exit_code_for_error(&denied), 4);
+        let not_found: anyhow::Error = CloudHandlerError::NotFound("s3bucket".to_string()).into();
+        assert_eq!(exit_code_for_error(&not_found), 3);
+        assert_eq!(exit_code_for_error(&anyhow::anyhow!("boom")), 1);
+    }
+
+    #[derive(Serialize)]
+    struct Entry {
+        module_name: String,
+        version: String,
+    }
+
+    #[test]
+    fn test_render_structured_uses_serde_field_names() {
+        let entries = vec![Entry {
+            module_name: "s3bucket".to_string(),
+            version: "0.1.0".to_string(),
+        }];
+        assert_eq!(
+            render_structured(&entries, OutputFormat::Table).unwrap(),
+            None
+        );
+        let json = render_structured(&entries, OutputFormat::Json)
+            .unwrap()
+            .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed[0]["module_name"], "s3bucket");
+        let yaml = render_structured(&entries, OutputFormat::Yaml)
+            .unwrap()
+            .unwrap();
+        assert_eq!(yaml, "- module_name: s3bucket\n  version: 0.1.0");
+    }
+}
@@ -1,19 +1,32 @@
+use std::collections::HashSet;
+use std::path::Path;
+
 use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD as base64, Engine as _};
+use chrono::{DateTime, Utc};
+use colored::Colorize;
 use env_common::{
     errors::ModuleError,
-    logic::{deprecate_module, precheck_module, publish_module},
+    interface::GenericCloudHandler,
+    logic::{
+        deprecate_module, get_modules_download_url, module_test_junit_report, precheck_module,
+        publish_module, publish_module_from_zip, run_module_tests, set_module_oci_reference,
+        unyank_module, yank_module, ModuleTestOptions, OCIRegistryProvider,
+    },
 };
 use env_defs::CloudProvider;
 use http_client::{
-    http_deprecate_module, http_get_all_latest_modules, http_get_all_versions_for_module,
-    http_get_module_version, is_http_mode_enabled, is_not_found_error,
+    http_deprecate_module, http_download_provider, http_get_all_latest_modules,
+    http_get_all_versions_for_module, http_get_module_version, http_yank_module,
+    is_http_mode_enabled, is_not_found_error,
 };
 use log::{error, info};
 
-use super::{exit_on_err, exit_on_none};
+use super::deployment::fetch_deployments_across_projects;
+use super::{exit_on_err, exit_on_none, print_structured, OutputFormat};
 use crate::current_region_handler;
 
-async fn fetch_all_latest_modules(track: &str) -> Result<Vec<env_defs::ModuleResp>> {
+pub async fn fetch_all_latest_modules(track: &str) -> Result<Vec<env_defs::ModuleResp>> {
     if is_http_mode_enabled() {
         Ok(http_get_all_latest_modules(track).await?)
     } else {
@@ -24,7 +37,7 @@ async fn fetch_all_latest_modules(track: &str) -> Result<Vec<env_defs::ModuleRes
     }
 }
 
-async fn fetch_module_version(
+pub async fn fetch_module_version(
     track: &str,
     module: &str,
     version: &str,
@@ -43,7 +56,10 @@ async fn fetch_module_version(
     }
 }
 
-async fn fetch_all_module_versions(track: &str, module: &str) -> Result<Vec<env_defs::ModuleResp>> {
+pub async fn fetch_all_module_versions(
+    track: &str,
+    module: &str,
+) -> Result<Vec<env_defs::ModuleResp>> {
     if is_http_mode_enabled() {
         Ok(http_get_all_versions_for_module(track, module).await?)
     } else {
@@ -110,19 +126,18 @@ pub async fn handle_precheck(file: &str) {
     info!("Module prechecked successfully");
 }
 
-pub async fn handle_list(track: &str) {
+pub async fn handle_list(track: &str, output: OutputFormat) {
     let modules = exit_on_err(fetch_all_latest_modules(track).await);
+    if print_structured(&modules, output) {
+        return;
+    }
 
     println!(
         "{:<20} {:<20} {:<20} {:<15} {:<15} {:<10}",
         "Module", "ModuleName", "Version", "Track", "Status", "Ref"
     );
     for entry in &modules {
-        let status = if entry.deprecated {
-            "DEPRECATED"
-        } else {
-            "Active"
-        };
+        let status = version_status(entry);
         println!(
             "{:<20} {:<20} {:<20} {:<15} {:<15} {:<10}",
             entry.module, entry.module_name, entry.version, entry.track, status, entry.reference,
@@ -130,12 +145,19 @@ pub async fn handle_list(track: &str) {
     }
 }
 
-pub async fn handle_get(module: &str, version: &str) {
+pub async fn handle_get(module: &str, version: &str, readme: bool, output: OutputFormat) {
     let track = "dev";
     let module = exit_on_none(
         exit_on_err(fetch_module_version(track, module, version).await),
         "Module not found",
     );
+    if readme {
+        print_module_docs(&module, output);
+        return;
+    }
+    if print_structured(&module, output) {
+        return;
+    }
     println!(
         "Module: {}",
         serde_json::to_string_pretty(&module).unwrap_or_else(|_| "Failed to serialize".to_string())
@@ -146,10 +168,77 @@ pub async fn handle_get(module: &str, version: &str) {
             println!("   Reason: {}", msg);
         }
     }
+    print_yank_warning(&module, "module");
+}
+
+/// Status of a module or stack version in listings, a yank takes precedence over a deprecation
+pub fn version_status(entry: &env_defs::ModuleResp) -> &'static str {
+    if entry.yanked.is_some() {
+        "YANKED"
+    } else if entry.deprecated {
+        "DEPRECATED"
+    } else {
+        "Active"
+    }
+}
+
+/// Message shown next to the status of a module or stack version in listings
+pub fn version_message(entry: &env_defs::ModuleResp) -> String {
+    match &entry.yanked {
+        Some(yank) => {
+            let mut message = yank.reason.clone().unwrap_or_default();
+            if let Some(replacement) = &yank.replacement_version {
+                if !message.is_empty() {
+                    message.push(' ');
+                }
+                message.push_str(&format!("(use {})", replacement));
+            }
+            message
+        }
+        None => entry.deprecated_message.clone().unwrap_or_default(),
+    }
+}
+
+pub fn print_yank_warning(entry: &env_defs::ModuleResp, kind: &str) {
+    if let Some(yank) = &entry.yanked {
+        println!(
+            "\n⛔ WARNING: This {} version is YANKED and cannot be used for new claims",
+            kind
+        );
+        if let Some(reason) = &yank.reason {
+            println!("   Reason: {}", reason);
+        }
+        if let Some(replacement) = &yank.replacement_version {
+            println!("   Use version {} instead", replacement);
+        }
+    }
 }
 
-pub async fn handle_versions(module: &str, track: &str) {
+fn print_module_docs(module: &env_defs::ModuleResp, output: OutputFormat) {
+    let docs = serde_json::json!({
+        "readme": module.readme,
+        "changelog": module.changelog,
+    });
+    if print_structured(&docs, output) {
+        return;
+    }
+    match &module.readme {
+        Some(readme) => println!("{}", readme.trim_end()),
+        None => println!(
+            "No README.md was bundled with {} version {}",
+            module.module, module.version
+        ),
+    }
+    if let Some(changelog) = &module.changelog {
+        println!("\n{}", changelog.trim_end());
+    }
+}
+
+pub async fn handle_versions(module: &str, track: &str, output: OutputFormat) {
     let versions = exit_on_err(fetch_all_module_versions(track, module).await);
+    if print_structured(&versions, output) {
+        return;
+    }
 
     if versions.is_empty() {
         println!("No versions found for module {} on track {}", module, track);
@@ -161,12 +250,8 @@ pub async fn handle_versions(module: &str, track: &str) {
         "Version", "Status", "Created", "Message"
     );
     for entry in &versions {
-        let status = if entry.deprecated {
-            "DEPRECATED"
-        } else {
-            "Active"
-        };
-        let message = entry.deprecated_message.as_deref().unwrap_or("");
+        let status = version_status(entry);
+        let message = version_message(entry);
         println!(
             "{:<20} {:<15} {:<30} {}",
             entry.version, status, entry.timestamp, message
@@ -174,6 +259,121 @@ pub async fn handle_versions(module: &str, track: &str) {
     }
 }
 
+pub async fn handle_diff(
+    module: &str,
+    track: &str,
+    version1: &str,
+    version2: &str,
+    schema: bool,
+    output: OutputFormat,
+) {
+    let diff = exit_on_err(do_diff_module(module, track, version1, version2, schema).await);
+    if print_structured(&diff, output) {
+        return;
+    }
+
+    if diff.added.is_empty() && diff.changed.is_empty() && diff.removed.is_empty() {
+        println!(
+            "No changes to the Terraform code between {} and {}",
+            version1, version2
+        );
+    }
+    for addition in &diff.added {
+        println!(
+            "{}",
+            format!("+ {} = {}", addition.path, addition.value).green()
+        );
+    }
+    for change in &diff.changed {
+        println!(
+            "{}",
+            format!(
+                "~ {}: {} -> {}",
+                change.path, change.old_value, change.new_value
+            )
+            .yellow()
+        );
+    }
+    for removal in &diff.removed {
+        println!(
+            "{}",
+            format!("- {} = {}", removal.path, removal.value).red()
+        );
+    }
+
+    if let Some(schema_changes) = &diff.schema {
+        println!("\nSchema changes:");
+        if schema_changes.is_empty() {
+            println!("No changes to variables or outputs");
+        }
+        for change in schema_changes {
+            let line = format!("{} {}: {}", change.kind, change.name, change.change);
+            if change.breaking {
+                println!("{} {}", format!("{:<12}", "BREAKING").red().bold(), line);
+            } else {
+                println!("{:<12} {}", "", line);
+            }
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct ModuleDiff {
+    module: String,
+    from_version: String,
+    to_version: String,
+    added: Vec<env_defs::ModuleDiffAddition>,
+    changed: Vec<env_defs::ModuleDiffChange>,
+    removed: Vec<env_defs::ModuleDiffRemoval>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema: Option<Vec<env_defs::ModuleSchemaChange>>,
+}
+
+async fn do_diff_module(
+    module: &str,
+    track: &str,
+    version1: &str,
+    version2: &str,
+    schema: bool,
+) -> Result<ModuleDiff> {
+    let mut tf_contents = vec![];
+    let mut module_versions = vec![];
+    for version in [version1, version2] {
+        let module_resp = fetch_module_version(track, module, version)
+            .await?
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Module {} version {} not found on track {}",
+                    module,
+                    version,
+                    track
+                )
+            })?;
+        let zip = download_module_zip(&module_resp.s3_key).await?;
+        tf_contents.push(env_utils::read_tf_from_zip(&zip)?);
+        module_versions.push(module_resp);
+    }
+
+    let (added, changed, removed) = env_utils::diff_modules(&tf_contents[0], &tf_contents[1]);
+    let schema = schema.then(|| {
+        env_utils::diff_module_schema(
+            &module_versions[0].tf_variables,
+            &module_versions[1].tf_variables,
+            &module_versions[0].tf_outputs,
+            &module_versions[1].tf_outputs,
+        )
+    });
+    Ok(ModuleDiff {
+        module: module.to_string(),
+        from_version: version1.to_string(),
+        to_version: version2.to_string(),
+        added,
+        changed,
+        removed,
+        schema,
+    })
+}
+
 pub async fn handle_deprecate(module: &str, track: &str, version: &str, message: Option<&str>) {
     exit_on_err(do_deprecate_module(module, track, version, message).await);
     info!(
@@ -181,3 +381,526 @@ pub async fn handle_deprecate(module: &str, track: &str, version: &str, message:
         module, version, track
     );
 }
+
+/// Yanks a module version, or lifts its yank with `undo`. The track follows from the version
+pub async fn handle_yank(
+    module: &str,
+    version: &str,
+    reason: Option<&str>,
+    replacement: Option<&str>,
+    undo: bool,
+) {
+    let track = exit_on_err(env_utils::get_version_track(version));
+    if is_http_mode_enabled() {
+        let body = serde_json::json!({
+            "reason": reason,
+            "replacement_version": replacement,
+            "undo": undo,
+        });
+        let response = exit_on_err(http_yank_module(&track, module, version, &body).await);
+        let yank: Option<env_defs::ModuleYank> = response
+            .get("yanked")
+            .and_then(|v| serde_json::from_value(v.clone()).ok());
+        print_yank_result(module, version, &track, yank.as_ref());
+    } else if undo {
+        exit_on_err(unyank_module(&current_region_handler().await, module, &track, version).await);
+        print_yank_result(module, version, &track, None);
+    } else {
+        let yank = exit_on_err(
+            yank_module(
+                &current_region_handler().await,
+                module,
+                &track,
+                version,
+                reason,
+                replacement,
+                None,
+            )
+            .await,
+        );
+        print_yank_result(module, version, &track, Some(&yank));
+    }
+}
+
+pub fn print_yank_result(
+    name: &str,
+    version: &str,
+    track: &str,
+    yank: Option<&env_defs::ModuleYank>,
+) {
+    let Some(yank) = yank else {
+        info!(
+            "{} version {} in track {} is no longer yanked",
+            name, version, track
+        );
+        return;
+    };
+    info!(
+        "{} version {} in track {} has been yanked, existing deployments keep using it",
+        name, version, track
+    );
+    match &yank.replacement_version {
+        Some(replacement) => info!("New claims are pointed at version {}", replacement),
+        None => info!("No replacement version is available, consider publishing one"),
+    }
+}
+
+/// Downloads the zip of a module version from the modules bucket
+pub async fn download_module_zip(s3_key: &str) -> Result<Vec<u8>> {
+    if is_http_mode_enabled() {
+        http_download_provider(s3_key).await
+    } else {
+        let handler = current_region_handler().await;
+        let url = get_modules_download_url(&handler, s3_key)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get download url for {}: {}", s3_key, e))?;
+        env_utils::download_zip_to_vec(&url).await
+    }
+}
+
+/// Downloads an artifact stored by the package webhook to `destination`
+async fn download_oci_artifact(key: &str, destination: &Path) -> Result<()> {
+    if is_http_mode_enabled() {
+        let bytes = http_download_provider(key).await?;
+        std::fs::write(destination, bytes)?;
+    } else {
+        let handler = current_region_handler().await;
+        let url = get_modules_download_url(&handler, key)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get download url for {}: {}", key, e))?;
+        env_utils::download_zip(&url, destination).await?;
+    }
+    Ok(())
+}
+
+async fn do_verify_attestations(
+    module: &str,
+    track: &str,
+    version: &str,
+    config_path: Option<&str>,
+) -> Result<()> {
+    let module_resp = fetch_module_version(track, module, version)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Module {} version {} not found", module, version))?;
+    let artifact_set = module_resp.oci_artifact_set.ok_or_else(|| {
+        anyhow::anyhow!(
+            "Module {} version {} was not published as an OCI artifact",
+            module,
+            version
+        )
+    })?;
+
+    let directory = env_utils::create_temp_dir()?;
+    let tags = [
+        Some(&artifact_set.tag_main),
+        artifact_set.tag_signature.as_ref(),
+        artifact_set.tag_attestation.as_ref(),
+    ];
+    for tag in tags.into_iter().flatten() {
+        let key = format!(
+            "{}/{}.tar.gz",
+            artifact_set.oci_artifact_path.trim_end_matches('/'),
+            tag
+        );
+        download_oci_artifact(&key, &directory.join(format!("{}.tar.gz", tag))).await?;
+    }
+    // Modules published before SBOMs were generated have none stored
+    if let Some(tag) = &artifact_set.tag_sbom {
+        let key = format!(
+            "{}/{}.tar.gz",
+            artifact_set.oci_artifact_path.trim_end_matches('/'),
+            tag
+        );
+        if let Err(e) =
+            download_oci_artifact(&key, &directory.join(format!("{}.tar.gz", tag))).await
+        {
+            info!("No SBOM found for {} version {}: {}", module, version, e);
+        }
+    }
+
+    let result = env_utils::verify_oci_artifacts_offline_in(
+        &directory,
+        &artifact_set,
+        config_path,
+        &env_defs::ArtifactVerificationPolicy::default(),
+    );
+    let _ = std::fs::remove_dir_all(&directory);
+    result
+}
+
+pub async fn handle_attest_verify(
+    module: &str,
+    track: &str,
+    version: &str,
+    config_path: Option<&str>,
+) {
+    exit_on_err(do_verify_attestations(module, track, version, config_path).await);
+    println!(
+        "✅ Attestations of module {} version {} in track {} verified",
+        module, version, track
+    );
+}
+
+/// Outcome of `module verify`, comparing the stored zip of a version with its recorded digest
+#[derive(serde::Serialize)]
+struct ArtifactVerification {
+    module: String,
+    track: String,
+    version: String,
+    s3_key: String,
+    recorded_digest: Option<String>,
+    stored_digest: String,
+    verified: bool,
+}
+
+async fn do_verify_artifact(
+    module: &str,
+    track: &str,
+    version: &str,
+) -> Result<ArtifactVerification> {
+    let module_resp = fetch_module_version(track, module, version)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Module {} version {} not found", module, version))?;
+    let zip = download_module_zip(&module_resp.s3_key).await?;
+    let stored_digest = env_utils::artifact_digest(&zip);
+    Ok(ArtifactVerification {
+        module: module.to_string(),
+        track: track.to_string(),
+        version: version.to_string(),
+        s3_key: module_resp.s3_key,
+        verified: module_resp.digest.as_ref() == Some(&stored_digest),
+        recorded_digest: module_resp.digest,
+        stored_digest,
+    })
+}
+
+pub async fn handle_verify(module: &str, track: &str, version: &str, output: OutputFormat) {
+    let verification = exit_on_err(do_verify_artifact(module, track, version).await);
+    if !print_structured(&verification, output) {
+        match &verification.recorded_digest {
+            None => println!(
+                "⚠️  Module {} version {} was published before digests were recorded, stored zip has digest {}",
+                module, version, verification.stored_digest
+            ),
+            Some(_) if verification.verified => println!(
+                "✅ Stored zip of module {} version {} in track {} matches its digest {}",
+                module, version, track, verification.stored_digest
+            ),
+            Some(recorded) => println!(
+                "❌ Stored zip of module {} version {} in track {} has digest {}, but {} was recorded when it was published",
+                module, version, track, verification.stored_digest, recorded
+            ),
+        }
+    }
+    if !verification.verified {
+        std::process::exit(1);
+    }
+}
+
+async fn do_push_module(
+    module: &str,
+    track: &str,
+    version: &str,
+    repository: &str,
+) -> Result<String> {
+    let module_resp = fetch_module_version(track, module, version)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Module {} version {} not found", module, version))?;
+    let zip = download_module_zip(&module_resp.s3_key).await?;
+    let registry = OCIRegistryProvider::new(
+        repository.to_string(),
+        std::env::var("OCI_REGISTRY_USERNAME").ok(),
+        std::env::var("OCI_REGISTRY_PASSWORD").ok(),
+    );
+    let oci_reference = registry
+        .upload_module(&module_resp, &base64.encode(&zip))
+        .await?;
+
+    if is_http_mode_enabled() {
+        info!("HTTP mode: the reference is not recorded on the module version");
+    } else {
+        set_module_oci_reference(
+            &current_region_handler().await,
+            module,
+            track,
+            version,
+            &oci_reference,
+        )
+        .await?;
+    }
+    Ok(oci_reference)
+}
+
+pub async fn handle_push(module: &str, track: &str, version: &str, repository: &str) {
+    let oci_reference = exit_on_err(do_push_module(module, track, version, repository).await);
+    println!(
+        "✅ Pushed module {} version {} to {}",
+        module, version, oci_reference
+    );
+}
+
+async fn do_pull_module(
+    reference: &str,
+    track: Option<&str>,
+    output: Option<&str>,
+) -> Result<env_defs::ModuleResp> {
+    let registry = OCIRegistryProvider::new(
+        reference.to_string(),
+        std::env::var("OCI_REGISTRY_USERNAME").ok(),
+        std::env::var("OCI_REGISTRY_PASSWORD").ok(),
+    );
+    let pulled = registry.pull_module(reference).await?;
+    info!("Pulled {}", pulled.reference);
+    let module = pulled.module;
+
+    if let Some(output) = output {
+        let directory = Path::new(output);
+        std::fs::create_dir_all(directory)?;
+        let name = format!("{}-{}", module.module, module.version);
+        std::fs::write(directory.join(format!("{}.zip", name)), &pulled.zip)?;
+        std::fs::write(
+            directory.join(format!("{}.json", name)),
+            serde_json::to_string_pretty(&module)?,
+        )?;
+        return Ok(module);
+    }
+
+    publish_module_from_zip(
+        &current_region_handler().await,
+        module.manifest.clone(),
+        track.unwrap_or(&module.track),
+        &pulled.zip,
+        None,
+        None,
+    )
+    .await?;
+    Ok(module)
+}
+
+pub async fn handle_pull(reference: &str, track: Option<&str>, output: Option<&str>) {
+    let module = exit_on_err(do_pull_module(reference, track, output).await);
+    match output {
+        Some(output) => println!(
+            "✅ Saved module {} version {} to {}",
+            module.module, module.version, output
+        ),
+        None => println!(
+            "✅ Published module {} version {} to track {}",
+            module.module,
+            module.version,
+            track.unwrap_or(&module.track)
+        ),
+    }
+}
+
+pub async fn handle_test(
+    module: &str,
+    track: &str,
+    version: &str,
+    options: ModuleTestOptions,
+    junit: Option<&str>,
+) {
+    let module_resp = exit_on_none(
+        exit_on_err(fetch_module_version(track, module, version).await),
+        &format!(
+            "Module {} version {} not found in track {}",
+            module, version, track
+        ),
+    );
+    let handler = GenericCloudHandler::region(&options.region).await;
+    let results = exit_on_err(run_module_tests(&handler, &module_resp, &options).await);
+
+    if let Some(junit) = junit {
+        exit_on_err(
+            std::fs::write(junit, module_test_junit_report(&module_resp, &results))
+                .map_err(anyhow::Error::from),
+        );
+        info!("Wrote JUnit report to {}", junit);
+    }
+
+    println!("{:<30} {:<10} {:<10}", "Example", "Result", "Duration");
+    for result in &results {
+        let outcome = if result.passed() { "passed" } else { "FAILED" };
+        println!(
+            "{:<30} {:<10} {:<10}",
+            result.example,
+            outcome,
+            format!("{}s", result.duration.as_secs())
+        );
+        if let Some(failure) = &result.failure {
+            println!("  {}", failure);
+        }
+        if let Some(cleanup_failure) = &result.cleanup_failure {
+            println!(
+                "  {} was not destroyed, clean it up by hand: {}",
+                result.deployment_id, cleanup_failure
+            );
+        }
+    }
+
+    if results.iter().any(|r| !r.passed()) {
+        std::process::exit(1);
+    }
+}
+
+/// A module version no deployment uses, which has been superseded by a newer version
+#[derive(Debug, PartialEq)]
+struct PruneCandidate {
+    version: String,
+    deprecated: bool,
+    superseded_days: i64,
+}
+
+/// Versions that are not in use and were superseded at least `min_superseded_days` ago.
+/// The latest version is never a candidate, and versions that are not valid semver are skipped
+fn select_prune_candidates(
+    versions: &[env_defs::ModuleResp],
+    versions_in_use: &HashSet<String>,
+    min_superseded_days: i64,
+    now: DateTime<Utc>,
+) -> Vec<PruneCandidate> {
+    let mut sorted: Vec<(semver::Version, &env_defs::ModuleResp)> = versions
+        .iter()
+        .filter_map(|m| match semver::Version::parse(&m.version) {
+            Ok(version) => Some((version, m)),
+            Err(e) => {
+                error!("Skipping version {} of {}: {}", m.version, m.module, e);
+                None
+            }
+        })
+        .collect();
+    sorted.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    sorted
+        .windows(2)
+        .filter_map(|pair| {
+            let (_, module) = pair[0];
+            let (_, successor) = pair[1];
+            if versions_in_use.contains(&module.version) {
+                return None;
+            }
+            // A version is superseded from the moment its successor was published
+            let superseded_at = DateTime::parse_from_rfc3339(&successor.timestamp).ok()?;
+            let superseded_days = (now - superseded_at.with_timezone(&Utc)).num_days();
+            (superseded_days >= min_superseded_days).then(|| PruneCandidate {
+                version: module.version.clone(),
+                deprecated: module.deprecated,
+                superseded_days,
+            })
+        })
+        .collect()
+}
+
+pub async fn handle_prune_candidates(
+    module: Option<&str>,
+    track: &str,
+    min_superseded_days: i64,
+    apply: bool,
+) {
+    let modules: Vec<String> = match module {
+        Some(module) => vec![module.to_string()],
+        None => exit_on_err(fetch_all_latest_modules(track).await)
+            .into_iter()
+            .map(|m| m.module)
+            .collect(),
+    };
+    let deployments = exit_on_err(fetch_deployments_across_projects(None, None).await);
+
+    println!(
+        "{:<20} {:<20} {:<15} {:<12} {}",
+        "Module", "Version", "Status", "Superseded", "Suggestion"
+    );
+    let mut deprecations = Vec::new();
+    for module in &modules {
+        let versions = exit_on_err(fetch_all_module_versions(track, module).await);
+        let versions_in_use: HashSet<String> = deployments
+            .iter()
+            .filter(|d| !d.deleted && &d.module == module && d.module_track == track)
+            .map(|d| d.module_version.clone())
+            .collect();
+
+        for candidate in
+            select_prune_candidates(&versions, &versions_in_use, min_superseded_days, Utc::now())
+        {
+            let (status, suggestion) = if candidate.deprecated {
+                ("DEPRECATED", "delete")
+            } else {
+                ("Active", "deprecate")
+            };
+            println!(
+                "{:<20} {:<20} {:<15} {:<12} {}",
+                module,
+                candidate.version,
+                status,
+                format!("{}d", candidate.superseded_days),
+                suggestion
+            );
+            if !candidate.deprecated {
+                deprecations.push((module.clone(), candidate));
+            }
+        }
+    }
+
+    if !apply {
+        return;
+    }
+    for (module, candidate) in &deprecations {
+        let message = format!(
+            "Superseded for {} days without deployments",
+            candidate.superseded_days
+        );
+        exit_on_err(do_deprecate_module(module, track, &candidate.version, Some(&message)).await);
+        info!(
+            "Module {} version {} in track {} has been deprecated",
+            module, candidate.version, track
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module_version(version: &str, timestamp: &str, deprecated: bool) -> env_defs::ModuleResp {
+        env_defs::ModuleResp {
+            module: "s3bucket".to_string(),
+            version: version.to_string(),
+            timestamp: timestamp.to_string(),
+            deprecated,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_select_prune_candidates() {
+        let versions = vec![
+            module_version("0.1.10", "2025-03-01T00:00:00.000Z", false),
+            module_version("0.1.2", "2025-01-01T00:00:00.000Z", false),
+            module_version("0.1.3", "2025-02-01T00:00:00.000Z", true),
+            module_version("0.1.1", "2024-12-01T00:00:00.000Z", false),
+            module_version("0.2.0", "2025-06-20T00:00:00.000Z", false),
+        ];
+        let versions_in_use = HashSet::from(["0.1.2".to_string()]);
+        let now = DateTime::parse_from_rfc3339("2025-07-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let candidates = select_prune_candidates(&versions, &versions_in_use, 30, now);
+
+        assert_eq!(
+            candidates,
+            vec![
+                PruneCandidate {
+                    version: "0.1.1".to_string(),
+                    deprecated: false,
+                    superseded_days: 181,
+                },
+                PruneCandidate {
+                    version: "0.1.3".to_string(),
+                    deprecated: true,
+                    superseded_days: 122,
+                },
+            ]
+        );
+    }
+}
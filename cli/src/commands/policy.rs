@@ -1,10 +1,11 @@
 use anyhow::Result;
-use env_common::logic::publish_policy;
+use env_common::logic::{publish_policy, publish_policy_pack, run_policy_tests};
 use env_defs::CloudProvider;
 use http_client::{http_get_policies, http_get_policy_version, is_http_mode_enabled};
 use log::{error, info};
+use std::path::Path;
 
-use super::exit_on_err;
+use super::{exit_on_err, print_structured, OutputFormat};
 use crate::current_region_handler;
 
 async fn fetch_all_policies(environment: &str) -> Result<Vec<env_defs::PolicyResp>> {
@@ -50,8 +51,23 @@ pub async fn handle_publish(file: &str, environment: &str) {
     }
 }
 
-pub async fn handle_list(environment: &str) {
+pub async fn handle_publish_pack(file: &str, environment: &str) {
+    match publish_policy_pack(&current_region_handler().await, file, environment).await {
+        Ok(_) => {
+            info!("Policy pack published successfully");
+        }
+        Err(e) => {
+            error!("Failed to publish policy pack: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+pub async fn handle_list(environment: &str, output: OutputFormat) {
     let policies = exit_on_err(fetch_all_policies(environment).await);
+    if print_structured(&policies, output) {
+        return;
+    }
 
     println!(
         "{:<30} {:<20} {:<20} {:<15} {:<10}",
@@ -65,7 +81,32 @@ pub async fn handle_list(environment: &str) {
     }
 }
 
-pub async fn handle_get(policy: &str, environment: &str, version: &str) {
+pub async fn handle_get(policy: &str, environment: &str, version: &str, output: OutputFormat) {
     let policy = exit_on_err(fetch_policy(policy, environment, version).await);
+    if print_structured(&policy, output) {
+        return;
+    }
     println!("Policy: {}", serde_json::to_string_pretty(&policy).unwrap());
 }
+
+pub fn handle_test(path: &str, fixtures: Option<&str>) {
+    let results = exit_on_err(run_policy_tests(Path::new(path), fixtures.map(Path::new)));
+    if results.is_empty() {
+        println!("No test rules or fixtures found in {}", path);
+        return;
+    }
+
+    for result in &results {
+        let outcome = if result.passed { "PASS" } else { "FAIL" };
+        println!("{:<5} {}", outcome, result.name);
+        if let Some(details) = &result.details {
+            println!("      {}", details);
+        }
+    }
+
+    let failed = results.iter().filter(|r| !r.passed).count();
+    println!("\n{} passed, {} failed", results.len() - failed, failed);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
@@ -1,11 +1,11 @@
 use colored::Colorize;
 
-use super::{exit_on_err, fetch_all_projects};
+use super::{exit_on_err, fetch_all_projects, print_structured, OutputFormat};
 use crate::current_region_handler;
 use env_defs::CloudProvider;
 use http_client::is_http_mode_enabled;
 
-pub async fn handle_get_current() {
+pub async fn handle_get_current(output: OutputFormat) {
     if is_http_mode_enabled() {
         eprintln!(
             "{}",
@@ -14,14 +14,20 @@ pub async fn handle_get_current() {
         std::process::exit(1);
     }
     let project = exit_on_err(current_region_handler().await.get_current_project().await);
+    if print_structured(&project, output) {
+        return;
+    }
     println!(
         "Project: {}",
         serde_json::to_string_pretty(&project).unwrap()
     );
 }
 
-pub async fn handle_get_all() {
+pub async fn handle_get_all(output: OutputFormat) {
     let projects = exit_on_err(fetch_all_projects().await);
+    if print_structured(&projects, output) {
+        return;
+    }
 
     if projects.is_empty() {
         println!("No projects found.");
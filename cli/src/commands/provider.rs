@@ -4,7 +4,7 @@ use env_defs::CloudProvider;
 use http_client::{http_get_all_latest_providers, is_http_mode_enabled};
 use log::{error, info};
 
-use super::exit_on_err;
+use super::{exit_on_err, print_structured, OutputFormat};
 use crate::current_region_handler;
 
 async fn fetch_all_latest_providers() -> Result<Vec<env_defs::ProviderResp>> {
@@ -38,8 +38,11 @@ pub async fn handle_publish(path: &str, version: Option<&str>, no_fail_on_exist:
     }
 }
 
-pub async fn handle_list() {
+pub async fn handle_list(output: OutputFormat) {
     let providers = exit_on_err(fetch_all_latest_providers().await);
+    if print_structured(&providers, output) {
+        return;
+    }
 
     println!(
         "{:<20} {:<20} {:<20} {:<15} {:<10}",
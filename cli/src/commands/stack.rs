@@ -1,16 +1,20 @@
 use anyhow::Result;
 use env_common::{
     errors::ModuleError,
-    logic::{deprecate_stack, get_stack_preview, publish_stack},
+    logic::{
+        deprecate_stack, get_stack_preview, get_stack_preview_configuration, publish_stack,
+        unyank_stack, yank_stack,
+    },
 };
 use env_defs::CloudProvider;
 use http_client::{
     http_deprecate_stack, http_get_all_latest_stacks, http_get_all_versions_for_stack,
-    http_get_stack_version, is_http_mode_enabled, is_not_found_error,
+    http_get_stack_version, http_yank_stack, is_http_mode_enabled, is_not_found_error,
 };
 use log::{error, info};
 
-use super::{exit_on_err, exit_on_none};
+use super::module::{print_yank_result, print_yank_warning, version_message, version_status};
+use super::{exit_on_err, exit_on_none, print_structured, OutputFormat};
 use crate::current_region_handler;
 
 async fn fetch_all_latest_stacks(track: &str) -> Result<Vec<env_defs::ModuleResp>> {
@@ -24,7 +28,7 @@ async fn fetch_all_latest_stacks(track: &str) -> Result<Vec<env_defs::ModuleResp
     }
 }
 
-async fn fetch_stack_version(
+pub async fn fetch_stack_version(
     track: &str,
     stack: &str,
     version: &str,
@@ -77,11 +81,23 @@ async fn do_deprecate_stack(
     }
 }
 
-pub async fn handle_preview(path: &str) {
-    let stack_module =
-        exit_on_err(get_stack_preview(&current_region_handler().await, &path.to_string()).await);
-    info!("Stack generated successfully");
-    println!("{}", stack_module);
+pub async fn handle_preview(path: &str, graph_format: Option<&str>) {
+    let handler = current_region_handler().await;
+    let Some(format) = graph_format else {
+        let stack_module = exit_on_err(get_stack_preview(&handler, &path.to_string()).await);
+        info!("Stack generated successfully");
+        println!("{}", stack_module);
+        return;
+    };
+
+    let configuration =
+        exit_on_err(get_stack_preview_configuration(&handler, &path.to_string()).await);
+    let graph = exit_on_err(graph::process_configuration(&configuration.to_string()));
+    info!("Stack graph generated successfully");
+    match format {
+        "mermaid" => println!("{}", graph.to_mermaid()),
+        _ => println!("{}", serde_json::to_string_pretty(&graph).unwrap()),
+    }
 }
 
 pub async fn handle_publish(
@@ -112,19 +128,18 @@ pub async fn handle_publish(
     }
 }
 
-pub async fn handle_list(track: &str) {
+pub async fn handle_list(track: &str, output: OutputFormat) {
     let stacks = exit_on_err(fetch_all_latest_stacks(track).await);
+    if print_structured(&stacks, output) {
+        return;
+    }
 
     println!(
         "{:<20} {:<20} {:<20} {:<15} {:<15} {:<10}",
         "Stack", "StackName", "Version", "Track", "Status", "Ref"
     );
     for entry in &stacks {
-        let status = if entry.deprecated {
-            "DEPRECATED"
-        } else {
-            "Active"
-        };
+        let status = version_status(entry);
         println!(
             "{:<20} {:<20} {:<20} {:<15} {:<15} {:<10}",
             entry.module, entry.module_name, entry.version, entry.track, status, entry.reference,
@@ -132,12 +147,15 @@ pub async fn handle_list(track: &str) {
     }
 }
 
-pub async fn handle_get(stack: &str, version: &str) {
+pub async fn handle_get(stack: &str, version: &str, output: OutputFormat) {
     let track = "dev";
     let stack = exit_on_none(
         exit_on_err(fetch_stack_version(track, stack, version).await),
         "Stack not found",
     );
+    if print_structured(&stack, output) {
+        return;
+    }
     println!("Stack: {}", serde_json::to_string_pretty(&stack).unwrap());
     if stack.deprecated {
         println!("\n⚠️  WARNING: This stack version is DEPRECATED");
@@ -145,10 +163,14 @@ pub async fn handle_get(stack: &str, version: &str) {
             println!("   Reason: {}", msg);
         }
     }
+    print_yank_warning(&stack, "stack");
 }
 
-pub async fn handle_versions(stack: &str, track: &str) {
+pub async fn handle_versions(stack: &str, track: &str, output: OutputFormat) {
     let versions = exit_on_err(fetch_all_stack_versions(track, stack).await);
+    if print_structured(&versions, output) {
+        return;
+    }
 
     if versions.is_empty() {
         println!("No versions found for stack {} on track {}", stack, track);
@@ -160,12 +182,8 @@ pub async fn handle_versions(stack: &str, track: &str) {
         "Version", "Status", "Created", "Message"
     );
     for entry in &versions {
-        let status = if entry.deprecated {
-            "DEPRECATED"
-        } else {
-            "Active"
-        };
-        let message = entry.deprecated_message.as_deref().unwrap_or("");
+        let status = version_status(entry);
+        let message = version_message(entry);
         println!(
             "{:<20} {:<15} {:<30} {}",
             entry.version, status, entry.timestamp, message
@@ -180,3 +198,43 @@ pub async fn handle_deprecate(stack: &str, track: &str, version: &str, message:
         stack, version, track
     );
 }
+
+/// Yanks a stack version, or lifts its yank with `undo`. The track follows from the version
+pub async fn handle_yank(
+    stack: &str,
+    version: &str,
+    reason: Option<&str>,
+    replacement: Option<&str>,
+    undo: bool,
+) {
+    let track = exit_on_err(env_utils::get_version_track(version));
+    if is_http_mode_enabled() {
+        let body = serde_json::json!({
+            "reason": reason,
+            "replacement_version": replacement,
+            "undo": undo,
+        });
+        let response = exit_on_err(http_yank_stack(&track, stack, version, &body).await);
+        let yank: Option<env_defs::ModuleYank> = response
+            .get("yanked")
+            .and_then(|v| serde_json::from_value(v.clone()).ok());
+        print_yank_result(stack, version, &track, yank.as_ref());
+    } else if undo {
+        exit_on_err(unyank_stack(&current_region_handler().await, stack, &track, version).await);
+        print_yank_result(stack, version, &track, None);
+    } else {
+        let yank = exit_on_err(
+            yank_stack(
+                &current_region_handler().await,
+                stack,
+                &track,
+                version,
+                reason,
+                replacement,
+                None,
+            )
+            .await,
+        );
+        print_yank_result(stack, version, &track, Some(&yank));
+    }
+}
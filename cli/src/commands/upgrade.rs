@@ -1,22 +1,84 @@
-use self_update::update::Release;
-
-fn get_target_name() -> &'static str {
-    match std::env::consts::OS {
-        "linux" => match std::env::consts::ARCH {
-            "x86_64" => "linux-amd64",
-            "aarch64" => "linux-arm64",
-            _ => panic!("Unsupported architecture"),
-        },
-        "macos" => match std::env::consts::ARCH {
-            "x86_64" => "macos-amd64",
-            "aarch64" => "macos-arm64",
-            _ => panic!("Unsupported architecture"),
-        },
-        "windows" => match std::env::consts::ARCH {
-            "x86_64" => "windows-amd64",
-            _ => panic!("Unsupported architecture"),
-        },
-        _ => panic!("Unsupported operating system"),
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use reqwest::header::{ACCEPT, USER_AGENT};
+use self_update::update::{Release, ReleaseAsset};
+use std::io::Write;
+
+use super::exit_on_err;
+
+/// Ed25519 public key (base64 of the raw 32 bytes) that the release binaries are signed with,
+/// embedded by the release build from `INFRAWEAVE_RELEASE_PUBLIC_KEY`
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("INFRAWEAVE_RELEASE_PUBLIC_KEY");
+
+/// Release channel followed by `self-update`, named after the version tracks.
+/// A channel gets its own prereleases and the releases of every more stable channel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum ReleaseChannel {
+    #[default]
+    Stable,
+    Rc,
+    Beta,
+    Alpha,
+    Dev,
+}
+
+impl ReleaseChannel {
+    /// Channel a version is released on, from the letters of its prerelease (`1.2.0-rc3` is `rc`)
+    fn of(version: &semver::Version) -> Option<ReleaseChannel> {
+        if version.pre.is_empty() {
+            return Some(ReleaseChannel::Stable);
+        }
+        let track: String = version
+            .pre
+            .as_str()
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect();
+        match track.as_str() {
+            "rc" => Some(ReleaseChannel::Rc),
+            "beta" => Some(ReleaseChannel::Beta),
+            "alpha" => Some(ReleaseChannel::Alpha),
+            "dev" => Some(ReleaseChannel::Dev),
+            _ => None,
+        }
+    }
+
+    fn includes(self, version: &semver::Version) -> bool {
+        ReleaseChannel::of(version).is_some_and(|channel| channel <= self)
+    }
+}
+
+impl std::fmt::Display for ReleaseChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReleaseChannel::Stable => write!(f, "stable"),
+            ReleaseChannel::Rc => write!(f, "rc"),
+            ReleaseChannel::Beta => write!(f, "beta"),
+            ReleaseChannel::Alpha => write!(f, "alpha"),
+            ReleaseChannel::Dev => write!(f, "dev"),
+        }
+    }
+}
+
+/// Platform suffix of the release binaries. Linux binaries are only released statically
+/// linked against musl, which also run on glibc systems
+fn get_target_name() -> Result<&'static str> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => Ok("linux-amd64-musl"),
+        ("linux", "aarch64") => Ok("linux-arm64-musl"),
+        ("macos", "x86_64") => Ok("macos-amd64"),
+        ("macos", "aarch64") => Ok("macos-arm64"),
+        ("windows", "x86_64") => Ok("windows-amd64"),
+        (os, arch) => Err(anyhow!("No releases are published for {} {}", os, arch)),
+    }
+}
+
+/// Name of the release asset holding the binary for `target`
+fn get_asset_name(target: &str) -> String {
+    if target.starts_with("windows") {
+        format!("cli-{}.exe", target)
+    } else {
+        format!("cli-{}", target)
     }
 }
 
@@ -30,37 +92,87 @@ fn get_current_version() -> semver::Version {
     })
 }
 
-fn get_latest_version(releases: &[Release], include_prerelease: bool) -> Option<semver::Version> {
+fn parse_release_version(release: &Release) -> Option<semver::Version> {
+    // Strip the 'v' prefix if present
+    let version_str = release
+        .version
+        .strip_prefix('v')
+        .unwrap_or(&release.version);
+    semver::Version::parse(version_str).ok()
+}
+
+/// Newest release of the channel, along with its version
+fn get_latest_release(
+    releases: &[Release],
+    channel: ReleaseChannel,
+) -> Option<(semver::Version, &Release)> {
     releases
         .iter()
-        .filter_map(|release| {
-            // Strip the 'v' prefix if present
-            let version_str = release
-                .version
-                .strip_prefix('v')
-                .unwrap_or(&release.version);
-            let version = semver::Version::parse(version_str).ok()?;
-
-            if include_prerelease {
-                // Include all versions (stable and pre-releases)
-                Some(version)
-            } else {
-                // Only include stable versions (no pre-releases)
-                if version.pre.is_empty() {
-                    Some(version)
-                } else {
-                    None
-                }
-            }
-        })
-        .max()
+        .filter_map(|release| Some((parse_release_version(release)?, release)))
+        .filter(|(version, _)| channel.includes(version))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
 }
 
 fn needs_upgrade(current: &semver::Version, latest: &semver::Version) -> bool {
     latest > current
 }
 
-pub async fn handle_upgrade(check_only: bool, include_prerelease: bool) {
+fn find_asset<'a>(release: &'a Release, name: &str) -> Result<&'a ReleaseAsset> {
+    release
+        .assets
+        .iter()
+        .find(|asset| asset.name == name)
+        .ok_or_else(|| anyhow!("Release {} has no asset {}", release.version, name))
+}
+
+/// Checks the Ed25519 signature of a downloaded binary against the release public key
+fn verify_signature(public_key: &str, binary: &[u8], signature: &[u8]) -> Result<()> {
+    let public_key = general_purpose::STANDARD
+        .decode(public_key.trim())
+        .context("Invalid release public key")?;
+    ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, public_key)
+        .verify(binary, signature)
+        .map_err(|_| anyhow!("The signature of the downloaded binary is not valid"))
+}
+
+async fn download_asset(client: &reqwest::Client, asset: &ReleaseAsset) -> Result<Vec<u8>> {
+    let response = client
+        .get(&asset.download_url)
+        .header(ACCEPT, "application/octet-stream")
+        .header(USER_AGENT, "infraweave-cli")
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| format!("Failed to download {}", asset.name))?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Downloads the binary of the release for this platform, verifies its signature and
+/// replaces the running executable with it
+async fn install_release(release: &Release) -> Result<()> {
+    let public_key = RELEASE_PUBLIC_KEY.filter(|key| !key.is_empty()).ok_or_else(|| {
+        anyhow!("This build has no release public key to verify updates with, install the new version manually")
+    })?;
+
+    let asset_name = get_asset_name(get_target_name()?);
+    let binary_asset = find_asset(release, &asset_name)?;
+    let signature_asset = find_asset(release, &format!("{}.sig", asset_name))?;
+
+    let client = reqwest::Client::new();
+    let binary = download_asset(&client, binary_asset).await?;
+    let signature = download_asset(&client, signature_asset).await?;
+    verify_signature(public_key, &binary, &signature)?;
+
+    // self_replace copies the binary next to the executable and renames it over it, so
+    // the executable is either the old or the new version if the update is interrupted
+    let mut new_binary = tempfile::NamedTempFile::new()?;
+    new_binary.write_all(&binary)?;
+    new_binary.flush()?;
+    self_replace::self_replace(new_binary.path()).context("Failed to replace the executable")?;
+    Ok(())
+}
+
+pub async fn handle_self_update(check_only: bool, channel: ReleaseChannel) {
     let current_version = get_current_version();
     println!("Current version: {}", current_version);
 
@@ -87,72 +199,89 @@ pub async fn handle_upgrade(check_only: bool, include_prerelease: bool) {
         }
     };
 
-    let latest_version = match get_latest_version(&releases, include_prerelease) {
-        Some(version) => version,
+    let (latest_version, latest_release) = match get_latest_release(&releases, channel) {
+        Some(latest) => latest,
         None => {
-            let release_type = if include_prerelease {
-                "releases"
-            } else {
-                "stable releases"
-            };
-            println!("No {} found", release_type);
+            println!("No releases found on the {} channel", channel);
             std::process::exit(1);
         }
     };
-    let version_type = if include_prerelease && !latest_version.pre.is_empty() {
-        "Latest version (including pre-release)"
-    } else {
-        "Latest stable version"
-    };
-    println!("{}: {}", version_type, latest_version);
+    println!(
+        "Latest version on the {} channel: {}",
+        channel, latest_version
+    );
 
     if needs_upgrade(&current_version, &latest_version) {
         println!(
-            "An upgrade is available: {} -> {}",
+            "An update is available: {} -> {}",
             current_version, latest_version
         );
 
         if check_only {
-            println!("Run 'infraweave upgrade' without --check to install the update.");
+            println!("Run 'infraweave self-update' without --check to install the update.");
         } else {
             println!("Downloading and installing version {}...", latest_version);
-
-            let target_name = get_target_name();
-            let bin_name = if cfg!(windows) { "cli.exe" } else { "cli" };
-            let bin_path = format!("cli-{}-v{}", target_name, latest_version);
-
-            let status = tokio::task::spawn_blocking(move || {
-                self_update::backends::github::Update::configure()
-                    .repo_owner("infraweave-io")
-                    .repo_name("infraweave")
-                    .bin_name(bin_name)
-                    .target(&target_name)
-                    .bin_path_in_archive(&bin_path)
-                    .target_version_tag(&format!("v{}", latest_version))
-                    .show_download_progress(true)
-                    .current_version(&current_version.to_string())
-                    .no_confirm(true)
-                    .build()
-                    .and_then(|updater| updater.update())
-            })
-            .await;
-
-            match status {
-                Ok(Ok(status)) => {
-                    println!("✓ Successfully upgraded to version {}", status.version());
-                    println!("Please restart the CLI to use the new version.");
-                }
-                Ok(Err(e)) => {
-                    eprintln!("Failed to upgrade: {}", e);
-                    std::process::exit(1);
-                }
-                Err(e) => {
-                    eprintln!("Error during upgrade: {}", e);
-                    std::process::exit(1);
-                }
-            }
+            exit_on_err(install_release(latest_release).await);
+            println!("✓ Successfully updated to version {}", latest_version);
+            println!("Please restart the CLI to use the new version.");
         }
     } else {
         println!("You are already on the latest version!");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    fn release(version: &str) -> Release {
+        Release {
+            name: version.to_string(),
+            version: version.to_string(),
+            date: String::new(),
+            body: None,
+            assets: vec![],
+        }
+    }
+
+    #[test]
+    fn test_get_latest_release() {
+        let releases = vec![
+            release("v1.1.0"),
+            release("v1.2.0-rc4"),
+            release("v1.2.0-dev7+abc1234"),
+            release("v1.0.0"),
+        ];
+        let latest = |channel| {
+            get_latest_release(&releases, channel)
+                .map(|(version, _)| version.to_string())
+                .unwrap()
+        };
+        assert_eq!(latest(ReleaseChannel::Stable), "1.1.0");
+        assert_eq!(latest(ReleaseChannel::Rc), "1.2.0-rc4");
+        assert_eq!(latest(ReleaseChannel::Beta), "1.2.0-rc4");
+        assert_eq!(latest(ReleaseChannel::Dev), "1.2.0-rc4");
+
+        let releases = vec![release("v1.1.0"), release("v1.3.0-dev2+abc1234")];
+        assert_eq!(
+            get_latest_release(&releases, ReleaseChannel::Dev)
+                .map(|(version, _)| version.to_string()),
+            Some("1.3.0-dev2+abc1234".to_string())
+        );
+        assert!(get_latest_release(&[release("v1.3.0-nightly")], ReleaseChannel::Dev).is_none());
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let public_key = general_purpose::STANDARD.encode(key_pair.public_key().as_ref());
+
+        let binary = b"new binary";
+        let signature = key_pair.sign(binary);
+        assert!(verify_signature(&public_key, binary, signature.as_ref()).is_ok());
+        assert!(verify_signature(&public_key, b"tampered binary", signature.as_ref()).is_err());
+    }
+}
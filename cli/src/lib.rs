@@ -6,7 +6,10 @@ pub mod tui;
 mod utils;
 
 pub use defs::ClaimJobStruct;
-pub use plan::{follow_driftcheck, follow_execution, DriftOutcome, SummaryTables};
+pub use plan::{
+    follow_driftcheck, follow_execution, follow_job_changes, follow_job_status, DriftOutcome,
+    JobChanges, SummaryTables,
+};
 pub use run::run_claim_file;
 pub use utils::{
     current_region_handler, get_environment, resolve_deployment_id,
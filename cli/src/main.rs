@@ -1,7 +1,8 @@
 use clap::{Args, Parser, Subcommand};
 use cli::{
-    commands, get_environment, resolve_environment_and_deployment, resolve_environment_id,
-    resolve_environment_id_for_new_deployment,
+    commands, commands::deployment::GraphFormat, commands::upgrade::ReleaseChannel,
+    commands::OutputFormat, get_environment, resolve_environment_and_deployment,
+    resolve_environment_id, resolve_environment_id_for_new_deployment,
 };
 use env_common::interface::initialize_project_id_and_region;
 use env_utils::setup_logging;
@@ -32,6 +33,10 @@ fn get_default_branch() -> String {
 #[command(author = "InfraWeave <opensource@infraweave.com>")]
 #[command(about = "Handles all InfraWeave CLI operations")]
 struct Cli {
+    /// Output format of read commands such as `module list` or `deployment describe`
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -67,9 +72,33 @@ enum Commands {
     GetCurrentProject,
     /// Get all projects
     GetAllProjects,
+    /// Check claims for common mistakes before planning or applying them
+    #[command(after_help = r#"Example:
+```
+$ infraweave lint claims/
+claims/bucket.yaml:11: error [variable-casing] spec.variables.bucket_name: Variable "bucket_name" should be written as "bucketName" (fixable with --fix)
+claims/bucket.yaml:7: warning [deprecated-version] spec.moduleVersion: Version 0.1.3 of s3bucket is deprecated
+```"#)]
+    Lint {
+        /// Claim files or directories of claim files to lint, e.g. claim.yaml
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Rewrite trivially fixable problems such as variable casing in place (comments are not kept)
+        #[arg(long)]
+        fix: bool,
+        /// Print the findings as SARIF for CI code scanning annotations
+        #[arg(long)]
+        sarif: bool,
+        /// Region the claims are expected to deploy to, flags claims deploying elsewhere
+        #[arg(long)]
+        region: Option<String>,
+        /// Only run the checks that don't need to look up the module of the claim
+        #[arg(long)]
+        offline: bool,
+    },
     /// Plan a claim to a specific environment
     Plan {
-        /// Claim file to deploy, e.g. claim.yaml
+        /// Claim file or directory of claim files to plan, e.g. claim.yaml. Files can hold several claims separated by `---`
         claim: String,
         /// Environment id used when planning, e.g. `default` (prompts with `default` as the default if not provided)
         #[arg(short, long)]
@@ -83,14 +112,55 @@ enum Commands {
         /// Flag to plan a destroy operation
         #[arg(long)]
         destroy: bool,
+        /// Variable file, as tfvars or as YAML/JSON for `.yaml`, `.yml` and `.json` files. Variables set in the claim take precedence
+        #[arg(long)]
+        var_file: Option<String>,
+        /// Variable to set as `key=value`, overriding the claim. Can be given several times, values are read as JSON if valid (`3`, `true`, `["a"]`)
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+        /// Exit with 0 when there are no changes, 2 when there are changes and 1 on errors
+        #[arg(long)]
+        detailed_exitcode: bool,
+        /// Print a JSON summary of the resources to add, change and destroy instead of the plan output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Compare a claim to the deployment it was last applied as: changed variables, the version
+    /// bump with its changes to the Terraform code, and the drift status of the deployment
+    #[command(after_help = r#"Examples:
+  infraweave diff dev claim.yaml
+  infraweave diff dev claim.yaml --output json"#)]
+    Diff {
+        /// Environment id of the deployment, e.g. dev or cli/dev
+        environment_id: String,
+        /// Claim file holding a single claim for a single region
+        claim: String,
+        /// Project ID (AWS account ID) for HTTP mode
+        #[arg(short, long)]
+        project: Option<String>,
     },
     /// Check drift of a deployment in a specific environment
+    #[command(after_help = r#"Examples:
+  infraweave driftcheck s3bucket/my-s3-bucket -e cli/default
+  infraweave driftcheck --all
+  infraweave driftcheck --all --module s3bucket --environment prod --output json"#)]
     Driftcheck {
         /// Deployment id to check, e.g. s3bucket/my-s3-bucket (optional, will prompt if not provided)
+        #[arg(conflicts_with = "all")]
         deployment_id: Option<String>,
-        /// Environment id used when checking drift, e.g. cli/default (optional, will prompt if not provided)
-        #[arg(short, long)]
+        /// Environment id used when checking drift, e.g. cli/default (optional, will prompt if not provided).
+        /// With --all, only deployments in matching environments are checked, e.g. prod or prod-*
+        #[arg(short, long, alias = "environment")]
         environment_id: Option<String>,
+        /// Check all deployments in the region and print a report of the drifted ones
+        #[arg(long)]
+        all: bool,
+        /// With --all, only check deployments of this module, e.g. s3bucket
+        #[arg(long, requires = "all")]
+        module: Option<String>,
+        /// With --all, how many drift checks are submitted at a time
+        #[arg(long, default_value_t = 10, requires = "all")]
+        concurrency: usize,
         /// Project ID (AWS account ID) for HTTP mode
         #[arg(short, long)]
         project: Option<String>,
@@ -103,7 +173,7 @@ enum Commands {
     },
     /// Apply a claim to a specific environment
     Apply {
-        /// Claim file to apply, e.g. claim.yaml
+        /// Claim file or directory of claim files to apply, e.g. claim.yaml. Files can hold several claims separated by `---`
         claim: String,
         /// Environment id used when applying, e.g. `default` (prompts with `default` as the default if not provided)
         #[arg(short, long)]
@@ -117,6 +187,31 @@ enum Commands {
         /// Do not stream progress; return immediately after the job is submitted
         #[arg(long)]
         no_follow: bool,
+        /// Variable file, as tfvars or as YAML/JSON for `.yaml`, `.yml` and `.json` files. Variables set in the claim take precedence
+        #[arg(long)]
+        var_file: Option<String>,
+        /// Variable to set as `key=value`, overriding the claim. Can be given several times, values are read as JSON if valid (`3`, `true`, `["a"]`)
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+    },
+    /// Apply the changes of a completed plan job exactly as they were planned
+    ApplyPlan {
+        /// Job id of the plan to apply
+        job_id: String,
+        /// Deployment id that was planned, e.g. s3bucket/my-s3-bucket (optional, will prompt if not provided)
+        deployment_id: Option<String>,
+        /// Environment id of the deployment, e.g. cli/default (optional, will prompt if not provided)
+        #[arg(short, long)]
+        environment_id: Option<String>,
+        /// Project ID (AWS account ID) for HTTP mode
+        #[arg(short, long)]
+        project: Option<String>,
+        /// Region for the deployment, only used in HTTP mode (ignored otherwise)
+        #[arg(long)]
+        region: Option<String>,
+        /// Do not stream progress; return immediately after the job is submitted
+        #[arg(long)]
+        no_follow: bool,
     },
     /// Delete resources in cloud
     Destroy {
@@ -140,8 +235,14 @@ enum Commands {
         /// Do not stream progress; return immediately after the job is submitted
         #[arg(long)]
         no_follow: bool,
+        /// Also destroy all deployments depending on this one, dependents first
+        #[arg(long, conflicts_with = "no_follow")]
+        cascade: bool,
+        /// Skip the confirmation of the deployments destroyed by --cascade
+        #[arg(long, requires = "cascade")]
+        yes: bool,
     },
-    /// Get YAML claim from a deployment
+    /// Get YAML claim from a deployment, or the claims of the modules in a stack deployment
     GetClaim {
         /// Deployment id to get claim for, e.g. s3bucket/my-s3-bucket (optional, will prompt if not provided)
         deployment_id: Option<String>,
@@ -167,13 +268,44 @@ enum Commands {
         region: Option<String>,
         /// Optional output file path (prints to stdout if not specified)
         #[arg(short, long)]
-        output: Option<String>,
+        output_file: Option<String>,
+    },
+    /// Print logs for a job, optionally following until the job finishes
+    Logs {
+        /// Job ID to print logs for
+        job_id: String,
+        /// Keep polling for new log lines until the job stops, then exit with its final status
+        #[arg(short, long)]
+        follow: bool,
+        /// Only show lines newer than this duration, e.g. 10m or 1h
+        #[arg(long)]
+        since: Option<String>,
+        /// Only show the last N lines of the existing logs
+        #[arg(long)]
+        tail: Option<usize>,
+        /// Deployment id the job belongs to, used to resolve the final status, e.g. s3bucket/my-s3-bucket
+        #[arg(short, long, requires = "environment_id")]
+        deployment_id: Option<String>,
+        /// Environment id of the deployment, e.g. cli/default
+        #[arg(short, long, requires = "deployment_id")]
+        environment_id: Option<String>,
+        /// Project ID, only used in HTTP mode (ignored otherwise)
+        #[arg(long)]
+        project: Option<String>,
+        /// Region for the deployment, only used in HTTP mode (ignored otherwise)
+        #[arg(long)]
+        region: Option<String>,
     },
     /// Work with deployments
     Deployments {
         #[command(subcommand)]
         command: DeploymentCommands,
     },
+    /// Review jobs held for approval in protected environments
+    Approvals {
+        #[command(subcommand)]
+        command: ApprovalCommands,
+    },
     /// Admin operations for advanced users (workspace setup, state file access)
     /// Requires elevated permissions to perform operations
     Admin {
@@ -181,7 +313,11 @@ enum Commands {
         command: AdminCommands,
     },
     /// Launch interactive TUI for exploring modules and deployments
-    Ui,
+    Ui {
+        /// Browse the snapshot of the last fetched data without connecting to the cloud
+        #[arg(long)]
+        offline: bool,
+    },
     /// Authenticate with InfraWeave API using AWS IAM credentials
     Login {
         /// API endpoint URL (e.g., https://api.example.com/v1) or use INFRAWEAVE_API_ENDPOINT env var
@@ -196,16 +332,55 @@ enum Commands {
         #[command(subcommand)]
         command: Option<McpCommands>,
     },
+    /// Interactively scaffold a claim for a module, prompting for its required variables
+    #[command(after_help = r#"Example:
+```
+$ infraweave init --track stable --module s3bucket
+Wrote claim for s3bucket version 0.1.4 to my-s3bucket.yaml
+```"#)]
+    Init {
+        /// Track to pick the module from, e.g. dev, beta, stable
+        #[arg(short, long, default_value = "stable")]
+        track: String,
+        /// Module to create a claim for, e.g. s3bucket (prompts if not provided)
+        #[arg(short, long)]
+        module: Option<String>,
+        /// Module version, e.g. 0.1.4 (prompts with the latest version if not provided)
+        #[arg(short, long)]
+        version: Option<String>,
+        /// Name of the deployment, used as metadata.name (prompts if not provided)
+        #[arg(short, long)]
+        name: Option<String>,
+        /// Region to deploy to, e.g. eu-west-1 (prompts if not provided)
+        #[arg(long)]
+        region: Option<String>,
+        /// File to write the claim to (defaults to <name>.yaml)
+        #[arg(short, long)]
+        file: Option<String>,
+        /// Overwrite the file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Generate shell completions, e.g. `infraweave completions zsh > _infraweave`
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
     /// Generate markdown documentation (hidden)
     #[command(hide = true)]
     GenerateDocs,
-    /// Upgrade to the latest released version of InfraWeave
-    Upgrade {
-        /// Only check for available upgrades without installing
+    /// Update InfraWeave to the latest release of a channel
+    #[command(alias = "upgrade")]
+    SelfUpdate {
+        /// Only check for available updates without installing
         #[arg(long)]
         check: bool,
-        /// Include pre-release versions (e.g. beta, rc)
-        #[arg(long)]
+        /// Release channel to follow, it also gets the releases of the more stable channels
+        #[arg(long, value_enum, default_value_t)]
+        channel: ReleaseChannel,
+        /// Same as `--channel dev`
+        #[arg(long, hide = true)]
         prerelease: bool,
     },
 }
@@ -276,6 +451,9 @@ Created: 2025-10-15 14:30:00
         module: String,
         /// Version to get, e.g. 0.1.4
         version: String,
+        /// Print the README and changelog bundled with the version instead of the module
+        #[arg(long)]
+        readme: bool,
     },
     /// List all versions of a specific module on a track
     #[command(after_help = r#"Example:
@@ -292,6 +470,33 @@ v0.1.2     Active       2025-10-10 09:15:00
         /// Track to list from, e.g. dev, beta, stable
         track: String,
     },
+    /// Show the changes between two versions of a module
+    #[command(after_help = r#"Example:
+```
+$ infraweave module diff s3bucket 0.1.3 0.1.4 --schema
++ /resource/aws_s3_bucket_versioning/versioning = {"bucket":"${aws_s3_bucket.bucket.id}"}
+~ /variable/tags/default: {} -> null
+- /output/bucket_id = {"value":"${aws_s3_bucket.bucket.id}"}
+
+Schema changes:
+BREAKING     variable tags: now required
+BREAKING     output bucket_id: removed
+             output bucket_domain: added
+```"#)]
+    Diff {
+        /// Module name, e.g. s3bucket
+        module: String,
+        /// Version to compare from, e.g. 0.1.3
+        version1: String,
+        /// Version to compare to, e.g. 0.1.4
+        version2: String,
+        /// Track of the module, e.g. dev, beta, stable
+        #[arg(long, default_value = "dev")]
+        track: String,
+        /// Also compare the variables and outputs, flagging breaking changes
+        #[arg(long)]
+        schema: bool,
+    },
     /// Configure versions for a module
     Version {
         #[command(subcommand)]
@@ -309,6 +514,149 @@ v0.1.2     Active       2025-10-10 09:15:00
         #[arg(short, long)]
         message: Option<String>,
     },
+    /// Yank a published version of a module, blocking it for new claims and upgrades
+    #[command(
+        after_help = r#"Deployments already running the version keep working, but new claims and upgrades to it are refused with an error pointing at the replacement version. Without --replacement the newest version of the track that is neither yanked nor deprecated is recommended.
+
+Example:
+```
+$ infraweave module yank s3bucket 0.1.4 --reason "Deletes the bucket policy on apply" --replacement 0.1.5
+$ infraweave module yank s3bucket 0.1.4 --undo
+```"#
+    )]
+    Yank {
+        /// Module name to yank, e.g. s3bucket
+        module: String,
+        /// Version to yank, e.g. 0.1.4
+        version: String,
+        /// Reason shown to users of the version
+        #[arg(short, long)]
+        reason: Option<String>,
+        /// Version recommended instead, e.g. 0.1.5
+        #[arg(long)]
+        replacement: Option<String>,
+        /// Lift the yank so new claims can use the version again
+        #[arg(long, conflicts_with_all = ["reason", "replacement"])]
+        undo: bool,
+    },
+    /// List module versions that are safe to deprecate or delete
+    #[command(
+        after_help = r#"Versions are candidates when no deployment uses them and a newer version has been published for a while.
+
+Example:
+```
+$ infraweave module prune-candidates dev s3bucket
+Module               Version              Status          Superseded   Suggestion
+s3bucket             0.1.1                DEPRECATED      120d         delete
+s3bucket             0.1.2                Active          95d          deprecate
+```"#
+    )]
+    PruneCandidates {
+        /// Track to check, e.g. dev, beta, stable
+        track: String,
+        /// Module name to check, e.g. s3bucket (all modules on the track if not provided)
+        module: Option<String>,
+        /// Minimum number of days since a newer version was published
+        #[arg(long, default_value_t = 30)]
+        superseded_days: i64,
+        /// Deprecate the listed versions that are still active
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Test a published module by deploying, asserting and destroying each of its examples
+    #[command(
+        after_help = r#"Each example is deployed into its own ephemeral environment and destroyed afterwards, also when the test fails or is interrupted.
+
+Example:
+```
+$ infraweave module test s3bucket dev 0.1.4-dev --region eu-central-1 --assert ./tests/assert.sh --junit report.xml
+Example                        Result     Duration
+simple                         passed     94s
+versioned                      FAILED     81s
+```"#
+    )]
+    Test(ModuleTestArgs),
+    /// Verify that the stored zip of a module version has the digest recorded when it was published
+    #[command(
+        after_help = r#"Exits with 1 if the digests differ, or if the version was published before digests were recorded.
+
+Example:
+```
+$ infraweave module verify s3bucket stable 0.1.4
+✅ Stored zip of module s3bucket version 0.1.4 in track stable matches its digest sha256:9f86d0...
+```"#
+    )]
+    Verify {
+        /// Module name, e.g. s3bucket
+        module: String,
+        /// Track of the module, e.g. dev, beta, stable
+        track: String,
+        /// Version to verify, e.g. 0.1.4
+        version: String,
+    },
+    /// Work with the SBOM and provenance attestations of published modules
+    Attest {
+        #[command(subcommand)]
+        command: ModuleAttestCommands,
+    },
+    /// Push a published module version to an OCI registry, such as GHCR, ECR or ACR
+    #[command(
+        after_help = r#"The artifact is tagged <module>-<version>, and its digest-pinned reference is recorded on the module version.
+Credentials are taken from OCI_REGISTRY_USERNAME and OCI_REGISTRY_PASSWORD if set, from the cloud provider for ECR and ACR, and otherwise from the docker config.
+
+Example:
+```
+$ infraweave module push s3bucket stable 0.1.4 ghcr.io/my-org/modules
+✅ Pushed module s3bucket version 0.1.4 to ghcr.io/my-org/modules@sha256:9f86d0...
+```"#
+    )]
+    Push {
+        /// Module name, e.g. s3bucket
+        module: String,
+        /// Track of the module, e.g. dev, beta, stable
+        track: String,
+        /// Version to push, e.g. 0.1.4
+        version: String,
+        /// Repository to push to, e.g. ghcr.io/my-org/modules
+        repository: String,
+    },
+    /// Pull a module from an OCI registry and publish it, e.g. to replicate it into an air-gapped environment
+    #[command(after_help = r#"Example:
+```
+$ infraweave module pull --from-oci ghcr.io/my-org/modules@sha256:9f86d0...
+✅ Published module s3bucket version 0.1.4 to track stable
+```"#)]
+    Pull {
+        /// Reference of the artifact, pinned by digest or tagged, e.g. ghcr.io/my-org/modules:s3bucket-0.1.4
+        #[arg(long, value_name = "REFERENCE")]
+        from_oci: String,
+        /// Track to publish to (the track the module was published to if not provided)
+        #[arg(long)]
+        track: Option<String>,
+        /// Write the module zip and metadata to this directory instead of publishing it
+        #[arg(long)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ModuleAttestCommands {
+    /// Verify the signature, attestations and SBOM of a module published as an OCI artifact
+    #[command(after_help = r#"Example:
+```
+$ infraweave module attest verify s3bucket stable 0.1.4
+```"#)]
+    Verify {
+        /// Module name, e.g. s3bucket
+        module: String,
+        /// Track of the module, e.g. dev, beta, stable
+        track: String,
+        /// Version to verify, e.g. 0.1.4
+        version: String,
+        /// Path to a JSON verification config, uses ATTESTATION_POLICY if not provided
+        #[arg(long)]
+        config: Option<String>,
+    },
 }
 
 #[derive(Args)]
@@ -343,6 +691,31 @@ struct ModulePrecheckArgs {
     description: Option<String>,
 }
 
+#[derive(Args)]
+struct ModuleTestArgs {
+    /// Module name to test, e.g. s3bucket
+    module: String,
+    /// Track of the module, e.g. dev, beta, stable
+    track: String,
+    /// Version to test, e.g. 0.1.4-dev
+    version: String,
+    /// Region to deploy the examples to
+    #[arg(long)]
+    region: String,
+    /// Example to test, can be repeated (all examples if not provided)
+    #[arg(long = "example")]
+    examples: Vec<String>,
+    /// Script to run after each apply, with the outputs as JSON in the file at INFRAWEAVE_TEST_OUTPUTS
+    #[arg(long)]
+    assert: Option<String>,
+    /// Write a JUnit XML report to this path
+    #[arg(long)]
+    junit: Option<String>,
+    /// Minutes to wait for each apply and destroy job
+    #[arg(long, default_value_t = 60)]
+    timeout: u64,
+}
+
 #[derive(Subcommand)]
 enum ModuleVersionCommands {
     /// Promote a version of a module to a new track, e.g. add 0.4.7 in dev to 0.4.7 in prod
@@ -355,6 +728,9 @@ enum StackCommands {
     Preview {
         /// Path to the stack to preview, e.g. ./src
         path: String,
+        /// Print the claims, their references and exposed variables/outputs as a graph instead of the generated Terraform
+        #[arg(long, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "json", value_parser = ["json", "mermaid"])]
+        graph: Option<String>,
     },
     /// Upload and publish a stack to a specific track
     Publish(StackPublishArgs),
@@ -410,6 +786,22 @@ v0.0.9     DEPRECATED   2025-10-12 10:20:00
         #[arg(short, long)]
         message: Option<String>,
     },
+    /// Yank a published version of a stack, blocking it for new claims and upgrades
+    Yank {
+        /// Stack name to yank, e.g. bucketcollection
+        stack: String,
+        /// Version to yank, e.g. 0.1.4
+        version: String,
+        /// Reason shown to users of the version
+        #[arg(short, long)]
+        reason: Option<String>,
+        /// Version recommended instead, e.g. 0.1.5
+        #[arg(long)]
+        replacement: Option<String>,
+        /// Lift the yank so new claims can use the version again
+        #[arg(long, conflicts_with_all = ["reason", "replacement"])]
+        undo: bool,
+    },
 }
 
 #[derive(Args)]
@@ -445,6 +837,13 @@ enum PolicyCommands {
         /// Metadata field for storing a description of the policy, e.g. a git commit message
         description: Option<String>,
     },
+    /// Publish a policy pack, grouping published policies that are enabled as a unit
+    PublishPack {
+        /// Environment id to publish to, e.g. cli/default (optional, will prompt if not provided)
+        environment_id: Option<String>,
+        /// Path to the directory containing policypack.yaml, e.g. ./packs/pci
+        file: String,
+    },
     /// List all latest versions of policies from a specific environment
     List {
         /// Environment to list from, e.g. aws, azure (optional, will prompt if not provided)
@@ -459,6 +858,24 @@ enum PolicyCommands {
         /// Version to get, e.g. 0.1.4
         version: String,
     },
+    /// Run the rego tests of a local policy and evaluate it against input fixtures
+    #[command(after_help = r#"Example:
+```
+$ infraweave policy test ./policies/regions --fixtures ./policies/regions/fixtures
+PASS  data.infraweave.regions.test_allowed_region
+PASS  allowed-plan.json
+FAIL  other-region-plan.json
+      {"regions":["aws_s3_bucket.this is in us-east-1"]}
+
+2 passed, 1 failed
+```"#)]
+    Test {
+        /// Path to the policy, the directory containing policy.yaml, e.g. ./src
+        path: String,
+        /// Directory of JSON input fixtures, fixtures ending with .deny.json are expected to be denied
+        #[arg(long)]
+        fixtures: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -502,6 +919,124 @@ enum DeploymentCommands {
         #[arg(long)]
         region: Option<String>,
     },
+    /// Show the terraform outputs of a deployment, with sensitive values masked
+    Outputs {
+        /// Environment id where the deployment exists, e.g. cli/default (optional, will prompt if not provided)
+        environment_id: Option<String>,
+        /// Deployment id to show outputs of, e.g. s3bucket/my-s3-bucket (optional, will prompt if not provided)
+        deployment_id: Option<String>,
+        /// Show the values of sensitive outputs, requires permission to unmask outputs
+        #[arg(long)]
+        unmask: bool,
+        /// Project ID, only used in HTTP mode (ignored otherwise)
+        #[arg(long)]
+        project: Option<String>,
+        /// Region for the deployment, only used in HTTP mode (ignored otherwise)
+        #[arg(long)]
+        region: Option<String>,
+    },
+    /// Show which deployments of an environment depend on which, and the outputs they consume.
+    /// Use `--output json` for the nodes and edges of the graph
+    #[command(after_help = r#"Examples:
+  infraweave deployments graph cli/dev
+  infraweave deployments graph cli/dev --format dot | dot -Tsvg > deployments.svg"#)]
+    Graph {
+        /// Environment id to graph, e.g. cli/default
+        environment_id: String,
+        /// Language to render the graph in
+        #[arg(long, value_enum, default_value_t = GraphFormat::Mermaid)]
+        format: GraphFormat,
+        /// Project ID, only used in HTTP mode (ignored otherwise)
+        #[arg(long)]
+        project: Option<String>,
+        /// Region of the deployments, only used in HTTP mode (ignored otherwise)
+        #[arg(long)]
+        region: Option<String>,
+    },
+    /// Adopt infrastructure managed by an existing terraform state. The state is uploaded to the
+    /// backend of the deployment and the deployment is recorded without applying the claim
+    #[command(after_help = r#"Examples:
+  infraweave deployments import claim.yaml --state terraform.tfstate -e dev
+  terraform state pull | infraweave deployments import claim.yaml --state - -e dev --verify
+  infraweave deployments import claim.yaml --state s3://legacy-state/network/terraform.tfstate -e dev"#)]
+    Import {
+        /// Claim file of the deployment, holding a single claim for a single region
+        claim: String,
+        /// Terraform state to import: a file, `-` for stdin, or an address in the storage of the
+        /// cloud provider (`s3://<bucket>/<key>`, `https://<account>.blob.core.windows.net/<container>/<key>`)
+        #[arg(long)]
+        state: String,
+        /// Environment id to import into, e.g. `default` (prompts with `default` as the default if not provided)
+        #[arg(short, long)]
+        environment_id: Option<String>,
+        /// Run a plan of the claim after importing and report where it diverges from the state,
+        /// exiting with 2 if it does
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Release a terraform state lock left behind by a job that stopped without releasing it.
+    /// The lock ID is shown in the error of the job that failed to acquire the lock
+    #[command(after_help = r#"Examples:
+  infraweave deployments unlock cli/dev s3bucket/my-bucket --lock-id 4c6b1b5e-2c1d-8f6a-0b3e-6f4d2a1c9e7b"#)]
+    Unlock {
+        /// Environment id of the deployment, e.g. cli/dev
+        environment_id: String,
+        /// Deployment id to unlock, e.g. s3bucket/my-bucket
+        deployment_id: String,
+        /// ID of the lock to release
+        #[arg(long)]
+        lock_id: String,
+        /// Skip the confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ApprovalCommands {
+    /// List the jobs pending approval
+    List {
+        /// Environment id to list from, e.g. cli/prod (all environments if not provided)
+        environment_id: Option<String>,
+        /// Project ID, only used in HTTP mode (ignored otherwise)
+        #[arg(long)]
+        project: Option<String>,
+        /// Region to list from, only used in HTTP mode (ignored otherwise)
+        #[arg(long)]
+        region: Option<String>,
+    },
+    /// Approve the job pending approval of a deployment, starting it once enough approvers signed off
+    Approve {
+        /// Environment id of the deployment, e.g. cli/prod
+        environment_id: String,
+        /// Deployment id to approve, e.g. s3bucket/my-s3-bucket
+        deployment_id: String,
+        /// Comment stored with the approval
+        #[arg(short = 'm', long)]
+        comment: Option<String>,
+        /// Project ID, only used in HTTP mode (ignored otherwise)
+        #[arg(long)]
+        project: Option<String>,
+        /// Region for the deployment, only used in HTTP mode (ignored otherwise)
+        #[arg(long)]
+        region: Option<String>,
+    },
+    /// Reject the job pending approval of a deployment
+    Reject {
+        /// Environment id of the deployment, e.g. cli/prod
+        environment_id: String,
+        /// Deployment id to reject, e.g. s3bucket/my-s3-bucket
+        deployment_id: String,
+        /// Reason for the rejection, stored with the job
+        #[arg(short = 'm', long)]
+        comment: Option<String>,
+        /// Project ID, only used in HTTP mode (ignored otherwise)
+        #[arg(long)]
+        project: Option<String>,
+        /// Region for the deployment, only used in HTTP mode (ignored otherwise)
+        #[arg(long)]
+        region: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -533,7 +1068,92 @@ enum AdminCommands {
         region: Option<String>,
         /// Optional output file path (prints to stdout if not specified)
         #[arg(short, long)]
-        output: Option<String>,
+        output_file: Option<String>,
+    },
+    /// Break-glass: run a module version with raw variables instead of a claim file, for emergencies.
+    /// Validation and policies still apply, and the job is recorded as a break-glass action
+    RunModule {
+        /// Module and version to run, e.g. s3bucket@0.1.0
+        module: String,
+        /// Environment id to deploy to, e.g. cli/default
+        #[arg(short, long = "env")]
+        environment_id: String,
+        /// Name of the deployment, the deployment id becomes <module>/<name>
+        #[arg(long)]
+        name: String,
+        /// JSON file with the variables of the module, e.g. vars.json
+        #[arg(long)]
+        vars_file: String,
+        /// Only plan the changes instead of applying them
+        #[arg(long)]
+        plan: bool,
+        /// Don't follow the job after starting it
+        #[arg(long)]
+        no_follow: bool,
+        /// Project ID, only used in HTTP mode (ignored otherwise)
+        #[arg(long)]
+        project: Option<String>,
+        /// Region to deploy to (defaults to the current region)
+        #[arg(long)]
+        region: Option<String>,
+    },
+    /// Make projects and policy bundles match a platform configuration file
+    ApplyConfig {
+        /// Path to the platform configuration file, e.g. platform.yaml
+        file: String,
+        /// Only show the differences between the file and the current configuration
+        #[arg(long)]
+        plan: bool,
+    },
+    /// Delete change records and events older than the retention of the project, archiving them first
+    Prune {
+        /// Keep this many days of history (defaults to the retention in the project settings, 90 days if unset)
+        #[arg(long)]
+        days: Option<u32>,
+        /// Delete without writing a compressed archive to storage first
+        #[arg(long)]
+        no_archive: bool,
+        /// Only count what would be pruned
+        #[arg(long)]
+        dry_run: bool,
+        /// Region to prune (defaults to the current region)
+        #[arg(long)]
+        region: Option<String>,
+    },
+    /// Export and verify the audit log of deployment activity
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditCommands {
+    /// Write the audit events of a period as signed JSON lines for compliance archiving.
+    /// Each line is signed with the secret in INFRAWEAVE_AUDIT_SIGNING_KEY and chained to the line before it
+    #[command(after_help = r#"Example:
+```
+$ infraweave admin audit export --from 2024-01-01 --to 2024-03-31 -o audit-2024-q1.jsonl
+Exported 1284 audit events to audit-2024-q1.jsonl
+```"#)]
+    Export {
+        /// Start of the period, a date such as 2024-01-01 or an RFC 3339 timestamp
+        #[arg(long)]
+        from: String,
+        /// End of the period, a date (included) or an RFC 3339 timestamp (defaults to now)
+        #[arg(long)]
+        to: Option<String>,
+        /// Optional output file path (prints to stdout if not specified)
+        #[arg(short, long)]
+        output_file: Option<String>,
+        /// Region to export (defaults to the current region)
+        #[arg(long)]
+        region: Option<String>,
+    },
+    /// Check the signatures of an exported audit log with the secret in INFRAWEAVE_AUDIT_SIGNING_KEY
+    Verify {
+        /// Path to the exported audit log, e.g. audit-2024-q1.jsonl
+        file: String,
     },
 }
 
@@ -543,22 +1163,37 @@ async fn main() {
 
     match &cli.command {
         Commands::Plan { project, .. }
+        | Commands::Diff { project, .. }
         | Commands::Apply { project, .. }
         | Commands::Driftcheck { project, .. }
+        | Commands::ApplyPlan { project, .. }
         | Commands::Destroy { project, .. }
         | Commands::GetClaim { project, .. }
-        | Commands::GetLogs { project, .. } => {
+        | Commands::GetLogs { project, .. }
+        | Commands::Logs { project, .. } => {
             if let Some(project_id) = project {
                 let _ = env_common::logic::PROJECT_ID.set(project_id.clone());
             }
         }
         Commands::Deployments { command } => match command {
             DeploymentCommands::Describe { project, .. }
+            | DeploymentCommands::Outputs { project, .. }
+            | DeploymentCommands::Graph { project, .. }
             | DeploymentCommands::List { project, .. } => {
                 if let Some(project_id) = project {
                     let _ = env_common::logic::PROJECT_ID.set(project_id.clone());
                 }
             }
+            DeploymentCommands::Import { .. } | DeploymentCommands::Unlock { .. } => {}
+        },
+        Commands::Approvals { command } => match command {
+            ApprovalCommands::List { project, .. }
+            | ApprovalCommands::Approve { project, .. }
+            | ApprovalCommands::Reject { project, .. } => {
+                if let Some(project_id) = project {
+                    let _ = env_common::logic::PROJECT_ID.set(project_id.clone());
+                }
+            }
         },
         Commands::Admin { command } => match command {
             AdminCommands::SetupWorkspace { project, .. }
@@ -567,14 +1202,49 @@ async fn main() {
                     let _ = env_common::logic::PROJECT_ID.set(project_id.clone());
                 }
             }
+            AdminCommands::RunModule {
+                project, region, ..
+            } => {
+                if let Some(project_id) = project {
+                    let _ = env_common::logic::PROJECT_ID.set(project_id.clone());
+                }
+                if let Some(region) = region {
+                    let _ = env_common::logic::REGION.set(region.clone());
+                }
+            }
+            AdminCommands::Prune { region, .. } => {
+                if let Some(region) = region {
+                    let _ = env_common::logic::REGION.set(region.clone());
+                }
+            }
+            AdminCommands::ApplyConfig { .. } => {}
+            AdminCommands::Audit { command } => match command {
+                AuditCommands::Export { region, .. } => {
+                    if let Some(region) = region {
+                        let _ = env_common::logic::REGION.set(region.clone());
+                    }
+                }
+                AuditCommands::Verify { .. } => {}
+            },
         },
         _ => {}
     }
 
     match &cli.command {
-        Commands::Plan { claim, .. } | Commands::Apply { claim, .. } => {
+        Commands::Plan { claim, .. }
+        | Commands::Diff { claim, .. }
+        | Commands::Apply { claim, .. } => {
             if let Ok(content) = std::fs::read_to_string(claim) {
-                match serde_yaml::from_str::<env_defs::DeploymentManifest>(&content) {
+                // A claim fanned out to several regions initializes with the first one
+                let manifest = serde_yaml::from_str::<serde_yaml::Value>(&content)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|yaml| env_utils::expand_claim_regions(&yaml))
+                    .and_then(|claims| {
+                        Ok(serde_yaml::from_value::<env_defs::DeploymentManifest>(
+                            claims[0].clone(),
+                        )?)
+                    });
+                match manifest {
                     Ok(manifest) => {
                         let _ = env_common::logic::REGION.set(manifest.spec.region);
                     }
@@ -623,6 +1293,9 @@ async fn main() {
             Commands::Plan { project, .. } => {
                 require_project(project, "plan");
             }
+            Commands::Diff { project, .. } => {
+                require_project(project, "diff");
+            }
             Commands::Apply { project, .. } => {
                 require_project(project, "apply");
             }
@@ -638,6 +1311,12 @@ async fn main() {
                 require_project(project, "driftcheck");
                 resolve_region(region, "driftcheck");
             }
+            Commands::ApplyPlan {
+                project, region, ..
+            } => {
+                require_project(project, "apply-plan");
+                resolve_region(region, "apply-plan");
+            }
             Commands::GetClaim {
                 project, region, ..
             } => {
@@ -650,6 +1329,12 @@ async fn main() {
                 require_project(project, "get-logs");
                 resolve_region(region, "get-logs");
             }
+            Commands::Logs {
+                project, region, ..
+            } => {
+                require_project(project, "logs");
+                resolve_region(region, "logs");
+            }
             Commands::Deployments { command } => match command {
                 DeploymentCommands::List { project, .. } => {
                     require_project(project, "deployments list");
@@ -660,6 +1345,39 @@ async fn main() {
                     require_project(project, "deployments describe");
                     resolve_region(region, "deployments describe");
                 }
+                DeploymentCommands::Outputs {
+                    project, region, ..
+                } => {
+                    require_project(project, "deployments outputs");
+                    resolve_region(region, "deployments outputs");
+                }
+                DeploymentCommands::Graph {
+                    project, region, ..
+                } => {
+                    require_project(project, "deployments graph");
+                    resolve_region(region, "deployments graph");
+                }
+                DeploymentCommands::Import { .. } | DeploymentCommands::Unlock { .. } => {}
+            },
+            Commands::Approvals { command } => match command {
+                ApprovalCommands::List {
+                    project, region, ..
+                } => {
+                    require_project(project, "approvals list");
+                    resolve_region(region, "approvals list");
+                }
+                ApprovalCommands::Approve {
+                    project, region, ..
+                } => {
+                    require_project(project, "approvals approve");
+                    resolve_region(region, "approvals approve");
+                }
+                ApprovalCommands::Reject {
+                    project, region, ..
+                } => {
+                    require_project(project, "approvals reject");
+                    resolve_region(region, "approvals reject");
+                }
             },
             Commands::Admin { command } => match command {
                 AdminCommands::SetupWorkspace {
@@ -674,6 +1392,35 @@ async fn main() {
                     require_project(project, "admin get-state");
                     resolve_region(region, "admin get-state");
                 }
+                AdminCommands::RunModule {
+                    project, region, ..
+                } => {
+                    require_project(project, "admin run-module");
+                    resolve_region(region, "admin run-module");
+                }
+                AdminCommands::ApplyConfig { .. } => {
+                    eprintln!(
+                        "Error: 'admin apply-config' requires direct cloud access and is not available in HTTP mode."
+                    );
+                    std::process::exit(1);
+                }
+                AdminCommands::Prune { .. } => {
+                    eprintln!(
+                        "Error: 'admin prune' requires direct cloud access and is not available in HTTP mode."
+                    );
+                    std::process::exit(1);
+                }
+                AdminCommands::Audit {
+                    command: AuditCommands::Export { .. },
+                } => {
+                    eprintln!(
+                        "Error: 'admin audit export' requires direct cloud access and is not available in HTTP mode."
+                    );
+                    std::process::exit(1);
+                }
+                AdminCommands::Audit {
+                    command: AuditCommands::Verify { .. },
+                } => {}
             },
             _ => {}
         }
@@ -682,8 +1429,24 @@ async fn main() {
     // Skip initialization for documentation generation and MCP server
     // MCP uses stdio for JSON-RPC, so initialization logging would interfere
     let skip_init = matches!(cli.command, Commands::GenerateDocs)
-        || matches!(cli.command, Commands::Upgrade { .. })
+        || matches!(cli.command, Commands::Completions { .. })
+        || matches!(cli.command, Commands::SelfUpdate { .. })
         || matches!(cli.command, Commands::Login { .. })
+        || matches!(cli.command, Commands::Lint { offline: true, .. })
+        || matches!(
+            cli.command,
+            Commands::Policy {
+                command: PolicyCommands::Test { .. }
+            }
+        )
+        || matches!(
+            cli.command,
+            Commands::Admin {
+                command: AdminCommands::Audit {
+                    command: AuditCommands::Verify { .. }
+                }
+            }
+        )
         || matches!(cli.command, Commands::Mcp { command: None })
         || matches!(
             cli.command,
@@ -703,6 +1466,7 @@ async fn main() {
         initialize_project_id_and_region().await;
     }
 
+    let output = cli.output;
     match cli.command {
         Commands::Provider { command } => match command {
             ProviderCommands::Publish(args) => {
@@ -714,7 +1478,7 @@ async fn main() {
                 .await;
             }
             ProviderCommands::List => {
-                commands::provider::handle_list().await;
+                commands::provider::handle_list(output).await;
             }
         },
         Commands::Module { command } => match command {
@@ -733,14 +1497,45 @@ async fn main() {
                 let _environment_id = resolve_environment_id(args.environment_id).await;
                 commands::module::handle_precheck(&args.file).await;
             }
+            ModuleCommands::Test(args) => {
+                commands::module::handle_test(
+                    &args.module,
+                    &args.track,
+                    &args.version,
+                    env_common::logic::ModuleTestOptions {
+                        region: args.region,
+                        examples: args.examples,
+                        assert_script: args.assert.map(std::path::PathBuf::from),
+                        job_timeout: std::time::Duration::from_secs(args.timeout * 60),
+                    },
+                    args.junit.as_deref(),
+                )
+                .await;
+            }
             ModuleCommands::List { track } => {
-                commands::module::handle_list(&track).await;
+                commands::module::handle_list(&track, output).await;
             }
-            ModuleCommands::Get { module, version } => {
-                commands::module::handle_get(&module, &version).await;
+            ModuleCommands::Get {
+                module,
+                version,
+                readme,
+            } => {
+                commands::module::handle_get(&module, &version, readme, output).await;
             }
             ModuleCommands::Versions { module, track } => {
-                commands::module::handle_versions(&module, &track).await;
+                commands::module::handle_versions(&module, &track, output).await;
+            }
+            ModuleCommands::Diff {
+                module,
+                version1,
+                version2,
+                track,
+                schema,
+            } => {
+                commands::module::handle_diff(
+                    &module, &track, &version1, &version2, schema, output,
+                )
+                .await;
             }
             ModuleCommands::Version { command: _ } => {
                 eprintln!("Module version promote not yet implemented");
@@ -754,10 +1549,78 @@ async fn main() {
                 commands::module::handle_deprecate(&module, &track, &version, message.as_deref())
                     .await;
             }
+            ModuleCommands::Yank {
+                module,
+                version,
+                reason,
+                replacement,
+                undo,
+            } => {
+                commands::module::handle_yank(
+                    &module,
+                    &version,
+                    reason.as_deref(),
+                    replacement.as_deref(),
+                    undo,
+                )
+                .await;
+            }
+            ModuleCommands::PruneCandidates {
+                track,
+                module,
+                superseded_days,
+                apply,
+            } => {
+                commands::module::handle_prune_candidates(
+                    module.as_deref(),
+                    &track,
+                    superseded_days,
+                    apply,
+                )
+                .await;
+            }
+            ModuleCommands::Verify {
+                module,
+                track,
+                version,
+            } => {
+                commands::module::handle_verify(&module, &track, &version, output).await;
+            }
+            ModuleCommands::Attest { command } => match command {
+                ModuleAttestCommands::Verify {
+                    module,
+                    track,
+                    version,
+                    config,
+                } => {
+                    commands::module::handle_attest_verify(
+                        &module,
+                        &track,
+                        &version,
+                        config.as_deref(),
+                    )
+                    .await;
+                }
+            },
+            ModuleCommands::Push {
+                module,
+                track,
+                version,
+                repository,
+            } => {
+                commands::module::handle_push(&module, &track, &version, &repository).await;
+            }
+            ModuleCommands::Pull {
+                from_oci,
+                track,
+                output,
+            } => {
+                commands::module::handle_pull(&from_oci, track.as_deref(), output.as_deref()).await;
+            }
         },
         Commands::Stack { command } => match command {
-            StackCommands::Preview { path } => {
-                commands::stack::handle_preview(&path).await;
+            StackCommands::Preview { path, graph } => {
+                commands::stack::handle_preview(&path, graph.as_deref()).await;
             }
             StackCommands::Publish(args) => {
                 commands::stack::handle_publish(
@@ -769,13 +1632,13 @@ async fn main() {
                 .await;
             }
             StackCommands::List { track } => {
-                commands::stack::handle_list(&track).await;
+                commands::stack::handle_list(&track, output).await;
             }
             StackCommands::Get { stack, version } => {
-                commands::stack::handle_get(&stack, &version).await;
+                commands::stack::handle_get(&stack, &version, output).await;
             }
             StackCommands::Versions { stack, track } => {
-                commands::stack::handle_versions(&stack, &track).await;
+                commands::stack::handle_versions(&stack, &track, output).await;
             }
             StackCommands::Deprecate {
                 stack,
@@ -786,6 +1649,22 @@ async fn main() {
                 commands::stack::handle_deprecate(&stack, &track, &version, message.as_deref())
                     .await;
             }
+            StackCommands::Yank {
+                stack,
+                version,
+                reason,
+                replacement,
+                undo,
+            } => {
+                commands::stack::handle_yank(
+                    &stack,
+                    &version,
+                    reason.as_deref(),
+                    replacement.as_deref(),
+                    undo,
+                )
+                .await;
+            }
         },
         Commands::Policy { command } => match command {
             PolicyCommands::Publish {
@@ -798,10 +1677,18 @@ async fn main() {
                 let env = get_environment(&environment_id);
                 commands::policy::handle_publish(&file, &env).await;
             }
+            PolicyCommands::PublishPack {
+                environment_id,
+                file,
+            } => {
+                let environment_id = resolve_environment_id(environment_id).await;
+                let env = get_environment(&environment_id);
+                commands::policy::handle_publish_pack(&file, &env).await;
+            }
             PolicyCommands::List { environment_id } => {
                 let environment_id = resolve_environment_id(environment_id).await;
                 let env = get_environment(&environment_id);
-                commands::policy::handle_list(&env).await;
+                commands::policy::handle_list(&env, output).await;
             }
             PolicyCommands::Get {
                 policy,
@@ -810,7 +1697,10 @@ async fn main() {
             } => {
                 let environment_id = resolve_environment_id(environment_id).await;
                 let env = get_environment(&environment_id);
-                commands::policy::handle_get(&policy, &env, &version).await;
+                commands::policy::handle_get(&policy, &env, &version, output).await;
+            }
+            PolicyCommands::Test { path, fixtures } => {
+                commands::policy::handle_test(&path, fixtures.as_deref());
             }
         },
         Commands::Gitops { command } => match command {
@@ -851,10 +1741,10 @@ async fn main() {
             }
         },
         Commands::GetCurrentProject => {
-            commands::project::handle_get_current().await;
+            commands::project::handle_get_current(output).await;
         }
         Commands::GetAllProjects => {
-            commands::project::handle_get_all().await;
+            commands::project::handle_get_all(output).await;
         }
         Commands::GetClaim {
             environment_id,
@@ -871,9 +1761,34 @@ async fn main() {
             job_id,
             project: _,
             region: _,
-            output,
+            output_file,
         } => {
-            commands::deployment::handle_get_logs(&job_id, output.as_deref()).await;
+            commands::deployment::handle_get_logs(&job_id, output_file.as_deref()).await;
+        }
+        Commands::Logs {
+            job_id,
+            follow,
+            since,
+            tail,
+            deployment_id,
+            environment_id,
+            project: _,
+            region: _,
+        } => {
+            let environment = environment_id.as_deref().map(get_environment);
+            let deployment = deployment_id.as_deref().zip(environment.as_deref());
+            commands::deployment::handle_logs(&job_id, follow, since.as_deref(), tail, deployment)
+                .await;
+        }
+        Commands::Lint {
+            paths,
+            fix,
+            sarif,
+            region,
+            offline,
+        } => {
+            commands::lint::handle_lint(&paths, fix, sarif, region.as_deref(), offline, output)
+                .await;
         }
         Commands::Plan {
             environment_id,
@@ -881,10 +1796,51 @@ async fn main() {
             project: _,
             store_files,
             destroy,
+            var_file,
+            vars,
+            detailed_exitcode,
+            json,
         } => {
             let environment_id = resolve_environment_id_for_new_deployment(environment_id).await;
             let env = get_environment(&environment_id);
-            commands::claim::handle_plan(&env, &claim, store_files, destroy).await;
+            commands::claim::handle_plan(
+                &env,
+                &claim,
+                store_files,
+                destroy,
+                var_file.as_deref(),
+                &vars,
+                detailed_exitcode,
+                json,
+            )
+            .await;
+        }
+        Commands::Diff {
+            environment_id,
+            claim,
+            project: _,
+        } => {
+            commands::deployment::handle_diff(&get_environment(&environment_id), &claim, output)
+                .await;
+        }
+        Commands::Driftcheck {
+            environment_id,
+            deployment_id: _,
+            all: true,
+            module,
+            concurrency,
+            project: _,
+            region: _,
+            remediate,
+        } => {
+            commands::claim::handle_driftcheck_all(
+                module.as_deref(),
+                environment_id.as_deref(),
+                remediate,
+                concurrency,
+                output,
+            )
+            .await;
         }
         Commands::Driftcheck {
             environment_id,
@@ -892,6 +1848,7 @@ async fn main() {
             project: _,
             region: _,
             remediate,
+            ..
         } => {
             let (environment_id, deployment_id) =
                 resolve_environment_and_deployment(environment_id, deployment_id).await;
@@ -904,10 +1861,33 @@ async fn main() {
             project: _,
             store_files,
             no_follow,
+            var_file,
+            vars,
         } => {
             let environment_id = resolve_environment_id_for_new_deployment(environment_id).await;
             let env = get_environment(&environment_id);
-            commands::claim::handle_apply(&env, &claim, store_files, !no_follow).await;
+            commands::claim::handle_apply(
+                &env,
+                &claim,
+                store_files,
+                !no_follow,
+                var_file.as_deref(),
+                &vars,
+            )
+            .await;
+        }
+        Commands::ApplyPlan {
+            job_id,
+            deployment_id,
+            environment_id,
+            project: _,
+            region: _,
+            no_follow,
+        } => {
+            let (environment_id, deployment_id) =
+                resolve_environment_and_deployment(environment_id, deployment_id).await;
+            let env = get_environment(&environment_id);
+            commands::claim::handle_apply_plan(&deployment_id, &env, &job_id, !no_follow).await;
         }
         Commands::Destroy {
             environment_id,
@@ -917,22 +1897,35 @@ async fn main() {
             version,
             store_files,
             no_follow,
+            cascade,
+            yes,
         } => {
             let (environment_id, deployment_id) =
                 resolve_environment_and_deployment(environment_id, deployment_id).await;
             let env = get_environment(&environment_id);
-            commands::claim::handle_destroy(
-                &deployment_id,
-                &env,
-                version.as_deref(),
-                store_files,
-                !no_follow,
-            )
-            .await;
+            if cascade {
+                commands::claim::handle_destroy_cascade(
+                    &deployment_id,
+                    &env,
+                    version.as_deref(),
+                    yes,
+                )
+                .await;
+            } else {
+                commands::claim::handle_destroy(
+                    &deployment_id,
+                    &env,
+                    version.as_deref(),
+                    store_files,
+                    !no_follow,
+                )
+                .await;
+            }
         }
         Commands::Deployments { command } => match command {
             DeploymentCommands::List { project, region } => {
-                commands::deployment::handle_list(project.as_deref(), region.as_deref()).await;
+                commands::deployment::handle_list(project.as_deref(), region.as_deref(), output)
+                    .await;
             }
             DeploymentCommands::Describe {
                 environment_id,
@@ -942,7 +1935,101 @@ async fn main() {
             } => {
                 let (environment_id, deployment_id) =
                     resolve_environment_and_deployment(environment_id, deployment_id).await;
-                commands::deployment::handle_describe(&deployment_id, &environment_id).await;
+                commands::deployment::handle_describe(&deployment_id, &environment_id, output)
+                    .await;
+            }
+            DeploymentCommands::Outputs {
+                environment_id,
+                deployment_id,
+                unmask,
+                project: _,
+                region: _,
+            } => {
+                let (environment_id, deployment_id) =
+                    resolve_environment_and_deployment(environment_id, deployment_id).await;
+                commands::deployment::handle_outputs(
+                    &deployment_id,
+                    &environment_id,
+                    unmask,
+                    output,
+                )
+                .await;
+            }
+            DeploymentCommands::Graph {
+                environment_id,
+                format,
+                ..
+            } => {
+                commands::deployment::handle_graph(
+                    &get_environment(&environment_id),
+                    format,
+                    output,
+                )
+                .await;
+            }
+            DeploymentCommands::Import {
+                claim,
+                state,
+                environment_id,
+                verify,
+            } => {
+                let environment_id =
+                    resolve_environment_id_for_new_deployment(environment_id).await;
+                commands::deployment::handle_import(
+                    &get_environment(&environment_id),
+                    &claim,
+                    &state,
+                    verify,
+                )
+                .await;
+            }
+            DeploymentCommands::Unlock {
+                environment_id,
+                deployment_id,
+                lock_id,
+                yes,
+            } => {
+                commands::deployment::handle_unlock(
+                    &deployment_id,
+                    &get_environment(&environment_id),
+                    &lock_id,
+                    yes,
+                )
+                .await;
+            }
+        },
+        Commands::Approvals { command } => match command {
+            ApprovalCommands::List { environment_id, .. } => {
+                let environment = environment_id.as_deref().map(get_environment);
+                commands::approval::handle_list(environment.as_deref(), output).await;
+            }
+            ApprovalCommands::Approve {
+                environment_id,
+                deployment_id,
+                comment,
+                ..
+            } => {
+                commands::approval::handle_decide(
+                    &get_environment(&environment_id),
+                    &deployment_id,
+                    comment.as_deref().unwrap_or_default(),
+                    true,
+                )
+                .await;
+            }
+            ApprovalCommands::Reject {
+                environment_id,
+                deployment_id,
+                comment,
+                ..
+            } => {
+                commands::approval::handle_decide(
+                    &get_environment(&environment_id),
+                    &deployment_id,
+                    comment.as_deref().unwrap_or_default(),
+                    false,
+                )
+                .await;
             }
         },
         Commands::Admin { command } => match command {
@@ -961,20 +2048,65 @@ async fn main() {
                 deployment_id,
                 project: _,
                 region: _,
-                output,
+                output_file,
             } => {
                 let (environment_id, deployment_id) =
                     resolve_environment_and_deployment(environment_id, deployment_id).await;
                 commands::admin::handle_get_state(
                     &deployment_id,
                     &environment_id,
-                    output.as_deref(),
+                    output_file.as_deref(),
+                )
+                .await;
+            }
+            AdminCommands::RunModule {
+                module,
+                environment_id,
+                name,
+                vars_file,
+                plan,
+                no_follow,
+                project: _,
+                region: _,
+            } => {
+                let env = get_environment(&environment_id);
+                commands::admin::handle_run_module(
+                    &module, &env, &name, &vars_file, plan, !no_follow,
                 )
                 .await;
             }
+            AdminCommands::ApplyConfig { file, plan } => {
+                commands::admin::handle_apply_config(&file, plan).await;
+            }
+            AdminCommands::Prune {
+                days,
+                no_archive,
+                dry_run,
+                region: _,
+            } => {
+                commands::admin::handle_prune(days, !no_archive, dry_run).await;
+            }
+            AdminCommands::Audit { command } => match command {
+                AuditCommands::Export {
+                    from,
+                    to,
+                    output_file,
+                    region: _,
+                } => {
+                    commands::admin::handle_audit_export(
+                        &from,
+                        to.as_deref(),
+                        output_file.as_deref(),
+                    )
+                    .await;
+                }
+                AuditCommands::Verify { file } => {
+                    commands::admin::handle_audit_verify(&file);
+                }
+            },
         },
-        Commands::Ui => {
-            if let Err(e) = run_tui().await {
+        Commands::Ui { offline } => {
+            if let Err(e) = run_tui(offline).await {
                 eprintln!("Error running TUI: {}", e);
                 std::process::exit(1);
             }
@@ -1069,13 +2201,43 @@ async fn main() {
 
             println!("{}", output);
         }
-        Commands::Upgrade { check, prerelease } => {
-            commands::upgrade::handle_upgrade(check, prerelease).await;
+        Commands::SelfUpdate {
+            check,
+            channel,
+            prerelease,
+        } => {
+            let channel = if prerelease {
+                ReleaseChannel::Dev
+            } else {
+                channel
+            };
+            commands::upgrade::handle_self_update(check, channel).await;
+        }
+        Commands::Init {
+            track,
+            module,
+            version,
+            name,
+            region,
+            file,
+            force,
+        } => {
+            commands::init::handle_init(&track, module, version, name, region, file, force).await;
+        }
+        Commands::Completions { shell } => {
+            use clap::CommandFactory;
+
+            clap_complete::generate(
+                shell,
+                &mut Cli::command(),
+                "infraweave",
+                &mut std::io::stdout(),
+            );
         }
     }
 }
 
-async fn run_tui() -> anyhow::Result<()> {
+async fn run_tui(offline: bool) -> anyhow::Result<()> {
     use crossterm::{
         execute,
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -1092,10 +2254,13 @@ async fn run_tui() -> anyhow::Result<()> {
 
     // Create app and background task channel
     let mut app = cli::tui::App::new();
+    app.load_snapshot(offline);
     let (bg_sender, mut bg_receiver) = cli::tui::background::create_channel();
     app.set_background_sender(bg_sender);
 
-    app.preload_projects();
+    if !offline {
+        app.preload_projects();
+    }
 
     // Main loop
     loop {
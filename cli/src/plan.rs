@@ -1,21 +1,29 @@
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 use anyhow::Result;
 use colored::Colorize;
 use env_common::{
     interface::{get_region_env_var, GenericCloudHandler},
-    logic::{is_deployment_in_progress, is_deployment_plan_in_progress, PROJECT_ID},
+    logic::{
+        is_deployment_in_progress, is_deployment_plan_in_progress, is_queued_job_id,
+        queued_job_state, QueuedJobState, PROJECT_ID,
+    },
 };
 use env_defs::{
-    pretty_print_resource_changes, CloudProvider, DeploymentResp, DeploymentStatus,
-    InfraChangeRecord,
+    pretty_print_resource_changes, CloudProvider, DeploymentResp, DeploymentStatus, EventData,
+    InfraChangeRecord, JobQueueEntry, ResourceAction, SanitizedResourceChange,
 };
 use http_client::{
-    http_check_deployment_progress as http_check_progress, http_get_change_record,
-    http_is_deployment_plan_in_progress as http_is_plan_in_progress, is_http_mode_enabled,
+    http_check_deployment_progress as http_check_progress, http_get_change_record, http_get_events,
+    http_get_job_queue, http_is_deployment_plan_in_progress as http_is_plan_in_progress,
+    is_http_mode_enabled,
 };
 use log::{debug, error};
 use prettytable::{row, Table};
+use serde::Serialize;
 
 use crate::ClaimJobStruct;
 
@@ -36,6 +44,54 @@ pub struct SummaryTables {
     pub overview: String,
     pub std_output: String,
     pub violations: String,
+    pub changes: Vec<JobChanges>,
+    /// Final status of each finished job, by job id
+    pub statuses: HashMap<String, DeploymentStatus>,
+}
+
+/// Resource changes of a finished job, counted the way Terraform summarizes a plan
+#[derive(Debug, Clone, Serialize)]
+pub struct JobChanges {
+    pub deployment_id: String,
+    pub environment: String,
+    pub job_id: String,
+    pub add: usize,
+    pub change: usize,
+    pub destroy: usize,
+    /// Addresses of the resources that are deleted or replaced
+    pub destructive_changes: Vec<String>,
+}
+
+impl JobChanges {
+    fn new(
+        cj: &ClaimJobStruct,
+        job_id: &str,
+        resource_changes: &[SanitizedResourceChange],
+    ) -> Self {
+        let count = |actions: &[ResourceAction]| {
+            resource_changes
+                .iter()
+                .filter(|c| actions.contains(&c.action))
+                .count()
+        };
+        JobChanges {
+            deployment_id: cj.deployment_id.clone(),
+            environment: cj.environment.clone(),
+            job_id: job_id.to_string(),
+            add: count(&[ResourceAction::Create, ResourceAction::Replace]),
+            change: count(&[ResourceAction::Update]),
+            destroy: count(&[ResourceAction::Delete, ResourceAction::Replace]),
+            destructive_changes: resource_changes
+                .iter()
+                .filter(|c| matches!(c.action, ResourceAction::Delete | ResourceAction::Replace))
+                .map(|c| c.address.clone())
+                .collect(),
+        }
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.add + self.change + self.destroy > 0
+    }
 }
 
 async fn fetch_progress(
@@ -75,6 +131,28 @@ async fn fetch_progress(
     Ok((in_progress, deployment))
 }
 
+/// Where a job that was queued because its project runs as many jobs as it allows is now
+async fn fetch_queued_job_state(http_mode: bool, cj: &ClaimJobStruct) -> Result<QueuedJobState> {
+    if http_mode {
+        let project_id = require_project_id()?;
+        let queue: Vec<JobQueueEntry> =
+            serde_json::from_value(http_get_job_queue(project_id, &cj.region).await?)?;
+        let events: Vec<EventData> =
+            http_get_events(project_id, &cj.region, &cj.environment, &cj.deployment_id)
+                .await?
+                .into_iter()
+                .filter_map(|event| serde_json::from_value(event).ok())
+                .collect();
+        return Ok(queued_job_state(&queue, &events, &cj.job_id));
+    }
+    let handler = GenericCloudHandler::region(&cj.region).await;
+    let queue = handler.get_job_queue().await?;
+    let events = handler
+        .get_events(&cj.deployment_id, &cj.environment)
+        .await?;
+    Ok(queued_job_state(&queue, &events, &cj.job_id))
+}
+
 async fn fetch_change_record(
     http_mode: bool,
     region: &str,
@@ -131,6 +209,18 @@ fn report_job(
             DeploymentStatus::Failed
         });
 
+    if observed == DeploymentStatus::PendingApproval {
+        if last_status.get(job_id) != Some(&observed) {
+            println!(
+                "Job {} is {}",
+                short.yellow(),
+                "pending approval".yellow().bold()
+            );
+            last_status.insert(job_id.to_string(), observed);
+        }
+        return false;
+    }
+
     if !observed.is_final() {
         if !last_status.contains_key(job_id) {
             if !quiet {
@@ -191,16 +281,76 @@ async fn poll_until_done(
     let mut statuses: HashMap<String, DeploymentResp> = HashMap::new();
     let mut last_status: HashMap<String, DeploymentStatus> = HashMap::new();
     let mut failure_errors: Vec<String> = Vec::new();
+    // Queued jobs are followed under the job id they were started as once they left the queue
+    let mut started: HashMap<String, String> = HashMap::new();
+    let mut queue_positions: HashMap<String, usize> = HashMap::new();
+    // A claimed job is briefly neither queued nor started, so it is only gone when seen twice
+    let mut seen_gone: HashSet<String> = HashSet::new();
 
     loop {
         let mut all_finished = true;
         let mut any_failed = false;
 
         for cj in job_ids {
-            let (in_progress, deployment) = fetch_progress(operation, http_mode, cj).await?;
+            let job_id = match started.get(&cj.job_id) {
+                Some(job_id) => job_id.clone(),
+                None if is_queued_job_id(&cj.job_id) => {
+                    match fetch_queued_job_state(http_mode, cj).await? {
+                        QueuedJobState::Waiting { position, length } => {
+                            if !quiet && queue_positions.get(&cj.job_id) != Some(&position) {
+                                println!(
+                                    "Job {} is {} (position {} of {})",
+                                    cj.job_id.yellow(),
+                                    "queued".yellow().bold(),
+                                    position,
+                                    length
+                                );
+                            }
+                            queue_positions.insert(cj.job_id.clone(), position);
+                            seen_gone.remove(&cj.job_id);
+                            all_finished = false;
+                            continue;
+                        }
+                        QueuedJobState::Started(job_id) => {
+                            if !quiet {
+                                println!(
+                                    "Job {} left the queue and started as job {}",
+                                    cj.job_id.yellow(),
+                                    short_id(&job_id).cyan()
+                                );
+                            }
+                            started.insert(cj.job_id.clone(), job_id.clone());
+                            job_id
+                        }
+                        QueuedJobState::Gone if seen_gone.insert(cj.job_id.clone()) => {
+                            all_finished = false;
+                            continue;
+                        }
+                        QueuedJobState::Gone => {
+                            let error_text =
+                                format!("Job {} left the queue without being started", cj.job_id);
+                            if !failure_errors.contains(&error_text) {
+                                println!("{}", error_text.red());
+                                failure_errors.push(error_text);
+                            }
+                            any_failed = true;
+                            continue;
+                        }
+                    }
+                }
+                None => cj.job_id.clone(),
+            };
+            let job = ClaimJobStruct {
+                job_id,
+                deployment_id: cj.deployment_id.clone(),
+                environment: cj.environment.clone(),
+                region: cj.region.clone(),
+            };
+
+            let (in_progress, deployment) = fetch_progress(operation, http_mode, &job).await?;
             if report_job(
                 in_progress,
-                &cj.job_id,
+                &job.job_id,
                 deployment.as_ref(),
                 &mut last_status,
                 &mut failure_errors,
@@ -230,7 +380,18 @@ async fn poll_until_done(
                 }
                 return Err(anyhow::anyhow!("One or more jobs failed"));
             }
-            if !quiet {
+            let pending_approval = statuses
+                .values()
+                .filter(|dep| dep.status == DeploymentStatus::PendingApproval)
+                .count();
+            if pending_approval > 0 {
+                println!(
+                    "\n{}",
+                    format!("{} {} job(s) pending approval", pending_approval, operation)
+                        .yellow()
+                        .bold()
+                );
+            } else if !quiet {
                 println!(
                     "\n{}",
                     format!("All {} jobs completed successfully!", operation)
@@ -275,12 +436,18 @@ async fn render_summary(
     ]);
     let mut violations_has_rows = false;
 
+    let mut changes = vec![];
     for cj in job_ids {
         let Some(deployment) = statuses.get(&cj.job_id) else {
             continue;
         };
-        let (deployment_id, environment, job_id, region) =
-            (&cj.deployment_id, &cj.environment, &cj.job_id, &cj.region);
+        // The job id a queued job was started as
+        let (deployment_id, environment, job_id, region) = (
+            &cj.deployment_id,
+            &cj.environment,
+            &deployment.job_id,
+            &cj.region,
+        );
 
         println!("\n{}", "=".repeat(80));
         println!(
@@ -334,6 +501,7 @@ async fn render_summary(
                         "Changes: \n{}",
                         pretty_print_resource_changes(&change_record.resource_changes)
                     );
+                    changes.push(JobChanges::new(cj, job_id, &change_record.resource_changes));
                 }
                 Err(e) => error!("Failed to get change record: {}", e),
             }
@@ -378,6 +546,11 @@ async fn render_summary(
         overview: render(overview, overview_has_rows),
         std_output: render(std_output, std_output_has_rows),
         violations: render(violations, violations_has_rows),
+        changes,
+        statuses: statuses
+            .iter()
+            .map(|(job_id, deployment)| (job_id.clone(), deployment.status.clone()))
+            .collect(),
     }
 }
 
@@ -390,7 +563,52 @@ pub async fn follow_execution(
     Ok(render_summary(job_ids, operation, http_mode, &statuses).await)
 }
 
+/// Follow jobs to completion without printing their progress or summary, and return their
+/// resource changes. Used when the changes are printed in a machine-readable format
+pub async fn follow_job_changes(
+    job_ids: &[ClaimJobStruct],
+    operation: &str,
+) -> Result<Vec<JobChanges>> {
+    let http_mode = is_http_mode_enabled();
+    let statuses = poll_until_done(job_ids, operation, http_mode, true).await?;
+
+    let mut changes = vec![];
+    for cj in job_ids {
+        let Some(deployment) = statuses.get(&cj.job_id) else {
+            continue;
+        };
+        let change_record = fetch_change_record(
+            http_mode,
+            &cj.region,
+            &cj.environment,
+            &cj.deployment_id,
+            &deployment.job_id,
+            &operation.to_uppercase(),
+        )
+        .await?;
+        changes.push(JobChanges::new(
+            cj,
+            &deployment.job_id,
+            &change_record.resource_changes,
+        ));
+    }
+    Ok(changes)
+}
+
+/// Follow a single job to completion and return the final status of its deployment, without
+/// rendering the summary tables. Used to run jobs one after another
+pub async fn follow_job_status(
+    job: &ClaimJobStruct,
+    operation: &str,
+) -> Result<Option<DeploymentStatus>> {
+    let http_mode = is_http_mode_enabled();
+    let statuses = poll_until_done(std::slice::from_ref(job), operation, http_mode, false).await?;
+    Ok(statuses.get(&job.job_id).map(|d| d.status.clone()))
+}
+
 pub struct DriftOutcome {
+    /// Job the drift check ran as, which differs from the submitted job id if it was queued
+    pub job_id: String,
     pub deployment_status: DeploymentStatus,
     pub resource_changes: Vec<env_defs::SanitizedResourceChange>,
 }
@@ -413,12 +631,13 @@ pub async fn follow_driftcheck(job: &ClaimJobStruct, remediate: bool) -> Result<
         &job.region,
         &job.environment,
         &job.deployment_id,
-        &job.job_id,
+        &deployment.job_id,
         &operation.to_uppercase(),
     )
     .await?;
 
     Ok(DriftOutcome {
+        job_id: deployment.job_id.clone(),
         deployment_status: deployment.status.clone(),
         resource_changes: change_record.resource_changes,
     })
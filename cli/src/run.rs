@@ -1,11 +1,71 @@
 use anyhow::Result;
+use colored::Colorize;
 use env_common::{interface::GenericCloudHandler, logic::run_claim};
-use env_defs::{DeploymentManifest, ExtraData};
+use env_defs::{CloudProvider, DeploymentManifest, DeploymentStatus, ExtraData};
+use env_utils::to_snake_case;
+use prettytable::{row, Table};
 use serde::Deserialize;
-use std::vec;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
 
-use crate::{follow_execution, ClaimJobStruct};
+use crate::{follow_execution, follow_job_changes, ClaimJobStruct, JobChanges};
 
+/// Claim read from a claim file of `apply` or `plan`
+struct ClaimDocument {
+    file: PathBuf,
+    /// Deployment id of the claim, e.g. s3bucket/bucket
+    deployment_id: String,
+    yaml: serde_yaml::Value,
+}
+
+/// Outcome of a claim, one row of the summary printed after running the claims
+struct ClaimResult {
+    file: String,
+    deployment_id: String,
+    region: String,
+    job_id: String,
+    status: String,
+}
+
+/// `{{ Kind::name::output }}` reference to an output of another deployment, the syntax stacks use
+/// to refer to the outputs of their claims
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ClaimReference {
+    kind: String,
+    name: String,
+    pub(crate) field: String,
+}
+
+impl ClaimReference {
+    pub(crate) fn deployment_id(&self) -> String {
+        format!("{}/{}", self.kind.to_lowercase(), self.name)
+    }
+}
+
+impl std::fmt::Display for ClaimReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{{{ {}::{}::{} }}}}", self.kind, self.name, self.field)
+    }
+}
+
+/// Outputs of the jobs followed so far, merged over every batch of jobs
+#[derive(Default)]
+struct FollowedJobs {
+    overview: Vec<String>,
+    std_output: Vec<String>,
+    violations: Vec<String>,
+    changes: Vec<JobChanges>,
+    statuses: HashMap<String, DeploymentStatus>,
+}
+
+/// Runs every claim in the claim file, or in the YAML files below the claim directory, and when
+/// following returns the resource changes of the jobs. Claims referring to the outputs of other
+/// deployments with `{{ Kind::name::output }}` get the outputs filled in, and run after the claims
+/// they refer to. With `json` the progress and summary are not printed, so the caller can print
+/// the changes instead
+#[allow(clippy::too_many_arguments)]
 pub async fn run_claim_file(
     environment: &str,
     claim: &str,
@@ -13,17 +73,26 @@ pub async fn run_claim_file(
     store_files: bool,
     destroy: bool,
     follow: bool,
-) -> Result<(), anyhow::Error> {
-    // Read claim yaml file:
-    let file_content = std::fs::read_to_string(claim).expect("Failed to read claim file");
-
-    // Parse multiple YAML documents
-    let claims: Vec<serde_yaml::Value> = serde_yaml::Deserializer::from_str(&file_content)
-        .map(|doc| serde_yaml::Value::deserialize(doc).unwrap_or("".into()))
-        .collect();
+    var_file: Option<&str>,
+    vars: &[String],
+    json: bool,
+) -> Result<Vec<JobChanges>, anyhow::Error> {
+    let mut files = vec![];
+    collect_claim_files(Path::new(claim), &mut files)?;
+    if files.is_empty() {
+        return Err(anyhow::anyhow!("No claim files found in {}", claim));
+    }
+    let mut documents = vec![];
+    for file in files {
+        documents.extend(read_claim_documents(&file)?);
+    }
+    let documents = order_by_references(documents)?;
 
-    // job_id, deployment_id, environment
-    let mut job_ids: Vec<ClaimJobStruct> = Vec::new();
+    let mut results: Vec<ClaimResult> = Vec::new();
+    let mut pending: Vec<ClaimJobStruct> = Vec::new();
+    let mut followed = FollowedJobs::default();
+    // Deployment ids of the claims that failed, so the claims referring to them are skipped
+    let mut failed: HashSet<String> = HashSet::new();
     let mut errors: Vec<String> = Vec::new();
 
     let reference_fallback: String = match hostname::get() {
@@ -33,55 +102,134 @@ pub async fn run_claim_file(
         }
     };
 
-    log::info!("Applying {} claims in file", claims.len());
-    for yaml in claims.iter() {
-        let flags = if destroy {
-            vec!["-destroy".to_string()]
-        } else {
-            vec![]
-        };
-        let deployment_manifest: DeploymentManifest = serde_yaml::from_value(yaml.clone())?;
-        let region = &deployment_manifest.spec.region;
-        let (job_id, deployment_id) = match run_claim(
-            &GenericCloudHandler::region(region).await,
-            yaml,
-            environment,
-            command,
-            flags,
-            ExtraData::None,
-            &reference_fallback,
-        )
-        .await
+    let flags = if destroy {
+        vec!["-destroy".to_string()]
+    } else {
+        vec![]
+    };
+
+    log::info!("Running {} claims", documents.len());
+    for document in documents.iter() {
+        let file = document.file.display().to_string();
+        let dependencies: HashSet<String> = claim_references(&document.yaml)
+            .iter()
+            .map(ClaimReference::deployment_id)
+            .filter(|id| documents.iter().any(|d| &d.deployment_id == id))
+            .collect();
+
+        // The outputs of a claim submitted in this run exist once its job has finished
+        if follow
+            && pending
+                .iter()
+                .any(|j| dependencies.contains(&j.deployment_id))
         {
-            Ok((job_id, deployment_id, _)) => (job_id, deployment_id),
+            follow_jobs(&mut pending, command, json, &mut followed).await?;
+            for result in results.iter_mut() {
+                if let Some(status) = followed.statuses.get(&result.job_id) {
+                    if *status != DeploymentStatus::Successful {
+                        failed.insert(result.deployment_id.clone());
+                    }
+                }
+            }
+        }
+        if let Some(dependency) = dependencies.iter().find(|id| failed.contains(*id)) {
+            failed.insert(document.deployment_id.clone());
+            results.push(ClaimResult {
+                file,
+                deployment_id: document.deployment_id.clone(),
+                region: String::new(),
+                job_id: String::new(),
+                status: format!("skipped, {} failed", dependency),
+            });
+            continue;
+        }
+
+        let mut yaml = document.yaml.clone();
+        if let Err(e) = resolve_variables(&mut yaml, &document.file, var_file, vars) {
+            let error_msg = format!("Failed to set variables of claim {}: {}", file, e);
+            eprintln!("{}", error_msg);
+            errors.push(error_msg);
+            failed.insert(document.deployment_id.clone());
+            continue;
+        }
+        // A claim listing several regions is deployed once per region
+        let region_claims = match env_utils::expand_claim_regions(&yaml) {
+            Ok(region_claims) => region_claims,
             Err(e) => {
-                let error_msg = format!("Failed to run a manifest in claim {}: {}", claim, e);
+                let error_msg = format!("Invalid region list in claim {}: {}", file, e);
                 eprintln!("{}", error_msg);
                 errors.push(error_msg);
+                failed.insert(document.deployment_id.clone());
                 continue;
             }
         };
-        job_ids.push(ClaimJobStruct {
-            job_id,
-            deployment_id,
-            environment: environment.to_string(),
-            region: region.to_string(),
-        });
-    }
-
-    for claim_job in &job_ids {
-        println!(
-            "Started {} job: {} in {} (job id: {})",
-            command, claim_job.deployment_id, claim_job.environment, claim_job.job_id
-        );
+        for mut yaml in region_claims {
+            let deployment_manifest: DeploymentManifest = serde_yaml::from_value(yaml.clone())?;
+            let region = deployment_manifest.spec.region.clone();
+            let handler = GenericCloudHandler::region(&region).await;
+            let mut result = ClaimResult {
+                file: file.clone(),
+                deployment_id: document.deployment_id.clone(),
+                region: region.clone(),
+                job_id: String::new(),
+                status: String::new(),
+            };
+            if let Err(e) = resolve_claim_references(&handler, &mut yaml, environment).await {
+                let error_msg = format!("Failed to resolve references in claim {}: {}", file, e);
+                eprintln!("{}", error_msg);
+                errors.push(error_msg);
+                failed.insert(document.deployment_id.clone());
+                result.status = "unresolved reference".to_string();
+                results.push(result);
+                continue;
+            }
+            match run_claim(
+                &handler,
+                &yaml,
+                environment,
+                command,
+                flags.clone(),
+                ExtraData::None,
+                &reference_fallback,
+            )
+            .await
+            {
+                Ok((job_id, deployment_id, _)) => {
+                    if !json {
+                        println!(
+                            "Started {} job: {} in {} (job id: {})",
+                            command, deployment_id, environment, job_id
+                        );
+                    }
+                    result.job_id = job_id.clone();
+                    result.status = "submitted".to_string();
+                    pending.push(ClaimJobStruct {
+                        job_id,
+                        deployment_id,
+                        environment: environment.to_string(),
+                        region,
+                    });
+                }
+                Err(e) => {
+                    let error_msg = format!("Failed to run a manifest in claim {}: {}", file, e);
+                    eprintln!("{}", error_msg);
+                    errors.push(error_msg);
+                    failed.insert(document.deployment_id.clone());
+                    result.status = "failed to submit".to_string();
+                }
+            };
+            results.push(result);
+        }
     }
 
-    if job_ids.is_empty() {
+    if results.iter().all(|r| r.job_id.is_empty()) {
         if !errors.is_empty() {
             return Err(anyhow::anyhow!("All claims failed:\n{}", errors.join("\n")));
         }
-        println!("No jobs to run");
-        return Ok(());
+        if !json {
+            println!("No jobs to run");
+        }
+        return Ok(vec![]);
     }
 
     // Warn if user wants to store files but opted out of following
@@ -92,34 +240,457 @@ pub async fn run_claim_file(
     }
 
     if follow {
-        let tables = match follow_execution(&job_ids, command).await {
-            Ok(tables) => tables,
-            Err(e) => {
-                println!("Failed to follow {}: {}", command, e);
-                return Err(e);
+        follow_jobs(&mut pending, command, json, &mut followed).await?;
+    }
+
+    if !json {
+        for result in results.iter_mut() {
+            if let Some(status) = followed.statuses.get(&result.job_id) {
+                result.status = status.to_string();
             }
-        };
+        }
+        println!("\n{}", render_claim_results(&results));
+    }
 
-        if store_files {
-            if !tables.overview.is_empty() {
-                std::fs::write("overview.txt", tables.overview)
-                    .expect("Failed to write overview file");
-                println!("Overview written to overview.txt");
+    if follow && store_files {
+        let write = |name: &str, tables: &[String]| {
+            if !tables.is_empty() {
+                std::fs::write(name, tables.join("\n"))
+                    .unwrap_or_else(|e| panic!("Failed to write {}: {}", name, e));
+                println!("{} written to {}", name.trim_end_matches(".txt"), name);
             }
+        };
+        write("overview.txt", &followed.overview);
+        write("std_output.txt", &followed.std_output);
+        if command == "plan" {
+            write("violations.txt", &followed.violations);
+        }
+    }
+
+    Ok(followed.changes)
+}
+
+/// Reads a claim file holding a single claim for a single region, with its variable file merged
+/// and its references resolved, along with the handler of the region of the claim
+pub(crate) async fn read_single_claim(
+    environment: &str,
+    claim: &str,
+) -> Result<(GenericCloudHandler, serde_yaml::Value)> {
+    let documents = read_claim_documents(Path::new(claim))?;
+    let [document] = documents.as_slice() else {
+        return Err(anyhow::anyhow!(
+            "{} must hold exactly one claim, found {}",
+            claim,
+            documents.len()
+        ));
+    };
+    let mut yaml = document.yaml.clone();
+    resolve_variables(&mut yaml, &document.file, None, &[])?;
+    let region_claims = env_utils::expand_claim_regions(&yaml)?;
+    let [yaml] = region_claims.as_slice() else {
+        return Err(anyhow::anyhow!(
+            "The claim in {} must be for a single region",
+            claim
+        ));
+    };
+    let mut yaml = yaml.clone();
+    let deployment_manifest: DeploymentManifest = serde_yaml::from_value(yaml.clone())?;
+    let handler = GenericCloudHandler::region(&deployment_manifest.spec.region).await;
+    resolve_claim_references(&handler, &mut yaml, environment).await?;
+    Ok((handler, yaml))
+}
+
+/// Follows the submitted jobs to completion and adds their outputs to `followed`
+async fn follow_jobs(
+    pending: &mut Vec<ClaimJobStruct>,
+    command: &str,
+    json: bool,
+    followed: &mut FollowedJobs,
+) -> Result<(), anyhow::Error> {
+    if pending.is_empty() {
+        return Ok(());
+    }
+    let jobs = std::mem::take(pending);
+    if json {
+        followed
+            .changes
+            .extend(follow_job_changes(&jobs, command).await?);
+        return Ok(());
+    }
+    let tables = match follow_execution(&jobs, command).await {
+        Ok(tables) => tables,
+        Err(e) => {
+            println!("Failed to follow {}: {}", command, e);
+            return Err(e);
+        }
+    };
+    for (tables_of_kind, table) in [
+        (&mut followed.overview, tables.overview),
+        (&mut followed.std_output, tables.std_output),
+        (&mut followed.violations, tables.violations),
+    ] {
+        if !table.is_empty() {
+            tables_of_kind.push(table);
+        }
+    }
+    followed.changes.extend(tables.changes);
+    followed.statuses.extend(tables.statuses);
+    Ok(())
+}
+
+fn render_claim_results(results: &[ClaimResult]) -> String {
+    let mut table = Table::new();
+    table.add_row(row![
+        "Claim file".purple().bold(),
+        "Deployment id".purple().bold(),
+        "Region".blue().bold(),
+        "Job id".green().bold(),
+        "Status".red().bold(),
+    ]);
+    for result in results {
+        let status = match result.status.as_str() {
+            "successful" | "submitted" => result.status.green(),
+            _ => result.status.red(),
+        };
+        table.add_row(row![
+            result.file,
+            result.deployment_id,
+            result.region,
+            result.job_id,
+            status
+        ]);
+    }
+    table.to_string()
+}
+
+/// Adds `path` if it is a file, or the YAML files below it if it is a directory
+pub(crate) fn collect_claim_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if !path.is_dir() {
+        if !path.exists() {
+            return Err(anyhow::anyhow!("{} does not exist", path.display()));
+        }
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for entry in entries {
+        let is_yaml = entry
+            .extension()
+            .is_some_and(|ext| ext == "yaml" || ext == "yml");
+        if entry.is_dir() || is_yaml {
+            collect_claim_files(&entry, files)?;
+        }
+    }
+    Ok(())
+}
 
-            if !tables.std_output.is_empty() {
-                std::fs::write("std_output.txt", tables.std_output)
-                    .expect("Failed to write std output file");
-                println!("Std output written to std_output.txt");
+/// Claims of the `---` separated YAML documents of a file, skipping empty documents
+fn read_claim_documents(file: &Path) -> Result<Vec<ClaimDocument>> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file.display(), e))?;
+    let mut documents = vec![];
+    for document in serde_yaml::Deserializer::from_str(&content) {
+        let yaml = serde_yaml::Value::deserialize(document)
+            .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", file.display(), e))?;
+        if yaml.is_null() {
+            continue;
+        }
+        let kind = yaml["kind"].as_str().unwrap_or_default();
+        let name = yaml["metadata"]["name"].as_str().unwrap_or_default();
+        documents.push(ClaimDocument {
+            file: file.to_path_buf(),
+            deployment_id: format!("{}/{}", kind.to_lowercase(), name),
+            yaml,
+        });
+    }
+    Ok(documents)
+}
+
+/// Orders the claims so that claims come after the claims they refer to, keeping the order of
+/// the files otherwise
+fn order_by_references(documents: Vec<ClaimDocument>) -> Result<Vec<ClaimDocument>> {
+    let dependencies: Vec<HashSet<String>> = documents
+        .iter()
+        .map(|document| {
+            claim_references(&document.yaml)
+                .iter()
+                .map(ClaimReference::deployment_id)
+                .filter(|id| *id != document.deployment_id)
+                .filter(|id| documents.iter().any(|d| &d.deployment_id == id))
+                .collect()
+        })
+        .collect();
+
+    let mut remaining: Vec<(ClaimDocument, HashSet<String>)> =
+        documents.into_iter().zip(dependencies).collect();
+    let mut ordered: Vec<ClaimDocument> = vec![];
+    while !remaining.is_empty() {
+        let Some(next) = remaining.iter().position(|(_, dependencies)| {
+            dependencies
+                .iter()
+                .all(|id| !remaining.iter().any(|(d, _)| &d.deployment_id == id))
+        }) else {
+            let cycle: Vec<&str> = remaining
+                .iter()
+                .map(|(d, _)| d.deployment_id.as_str())
+                .collect();
+            return Err(anyhow::anyhow!(
+                "Claims refer to each other in a cycle: {}",
+                cycle.join(", ")
+            ));
+        };
+        ordered.push(remaining.remove(next).0);
+    }
+    Ok(ordered)
+}
+
+/// References to outputs of other deployments in the variables of the claim
+fn claim_references(claim: &serde_yaml::Value) -> Vec<ClaimReference> {
+    let mut references = vec![];
+    visit_strings(&claim["spec"]["variables"], &mut |s| {
+        for (_, reference) in parse_references(s) {
+            if !references.contains(&reference) {
+                references.push(reference);
             }
+        }
+    });
+    references
+}
+
+fn visit_strings(value: &serde_yaml::Value, visit: &mut impl FnMut(&str)) {
+    match value {
+        serde_yaml::Value::String(s) => visit(s),
+        serde_yaml::Value::Sequence(items) => items.iter().for_each(|v| visit_strings(v, visit)),
+        serde_yaml::Value::Mapping(map) => map.values().for_each(|v| visit_strings(v, visit)),
+        serde_yaml::Value::Tagged(tagged) => visit_strings(&tagged.value, visit),
+        _ => {}
+    }
+}
+
+/// `{{ Kind::name::field }}` references in `s`, with the byte range each one covers
+pub(crate) fn parse_references(s: &str) -> Vec<(std::ops::Range<usize>, ClaimReference)> {
+    let mut references = vec![];
+    let mut offset = 0;
+    while let Some(start) = s[offset..].find("{{").map(|i| offset + i) {
+        let Some(end) = s[start..].find("}}").map(|i| start + i + 2) else {
+            break;
+        };
+        let parts: Vec<&str> = s[start + 2..end - 2].trim().split("::").collect();
+        if let [kind, name, field] = parts[..] {
+            references.push((
+                start..end,
+                ClaimReference {
+                    kind: kind.to_string(),
+                    name: name.to_string(),
+                    field: field.to_string(),
+                },
+            ));
+        }
+        offset = end;
+    }
+    references
+}
+
+/// Replaces the references in the variables of the claim with the outputs of the deployments
+/// they refer to, which have to exist in the environment
+async fn resolve_claim_references(
+    handler: &GenericCloudHandler,
+    claim: &mut serde_yaml::Value,
+    environment: &str,
+) -> Result<(), anyhow::Error> {
+    let mut outputs: HashMap<ClaimReference, serde_json::Value> = HashMap::new();
+    for reference in claim_references(claim) {
+        let deployment_id = reference.deployment_id();
+        let deployment = handler
+            .get_deployment(&deployment_id, environment, false)
+            .await?
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "{} refers to {}, which is not deployed in {}",
+                    reference,
+                    deployment_id,
+                    environment
+                )
+            })?;
+        let output = deployment.output[to_snake_case(&reference.field)]["value"].clone();
+        if output.is_null() {
+            return Err(anyhow::anyhow!(
+                "{} refers to an output {} has no value for",
+                reference,
+                deployment_id
+            ));
+        }
+        outputs.insert(reference, output);
+    }
+    if !outputs.is_empty() {
+        substitute_references(&mut claim["spec"]["variables"], &outputs)?;
+    }
+    Ok(())
+}
 
-            if command == "plan" && !tables.violations.is_empty() {
-                std::fs::write("violations.txt", tables.violations)
-                    .expect("Failed to write violations file");
-                println!("Violations written to violations.txt");
+/// Replaces the references in `value` with their outputs. A string that is only a reference
+/// takes the value of the output as is, references inside a longer string are formatted into it
+fn substitute_references(
+    value: &mut serde_yaml::Value,
+    outputs: &HashMap<ClaimReference, serde_json::Value>,
+) -> Result<(), anyhow::Error> {
+    match value {
+        serde_yaml::Value::String(s) => {
+            let references = parse_references(s);
+            if let [(range, reference)] = &references[..] {
+                if *range == (0..s.len()) {
+                    if let Some(output) = outputs.get(reference) {
+                        *value = serde_yaml::to_value(output)?;
+                    }
+                    return Ok(());
+                }
+            }
+            for (range, reference) in references.into_iter().rev() {
+                let formatted = match outputs.get(&reference) {
+                    Some(serde_json::Value::String(output)) => output.clone(),
+                    Some(output) => output.to_string(),
+                    None => continue,
+                };
+                s.replace_range(range, &formatted);
             }
         }
+        serde_yaml::Value::Sequence(items) => {
+            for item in items {
+                substitute_references(item, outputs)?;
+            }
+        }
+        serde_yaml::Value::Mapping(map) => {
+            for item in map.values_mut() {
+                substitute_references(item, outputs)?;
+            }
+        }
+        serde_yaml::Value::Tagged(tagged) => substitute_references(&mut tagged.value, outputs)?,
+        _ => {}
     }
+    Ok(())
+}
 
+/// Merges the variable files and `--var` overrides into the claim variables. Overrides win over
+/// variables set in the claim, which win over `--var-file`, which in turn wins over
+/// `spec.varFile` (resolved relative to the claim file)
+fn resolve_variables(
+    yaml: &mut serde_yaml::Value,
+    claim: &Path,
+    var_file: Option<&str>,
+    vars: &[String],
+) -> Result<(), anyhow::Error> {
+    env_utils::set_claim_variable_overrides(yaml, vars)?;
+    if let Some(var_file) = var_file {
+        let contents = std::fs::read_to_string(var_file)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", var_file, e))?;
+        env_utils::merge_var_file_into_claim(yaml, var_file, &contents)?;
+    }
+    if let Some(spec_var_file) = yaml["spec"]["varFile"].as_str() {
+        let path = claim.parent().unwrap_or(Path::new(".")).join(spec_var_file);
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        env_utils::merge_var_file_into_claim(yaml, spec_var_file, &contents)?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(file: &str, yaml: &str) -> ClaimDocument {
+        let yaml: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+        ClaimDocument {
+            file: PathBuf::from(file),
+            deployment_id: format!(
+                "{}/{}",
+                yaml["kind"].as_str().unwrap().to_lowercase(),
+                yaml["metadata"]["name"].as_str().unwrap()
+            ),
+            yaml,
+        }
+    }
+
+    #[test]
+    fn test_order_by_references() {
+        let documents = vec![
+            document(
+                "policy.yaml",
+                r#"
+kind: BucketPolicy
+metadata:
+  name: policy
+spec:
+  variables:
+    bucketArn: "{{ S3Bucket::bucket::bucketArn }}"
+"#,
+            ),
+            document(
+                "bucket.yaml",
+                r#"
+kind: S3Bucket
+metadata:
+  name: bucket
+spec:
+  variables:
+    bucketName: "bucket-{{ Vpc::network::vpcId }}"
+"#,
+            ),
+            document("queue.yaml", "kind: Queue\nmetadata:\n  name: queue\n"),
+        ];
+        let ordered = order_by_references(documents).unwrap();
+        let ids: Vec<&str> = ordered.iter().map(|d| d.deployment_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["s3bucket/bucket", "bucketpolicy/policy", "queue/queue"]
+        );
+
+        let cycle = vec![
+            document(
+                "a.yaml",
+                "kind: A\nmetadata:\n  name: a\nspec:\n  variables:\n    x: '{{ B::b::y }}'\n",
+            ),
+            document(
+                "b.yaml",
+                "kind: B\nmetadata:\n  name: b\nspec:\n  variables:\n    y: '{{ A::a::x }}'\n",
+            ),
+        ];
+        assert!(order_by_references(cycle).is_err());
+    }
+
+    #[test]
+    fn test_substitute_references() {
+        let mut variables: serde_yaml::Value = serde_yaml::from_str(
+            r#"
+bucketArn: "{{ S3Bucket::bucket::bucketArn }}"
+tags:
+  Name: "policy-for-{{S3Bucket::bucket::bucketName}}"
+ports: "{{ S3Bucket::bucket::ports }}"
+other: "{{ Queue::queue::url }}"
+"#,
+        )
+        .unwrap();
+        let reference = |field: &str| ClaimReference {
+            kind: "S3Bucket".to_string(),
+            name: "bucket".to_string(),
+            field: field.to_string(),
+        };
+        let outputs = HashMap::from([
+            (
+                reference("bucketArn"),
+                serde_json::json!("arn:aws:s3:::bucket"),
+            ),
+            (reference("bucketName"), serde_json::json!("bucket")),
+            (reference("ports"), serde_json::json!([80, 443])),
+        ]);
+        substitute_references(&mut variables, &outputs).unwrap();
+
+        assert_eq!(variables["bucketArn"], "arn:aws:s3:::bucket");
+        assert_eq!(variables["tags"]["Name"], "policy-for-bucket");
+        assert_eq!(variables["ports"][1], 443);
+        assert_eq!(variables["other"], "{{ Queue::queue::url }}");
+    }
+}
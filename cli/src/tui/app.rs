@@ -1,11 +1,13 @@
 use anyhow::Result;
 
+use super::snapshot::{events_key, Snapshot};
 use super::state::{
     claim_builder_state::ClaimBuilderState, detail_state::DetailState, events_state::EventsState,
     modal_state::ModalState, search_state::SearchState, view_state::ViewState,
 };
 use super::utils::NavItem;
 use crate::current_region_handler;
+use env_common::interface::GenericCloudHandler;
 use env_defs::{CloudProvider, CloudProviderCommon, ModuleResp};
 use http_client::is_http_mode_enabled;
 
@@ -39,10 +41,28 @@ pub enum PendingAction {
     DestroyDeployment(usize),
     ReloadCurrentDeploymentDetail,
     SaveClaimToFile,
+    PlanClaimFromBuilder,
     RunClaimFromBuilder,
+    ShowContextSwitcher,
+    LoadContextRegions(String), // project_id
 }
 
-#[derive(Debug, Clone)]
+impl PendingAction {
+    /// Whether the action needs the cloud, i.e. it can't be served from the snapshot
+    pub fn requires_connectivity(&self) -> bool {
+        !matches!(
+            self,
+            PendingAction::None
+                | PendingAction::LoadModules
+                | PendingAction::LoadStacks
+                | PendingAction::LoadDeployments
+                | PendingAction::ShowDeploymentEvents(_)
+                | PendingAction::SaveClaimToFile
+        )
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Module {
     pub module: String,
     pub module_name: String,
@@ -71,7 +91,7 @@ pub struct GroupedModule {
     pub dev_deprecated: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Deployment {
     pub status: String,
     pub deployment_id: String,
@@ -109,11 +129,21 @@ pub struct App {
     pub selected_project_filter: Option<String>,
     pub selected_region_filter: Option<String>,
     pub project_selection_made: bool,
+    /// Project picked in the switcher while its region is being selected
+    pub switching_project_id: Option<String>,
 
     // Cache
     pub projects_cache: Option<Vec<env_defs::ProjectData>>,
     pub pending_deployment_requests: usize,
 
+    // Snapshot of the last fetched data
+    pub snapshot: Snapshot,
+    pub offline: bool,
+    /// When the shown list was fetched, set while it comes from the snapshot
+    pub stale_since: Option<String>,
+    /// When the shown events were fetched, set while they come from the snapshot
+    pub events_stale_since: Option<String>,
+
     // ==================== BACKGROUND TASKS ====================
     pub background_sender:
         Option<tokio::sync::mpsc::UnboundedSender<crate::tui::background::BackgroundMessage>>,
@@ -222,9 +252,16 @@ impl App {
             selected_project_filter: None,
             selected_region_filter: None,
             project_selection_made: false,
+            switching_project_id: None,
             projects_cache: None,
             pending_deployment_requests: 0,
 
+            // Snapshot
+            snapshot: Snapshot::default(),
+            offline: false,
+            stale_since: None,
+            events_stale_since: None,
+
             // Background tasks
             background_sender: None,
 
@@ -299,6 +336,17 @@ impl App {
         }
     }
 
+    /// Load the on-disk snapshot so cached data is shown while fresh data loads.
+    /// In offline mode all lists are served from the snapshot only.
+    pub fn load_snapshot(&mut self, offline: bool) {
+        self.snapshot = Snapshot::load();
+        self.offline = offline;
+        if offline {
+            self.projects_cache = self.snapshot.projects.as_ref().map(|p| p.data.clone());
+            self.project_selection_made = true;
+        }
+    }
+
     pub fn set_background_sender(
         &mut self,
         sender: tokio::sync::mpsc::UnboundedSender<crate::tui::background::BackgroundMessage>,
@@ -315,9 +363,13 @@ impl App {
         match message {
             BackgroundMessage::ModulesLoaded(result) => {
                 match result {
-                    Ok(modules) => {
-                        self.modules = modules;
-                        self.view_state.modules = self.modules.clone();
+                    Ok((track, modules)) => {
+                        self.snapshot.store_modules(&track, &modules);
+                        if self.current_view == View::Modules && self.current_track == track {
+                            self.modules = modules;
+                            self.view_state.modules = self.modules.clone();
+                            self.stale_since = None;
+                        }
                     }
                     Err(e) => {
                         eprintln!("Failed to load modules: {}", e);
@@ -327,9 +379,13 @@ impl App {
             }
             BackgroundMessage::StacksLoaded(result) => {
                 match result {
-                    Ok(stacks) => {
-                        self.stacks = stacks;
-                        self.view_state.stacks = self.stacks.clone();
+                    Ok((track, stacks)) => {
+                        self.snapshot.store_stacks(&track, &stacks);
+                        if self.current_view == View::Stacks && self.current_track == track {
+                            self.stacks = stacks;
+                            self.view_state.stacks = self.stacks.clone();
+                            self.stale_since = None;
+                        }
                     }
                     Err(e) => {
                         eprintln!("Failed to load stacks: {}", e);
@@ -379,6 +435,11 @@ impl App {
             BackgroundMessage::DeploymentsBatchLoaded(result) => {
                 match result {
                     Ok(batch) => {
+                        // Replace the cached deployments once fresh data arrives
+                        if self.stale_since.is_some() && self.current_view == View::Deployments {
+                            self.deployments.clear();
+                            self.stale_since = None;
+                        }
                         self.process_deployment_batch(batch);
                     }
                     Err(e) => {
@@ -391,11 +452,15 @@ impl App {
                 }
 
                 if self.pending_deployment_requests == 0 {
+                    if self.stale_since.is_none() {
+                        self.snapshot.store_deployments(&self.deployments);
+                    }
                     self.clear_loading();
                 }
             }
             BackgroundMessage::ProjectsLoaded(result) => match result {
                 Ok(projects) => {
+                    self.snapshot.store_projects(&projects);
                     self.projects_cache = Some(projects);
                 }
                 Err(e) => {
@@ -501,6 +566,29 @@ impl App {
                 }
                 self.clear_loading();
             }
+            BackgroundMessage::ClaimPlanned(result) => {
+                match result {
+                    // The plan is dropped when the claim builder was closed while it ran
+                    Ok(plan) if self.claim_builder_state.showing_claim_builder => {
+                        let message = plan.confirmation_message();
+                        self.claim_builder_state.plan = Some(plan);
+
+                        self.modal_state.showing_confirmation = true;
+                        self.modal_state.confirmation_message = message.clone();
+                        self.modal_state.confirmation_action = PendingAction::RunClaimFromBuilder;
+
+                        self.showing_confirmation = true;
+                        self.confirmation_message = message;
+                        self.confirmation_action = PendingAction::RunClaimFromBuilder;
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        self.detail_state
+                            .show_error(&format!("❌ Failed to plan deployment claim:\n\n{}", e));
+                    }
+                }
+                self.clear_loading();
+            }
             // Handle other message types as needed
             _ => {
                 // For now, just clear loading for unhandled messages
@@ -643,6 +731,12 @@ impl App {
 
         self.pending_action = PendingAction::None;
 
+        if self.offline && action.requires_connectivity() {
+            let message = "Not available in offline mode, only cached modules, stacks, deployments and events can be browsed.".to_string();
+            self.show_message(message);
+            return Ok(());
+        }
+
         match action {
             PendingAction::None => {}
             PendingAction::LoadModules => {
@@ -723,9 +817,18 @@ impl App {
             PendingAction::SaveClaimToFile => {
                 self.save_claim_to_file().await?;
             }
+            PendingAction::PlanClaimFromBuilder => {
+                self.plan_claim_from_builder().await?;
+            }
             PendingAction::RunClaimFromBuilder => {
                 self.run_claim_from_builder().await?;
             }
+            PendingAction::ShowContextSwitcher => {
+                self.show_context_switcher().await?;
+            }
+            PendingAction::LoadContextRegions(project_id) => {
+                self.load_context_regions(&project_id).await?;
+            }
         }
 
         Ok(())
@@ -739,16 +842,22 @@ impl App {
         match &self.pending_action {
             PendingAction::None => {}
             PendingAction::LoadModules => {
-                self.modules.clear();
-                self.set_loading("Loading modules...");
+                if !self.show_cached_modules() {
+                    self.modules.clear();
+                    self.set_loading("Loading modules...");
+                }
             }
             PendingAction::LoadStacks => {
-                self.stacks.clear();
-                self.set_loading("Loading stacks...");
+                if !self.show_cached_stacks() {
+                    self.stacks.clear();
+                    self.set_loading("Loading stacks...");
+                }
             }
             PendingAction::LoadDeployments => {
-                self.deployments.clear();
-                self.set_loading("Loading deployments...");
+                if !self.show_cached_deployments() {
+                    self.deployments.clear();
+                    self.set_loading("Loading deployments...");
+                }
             }
             PendingAction::ShowModuleDetail(_) => {
                 self.set_loading("Loading module details...");
@@ -790,8 +899,17 @@ impl App {
             PendingAction::SaveClaimToFile => {
                 self.set_loading("Saving claim to file...");
             }
+            PendingAction::PlanClaimFromBuilder => {
+                self.set_loading("Planning claim...");
+            }
             PendingAction::RunClaimFromBuilder => {
-                self.set_loading("Running claim...");
+                self.set_loading("Applying plan...");
+            }
+            PendingAction::ShowContextSwitcher => {
+                self.set_loading("Loading projects...");
+            }
+            PendingAction::LoadContextRegions(_) => {
+                self.set_loading("Loading regions...");
             }
         }
     }
@@ -799,6 +917,8 @@ impl App {
     pub fn preload_projects(&self) {
         if let Some(sender) = &self.background_sender {
             let sender_clone = sender.clone();
+            let project_id = self.project_id.clone();
+            let region = self.region.clone();
             tokio::spawn(async move {
                 let result = if is_http_mode_enabled() {
                     http_client::http_get_all_projects()
@@ -810,7 +930,7 @@ impl App {
                                 .collect()
                         })
                 } else {
-                    current_region_handler()
+                    context_handler(&project_id, &region)
                         .await
                         .get_all_projects()
                         .await
@@ -830,84 +950,278 @@ impl App {
         }
     }
 
-    pub async fn load_modules(&mut self) -> Result<()> {
-        // Use empty string for "all" track to get modules from all tracks
-        let track_filter = if self.current_track == "all" {
-            ""
-        } else {
-            &self.current_track
-        };
+    /// Handler for the project and region selected in the switcher
+    pub async fn handler(&self) -> GenericCloudHandler {
+        context_handler(&self.project_id, &self.region).await
+    }
+
+    /// Show a message in place of the current view, used when an action can't complete
+    fn show_message(&mut self, message: String) {
+        self.detail_state.show_message(message.clone());
+        self.detail_content = message;
+        self.showing_detail = true;
+        self.clear_loading();
+    }
+
+    /// Open the project switcher with the projects from `get_all_projects`
+    pub async fn show_context_switcher(&mut self) -> Result<()> {
+        if self.projects_cache.is_none() {
+            match fetch_projects(self.handler().await).await {
+                Ok(projects) => {
+                    self.snapshot.store_projects(&projects);
+                    self.projects_cache = Some(projects);
+                }
+                Err(e) => {
+                    self.show_message(format!("Failed to load projects: {}", e));
+                    return Ok(());
+                }
+            }
+        }
+
+        let projects = self.projects_cache.as_deref().unwrap_or_default();
+        let mut options: Vec<String> = projects.iter().map(project_display).collect();
+        options.sort();
+        let current = projects
+            .iter()
+            .find(|p| p.project_id == self.project_id)
+            .map(project_display);
+        self.modal_state.show_filter_modal(
+            crate::tui::state::modal_state::FilterType::SwitchProject,
+            options,
+            current,
+        );
+        self.clear_loading();
+        Ok(())
+    }
+
+    /// Continue the switcher with the project shown as `display`, asking for its region next
+    pub fn select_context_project(&mut self, display: &str) {
+        let project_id = self
+            .projects_cache
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .find(|p| project_display(p) == display)
+            .map(|p| p.project_id.clone());
+        if let Some(project_id) = project_id {
+            self.schedule_action(PendingAction::LoadContextRegions(project_id));
+        }
+    }
 
-        let modules = if is_http_mode_enabled() {
-            http_client::http_get_all_latest_modules(track_filter).await?
+    /// Open the region step of the switcher with the regions from `get_all_regions`
+    pub async fn load_context_regions(&mut self, project_id: &str) -> Result<()> {
+        let regions = if is_http_mode_enabled() {
+            // The API has no region listing, use the regions the project is set up in
+            Ok(self
+                .projects_cache
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .find(|p| p.project_id == project_id)
+                .map(|p| p.regions.clone())
+                .unwrap_or_default())
         } else {
-            current_region_handler()
+            context_handler(project_id, &self.region)
+                .await
+                .get_all_regions()
                 .await
-                .get_all_latest_module(track_filter)
-                .await?
         };
+        let mut regions = match regions {
+            Ok(regions) if !regions.is_empty() => regions,
+            Ok(_) => {
+                self.show_message(format!("No regions found for project {}", project_id));
+                return Ok(());
+            }
+            Err(e) => {
+                self.show_message(format!("Failed to load regions: {}", e));
+                return Ok(());
+            }
+        };
+        regions.sort();
+
+        self.switching_project_id = Some(project_id.to_string());
+        self.modal_state.show_filter_modal(
+            crate::tui::state::modal_state::FilterType::SwitchRegion,
+            regions,
+            Some(self.region.clone()),
+        );
+        self.clear_loading();
+        Ok(())
+    }
 
-        let mut module_list: Vec<Module> = modules
-            .into_iter()
-            .map(|m| Module {
-                module: m.module,
-                module_name: m.module_name,
-                version: m.version,
-                track: m.track,
-                reference: m.reference,
-                timestamp: m.timestamp,
-                deprecated: m.deprecated,
-                deprecated_message: m.deprecated_message,
-            })
-            .collect();
+    /// Switch to `region` of the project picked in the switcher and reload the current view
+    pub fn select_context_region(&mut self, region: String) {
+        let Some(project_id) = self.switching_project_id.take() else {
+            return;
+        };
+        self.project_id = project_id;
+        self.region = region;
+
+        // Cached lists belong to the previous project and region
+        self.snapshot.clear_lists();
+        self.stale_since = None;
+        self.modules.clear();
+        self.stacks.clear();
+        self.deployments.clear();
+        self.view_state.modules.clear();
+        self.view_state.stacks.clear();
+        self.view_state.deployments.clear();
+        self.selected_index = 0;
 
-        module_list.sort_by(|a, b| a.module_name.cmp(&b.module_name));
+        // Narrow the deployments to the selected project and region
+        self.selected_project_filter = self
+            .projects_cache
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .find(|p| p.project_id == self.project_id)
+            .map(project_display);
+        self.selected_region_filter = Some(self.region.clone());
+        self.project_selection_made = true;
+
+        let action = match self.current_view {
+            View::Modules => PendingAction::LoadModules,
+            View::Stacks => PendingAction::LoadStacks,
+            View::Deployments => PendingAction::LoadDeployments,
+            View::Policies => PendingAction::None,
+        };
+        self.schedule_action(action);
+    }
+
+    /// Show the cached modules for the current track, returns false if there are none
+    fn show_cached_modules(&mut self) -> bool {
+        match self.snapshot.modules_for_track(&self.current_track) {
+            Some(cached) => {
+                self.modules = cached.data;
+                self.stale_since = Some(cached.fetched_at);
+                true
+            }
+            None => {
+                self.stale_since = None;
+                false
+            }
+        }
+    }
+
+    /// Show the cached stacks for the current track, returns false if there are none
+    fn show_cached_stacks(&mut self) -> bool {
+        match self.snapshot.stacks_for_track(&self.current_track) {
+            Some(cached) => {
+                self.stacks = cached.data;
+                self.stale_since = Some(cached.fetched_at);
+                true
+            }
+            None => {
+                self.stale_since = None;
+                false
+            }
+        }
+    }
+
+    /// Show the cached deployments, returns false if there are none
+    fn show_cached_deployments(&mut self) -> bool {
+        match self.snapshot.deployments.clone() {
+            Some(cached) => {
+                self.deployments = cached.data;
+                self.stale_since = Some(cached.fetched_at);
+                true
+            }
+            None => {
+                self.stale_since = None;
+                false
+            }
+        }
+    }
+
+    pub async fn load_modules(&mut self) -> Result<()> {
+        let track = self.current_track.clone();
+        let handler = self.handler().await;
+
+        if self.offline {
+            if !self.show_cached_modules() {
+                self.modules.clear();
+            }
+        } else if let (Some(_), Some(sender)) = (&self.stale_since, &self.background_sender) {
+            // Cached modules are shown, refresh them in the background
+            crate::tui::background::spawn_task(
+                sender.clone(),
+                fetch_modules(handler.clone(), track.clone()),
+                |result| {
+                    crate::tui::background::BackgroundMessage::ModulesLoaded(
+                        result.map(|modules| (track, modules)),
+                    )
+                },
+            );
+        } else {
+            match fetch_modules(handler.clone(), track.clone()).await {
+                Ok(modules) => {
+                    self.snapshot.store_modules(&track, &modules);
+                    self.modules = modules;
+                    self.stale_since = None;
+                }
+                Err(e) => {
+                    if !self.show_cached_modules() {
+                        return Err(e);
+                    }
+                }
+            }
+        }
 
-        self.modules = module_list;
         self.selected_index = 0;
         self.clear_loading();
         Ok(())
     }
 
     pub async fn load_stacks(&mut self) -> Result<()> {
-        let track_filter = if self.current_track == "all" {
-            ""
-        } else {
-            &self.current_track
-        };
+        let track = self.current_track.clone();
+        let handler = self.handler().await;
 
-        let stacks = if is_http_mode_enabled() {
-            http_client::http_get_all_latest_stacks(track_filter).await?
+        if self.offline {
+            if !self.show_cached_stacks() {
+                self.stacks.clear();
+            }
+        } else if let (Some(_), Some(sender)) = (&self.stale_since, &self.background_sender) {
+            // Cached stacks are shown, refresh them in the background
+            crate::tui::background::spawn_task(
+                sender.clone(),
+                fetch_stacks(handler.clone(), track.clone()),
+                |result| {
+                    crate::tui::background::BackgroundMessage::StacksLoaded(
+                        result.map(|stacks| (track, stacks)),
+                    )
+                },
+            );
         } else {
-            current_region_handler()
-                .await
-                .get_all_latest_stack(track_filter)
-                .await?
-        };
-
-        let mut stack_list: Vec<Module> = stacks
-            .into_iter()
-            .map(|s| Module {
-                module: s.module,
-                module_name: s.module_name,
-                version: s.version,
-                track: s.track,
-                reference: s.reference,
-                timestamp: s.timestamp,
-                deprecated: s.deprecated,
-                deprecated_message: s.deprecated_message,
-            })
-            .collect();
-
-        stack_list.sort_by(|a, b| a.module_name.cmp(&b.module_name));
+            match fetch_stacks(handler.clone(), track.clone()).await {
+                Ok(stacks) => {
+                    self.snapshot.store_stacks(&track, &stacks);
+                    self.stacks = stacks;
+                    self.stale_since = None;
+                }
+                Err(e) => {
+                    if !self.show_cached_stacks() {
+                        return Err(e);
+                    }
+                }
+            }
+        }
 
-        self.stacks = stack_list;
         self.selected_index = 0;
         self.clear_loading();
         Ok(())
     }
 
     pub async fn load_deployments(&mut self) -> Result<()> {
+        if self.offline {
+            if !self.show_cached_deployments() {
+                self.deployments.clear();
+            }
+            // Rebuild the filters from the cached projects
+            self.process_deployment_batch(Vec::new());
+            self.clear_loading();
+            return Ok(());
+        }
+
         // Populate cache if empty
         if self.projects_cache.is_none() {
             let projects_result = if is_http_mode_enabled() {
@@ -920,18 +1234,20 @@ impl App {
                             .collect::<Result<Vec<env_defs::ProjectData>>>()
                     })
             } else {
-                let handler = current_region_handler().await;
+                let handler = self.handler().await;
                 handler.get_all_projects().await.map_err(Into::into)
             };
 
             match projects_result {
                 Ok(projects) => {
+                    self.snapshot.store_projects(&projects);
                     self.projects_cache = Some(projects);
                 }
                 Err(_e) => {
-                    if !is_http_mode_enabled() {
+                    // Keep showing the cached deployments when the cloud is unreachable
+                    if !is_http_mode_enabled() && self.stale_since.is_none() {
                         // Fallback to simpler loading if project list fails (non-HTTP only)
-                        let handler = current_region_handler().await;
+                        let handler = self.handler().await;
                         let deployments = handler.get_all_deployments("", false).await?;
                         self.process_deployments_internal(deployments);
                     }
@@ -944,8 +1260,10 @@ impl App {
         // We can safely unwrap here because we just populated it or returned
         let projects = self.projects_cache.as_ref().unwrap();
 
-        // Reset deployments and filters
-        self.deployments.clear();
+        // Reset deployments and filters, cached deployments are replaced when the first batch arrives
+        if self.stale_since.is_none() {
+            self.deployments.clear();
+        }
         self.available_projects.clear();
         self.available_regions.clear();
 
@@ -1243,7 +1561,7 @@ impl App {
                     .await
                     .map(Some)
             } else {
-                current_region_handler()
+                self.handler()
                     .await
                     .get_module_version(&module_name, &module_track, &module_version)
                     .await
@@ -1320,7 +1638,7 @@ impl App {
                     .await
                     .map(Some)
             } else {
-                current_region_handler()
+                self.handler()
                     .await
                     .get_stack_version(&stack_name, &stack_track, &stack_version)
                     .await
@@ -1407,6 +1725,15 @@ impl App {
             };
 
             let (deployment_detail, _) = deployment_detail;
+            let deployment_detail = match deployment_detail {
+                Some(detail) => Some(
+                    crate::utils::with_stack_instance(
+                        crate::utils::with_drift_report(detail).await,
+                    )
+                    .await,
+                ),
+                None => None,
+            };
 
             if let Some(detail) = deployment_detail {
                 // Build navigation items for this deployment
@@ -1501,6 +1828,15 @@ impl App {
 
                     let message = match result {
                         Ok((deployment_detail, _)) => {
+                            let deployment_detail = match deployment_detail {
+                                Some(detail) => Some(
+                                    crate::utils::with_stack_instance(
+                                        crate::utils::with_drift_report(detail).await,
+                                    )
+                                    .await,
+                                ),
+                                None => None,
+                            };
                             crate::tui::background::BackgroundMessage::DeploymentDetailLoaded(Ok(
                                 deployment_detail,
                             ))
@@ -1876,7 +2212,7 @@ impl App {
                 )
                 .await?
             } else {
-                current_region_handler()
+                self.handler()
                     .await
                     .get_all_stack_versions(&self.modal_module_name, &self.modal_track)
                     .await?
@@ -1889,7 +2225,7 @@ impl App {
                 )
                 .await?
             } else {
-                current_region_handler()
+                self.handler()
                     .await
                     .get_all_module_versions(&self.modal_module_name, &self.modal_track)
                     .await?
@@ -2126,6 +2462,17 @@ impl App {
 
         self.set_loading("Loading deployment events...");
 
+        let key = events_key(&project_id, &region, &environment, &deployment_id);
+        let cached_events = self.snapshot.events.get(&key).cloned();
+        self.events_stale_since = None;
+
+        if self.offline {
+            self.events_stale_since = cached_events.as_ref().map(|c| c.fetched_at.clone());
+            self.events_data = cached_events.map(|c| c.data).unwrap_or_default();
+            self.clear_loading();
+            return Ok(());
+        }
+
         let events_result = if is_http_mode_enabled() {
             http_client::http_get_events(&project_id, &region, &environment, &deployment_id)
                 .await
@@ -2144,16 +2491,24 @@ impl App {
                 .map_err(Into::into)
         };
 
-        match events_result {
-            Ok(events) => {
+        match (events_result, cached_events) {
+            (Ok(events), _) => {
                 // Sort events by epoch (oldest first for chronological order)
                 let mut sorted_events = events;
                 sorted_events.sort_by(|a, b| a.epoch.cmp(&b.epoch));
+                self.snapshot.store_events(key, &sorted_events);
                 self.events_data = sorted_events;
                 self.clear_loading();
                 Ok(())
             }
-            Err(e) => {
+            // Fall back to the cached events when the cloud is unreachable
+            (Err(_), Some(cached)) => {
+                self.events_stale_since = Some(cached.fetched_at);
+                self.events_data = cached.data;
+                self.clear_loading();
+                Ok(())
+            }
+            (Err(e), None) => {
                 self.events_data.clear();
                 self.clear_loading();
                 Err(e)
@@ -2234,6 +2589,7 @@ impl App {
                             text.lines()
                                 .map(|line| env_defs::LogData {
                                     message: line.to_string(),
+                                    timestamp: None,
                                 })
                                 .collect::<Vec<_>>()
                         })
@@ -2271,6 +2627,7 @@ impl App {
                         text.lines()
                             .map(|line| env_defs::LogData {
                                 message: line.to_string(),
+                                timestamp: None,
                             })
                             .collect::<Vec<_>>()
                     })
@@ -2592,6 +2949,7 @@ impl App {
                 }
                 _ => {}
             }
+            self.stale_since = None;
             self.current_view = view;
             self.selected_index = 0;
             self.showing_detail = false;
@@ -2986,17 +3344,17 @@ impl App {
         Ok(())
     }
 
-    pub async fn run_claim_from_builder(&mut self) -> Result<()> {
+    /// Validate the claim builder form and parse its claim, along with the environment and
+    /// reference fallback to run it with. Shows the error and returns None when it can't be run
+    fn prepare_claim_from_builder(&mut self) -> Option<(serde_yaml::Value, String, String)> {
         use crate::utils::get_environment;
-        use env_common::logic::run_claim;
-        use env_defs::ExtraData;
 
         // Validate the form first
         if let Err(err) = self.claim_builder_state.validate() {
             self.detail_state
                 .show_error(&format!("Validation failed: {}", err));
             self.clear_loading();
-            return Ok(());
+            return None;
         }
 
         // Generate the YAML
@@ -3010,7 +3368,7 @@ impl App {
                     self.detail_state
                         .show_error(&format!("Failed to parse YAML: {}", e));
                     self.clear_loading();
-                    return Ok(());
+                    return None;
                 }
             };
 
@@ -3023,50 +3381,127 @@ impl App {
                 self.detail_state
                     .show_error(&format!("Failed to get hostname: {}", e));
                 self.clear_loading();
-                return Ok(());
+                return None;
             }
         };
 
-        // Run the claim using current_region_handler (same as reapply_deployment)
-        let handler = current_region_handler().await;
-        match run_claim(
+        Some((yaml, environment, reference_fallback))
+    }
+
+    /// Submit a plan job for the claim of the builder. Once the job finished, its summary is
+    /// shown for confirmation before the plan is applied
+    pub async fn plan_claim_from_builder(&mut self) -> Result<()> {
+        use crate::tui::background::{spawn_task, BackgroundMessage};
+        use crate::tui::state::claim_builder_state::ClaimPlan;
+        use crate::{follow_job_changes, ClaimJobStruct, JobChanges};
+        use env_common::logic::run_claim;
+        use env_defs::ExtraData;
+
+        let Some((yaml, environment, reference_fallback)) = self.prepare_claim_from_builder()
+        else {
+            return Ok(());
+        };
+        self.claim_builder_state.plan = None;
+
+        // Run the claim in the project and region selected in the switcher
+        let handler = self.handler().await;
+        let (job_id, deployment_id) = match run_claim(
             &handler,
             &yaml,
             &environment,
-            "apply",
+            "plan",
             vec![],
             ExtraData::None,
             &reference_fallback,
         )
         .await
         {
-            Ok((job_id, deployment_id, _)) => {
+            Ok((job_id, deployment_id, _)) => (job_id, deployment_id),
+            Err(e) => {
                 let message = format!(
-                    "✅ Deployment claim executed successfully!\n\n\
-                    Job ID: {}\n\
-                    Deployment ID: {}\n\
-                    Environment: {}\n\n\
-                    The deployment is being processed in the background.\n\
-                    You can monitor its progress in the deployments list.\n\n\
-                    Press any key to close.",
-                    job_id, deployment_id, environment
+                    "❌ Failed to plan deployment claim:\n\n{}\n\n\
+                    Please check your configuration and try again.",
+                    e
                 );
+                self.detail_state.show_error(&message);
+                self.clear_loading();
+                return Ok(());
+            }
+        };
 
-                // Close the claim builder BEFORE showing the message
-                // so the message is displayed in the main view
-                self.claim_builder_state.close();
+        let project_id = self.project_id.clone();
+        let region = handler.get_region().to_string();
+        let job = ClaimJobStruct {
+            job_id: job_id.clone(),
+            deployment_id,
+            environment,
+            region: region.clone(),
+        };
+        let planned = async move {
+            follow_job_changes(&[job], "plan")
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("No plan was recorded for job {}", job_id))
+        };
+        let to_message = move |result: std::result::Result<JobChanges, String>| {
+            BackgroundMessage::ClaimPlanned(result.map(|changes| ClaimPlan {
+                project_id,
+                region,
+                changes,
+            }))
+        };
 
-                // Show success message in detail view
-                self.detail_state.show_message(message.clone());
+        // The loading indicator stays until the plan job finished
+        if let Some(sender) = self.background_sender.clone() {
+            spawn_task(sender, planned, to_message);
+        } else {
+            let result = planned.await.map_err(|e| e.to_string());
+            self.process_background_message(to_message(result));
+        }
+        Ok(())
+    }
+
+    /// Apply the confirmed plan of the claim builder and follow its job in the events view
+    pub async fn run_claim_from_builder(&mut self) -> Result<()> {
+        use env_common::logic::apply_plan_infra;
+        use env_defs::ExtraData;
+
+        let Some(plan) = self.claim_builder_state.plan.take() else {
+            self.clear_loading();
+            return Ok(());
+        };
+        let changes = &plan.changes;
+
+        let handler = context_handler(&plan.project_id, &plan.region).await;
+        match apply_plan_infra(
+            &handler,
+            &changes.deployment_id,
+            &changes.environment,
+            &changes.job_id,
+            ExtraData::None,
+        )
+        .await
+        {
+            Ok(_) => {
+                // Close the claim builder so the events view is displayed in the main view
+                self.claim_builder_state.close();
 
                 // Reload deployments list to show the new/updated deployment
                 self.schedule_action(PendingAction::LoadDeployments);
+
+                self.show_deployment_events(
+                    changes.deployment_id.clone(),
+                    plan.project_id.clone(),
+                    plan.region.clone(),
+                    changes.environment.clone(),
+                )
+                .await?;
             }
             Err(e) => {
                 let message = format!(
-                    "❌ Failed to execute deployment claim:\n\n{}\n\n\
-                    Please check your configuration and try again.",
-                    e
+                    "❌ Failed to apply plan {} of deployment claim:\n\n{}",
+                    changes.job_id, e
                 );
                 self.detail_state.show_error(&message);
             }
@@ -3078,6 +3513,77 @@ impl App {
 }
 
 // Implement VersionItem trait for Module to work with VersionsModal widget
+/// Fetch the latest modules on a track ("all" for every track), sorted by name
+async fn fetch_modules(handler: GenericCloudHandler, track: String) -> Result<Vec<Module>> {
+    // Use empty string for "all" track to get modules from all tracks
+    let track_filter = if track == "all" { "" } else { &track };
+
+    let modules = if is_http_mode_enabled() {
+        http_client::http_get_all_latest_modules(track_filter).await?
+    } else {
+        handler.get_all_latest_module(track_filter).await?
+    };
+
+    let mut module_list: Vec<Module> = modules.into_iter().map(to_module).collect();
+    module_list.sort_by(|a, b| a.module_name.cmp(&b.module_name));
+    Ok(module_list)
+}
+
+/// Fetch all projects, through the API in HTTP mode
+async fn fetch_projects(handler: GenericCloudHandler) -> Result<Vec<env_defs::ProjectData>> {
+    if is_http_mode_enabled() {
+        http_client::http_get_all_projects()
+            .await?
+            .into_iter()
+            .map(|v| serde_json::from_value(v).map_err(Into::into))
+            .collect()
+    } else {
+        Ok(handler.get_all_projects().await?)
+    }
+}
+
+/// Handler for a project and region, or the one initialized at startup while they are unknown
+async fn context_handler(project_id: &str, region: &str) -> GenericCloudHandler {
+    if project_id == "unknown" || region == "unknown" {
+        current_region_handler().await
+    } else {
+        GenericCloudHandler::workload(project_id, region).await
+    }
+}
+
+/// How a project is listed in the project filter and switcher
+fn project_display(project: &env_defs::ProjectData) -> String {
+    format!("{} ({})", project.name, project.project_id)
+}
+
+/// Fetch the latest stacks on a track ("all" for every track), sorted by name
+async fn fetch_stacks(handler: GenericCloudHandler, track: String) -> Result<Vec<Module>> {
+    let track_filter = if track == "all" { "" } else { &track };
+
+    let stacks = if is_http_mode_enabled() {
+        http_client::http_get_all_latest_stacks(track_filter).await?
+    } else {
+        handler.get_all_latest_stack(track_filter).await?
+    };
+
+    let mut stack_list: Vec<Module> = stacks.into_iter().map(to_module).collect();
+    stack_list.sort_by(|a, b| a.module_name.cmp(&b.module_name));
+    Ok(stack_list)
+}
+
+fn to_module(m: ModuleResp) -> Module {
+    Module {
+        module: m.module,
+        module_name: m.module_name,
+        version: m.version,
+        track: m.track,
+        reference: m.reference,
+        timestamp: m.timestamp,
+        deprecated: m.deprecated,
+        deprecated_message: m.deprecated_message,
+    }
+}
+
 impl crate::tui::widgets::modal::VersionItem for Module {
     fn get_version(&self) -> &str {
         &self.version
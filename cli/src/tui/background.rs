@@ -7,8 +7,10 @@ use super::app::{Deployment, Module};
 #[derive(Debug, Clone)]
 pub enum BackgroundMessage {
     // Data loading results
-    ModulesLoaded(Result<Vec<Module>, String>),
-    StacksLoaded(Result<Vec<Module>, String>),
+    /// Track and the modules loaded for it
+    ModulesLoaded(Result<(String, Vec<Module>), String>),
+    /// Track and the stacks loaded for it
+    StacksLoaded(Result<(String, Vec<Module>), String>),
     DeploymentsLoaded(Result<Vec<Deployment>, String>),
 
     // Detail loading results
@@ -29,6 +31,7 @@ pub enum BackgroundMessage {
     // Actions
     DeploymentReapplied(Result<(String, String, String), String>),
     DeploymentDestroyed(Result<String, String>),
+    ClaimPlanned(Result<super::state::claim_builder_state::ClaimPlan, String>),
 
     // Batch loading
     DeploymentsBatchLoaded(Result<Vec<Deployment>, String>),
@@ -59,7 +59,7 @@ impl ClaimBuilderHandler {
                     return Ok(());
                 }
                 KeyCode::Char('r') => {
-                    // Run claim with confirmation
+                    // Plan the claim, then apply it once its plan is confirmed
                     if !state.show_preview {
                         state.generate_yaml();
                     }
@@ -71,26 +71,8 @@ impl ClaimBuilderHandler {
                         return Ok(());
                     }
 
-                    // Show confirmation dialog
-                    let deployment_name = &state.deployment_name;
-                    let region = &state.region;
-                    let message = format!(
-                        "Are you sure you want to run this deployment claim?\n\n\
-                        Deployment: {}\n\
-                        Region: {}\n\n\
-                        This will create or update the deployment.\n\n\
-                        Press 'y' to confirm or 'n' to cancel.",
-                        deployment_name, region
-                    );
-
-                    app.modal_state.showing_confirmation = true;
-                    app.modal_state.confirmation_message = message.clone();
-                    app.modal_state.confirmation_action =
-                        crate::tui::app::PendingAction::RunClaimFromBuilder;
-
-                    app.showing_confirmation = true;
-                    app.confirmation_message = message;
-                    app.confirmation_action = crate::tui::app::PendingAction::RunClaimFromBuilder;
+                    // The plan summary is confirmed before the claim is applied
+                    app.schedule_action(crate::tui::app::PendingAction::PlanClaimFromBuilder);
 
                     return Ok(());
                 }
@@ -175,6 +175,9 @@ impl MainHandler {
                     );
                 }
             }
+            KeyCode::Char('S') => {
+                app.schedule_action(PendingAction::ShowContextSwitcher);
+            }
             KeyCode::Char('r') => match app.current_view {
                 crate::tui::app::View::Modules => {
                     app.schedule_action(PendingAction::LoadModules);
@@ -63,7 +63,12 @@ impl ModalHandler {
             }
             KeyCode::Down | KeyCode::Char('j') => {
                 // Limit to length (which is the "All" option at the end)
-                if app.modal_state.filter_selected_index < app.modal_state.filter_options.len() {
+                let max_index = if app.modal_state.filter_has_all_option() {
+                    app.modal_state.filter_options.len()
+                } else {
+                    app.modal_state.filter_options.len().saturating_sub(1)
+                };
+                if app.modal_state.filter_selected_index < max_index {
                     app.modal_state.filter_selected_index += 1;
                 }
             }
@@ -92,6 +97,16 @@ impl ModalHandler {
                         app.project_selection_made = true;
                         app.schedule_action(PendingAction::LoadDeployments);
                     }
+                    crate::tui::state::modal_state::FilterType::SwitchProject => {
+                        if let Some(project) = selected {
+                            app.select_context_project(&project);
+                        }
+                    }
+                    crate::tui::state::modal_state::FilterType::SwitchRegion => {
+                        if let Some(region) = selected {
+                            app.select_context_region(region);
+                        }
+                    }
                     _ => {}
                 }
                 app.modal_state.close_filter_modal();
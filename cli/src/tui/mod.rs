@@ -4,6 +4,7 @@ pub mod background_tasks;
 pub mod events;
 pub mod handlers;
 pub mod renderers;
+pub mod snapshot;
 pub mod state;
 pub mod ui;
 pub mod utils;
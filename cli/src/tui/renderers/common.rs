@@ -88,7 +88,7 @@ pub fn render_header(frame: &mut Frame, area: Rect, app: &App) {
         _ => String::new(),
     };
 
-    let content = vec![
+    let mut content = vec![
         Span::styled(icon, Style::default().fg(Color::Cyan)),
         Span::raw(" "),
         Span::styled(
@@ -100,6 +100,26 @@ pub fn render_header(frame: &mut Frame, area: Rect, app: &App) {
         Span::styled(count, Style::default().fg(Color::DarkGray)),
     ];
 
+    // Mark data served from the snapshot so it isn't mistaken for live data
+    if app.offline {
+        content.push(Span::styled(
+            " • OFFLINE",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(fetched_at) = &app.stale_since {
+        content.push(Span::styled(
+            format!(" • cached data from {}", fetched_at),
+            Style::default().fg(Color::Yellow),
+        ));
+        if !app.offline {
+            content.push(Span::styled(
+                ", refreshing...",
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+    }
+
     let header = Paragraph::new(Line::from(content)).block(
         Block::default()
             .borders(Borders::ALL)
@@ -207,6 +227,7 @@ fn get_footer_actions(app: &App) -> Vec<(&'static str, &'static str)> {
             ("/", "Search"),
             ("Enter", "Details"),
             ("r", "Reload"),
+            ("S", "Switch Project"),
             ("Ctrl+C", "Quit"),
         ]
     } else if matches!(app.current_view, View::Stacks) {
@@ -215,6 +236,7 @@ fn get_footer_actions(app: &App) -> Vec<(&'static str, &'static str)> {
             ("/", "Search"),
             ("Enter", "Details"),
             ("r", "Reload"),
+            ("S", "Switch Project"),
             ("Ctrl+C", "Quit"),
         ]
     } else if matches!(app.current_view, View::Deployments) {
@@ -225,6 +247,7 @@ fn get_footer_actions(app: &App) -> Vec<(&'static str, &'static str)> {
             ("r", "Reload"),
             ("Ctrl+R", "Reapply"),
             ("Ctrl+D", "Destroy"),
+            ("S", "Switch Project"),
             ("Ctrl+C", "Quit"),
         ]
     } else {
@@ -232,6 +255,7 @@ fn get_footer_actions(app: &App) -> Vec<(&'static str, &'static str)> {
             ("/", "Search"),
             ("Enter", "Details"),
             ("r", "Reload"),
+            ("S", "Switch Project"),
             ("Ctrl+C", "Quit"),
         ]
     }
@@ -9,8 +9,10 @@ use ratatui::{
     Frame,
 };
 
+use env_defs::ResourceAction;
+
 use crate::tui::app::{App, PendingAction, View};
-use crate::tui::utils::{to_camel_case, NavItem};
+use crate::tui::utils::{build_module_doc_nav_items, module_variable_rows, to_camel_case, NavItem};
 
 /// Render detail view (module/stack/deployment details)
 pub fn render_detail(frame: &mut Frame, area: Rect, app: &mut App) {
@@ -239,6 +241,12 @@ fn render_deployment_detail(
     }
 }
 
+fn format_epoch(epoch: u128) -> String {
+    chrono::DateTime::from_timestamp((epoch / 1000) as i64, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
 fn format_json_value_nicely(value: &serde_json::Value, indent: usize) -> Vec<Line<'static>> {
     let mut lines = Vec::new();
     let indent_str = "  ".repeat(indent);
@@ -531,6 +539,47 @@ fn build_deployment_detail_content(
 
         lines.push(Line::from(""));
 
+        if let Some(ref stack_instance) = deployment.stack_instance {
+            lines.push(Line::from(Span::styled(
+                "Stack Modules",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )));
+            lines.push(Line::from(Span::styled(
+                "─".repeat(40),
+                Style::default().fg(Color::DarkGray),
+            )));
+            for module in stack_instance {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("  {} ", module.claim),
+                        Style::default()
+                            .fg(Color::White)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!("{} {}", module.module, module.version),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::styled(
+                        format!(" ({})", module.track),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]));
+                for output in &module.outputs {
+                    lines.push(Line::from(vec![
+                        Span::styled(
+                            format!("      {} ← {}: ", output.name, output.module_output),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                        Span::styled(output.value.to_string(), Style::default().fg(Color::White)),
+                    ]));
+                }
+            }
+            lines.push(Line::from(""));
+        }
+
         // Drift Detection subsection
         lines.push(Line::from(Span::styled(
             "Drift Detection",
@@ -560,14 +609,35 @@ fn build_deployment_detail_content(
         ]));
 
         if deployment.drift_detection.enabled {
+            let (schedule_label, schedule) = match &deployment.drift_detection.schedule {
+                Some(schedule) => ("Schedule: ", schedule.clone()),
+                None => ("Interval: ", deployment.drift_detection.interval.clone()),
+            };
             lines.push(Line::from(vec![
-                Span::styled("Interval: ", Style::default().fg(Color::DarkGray)),
-                Span::styled(
-                    deployment.drift_detection.interval.clone(),
-                    Style::default().fg(Color::White),
-                ),
+                Span::styled(schedule_label, Style::default().fg(Color::DarkGray)),
+                Span::styled(schedule, Style::default().fg(Color::White)),
             ]));
 
+            for window in &deployment.drift_detection.blackout_windows {
+                lines.push(Line::from(vec![
+                    Span::styled("Blackout: ", Style::default().fg(Color::DarkGray)),
+                    Span::styled(
+                        format!("{} for {}", window.schedule, window.duration),
+                        Style::default().fg(Color::White),
+                    ),
+                ]));
+            }
+
+            if deployment.next_drift_check_epoch > 0 {
+                lines.push(Line::from(vec![
+                    Span::styled("Next Check: ", Style::default().fg(Color::DarkGray)),
+                    Span::styled(
+                        format_epoch(deployment.next_drift_check_epoch as u128),
+                        Style::default().fg(Color::White),
+                    ),
+                ]));
+            }
+
             lines.push(Line::from(vec![
                 Span::styled("Auto Remediate: ", Style::default().fg(Color::DarkGray)),
                 Span::styled(
@@ -581,6 +651,16 @@ fn build_deployment_detail_content(
             ]));
         }
 
+        if let Some(last_drift_check_epoch) = deployment.last_drift_check_epoch {
+            lines.push(Line::from(vec![
+                Span::styled("Last Check: ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    format_epoch(last_drift_check_epoch),
+                    Style::default().fg(Color::White),
+                ),
+            ]));
+        }
+
         lines.push(Line::from(""));
 
         if deployment.has_drifted {
@@ -591,6 +671,32 @@ fn build_deployment_detail_content(
                     Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
                 ),
             ]));
+            if let Some(ref drift_report) = deployment.drift_report {
+                for change in drift_report {
+                    let (symbol, color) = match change.action {
+                        ResourceAction::Create => ("+", Color::Green),
+                        ResourceAction::Delete => ("-", Color::Red),
+                        ResourceAction::Replace => ("±", Color::Magenta),
+                        ResourceAction::Update | ResourceAction::NoOp => ("~", Color::Yellow),
+                    };
+                    lines.push(Line::from(vec![
+                        Span::styled(format!("  {} ", symbol), Style::default().fg(color)),
+                        Span::styled(change.address.clone(), Style::default().fg(Color::White)),
+                    ]));
+                    for (attribute, diff) in change.changes.iter().flatten() {
+                        lines.push(Line::from(vec![
+                            Span::styled(
+                                format!("      {}: ", attribute),
+                                Style::default().fg(Color::DarkGray),
+                            ),
+                            Span::styled(
+                                format!("{} → {}", diff["before"], diff["after"]),
+                                Style::default().fg(color),
+                            ),
+                        ]));
+                    }
+                }
+            }
             lines.push(Line::from(""));
         }
 
@@ -1235,6 +1341,8 @@ fn render_stack_general(stack: &env_defs::ModuleResp, lines: &mut Vec<Line<'stat
         lines.push(Line::from(""));
     }
 
+    push_owners(&stack.manifest.spec.owners, lines);
+
     lines.push(Line::from(Span::styled(
         "Summary:",
         Style::default().fg(Color::DarkGray),
@@ -1265,6 +1373,18 @@ fn render_stack_general(stack: &env_defs::ModuleResp, lines: &mut Vec<Line<'stat
     }
 }
 
+/// Teams and people maintaining a module or stack, if set
+fn push_owners(owners: &[String], lines: &mut Vec<Line<'static>>) {
+    if owners.is_empty() {
+        return;
+    }
+    lines.push(Line::from(vec![
+        Span::styled("Owners: ", Style::default().fg(Color::DarkGray)),
+        Span::styled(owners.join(", "), Style::default().fg(Color::Cyan)),
+    ]));
+    lines.push(Line::from(""));
+}
+
 fn render_stack_composition(stack: &env_defs::ModuleResp, lines: &mut Vec<Line<'static>>) {
     if let Some(stack_data) = &stack.stack_data {
         use std::collections::HashMap;
@@ -1775,6 +1895,10 @@ fn render_module_detail(
         }
     }
 
+    for item in build_module_doc_nav_items(module) {
+        nav_items.push(item.display_string());
+    }
+
     // Render navigation tree (left pane)
     let nav_list_items: Vec<ListItem> = nav_items
         .iter()
@@ -1945,6 +2069,8 @@ fn build_detail_content(app: &App, module: &env_defs::ModuleResp) -> Vec<Line<'s
             lines.push(Line::from(""));
         }
 
+        push_owners(&module.manifest.spec.owners, &mut lines);
+
         lines.push(Line::from(Span::styled(
             "Summary:",
             Style::default().fg(Color::DarkGray),
@@ -2020,67 +2146,7 @@ fn build_detail_content(app: &App, module: &env_defs::ModuleResp) -> Vec<Line<'s
             )));
             lines.push(Line::from(""));
 
-            for var in &sorted_vars {
-                let type_str = match &var._type {
-                    serde_json::Value::String(s) => s.clone(),
-                    other => format!("{}", other),
-                };
-                let is_required = var.required();
-                let camel_case = to_camel_case(&var.name);
-
-                // Highlight required variables with red bullet and bold name
-                let bullet = if is_required {
-                    Span::styled("⚠ ", Style::default().fg(Color::Red))
-                } else {
-                    Span::styled("• ", Style::default().fg(Color::Yellow))
-                };
-
-                let name_style = if is_required {
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
-                } else {
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD)
-                };
-
-                lines.push(Line::from(vec![
-                    bullet,
-                    Span::styled(camel_case, name_style),
-                    Span::raw(" : "),
-                    Span::styled(type_str, Style::default().fg(Color::Blue)),
-                ]));
-
-                if !var.description.is_empty() {
-                    lines.push(Line::from(vec![
-                        Span::raw("  "),
-                        Span::styled(var.description.clone(), Style::default().fg(Color::White)),
-                    ]));
-                }
-
-                if let Some(default) = &var.default {
-                    let default_str = match default {
-                        serde_json::Value::String(s) => format!("\"{}\"", s),
-                        serde_json::Value::Null => "null".to_string(),
-                        other => format!("{}", other),
-                    };
-                    lines.push(Line::from(vec![
-                        Span::raw("  Default: "),
-                        Span::styled(default_str, Style::default().fg(Color::Green)),
-                    ]));
-                } else {
-                    lines.push(Line::from(vec![
-                        Span::raw("  "),
-                        Span::styled(
-                            "⚠ REQUIRED",
-                            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-                        ),
-                    ]));
-                }
-
-                lines.push(Line::from(""));
-            }
+            render_variables_table(module, &mut lines);
 
             return lines;
         }
@@ -2300,5 +2366,221 @@ fn build_detail_content(app: &App, module: &env_defs::ModuleResp) -> Vec<Line<'s
         }
     }
 
+    for item in build_module_doc_nav_items(module) {
+        if app.detail_browser_index == current_idx {
+            match item {
+                NavItem::Examples => render_module_examples(module, &mut lines),
+                NavItem::Readme => render_module_readme(module, &mut lines),
+                NavItem::VersionDiff { .. } => render_module_version_diff(module, &mut lines),
+                _ => {}
+            }
+            return lines;
+        }
+        current_idx += 1;
+    }
+
     lines
 }
+
+fn push_section_title(title: String, color: Color, lines: &mut Vec<Line<'static>>) {
+    lines.push(Line::from(Span::styled(
+        title,
+        Style::default().fg(color).add_modifier(Modifier::BOLD),
+    )));
+    lines.push(Line::from(Span::styled(
+        "═".repeat(60),
+        Style::default().fg(Color::DarkGray),
+    )));
+    lines.push(Line::from(""));
+}
+
+/// Variables as a table of name, type, default and whether they are required
+fn render_variables_table(module: &env_defs::ModuleResp, lines: &mut Vec<Line<'static>>) {
+    let headers = ["Name", "Type", "Default", "Required"];
+    let rows = module_variable_rows(module);
+    // Long types and defaults are cut so the table stays readable, the variable itself shows them in full
+    const MAX_COLUMN_WIDTH: usize = 30;
+    let truncate = |value: &str| -> String {
+        if value.chars().count() > MAX_COLUMN_WIDTH {
+            let cut: String = value.chars().take(MAX_COLUMN_WIDTH - 1).collect();
+            format!("{}…", cut)
+        } else {
+            value.to_string()
+        }
+    };
+    let rows: Vec<[String; 4]> = rows
+        .iter()
+        .map(|row| row.clone().map(|cell| truncate(&cell)))
+        .collect();
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let pad = |value: &str, width: usize| -> String {
+        format!("{}{}  ", value, " ".repeat(width - value.chars().count()))
+    };
+
+    lines.push(Line::from(
+        headers
+            .iter()
+            .zip(&widths)
+            .map(|(header, width)| {
+                Span::styled(
+                    pad(header, *width),
+                    Style::default()
+                        .fg(Color::DarkGray)
+                        .add_modifier(Modifier::BOLD),
+                )
+            })
+            .collect::<Vec<_>>(),
+    ));
+    lines.push(Line::from(Span::styled(
+        "─".repeat(widths.iter().map(|w| w + 2).sum()),
+        Style::default().fg(Color::DarkGray),
+    )));
+    for [name, type_str, default, required] in rows {
+        let is_required = required == "yes";
+        lines.push(Line::from(vec![
+            Span::styled(
+                pad(&name, widths[0]),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(pad(&type_str, widths[1]), Style::default().fg(Color::Blue)),
+            Span::styled(pad(&default, widths[2]), Style::default().fg(Color::Green)),
+            Span::styled(
+                pad(&required, widths[3]),
+                if is_required {
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                },
+            ),
+        ]));
+    }
+}
+
+fn render_module_examples(module: &env_defs::ModuleResp, lines: &mut Vec<Line<'static>>) {
+    push_section_title("💡 Examples".to_string(), Color::Yellow, lines);
+
+    for example in module.manifest.spec.examples.iter().flatten() {
+        lines.push(Line::from(vec![
+            Span::styled("• ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                example.name.clone(),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]));
+        if !example.description.is_empty() {
+            for line in example.description.lines() {
+                lines.push(Line::from(Span::styled(
+                    format!("  {}", line),
+                    Style::default().fg(Color::White),
+                )));
+            }
+        }
+        lines.push(Line::from(Span::styled(
+            "  variables:",
+            Style::default().fg(Color::DarkGray),
+        )));
+        let variables = serde_yaml::to_string(&example.variables).unwrap_or_default();
+        for line in variables.lines() {
+            lines.push(Line::from(Span::styled(
+                format!("    {}", line),
+                Style::default().fg(Color::Green),
+            )));
+        }
+        lines.push(Line::from(""));
+    }
+
+    lines.push(Line::from(Span::styled(
+        "Press 'c' to build a claim for this module",
+        Style::default().fg(Color::DarkGray),
+    )));
+}
+
+/// README as published with the module, with headings and code blocks highlighted
+fn render_module_readme(module: &env_defs::ModuleResp, lines: &mut Vec<Line<'static>>) {
+    push_section_title("📖 README".to_string(), Color::Cyan, lines);
+
+    let mut in_code_block = false;
+    for line in module.readme.as_deref().unwrap_or_default().lines() {
+        let style = if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            Style::default().fg(Color::DarkGray)
+        } else if in_code_block {
+            Style::default().fg(Color::Green)
+        } else if line.starts_with('#') {
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        lines.push(Line::from(Span::styled(line.to_string(), style)));
+    }
+}
+
+fn render_module_version_diff(module: &env_defs::ModuleResp, lines: &mut Vec<Line<'static>>) {
+    let Some(diff) = &module.version_diff else {
+        return;
+    };
+    push_section_title(
+        format!(
+            "🔀 Changes from {} to {}",
+            diff.previous_version, module.version
+        ),
+        Color::Magenta,
+        lines,
+    );
+
+    if diff.added.is_empty() && diff.changed.is_empty() && diff.removed.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No changes to the module code",
+            Style::default().fg(Color::DarkGray),
+        )));
+        return;
+    }
+
+    for addition in &diff.added {
+        lines.push(Line::from(Span::styled(
+            format!("+ {}", addition.path),
+            Style::default().fg(Color::Green),
+        )));
+    }
+    for change in &diff.changed {
+        lines.push(Line::from(Span::styled(
+            format!("~ {}", change.path),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    for removal in &diff.removed {
+        lines.push(Line::from(Span::styled(
+            format!("- {}", removal.path),
+            Style::default().fg(Color::Red),
+        )));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(
+            format!("{} added", diff.added.len()),
+            Style::default().fg(Color::Green),
+        ),
+        Span::raw(", "),
+        Span::styled(
+            format!("{} changed", diff.changed.len()),
+            Style::default().fg(Color::Yellow),
+        ),
+        Span::raw(", "),
+        Span::styled(
+            format!("{} removed", diff.removed.len()),
+            Style::default().fg(Color::Red),
+        ),
+    ]));
+}
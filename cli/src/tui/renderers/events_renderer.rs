@@ -175,13 +175,17 @@ pub fn render_events(frame: &mut Frame, area: Rect, app: &mut App) {
     } else {
         Color::Yellow
     };
+    let jobs_title = match &app.events_stale_since {
+        Some(fetched_at) => format!(" 📅 Jobs (cached {}) ", fetched_at),
+        None => " 📅 Jobs ".to_string(),
+    };
     let job_list = List::new(job_items)
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(job_border_color))
                 .title(Span::styled(
-                    " 📅 Jobs ",
+                    jobs_title,
                     Style::default()
                         .fg(Color::Yellow)
                         .add_modifier(Modifier::BOLD),
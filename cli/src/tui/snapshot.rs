@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use super::app::{Deployment, Module};
+
+/// Maximum number of deployments whose events are kept in the snapshot
+const MAX_CACHED_EVENTS: usize = 50;
+
+/// Data together with the time it was fetched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cached<T> {
+    pub fetched_at: String,
+    pub data: T,
+}
+
+impl<T> Cached<T> {
+    fn now(data: T) -> Self {
+        Self {
+            fetched_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            data,
+        }
+    }
+}
+
+/// On-disk copy of the last fetched TUI data, used to show stale data on startup
+/// and to browse when offline
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Modules keyed by track ("all" for every track)
+    #[serde(default)]
+    pub modules: HashMap<String, Cached<Vec<Module>>>,
+    /// Stacks keyed by track ("all" for every track)
+    #[serde(default)]
+    pub stacks: HashMap<String, Cached<Vec<Module>>>,
+    #[serde(default)]
+    pub deployments: Option<Cached<Vec<Deployment>>>,
+    #[serde(default)]
+    pub projects: Option<Cached<Vec<env_defs::ProjectData>>>,
+    /// Events keyed by `events_key`
+    #[serde(default)]
+    pub events: HashMap<String, Cached<Vec<env_defs::EventData>>>,
+}
+
+impl Snapshot {
+    /// Load the snapshot from disk, returns an empty snapshot if none exists or it can't be read
+    pub fn load() -> Self {
+        snapshot_path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the snapshot to disk, failures are ignored since the cache is best-effort
+    pub fn save(&self) {
+        if let (Ok(path), Ok(json)) = (snapshot_path(), serde_json::to_string(self)) {
+            let _ = std::fs::write(path, json);
+        }
+    }
+
+    /// Cached modules for a track, falling back to filtering the "all" track
+    pub fn modules_for_track(&self, track: &str) -> Option<Cached<Vec<Module>>> {
+        cached_for_track(&self.modules, track)
+    }
+
+    /// Cached stacks for a track, falling back to filtering the "all" track
+    pub fn stacks_for_track(&self, track: &str) -> Option<Cached<Vec<Module>>> {
+        cached_for_track(&self.stacks, track)
+    }
+
+    pub fn store_modules(&mut self, track: &str, modules: &[Module]) {
+        self.modules
+            .insert(track.to_string(), Cached::now(modules.to_vec()));
+        self.save();
+    }
+
+    pub fn store_stacks(&mut self, track: &str, stacks: &[Module]) {
+        self.stacks
+            .insert(track.to_string(), Cached::now(stacks.to_vec()));
+        self.save();
+    }
+
+    pub fn store_deployments(&mut self, deployments: &[Deployment]) {
+        self.deployments = Some(Cached::now(deployments.to_vec()));
+        self.save();
+    }
+
+    pub fn store_projects(&mut self, projects: &[env_defs::ProjectData]) {
+        self.projects = Some(Cached::now(projects.to_vec()));
+        self.save();
+    }
+
+    /// Drop the cached modules, stacks and deployments, e.g. after switching project or region
+    pub fn clear_lists(&mut self) {
+        self.modules.clear();
+        self.stacks.clear();
+        self.deployments = None;
+        self.save();
+    }
+
+    pub fn store_events(&mut self, key: String, events: &[env_defs::EventData]) {
+        self.events.insert(key, Cached::now(events.to_vec()));
+
+        // Evict the oldest entries to keep the snapshot small
+        while self.events.len() > MAX_CACHED_EVENTS {
+            let oldest = self
+                .events
+                .iter()
+                .min_by(|a, b| a.1.fetched_at.cmp(&b.1.fetched_at))
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => self.events.remove(&key),
+                None => break,
+            };
+        }
+        self.save();
+    }
+}
+
+/// Key used to cache the events of a deployment
+pub fn events_key(
+    project_id: &str,
+    region: &str,
+    environment: &str,
+    deployment_id: &str,
+) -> String {
+    format!(
+        "{}/{}/{}/{}",
+        project_id, region, environment, deployment_id
+    )
+}
+
+fn cached_for_track(
+    cache: &HashMap<String, Cached<Vec<Module>>>,
+    track: &str,
+) -> Option<Cached<Vec<Module>>> {
+    if let Some(cached) = cache.get(track) {
+        return Some(cached.clone());
+    }
+    cache.get("all").map(|all| Cached {
+        fetched_at: all.fetched_at.clone(),
+        data: all
+            .data
+            .iter()
+            .filter(|m| m.track == track)
+            .cloned()
+            .collect(),
+    })
+}
+
+fn snapshot_path() -> anyhow::Result<PathBuf> {
+    let mut path = env_utils::config_path::get_config_dir()?;
+    std::fs::create_dir_all(&path)?;
+    path.push("tui-snapshot.json");
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(name: &str, track: &str) -> Module {
+        Module {
+            module: name.to_string(),
+            module_name: name.to_string(),
+            version: "0.1.0".to_string(),
+            track: track.to_string(),
+            reference: String::new(),
+            timestamp: String::new(),
+            deprecated: false,
+            deprecated_message: None,
+        }
+    }
+
+    #[test]
+    fn test_modules_for_track_falls_back_to_all() {
+        let mut snapshot = Snapshot::default();
+        snapshot.modules.insert(
+            "all".to_string(),
+            Cached::now(vec![module("s3bucket", "stable"), module("vpc", "dev")]),
+        );
+
+        let stable = snapshot.modules_for_track("stable").unwrap();
+        assert_eq!(stable.data.len(), 1);
+        assert_eq!(stable.data[0].module, "s3bucket");
+        assert!(snapshot.stacks_for_track("stable").is_none());
+    }
+}
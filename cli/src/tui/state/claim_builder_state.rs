@@ -1,6 +1,8 @@
-use env_defs::{ModuleResp, TfVariable};
+use env_defs::{ModuleResp, ModuleUi, TfVariable};
 use env_utils::to_camel_case;
 
+use crate::JobChanges;
+
 /// Represents a single variable input field in the claim builder form
 #[derive(Debug, Clone)]
 pub struct VariableInput {
@@ -12,6 +14,8 @@ pub struct VariableInput {
     pub is_sensitive: bool,
     pub user_value: String,
     pub cursor_position: usize,
+    /// Group of the variable in `spec.ui` of the module manifest
+    pub group: Option<String>,
 }
 
 impl VariableInput {
@@ -39,6 +43,7 @@ impl VariableInput {
                 String::new()
             },
             cursor_position: 0,
+            group: None,
         }
     }
 
@@ -182,6 +187,47 @@ impl VariableInput {
     }
 }
 
+// Destructive changes listed in the plan confirmation, the modal has room for a few only
+const MAX_LISTED_CHANGES: usize = 8;
+
+/// Plan job run for the claim of the builder, applied once its summary is confirmed
+#[derive(Debug, Clone)]
+pub struct ClaimPlan {
+    pub project_id: String,
+    pub region: String,
+    pub changes: JobChanges,
+}
+
+impl ClaimPlan {
+    /// Confirmation message with the change counts of the plan and the resources it deletes
+    /// or replaces
+    pub fn confirmation_message(&self) -> String {
+        let changes = &self.changes;
+        let mut message = format!(
+            "Plan of {} in {}:\n\n{} to add, {} to change, {} to destroy.\n",
+            changes.deployment_id,
+            changes.environment,
+            changes.add,
+            changes.change,
+            changes.destroy
+        );
+        if !changes.destructive_changes.is_empty() {
+            message.push_str("\n⚠️  Resources deleted or replaced:\n");
+            for address in changes.destructive_changes.iter().take(MAX_LISTED_CHANGES) {
+                message.push_str(&format!("  - {}\n", address));
+            }
+            if changes.destructive_changes.len() > MAX_LISTED_CHANGES {
+                message.push_str(&format!(
+                    "  ... and {} more\n",
+                    changes.destructive_changes.len() - MAX_LISTED_CHANGES
+                ));
+            }
+        }
+        message.push_str("\nApply this plan?\n\nPress 'y' to confirm or 'n' to cancel.");
+        message
+    }
+}
+
 /// State for the claim builder view
 #[derive(Debug, Clone)]
 pub struct ClaimBuilderState {
@@ -210,6 +256,9 @@ pub struct ClaimBuilderState {
 
     // Validation
     pub validation_error: Option<String>,
+
+    // Plan waiting for confirmation before it is applied
+    pub plan: Option<ClaimPlan>,
 }
 
 impl ClaimBuilderState {
@@ -230,6 +279,7 @@ impl ClaimBuilderState {
             show_preview: false,
             preview_scroll: 0,
             validation_error: None,
+            plan: None,
         }
     }
 
@@ -277,12 +327,21 @@ impl ClaimBuilderState {
         // For stacks, variables are already in the correct order from tf_variables
         // (grouped by instance like bucket1a__*, bucket2__*)
 
+        // Modules describing their variables in `spec.ui` present them in their groups
+        if let Some(ui) = module.manifest.spec.ui.as_ref().filter(|_| !self.is_stack) {
+            variables.sort_by_key(|var| ui.position(&var.name));
+            for var in &mut variables {
+                var.group = ui.group(&var.name).map(|group| group.name.clone());
+            }
+        }
+
         self.variable_inputs = variables;
 
         self.selected_field_index = 0;
         self.scroll_offset = 0;
         self.show_preview = false;
         self.preview_scroll = 0;
+        self.plan = None;
     }
 
     /// Determines if a variable should be included in the generated YAML
@@ -343,6 +402,7 @@ impl ClaimBuilderState {
         self.scroll_offset = 0;
         self.show_preview = false;
         self.preview_scroll = 0;
+        self.plan = None;
     }
 
     /// Get the total number of fields (2 base fields + variables)
@@ -350,26 +410,68 @@ impl ClaimBuilderState {
         2 + self.variable_inputs.len()
     }
 
-    /// Move to the next field
+    /// Move to the next field, skipping hidden variables
     pub fn next_field(&mut self) {
         // Validate and auto-correct current field before moving
         self.autocorrect_field();
 
-        if self.selected_field_index < self.total_fields() - 1 {
-            self.selected_field_index += 1;
+        if let Some(index) = (self.selected_field_index + 1..self.total_fields())
+            .find(|&index| self.is_field_visible(index))
+        {
+            self.selected_field_index = index;
         }
     }
 
-    /// Move to the previous field
+    /// Move to the previous field, skipping hidden variables
     pub fn previous_field(&mut self) {
         // Validate and auto-correct current field before moving
         self.autocorrect_field();
 
-        if self.selected_field_index > 0 {
-            self.selected_field_index -= 1;
+        if let Some(index) = (0..self.selected_field_index)
+            .rev()
+            .find(|&index| self.is_field_visible(index))
+        {
+            self.selected_field_index = index;
         }
     }
 
+    /// The `spec.ui` of the module, not used for stacks whose variables are grouped by instance
+    fn ui(&self) -> Option<&ModuleUi> {
+        self.source_module
+            .as_ref()
+            .filter(|_| !self.is_stack)
+            .and_then(|module| module.manifest.spec.ui.as_ref())
+    }
+
+    /// Whether the field is shown, variables being hidden while their `visibleWhen` condition
+    /// of the module is not met
+    pub fn is_field_visible(&self, field_index: usize) -> bool {
+        let (Some(ui), Some(var)) = (
+            self.ui(),
+            field_index
+                .checked_sub(2)
+                .and_then(|index| self.variable_inputs.get(index)),
+        ) else {
+            return true;
+        };
+        ui.is_visible(&var.name, |name| self.variable_value(name))
+    }
+
+    /// Value of a variable as it would be deployed: the entered value, else its default
+    fn variable_value(&self, name: &str) -> Option<serde_json::Value> {
+        let var = self.variable_inputs.iter().find(|var| var.name == name)?;
+        if var.user_value.is_empty() {
+            return var
+                .default_value
+                .as_deref()
+                .and_then(|default| serde_json::from_str(default).ok());
+        }
+        Some(
+            serde_json::from_str(&var.user_value)
+                .unwrap_or_else(|_| serde_json::Value::String(var.user_value.clone())),
+        )
+    }
+
     /// Auto-correct the current field value based on its type
     fn autocorrect_field(&mut self) {
         if self.selected_field_index == 0 || self.selected_field_index == 1 {
@@ -562,8 +664,11 @@ impl ClaimBuilderState {
             return Err("Region is required".to_string());
         }
 
-        // Validate all variable inputs
-        for var in &self.variable_inputs {
+        // Validate all variable inputs, hidden ones are left out of the claim
+        for (index, var) in self.variable_inputs.iter().enumerate() {
+            if !self.is_field_visible(index + 2) {
+                continue;
+            }
             if let Err(err) = var.validate_value() {
                 return Err(err);
             }
@@ -629,9 +734,10 @@ impl ClaimBuilderState {
         } else {
             // For modules, keep as snake_case - generate_deployment_claim will handle conversion
             let mut module_vars = serde_json::Map::new();
-            for var in &self.variable_inputs {
-                // Skip variables that haven't been explicitly set or differ from default
-                if !Self::should_include_variable(var) {
+            for (index, var) in self.variable_inputs.iter().enumerate() {
+                // Skip variables that haven't been explicitly set or differ from default,
+                // and the ones hidden by the module
+                if !Self::should_include_variable(var) || !self.is_field_visible(index + 2) {
                     continue;
                 }
 
@@ -669,8 +775,11 @@ impl ClaimBuilderState {
                 interval: "24h".to_string(),
                 auto_remediate: false,
                 webhooks: Vec::new(),
+                schedule: None,
+                blackout_windows: Vec::new(),
             },
             next_drift_check_epoch: 0,
+            last_drift_check_epoch: None,
             has_drifted: false,
             output: serde_json::Value::Null,
             policy_results: Vec::new(),
@@ -682,6 +791,10 @@ impl ClaimBuilderState {
             memory: String::new(),
             reference: String::new(),
             tf_resources: None,
+            module_digest: None,
+            idempotency_key: None,
+            drift_report: None,
+            stack_instance: None,
         };
 
         // Use the existing generate_deployment_claim function
@@ -721,3 +834,38 @@ impl ClaimBuilderState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_confirmation_message() {
+        let mut plan = ClaimPlan {
+            project_id: "123".to_string(),
+            region: "us-west-2".to_string(),
+            changes: JobChanges {
+                deployment_id: "s3bucket/my-bucket".to_string(),
+                environment: "cli/default".to_string(),
+                job_id: "job-1".to_string(),
+                add: 2,
+                change: 1,
+                destroy: 0,
+                destructive_changes: vec![],
+            },
+        };
+        let message = plan.confirmation_message();
+        assert!(message.starts_with(
+            "Plan of s3bucket/my-bucket in cli/default:\n\n2 to add, 1 to change, 0 to destroy.\n"
+        ));
+        assert!(!message.contains("deleted or replaced"));
+
+        plan.changes.destroy = 10;
+        plan.changes.destructive_changes = (0..10)
+            .map(|i| format!("aws_s3_object.file[{}]", i))
+            .collect();
+        let message = plan.confirmation_message();
+        assert!(message.contains("  - aws_s3_object.file[7]\n  ... and 2 more\n"));
+        assert!(!message.contains("aws_s3_object.file[8]"));
+    }
+}
@@ -4,6 +4,9 @@ pub enum FilterType {
     None,
     Project,
     Region,
+    /// Switching the project the TUI works in, followed by `SwitchRegion`
+    SwitchProject,
+    SwitchRegion,
 }
 
 pub struct ModalState {
@@ -66,6 +69,11 @@ impl ModalState {
         self.filter_options = options;
     }
 
+    /// The switcher has no "All" option, unlike the filters
+    pub fn filter_has_all_option(&self) -> bool {
+        matches!(self.filter_type, FilterType::Project | FilterType::Region)
+    }
+
     pub fn close_filter_modal(&mut self) {
         self.showing_filter_modal = false;
         self.filter_type = FilterType::None;
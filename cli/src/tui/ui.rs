@@ -501,6 +501,8 @@ fn render_filter_modal(frame: &mut Frame, area: Rect, app: &App) {
     let title = match app.modal_state.filter_type {
         crate::tui::state::modal_state::FilterType::Project => " Filter by Project ",
         crate::tui::state::modal_state::FilterType::Region => " Filter by Region ",
+        crate::tui::state::modal_state::FilterType::SwitchProject => " Switch Project ",
+        crate::tui::state::modal_state::FilterType::SwitchRegion => " Switch Region ",
         _ => " Filter ",
     };
 
@@ -522,12 +524,14 @@ fn render_filter_modal(frame: &mut Frame, area: Rect, app: &App) {
     }
 
     // Add "All" at the bottom
-    let all_label = match app.modal_state.filter_type {
-        crate::tui::state::modal_state::FilterType::Project => "All Projects",
-        crate::tui::state::modal_state::FilterType::Region => "All Regions",
-        _ => "All",
-    };
-    items.push(ListItem::new(Span::raw(all_label)));
+    if app.modal_state.filter_has_all_option() {
+        let all_label = match app.modal_state.filter_type {
+            crate::tui::state::modal_state::FilterType::Project => "All Projects",
+            crate::tui::state::modal_state::FilterType::Region => "All Regions",
+            _ => "All",
+        };
+        items.push(ListItem::new(Span::raw(all_label)));
+    }
 
     let list = List::new(items)
         .block(block)
@@ -30,6 +30,11 @@ pub enum NavItem {
     Dependencies,
     PolicyResults,
     Logs,
+    Examples,
+    Readme,
+    VersionDiff {
+        previous_version: String,
+    },
 }
 
 impl NavItem {
@@ -109,6 +114,11 @@ impl NavItem {
             NavItem::Dependencies => "🔗 Dependencies".to_string(),
             NavItem::PolicyResults => "📊 Policy Results".to_string(),
             NavItem::Logs => "📝 Logs".to_string(),
+            NavItem::Examples => "💡 Examples".to_string(),
+            NavItem::Readme => "📖 README".to_string(),
+            NavItem::VersionDiff { previous_version } => {
+                format!("🔀 Changes since {}", previous_version)
+            }
         }
     }
 
@@ -143,6 +153,11 @@ impl NavItem {
             NavItem::Dependencies => "Dependencies".to_string(),
             NavItem::PolicyResults => "Policy Results".to_string(),
             NavItem::Logs => "Logs".to_string(),
+            NavItem::Examples => "Examples".to_string(),
+            NavItem::Readme => "README".to_string(),
+            NavItem::VersionDiff { previous_version } => {
+                format!("Changes since {}", previous_version)
+            }
         }
     }
 }
@@ -306,9 +321,71 @@ pub fn build_module_nav_items(module: &env_defs::ModuleResp) -> Vec<NavItem> {
         }
     }
 
+    items.extend(build_module_doc_nav_items(module));
+
+    items
+}
+
+/// Sections after the outputs of a module: its examples, README and the changes since the
+/// previous version, each only when the module has them
+pub fn build_module_doc_nav_items(module: &env_defs::ModuleResp) -> Vec<NavItem> {
+    let mut items = vec![];
+
+    if module
+        .manifest
+        .spec
+        .examples
+        .as_ref()
+        .is_some_and(|examples| !examples.is_empty())
+    {
+        items.push(NavItem::Examples);
+    }
+
+    if module
+        .readme
+        .as_ref()
+        .is_some_and(|readme| !readme.trim().is_empty())
+    {
+        items.push(NavItem::Readme);
+    }
+
+    if let Some(diff) = &module.version_diff {
+        items.push(NavItem::VersionDiff {
+            previous_version: diff.previous_version.clone(),
+        });
+    }
+
     items
 }
 
+/// Rows of the variables table of a module: name, type, default and whether it is required,
+/// with required variables first
+pub fn module_variable_rows(module: &env_defs::ModuleResp) -> Vec<[String; 4]> {
+    let mut variables: Vec<_> = module.tf_variables.iter().collect();
+    variables.sort_by_key(|var| (!var.required(), var.name.clone()));
+    variables
+        .into_iter()
+        .map(|var| {
+            let type_str = match &var._type {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            let default = match &var.default {
+                Some(serde_json::Value::String(s)) => format!("\"{}\"", s),
+                Some(other) => other.to_string(),
+                None => "-".to_string(),
+            };
+            let required = if var.required() { "yes" } else { "no" };
+            [
+                to_camel_case(&var.name),
+                type_str,
+                default,
+                required.to_string(),
+            ]
+        })
+        .collect()
+}
+
 pub fn build_deployment_nav_items(deployment: &env_defs::DeploymentResp) -> Vec<NavItem> {
     let mut items = vec![NavItem::General];
 
@@ -382,4 +459,69 @@ mod tests {
         assert_eq!(grouped.grouped.get("module2").unwrap().len(), 1);
         assert_eq!(grouped.grouped.get(UNGROUPED_KEY).unwrap().len(), 1);
     }
+
+    #[test]
+    fn test_module_nav_items_and_variable_rows() {
+        let mut module = env_defs::ModuleResp {
+            tf_variables: serde_json::from_value(serde_json::json!([
+                { "name": "tags", "type": "map(string)", "default": {} },
+                { "name": "bucket_name", "type": "string" },
+            ]))
+            .unwrap(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            build_module_nav_items(&module),
+            vec![
+                NavItem::General,
+                NavItem::VariablesHeader,
+                NavItem::Variable {
+                    module_name: None,
+                    name: "tags".to_string(),
+                    is_required: false,
+                },
+                NavItem::Variable {
+                    module_name: None,
+                    name: "bucket_name".to_string(),
+                    is_required: true,
+                },
+            ]
+        );
+
+        module.readme = Some("# S3Bucket".to_string());
+        module.version_diff = Some(env_defs::ModuleVersionDiff {
+            added: vec![],
+            changed: vec![],
+            removed: vec![],
+            previous_version: "0.1.0".to_string(),
+        });
+        assert_eq!(
+            build_module_doc_nav_items(&module),
+            vec![
+                NavItem::Readme,
+                NavItem::VersionDiff {
+                    previous_version: "0.1.0".to_string()
+                },
+            ]
+        );
+
+        assert_eq!(
+            module_variable_rows(&module),
+            vec![
+                [
+                    "bucketName".to_string(),
+                    "string".to_string(),
+                    "-".to_string(),
+                    "yes".to_string(),
+                ],
+                [
+                    "tags".to_string(),
+                    "map(string)".to_string(),
+                    "{}".to_string(),
+                    "no".to_string(),
+                ],
+            ]
+        );
+    }
 }
@@ -362,13 +362,35 @@ fn render_enhanced_form_fields(f: &mut Frame, area: Rect, state: &ClaimBuilderSt
     for (idx, var) in state.variable_inputs.iter().enumerate() {
         let global_idx = idx + 2; // 0 = name, 1 = region, 2+ = variables
 
-        if global_idx < scroll_offset {
+        if global_idx < scroll_offset || !state.is_field_visible(global_idx) {
             continue;
         }
         if items.len() >= available_height.saturating_sub(1) {
             break;
         }
 
+        // For modules with variable groups, add a header before the first variable of each group,
+        // and before the variables not in a group that come after them
+        let group = match &var.group {
+            Some(group) => Some(group.as_str()),
+            None if !state.is_stack && !rendered_sections.is_empty() => Some("Other"),
+            None => None,
+        };
+        if let Some(group) = group {
+            if !rendered_sections.contains(group) {
+                if !rendered_sections.is_empty() {
+                    items.push(ListItem::new(Line::from("")));
+                }
+                items.push(ListItem::new(Line::from(vec![Span::styled(
+                    format!("── {} ──", group),
+                    Style::default()
+                        .fg(Color::Magenta)
+                        .add_modifier(Modifier::BOLD),
+                )])));
+                rendered_sections.insert(group.to_string());
+            }
+        }
+
         // For stacks, add section headers for each module instance (only when visible and not already rendered)
         if state.is_stack {
             if let Some((instance_name, _)) = var.name.split_once("__") {
@@ -810,7 +832,7 @@ fn render_preview_mode(f: &mut Frame, area: Rect, state: &ClaimBuilderState) {
                 .fg(Color::Blue)
                 .add_modifier(Modifier::BOLD),
         ),
-        Span::raw(": Run"),
+        Span::raw(": Plan & Apply"),
     ])];
 
     let help = Paragraph::new(help_lines)
@@ -1,7 +1,13 @@
 use env_common::interface::GenericCloudHandler;
-use env_common::logic::{PROJECT_ID, REGION};
-use env_defs::CloudProvider;
-use http_client::{http_get_deployments, is_http_mode_enabled};
+use env_common::logic::{get_drift_report, PROJECT_ID, REGION};
+use env_defs::{
+    CloudProvider, DeploymentResp, InfraChangeRecord, ModuleResp, SanitizedResourceChange,
+    StackInstanceModule,
+};
+use env_utils::stack_instance_modules;
+use http_client::{
+    http_get_change_record, http_get_deployments, http_get_stack_version, is_http_mode_enabled,
+};
 use inquire::{Select, Text};
 use std::collections::HashSet;
 
@@ -40,6 +46,91 @@ pub fn get_environment(environment_arg: &str) -> String {
     }
 }
 
+/// Loads the per-resource drift report of a drifted deployment from its drift change record
+pub async fn fetch_drift_report(
+    deployment: &DeploymentResp,
+) -> anyhow::Result<Option<Vec<SanitizedResourceChange>>> {
+    if !deployment.has_drifted {
+        return Ok(None);
+    }
+    if is_http_mode_enabled() {
+        let value = http_get_change_record(
+            &deployment.project_id,
+            &deployment.region,
+            &deployment.environment,
+            &deployment.deployment_id,
+            &deployment.job_id,
+            "DRIFT",
+        )
+        .await?;
+        let drift_change_record: InfraChangeRecord = serde_json::from_value(value)?;
+        Ok(Some(drift_change_record.resource_changes))
+    } else {
+        let handler =
+            GenericCloudHandler::workload(&deployment.project_id, &deployment.region).await;
+        get_drift_report(&handler, deployment).await
+    }
+}
+
+/// Attaches the drift report to a deployment, leaving it out if it can not be loaded
+pub async fn with_drift_report(mut deployment: DeploymentResp) -> DeploymentResp {
+    match fetch_drift_report(&deployment).await {
+        Ok(drift_report) => deployment.drift_report = drift_report,
+        Err(e) => log::warn!(
+            "Failed to load drift report for {} (job {}): {}",
+            deployment.deployment_id,
+            deployment.job_id,
+            e
+        ),
+    }
+    deployment
+}
+
+/// Loads the modules of a stack deployment from the `stack_data` of its stack version
+pub async fn fetch_stack_instance(
+    deployment: &DeploymentResp,
+) -> anyhow::Result<Option<Vec<StackInstanceModule>>> {
+    if deployment.module_type != "stack" {
+        return Ok(None);
+    }
+    let stack: Option<ModuleResp> = if is_http_mode_enabled() {
+        Some(
+            http_get_stack_version(
+                &deployment.module_track,
+                &deployment.module,
+                &deployment.module_version,
+            )
+            .await?,
+        )
+    } else {
+        current_region_handler()
+            .await
+            .get_stack_version(
+                &deployment.module,
+                &deployment.module_track,
+                &deployment.module_version,
+            )
+            .await?
+    };
+    Ok(stack.map(|stack| stack_instance_modules(&stack, &deployment.output)))
+}
+
+/// Attaches the modules of a stack deployment, unless the API already included them
+pub async fn with_stack_instance(mut deployment: DeploymentResp) -> DeploymentResp {
+    if deployment.stack_instance.is_some() {
+        return deployment;
+    }
+    match fetch_stack_instance(&deployment).await {
+        Ok(stack_instance) => deployment.stack_instance = stack_instance,
+        Err(e) => log::warn!(
+            "Failed to load the modules of stack deployment {}: {}",
+            deployment.deployment_id,
+            e
+        ),
+    }
+    deployment
+}
+
 pub async fn current_region_handler() -> GenericCloudHandler {
     // GenericCloudHandler::default() will automatically check for HTTP mode
     // and skip AWS SDK initialization if enabled
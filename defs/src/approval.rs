@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+/// A job held until enough approvers signed off, as required by the approval policies of the project
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ApprovalRequest {
+    pub job_id: String,
+    pub deployment_id: String,
+    pub environment: String,
+    pub project_id: String,
+    pub region: String,
+    pub command: String,
+    pub module: String,
+    pub module_version: String,
+    pub initiated_by: String,
+    pub required_approvals: u32,
+    pub epoch: u128,
+    pub decisions: Vec<ApprovalDecision>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ApprovalDecision {
+    pub approver: String,
+    pub approved: bool,
+    #[serde(default)]
+    pub comment: String,
+    pub epoch: u128,
+}
+
+impl ApprovalRequest {
+    /// Users who approved the job so far, each counted once
+    pub fn approvers(&self) -> Vec<String> {
+        let mut approvers: Vec<String> = Vec::new();
+        for decision in self.decisions.iter().filter(|d| d.approved) {
+            if !approvers.contains(&decision.approver) {
+                approvers.push(decision.approver.clone());
+            }
+        }
+        approvers
+    }
+
+    pub fn is_rejected(&self) -> bool {
+        self.decisions.iter().any(|d| !d.approved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decision(approver: &str, approved: bool) -> ApprovalDecision {
+        ApprovalDecision {
+            approver: approver.to_string(),
+            approved,
+            comment: String::new(),
+            epoch: 0,
+        }
+    }
+
+    #[test]
+    fn test_approvers_counted_once() {
+        let request = ApprovalRequest {
+            job_id: "pending-approval-1".to_string(),
+            deployment_id: "s3bucket/my-bucket".to_string(),
+            environment: "cli/prod".to_string(),
+            project_id: "123".to_string(),
+            region: "us-west-2".to_string(),
+            command: "apply".to_string(),
+            module: "s3bucket".to_string(),
+            module_version: "0.1.0".to_string(),
+            initiated_by: "carol".to_string(),
+            required_approvals: 2,
+            epoch: 0,
+            decisions: vec![decision("alice", true), decision("alice", true)],
+        };
+        assert_eq!(request.approvers(), vec!["alice".to_string()]);
+        assert!(!request.is_rejected());
+
+        let request = ApprovalRequest {
+            decisions: vec![decision("alice", true), decision("bob", false)],
+            ..request
+        };
+        assert!(request.is_rejected());
+    }
+}
@@ -2,8 +2,8 @@ use std::{future::Future, pin::Pin};
 
 use crate::{
     deployment::JobStatus, Dependent, DeploymentResp, EventData, GenericFunctionResponse,
-    InfraChangeRecord, LogData, ModuleResp, NotificationData, PolicyResp, ProjectData,
-    ProviderResp,
+    InfraChangeRecord, JobQueueEntry, JobStats, LogData, ModuleResp, NotificationData,
+    PolicyPackResp, PolicyResp, ProjectData, ProviderResp,
 };
 
 use async_trait::async_trait;
@@ -159,8 +159,15 @@ pub trait CloudProvider: Send + Sync {
         environment: &str,
     ) -> Result<Vec<Dependent>, anyhow::Error>;
     async fn get_deployments_to_driftcheck(&self) -> Result<Vec<DeploymentResp>, anyhow::Error>;
+    /// Queued and running jobs of the project in the region, kept when the project limits its
+    /// concurrent jobs
+    async fn get_job_queue(&self) -> Result<Vec<JobQueueEntry>, anyhow::Error>;
+    /// Duration and peak memory of the finished jobs of the module in the region
+    async fn get_job_stats(&self, module: &str) -> Result<Vec<JobStats>, anyhow::Error>;
     async fn get_all_projects(&self) -> Result<Vec<ProjectData>, anyhow::Error>;
     async fn get_current_project(&self) -> Result<ProjectData, anyhow::Error>;
+    /// Items in the config table under the partition `kind`, e.g. `TRACKS`
+    async fn get_config_items(&self, kind: &str) -> Result<Vec<Value>, anyhow::Error>;
     // Event
     async fn get_events(
         &self,
@@ -172,6 +179,8 @@ pub trait CloudProvider: Send + Sync {
         start_epoch: u128,
         end_epoch: u128,
     ) -> Result<Vec<EventData>, anyhow::Error>;
+    /// Raw event items in the region from before `end_epoch`, including their keys
+    async fn get_event_items_before(&self, end_epoch: u128) -> Result<Vec<Value>, anyhow::Error>;
     // Change record
     async fn get_change_record(
         &self,
@@ -194,11 +203,34 @@ pub trait CloudProvider: Send + Sync {
         environment: &str,
         version: &str,
     ) -> Result<PolicyResp, anyhow::Error>;
+    async fn get_newest_policy_pack_version(
+        &self,
+        policy_pack: &str,
+        environment: &str,
+    ) -> Result<PolicyPackResp, anyhow::Error>;
+    async fn get_all_policy_packs(
+        &self,
+        environment: &str,
+    ) -> Result<Vec<PolicyPackResp>, anyhow::Error>;
     async fn get_environment_variables(&self) -> Result<serde_json::Value, anyhow::Error>;
+    async fn read_state_file(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+    ) -> Result<Vec<u8>, anyhow::Error>;
     async fn download_state_file(
         &self,
         environment: &str,
         deployment_id: &str,
         output: Option<String>,
     ) -> Result<(), anyhow::Error>;
+    /// Replaces the state file of a deployment, used when importing existing infrastructure
+    async fn write_state_file(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+        data: &[u8],
+    ) -> Result<(), anyhow::Error>;
+    /// Reads a state file from storage outside of InfraWeave, such as `s3://bucket/key`
+    async fn read_state_file_at(&self, address: &str) -> Result<Vec<u8>, anyhow::Error>;
 }
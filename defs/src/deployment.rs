@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::fmt;
 
+use crate::{ArtifactPolicyViolation, OciArtifactSet};
+
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum DeploymentStatus {
@@ -37,6 +40,12 @@ pub enum DeploymentStatus {
     HasDependants,
     #[serde(rename = "failed_graph")]
     FailedGraph,
+    #[serde(rename = "pending_approval")]
+    PendingApproval,
+    #[serde(rename = "rejected")]
+    Rejected,
+    #[serde(rename = "queued")]
+    Queued,
 }
 
 impl fmt::Display for DeploymentStatus {
@@ -58,6 +67,9 @@ impl fmt::Display for DeploymentStatus {
             DeploymentStatus::WaitingOnDependency => write!(f, "waiting-on-dependency"),
             DeploymentStatus::HasDependants => write!(f, "has-dependants"),
             DeploymentStatus::FailedGraph => write!(f, "failed_graph"),
+            DeploymentStatus::PendingApproval => write!(f, "pending_approval"),
+            DeploymentStatus::Rejected => write!(f, "rejected"),
+            DeploymentStatus::Queued => write!(f, "queued"),
         }
     }
 }
@@ -81,6 +93,7 @@ impl DeploymentStatus {
                 | DeploymentStatus::WaitingOnDependency
                 | DeploymentStatus::HasDependants
                 | DeploymentStatus::FailedGraph
+                | DeploymentStatus::Rejected
         )
     }
 
@@ -187,6 +200,34 @@ pub struct DeploymentSpec {
     pub dependencies: Option<Vec<DependencySpec>>,
     #[serde(rename = "driftDetection")]
     pub drift_detection: Option<DriftDetection>,
+    /// Terraform variable file (tfvars) relative to the claim, merged into the variables by the CLI and GitOps
+    #[serde(rename = "varFile", skip_serializing_if = "Option::is_none")]
+    pub var_file: Option<String>,
+    /// Resource addresses to limit plan and apply to, passed to Terraform as `-target` flags
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+    /// Names of claims in the same stack to create before this one, for ordering without referencing their outputs
+    #[serde(rename = "dependsOn", default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Kubernetes object the operator writes the outputs to after a successful apply
+    #[serde(rename = "outputsTo", default, skip_serializing_if = "Option::is_none")]
+    pub outputs_to: Option<OutputsTo>,
+}
+
+/// Secret or ConfigMap in the namespace of a claim holding outputs of its deployment
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OutputsTo {
+    pub kind: OutputsToKind,
+    pub name: String,
+    /// Names of the outputs to write, all outputs if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum OutputsToKind {
+    Secret,
+    ConfigMap,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -194,6 +235,31 @@ pub struct DependencySpec {
     #[serde(rename = "deploymentId")]
     pub deployment_id: String,
     pub environment: String,
+    #[serde(rename = "onOutputChange", default)]
+    pub on_output_change: Option<DependencyTrigger>,
+}
+
+/// What to do with a dependent deployment when the outputs of its dependency change after an apply
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DependencyTrigger {
+    #[serde(rename = "auto-apply")]
+    AutoApply,
+    #[default]
+    #[serde(rename = "plan-only")]
+    PlanOnly,
+    #[serde(rename = "ignore")]
+    Ignore,
+}
+
+impl fmt::Display for DependencyTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencyTrigger::AutoApply => write!(f, "auto-apply"),
+            DependencyTrigger::PlanOnly => write!(f, "plan-only"),
+            DependencyTrigger::Ignore => write!(f, "ignore"),
+        }
+    }
 }
 
 // Manifest above (camelCase), Database data below (snake_case)
@@ -213,6 +279,9 @@ pub struct DeploymentResp {
     pub module_track: String,
     pub drift_detection: DriftDetection,
     pub next_drift_check_epoch: i128,
+    /// When the latest drift check of the deployment finished
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_drift_check_epoch: Option<u128>,
     #[serde(deserialize_with = "deserialize_bool_from_int")]
     pub has_drifted: bool,
     pub variables: Value,
@@ -227,6 +296,43 @@ pub struct DeploymentResp {
     pub memory: String,
     pub reference: String,
     pub tf_resources: Option<Vec<String>>,
+    /// Digest of the OCI module artifact the deployment last ran with, used for digest pinning
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub module_digest: Option<String>,
+    /// Idempotency key of the submission that started the latest job
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// Resources that drifted in the latest drift check, read from its drift change record.
+    /// Not stored on the deployment itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Vec<Object>>))]
+    pub drift_report: Option<Vec<crate::SanitizedResourceChange>>,
+    /// Modules of the stack version a stack deployment was deployed with, read from its
+    /// `stack_data`. Not stored on the deployment itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stack_instance: Option<Vec<crate::StackInstanceModule>>,
+}
+
+/// Value that replaces sensitive terraform outputs when they are stored or returned masked
+pub const SANITIZED_OUTPUT_VALUE: &str = "(output sanitized)";
+
+/// Replaces the values of the outputs marked `sensitive` in the output of `terraform output -json`
+pub fn sanitize_terraform_output(mut output: Value) -> Value {
+    if let Some(map) = output.as_object_mut() {
+        for (_, v) in map.iter_mut() {
+            if let Some(val_map) = v.as_object_mut() {
+                if let Some(sensitive) = val_map.get("sensitive") {
+                    if sensitive.as_bool().unwrap_or(false) {
+                        val_map.insert(
+                            "value".to_string(),
+                            Value::String(SANITIZED_OUTPUT_VALUE.to_string()),
+                        );
+                    }
+                }
+            }
+        }
+    }
+    output
 }
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -243,6 +349,8 @@ pub struct Dependency {
     pub region: String,
     pub deployment_id: String,
     pub environment: String,
+    #[serde(default)]
+    pub on_output_change: DependencyTrigger,
 }
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -252,6 +360,8 @@ pub struct Dependent {
     pub region: String,
     pub dependent_id: String,
     pub environment: String,
+    #[serde(default)]
+    pub on_output_change: DependencyTrigger,
 }
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -272,6 +382,273 @@ pub struct ProjectData {
     pub description: String,
     pub regions: Vec<String>,
     pub repositories: Vec<RepositoryData>,
+    #[serde(default)]
+    pub settings: ProjectSettings,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ProjectSettings {
+    #[serde(default)]
+    pub approval_policies: Vec<ApprovalPolicy>,
+    #[serde(default)]
+    pub artifact_verification: ArtifactVerificationPolicy,
+    #[serde(default)]
+    pub aws_access: AwsAccessSettings,
+    #[serde(default)]
+    pub retention: RetentionSettings,
+    #[serde(default)]
+    pub runner_sizing: RunnerSizingSettings,
+    #[serde(default)]
+    pub runner_storage: RunnerStorageSettings,
+    #[serde(default)]
+    pub validation_webhooks: Vec<ValidationWebhook>,
+    /// Jobs the project may run at the same time in a region, further jobs wait in the job queue.
+    /// Unlimited when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_jobs: Option<u32>,
+}
+
+/// How long change records and events are kept before `admin prune` removes them
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RetentionSettings {
+    #[serde(default = "default_retention_days")]
+    pub days: u32,
+    /// Whether pruned items are first written to storage as compressed JSON
+    #[serde(default = "default_retention_archive")]
+    pub archive: bool,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        RetentionSettings {
+            days: default_retention_days(),
+            archive: default_retention_archive(),
+        }
+    }
+}
+
+fn default_retention_days() -> u32 {
+    90
+}
+
+fn default_retention_archive() -> bool {
+    true
+}
+
+/// How cpu and memory of runner jobs are chosen for modules that don't set them in their manifest
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RunnerSizingSettings {
+    /// Size jobs from the p95 duration and peak memory of the recorded jobs of their module
+    /// instead of the static defaults
+    #[serde(default)]
+    pub auto: bool,
+    /// Recorded jobs a module needs before it is sized automatically
+    #[serde(default = "default_runner_sizing_min_samples")]
+    pub min_samples: u32,
+}
+
+impl Default for RunnerSizingSettings {
+    fn default() -> Self {
+        RunnerSizingSettings {
+            auto: false,
+            min_samples: default_runner_sizing_min_samples(),
+        }
+    }
+}
+
+fn default_runner_sizing_min_samples() -> u32 {
+    5
+}
+
+/// Mounted volume (EFS or Azure Files) the runner keeps its working directories and provider
+/// mirror on, for modules that don't fit in the ephemeral storage of the container
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RunnerStorageSettings {
+    /// Path the volume is mounted at in the runner container, e.g. `/mnt/infraweave`.
+    /// Ephemeral storage is used when unset
+    #[serde(default)]
+    pub mount_path: Option<String>,
+}
+
+/// External endpoint that claims are posted to for validation before they are run
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ValidationWebhook {
+    pub url: String,
+    #[serde(default = "default_validation_webhook_timeout")]
+    pub timeout_seconds: u64,
+    /// What happens to the claim when the webhook can't be reached or gives no verdict
+    #[serde(default)]
+    pub failure_mode: ValidationWebhookFailureMode,
+}
+
+fn default_validation_webhook_timeout() -> u64 {
+    10
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationWebhookFailureMode {
+    /// Reject the claim
+    #[default]
+    Fail,
+    /// Continue as if the webhook allowed the claim
+    Ignore,
+}
+
+/// How the platform assumes the workload role in an AWS project
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AwsAccessSettings {
+    /// Intermediary roles assumed in order before the workload role, e.g. hub and spoke roles
+    #[serde(default)]
+    pub role_chain: Vec<AssumeRoleStep>,
+    /// External ID required by the trust policy of the workload role
+    #[serde(default)]
+    pub external_id: Option<String>,
+    /// Session tags passed when assuming the workload role
+    #[serde(default)]
+    pub session_tags: BTreeMap<String, String>,
+}
+
+/// A role assumed as part of a role chain
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AssumeRoleStep {
+    pub role_arn: String,
+    #[serde(default)]
+    pub external_id: Option<String>,
+    #[serde(default)]
+    pub session_tags: BTreeMap<String, String>,
+}
+
+/// Requirements the runner enforces on OCI module artifacts before using them
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ArtifactVerificationPolicy {
+    /// Accept artifacts without a signature. Unsigned artifacts fail verification unless a
+    /// project opts out with this
+    #[serde(default)]
+    pub allow_unsigned: bool,
+    /// Fail when the artifact digest differs from the digest recorded on the deployment
+    /// for the same module version
+    #[serde(default)]
+    pub require_digest_pinning: bool,
+    /// Registries or repository prefixes artifacts may come from, e.g. `ghcr.io/my-org`.
+    /// An empty list allows all registries
+    #[serde(default)]
+    pub allowed_registries: Vec<String>,
+}
+
+impl ArtifactVerificationPolicy {
+    /// Returns whether an artifact from `repository` (e.g. `ghcr.io/my-org/s3bucket`) is allowed
+    pub fn is_registry_allowed(&self, repository: Option<&str>) -> bool {
+        if self.allowed_registries.is_empty() {
+            return true;
+        }
+        let Some(repository) = repository else {
+            return false;
+        };
+        self.allowed_registries.iter().any(|allowed| {
+            let allowed = allowed.trim_end_matches('/');
+            repository == allowed || repository.starts_with(&format!("{}/", allowed))
+        })
+    }
+
+    /// Checks `artifact_set` against the policy. `pinned_digest` is the digest recorded on the
+    /// deployment for the same module version, if any
+    pub fn check(
+        &self,
+        artifact_set: &OciArtifactSet,
+        pinned_digest: Option<&str>,
+    ) -> Result<(), ArtifactPolicyViolation> {
+        if !self.is_registry_allowed(artifact_set.repository.as_deref()) {
+            return Err(ArtifactPolicyViolation(format!(
+                "repository {} is not in the allowed registries {:?}",
+                artifact_set.repository.as_deref().unwrap_or("<unknown>"),
+                self.allowed_registries
+            )));
+        }
+        if !self.allow_unsigned && artifact_set.tag_signature.is_none() {
+            return Err(ArtifactPolicyViolation(format!(
+                "a signature is required but artifact {} is not signed",
+                artifact_set.digest
+            )));
+        }
+        if self.require_digest_pinning {
+            if let Some(pinned_digest) = pinned_digest {
+                if pinned_digest != artifact_set.digest {
+                    return Err(ArtifactPolicyViolation(format!(
+                        "artifact digest {} does not match the digest {} pinned on the deployment",
+                        artifact_set.digest, pinned_digest
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ProjectSettings {
+    /// Returns the number of approvals required before changes are applied to `environment`.
+    /// The strictest matching policy wins, and zero means the job is auto-approved.
+    pub fn required_approvals(&self, environment: &str) -> u32 {
+        self.approval_policies
+            .iter()
+            .filter(|policy| policy.matches(environment))
+            .map(|policy| policy.required_approvals)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether `approver` may approve changes to `environment`. Anyone may unless a matching
+    /// policy lists its approvers, in which case the approver must be listed by one of them
+    pub fn is_authorized_approver(&self, environment: &str, approver: &str) -> bool {
+        let mut approvers = self
+            .approval_policies
+            .iter()
+            .filter(|policy| policy.matches(environment))
+            .flat_map(|policy| policy.approvers.iter())
+            .peekable();
+        approvers.peek().is_none() || approvers.any(|a| a == approver)
+    }
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApprovalPolicy {
+    /// Full environment (`github-org-repo/prod`) or namespace (`prod`), a trailing `*` matches any suffix
+    pub environment: String,
+    pub required_approvals: u32,
+    /// Users allowed to approve, anyone except the one who started the job if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approvers: Vec<String>,
+}
+
+impl ApprovalPolicy {
+    fn matches(&self, environment: &str) -> bool {
+        environment_matches(&self.environment, environment)
+    }
+}
+
+/// Whether `environment` matches `pattern`, either as the full environment (`github-org-repo/prod`)
+/// or its namespace (`prod`), where a trailing `*` in the pattern matches any suffix
+pub fn environment_matches(pattern: &str, environment: &str) -> bool {
+    let namespace = environment
+        .split_once('/')
+        .map(|(_, namespace)| namespace)
+        .unwrap_or(environment);
+    [environment, namespace]
+        .iter()
+        .any(|candidate| match pattern.strip_suffix('*') {
+            Some(prefix) => candidate.starts_with(prefix),
+            None => *candidate == pattern,
+        })
 }
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -289,6 +666,30 @@ pub struct DriftDetection {
 
     #[serde(default = "default_drift_detection_empty_list")]
     pub webhooks: Vec<Webhook>,
+
+    /// Cron expression (`minute hour day-of-month month day-of-week`, in UTC) of the drift
+    /// checks, used instead of `interval` when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+
+    /// Windows in which no drift checks are started, a check that falls into one is moved to
+    /// its end
+    #[serde(
+        rename = "blackoutWindows",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub blackout_windows: Vec<BlackoutWindow>,
+}
+
+/// Recurring window, starting at every match of `schedule` and lasting `duration`
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BlackoutWindow {
+    /// Cron expression of the start of the window, in UTC, e.g. `0 22 * * 5`
+    pub schedule: String,
+    /// Length of the window, e.g. `56h`
+    pub duration: String,
 }
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -296,6 +697,78 @@ pub struct DriftDetection {
 pub struct Webhook {
     pub url: Option<String>,
     // TODO: Add alias to provide option to avoid having to provide sensitive url in the config
+    /// Events the webhook is called for, only drift when not set
+    #[serde(default = "default_webhook_events")]
+    pub events: Vec<WebhookEvent>,
+    /// Environment variable of the runner holding the secret the payload is signed with, sent as
+    /// `X-Infraweave-Signature: sha256=<hmac>`
+    #[serde(rename = "secretEnv", default, skip_serializing_if = "Option::is_none")]
+    pub secret_env: Option<String>,
+    /// Body to post instead of the default JSON payload, where `{{field}}` is replaced by the
+    /// field of the payload, e.g. `{"text": "{{deployment_id}} {{event}}"}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Attempts before the delivery is recorded as dead-lettered
+    #[serde(rename = "maxAttempts", default = "default_webhook_max_attempts")]
+    pub max_attempts: u32,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookEvent {
+    ApplyStarted,
+    ApplySucceeded,
+    ApplyFailed,
+    DriftDetected,
+}
+
+impl std::fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let event = match self {
+            WebhookEvent::ApplyStarted => "applyStarted",
+            WebhookEvent::ApplySucceeded => "applySucceeded",
+            WebhookEvent::ApplyFailed => "applyFailed",
+            WebhookEvent::DriftDetected => "driftDetected",
+        };
+        write!(f, "{}", event)
+    }
+}
+
+fn default_webhook_events() -> Vec<WebhookEvent> {
+    vec![WebhookEvent::DriftDetected]
+}
+
+fn default_webhook_max_attempts() -> u32 {
+    3
+}
+
+/// Outcome of posting an event to a webhook, recorded as a `webhook_delivery` event of the deployment
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookDelivery {
+    pub url: String,
+    pub event: WebhookEvent,
+    pub job_id: String,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: u32,
+    /// HTTP status of the last attempt, if the webhook responded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_status: Option<u16>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub error: String,
+    /// Body that was posted, kept so a dead-lettered delivery can be replayed
+    pub payload: String,
+    pub epoch: u128,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookDeliveryStatus {
+    Delivered,
+    /// Every attempt failed
+    DeadLetter,
 }
 
 fn default_drift_detection_false() -> bool {
@@ -311,3 +784,115 @@ pub const DEFAULT_DRIFT_DETECTION_INTERVAL: &str = "15m"; // Also used in CLI, h
 fn default_drift_detection_interval() -> String {
     DEFAULT_DRIFT_DETECTION_INTERVAL.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_terraform_output() {
+        let output = serde_json::json!({
+            "resource_name": {
+                "sensitive": false,
+                "type": "string",
+                "value": "some-name-here"
+            },
+            "secret_password": {
+                "sensitive": true,
+                "type": "string",
+                "value": "this_is_supersecret"
+            }
+        });
+
+        let sanitized = sanitize_terraform_output(output);
+
+        assert_eq!(sanitized["resource_name"]["value"], "some-name-here");
+        assert_eq!(sanitized["secret_password"]["value"], "(output sanitized)");
+    }
+
+    fn policy(environment: &str, required_approvals: u32) -> ApprovalPolicy {
+        ApprovalPolicy {
+            environment: environment.to_string(),
+            required_approvals,
+            approvers: vec![],
+        }
+    }
+
+    #[test]
+    fn test_required_approvals_per_environment() {
+        let settings = ProjectSettings {
+            approval_policies: vec![
+                policy("prod", 2),
+                policy("staging", 1),
+                policy("github-org-repo/prod-*", 3),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(settings.required_approvals("github-org-repo/prod"), 2);
+        assert_eq!(settings.required_approvals("cli/staging"), 1);
+        assert_eq!(settings.required_approvals("github-org-repo/prod-eu"), 3);
+        assert_eq!(settings.required_approvals("gitlab-org-repo/prod-eu"), 0);
+        assert_eq!(settings.required_approvals("cli/dev"), 0);
+        assert_eq!(ProjectSettings::default().required_approvals("prod"), 0);
+    }
+
+    #[test]
+    fn test_authorized_approvers() {
+        let settings = ProjectSettings {
+            approval_policies: vec![
+                ApprovalPolicy {
+                    approvers: vec!["alice".to_string()],
+                    ..policy("prod", 1)
+                },
+                policy("staging", 1),
+            ],
+            ..Default::default()
+        };
+
+        assert!(settings.is_authorized_approver("cli/prod", "alice"));
+        assert!(!settings.is_authorized_approver("cli/prod", "bob"));
+        assert!(settings.is_authorized_approver("cli/staging", "bob"));
+    }
+
+    #[test]
+    fn test_artifact_verification_allowed_registries() {
+        let policy = ArtifactVerificationPolicy {
+            allowed_registries: vec!["ghcr.io/my-org/".to_string()],
+            ..Default::default()
+        };
+
+        assert!(policy.is_registry_allowed(Some("ghcr.io/my-org/s3bucket")));
+        assert!(!policy.is_registry_allowed(Some("ghcr.io/my-org-fork/s3bucket")));
+        assert!(!policy.is_registry_allowed(Some("docker.io/my-org/s3bucket")));
+        assert!(!policy.is_registry_allowed(None));
+        assert!(ArtifactVerificationPolicy::default().is_registry_allowed(None));
+    }
+
+    #[test]
+    fn test_artifact_verification_check() {
+        let artifact_set = OciArtifactSet {
+            oci_artifact_path: "oci-artifacts/".to_string(),
+            digest: "sha256:abc".to_string(),
+            repository: Some("ghcr.io/my-org/s3bucket".to_string()),
+            tag_main: "s3bucket-0.1.0".to_string(),
+            tag_signature: None,
+            tag_attestation: Some("sha256-abc.att".to_string()),
+            tag_sbom: None,
+            platforms: vec![],
+        };
+        let policy = ArtifactVerificationPolicy {
+            allow_unsigned: true,
+            require_digest_pinning: true,
+            ..Default::default()
+        };
+
+        assert!(policy.check(&artifact_set, None).is_ok());
+        assert!(policy.check(&artifact_set, Some("sha256:abc")).is_ok());
+        assert!(policy.check(&artifact_set, Some("sha256:def")).is_err());
+
+        assert!(ArtifactVerificationPolicy::default()
+            .check(&artifact_set, None)
+            .is_err());
+    }
+}
@@ -19,4 +19,220 @@ pub enum CloudHandlerError {
 
     #[error("A job for this deployment is already in progress: {0}")]
     JobAlreadyInProgress(String),
+
+    #[error("The requested resource was not found: {0}")]
+    NotFound(String),
+
+    #[error("You are not allowed to make this request: {0}")]
+    AccessDenied(String),
+
+    #[error("The request was throttled by the cloud provider: {0}")]
+    Throttled(String),
+
+    #[error("The request conflicts with the current state: {0}")]
+    Conflict(String),
+
+    #[error("The request is invalid: {0}")]
+    Validation(String),
+
+    #[error("The cloud provider is temporarily unavailable: {0}")]
+    Transient(String),
+}
+
+type ErrorVariant = fn(String) -> CloudHandlerError;
+
+// Error codes and messages of AWS and Azure, matched case-insensitively. Checked in order so
+// that e.g. a throttled request is not classified by a "not found" in its message
+const ERROR_MARKERS: &[(&[&str], ErrorVariant)] = &[
+    (
+        &[
+            "throttl",
+            "toomanyrequests",
+            "too many requests",
+            "provisionedthroughputexceeded",
+            "requestlimitexceeded",
+            "request rate is large",
+            "slowdown",
+        ],
+        CloudHandlerError::Throttled,
+    ),
+    (
+        &[
+            "accessdenied",
+            "access denied",
+            "unauthorizedoperation",
+            "authorizationfailed",
+            "authorizationpermissionmismatch",
+            "forbidden",
+            "not authorized",
+        ],
+        CloudHandlerError::AccessDenied,
+    ),
+    (
+        &[
+            "conditionalcheckfailed",
+            "transactionconflict",
+            "resourceconflict",
+            "resourceinuse",
+            "conflict",
+            "already exists",
+        ],
+        CloudHandlerError::Conflict,
+    ),
+    (
+        &[
+            "resourcenotfound",
+            "nosuchkey",
+            "nosuchbucket",
+            "notfound",
+            "not found",
+        ],
+        CloudHandlerError::NotFound,
+    ),
+    (
+        &[
+            "validationexception",
+            "validationerror",
+            "invalidparameter",
+            "invalidrequest",
+            "badrequest",
+        ],
+        CloudHandlerError::Validation,
+    ),
+    (
+        &[
+            "serviceunavailable",
+            "service unavailable",
+            "internalservererror",
+            "internal server error",
+            "timed out",
+            "timeout",
+            "connection reset",
+        ],
+        CloudHandlerError::Transient,
+    ),
+];
+
+impl CloudHandlerError {
+    /// Classifies an error message of a cloud provider SDK or API function, e.g.
+    /// `AccessDeniedException: User is not authorized`, falling back to `OtherError`
+    pub fn from_message(message: String) -> Self {
+        let lowercase = message.to_lowercase();
+        for (markers, variant) in ERROR_MARKERS {
+            if markers.iter().any(|marker| lowercase.contains(marker)) {
+                return variant(message);
+            }
+        }
+        CloudHandlerError::OtherError(message)
+    }
+
+    /// Classifies an error response of an API function by its HTTP status code
+    pub fn from_status(status: u16, message: String) -> Self {
+        match status {
+            400 | 422 => CloudHandlerError::Validation(message),
+            401 => CloudHandlerError::Unauthenticated(message),
+            403 => CloudHandlerError::AccessDenied(message),
+            404 => CloudHandlerError::NotFound(message),
+            409 | 412 => CloudHandlerError::Conflict(message),
+            429 => CloudHandlerError::Throttled(message),
+            502..=504 => CloudHandlerError::Transient(message),
+            _ => CloudHandlerError::from_message(message),
+        }
+    }
+
+    /// Whether the same request may succeed when retried later
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            CloudHandlerError::NoAvailableRunner()
+                | CloudHandlerError::Throttled(_)
+                | CloudHandlerError::Transient(_)
+        )
+    }
+
+    /// Whether the caller lacks credentials or permissions for the request
+    pub fn is_permission_error(&self) -> bool {
+        matches!(
+            self,
+            CloudHandlerError::Unauthenticated(_) | CloudHandlerError::AccessDenied(_)
+        )
+    }
+
+    /// HTTP status code to answer a request failing with this error
+    pub fn status_code(&self) -> u16 {
+        match self {
+            CloudHandlerError::Validation(_) => 400,
+            CloudHandlerError::Unauthenticated(_) => 401,
+            CloudHandlerError::AccessDenied(_) => 403,
+            CloudHandlerError::NotFound(_) => 404,
+            CloudHandlerError::Conflict(_) | CloudHandlerError::JobAlreadyInProgress(_) => 409,
+            CloudHandlerError::Throttled(_) => 429,
+            CloudHandlerError::NoAvailableRunner() | CloudHandlerError::Transient(_) => 503,
+            CloudHandlerError::MissingPayload()
+            | CloudHandlerError::OtherError(_)
+            | CloudHandlerError::MissingEnvironment() => 500,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Artifact verification policy not met: {0}")]
+pub struct ArtifactPolicyViolation(pub String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_message() {
+        let classify = |message: &str| CloudHandlerError::from_message(message.to_string());
+        assert!(matches!(
+            classify("ThrottlingException: Rate exceeded"),
+            CloudHandlerError::Throttled(_)
+        ));
+        assert!(matches!(
+            classify("AccessDeniedException: User is not authorized to perform dynamodb:Query"),
+            CloudHandlerError::AccessDenied(_)
+        ));
+        assert!(matches!(
+            classify("ConditionalCheckFailedException: The conditional request failed"),
+            CloudHandlerError::Conflict(_)
+        ));
+        assert!(matches!(
+            classify("ResourceNotFoundException: Requested resource not found"),
+            CloudHandlerError::NotFound(_)
+        ));
+        assert!(matches!(
+            classify("ValidationException: One or more parameter values were invalid"),
+            CloudHandlerError::Validation(_)
+        ));
+        assert!(matches!(
+            classify("dispatch failure: connection reset by peer"),
+            CloudHandlerError::Transient(_)
+        ));
+        assert!(matches!(
+            classify("Failed to parse response"),
+            CloudHandlerError::OtherError(_)
+        ));
+    }
+
+    #[test]
+    fn test_classification() {
+        let throttled = CloudHandlerError::from_status(429, "slow down".to_string());
+        assert!(throttled.is_retryable());
+        assert!(!throttled.is_permission_error());
+        assert_eq!(throttled.status_code(), 429);
+
+        let forbidden = CloudHandlerError::from_status(403, "no".to_string());
+        assert!(forbidden.is_permission_error());
+        assert!(!forbidden.is_retryable());
+        assert_eq!(forbidden.status_code(), 403);
+
+        let unknown = CloudHandlerError::from_status(500, "Request rate is large".to_string());
+        assert!(matches!(unknown, CloudHandlerError::Throttled(_)));
+        assert_eq!(
+            CloudHandlerError::from_status(404, "gone".to_string()).status_code(),
+            404
+        );
+    }
 }
@@ -40,3 +40,55 @@ pub struct EventData {
     pub initiated_by: String,
     pub event_duration: u128,
 }
+
+/// Kind of an entry of the audit log. Stored events are freeform, their type is derived from the
+/// command and the status of the event
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEventType {
+    /// A claim was submitted, or held until it is approved
+    ClaimSubmitted,
+    PlanStarted,
+    /// The plan or apply was stopped by a policy
+    PolicyViolated,
+    ApplyFinished,
+    /// A drift check found changes made outside of InfraWeave
+    DriftDetected,
+    Destroyed,
+    ApprovalGranted,
+    ApprovalRejected,
+    /// The job failed for another reason than a policy
+    JobFailed,
+}
+
+/// Entry of the audit log, as exported for compliance archiving
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct AuditEvent {
+    pub event_type: AuditEventType,
+    pub timestamp: String,
+    pub epoch: u128,
+    /// Identity of the user or role that submitted the job, or decided on the approval
+    pub actor: String,
+    pub project_id: String,
+    pub region: String,
+    pub environment: String,
+    pub deployment_id: String,
+    pub module: String,
+    pub module_version: String,
+    pub job_id: String,
+    pub status: DeploymentStatus,
+    /// The error, policy violations or approval comment of the event, if any
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub details: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Line of an exported audit log. The signature covers the event and the signature of the line
+/// before it, so lines can't be changed, removed or reordered without breaking the chain
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct AuditLogRecord {
+    pub event: AuditEvent,
+    /// Empty for the first line
+    pub previous_signature: String,
+    /// `sha256=<hex HMAC-SHA256>`
+    pub signature: String,
+}
@@ -82,6 +82,9 @@ pub struct GitHubCheckRun {
     pub check_run: CheckRun,
     pub job_details: JobDetails,
     pub user: User,
+    /// Set when the job was triggered by a pull request, used to comment the plan on it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_request_number: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -89,11 +92,7 @@ pub struct GitHubCheckRun {
 #[allow(clippy::large_enum_variant)]
 pub enum ExtraData {
     GitHub(GitHubCheckRun),
-
-    // This is a placeholder for future GitLab-specific events
     GitLab(GitLabCheckRun),
-    // SEE: https://gitlab.com/-/user_settings/applications
-    //      https://developers.cloudflare.com/pages/configuration/git-integration/gitlab-integration/
     None,
 }
 
@@ -102,6 +101,13 @@ pub struct GitLabCheckRun {
     pub project: GitLabProject,
     pub pipeline: GitLabPipeline,
     pub job_details: JobDetails,
+    #[serde(default)]
+    pub commit_status: GitLabCommitStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<User>,
+    /// Set when the job was triggered by a merge request, used to comment the result on it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_request_iid: Option<i64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -109,6 +115,20 @@ pub struct GitLabProject {
     pub id: i64,
     pub name: String,
     pub path_with_namespace: String,
+    #[serde(default)]
+    pub web_url: String,
+}
+
+/// GitLab's counterpart of a check run, posted through the commit status API
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GitLabCommitStatus {
+    pub name: String,
+    /// One of pending, running, success, failed or canceled
+    pub state: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_url: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
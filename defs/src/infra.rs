@@ -27,9 +27,24 @@ pub struct ApiInfraPayload {
     pub memory: String,
     pub reference: String,
     pub extra_data: ExtraData,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_reason: Option<String>,
+    /// Plan job whose cached workspace and plan file an apply restores instead of planning again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_job_id: Option<String>,
+    /// Resource addresses the plan or apply is limited to, which makes the change partial
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+    /// Identifies identical submissions, a submission with the key of the job in progress returns that job.
+    /// Derived from the claim content when not provided by the caller
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// Users who approved the job, set when it was held for approval
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approved_by: Vec<String>,
 }
 
-#[derive(Clone, serde::Serialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ApiInfraPayloadWithVariables {
     pub payload: ApiInfraPayload,
     pub variables: serde_json::value::Value,
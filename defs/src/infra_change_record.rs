@@ -23,7 +23,7 @@ pub struct InfraChangeRecord {
     pub job_id: String,
     pub module: String,
     pub environment: String,
-    pub change_type: String, // plan or apply
+    pub change_type: String, // plan, apply, destroy or drift
     pub module_version: String,
     pub epoch: u128,
     pub timestamp: String,
@@ -41,4 +41,173 @@ pub struct InfraChangeRecord {
     /// Optional for backward compatibility with older change records.
     #[serde(default)]
     pub variables: Value,
+    /// Whether the change was limited to the targeted resources of the claim, leaving the rest
+    /// of the module unchanged.
+    #[serde(default)]
+    pub partial: bool,
+    /// Users who approved the job, empty if it did not need approval
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approved_by: Vec<String>,
+    /// Number of parts the record is stored in when it exceeds the item size limit of the
+    /// database, see `split_change_record_item`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunks: Option<usize>,
+}
+
+/// Separates the job id from the part number in the sort key of a part of a change record
+pub const CHANGE_RECORD_PART_SEPARATOR: &str = "#PART#";
+
+/// Largest change record that is stored as a single item, leaving room below the item size
+/// limit of the database of the cloud provider (400 KB in DynamoDB and 2 MB in Cosmos DB)
+pub fn change_record_max_item_bytes(cloud_provider: &str) -> usize {
+    match cloud_provider {
+        "azure" => 1900 * 1024,
+        _ => 380 * 1024,
+    }
+}
+
+/// Sort key of part `part` of the change record stored under the sort key `sk`
+pub fn get_change_record_part_sk(sk: &str, part: usize) -> String {
+    format!("{}{}{:05}", sk, CHANGE_RECORD_PART_SEPARATOR, part)
+}
+
+/// Splits a change record item, holding its `PK` and `SK`, that is larger than `max_item_bytes`.
+/// The parts hold consecutive pieces of the JSON of the record under the sort key of the record
+/// followed by the part number, and come first so the record is complete once the index item is
+/// written. The index item, last, keeps the fields of the record besides its output, resource
+/// changes and variables, and the number of parts in `chunks`. Smaller items are returned as is
+pub fn split_change_record_item(item: &Value, max_item_bytes: usize) -> Vec<Value> {
+    let serialized = item.to_string();
+    if serialized.len() <= max_item_bytes {
+        return vec![item.clone()];
+    }
+    let pk = item.get("PK").cloned().unwrap_or(Value::Null);
+    let sk = item.get("SK").and_then(Value::as_str).unwrap_or_default();
+
+    // Room for the keys and attribute names of a part
+    let max_chunk_bytes = max_item_bytes.saturating_sub(1024).max(1024);
+    let mut chunks: Vec<String> = vec![];
+    let mut chunk = String::new();
+    let mut chunk_bytes = 0;
+    for c in serialized.chars() {
+        // Size of the character once the chunk is stored as a JSON string
+        let c_bytes = match c {
+            '"' | '\\' | '\n' | '\r' | '\t' | '\u{8}' | '\u{c}' => 2,
+            c if (c as u32) < 0x20 => 6,
+            c => c.len_utf8(),
+        };
+        if chunk_bytes + c_bytes > max_chunk_bytes {
+            chunks.push(std::mem::take(&mut chunk));
+            chunk_bytes = 0;
+        }
+        chunk.push(c);
+        chunk_bytes += c_bytes;
+    }
+    chunks.push(chunk);
+
+    let mut items: Vec<Value> = chunks
+        .into_iter()
+        .enumerate()
+        .map(|(part, data)| {
+            serde_json::json!({
+                "PK": pk,
+                "SK": get_change_record_part_sk(sk, part),
+                "data": data,
+            })
+        })
+        .collect();
+
+    let mut index = item.clone();
+    if let Some(fields) = index.as_object_mut() {
+        fields.insert("plan_std_output".to_string(), Value::String(String::new()));
+        fields.remove("resource_changes");
+        fields.remove("variables");
+        fields.insert("chunks".to_string(), Value::from(items.len()));
+    }
+    items.push(index);
+    items
+}
+
+/// Reassembles a change record from the index item `split_change_record_item` returned and its
+/// parts, in any order
+pub fn join_change_record_parts(
+    index: &Value,
+    parts: &[Value],
+) -> Result<InfraChangeRecord, anyhow::Error> {
+    let chunks = index.get("chunks").and_then(Value::as_u64).unwrap_or(0) as usize;
+    let mut parts: Vec<(&str, &str)> = parts
+        .iter()
+        .filter_map(|part| Some((part.get("SK")?.as_str()?, part.get("data")?.as_str()?)))
+        .collect();
+    if parts.len() != chunks {
+        return Err(anyhow::anyhow!(
+            "Change record has {} of its {} parts",
+            parts.len(),
+            chunks
+        ));
+    }
+    parts.sort_by(|a, b| a.0.cmp(b.0));
+    let serialized: String = parts.into_iter().map(|(_, data)| data).collect();
+    let mut change_record: InfraChangeRecord = serde_json::from_str(&serialized)?;
+    change_record.chunks = Some(chunks);
+    Ok(change_record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn change_record_item(plan_std_output: &str) -> Value {
+        json!({
+            "PK": "PLAN#123456789012::eu-west-1::dev::s3bucket/bucket",
+            "SK": "job-1",
+            "deployment_id": "s3bucket/bucket",
+            "project_id": "123456789012",
+            "region": "eu-west-1",
+            "job_id": "job-1",
+            "module": "s3bucket",
+            "environment": "dev",
+            "change_type": "plan",
+            "module_version": "0.1.0",
+            "epoch": 1700000000000u64,
+            "timestamp": "2023-11-14T22:13:20.000Z",
+            "plan_std_output": plan_std_output,
+            "plan_raw_json_key": "job-1_plan_output.json",
+            "variables": { "bucket_name": "logs" },
+        })
+    }
+
+    #[test]
+    fn test_split_change_record_item_small() {
+        let item = change_record_item("No changes.");
+        assert_eq!(split_change_record_item(&item, 4096), vec![item]);
+    }
+
+    #[test]
+    fn test_split_and_join_change_record_item() {
+        let plan_std_output = "  # aws_s3_bucket.\"bucket\" will be created ✓\n".repeat(500);
+        let item = change_record_item(&plan_std_output);
+        let items = split_change_record_item(&item, 4096);
+
+        let (index, parts) = items.split_last().unwrap();
+        assert!(parts.len() > 1);
+        for part in parts {
+            assert!(part.to_string().len() <= 4096);
+            assert!(part["SK"].as_str().unwrap().starts_with("job-1#PART#"));
+        }
+        assert_eq!(index["SK"], "job-1");
+        assert_eq!(index["chunks"], parts.len());
+        assert_eq!(index["plan_std_output"], "");
+        assert!(index.get("variables").is_none());
+
+        let mut shuffled = parts.to_vec();
+        shuffled.reverse();
+        let change_record = join_change_record_parts(index, &shuffled).unwrap();
+        assert_eq!(change_record.plan_std_output, plan_std_output);
+        assert_eq!(change_record.variables["bucket_name"], "logs");
+        assert_eq!(change_record.chunks, Some(parts.len()));
+
+        assert!(join_change_record_parts(index, &parts[1..]).is_err());
+    }
 }
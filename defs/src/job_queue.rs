@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{ApiInfraPayloadWithVariables, ExtraData};
+
+/// Order in which queued jobs get a free slot of their project, interactive jobs first
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobPriority {
+    /// Started by a user from the CLI, who is waiting for the result
+    Interactive,
+    /// Started by a pull request, the operator or a change of an upstream deployment
+    Gitops,
+    /// Refresh-only plans checking deployments for drift
+    Driftcheck,
+}
+
+impl std::fmt::Display for JobPriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobPriority::Interactive => write!(f, "interactive"),
+            JobPriority::Gitops => write!(f, "gitops"),
+            JobPriority::Driftcheck => write!(f, "driftcheck"),
+        }
+    }
+}
+
+impl JobPriority {
+    /// Priority class of the submission, from where it was started
+    pub fn of(submission: &ApiInfraPayloadWithVariables) -> JobPriority {
+        let payload = &submission.payload;
+        if payload.flags.iter().any(|flag| flag == "-refresh-only") {
+            JobPriority::Driftcheck
+        } else if payload.environment.starts_with("cli/")
+            && matches!(payload.extra_data, ExtraData::None)
+            && payload.trigger_reason.is_none()
+        {
+            JobPriority::Interactive
+        } else {
+            JobPriority::Gitops
+        }
+    }
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobQueueState {
+    /// Waiting for a slot of the project
+    Queued,
+    /// Holding a slot of the project until the job finishes
+    Running,
+}
+
+/// Job of a project that limits how many jobs it runs at the same time, see
+/// `ProjectSettings::max_concurrent_jobs`
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Deserialize, Serialize)]
+pub struct JobQueueEntry {
+    pub job_id: String,
+    pub state: JobQueueState,
+    pub priority: JobPriority,
+    pub deployment_id: String,
+    pub environment: String,
+    pub command: String,
+    pub initiated_by: String,
+    pub epoch: u128,
+    /// Slot of the project the running job holds, at most one job holds a slot at a time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u32>,
+    /// Submission the job is started with once it gets a slot, only kept while it is queued
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submission: Option<ApiInfraPayloadWithVariables>,
+}
+
+/// Orders queued jobs the way they are started: by priority, then first come first served
+pub fn sort_job_queue(entries: &mut [JobQueueEntry]) {
+    entries.sort_by_key(|entry| (entry.priority, entry.epoch));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submission(environment: &str, flags: Vec<&str>) -> ApiInfraPayloadWithVariables {
+        serde_json::from_value(serde_json::json!({
+            "payload": {
+                "command": "plan",
+                "flags": flags,
+                "module": "s3bucket",
+                "module_version": "0.1.0",
+                "module_type": "module",
+                "module_track": "dev",
+                "name": "bucket",
+                "environment": environment,
+                "deployment_id": "s3bucket/bucket",
+                "project_id": "123456789012",
+                "region": "eu-west-1",
+                "drift_detection": {},
+                "next_drift_check_epoch": -1,
+                "annotations": {},
+                "dependencies": [],
+                "initiated_by": "alice",
+                "cpu": "1024",
+                "memory": "2048",
+                "reference": "",
+                "extra_data": null,
+            },
+            "variables": {},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_job_priority() {
+        assert_eq!(
+            JobPriority::of(&submission("cli/dev", vec![])),
+            JobPriority::Interactive
+        );
+        assert_eq!(
+            JobPriority::of(&submission("github-org-repo/dev", vec![])),
+            JobPriority::Gitops
+        );
+        assert_eq!(
+            JobPriority::of(&submission("cli/dev", vec!["-refresh-only"])),
+            JobPriority::Driftcheck
+        );
+
+        let mut triggered = submission("cli/dev", vec![]);
+        triggered.payload.trigger_reason = Some("triggered by upstream change".to_string());
+        assert_eq!(JobPriority::of(&triggered), JobPriority::Gitops);
+    }
+
+    #[test]
+    fn test_sort_job_queue() {
+        let entry = |job_id: &str, priority: JobPriority, epoch: u128| JobQueueEntry {
+            job_id: job_id.to_string(),
+            state: JobQueueState::Queued,
+            priority,
+            deployment_id: "s3bucket/bucket".to_string(),
+            environment: "cli/dev".to_string(),
+            command: "plan".to_string(),
+            initiated_by: "alice".to_string(),
+            epoch,
+            slot: None,
+            submission: None,
+        };
+        let mut entries = vec![
+            entry("drift", JobPriority::Driftcheck, 1),
+            entry("gitops-late", JobPriority::Gitops, 5),
+            entry("cli", JobPriority::Interactive, 9),
+            entry("gitops-early", JobPriority::Gitops, 2),
+        ];
+        sort_job_queue(&mut entries);
+        let order: Vec<&str> = entries.iter().map(|e| e.job_id.as_str()).collect();
+        assert_eq!(order, vec!["cli", "gitops-early", "gitops-late", "drift"]);
+    }
+}
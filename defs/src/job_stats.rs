@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+
+/// Memory added on top of the p95 peak memory of a module's jobs, in percent
+const MEMORY_HEADROOM_PERCENT: u64 = 25;
+
+/// Valid Fargate task sizes: cpu units and the memory (MB) range and step allowed for them
+const RUNNER_SIZES: [(u64, u64, u64, u64); 5] = [
+    (256, 512, 2048, 512),
+    (512, 1024, 4096, 1024),
+    (1024, 2048, 8192, 1024),
+    (2048, 4096, 16384, 1024),
+    (4096, 8192, 30720, 1024),
+];
+
+/// Duration and peak memory usage of a finished runner job, kept per module to size its next jobs
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct JobStats {
+    pub job_id: String,
+    pub module: String,
+    pub deployment_id: String,
+    pub environment: String,
+    pub command: String,
+    pub epoch: u128,
+    pub duration_seconds: u64,
+    /// Peak memory of the runner container, None when the runner can't read it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_memory_mb: Option<u64>,
+    /// Cpu and memory the job ran with
+    pub cpu: String,
+    pub memory: String,
+}
+
+/// Cpu and memory for the next job of a module, from the p95 duration and peak memory of its
+/// recorded jobs. None while fewer than `min_samples` jobs reported their peak memory
+pub fn auto_runner_size(stats: &[JobStats], min_samples: usize) -> Option<(String, String)> {
+    let mut peak_memory: Vec<u64> = stats.iter().filter_map(|s| s.peak_memory_mb).collect();
+    if peak_memory.is_empty() || peak_memory.len() < min_samples {
+        return None;
+    }
+    let mut durations: Vec<u64> = stats.iter().map(|s| s.duration_seconds).collect();
+
+    let memory = p95(&mut peak_memory) * (100 + MEMORY_HEADROOM_PERCENT) / 100;
+    // Long running jobs are mostly busy refreshing many resources, which more cpu speeds up
+    let min_cpu = match p95(&mut durations) {
+        0..=900 => 0,
+        901..=1800 => 1024,
+        _ => 2048,
+    };
+
+    let (cpu, memory) = RUNNER_SIZES
+        .iter()
+        .filter(|(cpu, ..)| *cpu >= min_cpu)
+        .find_map(|&(cpu, min_memory, max_memory, step)| {
+            let memory = memory.max(min_memory).div_ceil(step) * step;
+            (memory <= max_memory).then_some((cpu, memory))
+        })
+        .unwrap_or((4096, 30720));
+    Some((cpu.to_string(), memory.to_string()))
+}
+
+/// Nearest-rank 95th percentile of the values, which must not be empty
+fn p95(values: &mut [u64]) -> u64 {
+    values.sort_unstable();
+    let rank = (values.len() * 95).div_ceil(100);
+    values[rank - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(duration_seconds: u64, peak_memory_mb: Option<u64>) -> JobStats {
+        JobStats {
+            job_id: "job-1".to_string(),
+            module: "s3bucket".to_string(),
+            deployment_id: "s3bucket/my-bucket".to_string(),
+            environment: "playground".to_string(),
+            command: "apply".to_string(),
+            epoch: 0,
+            duration_seconds,
+            peak_memory_mb,
+            cpu: "1024".to_string(),
+            memory: "2048".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_auto_runner_size() {
+        // Too few jobs reported their peak memory
+        let history = vec![stats(60, Some(300)), stats(60, None)];
+        assert_eq!(auto_runner_size(&history, 2), None);
+
+        // Small, short jobs get the smallest size
+        let history: Vec<JobStats> = (0..20).map(|_| stats(60, Some(300))).collect();
+        assert_eq!(
+            auto_runner_size(&history, 5),
+            Some(("256".to_string(), "512".to_string()))
+        );
+
+        // The p95 ignores a single outlier among 20 jobs, 1000 MB plus headroom is 1250 MB
+        let mut history: Vec<JobStats> = (0..19).map(|_| stats(60, Some(1000))).collect();
+        history.push(stats(60, Some(20000)));
+        assert_eq!(
+            auto_runner_size(&history, 5),
+            Some(("256".to_string(), "1536".to_string()))
+        );
+
+        // Too much memory for the smallest cpu
+        let history: Vec<JobStats> = (0..5).map(|_| stats(60, Some(3000))).collect();
+        assert_eq!(
+            auto_runner_size(&history, 5),
+            Some(("512".to_string(), "4096".to_string()))
+        );
+
+        // Long running jobs get more cpu
+        let history: Vec<JobStats> = (0..5).map(|_| stats(1200, Some(300))).collect();
+        assert_eq!(
+            auto_runner_size(&history, 5),
+            Some(("1024".to_string(), "2048".to_string()))
+        );
+
+        // More memory than any size has
+        let history: Vec<JobStats> = (0..5).map(|_| stats(60, Some(40000))).collect();
+        assert_eq!(
+            auto_runner_size(&history, 5),
+            Some(("4096".to_string(), "30720".to_string()))
+        );
+    }
+}
@@ -1,4 +1,5 @@
 mod api;
+mod approval;
 mod cloudprovider;
 mod deployment;
 mod environment;
@@ -8,57 +9,82 @@ mod events;
 mod gitprovider;
 mod infra;
 mod infra_change_record;
+mod job_queue;
+mod job_stats;
 mod log;
 mod module;
 #[cfg(test)]
 mod module_test;
 mod notification;
 mod oci;
+mod platform_config;
 mod policy;
 mod resource;
 mod resource_change;
 #[cfg(test)]
 mod schema_test;
+mod secret_ref;
 mod stack;
 mod tfoutput;
 mod tfprovider;
+mod value_from;
 
 pub use api::GenericFunctionResponse;
+pub use approval::{ApprovalDecision, ApprovalRequest};
 pub use cloudprovider::{CloudProvider, CloudProviderCommon};
 pub use deployment::{
-    get_deployment_identifier, Dependency, DependencySpec, Dependent, DeploymentManifest,
-    DeploymentResp, DeploymentSpec, DeploymentStatus, DriftDetection, JobStatus,
-    Metadata as DeploymentMetadata, ProjectData, Webhook, DEFAULT_DRIFT_DETECTION_INTERVAL,
+    environment_matches, get_deployment_identifier, sanitize_terraform_output, ApprovalPolicy,
+    ArtifactVerificationPolicy, AssumeRoleStep, AwsAccessSettings, BlackoutWindow, Dependency,
+    DependencySpec, DependencyTrigger, Dependent, DeploymentManifest, DeploymentResp,
+    DeploymentSpec, DeploymentStatus, DriftDetection, JobStatus, Metadata as DeploymentMetadata,
+    OutputsTo, OutputsToKind, ProjectData, ProjectSettings, RetentionSettings,
+    RunnerSizingSettings, RunnerStorageSettings, ValidationWebhook, ValidationWebhookFailureMode,
+    Webhook, WebhookDelivery, WebhookDeliveryStatus, WebhookEvent,
+    DEFAULT_DRIFT_DETECTION_INTERVAL, SANITIZED_OUTPUT_VALUE,
 };
 pub use environment::EnvironmentResp;
-pub use errors::CloudHandlerError;
-pub use event::{get_event_identifier, EventData};
+pub use errors::{ArtifactPolicyViolation, CloudHandlerError};
+pub use event::{get_event_identifier, AuditEvent, AuditEventType, AuditLogRecord, EventData};
 pub use events::*;
 pub use gitprovider::{
-    CheckRun, CheckRunOutput, ExtraData, GitHubCheckRun, Installation, JobDetails, Owner,
-    Repository, User,
+    CheckRun, CheckRunOutput, ExtraData, GitHubCheckRun, GitLabCheckRun, GitLabCommitStatus,
+    GitLabPipeline, GitLabProject, Installation, JobDetails, Owner, Repository, User,
 };
 pub use infra::{ApiInfraPayload, ApiInfraPayloadWithVariables};
-pub use infra_change_record::{get_change_record_identifier, InfraChangeRecord};
+pub use infra_change_record::{
+    change_record_max_item_bytes, get_change_record_identifier, get_change_record_part_sk,
+    join_change_record_parts, split_change_record_item, InfraChangeRecord,
+    CHANGE_RECORD_PART_SEPARATOR,
+};
+pub use job_queue::{sort_job_queue, JobPriority, JobQueueEntry, JobQueueState};
+pub use job_stats::{auto_runner_size, JobStats};
 pub use log::LogData;
 pub use module::{
-    deserialize_module_manifest, get_module_identifier, Metadata, ModuleDiffAddition,
-    ModuleDiffChange, ModuleDiffRemoval, ModuleExample, ModuleManifest, ModuleResp, ModuleSpec,
-    ModuleStackData, ModuleVersionDiff, Provider, StackModule, TfLockProvider, TfRequiredProvider,
-    TfValidation, TfVariable,
+    deserialize_module_manifest, get_module_identifier, validate_owner, Metadata,
+    ModuleDiffAddition, ModuleDiffChange, ModuleDiffRemoval, ModuleExample, ModuleManifest,
+    ModuleResp, ModuleSchemaChange, ModuleSpec, ModuleStackData, ModuleUi, ModuleVersionDiff,
+    ModuleYank, Provider, StackInstanceModule, StackInstanceOutput, StackModule, TfLockProvider,
+    TfRequiredProvider, TfValidation, TfVariable, VariableGroup, VisibleWhen,
 };
 pub use notification::NotificationData;
 pub use oci::{
-    ArtifactType, Blob, IndexEntry, IndexJson, LayerDesc, LayoutFile, OciArtifactSet, OciManifest,
+    ArtifactType, Blob, IndexEntry, IndexJson, IndexPlatform, LayerDesc, LayoutFile,
+    OciArtifactSet, OciManifest, OciPlatform, CLOUD_ANNOTATION, OCI_INDEX_MEDIA_TYPE,
+    OCI_MANIFEST_MEDIA_TYPE,
 };
+pub use platform_config::{PlatformConfigManifest, PlatformConfigSpec, PolicyBundle};
 pub use policy::{
-    deserialize_policy_manifest, get_policy_identifier, PolicyManifest, PolicyResp, PolicyResult,
+    deserialize_policy_manifest, get_policy_identifier, PolicyManifest, PolicyPackAssignment,
+    PolicyPackManifest, PolicyPackPolicy, PolicyPackResp, PolicyPackSpec, PolicyResp, PolicyResult,
+    PolicyTarget,
 };
 pub use resource::ResourceResp;
 pub use resource_change::{
-    pretty_print_resource_changes, sanitize_resource_changes_from_plan, ResourceAction,
-    ResourceMode, SanitizedResourceChange,
+    pretty_print_resource_changes, redact_plan_json, sanitize_resource_changes_from_plan,
+    ResourceAction, ResourceMode, SanitizedResourceChange,
 };
-pub use stack::StackManifest;
+pub use secret_ref::{SecretProvider, SecretRef};
+pub use stack::{MemberVariable, MemberVariables, StackManifest, VariableExposure};
 pub use tfoutput::TfOutput;
 pub use tfprovider::{Metadata as ProviderMetaData, ProviderManifest, ProviderResp, ProviderSpec};
+pub use value_from::ValueFrom;
@@ -3,4 +3,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct LogData {
     pub message: String,
+    /// Milliseconds since epoch, when reported by the log backend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
 }
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::{de::Deserializer, Deserialize, Serialize};
 
 use crate::{oci::OciArtifactSet, ProviderResp, TfOutput};
@@ -25,6 +27,9 @@ pub struct TfVariable {
     pub nullable: bool,
     #[serde(default)]
     pub sensitive: bool,
+    /// The `validation` blocks of the variable, checked against the values in claims
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub validations: Vec<TfValidation>,
 }
 
 fn default_tf_variable_type() -> serde_json::Value {
@@ -61,9 +66,12 @@ impl TfVariable {
     }
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct TfValidation {
+    /// The `condition` of the validation block, e.g. `length(var.bucket_name) <= 63`
     pub expression: String,
+    /// The `error_message` of the validation block
     pub message: String,
 }
 
@@ -81,6 +89,32 @@ pub struct StackModule {
     pub s3_key: String,
     #[serde(default)]
     pub track: String,
+    /// Name of the claim in the stack, whose snake_case form prefixes the stack's variables and
+    /// outputs (empty for stacks published before it was recorded)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub claim: String,
+}
+
+/// Module of a stack deployment, with the outputs of the deployment that come from it
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct StackInstanceModule {
+    pub claim: String,
+    pub module: String,
+    pub version: String,
+    pub track: String,
+    pub outputs: Vec<StackInstanceOutput>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct StackInstanceOutput {
+    /// Output of the stack, e.g. `bucket1a__bucket_arn`
+    pub name: String,
+    /// Output of the module it maps to, e.g. `bucket_arn`
+    pub module_output: String,
+    /// Value in the deployment, null until the deployment has been applied
+    pub value: serde_json::Value,
 }
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -129,6 +163,19 @@ pub struct ModuleVersionDiff {
     pub previous_version: String,
 }
 
+/// Change to the variables or outputs of a module between two versions
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ModuleSchemaChange {
+    /// `variable` or `output`
+    pub kind: String,
+    pub name: String,
+    /// What changed, e.g. `added (required)` or `type changed from string to number`
+    pub change: String,
+    /// Whether claims or dependants of the old version may break on the new version
+    pub breaking: bool,
+}
+
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Deserialize, Serialize, Clone, Debug, Default)]
 pub struct ModuleResp {
@@ -164,6 +211,36 @@ pub struct ModuleResp {
     pub deprecated: bool,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub deprecated_message: Option<String>,
+    /// `README.md` of the module directory, bundled when the version was published
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readme: Option<String>,
+    /// `CHANGELOG.md` of the module directory, bundled when the version was published
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changelog: Option<String>,
+    /// `sha256:<hex>` digest of the zip stored for the version, recorded when it was published
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    /// Digest-pinned reference (`<repository>@sha256:<hex>`) of the OCI artifact the version was
+    /// last pushed to with `module push`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oci_reference: Option<String>,
+    /// Set when the version was yanked, see `ModuleYank`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yanked: Option<ModuleYank>,
+}
+
+/// Yank of a published version: deployments already running it keep doing so, but new claims
+/// can't use it and are pointed at the replacement version instead
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ModuleYank {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Version claims should use instead, None when the track has no usable version left
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement_version: Option<String>,
+    pub yanked_by: String,
+    pub epoch: u128,
 }
 
 pub fn deserialize_module_manifest<'de, D>(deserializer: D) -> Result<ModuleManifest, D::Error>
@@ -216,6 +293,7 @@ impl ModuleManifest {
         self.spec.validate_module_name()?;
         self.validate_name_consistency()?;
         self.validate_kind()?;
+        self.spec.validate_owners()?;
         Ok(())
     }
 
@@ -264,9 +342,22 @@ pub struct ModuleSpec {
     pub memory: Option<String>,
     #[serde(default)]
     pub providers: Vec<Provider>,
+    /// Teams (`@org/team`), users (`@user`) or emails maintaining the module, used to route notifications
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+    /// How the variables are presented in forms such as the claim builder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ui: Option<ModuleUi>,
 }
 
 impl ModuleSpec {
+    /// Validates `spec.owners`: each owner must be a team, a user or an email as in CODEOWNERS.
+    pub fn validate_owners(&self) -> Result<(), String> {
+        self.owners
+            .iter()
+            .try_for_each(|owner| validate_owner(owner))
+    }
+
     /// Validates `spec.moduleName`: must start with uppercase and contain only alphanumeric characters.
     pub fn validate_module_name(&self) -> Result<(), String> {
         let module_name = &self.module_name;
@@ -288,6 +379,155 @@ impl ModuleSpec {
     }
 }
 
+/// Presentation of the variables of a module, set in `spec.ui` of `module.yaml`. Variables are
+/// referred to by their terraform names
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ModuleUi {
+    /// Groups of variables in the order they are presented, before the variables not in a group
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<VariableGroup>,
+    /// Conditions for variables to be presented, by variable name
+    #[serde(
+        rename = "visibleWhen",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub visible_when: BTreeMap<String, VisibleWhen>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct VariableGroup {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Variables of the group in the order they are presented
+    pub variables: Vec<String>,
+}
+
+/// Presents a variable only while another variable has a given value
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct VisibleWhen {
+    pub variable: String,
+    pub equals: serde_json::Value,
+}
+
+impl ModuleUi {
+    /// Group of the variable and its position in the group. Variables not in a group come
+    /// after the groups, so sorting by position keeps them in their current order
+    pub fn position(&self, variable: &str) -> (usize, usize) {
+        self.groups
+            .iter()
+            .enumerate()
+            .find_map(|(group_index, group)| {
+                group
+                    .variables
+                    .iter()
+                    .position(|v| v == variable)
+                    .map(|index| (group_index, index))
+            })
+            .unwrap_or((self.groups.len(), 0))
+    }
+
+    /// The group the variable is in, None when it is not in a group
+    pub fn group(&self, variable: &str) -> Option<&VariableGroup> {
+        self.groups
+            .iter()
+            .find(|group| group.variables.iter().any(|v| v == variable))
+    }
+
+    /// Whether the variable is presented, `value_of` returning the current value of a variable
+    /// (its default when it is not set)
+    pub fn is_visible(
+        &self,
+        variable: &str,
+        value_of: impl Fn(&str) -> Option<serde_json::Value>,
+    ) -> bool {
+        match self.visible_when.get(variable) {
+            Some(condition) => value_of(&condition.variable).as_ref() == Some(&condition.equals),
+            None => true,
+        }
+    }
+
+    /// Validates that groups and conditions refer to variables of the module, that a variable is
+    /// in one group at most, and that required variables are never hidden.
+    pub fn validate(&self, tf_variables: &[TfVariable]) -> Result<(), String> {
+        let find = |name: &str| tf_variables.iter().find(|v| v.name == name);
+        let unknown = |name: &str| {
+            format!(
+                "The variable {} in spec.ui of module.yaml is not a variable of the module.",
+                name
+            )
+        };
+
+        let mut grouped: Vec<&str> = vec![];
+        for group in &self.groups {
+            for variable in &group.variables {
+                if find(variable).is_none() {
+                    return Err(unknown(variable));
+                }
+                if grouped.contains(&variable.as_str()) {
+                    return Err(format!(
+                        "The variable {} is in more than one group in spec.ui of module.yaml.",
+                        variable
+                    ));
+                }
+                grouped.push(variable);
+            }
+        }
+
+        for (variable, condition) in &self.visible_when {
+            let tf_variable = find(variable).ok_or_else(|| unknown(variable))?;
+            if find(&condition.variable).is_none() {
+                return Err(unknown(&condition.variable));
+            }
+            if tf_variable.required() {
+                return Err(format!(
+                    "The variable {} is required, so it can't be hidden with visibleWhen.",
+                    variable
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Validates an owner: `@org/team`, `@user` or `name@example.com`, the formats used in CODEOWNERS.
+pub fn validate_owner(owner: &str) -> Result<(), String> {
+    let is_handle_char = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.';
+    let valid = match owner.strip_prefix('@') {
+        Some(handle) => {
+            let mut parts = handle.splitn(2, '/');
+            let org = parts.next().unwrap_or("");
+            let team = parts.next();
+            !org.is_empty()
+                && org.chars().all(is_handle_char)
+                && team.is_none_or(|team| !team.is_empty() && team.chars().all(is_handle_char))
+        }
+        None => match owner.split_once('@') {
+            Some((local, domain)) => {
+                !local.is_empty()
+                    && !local.contains(char::is_whitespace)
+                    && domain.contains('.')
+                    && !domain.starts_with('.')
+                    && !domain.ends_with('.')
+                    && domain.chars().all(is_handle_char)
+            }
+            None => false,
+        },
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "The owner {} must be a team (@org/team), a user (@user) or an email.",
+            owner
+        ))
+    }
+}
+
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Metadata {
@@ -324,6 +324,7 @@ mod test_tf_variable_required {
             description: String::new(),
             nullable,
             sensitive: false,
+            validations: vec![],
         }
     }
 
@@ -360,3 +361,93 @@ mod test_tf_variable_required {
         );
     }
 }
+
+#[cfg(test)]
+mod test_module_ui {
+    use crate::{ModuleManifest, TfVariable};
+    use serde_json::{json, Value};
+
+    const MANIFEST: &str = r#"
+        apiVersion: infraweave.io/v1
+        kind: Module
+        metadata:
+            name: s3bucket
+        spec:
+            moduleName: S3Bucket
+            version: 0.2.1
+            providers: []
+            reference: https://github.com/your-org/s3bucket
+            description: "S3Bucket description here..."
+            ui:
+                groups:
+                    - name: Bucket
+                      variables: [bucket_name, tags]
+                    - name: Logging
+                      description: Access logs written to another bucket
+                      variables: [enable_logging, log_bucket]
+                visibleWhen:
+                    log_bucket:
+                        variable: enable_logging
+                        equals: true
+    "#;
+
+    fn var(name: &str, default: Option<Value>) -> TfVariable {
+        TfVariable {
+            name: name.to_string(),
+            _type: json!("string"),
+            default,
+            description: String::new(),
+            nullable: true,
+            sensitive: false,
+            validations: vec![],
+        }
+    }
+
+    fn ui() -> crate::ModuleUi {
+        serde_yaml::from_str::<ModuleManifest>(MANIFEST)
+            .unwrap()
+            .spec
+            .ui
+            .unwrap()
+    }
+
+    #[test]
+    fn orders_variables_by_group() {
+        let ui = ui();
+        assert_eq!(ui.position("bucket_name"), (0, 0));
+        assert_eq!(ui.position("log_bucket"), (1, 1));
+        assert_eq!(ui.position("versioning"), (2, 0));
+        assert_eq!(ui.group("enable_logging").unwrap().name, "Logging");
+        assert!(ui.group("versioning").is_none());
+    }
+
+    #[test]
+    fn hides_variables_until_condition_is_met() {
+        let ui = ui();
+        assert!(!ui.is_visible("log_bucket", |_| Some(json!(false))));
+        assert!(!ui.is_visible("log_bucket", |_| None));
+        assert!(ui.is_visible("log_bucket", |_| Some(json!(true))));
+        assert!(ui.is_visible("bucket_name", |_| None));
+    }
+
+    #[test]
+    fn validates_variable_references() {
+        let ui = ui();
+        let mut variables = vec![
+            var("bucket_name", None),
+            var("tags", Some(json!({}))),
+            var("enable_logging", Some(json!(false))),
+            var("log_bucket", Some(Value::Null)),
+        ];
+        assert_eq!(ui.validate(&variables), Ok(()));
+
+        variables[3].default = None;
+        assert!(ui.validate(&variables).unwrap_err().contains("is required"));
+
+        variables.pop();
+        assert!(ui
+            .validate(&variables)
+            .unwrap_err()
+            .contains("log_bucket in spec.ui of module.yaml is not a variable"));
+    }
+}
@@ -4,4 +4,8 @@ use serde::{Deserialize, Serialize};
 pub struct NotificationData {
     pub subject: String,            // Used to identify the type of notification
     pub message: serde_json::Value, // Value of the notification
+    /// Owners of the module the notification concerns, published as the `owners` message
+    /// attribute so subscriptions can filter on it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
 }
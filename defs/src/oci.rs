@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug)]
@@ -11,6 +13,7 @@ pub enum ArtifactType {
     MainPackage,
     Attestation,
     Signature,
+    Sbom,
     Unknown,
 }
 
@@ -33,19 +36,104 @@ pub struct LayerDesc {
     pub size: u64,
     pub digest: String,
 }
-#[derive(Serialize, Deserialize)]
+/// Annotation on an index entry naming the cloud provider a manifest is built for, e.g. `aws`
+pub const CLOUD_ANNOTATION: &str = "io.infraweave.cloud";
+
+pub const OCI_INDEX_MEDIA_TYPE: &str = "application/vnd.oci.image.index.v1+json";
+pub const OCI_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct IndexPlatform {
+    pub architecture: String,
+    pub os: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct IndexEntry {
     #[serde(rename = "mediaType")]
     pub media_type: String,
     pub digest: String,
     pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<IndexPlatform>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<BTreeMap<String, String>>,
 }
-#[derive(Serialize)]
+
+impl IndexEntry {
+    pub fn oci_platform(&self) -> OciPlatform {
+        OciPlatform {
+            os: self.platform.as_ref().map(|p| p.os.clone()),
+            architecture: self.platform.as_ref().map(|p| p.architecture.clone()),
+            cloud: self
+                .annotations
+                .as_ref()
+                .and_then(|a| a.get(CLOUD_ANNOTATION).cloned()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct IndexJson {
     #[serde(rename = "schemaVersion")]
     pub schema_version: i32,
+    #[serde(default, rename = "mediaType", skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
     pub manifests: Vec<IndexEntry>,
 }
+
+/// Platform a manifest in a multi-platform index targets, unset fields match any platform
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct OciPlatform {
+    #[serde(default)]
+    pub os: Option<String>,
+    #[serde(default)]
+    pub architecture: Option<String>,
+    #[serde(default)]
+    pub cloud: Option<String>,
+}
+
+impl OciPlatform {
+    /// Whether a manifest built for `self` can run on `target`
+    pub fn matches(&self, target: &OciPlatform) -> bool {
+        fn field_matches(field: &Option<String>, target: &Option<String>) -> bool {
+            match (field, target) {
+                (Some(field), Some(target)) => field.eq_ignore_ascii_case(target),
+                _ => true,
+            }
+        }
+        field_matches(&self.os, &target.os)
+            && field_matches(&self.architecture, &target.architecture)
+            && field_matches(&self.cloud, &target.cloud)
+    }
+
+    /// Number of set fields, a more specific manifest is preferred over a generic one
+    pub fn specificity(&self) -> usize {
+        [&self.os, &self.architecture, &self.cloud]
+            .iter()
+            .filter(|f| f.is_some())
+            .count()
+    }
+}
+
+impl std::fmt::Display for OciPlatform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{}",
+            self.os.as_deref().unwrap_or("*"),
+            self.architecture.as_deref().unwrap_or("*")
+        )?;
+        if let Some(cloud) = &self.cloud {
+            write!(f, " ({})", cloud)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Serialize)]
 pub struct LayoutFile {
     #[serde(rename = "imageLayoutVersion")]
@@ -58,10 +146,18 @@ pub struct LayoutFile {
 pub struct OciArtifactSet {
     pub oci_artifact_path: String,
     pub digest: String,
+    /// Registry repository the artifact was pulled from, e.g. `ghcr.io/my-org/s3bucket`
+    #[serde(default)]
+    pub repository: Option<String>,
     #[serde(default)]
     pub tag_main: String,
     #[serde(default)]
     pub tag_signature: Option<String>,
     #[serde(default)]
     pub tag_attestation: Option<String>,
+    #[serde(default)]
+    pub tag_sbom: Option<String>,
+    /// Platforms of the manifests when the artifact is a multi-platform index, empty otherwise
+    #[serde(default)]
+    pub platforms: Vec<OciPlatform>,
 }
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+use crate::deployment::ProjectData;
+use crate::policy::Metadata;
+
+/// Desired state of the control plane, applied with `infraweave admin apply-config`
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PlatformConfigManifest {
+    pub metadata: Metadata,
+    #[serde(rename = "apiVersion")]
+    pub api_version: String,
+    pub kind: String,
+    pub spec: PlatformConfigSpec,
+}
+
+/// Sections of the platform configuration. Only the sections present in the file are managed, a
+/// missing section leaves the existing objects of its kind untouched
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PlatformConfigSpec {
+    #[serde(default)]
+    pub projects: Option<Vec<ProjectData>>,
+    #[serde(default)]
+    pub policy_bundles: Option<Vec<PolicyBundle>>,
+}
+
+/// Policies published to an environment, policies in the environment that are not listed are removed
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PolicyBundle {
+    pub environment: String,
+    /// Directories containing a `policy.yaml`, relative to the configuration file
+    #[serde(default)]
+    pub policies: Vec<String>,
+}
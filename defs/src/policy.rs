@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::environment_matches;
+
 pub fn get_policy_identifier(policy: &str, environment: &str) -> String {
     format!("{}::{}", environment, policy)
 }
@@ -74,6 +76,20 @@ pub struct PolicySpec {
     pub description: String,
     pub reference: String,
     pub data: serde_json::Value,
+    /// What the policy is evaluated against, the Terraform plan unless set
+    #[serde(default)]
+    pub target: PolicyTarget,
+}
+
+/// Input of a policy: `plan` policies check the Terraform plan in the runner, `claim` policies
+/// check the claim when it is submitted, so that claims violating them never start a job
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyTarget {
+    #[default]
+    Plan,
+    Claim,
 }
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -83,6 +99,94 @@ pub struct Metadata {
     // pub group: String,
 }
 
+/// A versioned group of published policies that is enabled as a unit for the deployments
+/// matching its assignments
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PolicyPackManifest {
+    pub metadata: Metadata,
+    #[serde(rename = "apiVersion")]
+    pub api_version: String,
+    pub kind: String,
+    pub spec: PolicyPackSpec,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PolicyPackSpec {
+    #[serde(rename = "packName")]
+    pub pack_name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    pub policies: Vec<PolicyPackPolicy>,
+    /// Deployments the pack applies to, where an empty list applies it to all deployments
+    #[serde(default)]
+    pub assignments: Vec<PolicyPackAssignment>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PolicyPackPolicy {
+    /// Name of the published policy (its `metadata.name`)
+    pub name: String,
+    /// Version of the policy to evaluate, the newest version if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// Matches deployments on each of the set fields, where an empty list matches anything
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct PolicyPackAssignment {
+    #[serde(default)]
+    pub projects: Vec<String>,
+    /// Full environments (`github-org-repo/prod`) or namespaces (`prod`), a trailing `*` matches any suffix
+    #[serde(default)]
+    pub environments: Vec<String>,
+    /// Module tracks, e.g. `stable`
+    #[serde(default)]
+    pub tracks: Vec<String>,
+}
+
+impl PolicyPackAssignment {
+    pub fn matches(&self, project_id: &str, environment: &str, track: &str) -> bool {
+        (self.projects.is_empty() || self.projects.iter().any(|p| p == project_id))
+            && (self.environments.is_empty()
+                || self
+                    .environments
+                    .iter()
+                    .any(|pattern| environment_matches(pattern, environment)))
+            && (self.tracks.is_empty() || self.tracks.iter().any(|t| t == track))
+    }
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PolicyPackResp {
+    pub environment: String,
+    pub environment_version: String,
+    pub version: String,
+    pub timestamp: String,
+    pub policy_pack: String,
+    pub pack_name: String,
+    pub description: String,
+    pub policies: Vec<PolicyPackPolicy>,
+    #[serde(default)]
+    pub assignments: Vec<PolicyPackAssignment>,
+}
+
+impl PolicyPackResp {
+    /// Whether the pack is enabled for a deployment of a module on `track`
+    pub fn applies_to(&self, project_id: &str, environment: &str, track: &str) -> bool {
+        self.assignments.is_empty()
+            || self
+                .assignments
+                .iter()
+                .any(|assignment| assignment.matches(project_id, environment, track))
+    }
+}
+
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Deserialize, Clone, Debug, Serialize)]
 pub struct PolicyResult {
@@ -94,4 +198,44 @@ pub struct PolicyResult {
     pub policy_name: String,
     pub failed: bool,
     pub violations: serde_json::Value,
+    /// Policy pack the policy was evaluated for, unset for policies that apply to all deployments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_pack: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_pack_assignments() {
+        let mut pack: PolicyPackResp = serde_json::from_value(serde_json::json!({
+            "environment": "stable",
+            "environment_version": "stable#000.001.000",
+            "version": "0.1.0",
+            "timestamp": "",
+            "policy_pack": "pci",
+            "pack_name": "PCI",
+            "description": "",
+            "policies": [{ "name": "encryption" }],
+        }))
+        .unwrap();
+        assert!(pack.applies_to("123456789012", "cli/dev", "dev"));
+
+        pack.assignments = vec![
+            PolicyPackAssignment {
+                projects: vec!["123456789012".to_string()],
+                environments: vec!["prod*".to_string()],
+                ..Default::default()
+            },
+            PolicyPackAssignment {
+                tracks: vec!["stable".to_string()],
+                ..Default::default()
+            },
+        ];
+        assert!(pack.applies_to("123456789012", "github-org-repo/prod-eu", "dev"));
+        assert!(!pack.applies_to("210987654321", "github-org-repo/prod-eu", "dev"));
+        assert!(pack.applies_to("210987654321", "cli/dev", "stable"));
+        assert!(!pack.applies_to("123456789012", "cli/dev", "beta"));
+    }
 }
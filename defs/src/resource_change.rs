@@ -492,6 +492,141 @@ fn sanitize_resource_changes(resource_changes: &serde_json::Value) -> Vec<Saniti
         .unwrap_or_default()
 }
 
+/// Marker replacing sensitive values in redacted plans
+const REDACTED: &str = "[REDACTED]";
+
+/// Replaces every non-null value in `value` with the redaction marker, keeping the keys of
+/// objects and the length of lists so the structure can still be graphed
+fn mask_values(value: &mut Value) {
+    match value {
+        Value::Object(map) => map.values_mut().for_each(mask_values),
+        Value::Array(items) => items.iter_mut().for_each(mask_values),
+        Value::Null => {}
+        other => *other = Value::String(REDACTED.to_string()),
+    }
+}
+
+/// Masks the parts of `value` marked by Terraform's sensitivity markers, such as `after_sensitive`
+fn redact_sensitive(value: &mut Value, sensitive_markers: &Value) {
+    match (value, sensitive_markers) {
+        (value, Value::Bool(true)) => mask_values(value),
+        (Value::Object(val_map), Value::Object(sens_map)) => {
+            for (key, sens_val) in sens_map {
+                if let Some(val) = val_map.get_mut(key) {
+                    redact_sensitive(val, sens_val);
+                }
+            }
+        }
+        (Value::Array(val_arr), Value::Array(sens_arr)) => {
+            for (val, sens_val) in val_arr.iter_mut().zip(sens_arr) {
+                redact_sensitive(val, sens_val);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Redacts a `change` object of `resource_changes`, `resource_drift` or `output_changes`
+fn redact_change(change: &mut Value) {
+    for (key, markers_key) in [("before", "before_sensitive"), ("after", "after_sensitive")] {
+        let markers = change.get(markers_key).cloned().unwrap_or(Value::Null);
+        if let Some(value) = change.get_mut(key) {
+            redact_sensitive(value, &markers);
+        }
+    }
+}
+
+/// Redacts the resources and outputs of `planned_values`, `prior_state.values` or state `values`
+fn redact_values(values: &mut Value) {
+    if let Some(outputs) = values.get_mut("outputs").and_then(Value::as_object_mut) {
+        for output in outputs.values_mut() {
+            if output.get("sensitive") == Some(&Value::Bool(true)) {
+                if let Some(value) = output.get_mut("value") {
+                    mask_values(value);
+                }
+            }
+        }
+    }
+    if let Some(root_module) = values.get_mut("root_module") {
+        redact_module_values(root_module);
+    }
+}
+
+fn redact_module_values(module: &mut Value) {
+    if let Some(resources) = module.get_mut("resources").and_then(Value::as_array_mut) {
+        for resource in resources {
+            let markers = resource
+                .get("sensitive_values")
+                .cloned()
+                .unwrap_or(Value::Null);
+            if let Some(values) = resource.get_mut("values") {
+                redact_sensitive(values, &markers);
+            }
+        }
+    }
+    if let Some(child_modules) = module
+        .get_mut("child_modules")
+        .and_then(Value::as_array_mut)
+    {
+        child_modules.iter_mut().for_each(redact_module_values);
+    }
+}
+
+/// Redacts the sensitive values of a Terraform plan (or state) JSON before it is stored.
+///
+/// Values of variables declared sensitive and of attributes and outputs marked by Terraform's
+/// sensitivity markers (`before_sensitive`, `after_sensitive`, `sensitive_values`) are replaced
+/// with `[REDACTED]`. Keys, list lengths and everything else are kept, so the stored plan can
+/// still be graphed and diffed.
+pub fn redact_plan_json(plan_json: &Value) -> Value {
+    let mut plan = plan_json.clone();
+
+    let sensitive_variables: Vec<String> = plan
+        .pointer("/configuration/root_module/variables")
+        .and_then(Value::as_object)
+        .map(|variables| {
+            variables
+                .iter()
+                .filter(|(_, v)| v.get("sensitive") == Some(&Value::Bool(true)))
+                .map(|(name, _)| name.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    for name in &sensitive_variables {
+        for pointer in [
+            format!("/variables/{}/value", name),
+            format!("/configuration/root_module/variables/{}/default", name),
+        ] {
+            if let Some(value) = plan.pointer_mut(&pointer) {
+                mask_values(value);
+            }
+        }
+    }
+
+    for key in ["resource_changes", "resource_drift"] {
+        if let Some(changes) = plan.get_mut(key).and_then(Value::as_array_mut) {
+            for resource in changes {
+                if let Some(change) = resource.get_mut("change") {
+                    redact_change(change);
+                }
+            }
+        }
+    }
+    if let Some(outputs) = plan
+        .get_mut("output_changes")
+        .and_then(Value::as_object_mut)
+    {
+        outputs.values_mut().for_each(redact_change);
+    }
+
+    for pointer in ["/planned_values", "/prior_state/values", "/values"] {
+        if let Some(values) = plan.pointer_mut(pointer) {
+            redact_values(values);
+        }
+    }
+    plan
+}
+
 /// Format a JSON value for display in change output
 fn format_value(value: &Value) -> String {
     match value {
@@ -1693,4 +1828,80 @@ mod tests {
         // No dependencies means None
         assert!(sanitized[0].depends_on.is_none());
     }
+
+    #[test]
+    fn test_redact_plan_json() {
+        let plan = json!({
+            "variables": {
+                "bucket_name": {"value": "my-bucket"},
+                "password": {"value": "hunter2"}
+            },
+            "configuration": {
+                "root_module": {
+                    "variables": {
+                        "bucket_name": {},
+                        "password": {"sensitive": true, "default": "changeme"}
+                    }
+                }
+            },
+            "resource_changes": [{
+                "address": "aws_db_instance.db",
+                "change": {
+                    "actions": ["update"],
+                    "before": {"password": "old", "tags": {"env": "dev"}, "users": ["a", "b"]},
+                    "after": {"password": "new", "tags": {"env": "prod"}, "users": ["a", "c"]},
+                    "before_sensitive": {"password": true, "tags": {}},
+                    "after_sensitive": {"password": true, "users": [false, true]}
+                }
+            }],
+            "output_changes": {
+                "connection_string": {
+                    "before": null,
+                    "after": {"host": "db", "password": "new"},
+                    "before_sensitive": false,
+                    "after_sensitive": true
+                }
+            },
+            "planned_values": {
+                "outputs": {"connection_string": {"sensitive": true, "value": "db://new"}},
+                "root_module": {
+                    "child_modules": [{
+                        "resources": [{
+                            "address": "module.db.aws_db_instance.db",
+                            "values": {"password": "new", "engine": "postgres"},
+                            "sensitive_values": {"password": true}
+                        }]
+                    }]
+                }
+            }
+        });
+
+        let redacted = redact_plan_json(&plan);
+
+        assert_eq!(redacted["variables"]["bucket_name"]["value"], "my-bucket");
+        assert_eq!(redacted["variables"]["password"]["value"], "[REDACTED]");
+        assert_eq!(
+            redacted["configuration"]["root_module"]["variables"]["password"]["default"],
+            "[REDACTED]"
+        );
+        let change = &redacted["resource_changes"][0]["change"];
+        assert_eq!(change["before"]["password"], "[REDACTED]");
+        assert_eq!(change["before"]["tags"]["env"], "dev");
+        assert_eq!(change["after"]["password"], "[REDACTED]");
+        assert_eq!(change["after"]["users"], json!(["a", "[REDACTED]"]));
+        assert_eq!(change["actions"], json!(["update"]));
+        assert_eq!(
+            redacted["output_changes"]["connection_string"]["after"],
+            json!({"host": "[REDACTED]", "password": "[REDACTED]"})
+        );
+        assert!(redacted["output_changes"]["connection_string"]["before"].is_null());
+        assert_eq!(
+            redacted["planned_values"]["outputs"]["connection_string"]["value"],
+            "[REDACTED]"
+        );
+        let resource =
+            &redacted["planned_values"]["root_module"]["child_modules"][0]["resources"][0];
+        assert_eq!(resource["values"]["password"], "[REDACTED]");
+        assert_eq!(resource["values"]["engine"], "postgres");
+    }
 }
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Secret store a claim variable can be read from
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SecretProvider {
+    /// AWS Secrets Manager, `key` is the name or ARN of the secret
+    #[serde(rename = "aws-secretsmanager")]
+    AwsSecretsManager,
+    /// AWS Systems Manager Parameter Store, `key` is the parameter name
+    #[serde(rename = "aws-ssm")]
+    AwsSsm,
+    /// Azure Key Vault, `key` is `<vault-name>/<secret-name>`
+    #[serde(rename = "azure-keyvault")]
+    AzureKeyVault,
+}
+
+impl std::fmt::Display for SecretProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            SecretProvider::AwsSecretsManager => "aws-secretsmanager",
+            SecretProvider::AwsSsm => "aws-ssm",
+            SecretProvider::AzureKeyVault => "azure-keyvault",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Reference to a secret in place of a variable value, e.g.
+/// `password: {secretRef: {provider: aws-secretsmanager, key: my/secret}}`.
+///
+/// Only the reference is stored with the deployment, the runner reads the secret when the job runs
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SecretRef {
+    pub provider: SecretProvider,
+    pub key: String,
+}
+
+impl SecretRef {
+    /// The secret reference `value` consists of, if it is a `{secretRef: {...}}` object. Objects
+    /// with other keys next to `secretRef` are ordinary values
+    pub fn from_value(value: &Value) -> Option<Result<SecretRef, serde_json::Error>> {
+        let object = value.as_object()?;
+        if object.len() != 1 {
+            return None;
+        }
+        let secret_ref = object.get("secretRef")?;
+        Some(serde_json::from_value(secret_ref.clone()))
+    }
+
+    pub fn is_secret_ref(value: &Value) -> bool {
+        SecretRef::from_value(value).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_secret_ref_from_value() {
+        let value =
+            json!({ "secretRef": { "provider": "aws-secretsmanager", "key": "my/secret" } });
+        assert_eq!(
+            SecretRef::from_value(&value).unwrap().unwrap(),
+            SecretRef {
+                provider: SecretProvider::AwsSecretsManager,
+                key: "my/secret".to_string(),
+            }
+        );
+
+        let unknown_provider = json!({ "secretRef": { "provider": "vault", "key": "my/secret" } });
+        assert!(SecretRef::from_value(&unknown_provider).unwrap().is_err());
+
+        assert!(SecretRef::from_value(&json!("my/secret")).is_none());
+        assert!(SecretRef::from_value(&json!({ "secretRef": {}, "other": 1 })).is_none());
+    }
+}
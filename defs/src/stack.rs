@@ -33,6 +33,43 @@ pub struct StackSpec {
     pub dependencies: Option<Vec<Dependency>>,
     #[serde(rename = "stackVariableDefinitions", default)]
     pub stack_variable_definitions: Option<Vec<TfVariable>>,
+    /// Which variables of the member claims are exposed on the stack and under which names
+    #[serde(rename = "memberVariables", default)]
+    pub member_variables: Option<MemberVariables>,
+    /// Teams (`@org/team`), users (`@user`) or emails maintaining the stack, used to route notifications
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct MemberVariables {
+    /// Exposure of member variables that are not listed
+    #[serde(default)]
+    pub default: VariableExposure,
+    #[serde(default)]
+    pub variables: Vec<MemberVariable>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableExposure {
+    /// The variable can be set on the stack
+    #[default]
+    Exposed,
+    /// The variable is fixed to the value in the claim, or the module default
+    Internal,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct MemberVariable {
+    /// Variable of a member claim, as `<claim>.<variableName>`
+    pub variable: String,
+    #[serde(default)]
+    pub exposure: VariableExposure,
+    /// Exposes the variable as the stack variable `stack.<name>` instead
+    pub name: Option<String>,
+    /// Replaces the description of the module variable
+    pub description: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
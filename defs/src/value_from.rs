@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Reference to an output of another deployment in place of a variable value, e.g.
+/// `vpcId: {valueFrom: {deployment: network/main, output: vpcId}}`.
+///
+/// Only the reference is stored with the deployment, the runner reads the output when the job
+/// runs, so dependents that are re-planned after the upstream changes get its new outputs
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ValueFrom {
+    /// Deployment ID of the upstream deployment, `<module>/<name>`
+    pub deployment: String,
+    /// Name of the output, in camelCase or as named in the module
+    pub output: String,
+    /// Environment of the upstream deployment, the environment of the claim if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+}
+
+impl ValueFrom {
+    /// The reference `value` consists of, if it is a `{valueFrom: {...}}` object. Objects with
+    /// other keys next to `valueFrom` are ordinary values
+    pub fn from_value(value: &Value) -> Option<Result<ValueFrom, serde_json::Error>> {
+        let object = value.as_object()?;
+        if object.len() != 1 {
+            return None;
+        }
+        let value_from = object.get("valueFrom")?;
+        Some(serde_json::from_value(value_from.clone()))
+    }
+
+    pub fn is_value_from(value: &Value) -> bool {
+        ValueFrom::from_value(value).is_some()
+    }
+
+    /// Environment of the upstream deployment for a claim in `environment`
+    pub fn environment_or<'a>(&'a self, environment: &'a str) -> &'a str {
+        self.environment.as_deref().unwrap_or(environment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_value_from_from_value() {
+        let value = json!({ "valueFrom": { "deployment": "network/main", "output": "vpcId" } });
+        let value_from = ValueFrom::from_value(&value).unwrap().unwrap();
+        assert_eq!(
+            value_from,
+            ValueFrom {
+                deployment: "network/main".to_string(),
+                output: "vpcId".to_string(),
+                environment: None,
+            }
+        );
+        assert_eq!(value_from.environment_or("cli/dev"), "cli/dev");
+
+        let missing_output = json!({ "valueFrom": { "deployment": "network/main" } });
+        assert!(ValueFrom::from_value(&missing_output).unwrap().is_err());
+
+        assert!(ValueFrom::from_value(&json!("network/main")).is_none());
+        assert!(ValueFrom::from_value(&json!({ "valueFrom": {}, "other": 1 })).is_none());
+    }
+}
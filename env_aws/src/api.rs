@@ -1,11 +1,13 @@
 use aws_sdk_lambda::primitives::Blob;
 use aws_sdk_lambda::types::InvocationType;
 use aws_sdk_sts::types::Credentials;
+use base64::{engine::general_purpose, Engine as _};
 use env_defs::{
     get_change_record_identifier, get_deployment_identifier, get_event_identifier,
     get_module_identifier, get_policy_identifier, CloudHandlerError, GenericFunctionResponse,
+    CHANGE_RECORD_PART_SEPARATOR,
 };
-use env_utils::{get_epoch, sanitize_payload_for_logging, zero_pad_semver};
+use env_utils::{aws_arn, get_epoch, sanitize_payload_for_logging, zero_pad_semver};
 use log::{error, info};
 use serde_json::{json, Value};
 
@@ -39,6 +41,29 @@ pub async fn get_user_id() -> Result<String, anyhow::Error> {
     Ok(user_id.to_string())
 }
 
+/// Username and password for the ECR registries of the account in `region`, decoded from an
+/// authorization token that is valid for 12 hours
+pub async fn get_ecr_credentials(region: &str) -> Result<(String, String), anyhow::Error> {
+    let shared_config = aws_config::from_env()
+        .region(aws_config::Region::new(region.to_string()))
+        .load()
+        .await;
+    let client = aws_sdk_ecr::Client::new(&shared_config);
+
+    let response = client.get_authorization_token().send().await?;
+    let token = response
+        .authorization_data()
+        .first()
+        .and_then(|data| data.authorization_token())
+        .ok_or_else(|| anyhow::anyhow!("No ECR authorization token returned"))?;
+    let decoded = String::from_utf8(general_purpose::STANDARD.decode(token)?)?;
+    let (username, password) = decoded
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("Unexpected format of the ECR authorization token"))?;
+
+    Ok((username.to_string(), password.to_string()))
+}
+
 pub async fn assume_role(
     role_arn: &str,
     session_name: &str,
@@ -144,9 +169,16 @@ pub async fn run_function(
         Err(_) => format!("infraweave-api-{}", api_environment),
     };
 
+    // The full ARN, as the partition of the region is not always `aws`
     let function_name = match std::env::var("TEST_MODE") {
         Ok(_) => api_function_name,
-        Err(_) => format!("{}:function:{}", project_id, api_function_name),
+        Err(_) => aws_arn(
+            region,
+            "lambda",
+            region,
+            project_id,
+            &format!("function:{}", api_function_name),
+        ),
     };
 
     let request = client
@@ -158,10 +190,17 @@ pub async fn run_function(
     let response = match request.send().await {
         Ok(response) => response,
         Err(e) => {
-            error!("Failed to invoke Lambda: {}\nAre you authenticated?", e);
-            eprintln!("Failed to invoke Lambda: {}\nAre you authenticated?", e);
-            let error_message = format!("Failed to invoke Lambda: {}\nAre you authenticated?", e);
-            return Err(CloudHandlerError::Unauthenticated(error_message));
+            // The service error carries the error code, e.g. AccessDeniedException or TooManyRequestsException
+            let error = match CloudHandlerError::from_message(format!("{:?}", e)) {
+                CloudHandlerError::OtherError(_) => CloudHandlerError::Unauthenticated(format!(
+                    "Failed to invoke Lambda: {}\nAre you authenticated?",
+                    e
+                )),
+                classified => classified,
+            };
+            error!("Failed to invoke Lambda: {}", error);
+            eprintln!("Failed to invoke Lambda: {}", error);
+            return Err(error);
         }
     };
 
@@ -185,9 +224,10 @@ pub async fn run_function(
                 "IndexError" => {
                     return Err(CloudHandlerError::NoAvailableRunner());
                 }
-                _ => {
-                    return Err(CloudHandlerError::OtherError(format!(
-                        "Error in Lambda response: {}",
+                error_type => {
+                    return Err(CloudHandlerError::from_message(format!(
+                        "Error in Lambda response ({}): {}",
+                        error_type,
                         parsed_json.get("errorMessage").unwrap()
                     )));
                 }
@@ -438,6 +478,33 @@ pub fn get_environment_variables_query() -> Value {
     })
 }
 
+/// Narrows a query to the items where each attribute equals its value, on top of any existing filter
+pub fn with_attribute_filters(mut query: Value, filters: &[(&str, &str)]) -> Value {
+    if filters.is_empty() {
+        return query;
+    }
+    if let Some(obj) = query.as_object_mut() {
+        let mut expressions: Vec<String> = obj
+            .get("FilterExpression")
+            .and_then(|f| f.as_str())
+            .map(|f| vec![format!("({})", f)])
+            .unwrap_or_default();
+        for (i, (attribute, value)) in filters.iter().enumerate() {
+            // Attribute names are aliased since e.g. `status` is a reserved word in DynamoDB
+            expressions.push(format!("#filter{i} = :filter{i}"));
+            obj.entry("ExpressionAttributeNames")
+                .or_insert_with(|| json!({}))[format!("#filter{i}")] = json!(attribute);
+            obj.entry("ExpressionAttributeValues")
+                .or_insert_with(|| json!({}))[format!(":filter{i}")] = json!(value);
+        }
+        obj.insert(
+            "FilterExpression".to_string(),
+            json!(expressions.join(" AND ")),
+        );
+    }
+    query
+}
+
 pub fn get_all_deployments_query(
     project_id: &str,
     region: &str,
@@ -601,6 +668,24 @@ pub fn get_dependents_query(
     })
 }
 
+pub fn get_job_queue_query(project_id: &str, region: &str) -> Value {
+    json!({
+        "KeyConditionExpression": "PK = :pk",
+        "ExpressionAttributeValues": {
+            ":pk": format!("JOBQUEUE#{}", get_deployment_identifier(project_id, region, "", "")),
+        }
+    })
+}
+
+pub fn get_job_stats_query(project_id: &str, region: &str, module: &str) -> Value {
+    json!({
+        "KeyConditionExpression": "PK = :pk",
+        "ExpressionAttributeValues": {
+            ":pk": format!("JOBSTATS#{}{}", get_deployment_identifier(project_id, region, "", ""), module),
+        }
+    })
+}
+
 pub fn get_deployments_to_driftcheck_query(project_id: &str, region: &str) -> Value {
     json!({
         "IndexName": "DriftCheckIndex",
@@ -623,6 +708,16 @@ pub fn get_all_projects_query() -> Value {
     })
 }
 
+pub fn get_config_items_query(kind: &str) -> Value {
+    // Only available using central role
+    json!({
+        "KeyConditionExpression": "PK = :PK",
+        "ExpressionAttributeValues": {
+            ":PK": kind,
+        }
+    })
+}
+
 pub fn get_current_project_query(project_id: &str) -> Value {
     json!({
         "IndexName": "ReverseIndex",
@@ -716,6 +811,23 @@ pub fn get_change_records_query(
     })
 }
 
+pub fn get_change_record_parts_query(
+    project_id: &str,
+    region: &str,
+    environment: &str,
+    deployment_id: &str,
+    job_id: &str,
+    change_type: &str,
+) -> Value {
+    json!({
+        "KeyConditionExpression": "PK = :pk AND begins_with(SK, :sk_prefix)",
+        "ExpressionAttributeValues": {
+            ":pk": format!("{}#{}", change_type, get_change_record_identifier(project_id, region, deployment_id, environment)),
+            ":sk_prefix": format!("{}{}", job_id, CHANGE_RECORD_PART_SEPARATOR)
+        }
+    })
+}
+
 // Policy
 
 pub fn get_newest_policy_version_query(policy: &str, environment: &str) -> Value {
@@ -745,6 +857,22 @@ pub fn get_policy_query(policy: &str, environment: &str, version: &str) -> Value
     })
 }
 
+pub fn get_newest_policy_pack_version_query(policy_pack: &str, environment: &str) -> Value {
+    json!({
+        "KeyConditionExpression": "PK = :policy_pack",
+        "ExpressionAttributeValues": {":policy_pack": format!("POLICYPACK#{}", get_policy_identifier(policy_pack, environment))},
+        "ScanIndexForward": false,
+        "Limit": 1,
+    })
+}
+
+pub fn get_all_policy_packs_query(environment: &str) -> Value {
+    json!({
+        "KeyConditionExpression": "PK = :current AND begins_with(SK, :policy_pack_prefix)",
+        "ExpressionAttributeValues": {":current": "CURRENT", ":policy_pack_prefix": format!("POLICYPACK#{}", environment)},
+    })
+}
+
 pub fn get_project_map_query() -> Value {
     json!({
         "KeyConditionExpression": "PK = :project_map",
@@ -1,12 +1,13 @@
 use async_trait::async_trait;
 use env_defs::{
     CloudHandlerError, CloudProvider, Dependent, DeploymentResp, EventData,
-    GenericFunctionResponse, InfraChangeRecord, JobStatus, ModuleResp, PolicyResp, ProjectData,
-    ProviderResp,
+    GenericFunctionResponse, InfraChangeRecord, JobQueueEntry, JobStats, JobStatus, ModuleResp,
+    PolicyPackResp, PolicyResp, ProjectData, ProviderResp,
 };
 use env_utils::{
     _get_change_records, _get_dependents, _get_deployment, _get_deployment_and_dependents,
-    _get_deployments, _get_events, _get_module_optional, _get_modules, _get_policies, _get_policy,
+    _get_deployments, _get_events, _get_job_queue, _get_job_stats, _get_module_optional,
+    _get_modules, _get_policies, _get_policy, _get_policy_pack, _get_policy_packs,
     _get_provider_optional, _get_providers, get_projects,
 };
 use serde_json::{json, Value};
@@ -394,6 +395,20 @@ impl CloudProvider for AwsCloudProvider {
         )
         .await
     }
+    async fn get_job_queue(&self) -> Result<Vec<JobQueueEntry>, anyhow::Error> {
+        _get_job_queue(
+            self,
+            crate::get_job_queue_query(&self.project_id, &self.region),
+        )
+        .await
+    }
+    async fn get_job_stats(&self, module: &str) -> Result<Vec<JobStats>, anyhow::Error> {
+        _get_job_stats(
+            self,
+            crate::get_job_stats_query(&self.project_id, &self.region, module),
+        )
+        .await
+    }
     async fn get_all_projects(&self) -> Result<Vec<ProjectData>, anyhow::Error> {
         get_projects(self, crate::get_all_projects_query()).await
     }
@@ -402,6 +417,10 @@ impl CloudProvider for AwsCloudProvider {
             .await
             .map(|mut projects| projects.pop().expect("No project found"))
     }
+    async fn get_config_items(&self, kind: &str) -> Result<Vec<Value>, anyhow::Error> {
+        self.read_db_generic("config", &crate::get_config_items_query(kind))
+            .await
+    }
     // Event
     async fn get_events(
         &self,
@@ -431,6 +450,13 @@ impl CloudProvider for AwsCloudProvider {
         )
         .await
     }
+    async fn get_event_items_before(&self, end_epoch: u128) -> Result<Vec<Value>, anyhow::Error> {
+        self.read_db_generic(
+            "events",
+            &crate::get_all_events_between_query(&self.region, 0, end_epoch),
+        )
+        .await
+    }
     // Change record
     async fn get_change_record(
         &self,
@@ -449,6 +475,14 @@ impl CloudProvider for AwsCloudProvider {
                 job_id,
                 change_type,
             ),
+            crate::get_change_record_parts_query(
+                &self.project_id,
+                &self.region,
+                environment,
+                deployment_id,
+                job_id,
+                change_type,
+            ),
         )
         .await
     }
@@ -491,6 +525,23 @@ impl CloudProvider for AwsCloudProvider {
     ) -> Result<PolicyResp, anyhow::Error> {
         _get_policy(self, crate::get_policy_query(policy, environment, version)).await
     }
+    async fn get_newest_policy_pack_version(
+        &self,
+        policy_pack: &str,
+        environment: &str,
+    ) -> Result<PolicyPackResp, anyhow::Error> {
+        _get_policy_pack(
+            self,
+            crate::get_newest_policy_pack_version_query(policy_pack, environment),
+        )
+        .await
+    }
+    async fn get_all_policy_packs(
+        &self,
+        environment: &str,
+    ) -> Result<Vec<PolicyPackResp>, anyhow::Error> {
+        _get_policy_packs(self, crate::get_all_policy_packs_query(environment)).await
+    }
     async fn get_environment_variables(&self) -> Result<serde_json::Value, anyhow::Error> {
         match crate::run_function(
             &self.function_endpoint,
@@ -500,12 +551,12 @@ impl CloudProvider for AwsCloudProvider {
         )
         .await
         {
-            Ok(response) => match response.payload.get("body") {
-                Some(variables) => Ok(variables.clone()),
-                None => Err(anyhow::anyhow!(
-                    "Environment variables not found in response"
-                )),
-            },
+            // Older API functions wrap the variables in a "body" field
+            Ok(response) => Ok(response
+                .payload
+                .get("body")
+                .cloned()
+                .unwrap_or(response.payload)),
             Err(e) => {
                 println!("Error getting environment variables: {:?}", e);
                 Err(anyhow::anyhow!(
@@ -515,12 +566,11 @@ impl CloudProvider for AwsCloudProvider {
         }
     }
 
-    async fn download_state_file(
+    async fn read_state_file(
         &self,
         environment: &str,
         deployment_id: &str,
-        output: Option<String>,
-    ) -> Result<(), anyhow::Error> {
+    ) -> Result<Vec<u8>, anyhow::Error> {
         let backend_args = self
             .get_backend_provider_arguments(environment, deployment_id)
             .await;
@@ -546,6 +596,16 @@ impl CloudProvider for AwsCloudProvider {
 
         let resp = client.get_object().bucket(bucket).key(key).send().await?;
         let data = resp.body.collect().await?.into_bytes();
+        Ok(data.to_vec())
+    }
+
+    async fn download_state_file(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+        output: Option<String>,
+    ) -> Result<(), anyhow::Error> {
+        let data = self.read_state_file(environment, deployment_id).await?;
 
         if let Some(output_path) = output {
             std::fs::write(output_path, &data)?;
@@ -556,4 +616,62 @@ impl CloudProvider for AwsCloudProvider {
 
         Ok(())
     }
+
+    async fn write_state_file(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+        data: &[u8],
+    ) -> Result<(), anyhow::Error> {
+        let backend_args = self
+            .get_backend_provider_arguments(environment, deployment_id)
+            .await;
+
+        let bucket = backend_args
+            .get("bucket")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("bucket not found in backend args"))?;
+        let key = backend_args
+            .get("key")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("key not found in backend args"))?;
+        let region = backend_args
+            .get("region")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&self.region);
+
+        let config = aws_config::from_env()
+            .region(aws_config::Region::new(region.to_string()))
+            .load()
+            .await;
+        let client = aws_sdk_s3::Client::new(&config);
+
+        client
+            .put_object()
+            .bucket(bucket)
+            .key(key)
+            .body(aws_sdk_s3::primitives::ByteStream::from(data.to_vec()))
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    async fn read_state_file_at(&self, address: &str) -> Result<Vec<u8>, anyhow::Error> {
+        let (bucket, key) = address
+            .strip_prefix("s3://")
+            .and_then(|path| path.split_once('/'))
+            .ok_or_else(|| {
+                anyhow::anyhow!("State address must be s3://<bucket>/<key>, got {}", address)
+            })?;
+
+        let config = aws_config::from_env()
+            .region(aws_config::Region::new(self.region.clone()))
+            .load()
+            .await;
+        let client = aws_sdk_s3::Client::new(&config);
+
+        let resp = client.get_object().bucket(bucket).key(key).send().await?;
+        let data = resp.body.collect().await?.into_bytes();
+        Ok(data.to_vec())
+    }
 }
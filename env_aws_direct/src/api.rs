@@ -2,6 +2,7 @@ use aws_sdk_sts::types::Credentials;
 use env_defs::{
     get_change_record_identifier, get_deployment_identifier, get_event_identifier,
     get_module_identifier, get_policy_identifier, CloudHandlerError, GenericFunctionResponse,
+    CHANGE_RECORD_PART_SEPARATOR,
 };
 use env_utils::{get_epoch, zero_pad_semver};
 use serde_json::{json, Value};
@@ -101,22 +102,22 @@ pub async fn run_function(
     let event = payload
         .get("event")
         .and_then(|e| e.as_str())
-        .ok_or_else(|| CloudHandlerError::OtherError("Missing event field".to_string()))?;
+        .ok_or_else(|| CloudHandlerError::Validation("Missing event field".to_string()))?;
 
     match event {
         "read_db" => {
             let table = payload
                 .get("table")
                 .and_then(|t| t.as_str())
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing table field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing table field".to_string()))?;
             let query = payload
                 .get("data")
                 .and_then(|d| d.get("query"))
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing query field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing query field".to_string()))?;
 
             match read_db_direct(table, query, Some(region)).await {
                 Ok(data) => Ok(GenericFunctionResponse { payload: data }),
-                Err(e) => Err(CloudHandlerError::OtherError(format!(
+                Err(e) => Err(CloudHandlerError::from_message(format!(
                     "Direct DB read failed: {}",
                     e
                 ))),
@@ -127,11 +128,11 @@ pub async fn run_function(
                 .get("data")
                 .and_then(|d| d.get("job_id"))
                 .and_then(|j| j.as_str())
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing job_id field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing job_id field".to_string()))?;
 
             match get_job_status_direct(job_id, Some(region)).await {
                 Ok(data) => Ok(GenericFunctionResponse { payload: data }),
-                Err(e) => Err(CloudHandlerError::OtherError(format!(
+                Err(e) => Err(CloudHandlerError::from_message(format!(
                     "Direct get_job_status failed: {}",
                     e
                 ))),
@@ -140,17 +141,17 @@ pub async fn run_function(
         "read_logs" => {
             let data = payload
                 .get("data")
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing data field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing data field".to_string()))?;
             let job_id = data
                 .get("job_id")
                 .and_then(|j| j.as_str())
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing job_id field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing job_id field".to_string()))?;
             let next_token = data.get("next_token").and_then(|t| t.as_str());
             let limit = data.get("limit").and_then(|l| l.as_i64()).map(|l| l as i32);
 
             match read_logs_direct(job_id, project_id, region, next_token, limit).await {
                 Ok(data) => Ok(GenericFunctionResponse { payload: data }),
-                Err(e) => Err(CloudHandlerError::OtherError(format!(
+                Err(e) => Err(CloudHandlerError::from_message(format!(
                     "Direct read_logs failed: {}",
                     e
                 ))),
@@ -158,7 +159,7 @@ pub async fn run_function(
         }
         "get_environment_variables" => match get_environment_variables_direct() {
             Ok(data) => Ok(GenericFunctionResponse { payload: data }),
-            Err(e) => Err(CloudHandlerError::OtherError(format!(
+            Err(e) => Err(CloudHandlerError::from_message(format!(
                 "Direct get_environment_variables failed: {}",
                 e
             ))),
@@ -169,16 +170,16 @@ pub async fn run_function(
 
             let data = payload
                 .get("data")
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing data field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing data field".to_string()))?;
             let key = data
                 .get("key")
                 .and_then(|k| k.as_str())
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing key field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing key field".to_string()))?;
             let bucket = data
                 .get("bucket_name")
                 .and_then(|b| b.as_str())
                 .ok_or_else(|| {
-                    CloudHandlerError::OtherError("Missing bucket_name field".to_string())
+                    CloudHandlerError::Validation("Missing bucket_name field".to_string())
                 })?;
 
             let actual_bucket =
@@ -190,7 +191,7 @@ pub async fn run_function(
                 .expires_in(Duration::from_secs(60))
                 .build()
                 .map_err(|e| {
-                    CloudHandlerError::OtherError(format!(
+                    CloudHandlerError::from_message(format!(
                         "Failed to build presigning config: {}",
                         e
                     ))
@@ -203,7 +204,7 @@ pub async fn run_function(
                 .presigned(presigning_config)
                 .await
                 .map_err(|e| {
-                    CloudHandlerError::OtherError(format!(
+                    CloudHandlerError::from_message(format!(
                         "Failed to generate presigned URL: {}",
                         e
                     ))
@@ -217,22 +218,22 @@ pub async fn run_function(
         "upload_file_base64" => {
             let data = payload
                 .get("data")
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing data field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing data field".to_string()))?;
             let key = data
                 .get("key")
                 .and_then(|k| k.as_str())
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing key field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing key field".to_string()))?;
             let bucket = data
                 .get("bucket_name")
                 .and_then(|b| b.as_str())
                 .ok_or_else(|| {
-                    CloudHandlerError::OtherError("Missing bucket_name field".to_string())
+                    CloudHandlerError::Validation("Missing bucket_name field".to_string())
                 })?;
             let base64_content = data
                 .get("base64_content")
                 .and_then(|c| c.as_str())
                 .ok_or_else(|| {
-                    CloudHandlerError::OtherError("Missing base64_content field".to_string())
+                    CloudHandlerError::Validation("Missing base64_content field".to_string())
                 })?;
 
             let actual_bucket =
@@ -248,46 +249,52 @@ pub async fn run_function(
             let bytes = base64::engine::general_purpose::STANDARD
                 .decode(base64_content)
                 .map_err(|e| {
-                    CloudHandlerError::OtherError(format!("Failed to decode base64: {}", e))
+                    CloudHandlerError::Validation(format!("Failed to decode base64: {}", e))
                 })?;
 
             let client = get_s3_client_direct(region).await;
 
-            client
-                .put_object()
-                .bucket(&actual_bucket)
-                .key(key)
-                .body(ByteStream::from(bytes))
-                .send()
-                .await
-                .map_err(|e| {
-                    log::error!("Failed to upload {} to S3: {:?}", key, e);
-                    CloudHandlerError::OtherError(format!("Failed to upload to S3: {:?}", e))
-                })?;
+            let version_id = crate::direct_impl::put_object_direct(
+                &client,
+                &actual_bucket,
+                key,
+                bytes,
+                crate::direct_impl::object_lock_days(bucket),
+            )
+            .await
+            .map_err(|e| {
+                log::error!("Failed to upload {} to S3: {:?}", key, e);
+                CloudHandlerError::from_message(format!("Failed to upload to S3: {:?}", e))
+            })?;
 
             log::info!("Successfully uploaded {} to S3", key);
             Ok(GenericFunctionResponse {
-                payload: json!({ "success": true, "key": key, "bucket": actual_bucket }),
+                payload: json!({
+                    "success": true,
+                    "key": key,
+                    "bucket": actual_bucket,
+                    "version_id": version_id,
+                }),
             })
         }
         "upload_file_url" => {
             let data = payload
                 .get("data")
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing data field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing data field".to_string()))?;
             let key = data
                 .get("key")
                 .and_then(|k| k.as_str())
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing key field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing key field".to_string()))?;
             let bucket = data
                 .get("bucket_name")
                 .and_then(|b| b.as_str())
                 .ok_or_else(|| {
-                    CloudHandlerError::OtherError("Missing bucket_name field".to_string())
+                    CloudHandlerError::Validation("Missing bucket_name field".to_string())
                 })?;
             let url = data
                 .get("url")
                 .and_then(|u| u.as_str())
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing url field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing url field".to_string()))?;
 
             let actual_bucket =
                 get_bucket_name_for_region(bucket, region).unwrap_or_else(|_| bucket.to_string());
@@ -299,11 +306,11 @@ pub async fn run_function(
             );
 
             let response = reqwest::get(url).await.map_err(|e| {
-                CloudHandlerError::OtherError(format!("Failed to download from URL: {}", e))
+                CloudHandlerError::from_message(format!("Failed to download from URL: {}", e))
             })?;
 
             let bytes = response.bytes().await.map_err(|e| {
-                CloudHandlerError::OtherError(format!("Failed to read response bytes: {}", e))
+                CloudHandlerError::from_message(format!("Failed to read response bytes: {}", e))
             })?;
 
             let client = get_s3_client_direct(region).await;
@@ -317,7 +324,7 @@ pub async fn run_function(
                 .await
                 .map_err(|e| {
                     log::error!("Failed to upload {} to S3: {:?}", key, e);
-                    CloudHandlerError::OtherError(format!("Failed to upload to S3: {:?}", e))
+                    CloudHandlerError::from_message(format!("Failed to upload to S3: {:?}", e))
                 })?;
 
             log::info!("Successfully uploaded {} to S3", key);
@@ -328,16 +335,16 @@ pub async fn run_function(
         "download_file" => {
             let data = payload
                 .get("data")
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing data field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing data field".to_string()))?;
             let key = data
                 .get("key")
                 .and_then(|k| k.as_str())
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing key field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing key field".to_string()))?;
             let bucket = data
                 .get("bucket_name")
                 .and_then(|b| b.as_str())
                 .ok_or_else(|| {
-                    CloudHandlerError::OtherError("Missing bucket_name field".to_string())
+                    CloudHandlerError::Validation("Missing bucket_name field".to_string())
                 })?;
 
             let actual_bucket =
@@ -365,7 +372,7 @@ pub async fn run_function(
                         .collect()
                         .await
                         .map_err(|e| {
-                            CloudHandlerError::OtherError(format!(
+                            CloudHandlerError::from_message(format!(
                                 "Failed to read S3 object body: {}",
                                 e
                             ))
@@ -381,7 +388,7 @@ pub async fn run_function(
                 }
                 Err(e) => {
                     log::error!("Failed to download {}/{}: {:?}", actual_bucket, key, e);
-                    Err(CloudHandlerError::OtherError(format!(
+                    Err(CloudHandlerError::from_message(format!(
                         "Failed to download from S3: {}",
                         e
                     )))
@@ -391,11 +398,11 @@ pub async fn run_function(
         "transact_write" => {
             let items = payload
                 .get("items")
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing items field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing items field".to_string()))?;
 
             match transact_write_direct(items, Some(region)).await {
                 Ok(data) => Ok(GenericFunctionResponse { payload: data }),
-                Err(e) => Err(CloudHandlerError::OtherError(format!(
+                Err(e) => Err(CloudHandlerError::from_message(format!(
                     "Direct transact_write failed: {}",
                     e
                 ))),
@@ -405,14 +412,14 @@ pub async fn run_function(
             let table = payload
                 .get("table")
                 .and_then(|t| t.as_str())
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing table field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing table field".to_string()))?;
             let data = payload
                 .get("data")
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing data field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing data field".to_string()))?;
 
             match insert_db_direct(table, data, Some(region)).await {
                 Ok(data) => Ok(GenericFunctionResponse { payload: data }),
-                Err(e) => Err(CloudHandlerError::OtherError(format!(
+                Err(e) => Err(CloudHandlerError::from_message(format!(
                     "Direct insert_db failed: {}",
                     e
                 ))),
@@ -421,9 +428,9 @@ pub async fn run_function(
         "publish_notification" => {
             let data = payload
                 .get("data")
-                .ok_or_else(|| CloudHandlerError::OtherError("Missing data field".to_string()))?;
+                .ok_or_else(|| CloudHandlerError::Validation("Missing data field".to_string()))?;
             let message_value = data.get("message").ok_or_else(|| {
-                CloudHandlerError::OtherError("Missing message field".to_string())
+                CloudHandlerError::Validation("Missing message field".to_string())
             })?;
             let message = match message_value {
                 Value::String(s) => s.clone(),
@@ -436,15 +443,20 @@ pub async fn run_function(
                 .and_then(|s| s.as_str())
                 .unwrap_or("Unkown Subject");
 
-            match publish_notification_direct(&message, Some(subject)).await {
+            let owners: Vec<String> = data
+                .get("owners")
+                .and_then(|o| serde_json::from_value(o.clone()).ok())
+                .unwrap_or_default();
+
+            match publish_notification_direct(&message, Some(subject), &owners).await {
                 Ok(data) => Ok(GenericFunctionResponse { payload: data }),
-                Err(e) => Err(CloudHandlerError::OtherError(format!(
+                Err(e) => Err(CloudHandlerError::from_message(format!(
                     "Direct publish_notification failed: {}",
                     e
                 ))),
             }
         }
-        _ => Err(CloudHandlerError::OtherError(format!(
+        _ => Err(CloudHandlerError::Validation(format!(
             "Unknown event type: {}",
             event
         ))),
@@ -743,6 +755,33 @@ pub fn get_stack_version_query(module: &str, track: &str, version: &str) -> Valu
     get_module_version_query(module, track, version)
 }
 
+/// Narrows a query to the items where each attribute equals its value, on top of any existing filter
+pub fn with_attribute_filters(mut query: Value, filters: &[(&str, &str)]) -> Value {
+    if filters.is_empty() {
+        return query;
+    }
+    if let Some(obj) = query.as_object_mut() {
+        let mut expressions: Vec<String> = obj
+            .get("FilterExpression")
+            .and_then(|f| f.as_str())
+            .map(|f| vec![format!("({})", f)])
+            .unwrap_or_default();
+        for (i, (attribute, value)) in filters.iter().enumerate() {
+            // Attribute names are aliased since e.g. `status` is a reserved word in DynamoDB
+            expressions.push(format!("#filter{i} = :filter{i}"));
+            obj.entry("ExpressionAttributeNames")
+                .or_insert_with(|| json!({}))[format!("#filter{i}")] = json!(attribute);
+            obj.entry("ExpressionAttributeValues")
+                .or_insert_with(|| json!({}))[format!(":filter{i}")] = json!(value);
+        }
+        obj.insert(
+            "FilterExpression".to_string(),
+            json!(expressions.join(" AND ")),
+        );
+    }
+    query
+}
+
 pub fn get_all_deployments_query(
     project_id: &str,
     region: &str,
@@ -968,6 +1007,24 @@ pub fn get_dependents_query(
     })
 }
 
+pub fn get_job_queue_query(project_id: &str, region: &str) -> Value {
+    json!({
+        "KeyConditionExpression": "PK = :pk",
+        "ExpressionAttributeValues": {
+            ":pk": format!("JOBQUEUE#{}", get_deployment_identifier(project_id, region, "", "")),
+        }
+    })
+}
+
+pub fn get_job_stats_query(project_id: &str, region: &str, module: &str) -> Value {
+    json!({
+        "KeyConditionExpression": "PK = :pk",
+        "ExpressionAttributeValues": {
+            ":pk": format!("JOBSTATS#{}{}", get_deployment_identifier(project_id, region, "", ""), module),
+        }
+    })
+}
+
 pub fn get_deployments_to_driftcheck_query(project_id: &str, region: &str) -> Value {
     json!({
         "IndexName": "DriftCheckIndex",
@@ -990,6 +1047,16 @@ pub fn get_all_projects_query() -> Value {
     })
 }
 
+pub fn get_config_items_query(kind: &str) -> Value {
+    // Only available using central role
+    json!({
+        "KeyConditionExpression": "PK = :PK",
+        "ExpressionAttributeValues": {
+            ":PK": kind,
+        }
+    })
+}
+
 pub fn get_current_project_query(project_id: &str) -> Value {
     json!({
         "IndexName": "ReverseIndex",
@@ -1083,6 +1150,23 @@ pub fn get_change_records_query(
     })
 }
 
+pub fn get_change_record_parts_query(
+    project_id: &str,
+    region: &str,
+    environment: &str,
+    deployment_id: &str,
+    job_id: &str,
+    change_type: &str,
+) -> Value {
+    json!({
+        "KeyConditionExpression": "PK = :pk AND begins_with(SK, :sk_prefix)",
+        "ExpressionAttributeValues": {
+            ":pk": format!("{}#{}", change_type, get_change_record_identifier(project_id, region, deployment_id, environment)),
+            ":sk_prefix": format!("{}{}", job_id, CHANGE_RECORD_PART_SEPARATOR)
+        }
+    })
+}
+
 // Policy
 
 pub fn get_newest_policy_version_query(policy: &str, environment: &str) -> Value {
@@ -1112,6 +1196,22 @@ pub fn get_policy_query(policy: &str, environment: &str, version: &str) -> Value
     })
 }
 
+pub fn get_newest_policy_pack_version_query(policy_pack: &str, environment: &str) -> Value {
+    json!({
+        "KeyConditionExpression": "PK = :policy_pack",
+        "ExpressionAttributeValues": {":policy_pack": format!("POLICYPACK#{}", get_policy_identifier(policy_pack, environment))},
+        "ScanIndexForward": false,
+        "Limit": 1,
+    })
+}
+
+pub fn get_all_policy_packs_query(environment: &str) -> Value {
+    json!({
+        "KeyConditionExpression": "PK = :current AND begins_with(SK, :policy_pack_prefix)",
+        "ExpressionAttributeValues": {":current": "CURRENT", ":policy_pack_prefix": format!("POLICYPACK#{}", environment)},
+    })
+}
+
 pub fn get_project_map_query() -> Value {
     json!({
         "KeyConditionExpression": "PK = :project_map",
@@ -1,3 +1,4 @@
+use env_utils::aws_partition;
 use std::env;
 
 pub async fn set_backend(
@@ -15,6 +16,10 @@ pub async fn set_backend(
     exec.arg(format!("-backend-config=bucket={}", tf_bucket));
     exec.arg(format!("-backend-config=key={}", key));
     exec.arg(format!("-backend-config=region={}", region));
+    // The global STS endpoint only serves the aws partition, GovCloud and China use the one of the region
+    if aws_partition(&region) != "aws" {
+        exec.arg(format!("-backend-config=sts_region={}", region));
+    }
 
     // // In test mode, use MinIO for state storage instead of real S3
     // #[cfg(feature = "test-mode")]
@@ -2,8 +2,9 @@
 use anyhow::{anyhow, Result};
 use aws_sdk_dynamodb::error::ProvideErrorMetadata;
 use aws_sdk_dynamodb::types::AttributeValue;
+use env_defs::{AwsAccessSettings, ProjectData};
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::utils::get_table_name;
 
@@ -401,11 +402,18 @@ pub async fn read_logs_direct(
 }
 
 pub fn get_environment_variables_direct() -> Result<Value> {
-    Ok(json!({
-        "DYNAMODB_TF_LOCKS_TABLE_ARN": std::env::var("DYNAMODB_TF_LOCKS_TABLE_ARN").ok(),
-        "TF_STATE_S3_BUCKET": std::env::var("TF_STATE_S3_BUCKET").ok(),
-        "REGION": std::env::var("REGION").ok(),
-    }))
+    let mut variables = env_utils::get_platform_environment_variables();
+    variables.extend(
+        json!({
+            "DYNAMODB_TF_LOCKS_TABLE_ARN": std::env::var("DYNAMODB_TF_LOCKS_TABLE_ARN").ok(),
+            "TF_STATE_S3_BUCKET": std::env::var("TF_STATE_S3_BUCKET").ok(),
+            "REGION": std::env::var("REGION").ok(),
+        })
+        .as_object()
+        .cloned()
+        .unwrap_or_default(),
+    );
+    Ok(Value::Object(variables))
 }
 
 pub async fn transact_write_direct(items: &Value, region_opt: Option<&str>) -> Result<Value> {
@@ -457,6 +465,12 @@ pub async fn transact_write_direct(items: &Value, region_opt: Option<&str>) -> R
             let put_request = aws_sdk_dynamodb::types::Put::builder()
                 .table_name(table_name)
                 .set_item(Some(dynamo_item))
+                .set_condition_expression(
+                    put_op
+                        .get("ConditionExpression")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string()),
+                )
                 .build()
                 .map_err(|e| anyhow::anyhow!("Failed to build Put request: {}", e))?;
 
@@ -500,6 +514,12 @@ pub async fn transact_write_direct(items: &Value, region_opt: Option<&str>) -> R
             let delete_request = aws_sdk_dynamodb::types::Delete::builder()
                 .table_name(table_name)
                 .set_key(Some(key))
+                .set_condition_expression(
+                    delete_op
+                        .get("ConditionExpression")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string()),
+                )
                 .build()
                 .map_err(|e| anyhow::anyhow!("Failed to build Delete request: {}", e))?;
 
@@ -669,12 +689,62 @@ async fn get_s3_client(region_opt: Option<&str>) -> aws_sdk_s3::Client {
     }
 }
 
+/// Environment variable with the number of days module artifacts stay locked after upload
+const MODULE_OBJECT_LOCK_DAYS_ENV: &str = "INFRAWEAVE_MODULE_OBJECT_LOCK_DAYS";
+
+/// Days an artifact uploaded to `bucket` (the logical bucket name) is locked for. Only artifacts in
+/// the `modules` bucket are locked, and only when INFRAWEAVE_MODULE_OBJECT_LOCK_DAYS is set, in
+/// which case the bucket must be versioned with object lock enabled
+pub fn object_lock_days(bucket: &str) -> Option<u64> {
+    if bucket != "modules" {
+        return None;
+    }
+    std::env::var(MODULE_OBJECT_LOCK_DAYS_ENV)
+        .ok()?
+        .parse()
+        .ok()
+        .filter(|days| *days > 0)
+}
+
+/// Uploads `content` with its SHA-256 checksum, which S3 verifies and keeps with the object. With
+/// `lock_days` the new object version is locked in compliance mode, so nobody can overwrite or
+/// delete it before the retention has passed. Returns the version id in versioned buckets
+pub async fn put_object_direct(
+    client: &aws_sdk_s3::Client,
+    bucket_name: &str,
+    key: &str,
+    content: Vec<u8>,
+    lock_days: Option<u64>,
+) -> Result<Option<String>> {
+    use aws_sdk_s3::types::ObjectLockMode;
+    use base64::Engine;
+    use sha2::{Digest, Sha256};
+
+    let checksum = base64::engine::general_purpose::STANDARD.encode(Sha256::digest(&content));
+    let mut request = client
+        .put_object()
+        .bucket(bucket_name)
+        .key(key)
+        .checksum_sha256(checksum)
+        .body(content.into());
+    if let Some(days) = lock_days {
+        let retain_until =
+            std::time::SystemTime::now() + std::time::Duration::from_secs(days * 24 * 60 * 60);
+        request = request
+            .object_lock_mode(ObjectLockMode::Compliance)
+            .object_lock_retain_until_date(aws_sdk_s3::primitives::DateTime::from(retain_until));
+    }
+    let response = request.send().await?;
+    Ok(response.version_id().map(str::to_string))
+}
+
 pub async fn upload_file_base64_direct(
     bucket_name: &str,
     key: &str,
     base64_content: &str,
     region: Option<&str>,
-) -> Result<()> {
+    lock_days: Option<u64>,
+) -> Result<Option<String>> {
     use base64::Engine;
 
     let content = base64::engine::general_purpose::STANDARD
@@ -682,23 +752,16 @@ pub async fn upload_file_base64_direct(
         .map_err(|e| anyhow!("Failed to decode base64: {}", e))?;
 
     log::info!(
-        "Uploading {} bytes to {}/{} (region: {:?})",
+        "Uploading {} bytes to {}/{} (region: {:?}, locked for {:?} days)",
         content.len(),
         bucket_name,
         key,
-        region
+        region,
+        lock_days
     );
 
     let client = get_s3_client(region).await;
-    client
-        .put_object()
-        .bucket(bucket_name)
-        .key(key)
-        .body(content.into())
-        .send()
-        .await?;
-
-    Ok(())
+    put_object_direct(&client, bucket_name, key, content, lock_days).await
 }
 
 /// Upload a file from URL to S3. Returns true if the object already existed.
@@ -816,26 +879,101 @@ pub(crate) async fn get_aws_config(region: Option<&str>) -> aws_config::SdkConfi
     loader.load().await
 }
 
+/// Reads how to access a project's AWS account from its settings, falling back to the defaults
+/// (no role chain, external ID or session tags) if the project can't be read
+async fn get_project_aws_access(project_id: &str) -> AwsAccessSettings {
+    let query = crate::get_current_project_query(project_id);
+    match read_db_direct("config", &query, None).await {
+        Ok(response) => response["Items"]
+            .as_array()
+            .and_then(|items| items.first())
+            .and_then(|item| serde_json::from_value::<ProjectData>(item.clone()).ok())
+            .map(|project| project.settings.aws_access)
+            .unwrap_or_default(),
+        Err(e) => {
+            log::warn!(
+                "Failed to read access settings of project {}, assuming the workload role directly: {:?}",
+                project_id,
+                e
+            );
+            AwsAccessSettings::default()
+        }
+    }
+}
+
 async fn assume_role_config(
     project_id: &str,
     role_name: &str,
     session_name: &str,
     region: &str,
 ) -> Result<aws_config::SdkConfig> {
-    let config = get_aws_config(Some(region)).await;
-    let sts_client = aws_sdk_sts::Client::new(&config);
+    let access = get_project_aws_access(project_id).await;
+
+    // Walk the role chain (e.g. hub -> spoke) before assuming the workload role
+    let mut config = get_aws_config(Some(region)).await;
+    for step in &access.role_chain {
+        config = assume_role_step(
+            &config,
+            &step.role_arn,
+            step.external_id.as_deref(),
+            &step.session_tags,
+            session_name,
+            region,
+        )
+        .await?;
+    }
 
     let environment = get_env_var("ENVIRONMENT")?;
-    let role_arn = format!(
-        "arn:aws:iam::{}:role/{}-{}",
-        project_id, role_name, environment
+    let role_arn = env_utils::aws_arn(
+        region,
+        "iam",
+        "",
+        project_id,
+        &format!("role/{}-{}", role_name, environment),
     );
+    let config = assume_role_step(
+        &config,
+        &role_arn,
+        access.external_id.as_deref(),
+        &access.session_tags,
+        session_name,
+        region,
+    )
+    .await?;
+
+    log::info!("Successfully assumed role in account {}", project_id);
+    Ok(config)
+}
+
+/// Assumes `role_arn` with the credentials of `config`, returning a config for the assumed role
+async fn assume_role_step(
+    config: &aws_config::SdkConfig,
+    role_arn: &str,
+    external_id: Option<&str>,
+    session_tags: &BTreeMap<String, String>,
+    session_name: &str,
+    region: &str,
+) -> Result<aws_config::SdkConfig> {
+    let sts_client = aws_sdk_sts::Client::new(config);
     log::info!("Assuming role: {}", role_arn);
 
+    let tags = session_tags
+        .iter()
+        .map(|(key, value)| {
+            aws_sdk_sts::types::Tag::builder()
+                .key(key)
+                .value(value)
+                .build()
+                .map_err(|e| anyhow!("Invalid session tag {}: {:?}", key, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
     let assumed_role = sts_client
         .assume_role()
-        .role_arn(&role_arn)
+        .role_arn(role_arn)
         .role_session_name(session_name)
+        .set_external_id(external_id.map(str::to_string))
+        .set_tags((!tags.is_empty()).then_some(tags))
         .send()
         .await
         .map_err(|e| {
@@ -847,8 +985,6 @@ async fn assume_role_config(
         .credentials()
         .ok_or_else(|| anyhow!("No credentials returned from assume role"))?;
 
-    log::info!("Successfully assumed role in account {}", project_id);
-
     let creds = aws_credential_types::Credentials::new(
         credentials.access_key_id(),
         credentials.secret_access_key(),
@@ -1239,7 +1375,11 @@ pub async fn read_logs_cross_account(
     Ok(response)
 }
 
-pub async fn publish_notification_direct(message: &str, subject: Option<&str>) -> Result<Value> {
+pub async fn publish_notification_direct(
+    message: &str,
+    subject: Option<&str>,
+    owners: &[String],
+) -> Result<Value> {
     let topic_arn = get_env_var("NOTIFICATION_TOPIC_ARN")?;
     let config = get_aws_config(None).await;
     let sns_client = aws_sdk_sns::Client::new(&config);
@@ -1250,6 +1390,16 @@ pub async fn publish_notification_direct(message: &str, subject: Option<&str>) -
         request = request.subject(subj);
     }
 
+    // Lets subscriptions route notifications to module owners with a filter policy
+    if !owners.is_empty() {
+        let owners_attribute = aws_sdk_sns::types::MessageAttributeValue::builder()
+            .data_type("String.Array")
+            .string_value(serde_json::to_string(owners)?)
+            .build()
+            .map_err(|e| anyhow!("Failed to build owners message attribute: {}", e))?;
+        request = request.message_attributes("owners", owners_attribute);
+    }
+
     let result = request.send().await.map_err(|e| {
         anyhow!(
             "SNS publish to {} failed: {}",
@@ -20,10 +20,13 @@ pub use api::{
     get_all_latest_stacks_query,
     get_all_module_versions_query,
     get_all_policies_query,
+    get_all_policy_packs_query,
     get_all_projects_query,
     get_all_regions_query,
     get_all_stack_versions_query,
+    get_change_record_parts_query,
     get_change_records_query,
+    get_config_items_query,
     get_current_project_query,
     get_dependents_query,
     get_deployment_and_dependents_query,
@@ -33,10 +36,13 @@ pub use api::{
     get_deployments_to_driftcheck_query,
     get_deployments_using_module_query,
     get_events_query,
+    get_job_queue_query,
+    get_job_stats_query,
     get_latest_module_version_query,
     get_latest_provider_version_query,
     get_latest_stack_version_query,
     get_module_version_query,
+    get_newest_policy_pack_version_query,
     get_newest_policy_version_query,
     get_plan_deployment_query,
     get_policy_query,
@@ -47,6 +53,7 @@ pub use api::{
     get_user_id,
     read_db,
     run_function,
+    with_attribute_filters,
 };
 pub use backend::set_backend;
 pub use http_auth::{
@@ -65,9 +72,9 @@ pub use utils::{
 pub use direct_impl::{
     download_file_as_bytes_direct, download_file_as_string_direct, generate_presigned_url_direct,
     get_environment_variables_direct, get_job_status_cross_account, insert_db_direct,
-    publish_notification_direct, read_db_direct, read_logs_cross_account,
-    start_runner_cross_account, transact_write_direct, upload_file_base64_direct,
-    upload_file_url_direct,
+    object_lock_days, publish_notification_direct, put_object_direct, read_db_direct,
+    read_logs_cross_account, start_runner_cross_account, transact_write_direct,
+    upload_file_base64_direct, upload_file_url_direct,
 };
 
 pub use local_bootstrap::{bootstrap_dynamodb_tables, create_s3_buckets};
@@ -1,3 +1,6 @@
+#[cfg(not(feature = "test-mode"))]
+use crate::credential::AzureAuthMode;
+
 pub async fn set_backend(
     exec: &mut tokio::process::Command,
     storage_basepath: &str,
@@ -29,7 +32,39 @@ pub async fn set_backend(
         exec.arg(format!("-backend-config=container_name={}", tf_bucket));
         exec.arg(format!("-backend-config=key={}", key));
         exec.arg(format!("-backend-config=subscription_id={}", account_id));
+
+        let auth_mode = match AzureAuthMode::from_env() {
+            Ok(auth_mode) => auth_mode,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        for config in auth_backend_config(auth_mode) {
+            exec.arg(format!("-backend-config={}", config));
+        }
+    }
+}
+
+/// Backend settings that make the azurerm backend authenticate the same way as InfraWeave
+#[cfg(not(feature = "test-mode"))]
+fn auth_backend_config(auth_mode: AzureAuthMode) -> Vec<String> {
+    let mut config = match auth_mode {
+        AzureAuthMode::DeveloperTools => return vec![],
+        AzureAuthMode::ManagedIdentity => vec!["use_msi=true".to_string()],
+        AzureAuthMode::WorkloadIdentity => vec!["use_aks_workload_identity=true".to_string()],
+        // The backend requests the token of the GitHub Actions job itself
+        AzureAuthMode::GitHubOidc => vec!["use_oidc=true".to_string()],
+    };
+    for (key, env_var) in [
+        ("client_id", "AZURE_CLIENT_ID"),
+        ("tenant_id", "AZURE_TENANT_ID"),
+    ] {
+        if let Ok(value) = std::env::var(env_var) {
+            config.push(format!("{}={}", key, value));
+        }
     }
+    config
 }
 
 #[cfg(not(feature = "test-mode"))]
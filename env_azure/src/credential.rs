@@ -0,0 +1,151 @@
+use anyhow::{anyhow, Result};
+use azure_core::credentials::TokenCredential;
+use azure_identity::{
+    DeveloperToolsCredential, ManagedIdentityCredential, ManagedIdentityCredentialOptions,
+    UserAssignedId, WorkloadIdentityCredential, WorkloadIdentityCredentialOptions,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+// Audience Microsoft Entra ID expects in federated tokens
+const GITHUB_OIDC_AUDIENCE: &str = "api://AzureADTokenExchange";
+
+const ACR_SCOPE: &str = "https://containerregistry.azure.net/.default";
+// Username Azure Container Registry expects along with a refresh token
+const ACR_TOKEN_USERNAME: &str = "00000000-0000-0000-0000-000000000000";
+
+static CREDENTIAL: OnceCell<Arc<dyn TokenCredential>> = OnceCell::const_new();
+
+/// How InfraWeave authenticates to Azure, set with `AZURE_AUTH_MODE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AzureAuthMode {
+    /// Azure CLI or Azure Developer CLI login, the default
+    DeveloperTools,
+    /// Managed identity of the host, the user-assigned identity in `AZURE_CLIENT_ID` if set
+    ManagedIdentity,
+    /// Federated token in `AZURE_FEDERATED_TOKEN_FILE`, as injected by AKS workload identity
+    WorkloadIdentity,
+    /// OIDC token of the GitHub Actions job, federated with the app in `AZURE_CLIENT_ID`
+    GitHubOidc,
+}
+
+impl AzureAuthMode {
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("AZURE_AUTH_MODE") {
+            Ok(mode) => mode.parse(),
+            Err(_) => Ok(AzureAuthMode::DeveloperTools),
+        }
+    }
+}
+
+impl std::str::FromStr for AzureAuthMode {
+    type Err = anyhow::Error;
+
+    fn from_str(mode: &str) -> Result<Self> {
+        match mode {
+            "" | "developer-tools" => Ok(AzureAuthMode::DeveloperTools),
+            "managed-identity" => Ok(AzureAuthMode::ManagedIdentity),
+            "workload-identity" => Ok(AzureAuthMode::WorkloadIdentity),
+            "github-oidc" => Ok(AzureAuthMode::GitHubOidc),
+            _ => Err(anyhow!(
+                "Unsupported AZURE_AUTH_MODE \"{}\", expected developer-tools, managed-identity, workload-identity or github-oidc",
+                mode
+            )),
+        }
+    }
+}
+
+/// Azure credential for the auth mode in `AZURE_AUTH_MODE`, created once and shared so tokens are cached
+pub async fn get_credential() -> Result<Arc<dyn TokenCredential>> {
+    CREDENTIAL
+        .get_or_try_init(|| async { create_credential(AzureAuthMode::from_env()?).await })
+        .await
+        .cloned()
+}
+
+async fn create_credential(mode: AzureAuthMode) -> Result<Arc<dyn TokenCredential>> {
+    log::info!("Authenticating to Azure with {:?}", mode);
+    let credential: Arc<dyn TokenCredential> = match mode {
+        AzureAuthMode::DeveloperTools => DeveloperToolsCredential::new(None)?,
+        AzureAuthMode::ManagedIdentity => {
+            let options = ManagedIdentityCredentialOptions {
+                user_assigned_id: std::env::var("AZURE_CLIENT_ID")
+                    .ok()
+                    .map(UserAssignedId::ClientId),
+                ..Default::default()
+            };
+            ManagedIdentityCredential::new(Some(options))?
+        }
+        AzureAuthMode::WorkloadIdentity => WorkloadIdentityCredential::new(None)?,
+        AzureAuthMode::GitHubOidc => {
+            let token_file = std::env::temp_dir().join("infraweave-github-oidc-token");
+            std::fs::write(&token_file, get_github_oidc_token().await?)?;
+            let options = WorkloadIdentityCredentialOptions {
+                token_file_path: Some(token_file),
+                ..Default::default()
+            };
+            WorkloadIdentityCredential::new(Some(options))?
+        }
+    };
+    Ok(credential)
+}
+
+#[derive(Deserialize)]
+struct GitHubOidcTokenResponse {
+    value: String,
+}
+
+/// Requests an OIDC token for the GitHub Actions job, which needs the `id-token: write` permission
+async fn get_github_oidc_token() -> Result<String> {
+    let (Ok(request_url), Ok(request_token)) = (
+        std::env::var("ACTIONS_ID_TOKEN_REQUEST_URL"),
+        std::env::var("ACTIONS_ID_TOKEN_REQUEST_TOKEN"),
+    ) else {
+        return Err(anyhow!(
+            "AZURE_AUTH_MODE is github-oidc but no GitHub OIDC token can be requested, does the job have the id-token: write permission?"
+        ));
+    };
+
+    let response: GitHubOidcTokenResponse = reqwest::Client::new()
+        .get(&request_url)
+        .query(&[("audience", GITHUB_OIDC_AUDIENCE)])
+        .bearer_auth(request_token)
+        .send()
+        .await?
+        .error_for_status()
+        .map_err(|e| anyhow!("Failed to request GitHub OIDC token: {}", e))?
+        .json()
+        .await?;
+    Ok(response.value)
+}
+
+#[derive(Deserialize)]
+struct AcrExchangeResponse {
+    refresh_token: String,
+}
+
+/// Username and password for the Azure Container Registry at `registry_host` (`<name>.azurecr.io`),
+/// exchanging an Entra ID token of the credential for a registry refresh token
+pub async fn get_acr_credentials(registry_host: &str) -> Result<(String, String)> {
+    let token = get_credential()
+        .await?
+        .get_token(&[ACR_SCOPE], None)
+        .await
+        .map_err(|e| anyhow!("Failed to get token for {}: {}", registry_host, e))?;
+
+    let response: AcrExchangeResponse = reqwest::Client::new()
+        .post(format!("https://{}/oauth2/exchange", registry_host))
+        .form(&[
+            ("grant_type", "access_token"),
+            ("service", registry_host),
+            ("access_token", token.token.secret()),
+        ])
+        .send()
+        .await?
+        .error_for_status()
+        .map_err(|e| anyhow!("Failed to exchange token for {}: {}", registry_host, e))?
+        .json()
+        .await?;
+    Ok((ACR_TOKEN_USERNAME.to_string(), response.refresh_token))
+}
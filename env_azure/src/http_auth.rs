@@ -1,6 +1,5 @@
 use anyhow::{anyhow, Result};
 use azure_core::credentials::TokenCredential;
-use azure_identity::DeveloperToolsCredential;
 use serde_json::Value;
 
 /// Makes an authenticated HTTP call to an Azure endpoint using Azure credentials
@@ -17,8 +16,9 @@ pub async fn call_authenticated_http(
     url: &str,
     body: Option<Value>,
 ) -> Result<Value> {
-    // Get Azure credentials (auto-detects: Azure CLI, Azure Developer CLI, or environment variables)
-    let credential = DeveloperToolsCredential::new(None)
+    // Get Azure credentials for the auth mode in AZURE_AUTH_MODE (Azure CLI or Azure Developer CLI by default)
+    let credential = crate::credential::get_credential()
+        .await
         .map_err(|e| anyhow!("Failed to create Azure credentials: {}", e))?;
 
     call_authenticated_http_with_credential(method, url, body, credential).await
@@ -34,7 +34,7 @@ pub async fn call_authenticated_http(
 ///
 /// # Returns
 /// The JSON response from the API
-pub async fn call_authenticated_http_with_credential<T: TokenCredential + 'static>(
+pub async fn call_authenticated_http_with_credential<T: TokenCredential + ?Sized + 'static>(
     method: &str,
     url: &str,
     body: Option<Value>,
@@ -1,5 +1,6 @@
 mod api;
 mod backend;
+mod credential;
 mod custom;
 mod http_auth;
 mod job_id;
@@ -16,10 +17,13 @@ pub use api::{
     get_all_latest_stacks_query,
     get_all_module_versions_query,
     get_all_policies_query,
+    get_all_policy_packs_query,
     get_all_projects_query,
     get_all_regions_query,
     get_all_stack_versions_query,
+    get_change_record_parts_query,
     get_change_records_query,
+    get_config_items_query,
     get_current_project_query,
     get_dependents_query,
     get_deployment_and_dependents_query,
@@ -28,11 +32,15 @@ pub use api::{
     get_deployment_query,
     get_deployments_to_driftcheck_query,
     get_deployments_using_module_query,
+    get_environment_variables_query,
     get_events_query,
+    get_job_queue_query,
+    get_job_stats_query,
     get_latest_module_version_query,
     get_latest_provider_version_query,
     get_latest_stack_version_query,
     get_module_version_query,
+    get_newest_policy_pack_version_query,
     get_newest_policy_version_query,
     get_plan_deployment_query,
     get_policy_query,
@@ -43,8 +51,10 @@ pub use api::{
     get_user_id,
     read_db,
     run_function,
+    with_attribute_filters,
 };
 pub use backend::set_backend;
+pub use credential::{get_acr_credentials, get_credential, AzureAuthMode};
 pub use http_auth::{call_authenticated_http, call_authenticated_http_with_credential};
 pub use job_id::get_current_job_id;
 pub use provider::AzureCloudProvider;
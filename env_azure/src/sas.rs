@@ -1,10 +1,7 @@
 use anyhow::{anyhow, Result};
-use azure_core::credentials::TokenCredential;
-use azure_identity::DeveloperToolsCredential;
 use base64::{engine::general_purpose, Engine as _};
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
-use std::sync::Arc;
 
 pub struct UserDelegationKey {
     pub oid: String,
@@ -31,10 +28,9 @@ pub async fn get_user_delegation_key(
     storage_account: &str,
     expires_in: i64,
 ) -> Result<UserDelegationKey> {
-    let credential = Arc::new(
-        DeveloperToolsCredential::new(None)
-            .map_err(|e| anyhow!("Failed to create Azure credentials: {}", e))?,
-    );
+    let credential = crate::credential::get_credential()
+        .await
+        .map_err(|e| anyhow!("Failed to create Azure credentials: {}", e))?;
 
     let token_response = credential
         .get_token(&["https://storage.azure.com/.default"], None)
@@ -5,7 +5,8 @@ use azure_core::credentials::TokenCredential;
 use azure_identity::DeveloperToolsCredential;
 use env_defs::{
     get_change_record_identifier, get_deployment_identifier, get_event_identifier,
-    get_module_identifier, get_policy_identifier, GenericFunctionResponse,
+    get_module_identifier, get_policy_identifier, CloudHandlerError, GenericFunctionResponse,
+    CHANGE_RECORD_PART_SEPARATOR,
 };
 use env_utils::{get_epoch, sanitize_payload_for_logging, zero_pad_semver};
 use log::{error, info};
@@ -112,6 +113,16 @@ pub async fn run_function(
 
             eprintln!("Response status: {}", status);
             eprintln!("Function response: {}", response_string);
+            if !status.is_success() {
+                return Err(CloudHandlerError::from_status(
+                    status.as_u16(),
+                    format!(
+                        "Azure Function responded with {}: {}",
+                        status, response_string
+                    ),
+                )
+                .into());
+            }
             let parsed_json: Value =
                 serde_json::from_str(&response_string).expect("response not valid JSON");
 
@@ -121,10 +132,11 @@ pub async fn run_function(
         }
         Err(e) => {
             error!("Failed to invoke Azure Function: {}", e);
-            Err(anyhow::anyhow!(format!(
-                "Failed to invoke Azure Function: {}",
+            Err(CloudHandlerError::from_message(format!(
+                "Failed to invoke Azure Function: {:?}",
                 e
-            )))
+            ))
+            .into())
         }
     }
 }
@@ -307,6 +319,20 @@ pub fn get_stack_version_query(module: &str, track: &str, version: &str) -> Valu
 
 // Deployment
 
+/// Narrows a query to the items where each attribute equals its value, the query must end with its WHERE clause
+pub fn with_attribute_filters(mut query: Value, filters: &[(&str, &str)]) -> Value {
+    for (i, (attribute, value)) in filters.iter().enumerate() {
+        let name = format!("@filter{}", i);
+        if let Some(query_str) = query["query"].as_str() {
+            query["query"] = json!(format!("{} AND c.{} = {}", query_str, attribute, name));
+        }
+        if let Some(parameters) = query["parameters"].as_array_mut() {
+            parameters.push(json!({ "name": name, "value": value }));
+        }
+    }
+    query
+}
+
 pub fn get_all_deployments_query(
     project_id: &str,
     region: &str,
@@ -592,6 +618,30 @@ pub fn get_dependents_query(
     })
 }
 
+pub fn get_job_queue_query(project_id: &str, region: &str) -> Value {
+    json!({
+        "query": "SELECT * FROM c WHERE c.PK = @pk",
+        "parameters": [
+            {
+                "name": "@pk",
+                "value": format!("JOBQUEUE#{}", get_deployment_identifier(project_id, region, "", ""))
+            }
+        ]
+    })
+}
+
+pub fn get_job_stats_query(project_id: &str, region: &str, module: &str) -> Value {
+    json!({
+        "query": "SELECT * FROM c WHERE c.PK = @pk",
+        "parameters": [
+            {
+                "name": "@pk",
+                "value": format!("JOBSTATS#{}{}", get_deployment_identifier(project_id, region, "", ""), module)
+            }
+        ]
+    })
+}
+
 pub fn get_deployments_to_driftcheck_query(project_id: &str, region: &str) -> Value {
     json!({
         "query": "SELECT * FROM c WHERE c.deleted_SK_base = @deleted_SK_base AND c.next_drift_check_epoch BETWEEN @start_epoch AND @current_epoch",
@@ -619,6 +669,16 @@ pub fn get_all_projects_query() -> Value {
     })
 }
 
+pub fn get_config_items_query(kind: &str) -> Value {
+    // Only available using central role
+    json!({
+        "query": "SELECT * FROM c WHERE c.PK = @pk",
+        "parameters": [
+            { "name": "@pk", "value": kind }
+        ]
+    })
+}
+
 pub fn get_current_project_query(project_id: &str) -> Value {
     json!({
         "query": "SELECT * FROM c WHERE c.SK = @sk",
@@ -697,6 +757,29 @@ pub fn get_change_records_query(
     })
 }
 
+pub fn get_change_record_parts_query(
+    project_id: &str,
+    region: &str,
+    environment: &str,
+    deployment_id: &str,
+    job_id: &str,
+    change_type: &str,
+) -> Value {
+    json!({
+        "query": "SELECT * FROM c WHERE c.PK = @pk AND STARTSWITH(c.SK, @sk_prefix)",
+        "parameters": [
+            {
+                "name": "@pk",
+                "value": format!("{}#{}", change_type, get_change_record_identifier(project_id, region, deployment_id, environment))
+            },
+            {
+                "name": "@sk_prefix",
+                "value": format!("{}{}", job_id, CHANGE_RECORD_PART_SEPARATOR)
+            }
+        ]
+    })
+}
+
 // Policy
 
 pub fn get_newest_policy_version_query(policy: &str, environment: &str) -> Value {
@@ -737,6 +820,28 @@ pub fn get_policy_query(policy: &str, environment: &str, version: &str) -> Value
     })
 }
 
+pub fn get_newest_policy_pack_version_query(policy_pack: &str, environment: &str) -> Value {
+    json!({
+        "query": "SELECT TOP 1 * FROM c WHERE c.PK = @policy_pack ORDER BY c._ts DESC",
+        "parameters": [
+            {
+                "name": "@policy_pack",
+                "value": format!("POLICYPACK#{}", get_policy_identifier(policy_pack, environment))
+            }
+        ]
+    })
+}
+
+pub fn get_all_policy_packs_query(environment: &str) -> Value {
+    json!({
+        "query": "SELECT * FROM c WHERE c.PK = @current AND STARTSWITH(c.SK, @policy_pack_prefix)",
+        "parameters": [
+            { "name": "@current", "value": "CURRENT" },
+            { "name": "@policy_pack_prefix", "value": format!("POLICYPACK#{}", environment) }
+        ]
+    })
+}
+
 pub fn get_project_map_query() -> Value {
     json!({
         "query": "SELECT udf.getProjectMap() AS data",
@@ -1,11 +1,13 @@
 use async_trait::async_trait;
 use env_defs::{
     CloudProvider, Dependent, DeploymentResp, EventData, GenericFunctionResponse,
-    InfraChangeRecord, JobStatus, ModuleResp, PolicyResp, ProjectData, ProviderResp,
+    InfraChangeRecord, JobQueueEntry, JobStats, JobStatus, ModuleResp, PolicyPackResp, PolicyResp,
+    ProjectData, ProviderResp,
 };
 use env_utils::{
     _get_change_records, _get_dependents, _get_deployment, _get_deployment_and_dependents,
-    _get_deployments, _get_events, _get_module_optional, _get_modules, _get_policies, _get_policy,
+    _get_deployments, _get_events, _get_job_queue, _get_job_stats, _get_module_optional,
+    _get_modules, _get_policies, _get_policy, _get_policy_pack, _get_policy_packs,
     _get_provider_optional, _get_providers, get_projects,
 };
 use serde_json::Value;
@@ -365,6 +367,20 @@ impl CloudProvider for AzureCloudProvider {
         )
         .await
     }
+    async fn get_job_queue(&self) -> Result<Vec<JobQueueEntry>, anyhow::Error> {
+        _get_job_queue(
+            self,
+            crate::get_job_queue_query(&self.project_id, &self.region),
+        )
+        .await
+    }
+    async fn get_job_stats(&self, module: &str) -> Result<Vec<JobStats>, anyhow::Error> {
+        _get_job_stats(
+            self,
+            crate::get_job_stats_query(&self.project_id, &self.region, module),
+        )
+        .await
+    }
     async fn get_all_projects(&self) -> Result<Vec<ProjectData>, anyhow::Error> {
         get_projects(self, crate::get_all_projects_query()).await
     }
@@ -373,6 +389,10 @@ impl CloudProvider for AzureCloudProvider {
             .await
             .map(|mut projects| projects.pop().expect("No project found"))
     }
+    async fn get_config_items(&self, kind: &str) -> Result<Vec<Value>, anyhow::Error> {
+        self.read_db_generic("config", &crate::get_config_items_query(kind))
+            .await
+    }
     // Event
     async fn get_events(
         &self,
@@ -402,6 +422,13 @@ impl CloudProvider for AzureCloudProvider {
         )
         .await
     }
+    async fn get_event_items_before(&self, end_epoch: u128) -> Result<Vec<Value>, anyhow::Error> {
+        self.read_db_generic(
+            "events",
+            &crate::get_all_events_between_query(&self.region, 0, end_epoch),
+        )
+        .await
+    }
     // Change record
     async fn get_change_record(
         &self,
@@ -420,6 +447,14 @@ impl CloudProvider for AzureCloudProvider {
                 job_id,
                 change_type,
             ),
+            crate::get_change_record_parts_query(
+                &self.project_id,
+                &self.region,
+                environment,
+                deployment_id,
+                job_id,
+                change_type,
+            ),
         )
         .await
     }
@@ -462,6 +497,23 @@ impl CloudProvider for AzureCloudProvider {
     ) -> Result<PolicyResp, anyhow::Error> {
         _get_policy(self, crate::get_policy_query(policy, environment, version)).await
     }
+    async fn get_newest_policy_pack_version(
+        &self,
+        policy_pack: &str,
+        environment: &str,
+    ) -> Result<PolicyPackResp, anyhow::Error> {
+        _get_policy_pack(
+            self,
+            crate::get_newest_policy_pack_version_query(policy_pack, environment),
+        )
+        .await
+    }
+    async fn get_all_policy_packs(
+        &self,
+        environment: &str,
+    ) -> Result<Vec<PolicyPackResp>, anyhow::Error> {
+        _get_policy_packs(self, crate::get_all_policy_packs_query(environment)).await
+    }
     async fn get_environment_variables(&self) -> Result<serde_json::Value, anyhow::Error> {
         match crate::run_function(
             &self.function_endpoint,
@@ -481,12 +533,11 @@ impl CloudProvider for AzureCloudProvider {
         }
     }
 
-    async fn download_state_file(
+    async fn read_state_file(
         &self,
         environment: &str,
         deployment_id: &str,
-        output: Option<String>,
-    ) -> Result<(), anyhow::Error> {
+    ) -> Result<Vec<u8>, anyhow::Error> {
         let backend_args = self
             .get_backend_provider_arguments(environment, deployment_id)
             .await;
@@ -522,6 +573,16 @@ impl CloudProvider for AzureCloudProvider {
             .map_err(|e| anyhow::anyhow!("Failed to download state file from Azure: {}", e))?;
 
         let data = response.into_body().collect().await?;
+        Ok(data.to_vec())
+    }
+
+    async fn download_state_file(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+        output: Option<String>,
+    ) -> Result<(), anyhow::Error> {
+        let data = self.read_state_file(environment, deployment_id).await?;
 
         if let Some(output_path) = output {
             std::fs::write(&output_path, &data)?;
@@ -533,4 +594,84 @@ impl CloudProvider for AzureCloudProvider {
 
         Ok(())
     }
+
+    async fn write_state_file(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+        data: &[u8],
+    ) -> Result<(), anyhow::Error> {
+        let backend_args = self
+            .get_backend_provider_arguments(environment, deployment_id)
+            .await;
+
+        let key = backend_args
+            .get("key")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("key not found in backend args"))?;
+
+        let container = backend_args
+            .get("container_name")
+            .or_else(|| backend_args.get("container"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("container not found in backend args"))?;
+
+        let storage_account = backend_args
+            .get("storage_account_name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("storage_account_name not found in backend args"))?;
+
+        let endpoint = format!("https://{}.blob.core.windows.net", storage_account);
+        let credential = azure_identity::DeveloperToolsCredential::new(None)?;
+
+        let blob_service_client =
+            azure_storage_blob::BlobServiceClient::new(&endpoint, Some(credential), None)?;
+        let blob_client = blob_service_client
+            .blob_container_client(container)
+            .blob_client(key);
+
+        blob_client
+            .upload(
+                azure_core::Bytes::from(data.to_vec()).into(),
+                true,
+                data.len() as u64,
+                None,
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to upload state file to Azure: {}", e))?;
+        Ok(())
+    }
+
+    async fn read_state_file_at(&self, address: &str) -> Result<Vec<u8>, anyhow::Error> {
+        // https://<storage account>.blob.core.windows.net/<container>/<key>
+        let (host, path) = address
+            .strip_prefix("https://")
+            .and_then(|address| address.split_once('/'))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "State address must be https://<storage account>.blob.core.windows.net/<container>/<key>, got {}",
+                    address
+                )
+            })?;
+        let (container, key) = path
+            .split_once('/')
+            .ok_or_else(|| anyhow::anyhow!("State address {} has no container", address))?;
+
+        let endpoint = format!("https://{}", host);
+        let credential = azure_identity::DeveloperToolsCredential::new(None)?;
+
+        let blob_service_client =
+            azure_storage_blob::BlobServiceClient::new(&endpoint, Some(credential), None)?;
+        let blob_client = blob_service_client
+            .blob_container_client(container)
+            .blob_client(key);
+
+        let response = blob_client
+            .download(None)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to download state file from Azure: {}", e))?;
+
+        let data = response.into_body().collect().await?;
+        Ok(data.to_vec())
+    }
 }
@@ -61,6 +61,9 @@ pub enum ModuleError {
     #[error("The stack claim \"{1}\" of kind \"{0}\" has an invalid reference \"{2}\" to itself")]
     SelfReferencingClaim(String, String, String),
 
+    #[error("The claim \"{0}\" depends on \"{1}\" which is not a claim in the stack")]
+    DependsOnClaimNotFound(String, String),
+
     #[error("The manifest \"{0}\" is missing the \"version\" field")]
     ModuleVersionMissing(String),
 
@@ -91,3 +94,18 @@ pub enum ModuleError {
     #[error("Other error occurred: {0}")]
     Other(#[from] anyhow::Error),
 }
+
+#[derive(Error, Debug)]
+pub enum ApprovalError {
+    #[error("No job of {0} in {1} is pending approval")]
+    NotPending(String, String),
+
+    #[error("{0} is not an approver for {1}")]
+    NotAuthorized(String, String),
+
+    #[error("{0} started the job and cannot approve it")]
+    SelfApproval(String),
+
+    #[error("{0} already approved the job")]
+    AlreadyApproved(String),
+}
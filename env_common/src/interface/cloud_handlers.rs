@@ -1,5 +1,5 @@
 use core::panic;
-use std::{future::Future, pin::Pin, process::exit, sync::Arc};
+use std::{future::Future, path::Path, pin::Pin, process::exit, sync::Arc};
 
 use async_trait::async_trait;
 use env_aws::AwsCloudProvider;
@@ -7,11 +7,17 @@ use env_aws_direct::AwsCloudProvider as AwsDirectCloudProvider;
 use env_azure::AzureCloudProvider;
 use env_defs::{
     CloudProvider, CloudProviderCommon, Dependent, DeploymentResp, EventData,
-    GenericFunctionResponse, InfraChangeRecord, JobStatus, LogData, ModuleResp, NotificationData,
-    PolicyResp, ProjectData, ProviderResp,
+    GenericFunctionResponse, InfraChangeRecord, JobQueueEntry, JobStats, JobStatus, LogData,
+    ModuleResp, NotificationData, PolicyPackResp, PolicyResp, ProjectData, ProviderResp,
 };
+#[cfg(feature = "local")]
+use env_local::{LocalCloudProvider, LocalStore};
 use serde_json::Value;
 
+use super::recording_cloud_provider::{
+    RecordingCloudProvider, RECORD_FIXTURES_ENV, REPLAY_FIXTURES_ENV,
+};
+use super::retry::{is_throttling_error, is_transient_error, with_retry, RetryPolicy};
 use crate::logic::{
     insert_event, insert_infra_change_record, publish_notification, publish_policy, read_logs,
     set_deployment, OCIRegistryProvider, PROJECT_ID, REGION,
@@ -21,6 +27,7 @@ use crate::logic::{
 pub struct GenericCloudHandler {
     provider: Arc<dyn CloudProvider>,
     oci_registry: Option<OCIRegistryProvider>,
+    retry_policy: RetryPolicy,
 }
 
 impl GenericCloudHandler {
@@ -48,6 +55,17 @@ impl GenericCloudHandler {
     pub fn get_oci_client(&self) -> Option<&OCIRegistryProvider> {
         self.oci_registry.as_ref()
     }
+    /// Returns a copy of the handler that retries `run_function` and `read_db_generic`
+    /// according to `policy`, e.g. `RetryPolicy::none()` for a call that must not be retried
+    pub fn with_retry_policy(&self, policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy: policy,
+            ..self.clone()
+        }
+    }
+    pub fn get_retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
 
     /// Construct a handler with an injected provider (e.g. for tests with a mock).
     #[cfg(test)]
@@ -58,6 +76,7 @@ impl GenericCloudHandler {
         Self {
             provider,
             oci_registry,
+            retry_policy: RetryPolicy::from_env(),
         }
     }
 
@@ -66,7 +85,18 @@ impl GenericCloudHandler {
         region: Option<String>,
         function_endpoint: Option<String>,
     ) -> Self {
+        let replay_fixtures = std::env::var(REPLAY_FIXTURES_ENV).ok();
         let provider: Arc<dyn CloudProvider> = match provider_name().as_str() {
+            _ if replay_fixtures.is_some() => {
+                let path = replay_fixtures.clone().unwrap_or_default();
+                match RecordingCloudProvider::replay(Path::new(&path), project_id, region) {
+                    Ok(provider) => Arc::new(provider),
+                    Err(e) => {
+                        eprintln!("Error initializing: {:?}", e);
+                        exit(1);
+                    }
+                }
+            }
             "aws" => {
                 let region = match region {
                     Some(r) => r,
@@ -130,6 +160,17 @@ impl GenericCloudHandler {
                     function_endpoint,
                 })
             }
+            #[cfg(feature = "local")]
+            "local" => Arc::new(LocalCloudProvider {
+                project_id: project_id.unwrap_or_else(env_local::get_project_id),
+                region: region.unwrap_or_else(env_local::get_region),
+                function_endpoint,
+                store: LocalStore::new(&env_local::get_local_dir()),
+            }),
+            #[cfg(not(feature = "local"))]
+            "local" => panic!(
+                "The local backend is not supported by this build, it needs the local feature"
+            ),
             "http" | "none" => Arc::new(super::NoCloudProvider {
                 project_id: project_id.unwrap_or_default(),
                 region: region.unwrap_or_default(),
@@ -137,6 +178,18 @@ impl GenericCloudHandler {
             }),
             _ => panic!("Unsupported provider: {}", provider_name()),
         };
+        let provider: Arc<dyn CloudProvider> = match std::env::var(RECORD_FIXTURES_ENV) {
+            Ok(path) if replay_fixtures.is_none() => {
+                match RecordingCloudProvider::record(provider, Path::new(&path)) {
+                    Ok(provider) => Arc::new(provider),
+                    Err(e) => {
+                        eprintln!("Error initializing: {:?}", e);
+                        exit(1);
+                    }
+                }
+            }
+            _ => provider,
+        };
         let oci_registry = match std::env::var("OCI_REGISTRY_URI") {
             Ok(oci_registry) => {
                 let oci_username = match std::env::var("OCI_REGISTRY_USERNAME") {
@@ -158,6 +211,7 @@ impl GenericCloudHandler {
         Self {
             provider,
             oci_registry,
+            retry_policy: RetryPolicy::from_env(),
         }
     }
 
@@ -170,6 +224,7 @@ impl GenericCloudHandler {
             function_endpoint,
         )
         .await
+        .with_retry_policy(self.retry_policy)
     }
 }
 
@@ -264,14 +319,29 @@ impl CloudProvider for GenericCloudHandler {
         &self,
         payload: &Value,
     ) -> Result<GenericFunctionResponse, anyhow::Error> {
-        self.provider.run_function(payload).await
+        with_retry(
+            self.retry_policy,
+            "run_function",
+            is_throttling_error,
+            || self.provider.run_function(payload),
+        )
+        .await
     }
     fn read_db_generic(
         &self,
         table: &str,
         query: &Value,
     ) -> Pin<Box<dyn Future<Output = Result<Vec<Value>, anyhow::Error>> + Send>> {
-        self.provider.read_db_generic(table, query)
+        let provider = self.provider.clone();
+        let policy = self.retry_policy;
+        let table = table.to_string();
+        let query = query.clone();
+        Box::pin(async move {
+            with_retry(policy, "read_db", is_transient_error, || {
+                provider.read_db_generic(&table, &query)
+            })
+            .await
+        })
     }
     async fn get_latest_module_version(
         &self,
@@ -427,12 +497,21 @@ impl CloudProvider for GenericCloudHandler {
     async fn get_deployments_to_driftcheck(&self) -> Result<Vec<DeploymentResp>, anyhow::Error> {
         self.provider.get_deployments_to_driftcheck().await
     }
+    async fn get_job_queue(&self) -> Result<Vec<JobQueueEntry>, anyhow::Error> {
+        self.provider.get_job_queue().await
+    }
+    async fn get_job_stats(&self, module: &str) -> Result<Vec<JobStats>, anyhow::Error> {
+        self.provider.get_job_stats(module).await
+    }
     async fn get_all_projects(&self) -> Result<Vec<ProjectData>, anyhow::Error> {
         self.provider.get_all_projects().await
     }
     async fn get_current_project(&self) -> Result<ProjectData, anyhow::Error> {
         self.provider.get_current_project().await
     }
+    async fn get_config_items(&self, kind: &str) -> Result<Vec<Value>, anyhow::Error> {
+        self.provider.get_config_items(kind).await
+    }
     // Event
     async fn get_events(
         &self,
@@ -450,6 +529,9 @@ impl CloudProvider for GenericCloudHandler {
             .get_all_events_between(start_epoch, end_epoch)
             .await
     }
+    async fn get_event_items_before(&self, end_epoch: u128) -> Result<Vec<Value>, anyhow::Error> {
+        self.provider.get_event_items_before(end_epoch).await
+    }
     // Change record
     async fn get_change_record(
         &self,
@@ -499,9 +581,33 @@ impl CloudProvider for GenericCloudHandler {
     ) -> Result<PolicyResp, anyhow::Error> {
         self.provider.get_policy(policy, environment, version).await
     }
+    async fn get_newest_policy_pack_version(
+        &self,
+        policy_pack: &str,
+        environment: &str,
+    ) -> Result<PolicyPackResp, anyhow::Error> {
+        self.provider
+            .get_newest_policy_pack_version(policy_pack, environment)
+            .await
+    }
+    async fn get_all_policy_packs(
+        &self,
+        environment: &str,
+    ) -> Result<Vec<PolicyPackResp>, anyhow::Error> {
+        self.provider.get_all_policy_packs(environment).await
+    }
     async fn get_environment_variables(&self) -> Result<serde_json::Value, anyhow::Error> {
         self.provider.get_environment_variables().await
     }
+    async fn read_state_file(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        self.provider
+            .read_state_file(environment, deployment_id)
+            .await
+    }
     async fn download_state_file(
         &self,
         environment: &str,
@@ -512,6 +618,19 @@ impl CloudProvider for GenericCloudHandler {
             .download_state_file(environment, deployment_id, output)
             .await
     }
+    async fn write_state_file(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+        data: &[u8],
+    ) -> Result<(), anyhow::Error> {
+        self.provider
+            .write_state_file(environment, deployment_id, data)
+            .await
+    }
+    async fn read_state_file_at(&self, address: &str) -> Result<Vec<u8>, anyhow::Error> {
+        self.provider.read_state_file_at(address).await
+    }
 }
 
 impl GenericCloudHandler {
@@ -532,17 +651,12 @@ impl GenericCloudHandler {
             .generate_presigned_url(&change_record.plan_raw_json_key, "change_records")
             .await?;
 
-        let client = reqwest::Client::new();
-        let json_content = client
-            .get(&presigned_url)
-            .send()
+        let mut json_content = vec![];
+        env_utils::download_to_writer(&presigned_url, &mut json_content)
             .await
-            .map_err(|e| anyhow::anyhow!("Failed to download Terraform JSON output: {}", e))?
-            .text()
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to read Terraform JSON response: {}", e))?;
+            .map_err(|e| anyhow::anyhow!("Failed to download Terraform JSON output: {}", e))?;
 
-        let terraform_json: Value = serde_json::from_str(&json_content)
+        let terraform_json: Value = serde_json::from_slice(&json_content)
             .map_err(|e| anyhow::anyhow!("Failed to parse Terraform JSON output: {}", e))?;
 
         Ok(terraform_json)
@@ -614,6 +728,9 @@ pub fn get_region_env_var() -> &'static str {
 }
 
 fn provider_name() -> String {
+    if std::env::var("INFRAWEAVE_BACKEND").is_ok_and(|backend| backend == "local") {
+        return "local".into();
+    }
     std::env::var("CLOUD_PROVIDER")
         .or_else(|_| std::env::var("PROVIDER"))
         .unwrap_or_else(|_| {
@@ -2,11 +2,10 @@ use env_defs::{
     Dependency, DeploymentResp, DeploymentStatus, DriftDetection, EventData, PolicyResult,
 };
 use env_utils::{get_epoch, get_timestamp};
-use humantime::parse_duration;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use serde_json::Value;
 
-use crate::logic::{insert_event, set_deployment};
+use crate::logic::{insert_event, next_drift_check_epoch, set_deployment};
 
 use super::GenericCloudHandler;
 
@@ -40,6 +39,9 @@ pub struct DeploymentStatusHandler<'a> {
     memory: String,
     reference: String,
     tf_resources: Option<Vec<String>>,
+    module_digest: Option<String>,
+    idempotency_key: Option<String>,
+    metadata: Value,
 }
 
 impl<'a> DeploymentStatusHandler<'a> {
@@ -101,6 +103,9 @@ impl<'a> DeploymentStatusHandler<'a> {
             memory,
             reference,
             tf_resources: None,
+            module_digest: None,
+            idempotency_key: None,
+            metadata: Value::Null,
         }
     }
 
@@ -120,6 +125,30 @@ impl<'a> DeploymentStatusHandler<'a> {
         self.output = output;
     }
 
+    pub fn get_output(&self) -> &Value {
+        &self.output
+    }
+
+    pub fn set_module_digest(&mut self, module_digest: Option<String>) {
+        self.module_digest = module_digest;
+    }
+
+    pub fn get_module_digest(&self) -> Option<&str> {
+        self.module_digest.as_deref()
+    }
+
+    pub fn set_idempotency_key(&mut self, idempotency_key: Option<String>) {
+        self.idempotency_key = idempotency_key;
+    }
+
+    pub fn set_metadata(&mut self, metadata: Value) {
+        self.metadata = metadata;
+    }
+
+    pub fn get_metadata(&self) -> &Value {
+        &self.metadata
+    }
+
     pub fn set_error_text(&mut self, error_text: String) {
         self.error_text = error_text;
     }
@@ -192,7 +221,7 @@ impl<'a> DeploymentStatusHandler<'a> {
                 self.module, self.deployment_id, epoch, self.command, self.status
             ),
             job_id: self.job_id.to_string(),
-            metadata: serde_json::Value::Null,
+            metadata: self.metadata.clone(),
             name: self.name.to_string(),
             output: self.output.clone(),
             policy_results: self.policy_results.clone(),
@@ -211,7 +240,9 @@ impl<'a> DeploymentStatusHandler<'a> {
     }
 
     fn get_next_drift_check_epoch(&self) -> i128 {
-        if !self.drift_detection.enabled || self.drift_detection.interval.is_empty() {
+        if !self.drift_detection.enabled
+            || (self.drift_detection.interval.is_empty() && self.drift_detection.schedule.is_none())
+        {
             debug!("Drift detection not enabled");
             return -1;
         }
@@ -223,26 +254,18 @@ impl<'a> DeploymentStatusHandler<'a> {
             debug!("Destroy command, not scheduling next drift detection");
             return -1;
         }
-        match parse_duration(&self.drift_detection.interval) {
-            Ok(dur) => {
+        match next_drift_check_epoch(&self.drift_detection, get_epoch()) {
+            Ok(Some(epoch)) => {
                 info!("Final step, deployment either succeeded or failed, scheduling next drift detection");
-                debug!(
-                    "{} -> {} milliseconds",
-                    &self.drift_detection.interval,
-                    dur.as_millis()
-                );
-                let epoch: i128 = get_epoch().try_into().unwrap();
-                let wait_duration: i128 = dur.as_millis() as i128;
-                debug!(
-                    "Current epoch: {} + {} = {}",
-                    epoch,
-                    wait_duration,
-                    epoch + wait_duration
-                );
-                epoch + wait_duration
+                debug!("Next drift detection at {}", epoch);
+                epoch as i128
+            }
+            Ok(None) => {
+                warn!("No drift detection time is outside of the blackout windows, not scheduling next drift detection");
+                -1
             }
             Err(e) => {
-                error!("Error parsing {}: {}", &self.drift_detection.interval, e);
+                error!("Error scheduling next drift detection: {}", e);
                 -1
             }
         }
@@ -267,6 +290,8 @@ impl<'a> DeploymentStatusHandler<'a> {
             variables: self.variables.clone(),
             drift_detection: self.drift_detection.clone(),
             next_drift_check_epoch: self.get_next_drift_check_epoch(),
+            // Other jobs keep the time of the latest drift check, see set_deployment
+            last_drift_check_epoch: (self.is_drift_check && self.is_final_update()).then(get_epoch),
             has_drifted: self.has_drifted,
             output: self.output.clone(),
             policy_results: self.policy_results.clone(),
@@ -278,6 +303,10 @@ impl<'a> DeploymentStatusHandler<'a> {
             memory: self.memory.to_string(),
             reference: self.reference.to_string(),
             tf_resources: self.tf_resources.clone(),
+            module_digest: self.module_digest.clone(),
+            idempotency_key: self.idempotency_key.clone(),
+            drift_report: None,
+            stack_instance: None,
         };
 
         match set_deployment(handler, &deployment, self.is_plan()).await {
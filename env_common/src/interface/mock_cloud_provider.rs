@@ -8,7 +8,8 @@ use std::pin::Pin;
 use async_trait::async_trait;
 use env_defs::{
     CloudProvider, Dependent, DeploymentResp, EventData, GenericFunctionResponse,
-    InfraChangeRecord, JobStatus, ModuleResp, PolicyResp, ProjectData, ProviderResp,
+    InfraChangeRecord, JobQueueEntry, JobStats, JobStatus, ModuleResp, PolicyPackResp, PolicyResp,
+    ProjectData, ProviderResp,
 };
 use mockall::mock;
 use serde_json::Value;
@@ -142,8 +143,11 @@ mock! {
         ) -> Result<Vec<Dependent>, anyhow::Error>;
         async fn get_deployments_to_driftcheck(&self)
             -> Result<Vec<DeploymentResp>, anyhow::Error>;
+        async fn get_job_queue(&self) -> Result<Vec<JobQueueEntry>, anyhow::Error>;
+        async fn get_job_stats(&self, module: &str) -> Result<Vec<JobStats>, anyhow::Error>;
         async fn get_all_projects(&self) -> Result<Vec<ProjectData>, anyhow::Error>;
         async fn get_current_project(&self) -> Result<ProjectData, anyhow::Error>;
+        async fn get_config_items(&self, kind: &str) -> Result<Vec<Value>, anyhow::Error>;
         async fn get_events(
             &self,
             deployment_id: &str,
@@ -154,6 +158,7 @@ mock! {
             start_epoch: u128,
             end_epoch: u128,
         ) -> Result<Vec<EventData>, anyhow::Error>;
+        async fn get_event_items_before(&self, end_epoch: u128) -> Result<Vec<Value>, anyhow::Error>;
         async fn get_change_record(
             &self,
             environment: &str,
@@ -177,12 +182,33 @@ mock! {
             environment: &str,
             version: &str,
         ) -> Result<PolicyResp, anyhow::Error>;
+        async fn get_newest_policy_pack_version(
+            &self,
+            policy_pack: &str,
+            environment: &str,
+        ) -> Result<PolicyPackResp, anyhow::Error>;
+        async fn get_all_policy_packs(
+            &self,
+            environment: &str,
+        ) -> Result<Vec<PolicyPackResp>, anyhow::Error>;
         async fn get_environment_variables(&self) -> Result<Value, anyhow::Error>;
+        async fn read_state_file(
+            &self,
+            environment: &str,
+            deployment_id: &str,
+        ) -> Result<Vec<u8>, anyhow::Error>;
         async fn download_state_file(
             &self,
             environment: &str,
             deployment_id: &str,
             output: Option<String>,
         ) -> Result<(), anyhow::Error>;
+        async fn write_state_file(
+            &self,
+            environment: &str,
+            deployment_id: &str,
+            data: &[u8],
+        ) -> Result<(), anyhow::Error>;
+        async fn read_state_file_at(&self, address: &str) -> Result<Vec<u8>, anyhow::Error>;
     }
 }
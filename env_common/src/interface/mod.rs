@@ -3,6 +3,8 @@ mod deployment_status_handler;
 #[cfg(test)]
 mod mock_cloud_provider;
 mod no_cloud_provider;
+mod recording_cloud_provider;
+mod retry;
 
 pub use cloud_handlers::{
     get_region_env_var, initialize_project_id_and_region, GenericCloudHandler,
@@ -10,6 +12,12 @@ pub use cloud_handlers::{
 pub use deployment_status_handler::DeploymentStatusHandler;
 
 pub use no_cloud_provider::NoCloudProvider;
+pub use recording_cloud_provider::{
+    Fixtures, Interaction, RecordingCloudProvider, RECORD_FIXTURES_ENV, REPLAY_FIXTURES_ENV,
+};
+pub use retry::{
+    is_throttling_error, is_transient_error, retry_metrics, RetryMetrics, RetryPolicy,
+};
 
 #[cfg(test)]
 pub use mock_cloud_provider::MockTestCloudProvider as TestCloudProvider;
@@ -1,7 +1,7 @@
 use env_defs::{
     CloudProvider, CloudProviderCommon, Dependent, DeploymentResp, EventData,
-    GenericFunctionResponse, InfraChangeRecord, JobStatus, LogData, ModuleResp, NotificationData,
-    PolicyResp, ProjectData, ProviderResp,
+    GenericFunctionResponse, InfraChangeRecord, JobQueueEntry, JobStats, JobStatus, LogData,
+    ModuleResp, NotificationData, PolicyPackResp, PolicyResp, ProjectData, ProviderResp,
 };
 use serde_json::Value;
 use std::{future::Future, pin::Pin};
@@ -292,6 +292,14 @@ impl CloudProvider for NoCloudProvider {
         Ok(vec![])
     }
 
+    async fn get_job_queue(&self) -> Result<Vec<JobQueueEntry>, anyhow::Error> {
+        Ok(vec![])
+    }
+
+    async fn get_job_stats(&self, _module: &str) -> Result<Vec<JobStats>, anyhow::Error> {
+        Ok(vec![])
+    }
+
     async fn get_all_projects(&self) -> Result<Vec<ProjectData>, anyhow::Error> {
         Ok(vec![])
     }
@@ -300,6 +308,10 @@ impl CloudProvider for NoCloudProvider {
         Err(anyhow::anyhow!("no current project"))
     }
 
+    async fn get_config_items(&self, _kind: &str) -> Result<Vec<Value>, anyhow::Error> {
+        Ok(vec![])
+    }
+
     async fn get_events(
         &self,
         _deployment_id: &str,
@@ -316,6 +328,10 @@ impl CloudProvider for NoCloudProvider {
         Ok(vec![])
     }
 
+    async fn get_event_items_before(&self, _end_epoch: u128) -> Result<Vec<Value>, anyhow::Error> {
+        Ok(vec![])
+    }
+
     async fn get_change_record(
         &self,
         _environment: &str,
@@ -347,6 +363,21 @@ impl CloudProvider for NoCloudProvider {
         Err(anyhow::anyhow!("no policy"))
     }
 
+    async fn get_newest_policy_pack_version(
+        &self,
+        _policy_pack: &str,
+        _environment: &str,
+    ) -> Result<PolicyPackResp, anyhow::Error> {
+        Err(anyhow::anyhow!("no newest policy pack version"))
+    }
+
+    async fn get_all_policy_packs(
+        &self,
+        _environment: &str,
+    ) -> Result<Vec<PolicyPackResp>, anyhow::Error> {
+        Ok(vec![])
+    }
+
     async fn get_policy_download_url(&self, _key: &str) -> Result<String, anyhow::Error> {
         Ok(String::new())
     }
@@ -355,6 +386,14 @@ impl CloudProvider for NoCloudProvider {
         Ok(serde_json::Value::Null)
     }
 
+    async fn read_state_file(
+        &self,
+        _environment: &str,
+        _deployment_id: &str,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        Err(anyhow::anyhow!("not supported"))
+    }
+
     async fn download_state_file(
         &self,
         _environment: &str,
@@ -363,4 +402,17 @@ impl CloudProvider for NoCloudProvider {
     ) -> Result<(), anyhow::Error> {
         Err(anyhow::anyhow!("not supported"))
     }
+
+    async fn write_state_file(
+        &self,
+        _environment: &str,
+        _deployment_id: &str,
+        _data: &[u8],
+    ) -> Result<(), anyhow::Error> {
+        Err(anyhow::anyhow!("not supported"))
+    }
+
+    async fn read_state_file_at(&self, _address: &str) -> Result<Vec<u8>, anyhow::Error> {
+        Err(anyhow::anyhow!("not supported"))
+    }
 }
@@ -0,0 +1,871 @@
+//! [RecordingCloudProvider] records the calls made to a cloud provider and their responses to a
+//! fixture file, and serves them back without the cloud, so that integration tests of the CLI,
+//! TUI and webserver run deterministically.
+//!
+//! Set `INFRAWEAVE_RECORD_FIXTURES=<file>` to record a run against the configured provider, and
+//! `INFRAWEAVE_REPLAY_FIXTURES=<file>` to replay it. A fixture file holds the calls of one
+//! process, a new recording replaces it.
+//!
+//! Environment variables and state files are redacted when recorded. Requests are matched
+//! without the times and generated ids in them, and writes by their event and tables only.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use env_defs::{
+    CloudProvider, Dependent, DeploymentResp, EventData, GenericFunctionResponse,
+    InfraChangeRecord, JobQueueEntry, JobStats, JobStatus, ModuleResp, PolicyPackResp, PolicyResp,
+    ProjectData, ProviderResp,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// File the calls to the cloud provider are recorded to
+pub const RECORD_FIXTURES_ENV: &str = "INFRAWEAVE_RECORD_FIXTURES";
+/// File the calls to the cloud provider are replayed from, instead of calling the cloud
+pub const REPLAY_FIXTURES_ENV: &str = "INFRAWEAVE_REPLAY_FIXTURES";
+
+/// Recorded instead of the environment variables and the content of state files
+const REDACTED: &str = "<redacted>";
+
+/// Fields of requests that differ between runs, e.g. the time an event is inserted at
+const VOLATILE_FIELDS: &[&str] = &["epoch", "timestamp", "event_duration"];
+
+/// Events of `run_function` that write, which hold the times and generated ids of their items
+const WRITE_EVENTS: &[&str] = &[
+    "insert_db",
+    "transact_write",
+    "start_runner",
+    "publish_notification",
+    "upload_file_base64",
+    "upload_file_url",
+];
+
+// Generated ids, e.g. of jobs, are uuids with a prefix
+static UUID: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}").unwrap()
+});
+
+/// Content of a fixture file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Fixtures {
+    /// Provider the calls were recorded with, and its settings returned when replaying
+    pub cloud_provider: String,
+    pub backend_provider: String,
+    pub storage_basepath: String,
+    /// Project and region of the first handler, used by handlers created without them
+    pub project_id: String,
+    pub region: String,
+    pub interactions: Vec<Interaction>,
+}
+
+/// A call to the provider and its response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub project_id: String,
+    pub region: String,
+    pub method: String,
+    /// Arguments of the call
+    pub request: Value,
+    /// `{"ok": <response>}`, or `{"error": <message>}` when the call failed
+    pub response: Value,
+}
+
+struct FixtureFile {
+    path: PathBuf,
+    fixtures: Fixtures,
+    /// Interactions already replayed
+    replayed: Vec<bool>,
+}
+
+// Handlers of all projects and regions share the file, so that calls are replayed in the order
+// they were recorded in
+static FIXTURE_FILES: Lazy<Mutex<HashMap<PathBuf, Arc<Mutex<FixtureFile>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn fixture_file(path: &Path, replay: bool) -> Result<Arc<Mutex<FixtureFile>>, anyhow::Error> {
+    let mut files = FIXTURE_FILES.lock().unwrap();
+    if let Some(file) = files.get(path) {
+        return Ok(file.clone());
+    }
+    let fixtures = if replay {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read fixtures {}: {}", path.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Failed to parse fixtures {}: {}", path.display(), e))?
+    } else {
+        Fixtures::default()
+    };
+    let file = Arc::new(Mutex::new(FixtureFile {
+        path: path.to_path_buf(),
+        replayed: vec![false; fixtures.interactions.len()],
+        fixtures,
+    }));
+    files.insert(path.to_path_buf(), file.clone());
+    Ok(file)
+}
+
+/// Cloud provider recording the calls to another provider, or replaying them from a fixture file
+#[derive(Clone)]
+pub struct RecordingCloudProvider {
+    /// The provider calls are recorded from, None when replaying
+    provider: Option<Arc<dyn CloudProvider>>,
+    file: Arc<Mutex<FixtureFile>>,
+    project_id: String,
+    region: String,
+    cloud_provider: String,
+    backend_provider: String,
+}
+
+impl RecordingCloudProvider {
+    /// Records the calls to `provider` to the fixture file at `path`, replacing its content the
+    /// first time it is used in the process
+    pub fn record(
+        provider: Arc<dyn CloudProvider>,
+        path: &Path,
+    ) -> Result<RecordingCloudProvider, anyhow::Error> {
+        let file = fixture_file(path, false)?;
+        {
+            let mut file = file.lock().unwrap();
+            if file.fixtures.cloud_provider.is_empty() {
+                file.fixtures.cloud_provider = provider.get_cloud_provider().to_string();
+                file.fixtures.backend_provider = provider.get_backend_provider().to_string();
+                file.fixtures.storage_basepath = provider.get_storage_basepath();
+                file.fixtures.project_id = provider.get_project_id().to_string();
+                file.fixtures.region = provider.get_region().to_string();
+                write_fixtures(&file)?;
+            }
+        }
+        Ok(RecordingCloudProvider {
+            project_id: provider.get_project_id().to_string(),
+            region: provider.get_region().to_string(),
+            cloud_provider: provider.get_cloud_provider().to_string(),
+            backend_provider: provider.get_backend_provider().to_string(),
+            provider: Some(provider),
+            file,
+        })
+    }
+
+    /// Replays the calls recorded in the fixture file at `path`. The project and region default
+    /// to the ones the fixtures were recorded with
+    pub fn replay(
+        path: &Path,
+        project_id: Option<String>,
+        region: Option<String>,
+    ) -> Result<RecordingCloudProvider, anyhow::Error> {
+        let file = fixture_file(path, true)?;
+        let fixtures = file.lock().unwrap().fixtures.clone();
+        Ok(RecordingCloudProvider {
+            provider: None,
+            project_id: project_id.unwrap_or(fixtures.project_id),
+            region: region.unwrap_or(fixtures.region),
+            cloud_provider: fixtures.cloud_provider,
+            backend_provider: fixtures.backend_provider,
+            file,
+        })
+    }
+
+    fn save<T: Serialize>(
+        &self,
+        method: &str,
+        request: Value,
+        response: Result<T, anyhow::Error>,
+    ) -> Result<T, anyhow::Error> {
+        let recorded = match &response {
+            Ok(value) => json!({ "ok": redact_response(method, &request, json!(value)) }),
+            Err(e) => json!({ "error": e.to_string() }),
+        };
+        let request = normalize_request(method, request);
+        let mut file = self.file.lock().unwrap();
+        file.fixtures.interactions.push(Interaction {
+            project_id: self.project_id.clone(),
+            region: self.region.clone(),
+            method: method.to_string(),
+            request,
+            response: recorded,
+        });
+        file.replayed.push(false);
+        write_fixtures(&file)?;
+        response
+    }
+
+    /// The response of the first recorded call with the same arguments that was not replayed
+    /// yet, or of the last one when all were, e.g. when a job is polled more often than it was
+    /// while recording
+    fn load<T: DeserializeOwned>(&self, method: &str, request: Value) -> Result<T, anyhow::Error> {
+        let request = normalize_request(method, request);
+        let mut file = self.file.lock().unwrap();
+        let matching: Vec<usize> = file
+            .fixtures
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| {
+                interaction.method == method
+                    && same_request(&interaction.request, &request)
+                    && interaction.project_id == self.project_id
+                    && interaction.region == self.region
+            })
+            .map(|(index, _)| index)
+            .collect();
+        let index = match matching.iter().find(|&&index| !file.replayed[index]) {
+            Some(&index) => index,
+            None => *matching.last().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No recorded response for {} {} in project {} and region {} in {}",
+                    method,
+                    request,
+                    self.project_id,
+                    self.region,
+                    file.path.display()
+                )
+            })?,
+        };
+        file.replayed[index] = true;
+
+        let response = &file.fixtures.interactions[index].response;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow::anyhow!(error
+                .as_str()
+                .unwrap_or_default()
+                .to_string()));
+        }
+        serde_json::from_value(response.get("ok").cloned().unwrap_or(Value::Null))
+            .map_err(|e| anyhow::anyhow!("Failed to parse recorded response of {}: {}", method, e))
+    }
+}
+
+/// The request without the times and generated ids in it, and without the content of state files
+fn normalize_request(method: &str, mut request: Value) -> Value {
+    if method == "write_state_file" {
+        request[2] = json!(REDACTED);
+    }
+    normalize_value(&mut request);
+    request
+}
+
+fn normalize_value(value: &mut Value) {
+    match value {
+        Value::String(text) if UUID.is_match(text) => {
+            *text = UUID.replace_all(text, "<uuid>").to_string();
+        }
+        Value::Array(values) => values.iter_mut().for_each(normalize_value),
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                if VOLATILE_FIELDS.contains(&key.as_str()) {
+                    *field = Value::Null;
+                } else {
+                    normalize_value(field);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Writes are the same when they have the same event and tables, other requests when they are
+/// equal
+fn same_request(recorded: &Value, request: &Value) -> bool {
+    match request["event"].as_str() {
+        Some(event) if WRITE_EVENTS.contains(&event) => {
+            recorded["event"] == request["event"] && tables(recorded) == tables(request)
+        }
+        _ => recorded == request,
+    }
+}
+
+fn tables(request: &Value) -> Vec<&Value> {
+    match request["items"].as_array() {
+        Some(items) => items
+            .iter()
+            .flat_map(|item| item.as_object().into_iter().flat_map(|item| item.values()))
+            .map(|operation| &operation["TableName"])
+            .collect(),
+        None => vec![&request["table"]],
+    }
+}
+
+/// The response without the values of environment variables and the content of state files
+fn redact_response(method: &str, request: &Value, response: Value) -> Value {
+    let is_environment_variables = method == "get_environment_variables"
+        || (method == "run_function" && request["event"] == "get_environment_variables");
+    match response {
+        Value::Object(variables) if is_environment_variables => Value::Object(
+            variables
+                .into_iter()
+                .map(|(name, _)| (name, json!(REDACTED)))
+                .collect(),
+        ),
+        _ if matches!(
+            method,
+            "read_state_file" | "read_state_file_at" | "download_state_file"
+        ) =>
+        {
+            json!(REDACTED.as_bytes())
+        }
+        response => response,
+    }
+}
+
+/// Writes the state to `output`, or prints it when there is none, as the providers do
+fn output_state_file(data: &[u8], output: Option<String>) -> Result<(), anyhow::Error> {
+    match output {
+        Some(output_path) => std::fs::write(output_path, data)?,
+        None => println!("{}", String::from_utf8_lossy(data)),
+    }
+    Ok(())
+}
+
+fn write_fixtures(file: &FixtureFile) -> Result<(), anyhow::Error> {
+    std::fs::write(&file.path, serde_json::to_string_pretty(&file.fixtures)?)
+        .map_err(|e| anyhow::anyhow!("Failed to write fixtures {}: {}", file.path.display(), e))
+}
+
+/// Calls the method of the recorded provider and saves its response, or loads the response
+/// when replaying. The arguments are the request the response is recorded for
+macro_rules! recorded {
+    ($self:ident, $method:ident($($arg:expr),*)) => {
+        match &$self.provider {
+            Some(provider) => {
+                let response = provider.$method($($arg),*).await;
+                $self.save(stringify!($method), json!([$($arg),*]), response)
+            }
+            None => $self.load(stringify!($method), json!([$($arg),*])),
+        }
+    };
+}
+
+#[async_trait]
+impl CloudProvider for RecordingCloudProvider {
+    fn get_project_id(&self) -> &str {
+        &self.project_id
+    }
+    async fn get_user_id(&self) -> Result<String, anyhow::Error> {
+        recorded!(self, get_user_id())
+    }
+    fn get_region(&self) -> &str {
+        &self.region
+    }
+    fn get_function_endpoint(&self) -> Option<String> {
+        self.provider
+            .as_ref()
+            .and_then(|provider| provider.get_function_endpoint())
+    }
+    fn get_cloud_provider(&self) -> &str {
+        &self.cloud_provider
+    }
+    fn get_backend_provider(&self) -> &str {
+        &self.backend_provider
+    }
+    fn get_storage_basepath(&self) -> String {
+        match &self.provider {
+            Some(provider) => provider.get_storage_basepath(),
+            None => self.file.lock().unwrap().fixtures.storage_basepath.clone(),
+        }
+    }
+    async fn get_backend_provider_arguments(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+    ) -> serde_json::Value {
+        let response: Result<Value, anyhow::Error> = match &self.provider {
+            Some(provider) => {
+                let arguments = provider
+                    .get_backend_provider_arguments(environment, deployment_id)
+                    .await;
+                self.save(
+                    "get_backend_provider_arguments",
+                    json!([environment, deployment_id]),
+                    Ok(arguments),
+                )
+            }
+            None => self.load(
+                "get_backend_provider_arguments",
+                json!([environment, deployment_id]),
+            ),
+        };
+        response.unwrap_or(Value::Null)
+    }
+    // The backend is only set up for terraform, which does not run when replaying
+    async fn set_backend(
+        &self,
+        exec: &mut tokio::process::Command,
+        deployment_id: &str,
+        environment: &str,
+    ) {
+        if let Some(provider) = &self.provider {
+            provider.set_backend(exec, deployment_id, environment).await;
+        }
+    }
+    async fn get_current_job_id(&self) -> Result<String, anyhow::Error> {
+        recorded!(self, get_current_job_id())
+    }
+    async fn get_project_map(&self) -> Result<Value, anyhow::Error> {
+        recorded!(self, get_project_map())
+    }
+    async fn get_all_regions(&self) -> Result<Vec<String>, anyhow::Error> {
+        recorded!(self, get_all_regions())
+    }
+    async fn run_function(
+        &self,
+        payload: &Value,
+    ) -> Result<GenericFunctionResponse, anyhow::Error> {
+        let response = match &self.provider {
+            Some(provider) => {
+                let response = provider
+                    .run_function(payload)
+                    .await
+                    .map(|response| response.payload);
+                self.save("run_function", payload.clone(), response)
+            }
+            None => self.load("run_function", payload.clone()),
+        };
+        response.map(|payload| GenericFunctionResponse { payload })
+    }
+    fn read_db_generic(
+        &self,
+        table: &str,
+        query: &Value,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<Value>, anyhow::Error>> + Send>> {
+        let recorder = self.clone();
+        let table = table.to_string();
+        let query = query.clone();
+        Box::pin(async move {
+            let request = json!({ "table": table, "query": query });
+            match &recorder.provider {
+                Some(provider) => {
+                    let response = provider.read_db_generic(&table, &query).await;
+                    recorder.save("read_db", request, response)
+                }
+                None => recorder.load("read_db", request),
+            }
+        })
+    }
+    async fn get_latest_module_version(
+        &self,
+        module: &str,
+        track: &str,
+    ) -> Result<Option<ModuleResp>, anyhow::Error> {
+        recorded!(self, get_latest_module_version(module, track))
+    }
+    async fn get_latest_stack_version(
+        &self,
+        stack: &str,
+        track: &str,
+    ) -> Result<Option<ModuleResp>, anyhow::Error> {
+        recorded!(self, get_latest_stack_version(stack, track))
+    }
+    async fn get_latest_provider_version(
+        &self,
+        provider: &str,
+    ) -> Result<Option<ProviderResp>, anyhow::Error> {
+        recorded!(self, get_latest_provider_version(provider))
+    }
+    async fn generate_presigned_url(
+        &self,
+        key: &str,
+        bucket: &str,
+    ) -> Result<String, anyhow::Error> {
+        recorded!(self, generate_presigned_url(key, bucket))
+    }
+    async fn upload_file_base64(
+        &self,
+        key: &str,
+        bucket: &str,
+        base64_content: &str,
+    ) -> Result<(), anyhow::Error> {
+        recorded!(self, upload_file_base64(key, bucket, base64_content))
+    }
+    async fn upload_file_url(
+        &self,
+        key: &str,
+        bucket: &str,
+        url: &str,
+    ) -> Result<(), anyhow::Error> {
+        recorded!(self, upload_file_url(key, bucket, url))
+    }
+    async fn transact_write(&self, items: &serde_json::Value) -> Result<(), anyhow::Error> {
+        recorded!(self, transact_write(items))
+    }
+    async fn get_all_latest_module(&self, track: &str) -> Result<Vec<ModuleResp>, anyhow::Error> {
+        recorded!(self, get_all_latest_module(track))
+    }
+    async fn get_all_latest_stack(&self, track: &str) -> Result<Vec<ModuleResp>, anyhow::Error> {
+        recorded!(self, get_all_latest_stack(track))
+    }
+    async fn get_all_latest_provider(&self) -> Result<Vec<ProviderResp>, anyhow::Error> {
+        recorded!(self, get_all_latest_provider())
+    }
+    async fn get_all_module_versions(
+        &self,
+        module: &str,
+        track: &str,
+    ) -> Result<Vec<ModuleResp>, anyhow::Error> {
+        recorded!(self, get_all_module_versions(module, track))
+    }
+    async fn get_all_stack_versions(
+        &self,
+        stack: &str,
+        track: &str,
+    ) -> Result<Vec<ModuleResp>, anyhow::Error> {
+        recorded!(self, get_all_stack_versions(stack, track))
+    }
+    async fn get_module_version(
+        &self,
+        module: &str,
+        track: &str,
+        version: &str,
+    ) -> Result<Option<ModuleResp>, anyhow::Error> {
+        recorded!(self, get_module_version(module, track, version))
+    }
+    async fn get_stack_version(
+        &self,
+        module: &str,
+        track: &str,
+        version: &str,
+    ) -> Result<Option<ModuleResp>, anyhow::Error> {
+        recorded!(self, get_stack_version(module, track, version))
+    }
+    async fn get_all_deployments(
+        &self,
+        environment: &str,
+        include_deleted: bool,
+    ) -> Result<Vec<DeploymentResp>, anyhow::Error> {
+        recorded!(self, get_all_deployments(environment, include_deleted))
+    }
+    async fn get_deployment_and_dependents(
+        &self,
+        deployment_id: &str,
+        environment: &str,
+        include_deleted: bool,
+    ) -> Result<(Option<DeploymentResp>, Vec<Dependent>), anyhow::Error> {
+        recorded!(
+            self,
+            get_deployment_and_dependents(deployment_id, environment, include_deleted)
+        )
+    }
+    async fn get_deployment(
+        &self,
+        deployment_id: &str,
+        environment: &str,
+        include_deleted: bool,
+    ) -> Result<Option<DeploymentResp>, anyhow::Error> {
+        recorded!(
+            self,
+            get_deployment(deployment_id, environment, include_deleted)
+        )
+    }
+    async fn get_job_status(&self, job_id: &str) -> Result<Option<JobStatus>, anyhow::Error> {
+        recorded!(self, get_job_status(job_id))
+    }
+    async fn get_deployments_using_module(
+        &self,
+        module: &str,
+        environment: &str,
+        include_deleted: bool,
+    ) -> Result<Vec<DeploymentResp>, anyhow::Error> {
+        recorded!(
+            self,
+            get_deployments_using_module(module, environment, include_deleted)
+        )
+    }
+    async fn get_plan_deployment(
+        &self,
+        deployment_id: &str,
+        environment: &str,
+        job_id: &str,
+    ) -> Result<Option<DeploymentResp>, anyhow::Error> {
+        recorded!(
+            self,
+            get_plan_deployment(deployment_id, environment, job_id)
+        )
+    }
+    async fn get_dependents(
+        &self,
+        deployment_id: &str,
+        environment: &str,
+    ) -> Result<Vec<Dependent>, anyhow::Error> {
+        recorded!(self, get_dependents(deployment_id, environment))
+    }
+    async fn get_deployments_to_driftcheck(&self) -> Result<Vec<DeploymentResp>, anyhow::Error> {
+        recorded!(self, get_deployments_to_driftcheck())
+    }
+    async fn get_job_queue(&self) -> Result<Vec<JobQueueEntry>, anyhow::Error> {
+        recorded!(self, get_job_queue())
+    }
+    async fn get_job_stats(&self, module: &str) -> Result<Vec<JobStats>, anyhow::Error> {
+        recorded!(self, get_job_stats(module))
+    }
+    async fn get_all_projects(&self) -> Result<Vec<ProjectData>, anyhow::Error> {
+        recorded!(self, get_all_projects())
+    }
+    async fn get_current_project(&self) -> Result<ProjectData, anyhow::Error> {
+        recorded!(self, get_current_project())
+    }
+    async fn get_config_items(&self, kind: &str) -> Result<Vec<Value>, anyhow::Error> {
+        recorded!(self, get_config_items(kind))
+    }
+    async fn get_events(
+        &self,
+        deployment_id: &str,
+        environment: &str,
+    ) -> Result<Vec<EventData>, anyhow::Error> {
+        recorded!(self, get_events(deployment_id, environment))
+    }
+    async fn get_all_events_between(
+        &self,
+        start_epoch: u128,
+        end_epoch: u128,
+    ) -> Result<Vec<EventData>, anyhow::Error> {
+        recorded!(self, get_all_events_between(start_epoch, end_epoch))
+    }
+    async fn get_event_items_before(&self, end_epoch: u128) -> Result<Vec<Value>, anyhow::Error> {
+        recorded!(self, get_event_items_before(end_epoch))
+    }
+    async fn get_change_record(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+        job_id: &str,
+        change_type: &str,
+    ) -> Result<InfraChangeRecord, anyhow::Error> {
+        recorded!(
+            self,
+            get_change_record(environment, deployment_id, job_id, change_type)
+        )
+    }
+    async fn get_newest_policy_version(
+        &self,
+        policy: &str,
+        environment: &str,
+    ) -> Result<PolicyResp, anyhow::Error> {
+        recorded!(self, get_newest_policy_version(policy, environment))
+    }
+    async fn get_all_policies(&self, environment: &str) -> Result<Vec<PolicyResp>, anyhow::Error> {
+        recorded!(self, get_all_policies(environment))
+    }
+    async fn get_policy_download_url(&self, key: &str) -> Result<String, anyhow::Error> {
+        recorded!(self, get_policy_download_url(key))
+    }
+    async fn get_policy(
+        &self,
+        policy: &str,
+        environment: &str,
+        version: &str,
+    ) -> Result<PolicyResp, anyhow::Error> {
+        recorded!(self, get_policy(policy, environment, version))
+    }
+    async fn get_newest_policy_pack_version(
+        &self,
+        policy_pack: &str,
+        environment: &str,
+    ) -> Result<PolicyPackResp, anyhow::Error> {
+        recorded!(
+            self,
+            get_newest_policy_pack_version(policy_pack, environment)
+        )
+    }
+    async fn get_all_policy_packs(
+        &self,
+        environment: &str,
+    ) -> Result<Vec<PolicyPackResp>, anyhow::Error> {
+        recorded!(self, get_all_policy_packs(environment))
+    }
+    async fn get_environment_variables(&self) -> Result<serde_json::Value, anyhow::Error> {
+        recorded!(self, get_environment_variables())
+    }
+    async fn read_state_file(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        recorded!(self, read_state_file(environment, deployment_id))
+    }
+    async fn download_state_file(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+        output: Option<String>,
+    ) -> Result<(), anyhow::Error> {
+        // The state is recorded, so that a replayed download writes a file like the recorded one
+        let request = json!([environment, deployment_id]);
+        let data: Vec<u8> = match &self.provider {
+            Some(provider) => {
+                let data = provider.read_state_file(environment, deployment_id).await;
+                self.save("download_state_file", request, data)?
+            }
+            None => self.load("download_state_file", request)?,
+        };
+        output_state_file(&data, output)
+    }
+    async fn write_state_file(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+        data: &[u8],
+    ) -> Result<(), anyhow::Error> {
+        recorded!(self, write_state_file(environment, deployment_id, data))
+    }
+    async fn read_state_file_at(&self, address: &str) -> Result<Vec<u8>, anyhow::Error> {
+        recorded!(self, read_state_file_at(address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::TestCloudProvider;
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let dir = env_utils::tempdir().unwrap();
+        let path = dir.path().join("fixtures.json");
+
+        let mut mock = TestCloudProvider::new();
+        mock.expect_get_project_id().return_const("123".to_string());
+        mock.expect_get_region()
+            .return_const("us-west-2".to_string());
+        mock.expect_get_cloud_provider()
+            .return_const("aws".to_string());
+        mock.expect_get_backend_provider()
+            .return_const("s3".to_string());
+        mock.expect_get_storage_basepath().returning(String::new);
+        let mut polls = 0;
+        mock.expect_run_function().times(2).returning(move |_| {
+            polls += 1;
+            Ok(GenericFunctionResponse {
+                payload: json!({ "poll": polls }),
+            })
+        });
+        mock.expect_get_config_items()
+            .times(1)
+            .returning(|_| Err(anyhow::anyhow!("AccessDenied")));
+
+        let recorder = RecordingCloudProvider::record(Arc::new(mock), &path).unwrap();
+        let payload = json!({ "event": "job_status" });
+        assert_eq!(
+            recorder.run_function(&payload).await.unwrap().payload,
+            json!({ "poll": 1 })
+        );
+        assert_eq!(
+            recorder.run_function(&payload).await.unwrap().payload,
+            json!({ "poll": 2 })
+        );
+        assert!(recorder.get_config_items("TRACKS").await.is_err());
+
+        // Replayed from the file, as by another process
+        FIXTURE_FILES.lock().unwrap().clear();
+        let replayer = RecordingCloudProvider::replay(&path, None, None).unwrap();
+        assert_eq!(replayer.get_project_id(), "123");
+        assert_eq!(replayer.get_cloud_provider(), "aws");
+        for poll in [1, 2, 2] {
+            assert_eq!(
+                replayer.run_function(&payload).await.unwrap().payload,
+                json!({ "poll": poll })
+            );
+        }
+        assert_eq!(
+            replayer
+                .get_config_items("TRACKS")
+                .await
+                .unwrap_err()
+                .to_string(),
+            "AccessDenied"
+        );
+        assert!(replayer
+            .run_function(&json!({ "event": "other" }))
+            .await
+            .err()
+            .unwrap()
+            .to_string()
+            .starts_with("No recorded response for run_function"));
+    }
+
+    #[tokio::test]
+    async fn test_redacted_and_normalized_replay() {
+        let dir = env_utils::tempdir().unwrap();
+        let path = dir.path().join("fixtures.json");
+
+        let mut mock = TestCloudProvider::new();
+        mock.expect_get_project_id().return_const("123".to_string());
+        mock.expect_get_region()
+            .return_const("us-west-2".to_string());
+        mock.expect_get_cloud_provider()
+            .return_const("aws".to_string());
+        mock.expect_get_backend_provider()
+            .return_const("s3".to_string());
+        mock.expect_get_storage_basepath().returning(String::new);
+        mock.expect_get_environment_variables()
+            .returning(|| Ok(json!({ "TF_VAR_password": "hunter2" })));
+        mock.expect_read_state_file()
+            .returning(|_, _| Ok(br#"{"outputs": {"password": "hunter2"}}"#.to_vec()));
+        mock.expect_run_function().returning(|_| {
+            Ok(GenericFunctionResponse {
+                payload: json!({ "written": true }),
+            })
+        });
+
+        let recorder = RecordingCloudProvider::record(Arc::new(mock), &path).unwrap();
+        assert_eq!(
+            recorder.get_environment_variables().await.unwrap(),
+            json!({ "TF_VAR_password": "hunter2" })
+        );
+        let recorded_state = dir.path().join("recorded.tfstate");
+        recorder
+            .download_state_file(
+                "cli/dev",
+                "s3bucket/bucket",
+                Some(recorded_state.display().to_string()),
+            )
+            .await
+            .unwrap();
+        assert!(std::fs::read_to_string(&recorded_state)
+            .unwrap()
+            .contains("hunter2"));
+        let event = |job_id: &str, epoch: u128| {
+            env_defs::insert_db_event(
+                "events",
+                &json!({ "PK": format!("EVENT#{}", epoch), "job_id": job_id, "epoch": epoch }),
+            )
+        };
+        recorder
+            .run_function(&event("queued-0b5e7c2e-3f5a-4d6c-9a1b-2c3d4e5f6a7b", 1))
+            .await
+            .unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("hunter2"));
+
+        // Replayed from the file, as by another process
+        FIXTURE_FILES.lock().unwrap().clear();
+        let replayer = RecordingCloudProvider::replay(&path, None, None).unwrap();
+        assert_eq!(
+            replayer.get_environment_variables().await.unwrap(),
+            json!({ "TF_VAR_password": REDACTED })
+        );
+        let replayed_state = dir.path().join("replayed.tfstate");
+        replayer
+            .download_state_file(
+                "cli/dev",
+                "s3bucket/bucket",
+                Some(replayed_state.display().to_string()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&replayed_state).unwrap(), REDACTED);
+        assert_eq!(
+            replayer
+                .run_function(&event("queued-9c8d7e6f-5a4b-4c3d-8e2f-1a0b9c8d7e6f", 2,))
+                .await
+                .unwrap()
+                .payload,
+            json!({ "written": true })
+        );
+    }
+}
@@ -0,0 +1,405 @@
+//! Retry layer for the calls [GenericCloudHandler] makes to the cloud provider, so that
+//! transient throttling in DynamoDB/CosmosDB does not surface as a hard failure.
+//!
+//! Reads are retried on any transient error. Functions may start runners or write, and a call
+//! that timed out may still have run, so they are only retried when they were throttled.
+//!
+//! [GenericCloudHandler]: super::GenericCloudHandler
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use env_defs::CloudHandlerError;
+use once_cell::sync::Lazy;
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 4;
+const DEFAULT_BASE_DELAY_MS: u64 = 200;
+const DEFAULT_MAX_DELAY_MS: u64 = 5_000;
+const DEFAULT_RETRY_BUDGET: u64 = 50;
+
+// Budget tokens are stored in tenths, a retry withdraws a full token and a successful call
+// deposits a tenth of one
+const TOKEN_SCALE: u64 = 10;
+
+// Errors of requests that were rejected before they were processed
+const THROTTLING_ERROR_MARKERS: &[&str] = &[
+    "throttl",
+    "toomanyrequests",
+    "too many requests",
+    "provisionedthroughputexceeded",
+    "requestlimitexceeded",
+    "request rate is large",
+    "slowdown",
+    "status: 429",
+];
+
+// Errors of requests that may or may not have been processed
+const UNAVAILABLE_ERROR_MARKERS: &[&str] = &[
+    "serviceunavailable",
+    "service unavailable",
+    "status: 503",
+    "timed out",
+    "connection reset",
+];
+
+/// Backoff settings for calls to the cloud provider.
+///
+/// The defaults can be overridden with `INFRAWEAVE_RETRY_MAX_ATTEMPTS`,
+/// `INFRAWEAVE_RETRY_BASE_DELAY_MS` and `INFRAWEAVE_RETRY_MAX_DELAY_MS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first call
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: Duration::from_millis(DEFAULT_BASE_DELAY_MS),
+            max_delay: Duration::from_millis(DEFAULT_MAX_DELAY_MS),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that makes a single attempt, for calls that must not be retried
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            max_attempts: env_number("INFRAWEAVE_RETRY_MAX_ATTEMPTS")
+                .map(|n| n.max(1) as u32)
+                .unwrap_or(default.max_attempts),
+            base_delay: env_number("INFRAWEAVE_RETRY_BASE_DELAY_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(default.base_delay),
+            max_delay: env_number("INFRAWEAVE_RETRY_MAX_DELAY_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(default.max_delay),
+        }
+    }
+
+    /// Exponential backoff with full jitter for the given retry (starting at 0)
+    fn backoff(&self, retry: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        let ceiling_ms = ceiling.as_millis() as u64;
+        if ceiling_ms == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_millis(rand::random_range(0..=ceiling_ms))
+    }
+}
+
+/// Counters of the retries made by the retry layer since the process started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetryMetrics {
+    /// Calls that were retried at least once
+    pub retried_calls: u64,
+    /// Total number of retries
+    pub retries: u64,
+    /// Calls that still failed with a transient error after all attempts
+    pub exhausted: u64,
+    /// Retries skipped because the retry budget was empty
+    pub budget_rejections: u64,
+}
+
+struct RetryBudget {
+    tokens: AtomicU64,
+    max_tokens: u64,
+}
+
+impl RetryBudget {
+    fn new(max_retries: u64) -> Self {
+        let max_tokens = max_retries * TOKEN_SCALE;
+        Self {
+            tokens: AtomicU64::new(max_tokens),
+            max_tokens,
+        }
+    }
+
+    fn withdraw(&self) -> bool {
+        self.tokens
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |tokens| {
+                tokens.checked_sub(TOKEN_SCALE)
+            })
+            .is_ok()
+    }
+
+    fn deposit(&self) {
+        let _ = self
+            .tokens
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |tokens| {
+                (tokens < self.max_tokens).then_some(tokens + 1)
+            });
+    }
+}
+
+// Shared by all handlers so that a throttled table cannot be hammered by retries from
+// every call in the process at once
+static RETRY_BUDGET: Lazy<RetryBudget> = Lazy::new(|| {
+    RetryBudget::new(env_number("INFRAWEAVE_RETRY_BUDGET").unwrap_or(DEFAULT_RETRY_BUDGET))
+});
+
+static RETRIED_CALLS: AtomicU64 = AtomicU64::new(0);
+static RETRIES: AtomicU64 = AtomicU64::new(0);
+static EXHAUSTED: AtomicU64 = AtomicU64::new(0);
+static BUDGET_REJECTIONS: AtomicU64 = AtomicU64::new(0);
+
+pub fn retry_metrics() -> RetryMetrics {
+    RetryMetrics {
+        retried_calls: RETRIED_CALLS.load(Ordering::Relaxed),
+        retries: RETRIES.load(Ordering::Relaxed),
+        exhausted: EXHAUSTED.load(Ordering::Relaxed),
+        budget_rejections: BUDGET_REJECTIONS.load(Ordering::Relaxed),
+    }
+}
+
+/// Whether the error is a throttling or availability error that is safe to retry for an
+/// idempotent request
+pub fn is_transient_error(error: &anyhow::Error) -> bool {
+    if let Some(cloud_error) = error.downcast_ref::<CloudHandlerError>() {
+        return cloud_error.is_retryable();
+    }
+    let message = format!("{:?}", error).to_lowercase();
+    THROTTLING_ERROR_MARKERS
+        .iter()
+        .chain(UNAVAILABLE_ERROR_MARKERS)
+        .any(|marker| message.contains(marker))
+}
+
+/// Whether the request was rejected before it was processed, so that retrying it can't repeat
+/// a write or start a second runner
+pub fn is_throttling_error(error: &anyhow::Error) -> bool {
+    if let Some(cloud_error) = error.downcast_ref::<CloudHandlerError>() {
+        return matches!(
+            cloud_error,
+            CloudHandlerError::Throttled(_) | CloudHandlerError::NoAvailableRunner()
+        );
+    }
+    let message = format!("{:?}", error).to_lowercase();
+    THROTTLING_ERROR_MARKERS
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+/// Runs `call` until it succeeds, fails with an error that is not `retryable`, runs out of
+/// attempts or the shared retry budget is empty
+pub(crate) async fn with_retry<T, F, Fut>(
+    policy: RetryPolicy,
+    operation: &str,
+    retryable: fn(&anyhow::Error) -> bool,
+    mut call: F,
+) -> Result<T, anyhow::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, anyhow::Error>>,
+{
+    let mut retry = 0;
+    loop {
+        match call().await {
+            Ok(result) => {
+                RETRY_BUDGET.deposit();
+                return Ok(result);
+            }
+            Err(e) if retryable(&e) => {
+                if retry + 1 >= policy.max_attempts {
+                    if retry > 0 {
+                        EXHAUSTED.fetch_add(1, Ordering::Relaxed);
+                        log::warn!("{} failed after {} attempts: {}", operation, retry + 1, e);
+                    }
+                    return Err(e);
+                }
+                if !RETRY_BUDGET.withdraw() {
+                    BUDGET_REJECTIONS.fetch_add(1, Ordering::Relaxed);
+                    log::warn!("Retry budget exhausted, not retrying {}: {}", operation, e);
+                    return Err(e);
+                }
+                if retry == 0 {
+                    RETRIED_CALLS.fetch_add(1, Ordering::Relaxed);
+                }
+                RETRIES.fetch_add(1, Ordering::Relaxed);
+                let delay = policy.backoff(retry);
+                log::warn!(
+                    "{} failed with a transient error (attempt {}/{}), retrying in {:?}: {}",
+                    operation,
+                    retry + 1,
+                    policy.max_attempts,
+                    delay,
+                    e
+                );
+                tokio::time::sleep(delay).await;
+                retry += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn env_number(name: &str) -> Option<u64> {
+    std::env::var(name).ok().and_then(|v| v.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU32;
+    use std::sync::Arc;
+
+    use anyhow::anyhow;
+    use env_defs::{CloudProvider, GenericFunctionResponse};
+    use serde_json::json;
+
+    use super::*;
+    use crate::interface::{GenericCloudHandler, TestCloudProvider};
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_throttled_calls_until_success() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let mut mock = TestCloudProvider::new();
+        mock.expect_run_function().returning(move |_| {
+            if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(anyhow!("ThrottlingException: Rate exceeded"))
+            } else {
+                Ok(GenericFunctionResponse {
+                    payload: json!({"ok": true}),
+                })
+            }
+        });
+
+        let handler = GenericCloudHandler::with_provider(Arc::new(mock), None)
+            .with_retry_policy(fast_policy(4));
+        let response = handler.run_function(&json!({})).await.unwrap();
+
+        assert_eq!(response.payload, json!({"ok": true}));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_permanent_errors() {
+        let mut mock = TestCloudProvider::new();
+        mock.expect_run_function()
+            .times(1)
+            .returning(|_| Err(anyhow!("ValidationException: invalid key")));
+
+        let handler = GenericCloudHandler::with_provider(Arc::new(mock), None)
+            .with_retry_policy(fast_policy(4));
+
+        assert!(handler.run_function(&json!({})).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_functions_that_timed_out() {
+        let mut mock = TestCloudProvider::new();
+        mock.expect_run_function()
+            .times(1)
+            .returning(|_| Err(anyhow!("operation timed out")));
+
+        let handler = GenericCloudHandler::with_provider(Arc::new(mock), None)
+            .with_retry_policy(fast_policy(4));
+
+        assert!(handler.run_function(&json!({})).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn retries_reads_that_timed_out() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let mut mock = TestCloudProvider::new();
+        mock.expect_read_db_generic().returning(move |_, _| {
+            let attempt = counter.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                if attempt == 0 {
+                    Err(anyhow!("connection reset by peer"))
+                } else {
+                    Ok(vec![json!({"ok": true})])
+                }
+            })
+        });
+
+        let handler = GenericCloudHandler::with_provider(Arc::new(mock), None)
+            .with_retry_policy(fast_policy(4));
+        let items = handler.read_db_generic("events", &json!({})).await.unwrap();
+
+        assert_eq!(items, vec![json!({"ok": true})]);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn classifies_cloud_handler_errors() {
+        let mut mock = TestCloudProvider::new();
+        mock.expect_run_function().times(1).returning(|_| {
+            Err(CloudHandlerError::AccessDenied("dynamodb:Query".to_string()).into())
+        });
+
+        let handler = GenericCloudHandler::with_provider(Arc::new(mock), None)
+            .with_retry_policy(fast_policy(4));
+        let error = handler.run_function(&json!({})).await.err().unwrap();
+
+        assert!(error
+            .downcast_ref::<CloudHandlerError>()
+            .is_some_and(CloudHandlerError::is_permission_error));
+        assert!(is_transient_error(
+            &CloudHandlerError::Throttled("Rate exceeded".to_string()).into()
+        ));
+        assert!(!is_throttling_error(
+            &CloudHandlerError::Transient("timed out".to_string()).into()
+        ));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let mut mock = TestCloudProvider::new();
+        mock.expect_run_function()
+            .times(2)
+            .returning(|_| Err(anyhow!("ProvisionedThroughputExceededException")));
+
+        let handler = GenericCloudHandler::with_provider(Arc::new(mock), None)
+            .with_retry_policy(fast_policy(2));
+
+        assert!(handler.run_function(&json!({})).await.is_err());
+    }
+
+    #[test]
+    fn backoff_is_capped_by_max_delay() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+        };
+        for retry in 0..10 {
+            assert!(policy.backoff(retry) <= Duration::from_millis(300));
+        }
+    }
+
+    #[test]
+    fn budget_rejects_when_empty() {
+        let budget = RetryBudget::new(1);
+        assert!(budget.withdraw());
+        assert!(!budget.withdraw());
+        for _ in 0..TOKEN_SCALE {
+            budget.deposit();
+        }
+        assert!(budget.withdraw());
+    }
+}
@@ -5,6 +5,7 @@ pub mod logic;
 pub use interface::DeploymentStatusHandler;
 
 pub use logic::{
-    download_provider_to_vec, download_to_vec_from_modules, get_modules_download_url,
-    insert_request_event, publish_module, publish_provider, publish_stack, submit_claim_job,
+    download_provider_to_vec, download_to_vec_from_modules, get_artifact_verification_policy,
+    get_modules_download_url, get_required_approvals, insert_request_event, publish_module,
+    publish_provider, publish_stack, submit_claim_job, submit_pending_approval,
 };
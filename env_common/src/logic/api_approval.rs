@@ -0,0 +1,503 @@
+use env_defs::{
+    get_deployment_identifier, ApiInfraPayloadWithVariables, ApprovalDecision, ApprovalRequest,
+    CloudProvider, DeploymentResp, DeploymentStatus, EventData,
+};
+use env_utils::{get_epoch, get_timestamp};
+use log::info;
+use serde_json::json;
+
+use crate::errors::ApprovalError;
+use crate::interface::GenericCloudHandler;
+use crate::logic::api_deployment::get_payload;
+use crate::logic::api_event::insert_event;
+use crate::logic::api_infra::{insert_job_event, insert_request_event, mutate_infra};
+use crate::logic::api_job_queue::{admit_job, hold_job_slot, JobAdmission};
+
+pub(crate) const APPROVE_EVENT: &str = "approve";
+pub(crate) const REJECT_EVENT: &str = "reject";
+
+/// Result of signing off on a job pending approval
+#[derive(Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum ApprovalOutcome {
+    /// More approvals are needed before the job is started
+    Pending(ApprovalRequest),
+    /// Enough approvers signed off, the submission has `approved_by` set and is ready to start
+    Approved(ApiInfraPayloadWithVariables),
+    Rejected(ApprovalRequest),
+}
+
+/// Jobs in the region of the handler that are pending approval, optionally limited to `environment`
+pub async fn get_pending_approvals(
+    handler: &GenericCloudHandler,
+    environment: &str,
+) -> Result<Vec<ApprovalRequest>, anyhow::Error> {
+    let deployments = handler.get_all_deployments(environment, false).await?;
+    let mut requests = Vec::new();
+    for deployment in deployments
+        .iter()
+        .filter(|d| d.status == DeploymentStatus::PendingApproval)
+    {
+        let events = handler
+            .get_events(&deployment.deployment_id, &deployment.environment)
+            .await?;
+        if let Some((request, _)) = approval_request_from_events(&events, &deployment.job_id) {
+            requests.push(request);
+        }
+    }
+    requests.sort_by_key(|r| r.epoch);
+    Ok(requests)
+}
+
+/// Records the decision of `approver` on the job of the deployment that is pending approval.
+/// A rejection ends the job, an approval returns the submission to start once enough approvers
+/// other than the one who started the job signed off
+pub async fn decide_approval(
+    handler: &GenericCloudHandler,
+    deployment_id: &str,
+    environment: &str,
+    approver: &str,
+    approved: bool,
+    comment: &str,
+) -> Result<ApprovalOutcome, anyhow::Error> {
+    let not_pending =
+        || ApprovalError::NotPending(deployment_id.to_string(), environment.to_string());
+    let deployment = handler
+        .get_deployment(deployment_id, environment, false)
+        .await?
+        .filter(|d| d.status == DeploymentStatus::PendingApproval)
+        .ok_or_else(not_pending)?;
+    let events = handler.get_events(deployment_id, environment).await?;
+    let (mut request, pending_event) =
+        approval_request_from_events(&events, &deployment.job_id).ok_or_else(not_pending)?;
+    let mut submission: ApiInfraPayloadWithVariables = pending_event
+        .metadata
+        .get("submission")
+        .cloned()
+        .and_then(|s| serde_json::from_value(s).ok())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Job {} was held for approval without its submission, submit the claim again",
+                request.job_id
+            )
+        })?;
+
+    check_approver(handler, &request, approver, approved).await?;
+
+    let epoch = get_epoch();
+    let decision_event = EventData {
+        event: if approved {
+            APPROVE_EVENT
+        } else {
+            REJECT_EVENT
+        }
+        .to_string(),
+        epoch,
+        timestamp: get_timestamp(),
+        id: format!(
+            "{}-{}-{}-{}",
+            request.module,
+            deployment_id,
+            epoch,
+            if approved {
+                APPROVE_EVENT
+            } else {
+                REJECT_EVENT
+            }
+        ),
+        status: if approved {
+            DeploymentStatus::PendingApproval
+        } else {
+            DeploymentStatus::Rejected
+        },
+        initiated_by: approver.to_string(),
+        metadata: json!({ "comment": comment }),
+        error_text: String::new(),
+        event_duration: 0,
+        ..pending_event
+    };
+    insert_event(handler, decision_event).await?;
+    request.decisions.push(ApprovalDecision {
+        approver: approver.to_string(),
+        approved,
+        comment: comment.to_string(),
+        epoch,
+    });
+
+    if !approved {
+        let error_text = match comment {
+            "" => format!("Rejected by {}", approver),
+            comment => format!("Rejected by {}: {}", approver, comment),
+        };
+        insert_job_event(
+            handler,
+            &submission,
+            &request.job_id,
+            DeploymentStatus::Rejected,
+            serde_json::Map::new(),
+            &error_text,
+        )
+        .await?;
+        info!("Job {} of {} was rejected", request.job_id, deployment_id);
+        return Ok(ApprovalOutcome::Rejected(request));
+    }
+
+    let approvers = request.approvers();
+    if (approvers.len() as u32) < request.required_approvals {
+        info!(
+            "Job {} of {} has {} of {} approval(s)",
+            request.job_id,
+            deployment_id,
+            approvers.len(),
+            request.required_approvals
+        );
+        return Ok(ApprovalOutcome::Pending(request));
+    }
+    // Approvers signing off at the same time both see enough approvals, only the one whose
+    // approval moves the deployment out of pending_approval starts the job
+    if !claim_approval(handler, &deployment, &approvers).await {
+        info!(
+            "Job {} of {} was already approved by another approver",
+            request.job_id, deployment_id
+        );
+        return Err(not_pending().into());
+    }
+    submission.payload.approved_by = approvers;
+    Ok(ApprovalOutcome::Approved(submission))
+}
+
+/// Moves the deployment pending approval to `requested`, false when its job was already approved.
+/// The approval of the job is written with a condition in the same transaction, so that it
+/// succeeds once per job
+async fn claim_approval(
+    handler: &GenericCloudHandler,
+    deployment: &DeploymentResp,
+    approvers: &[String],
+) -> bool {
+    let requested = DeploymentResp {
+        status: DeploymentStatus::Requested,
+        ..deployment.clone()
+    };
+    let items = json!([
+        {
+            "Put": {
+                "TableName": "deployments",
+                "Item": {
+                    "PK": format!(
+                        "APPROVAL#{}",
+                        get_deployment_identifier(
+                            &deployment.project_id,
+                            &deployment.region,
+                            &deployment.deployment_id,
+                            &deployment.environment
+                        )
+                    ),
+                    "SK": format!("JOB#{}", deployment.job_id),
+                    "approved_by": approvers,
+                    "epoch": get_epoch(),
+                },
+                "ConditionExpression": "attribute_not_exists(PK)",
+            }
+        },
+        {
+            "Put": {
+                "TableName": "deployments",
+                "Item": get_payload(&requested, false),
+            }
+        },
+    ]);
+    match handler
+        .run_function(&env_defs::transact_write_event(&items))
+        .await
+    {
+        Ok(_) => true,
+        Err(e) => {
+            info!(
+                "Could not claim approval of job {}: {}",
+                deployment.job_id, e
+            );
+            false
+        }
+    }
+}
+
+/// Starts an approved job, returning its job id. The job is queued instead when its project
+/// already runs as many jobs as it allows
+pub async fn start_approved_job(
+    handler: &GenericCloudHandler,
+    submission: &ApiInfraPayloadWithVariables,
+) -> Result<String, anyhow::Error> {
+    let admission = admit_job(handler, submission).await?;
+    if let JobAdmission::Queued(job_id) = admission {
+        return Ok(job_id);
+    }
+    let resp = mutate_infra(handler, submission.payload.clone()).await?;
+    let job_id = resp.payload["job_id"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("No job_id in response"))?
+        .to_string();
+    insert_request_event(handler, submission, &job_id).await?;
+    if let JobAdmission::Start(slot) = admission {
+        hold_job_slot(handler, submission, slot, &job_id).await?;
+    }
+    Ok(job_id)
+}
+
+/// Checks that `approver` may decide on the job. Only the approvers of the project can approve
+/// or reject it, and approving also requires someone other than the one who started the job
+async fn check_approver(
+    handler: &GenericCloudHandler,
+    request: &ApprovalRequest,
+    approver: &str,
+    approved: bool,
+) -> Result<(), anyhow::Error> {
+    if approved && approver == request.initiated_by {
+        return Err(ApprovalError::SelfApproval(approver.to_string()).into());
+    }
+    if approved && request.approvers().iter().any(|a| a == approver) {
+        return Err(ApprovalError::AlreadyApproved(approver.to_string()).into());
+    }
+    let settings = handler.get_current_project().await?.settings;
+    if !settings.is_authorized_approver(&request.environment, approver) {
+        return Err(ApprovalError::NotAuthorized(
+            approver.to_string(),
+            request.environment.clone(),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// The approval request of `job_id` and the event that put it on hold, built from the events of
+/// its deployment
+fn approval_request_from_events(
+    events: &[EventData],
+    job_id: &str,
+) -> Option<(ApprovalRequest, EventData)> {
+    let pending = events.iter().find(|e| {
+        e.job_id == job_id
+            && e.status == DeploymentStatus::PendingApproval
+            && e.metadata.get("required_approvals").is_some()
+    })?;
+    let required_approvals = pending.metadata["required_approvals"].as_u64().unwrap_or(1) as u32;
+
+    let mut decisions: Vec<ApprovalDecision> = events
+        .iter()
+        .filter(|e| e.job_id == job_id && (e.event == APPROVE_EVENT || e.event == REJECT_EVENT))
+        .map(|e| ApprovalDecision {
+            approver: e.initiated_by.clone(),
+            approved: e.event == APPROVE_EVENT,
+            comment: e
+                .metadata
+                .get("comment")
+                .and_then(|c| c.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            epoch: e.epoch,
+        })
+        .collect();
+    decisions.sort_by_key(|d| d.epoch);
+
+    let request = ApprovalRequest {
+        job_id: job_id.to_string(),
+        deployment_id: pending.deployment_id.clone(),
+        environment: pending.environment.clone(),
+        project_id: pending.project_id.clone(),
+        region: pending.region.clone(),
+        command: pending.event.clone(),
+        module: pending.module.clone(),
+        module_version: pending.module_version.clone(),
+        initiated_by: pending.initiated_by.clone(),
+        required_approvals,
+        epoch: pending.epoch,
+        decisions,
+    };
+    Some((request, pending.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use env_defs::DriftDetection;
+
+    fn event(job_id: &str, event: &str, status: DeploymentStatus, initiated_by: &str) -> EventData {
+        EventData {
+            deployment_id: "s3bucket/my-bucket".to_string(),
+            project_id: "123".to_string(),
+            region: "us-west-2".to_string(),
+            environment: "cli/prod".to_string(),
+            event: event.to_string(),
+            epoch: 1,
+            error_text: String::new(),
+            id: String::new(),
+            job_id: job_id.to_string(),
+            metadata: json!({}),
+            drift_detection: DriftDetection {
+                enabled: false,
+                interval: String::new(),
+                auto_remediate: false,
+                webhooks: vec![],
+                schedule: None,
+                blackout_windows: vec![],
+            },
+            next_drift_check_epoch: -1,
+            has_drifted: false,
+            module: "s3bucket".to_string(),
+            module_version: "0.1.0".to_string(),
+            name: "my-bucket".to_string(),
+            status,
+            timestamp: String::new(),
+            output: json!({}),
+            policy_results: vec![],
+            initiated_by: initiated_by.to_string(),
+            event_duration: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_approver_rejection() {
+        use crate::interface::TestCloudProvider;
+        use env_defs::{ApprovalPolicy, ProjectData, ProjectSettings};
+        use std::sync::Arc;
+
+        let mut mock = TestCloudProvider::new();
+        mock.expect_get_current_project().returning(|| {
+            Ok(ProjectData {
+                project_id: "123".to_string(),
+                name: "project".to_string(),
+                description: String::new(),
+                regions: vec![],
+                repositories: vec![],
+                settings: ProjectSettings {
+                    approval_policies: vec![ApprovalPolicy {
+                        environment: "cli/prod".to_string(),
+                        required_approvals: 1,
+                        approvers: vec!["alice".to_string()],
+                    }],
+                    ..Default::default()
+                },
+            })
+        });
+        let handler = GenericCloudHandler::with_provider(Arc::new(mock), None);
+        let mut pending = event("job-1", "apply", DeploymentStatus::PendingApproval, "carol");
+        pending.metadata = json!({ "required_approvals": 1 });
+        let (request, _) = approval_request_from_events(&[pending], "job-1").unwrap();
+
+        // Rejecting is restricted to the approvers just like approving
+        let err = check_approver(&handler, &request, "bob", false)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("bob is not an approver"));
+        assert!(check_approver(&handler, &request, "alice", false)
+            .await
+            .is_ok());
+        // The one who started the job can't approve it, but can reject it as an approver
+        let mut pending = event("job-1", "apply", DeploymentStatus::PendingApproval, "alice");
+        pending.metadata = json!({ "required_approvals": 1 });
+        let (request, _) = approval_request_from_events(&[pending], "job-1").unwrap();
+        assert!(check_approver(&handler, &request, "alice", true)
+            .await
+            .is_err());
+        assert!(check_approver(&handler, &request, "alice", false)
+            .await
+            .is_ok());
+    }
+
+    #[test]
+    fn test_approval_request_from_events() {
+        let pending = EventData {
+            metadata: json!({ "required_approvals": 2 }),
+            ..event("job-1", "apply", DeploymentStatus::PendingApproval, "carol")
+        };
+        let approve = EventData {
+            epoch: 3,
+            metadata: json!({ "comment": "looks good" }),
+            ..event(
+                "job-1",
+                APPROVE_EVENT,
+                DeploymentStatus::PendingApproval,
+                "alice",
+            )
+        };
+        let other_job = EventData {
+            epoch: 2,
+            ..event(
+                "job-0",
+                APPROVE_EVENT,
+                DeploymentStatus::PendingApproval,
+                "bob",
+            )
+        };
+        let events = vec![approve, other_job, pending];
+
+        let (request, pending_event) = approval_request_from_events(&events, "job-1").unwrap();
+        assert_eq!(pending_event.initiated_by, "carol");
+        assert_eq!(request.command, "apply");
+        assert_eq!(request.required_approvals, 2);
+        assert_eq!(request.approvers(), vec!["alice".to_string()]);
+        assert_eq!(request.decisions[0].comment, "looks good");
+
+        assert!(approval_request_from_events(&events, "job-0").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_second_approval_claim_fails() {
+        use crate::interface::TestCloudProvider;
+        use env_defs::GenericFunctionResponse;
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        let claims = Arc::new(AtomicU32::new(0));
+        let counter = claims.clone();
+        let mut mock = TestCloudProvider::new();
+        mock.expect_run_function().returning(move |payload| {
+            let items = &payload["items"];
+            assert_eq!(
+                items[0]["Put"]["ConditionExpression"],
+                "attribute_not_exists(PK)"
+            );
+            assert_eq!(items[0]["Put"]["Item"]["SK"], "JOB#pending-approval-1");
+            assert_eq!(items[1]["Put"]["Item"]["status"], "requested");
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                Ok(GenericFunctionResponse { payload: json!({}) })
+            } else {
+                Err(anyhow::anyhow!(
+                    "ConditionalCheckFailed: the conditional request failed"
+                ))
+            }
+        });
+        let handler = GenericCloudHandler::with_provider(Arc::new(mock), None);
+        let deployment: DeploymentResp = serde_json::from_value(json!({
+            "epoch": 0,
+            "deployment_id": "s3bucket/my-bucket",
+            "status": "pending_approval",
+            "job_id": "pending-approval-1",
+            "environment": "cli/prod",
+            "project_id": "123",
+            "region": "us-west-2",
+            "module": "s3bucket",
+            "module_version": "0.1.0",
+            "module_type": "module",
+            "module_track": "stable",
+            "drift_detection": {},
+            "next_drift_check_epoch": 0,
+            "has_drifted": false,
+            "variables": {},
+            "output": {},
+            "policy_results": [],
+            "error_text": "",
+            "deleted": false,
+            "dependencies": [],
+            "initiated_by": "carol",
+            "cpu": "1024",
+            "memory": "2048",
+            "reference": "",
+        }))
+        .unwrap();
+        let approvers = vec!["alice".to_string()];
+
+        assert!(claim_approval(&handler, &deployment, &approvers).await);
+        assert!(!claim_approval(&handler, &deployment, &approvers).await);
+        assert_eq!(claims.load(Ordering::SeqCst), 2);
+    }
+}
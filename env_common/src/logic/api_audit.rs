@@ -0,0 +1,295 @@
+use env_defs::{
+    AuditEvent, AuditEventType, AuditLogRecord, CloudProvider, DeploymentStatus, EventData,
+};
+use serde_json::{json, Value};
+
+use crate::interface::GenericCloudHandler;
+use crate::logic::api_approval::{APPROVE_EVENT, REJECT_EVENT};
+use crate::logic::api_webhook::{sign_body, WEBHOOK_DELIVERY_EVENT};
+
+/// Secret the lines of exported audit logs are signed with
+pub const AUDIT_SIGNING_KEY_ENV: &str = "INFRAWEAVE_AUDIT_SIGNING_KEY";
+
+/// The audit log entry of a stored event, None for events that are not part of the audit log,
+/// such as webhook deliveries and intermediate job updates
+pub fn audit_event(event: &EventData) -> Option<AuditEvent> {
+    let event_type = match (event.event.as_str(), &event.status) {
+        (APPROVE_EVENT, _) => AuditEventType::ApprovalGranted,
+        (REJECT_EVENT, _) => AuditEventType::ApprovalRejected,
+        (WEBHOOK_DELIVERY_EVENT, _) => return None,
+        (_, DeploymentStatus::Requested | DeploymentStatus::PendingApproval) => {
+            AuditEventType::ClaimSubmitted
+        }
+        (_, DeploymentStatus::FailedPolicy) => AuditEventType::PolicyViolated,
+        (_, status) if status.is_failure() => AuditEventType::JobFailed,
+        ("plan", DeploymentStatus::Initiated) => AuditEventType::PlanStarted,
+        ("plan", DeploymentStatus::Successful) if event.has_drifted => {
+            AuditEventType::DriftDetected
+        }
+        ("apply", DeploymentStatus::Successful) => AuditEventType::ApplyFinished,
+        ("destroy", DeploymentStatus::Successful) => AuditEventType::Destroyed,
+        _ => return None,
+    };
+
+    let mut details = serde_json::Map::new();
+    if !event.error_text.is_empty() {
+        details.insert("error".to_string(), json!(event.error_text));
+    }
+    let violations: Vec<Value> = event
+        .policy_results
+        .iter()
+        .filter(|result| result.failed)
+        .map(|result| {
+            json!({
+                "policy": result.policy,
+                "version": result.version,
+                "violations": result.violations,
+            })
+        })
+        .collect();
+    if !violations.is_empty() {
+        details.insert("policy_violations".to_string(), Value::Array(violations));
+    }
+    if let Some(comment) = event
+        .metadata
+        .get("comment")
+        .and_then(Value::as_str)
+        .filter(|comment| !comment.is_empty())
+    {
+        details.insert("comment".to_string(), json!(comment));
+    }
+
+    Some(AuditEvent {
+        event_type,
+        timestamp: event.timestamp.clone(),
+        epoch: event.epoch,
+        actor: event.initiated_by.clone(),
+        project_id: event.project_id.clone(),
+        region: event.region.clone(),
+        environment: event.environment.clone(),
+        deployment_id: event.deployment_id.clone(),
+        module: event.module.clone(),
+        module_version: event.module_version.clone(),
+        job_id: event.job_id.clone(),
+        status: event.status.clone(),
+        details,
+    })
+}
+
+/// Audit log entries of the region of the handler from `start_epoch` until `end_epoch`, oldest first
+pub async fn get_audit_events(
+    handler: &GenericCloudHandler,
+    start_epoch: u128,
+    end_epoch: u128,
+) -> Result<Vec<AuditEvent>, anyhow::Error> {
+    let mut events: Vec<AuditEvent> = handler
+        .get_all_events_between(start_epoch, end_epoch)
+        .await?
+        .iter()
+        .filter_map(audit_event)
+        .collect();
+    events.sort_by_key(|event| event.epoch);
+    Ok(events)
+}
+
+/// Writes the events as JSON lines, each signed with HMAC-SHA256 and chained to the line before it
+pub fn sign_audit_log(events: &[AuditEvent], key: &str) -> Result<String, anyhow::Error> {
+    let mut log = String::new();
+    let mut previous_signature = String::new();
+    for event in events {
+        let signature = audit_signature(key, event, &previous_signature)?;
+        let record = AuditLogRecord {
+            event: event.clone(),
+            previous_signature,
+            signature: signature.clone(),
+        };
+        log.push_str(&serde_json::to_string(&record)?);
+        log.push('\n');
+        previous_signature = signature;
+    }
+    Ok(log)
+}
+
+/// Checks the signatures of an audit log written by `sign_audit_log`, returning its number of events
+pub fn verify_audit_log(log: &str, key: &str) -> Result<usize, anyhow::Error> {
+    let mut previous_signature = String::new();
+    let mut count = 0;
+    for (index, line) in log.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: AuditLogRecord = serde_json::from_str(line)
+            .map_err(|e| anyhow::anyhow!("Line {} is not an audit log record: {}", index + 1, e))?;
+        if record.previous_signature != previous_signature
+            || record.signature != audit_signature(key, &record.event, &previous_signature)?
+        {
+            return Err(anyhow::anyhow!(
+                "The signature of line {} does not match, the line was changed or lines before it were removed",
+                index + 1
+            ));
+        }
+        previous_signature = record.signature;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn audit_signature(
+    key: &str,
+    event: &AuditEvent,
+    previous_signature: &str,
+) -> Result<String, anyhow::Error> {
+    let body = format!("{}\n{}", previous_signature, serde_json::to_string(event)?);
+    Ok(sign_body(key, &body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use env_defs::{DriftDetection, PolicyResult};
+
+    fn event(event: &str, status: DeploymentStatus, epoch: u128) -> EventData {
+        EventData {
+            deployment_id: "s3bucket/my-bucket".to_string(),
+            project_id: "123".to_string(),
+            region: "us-west-2".to_string(),
+            environment: "cli/prod".to_string(),
+            event: event.to_string(),
+            epoch,
+            error_text: String::new(),
+            id: String::new(),
+            job_id: "job-1".to_string(),
+            metadata: json!({}),
+            drift_detection: DriftDetection {
+                enabled: false,
+                interval: String::new(),
+                auto_remediate: false,
+                webhooks: vec![],
+                schedule: None,
+                blackout_windows: vec![],
+            },
+            next_drift_check_epoch: -1,
+            has_drifted: false,
+            module: "s3bucket".to_string(),
+            module_version: "0.1.0".to_string(),
+            name: "my-bucket".to_string(),
+            status,
+            timestamp: String::new(),
+            output: json!({}),
+            policy_results: vec![],
+            initiated_by: "arn:aws:iam::123:user/alice".to_string(),
+            event_duration: 0,
+        }
+    }
+
+    fn event_type(event: &EventData) -> Option<AuditEventType> {
+        audit_event(event).map(|audit_event| audit_event.event_type)
+    }
+
+    #[test]
+    fn test_audit_event_type() {
+        use AuditEventType::*;
+        use DeploymentStatus::*;
+
+        assert_eq!(
+            event_type(&event("apply", Requested, 1)),
+            Some(ClaimSubmitted)
+        );
+        assert_eq!(
+            event_type(&event("apply", PendingApproval, 1)),
+            Some(ClaimSubmitted)
+        );
+        assert_eq!(event_type(&event("plan", Initiated, 1)), Some(PlanStarted));
+        assert_eq!(event_type(&event("plan", Successful, 1)), None);
+        assert_eq!(event_type(&event("apply", Initiated, 1)), None);
+        assert_eq!(
+            event_type(&event("apply", Successful, 1)),
+            Some(ApplyFinished)
+        );
+        assert_eq!(
+            event_type(&event("destroy", Successful, 1)),
+            Some(Destroyed)
+        );
+        assert_eq!(
+            event_type(&event("apply", FailedPolicy, 1)),
+            Some(PolicyViolated)
+        );
+        assert_eq!(event_type(&event("apply", FailedPlan, 1)), Some(JobFailed));
+        assert_eq!(
+            event_type(&event(APPROVE_EVENT, PendingApproval, 1)),
+            Some(ApprovalGranted)
+        );
+        assert_eq!(
+            event_type(&event(REJECT_EVENT, Rejected, 1)),
+            Some(ApprovalRejected)
+        );
+        assert_eq!(
+            event_type(&event(WEBHOOK_DELIVERY_EVENT, Successful, 1)),
+            None
+        );
+
+        let mut drift = event("plan", Successful, 1);
+        drift.has_drifted = true;
+        assert_eq!(event_type(&drift), Some(DriftDetected));
+    }
+
+    #[test]
+    fn test_audit_event_details() {
+        let mut violated = event("apply", DeploymentStatus::FailedPolicy, 1);
+        violated.policy_results = vec![PolicyResult {
+            policy: "regions".to_string(),
+            version: "0.1.0".to_string(),
+            environment: "stable".to_string(),
+            description: String::new(),
+            policy_name: "regions".to_string(),
+            failed: true,
+            violations: json!({ "regions": ["Region ap-south-1 is not allowed"] }),
+            policy_pack: None,
+        }];
+        let violation = audit_event(&violated).unwrap();
+        assert_eq!(violation.actor, "arn:aws:iam::123:user/alice");
+        assert_eq!(
+            Value::Object(violation.details),
+            json!({
+                "policy_violations": [{
+                    "policy": "regions",
+                    "version": "0.1.0",
+                    "violations": { "regions": ["Region ap-south-1 is not allowed"] }
+                }]
+            })
+        );
+
+        let mut rejected = event(REJECT_EVENT, DeploymentStatus::Rejected, 1);
+        rejected.metadata = json!({ "comment": "Not during the freeze" });
+        assert_eq!(
+            Value::Object(audit_event(&rejected).unwrap().details),
+            json!({ "comment": "Not during the freeze" })
+        );
+    }
+
+    #[test]
+    fn test_sign_and_verify_audit_log() {
+        let events: Vec<AuditEvent> = [
+            event("apply", DeploymentStatus::Requested, 1),
+            event("apply", DeploymentStatus::Successful, 2),
+            event("destroy", DeploymentStatus::Successful, 3),
+        ]
+        .iter()
+        .filter_map(audit_event)
+        .collect();
+
+        let log = sign_audit_log(&events, "secret").unwrap();
+        assert_eq!(log.lines().count(), 3);
+        assert_eq!(verify_audit_log(&log, "secret").unwrap(), 3);
+        assert!(verify_audit_log(&log, "other secret").is_err());
+
+        let lines: Vec<&str> = log.lines().collect();
+        let removed = format!("{}\n{}\n", lines[0], lines[2]);
+        assert!(verify_audit_log(&removed, "secret").is_err());
+        let changed = log.replace(
+            "\"actor\":\"arn:aws:iam::123:user/alice\"",
+            "\"actor\":\"bob\"",
+        );
+        assert!(verify_audit_log(&changed, "secret").is_err());
+    }
+}
@@ -1,6 +1,9 @@
 use base64::engine::general_purpose::STANDARD as base64;
 use base64::Engine;
-use env_defs::{get_change_record_identifier, CloudProvider, InfraChangeRecord};
+use env_defs::{
+    change_record_max_item_bytes, get_change_record_identifier, split_change_record_item,
+    CloudProvider, DeploymentResp, InfraChangeRecord, SanitizedResourceChange,
+};
 use env_utils::merge_json_dicts;
 
 use crate::interface::GenericCloudHandler;
@@ -9,15 +12,9 @@ pub async fn insert_infra_change_record(
     handler: &GenericCloudHandler,
     infra_change_record: InfraChangeRecord,
 ) -> Result<String, anyhow::Error> {
-    let pk_prefix = match infra_change_record.change_type.as_str() {
-        "apply" | "destroy" => "MUTATE",
-        "plan" => "PLAN",
-        _ => "UNKNOWN",
-    };
-
     let pk = format!(
         "{}#{}",
-        pk_prefix,
+        change_record_pk_prefix(&infra_change_record.change_type),
         get_change_record_identifier(
             &infra_change_record.project_id,
             &infra_change_record.region,
@@ -39,18 +36,31 @@ pub async fn insert_infra_change_record(
         infra_change_record_value
     );
 
-    let payload = env_defs::insert_db_event("change_records", &infra_change_record_payload);
+    // Records over the item size limit of the database are stored in parts, see get_change_record
+    let max_item_bytes = change_record_max_item_bytes(handler.get_cloud_provider());
+    for item in split_change_record_item(&infra_change_record_payload, max_item_bytes) {
+        let payload = env_defs::insert_db_event("change_records", &item);
+        if let Err(e) = handler.run_function(&payload).await {
+            return Err(anyhow::anyhow!("Failed to insert event: {}", e));
+        }
+    }
+    Ok("".to_string())
+}
 
-    match handler.run_function(&payload).await {
-        Ok(_) => Ok("".to_string()),
-        Err(e) => Err(anyhow::anyhow!("Failed to insert event: {}", e)),
+/// Prefix of the partition key change records of `change_type` are stored under
+pub(crate) fn change_record_pk_prefix(change_type: &str) -> &'static str {
+    match change_type {
+        "apply" | "destroy" => "MUTATE",
+        "plan" => "PLAN",
+        "drift" => "DRIFT",
+        _ => "UNKNOWN",
     }
 }
 
 pub async fn upload_file_to_change_records<T: CloudProvider>(
     handler: &T,
     key: &str,
-    content: &str,
+    content: impl AsRef<[u8]>,
 ) -> Result<String, anyhow::Error> {
     let base64_content = base64.encode(content);
 
@@ -61,3 +71,34 @@ pub async fn upload_file_to_change_records<T: CloudProvider>(
         Err(e) => Err(anyhow::anyhow!("Failed to upload file: {}", e)),
     }
 }
+
+pub async fn download_file_from_change_records<T: CloudProvider>(
+    handler: &T,
+    key: &str,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let url = handler
+        .generate_presigned_url(key, "change_records")
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to get download url for {}: {}", key, e))?;
+    env_utils::download_zip_to_vec(&url).await
+}
+
+/// Resources that drifted in the latest drift check of a deployment, `None` if it has not drifted.
+/// A drift check updates the deployment with its own job id, which the drift change record is stored under
+pub async fn get_drift_report(
+    handler: &GenericCloudHandler,
+    deployment: &DeploymentResp,
+) -> Result<Option<Vec<SanitizedResourceChange>>, anyhow::Error> {
+    if !deployment.has_drifted {
+        return Ok(None);
+    }
+    let drift_change_record = handler
+        .get_change_record(
+            &deployment.environment,
+            &deployment.deployment_id,
+            &deployment.job_id,
+            "DRIFT",
+        )
+        .await?;
+    Ok(Some(drift_change_record.resource_changes))
+}
@@ -0,0 +1,138 @@
+use env_defs::{CloudProvider, PolicyResp, PolicyResult, PolicyTarget};
+use env_utils::{download_zip_to_vec, read_files_from_zip};
+use log::info;
+use serde_json::{json, Value};
+
+use crate::interface::GenericCloudHandler;
+use crate::logic::api_policy::get_applicable_policies;
+
+/// Evaluates the claim policies that apply to the deployment with `request` (the rendered claim and
+/// module metadata, as posted to validation webhooks) as OPA input, failing with the violations of
+/// every policy the claim breaks. Plan policies are left to the runner
+pub async fn run_claim_policy_checks(
+    handler: &GenericCloudHandler,
+    request: &Value,
+    environment: &str,
+    track: &str,
+) -> Result<Vec<PolicyResult>, anyhow::Error> {
+    let policy_environment = "stable";
+    let policies: Vec<(PolicyResp, Option<String>)> = get_applicable_policies(
+        handler,
+        policy_environment,
+        handler.get_project_id(),
+        environment,
+        track,
+    )
+    .await?
+    .into_iter()
+    .filter(|(policy, _)| policy.manifest.spec.target == PolicyTarget::Claim)
+    .collect();
+    if policies.is_empty() {
+        return Ok(vec![]);
+    }
+
+    info!("Evaluating {} claim policies", policies.len());
+    let mut policy_results = vec![];
+    for (policy, policy_pack) in policies {
+        let url = handler.get_policy_download_url(&policy.s3_key).await?;
+        let zip = download_zip_to_vec(&url).await?;
+        let rego_files = read_files_from_zip(&zip, "rego")?;
+        let violations = evaluate_policy(&rego_files, &policy.data, request)
+            .map_err(|e| anyhow::anyhow!("Failed to evaluate policy {}: {}", policy.policy, e))?;
+        policy_results.push(PolicyResult {
+            policy: policy.policy.clone(),
+            version: policy.version.clone(),
+            environment: policy.environment.clone(),
+            description: policy.description.clone(),
+            policy_name: policy.policy_name.clone(),
+            failed: violations.as_object().is_some_and(|v| !v.is_empty()),
+            violations,
+            policy_pack,
+        });
+    }
+
+    let failed: Vec<String> = policy_results
+        .iter()
+        .filter(|result| result.failed)
+        .map(|result| format!("{}: {}", result.policy, result.violations))
+        .collect();
+    if !failed.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Claim violates {} policies:\n{}",
+            failed.len(),
+            failed.join("\n")
+        ));
+    }
+    Ok(policy_results)
+}
+
+/// Runs the policy's rego files the way the runner runs plan policies: every package below
+/// `data.infraweave` with a non-empty `deny` set is a violation. Returns the violations by package
+pub(super) fn evaluate_policy(
+    rego_files: &[(String, String)],
+    data: &Value,
+    input: &Value,
+) -> Result<Value, anyhow::Error> {
+    let mut engine = regorus::Engine::new();
+    for (name, rego) in rego_files {
+        engine.add_policy(name.clone(), rego.clone())?;
+    }
+    engine.add_data(regorus::Value::from_json_str(&data.to_string())?)?;
+    engine.set_input(regorus::Value::from_json_str(&input.to_string())?);
+
+    let results = engine.eval_query("data.infraweave".to_string(), false)?;
+    let packages: Value = match results
+        .result
+        .first()
+        .and_then(|result| result.expressions.first())
+    {
+        Some(expression) => serde_json::from_str(&expression.value.to_json_str()?)?,
+        None => json!({}),
+    };
+
+    let mut violations = json!({});
+    if let Some(packages) = packages.as_object() {
+        for (package, value) in packages {
+            if let Some(deny) = value.get("deny").and_then(|d| d.as_array()) {
+                if !deny.is_empty() {
+                    violations[package] = Value::Array(deny.clone());
+                }
+            }
+        }
+    }
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOWED_REGIONS: &str = r#"
+package infraweave.claim_regions
+
+import rego.v1
+
+deny contains msg if {
+    not input.region in data.allowed_regions
+    # regorus takes the space after a sprintf verb for a flag and drops it
+    msg := concat(" ", ["Region", input.region, "is not allowed"])
+}
+"#;
+
+    #[test]
+    fn test_evaluate_policy() {
+        let rego_files = vec![("regions.rego".to_string(), ALLOWED_REGIONS.to_string())];
+        let data = json!({ "allowed_regions": ["eu-west-1", "us-east-1"] });
+
+        let violations =
+            evaluate_policy(&rego_files, &data, &json!({ "region": "eu-west-1" })).unwrap();
+        assert_eq!(violations, json!({}));
+
+        let violations =
+            evaluate_policy(&rego_files, &data, &json!({ "region": "ap-south-1" })).unwrap();
+        assert_eq!(
+            violations,
+            json!({ "claim_regions": ["Region ap-south-1 is not allowed"] })
+        );
+    }
+}
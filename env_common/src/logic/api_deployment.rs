@@ -1,11 +1,14 @@
 use std::collections::HashSet;
 
-use env_defs::{get_deployment_identifier, CloudProvider, DeploymentResp};
-use env_utils::merge_json_dicts;
+use env_defs::{
+    get_deployment_identifier, sanitize_terraform_output, CloudProvider, DeploymentResp, ValueFrom,
+};
+use env_utils::{merge_json_dicts, register_secret_value, to_snake_case};
+use serde_json::Value;
 
 use crate::interface::GenericCloudHandler;
 
-fn get_payload(deployment: &DeploymentResp, is_plan: bool) -> serde_json::Value {
+pub(crate) fn get_payload(deployment: &DeploymentResp, is_plan: bool) -> serde_json::Value {
     let pk_prefix: &str = match is_plan {
         true => "PLAN",
         false => "DEPLOYMENT",
@@ -71,15 +74,12 @@ pub async fn set_deployment(
     // Prepare transaction items
     let mut transaction_items = vec![];
 
-    // Fetch existing dependencies (needed in both cases)
-    let existing_dependencies = match handler
+    // Fetch existing deployment for its dependencies (needed in both cases)
+    let existing_deployment = match handler
         .get_deployment(&deployment.deployment_id, &deployment.environment, false)
         .await
     {
-        Ok(deployment) => match deployment {
-            Some(deployment) => deployment.dependencies,
-            None => vec![],
-        },
+        Ok(deployment) => deployment,
         Err(e) => {
             return Err(anyhow::anyhow!(
                 "Failed to get deployment to find dependents: {}",
@@ -87,6 +87,18 @@ pub async fn set_deployment(
             ))
         }
     };
+    let existing_dependencies = match &existing_deployment {
+        Some(existing) => existing.dependencies.clone(),
+        None => vec![],
+    };
+
+    // Only drift checks set the time of the last drift check, other jobs keep the existing one
+    let deployment = &DeploymentResp {
+        last_drift_check_epoch: deployment.last_drift_check_epoch.or(existing_deployment
+            .as_ref()
+            .and_then(|existing| existing.last_drift_check_epoch)),
+        ..deployment.clone()
+    };
 
     let deployment_payload = get_payload(deployment, is_plan);
 
@@ -180,23 +192,32 @@ pub async fn set_deployment(
                 })
                 .collect();
 
-            // Identify dependencies to be added and removed
-            let dependencies_to_add = new_dependency_set.difference(&old_dependency_set);
+            // Identify dependencies to be removed
             let dependencies_to_remove = old_dependency_set.difference(&new_dependency_set);
 
-            // Add new DEPENDENT items
-            for dependency_pk in dependencies_to_add {
+            // Upsert DEPENDENT items, also for existing ones in case onOutputChange was changed
+            for dependency in deployment.dependencies.iter() {
+                let dependency_pk = format!(
+                    "DEPLOYMENT#{}",
+                    get_deployment_identifier(
+                        &dependency.project_id,
+                        &dependency.region,
+                        &dependency.deployment_id,
+                        &dependency.environment
+                    )
+                );
                 transaction_items.push(serde_json::json!({
                     "Put": {
                         "TableName": deployment_table_placeholder, // TODO: Use Dependent class
                         "Item": {
-                            "PK": dependency_pk.clone(),
+                            "PK": dependency_pk,
                             "SK": format!("DEPENDENT#{}", get_deployment_identifier(&deployment.project_id, &deployment.region, &deployment.deployment_id, &deployment.environment)),
                             "dependent_id": deployment.deployment_id,
                             "module": deployment.module,
                             "environment": deployment.environment,
                             "project_id": deployment.project_id,
                             "region": deployment.region,
+                            "on_output_change": dependency.on_output_change,
                         }
                     }
                 }));
@@ -239,3 +260,157 @@ pub async fn set_deployment(
 
     Ok(())
 }
+
+/// Terraform outputs of a deployment as `{name: {value, type, sensitive}}`. Sensitive values are
+/// masked unless `unmask` is set, in which case the outputs are read from the state file
+pub async fn get_deployment_outputs(
+    handler: &GenericCloudHandler,
+    deployment_id: &str,
+    environment: &str,
+    unmask: bool,
+) -> Result<Value, anyhow::Error> {
+    let deployment = handler
+        .get_deployment(deployment_id, environment, false)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Deployment not found: {}", deployment_id))?;
+    if !unmask {
+        return Ok(sanitize_terraform_output(deployment.output));
+    }
+    let state = handler.read_state_file(environment, deployment_id).await?;
+    state_outputs(&state)
+}
+
+/// The variables with each `valueFrom` replaced by the output of the deployment it references,
+/// for the tfvars of the job only. Sensitive outputs are read from the state of the upstream
+/// deployment and registered to be masked in logs
+pub async fn resolve_value_from_refs(
+    handler: &GenericCloudHandler,
+    variables: &Value,
+    environment: &str,
+) -> Result<Value, anyhow::Error> {
+    let Some(map) = variables.as_object() else {
+        return Ok(variables.clone());
+    };
+
+    let mut resolved = map.clone();
+    for (name, value) in resolved.iter_mut() {
+        let Some(value_from) = ValueFrom::from_value(value) else {
+            continue;
+        };
+        let value_from = value_from
+            .map_err(|e| anyhow::anyhow!("Invalid valueFrom for variable {}: {}", name, e))?;
+        let upstream_id = value_from.deployment.to_lowercase();
+        let upstream_environment = value_from.environment_or(environment);
+        let outputs = get_deployment_outputs(handler, &upstream_id, upstream_environment, false)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to read outputs of {} in {} for variable {}: {}",
+                    upstream_id,
+                    upstream_environment,
+                    name,
+                    e
+                )
+            })?;
+        let (output_name, output) = find_output(&outputs, &value_from.output).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Deployment {} in {} has no output {}, referenced by variable {}",
+                upstream_id,
+                upstream_environment,
+                value_from.output,
+                name
+            )
+        })?;
+        let output_value = if output["sensitive"].as_bool().unwrap_or(false) {
+            let unmasked =
+                get_deployment_outputs(handler, &upstream_id, upstream_environment, true).await?;
+            let output_value = unmasked[&output_name]["value"].clone();
+            register_secret_value(&match &output_value {
+                Value::String(secret) => secret.clone(),
+                other => other.to_string(),
+            });
+            output_value
+        } else {
+            output["value"].clone()
+        };
+        log::info!(
+            "Resolved variable {} from output {} of {} in {}",
+            name,
+            output_name,
+            upstream_id,
+            upstream_environment
+        );
+        *value = output_value;
+    }
+    Ok(Value::Object(resolved))
+}
+
+/// Output named `name` as in the module, or in camelCase as in claims
+fn find_output(outputs: &Value, name: &str) -> Option<(String, Value)> {
+    let outputs = outputs.as_object()?;
+    [name.to_string(), to_snake_case(name)]
+        .into_iter()
+        .find_map(|name| outputs.get(&name).map(|output| (name, output.clone())))
+}
+
+fn state_outputs(state: &[u8]) -> Result<Value, anyhow::Error> {
+    let state: Value = serde_json::from_slice(state)
+        .map_err(|e| anyhow::anyhow!("Failed to parse state file: {}", e))?;
+    let mut outputs = serde_json::Map::new();
+    if let Some(state_outputs) = state.get("outputs").and_then(|o| o.as_object()) {
+        for (name, output) in state_outputs {
+            outputs.insert(
+                name.clone(),
+                serde_json::json!({
+                    "value": output.get("value").cloned().unwrap_or(Value::Null),
+                    "type": output.get("type").cloned().unwrap_or(Value::Null),
+                    "sensitive": output.get("sensitive").and_then(|v| v.as_bool()).unwrap_or(false),
+                }),
+            );
+        }
+    }
+    Ok(Value::Object(outputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_output() {
+        let outputs = serde_json::json!({
+            "vpc_id": {"value": "vpc-123", "type": "string", "sensitive": false},
+            "subnetIds": {"value": ["subnet-1"], "type": ["list", "string"], "sensitive": false},
+        });
+
+        let (name, output) = find_output(&outputs, "vpcId").unwrap();
+        assert_eq!(name, "vpc_id");
+        assert_eq!(output["value"], "vpc-123");
+        assert_eq!(find_output(&outputs, "vpc_id").unwrap().0, "vpc_id");
+        assert_eq!(find_output(&outputs, "subnetIds").unwrap().0, "subnetIds");
+        assert!(find_output(&outputs, "routeTableId").is_none());
+    }
+
+    #[test]
+    fn test_state_outputs() {
+        let state = serde_json::json!({
+            "version": 4,
+            "outputs": {
+                "bucket_arn": {"value": "arn:aws:s3:::my-bucket", "type": "string"},
+                "password": {"value": "hunter2", "type": "string", "sensitive": true},
+            },
+            "resources": [],
+        });
+
+        let outputs = state_outputs(state.to_string().as_bytes()).unwrap();
+
+        assert_eq!(outputs["bucket_arn"]["value"], "arn:aws:s3:::my-bucket");
+        assert_eq!(outputs["bucket_arn"]["sensitive"], false);
+        assert_eq!(outputs["password"]["value"], "hunter2");
+        assert_eq!(outputs["password"]["sensitive"], true);
+        assert_eq!(
+            sanitize_terraform_output(outputs)["password"]["value"],
+            env_defs::SANITIZED_OUTPUT_VALUE
+        );
+    }
+}
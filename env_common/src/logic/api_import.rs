@@ -0,0 +1,235 @@
+use env_defs::{
+    sanitize_terraform_output, ApiInfraPayloadWithVariables, CloudProvider, DeploymentStatus,
+    ExtraData,
+};
+use log::info;
+use serde_json::Value;
+
+use super::validate_and_prepare_claim;
+use crate::{interface::GenericCloudHandler, DeploymentStatusHandler};
+
+/// Command recorded on the event and deployment of an import
+pub const IMPORT_COMMAND: &str = "import";
+
+/// Adopts infrastructure managed outside of InfraWeave. The claim is validated like for an
+/// apply, the terraform state is written to the backend key of the deployment and the
+/// deployment is recorded as successful, without running a job.
+///
+/// Returns the deployment id and the payload of the claim, which a verification plan can be
+/// submitted with
+pub async fn import_deployment(
+    handler: &GenericCloudHandler,
+    yaml: &serde_yaml::Value,
+    environment: &str,
+    state_file: &[u8],
+    reference_fallback: &str,
+) -> Result<(String, ApiInfraPayloadWithVariables), anyhow::Error> {
+    if http_client::is_http_mode_enabled() {
+        return Err(anyhow::anyhow!(
+            "Importing deployments is not supported in HTTP mode"
+        ));
+    }
+    let state: Value = serde_json::from_slice(state_file)
+        .map_err(|e| anyhow::anyhow!("State is not valid JSON: {}", e))?;
+    validate_terraform_state(&state)?;
+
+    let (deployment_id, payload_with_variables) = validate_and_prepare_claim(
+        handler,
+        yaml,
+        environment,
+        IMPORT_COMMAND,
+        vec![],
+        ExtraData::None,
+        reference_fallback,
+    )
+    .await?;
+    let payload = &payload_with_variables.payload;
+
+    if handler
+        .get_deployment(&deployment_id, environment, false)
+        .await?
+        .is_some()
+    {
+        return Err(anyhow::anyhow!(
+            "Deployment {} already exists in {}, only new deployments can be imported",
+            deployment_id,
+            environment
+        ));
+    }
+
+    handler
+        .write_state_file(environment, &deployment_id, state_file)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to upload the state of {}: {}", deployment_id, e))?;
+    info!("Uploaded the state of {} in {}", deployment_id, environment);
+
+    let job_id = format!("import-{}", uuid::Uuid::new_v4());
+    let mut status_handler = DeploymentStatusHandler::new(
+        IMPORT_COMMAND,
+        &payload.module,
+        &payload.module_version,
+        &payload.module_type,
+        &payload.module_track,
+        DeploymentStatus::Successful,
+        &payload.environment,
+        &payload.deployment_id,
+        &payload.project_id,
+        &payload.region,
+        String::new(),
+        job_id,
+        &payload.name,
+        payload_with_variables.variables.clone(),
+        payload.drift_detection.clone(),
+        payload.next_drift_check_epoch,
+        payload.dependencies.clone(),
+        state_outputs(&state),
+        vec![],
+        &payload.initiated_by,
+        payload.cpu.clone(),
+        payload.memory.clone(),
+        payload.reference.clone(),
+    );
+    status_handler.set_resources(Some(state_resource_addresses(&state)));
+    status_handler.send_event(handler).await;
+    status_handler.send_deployment(handler).await?;
+
+    Ok((deployment_id, payload_with_variables))
+}
+
+/// Checks that the file is a terraform state, such as the output of `terraform state pull`,
+/// and not the backend configuration terraform keeps in `.terraform/terraform.tfstate`
+fn validate_terraform_state(state: &Value) -> Result<(), anyhow::Error> {
+    if state.get("backend").is_some() {
+        return Err(anyhow::anyhow!(
+            "The file holds the backend configuration of a working directory, not its state. Use `terraform state pull` to read the state"
+        ));
+    }
+    match state.get("version").and_then(Value::as_u64) {
+        Some(4) => {}
+        Some(version) => {
+            return Err(anyhow::anyhow!(
+                "Unsupported state version {}, upgrade the state with terraform 0.12 or later",
+                version
+            ))
+        }
+        None => return Err(anyhow::anyhow!("The file is not a terraform state")),
+    }
+    if !state.get("resources").is_some_and(Value::is_array) {
+        return Err(anyhow::anyhow!("The state has no resources list"));
+    }
+    Ok(())
+}
+
+/// Outputs of the state in the format of `terraform output -json`, with sensitive values masked
+fn state_outputs(state: &Value) -> Value {
+    match state.get("outputs") {
+        Some(outputs) => sanitize_terraform_output(outputs.clone()),
+        None => Value::Object(Default::default()),
+    }
+}
+
+/// Addresses of the resource instances in the state, as listed by `terraform state list`
+fn state_resource_addresses(state: &Value) -> Vec<String> {
+    let mut addresses = vec![];
+    for resource in state["resources"].as_array().into_iter().flatten() {
+        let mut address = String::new();
+        if let Some(module) = resource["module"].as_str() {
+            address.push_str(module);
+            address.push('.');
+        }
+        if resource["mode"] == "data" {
+            address.push_str("data.");
+        }
+        address.push_str(&format!(
+            "{}.{}",
+            resource["type"].as_str().unwrap_or_default(),
+            resource["name"].as_str().unwrap_or_default()
+        ));
+        for instance in resource["instances"].as_array().into_iter().flatten() {
+            match &instance["index_key"] {
+                Value::Number(index) => addresses.push(format!("{}[{}]", address, index)),
+                Value::String(key) => addresses.push(format!("{}[\"{}\"]", address, key)),
+                _ => addresses.push(address.clone()),
+            }
+        }
+    }
+    addresses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn state() -> Value {
+        json!({
+            "version": 4,
+            "terraform_version": "1.8.0",
+            "serial": 12,
+            "lineage": "3f1c5a2e-8f0b-4a7c-9d1e-2b6a4c8e0f13",
+            "outputs": {
+                "bucket_arn": {"value": "arn:aws:s3:::my-bucket", "type": "string"},
+                "password": {"value": "hunter2", "type": "string", "sensitive": true}
+            },
+            "resources": [
+                {
+                    "mode": "managed",
+                    "type": "aws_s3_bucket",
+                    "name": "this",
+                    "instances": [{"attributes": {}}]
+                },
+                {
+                    "mode": "data",
+                    "type": "aws_caller_identity",
+                    "name": "current",
+                    "instances": [{"attributes": {}}]
+                },
+                {
+                    "module": "module.logs",
+                    "mode": "managed",
+                    "type": "aws_s3_object",
+                    "name": "files",
+                    "instances": [
+                        {"index_key": 0, "attributes": {}},
+                        {"index_key": "readme", "attributes": {}}
+                    ]
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_state_resource_addresses() {
+        assert_eq!(
+            state_resource_addresses(&state()),
+            vec![
+                "aws_s3_bucket.this",
+                "data.aws_caller_identity.current",
+                "module.logs.aws_s3_object.files[0]",
+                "module.logs.aws_s3_object.files[\"readme\"]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_state_outputs() {
+        let outputs = state_outputs(&state());
+        assert_eq!(outputs["bucket_arn"]["value"], "arn:aws:s3:::my-bucket");
+        assert_eq!(
+            outputs["password"]["value"],
+            env_defs::SANITIZED_OUTPUT_VALUE
+        );
+    }
+
+    #[test]
+    fn test_validate_terraform_state() {
+        assert!(validate_terraform_state(&state()).is_ok());
+        assert!(validate_terraform_state(&json!({"version": 3, "modules": []})).is_err());
+        assert!(validate_terraform_state(&json!({
+            "version": 3,
+            "backend": {"type": "s3", "config": {}}
+        }))
+        .is_err());
+        assert!(validate_terraform_state(&json!({"resources": []})).is_err());
+    }
+}
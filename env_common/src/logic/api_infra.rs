@@ -1,15 +1,22 @@
 use env_defs::{
-    ApiInfraPayload, ApiInfraPayloadWithVariables, CloudHandlerError, CloudProvider, Dependency,
-    DeploymentManifest, DeploymentResp, DeploymentStatus, DriftDetection, ExtraData,
-    GenericFunctionResponse, Webhook,
+    ApiInfraPayload, ApiInfraPayloadWithVariables, ArtifactVerificationPolicy, CloudHandlerError,
+    CloudProvider, Dependency, DependencyTrigger, Dependent, DeploymentManifest, DeploymentResp,
+    DeploymentStatus, DriftDetection, ExtraData, GenericFunctionResponse, ValueFrom, Webhook,
+    WebhookEvent,
 };
 use env_utils::{
     convert_first_level_keys_to_snake_case, flatten_and_convert_first_level_keys_to_snake_case,
-    get_version_track, verify_required_variables_are_set, verify_variable_claim_casing,
-    verify_variable_existence_and_type,
+    get_version_track, to_camel_case, verify_required_variables_are_set,
+    verify_variable_claim_casing, verify_variable_existence_and_type,
 };
 use log::{debug, error, info, warn};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet, VecDeque};
 
+use super::api_job_queue::{admit_job, hold_job_slot, JobAdmission};
+use super::api_job_stats::runner_size;
+use super::api_webhook::notify_webhooks;
+use super::{run_claim_policy_checks, run_validation_webhooks, validate_drift_detection};
 use crate::{interface::GenericCloudHandler, DeploymentStatusHandler};
 
 pub async fn mutate_infra(
@@ -87,6 +94,16 @@ pub async fn validate_and_prepare_claim(
         None => vec![],
     };
 
+    let drift_detection_schedule = deployment_manifest
+        .spec
+        .drift_detection
+        .as_ref()
+        .and_then(|drift_detection| drift_detection.schedule.clone());
+    let drift_detection_blackout_windows = match &deployment_manifest.spec.drift_detection {
+        Some(drift_detection) => drift_detection.blackout_windows.clone(),
+        None => vec![],
+    };
+
     let drift_detection: DriftDetection = if deployment_manifest.spec.drift_detection.is_none() {
         serde_json::from_value(serde_json::json!({})).unwrap()
     } else {
@@ -95,8 +112,11 @@ pub async fn validate_and_prepare_claim(
             enabled: drift_detection_enabled,
             auto_remediate: drift_detection_auto_remediate,
             webhooks: drift_detection_webhooks,
+            schedule: drift_detection_schedule,
+            blackout_windows: drift_detection_blackout_windows,
         }
     };
+    validate_drift_detection(&drift_detection)?;
 
     let deployment_variables: serde_yaml::Mapping = deployment_manifest.spec.variables;
     let provided_variables: serde_json::Value = if deployment_variables.is_empty() {
@@ -124,7 +144,7 @@ pub async fn validate_and_prepare_claim(
         }
     };
 
-    let dependencies: Vec<Dependency> = match deployment_manifest.spec.dependencies {
+    let mut dependencies: Vec<Dependency> = match deployment_manifest.spec.dependencies {
         None => vec![],
         Some(dependencies) => dependencies
             .iter()
@@ -137,9 +157,23 @@ pub async fn validate_and_prepare_claim(
                     d.environment.to_lowercase()
                 ),
                 environment: environment.clone(),
+                on_output_change: d.on_output_change.clone().unwrap_or_default(),
             })
             .collect(),
     };
+    for dependency in value_from_dependencies(
+        &provided_variables,
+        &project_id,
+        &region,
+        &environment,
+        &deployment_id,
+    )? {
+        if !dependencies.iter().any(|d| {
+            d.deployment_id == dependency.deployment_id && d.environment == dependency.environment
+        }) {
+            dependencies.push(dependency);
+        }
+    }
 
     let module_version = match is_stack {
         true => deployment_manifest.spec.stack_version.clone().unwrap(),
@@ -151,6 +185,8 @@ pub async fn validate_and_prepare_claim(
         Some(reference) => reference,
     };
 
+    let targets = deployment_manifest.spec.targets.clone();
+
     let annotations: serde_json::Value =
         serde_json::to_value(&deployment_manifest.metadata.annotations)
             .map_err(|e| anyhow::anyhow!("Failed to convert annotations YAML to JSON: {}", e))?;
@@ -219,6 +255,14 @@ pub async fn validate_and_prepare_claim(
         )
         .await?;
     }
+    check_module_yank(
+        handler,
+        &module_resp,
+        is_stack,
+        &deployment_id,
+        &environment,
+    )
+    .await?;
 
     let variables = if is_stack {
         let dont_flatten: Vec<&String> = module_resp
@@ -240,6 +284,36 @@ pub async fn validate_and_prepare_claim(
     // Verify that all provided claim variables are in camelCase and not in snake_case
     verify_variable_claim_casing(&claim, &provided_variables)?;
 
+    // In HTTP mode the server checks claim policies and runs validation webhooks, so skip them here
+    if !http_client::is_http_mode_enabled() {
+        let validation_request = serde_json::json!({
+            "claim": serde_json::to_value(yaml)?,
+            "command": command,
+            "project_id": project_id,
+            "region": region,
+            "environment": environment,
+            "deployment_id": deployment_id,
+            "variables": variables,
+            "module": {
+                "module": module_resp.module,
+                "module_name": module_resp.module_name,
+                "module_type": module_resp.module_type,
+                "track": module_resp.track,
+                "version": module_resp.version,
+                "owners": module_resp.manifest.spec.owners,
+            },
+        });
+        run_claim_policy_checks(handler, &validation_request, &environment, &track).await?;
+        run_validation_webhooks(handler, &validation_request).await?;
+    }
+
+    // In HTTP mode the project settings and job stats aren't readable, so the module defaults apply
+    let (cpu, memory) = if http_client::is_http_mode_enabled() {
+        (module_resp.cpu.clone(), module_resp.memory.clone())
+    } else {
+        runner_size(handler, &module_resp).await
+    };
+
     info!("Validated claim for environment: {}", environment);
     info!("command: {}", command);
     info!("module: {}", module);
@@ -249,6 +323,7 @@ pub async fn validate_and_prepare_claim(
     info!("variables: {}", variables);
     info!("annotations: {}", annotations);
     info!("dependencies: {:?}", dependencies);
+    info!("targets: {:?}", targets);
 
     let payload = ApiInfraPayload {
         command: command.to_string(),
@@ -267,10 +342,15 @@ pub async fn validate_and_prepare_claim(
         annotations,
         dependencies,
         initiated_by: handler.get_user_id().await.unwrap_or("cli".into()),
-        cpu: module_resp.cpu.clone(),
-        memory: module_resp.memory.clone(),
+        cpu,
+        memory,
         reference: reference.clone(),
         extra_data,
+        trigger_reason: None,
+        plan_job_id: None,
+        targets,
+        idempotency_key: None,
+        approved_by: vec![],
     };
 
     let payload_with_variables = ApiInfraPayloadWithVariables {
@@ -281,6 +361,47 @@ pub async fn validate_and_prepare_claim(
     Ok((deployment_id, payload_with_variables))
 }
 
+/// Dependencies on the deployments whose outputs the claim variables reference with `valueFrom`,
+/// so the claim waits for them and is re-planned when their outputs change
+fn value_from_dependencies(
+    variables: &serde_json::Value,
+    project_id: &str,
+    region: &str,
+    environment: &str,
+    deployment_id: &str,
+) -> Result<Vec<Dependency>, anyhow::Error> {
+    let mut dependencies: Vec<Dependency> = vec![];
+    for (name, value) in variables.as_object().into_iter().flatten() {
+        let Some(value_from) = ValueFrom::from_value(value) else {
+            continue;
+        };
+        let value_from = value_from
+            .map_err(|e| anyhow::anyhow!("Variable {} has an invalid valueFrom: {}", name, e))?;
+        let upstream_id = value_from.deployment.to_lowercase();
+        let upstream_environment = value_from.environment_or(environment).to_string();
+        if upstream_id == deployment_id && upstream_environment == environment {
+            return Err(anyhow::anyhow!(
+                "Variable {} references an output of its own deployment",
+                name
+            ));
+        }
+        if dependencies
+            .iter()
+            .any(|d| d.deployment_id == upstream_id && d.environment == upstream_environment)
+        {
+            continue;
+        }
+        dependencies.push(Dependency {
+            project_id: project_id.to_string(),
+            region: region.to_string(),
+            deployment_id: upstream_id,
+            environment: upstream_environment,
+            on_output_change: DependencyTrigger::default(),
+        });
+    }
+    Ok(dependencies)
+}
+
 pub async fn run_claim(
     handler: &GenericCloudHandler,
     yaml: &serde_yaml::Value,
@@ -306,6 +427,96 @@ pub async fn run_claim(
     Ok((job_id, deployment_id, payload_with_variables))
 }
 
+/// Trigger reason recorded on the events of break-glass jobs, which run a module version with
+/// raw variables instead of a claim file
+pub const BREAK_GLASS_TRIGGER_REASON: &str = "break-glass";
+
+/// Builds the claim of a break-glass job. Variables may be given in snake_case or camelCase, and
+/// the reference of the deployment is marked as break-glass so it shows up when described
+pub fn break_glass_claim(
+    module: &env_defs::ModuleResp,
+    name: &str,
+    region: &str,
+    variables: &serde_json::Value,
+    origin: &str,
+) -> Result<serde_yaml::Value, anyhow::Error> {
+    let variables = variables
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("Variables must be a JSON object"))?;
+    let mut claim_variables = serde_yaml::Mapping::new();
+    for (key, value) in variables {
+        claim_variables.insert(
+            serde_yaml::Value::String(to_camel_case(key)),
+            serde_yaml::to_value(value)?,
+        );
+    }
+
+    let claim = serde_json::json!({
+        "apiVersion": "infraweave.io/v1",
+        "kind": module.module_name,
+        "metadata": {
+            "name": name,
+        },
+        "spec": {
+            "region": region,
+            "moduleVersion": module.version,
+            "reference": format!("{}:{}", BREAK_GLASS_TRIGGER_REASON, origin),
+        },
+    });
+    let mut claim = serde_yaml::to_value(claim)?;
+    claim["spec"]["variables"] = serde_yaml::Value::Mapping(claim_variables);
+    Ok(claim)
+}
+
+/// Runs `command` for a module version with raw variables, bypassing claim files for emergency
+/// operations. The job goes through the same validation, webhooks, approvals and policies as a
+/// claim, and its events are tagged with [`BREAK_GLASS_TRIGGER_REASON`] for audit
+#[allow(clippy::too_many_arguments)]
+pub async fn run_break_glass_module(
+    handler: &GenericCloudHandler,
+    module: &str,
+    version: &str,
+    environment: &str,
+    name: &str,
+    variables: &serde_json::Value,
+    command: &str,
+    origin: &str,
+) -> Result<(String, String, ApiInfraPayloadWithVariables), anyhow::Error> {
+    let track = get_version_track(version)
+        .map_err(|e| anyhow::anyhow!("Failed to get track from version: {}", e))?;
+    let module_resp = if http_client::is_http_mode_enabled() {
+        http_client::http_get_module_version(&track, module, version)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to read module version: {}", e))?
+    } else {
+        handler
+            .get_module_version(module, &track, version)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Module version does not exist: {}", version))?
+    };
+
+    let claim = break_glass_claim(&module_resp, name, handler.get_region(), variables, origin)?;
+    let (deployment_id, mut payload_with_variables) = validate_and_prepare_claim(
+        handler,
+        &claim,
+        environment,
+        command,
+        vec![],
+        ExtraData::None,
+        origin,
+    )
+    .await?;
+    payload_with_variables.payload.trigger_reason = Some(BREAK_GLASS_TRIGGER_REASON.to_string());
+
+    warn!(
+        "Submitting break-glass {} of {} version {} in {}, initiated by {}",
+        command, deployment_id, version, environment, payload_with_variables.payload.initiated_by
+    );
+    let job_id = submit_claim_job(handler, &payload_with_variables).await?;
+
+    Ok((job_id, deployment_id, payload_with_variables))
+}
+
 fn validate_kind(kind: &str, module_name: &str) -> Result<(), anyhow::Error> {
     if module_name != kind {
         let error_msg = match module_name.to_lowercase() == kind.to_lowercase() {
@@ -411,6 +622,11 @@ pub async fn destroy_infra(
         memory: deployment.memory,
         reference: deployment.reference,
         extra_data,
+        trigger_reason: None,
+        plan_job_id: None,
+        targets: vec![],
+        idempotency_key: None,
+        approved_by: vec![],
     };
 
     let payload_with_variables = ApiInfraPayloadWithVariables {
@@ -422,6 +638,95 @@ pub async fn destroy_infra(
     Ok(job_id)
 }
 
+/// Deployments to destroy when cascading the destroy of `deployment_id` to everything depending
+/// on it, ordered so that each deployment comes before the deployments it depends on and ending
+/// with `deployment_id` itself
+pub async fn get_cascade_destroy_order(
+    handler: &GenericCloudHandler,
+    deployment_id: &str,
+    environment: &str,
+) -> Result<Vec<Dependent>, anyhow::Error> {
+    let root = Dependent {
+        project_id: handler.get_project_id().to_string(),
+        region: handler.get_region().to_string(),
+        dependent_id: deployment_id.to_string(),
+        environment: environment.to_string(),
+        on_output_change: DependencyTrigger::default(),
+    };
+
+    // Walk the dependents breadth first, dependents may live in other regions or projects
+    let mut dependents: HashMap<String, Vec<Dependent>> = HashMap::new();
+    let mut queue = VecDeque::from([root.clone()]);
+    while let Some(deployment) = queue.pop_front() {
+        let key = cascade_key(&deployment);
+        if dependents.contains_key(&key) {
+            continue;
+        }
+        let found = if deployment.project_id == handler.get_project_id()
+            && deployment.region == handler.get_region()
+        {
+            handler
+                .get_dependents(&deployment.dependent_id, &deployment.environment)
+                .await?
+        } else {
+            GenericCloudHandler::workload(&deployment.project_id, &deployment.region)
+                .await
+                .get_dependents(&deployment.dependent_id, &deployment.environment)
+                .await?
+        };
+        queue.extend(found.iter().cloned());
+        dependents.insert(key, found);
+    }
+
+    order_cascade_destroy(&root, &dependents)
+}
+
+fn cascade_key(deployment: &Dependent) -> String {
+    format!(
+        "{}::{}::{}::{}",
+        deployment.project_id, deployment.region, deployment.environment, deployment.dependent_id
+    )
+}
+
+/// Orders the deployments reachable from `root` so every deployment comes before the ones it
+/// depends on, failing on dependency cycles
+fn order_cascade_destroy(
+    root: &Dependent,
+    dependents: &HashMap<String, Vec<Dependent>>,
+) -> Result<Vec<Dependent>, anyhow::Error> {
+    let mut order = Vec::new();
+    let mut done: HashSet<String> = HashSet::new();
+    let mut in_progress: HashSet<String> = HashSet::new();
+    // (deployment, whether its dependents have been pushed)
+    let mut stack = vec![(root.clone(), false)];
+    while let Some((deployment, expanded)) = stack.pop() {
+        let key = cascade_key(&deployment);
+        if expanded {
+            in_progress.remove(&key);
+            if done.insert(key) {
+                order.push(deployment);
+            }
+            continue;
+        }
+        if done.contains(&key) {
+            continue;
+        }
+        if !in_progress.insert(key.clone()) {
+            return Err(anyhow::anyhow!(
+                "Dependency cycle detected at deployment {} in {}",
+                deployment.dependent_id,
+                deployment.environment
+            ));
+        }
+        stack.push((deployment, true));
+        for dependent in dependents.get(&key).into_iter().flatten() {
+            stack.push((dependent.clone(), false));
+        }
+    }
+    // Dependents were emitted before what they depend on, so the root comes last
+    Ok(order)
+}
+
 async fn verify_module_version(
     handler: &GenericCloudHandler,
     module: &str,
@@ -456,17 +761,137 @@ async fn verify_module_version(
     }
 }
 
+/// Trigger reason of the applies started by drift checks to remediate drift
+pub const DRIFT_REMEDIATION_REASON: &str = "remediation of drift";
+
 pub async fn driftcheck_infra(
     handler: &GenericCloudHandler,
     deployment_id: &str,
     environment: &str,
     remediate: bool,
     extra_data: ExtraData,
+) -> Result<(String, String), anyhow::Error> {
+    let flags = if remediate {
+        vec![]
+    } else {
+        vec!["-refresh-only".to_string()]
+    };
+    let command = if remediate { "apply" } else { "plan" };
+    let trigger_reason = remediate.then(|| DRIFT_REMEDIATION_REASON.to_string());
+
+    rerun_infra(
+        handler,
+        deployment_id,
+        environment,
+        command,
+        flags,
+        extra_data,
+        trigger_reason,
+        None,
+    )
+    .await
+}
+
+/// Applies the changes of a completed plan job exactly as planned, by restoring the workspace
+/// and plan file the plan job cached instead of planning again
+pub async fn apply_plan_infra(
+    handler: &GenericCloudHandler,
+    deployment_id: &str,
+    environment: &str,
+    plan_job_id: &str,
+    extra_data: ExtraData,
+) -> Result<(String, String), anyhow::Error> {
+    rerun_infra(
+        handler,
+        deployment_id,
+        environment,
+        "apply",
+        vec![],
+        extra_data,
+        Some(format!("apply of plan {}", plan_job_id)),
+        Some(plan_job_id),
+    )
+    .await
+}
+
+/// The dependents to re-run after an apply changed the outputs of the deployment they depend on,
+/// none when the outputs are unchanged. Each comes with the command of its `onOutputChange`
+/// setting, None for dependents that ignore output changes
+pub fn dependents_to_trigger<'a>(
+    previous_output: &serde_json::Value,
+    output: &serde_json::Value,
+    dependents: &'a [Dependent],
+) -> Vec<(&'a Dependent, Option<&'static str>)> {
+    if previous_output == output {
+        return vec![];
+    }
+    dependents
+        .iter()
+        .map(|dependent| {
+            let command = match dependent.on_output_change {
+                DependencyTrigger::AutoApply => Some("apply"),
+                DependencyTrigger::PlanOnly => Some("plan"),
+                DependencyTrigger::Ignore => None,
+            };
+            (dependent, command)
+        })
+        .collect()
+}
+
+/// Re-runs a dependent deployment with `command`, as returned by [`dependents_to_trigger`], after
+/// the outputs of the deployment it depends on have changed. The upstream deployment is recorded
+/// as the trigger reason on its events.
+pub async fn trigger_dependent_infra(
+    handler: &GenericCloudHandler,
+    dependent: &Dependent,
+    command: &str,
+    upstream_deployment_id: &str,
+) -> Result<String, anyhow::Error> {
+    let trigger_reason = format!(
+        "triggered by upstream change in {} ({})",
+        upstream_deployment_id, dependent.on_output_change
+    );
+
+    let (job_id, _) = rerun_infra(
+        handler,
+        &dependent.dependent_id,
+        &dependent.environment,
+        command,
+        vec![],
+        ExtraData::None,
+        Some(trigger_reason),
+        None,
+    )
+    .await?;
+    Ok(job_id)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn rerun_infra(
+    handler: &GenericCloudHandler,
+    deployment_id: &str,
+    environment: &str,
+    command: &str,
+    flags: Vec<String>,
+    extra_data: ExtraData,
+    trigger_reason: Option<String>,
+    plan_job_id: Option<&str>,
 ) -> Result<(String, String), anyhow::Error> {
     let name = "".to_string();
 
-    // In HTTP mode, fetch the existing deployment via the HTTP API.
-    let deployment = if http_client::is_http_mode_enabled() {
+    // Applying a plan reruns the deployment as it was planned, with the variables and module version of the plan
+    let deployment = if let Some(plan_job_id) = plan_job_id {
+        let plan = get_plan_deployment(handler, deployment_id, environment, plan_job_id).await?;
+        if plan.status != DeploymentStatus::Successful {
+            return Err(anyhow::anyhow!(
+                "Plan job {} has status {}, only successful plans can be applied",
+                plan_job_id,
+                plan.status
+            ));
+        }
+        Some(plan)
+    } else if http_client::is_http_mode_enabled() {
+        // In HTTP mode, fetch the existing deployment via the HTTP API.
         let project_id = handler.get_project_id();
         let region = handler.get_region();
         let value =
@@ -501,14 +926,7 @@ pub async fn driftcheck_infra(
     let dependencies = deployment.dependencies;
     let module_version = deployment.module_version;
 
-    let flags = if remediate {
-        vec![]
-    } else {
-        vec!["-refresh-only".to_string()]
-    };
-    let command = if remediate { "apply" } else { "plan" };
-
-    info!("Driftcheck deployment: {}", deployment_id);
+    info!("Rerunning deployment: {}", deployment_id);
     info!("command: {}", &command);
     info!("variables: {}", variables);
     info!("annotations: {}", annotations);
@@ -535,6 +953,11 @@ pub async fn driftcheck_infra(
         memory: deployment.memory.clone(),
         reference: deployment.reference.clone(),
         extra_data,
+        trigger_reason,
+        plan_job_id: plan_job_id.map(|job_id| job_id.to_string()),
+        targets: vec![],
+        idempotency_key: None,
+        approved_by: vec![],
     };
 
     let payload_with_variables = ApiInfraPayloadWithVariables {
@@ -547,10 +970,44 @@ pub async fn driftcheck_infra(
     Ok((job_id, region))
 }
 
+async fn get_plan_deployment(
+    handler: &GenericCloudHandler,
+    deployment_id: &str,
+    environment: &str,
+    plan_job_id: &str,
+) -> Result<DeploymentResp, anyhow::Error> {
+    let plan = if http_client::is_http_mode_enabled() {
+        let value = http_client::http_get_plan_deployment(
+            handler.get_project_id(),
+            handler.get_region(),
+            environment,
+            deployment_id,
+            plan_job_id,
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to get plan: {}", e))?;
+        match value.is_null() {
+            true => None,
+            false => Some(
+                serde_json::from_value::<DeploymentResp>(value)
+                    .map_err(|e| anyhow::anyhow!("Failed to parse plan: {}", e))?,
+            ),
+        }
+    } else {
+        handler
+            .get_plan_deployment(deployment_id, environment, plan_job_id)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get plan: {}", e))?
+    };
+    plan.ok_or_else(|| anyhow::anyhow!("Plan job {} was not found", plan_job_id))
+}
+
 pub async fn submit_claim_job(
     handler: &GenericCloudHandler,
     payload_with_variables: &ApiInfraPayloadWithVariables,
 ) -> Result<String, anyhow::Error> {
+    let payload_with_variables = &with_idempotency_key(payload_with_variables);
+
     // In HTTP mode, the server handles in-progress checks and event insertion,
     // so delegate directly to the HTTP API.
     if http_client::is_http_mode_enabled() {
@@ -560,7 +1017,7 @@ pub async fn submit_claim_job(
     }
 
     let payload = &payload_with_variables.payload;
-    let (in_progress, job_id, _, _) = is_deployment_in_progress(
+    let (in_progress, job_id, _, deployment) = is_deployment_in_progress(
         handler,
         &payload.deployment_id,
         &payload.environment,
@@ -569,9 +1026,27 @@ pub async fn submit_claim_job(
     )
     .await;
     if in_progress {
+        if is_identical_submission(deployment.as_ref(), payload) {
+            info!(
+                "An identical submission is already in progress as job {}",
+                job_id
+            );
+            return Ok(job_id);
+        }
         return Err(CloudHandlerError::JobAlreadyInProgress(job_id).into());
     }
 
+    let required_approvals =
+        get_required_approvals(handler, &payload.environment, &payload.command).await?;
+    if required_approvals > 0 {
+        return submit_pending_approval(handler, payload_with_variables, required_approvals).await;
+    }
+
+    let admission = admit_job(handler, payload_with_variables).await?;
+    if let JobAdmission::Queued(job_id) = admission {
+        return Ok(job_id);
+    }
+
     let job_id: String = match mutate_infra(handler, payload.clone()).await {
         Ok(resp) => {
             info!("Request successfully submitted");
@@ -586,23 +1061,175 @@ pub async fn submit_claim_job(
     };
 
     insert_request_event(handler, payload_with_variables, &job_id).await?;
+    if let JobAdmission::Start(slot) = admission {
+        hold_job_slot(handler, payload_with_variables, slot, &job_id).await?;
+    }
 
     Ok(job_id)
 }
 
+/// Returns the submission with its idempotency key set, derived from the claim content unless
+/// the caller provided one
+pub fn with_idempotency_key(
+    payload_with_variables: &ApiInfraPayloadWithVariables,
+) -> ApiInfraPayloadWithVariables {
+    let mut payload_with_variables = payload_with_variables.clone();
+    if payload_with_variables.payload.idempotency_key.is_none() {
+        payload_with_variables.payload.idempotency_key =
+            Some(claim_idempotency_key(&payload_with_variables));
+    }
+    payload_with_variables
+}
+
+/// Whether the job in progress for `deployment` was started by a submission with the same idempotency key
+pub fn is_identical_submission(
+    deployment: Option<&DeploymentResp>,
+    payload: &ApiInfraPayload,
+) -> bool {
+    match (deployment, &payload.idempotency_key) {
+        (Some(deployment), Some(idempotency_key)) => {
+            deployment.idempotency_key.as_ref() == Some(idempotency_key)
+        }
+        _ => false,
+    }
+}
+
+/// Hash of everything that decides what a job does, so retried submissions of an identical claim
+/// get the same key while any change to the claim, command or environment gets a new one
+fn claim_idempotency_key(payload_with_variables: &ApiInfraPayloadWithVariables) -> String {
+    let payload = &payload_with_variables.payload;
+    let content = serde_json::json!({
+        "command": payload.command,
+        "flags": payload.flags,
+        "module": payload.module,
+        "module_version": payload.module_version,
+        "module_track": payload.module_track,
+        "environment": payload.environment,
+        "deployment_id": payload.deployment_id,
+        "region": payload.region,
+        "variables": payload_with_variables.variables,
+        "dependencies": payload.dependencies,
+        "drift_detection": payload.drift_detection,
+        "targets": payload.targets,
+        "plan_job_id": payload.plan_job_id,
+    });
+    format!("{:x}", Sha256::digest(content.to_string()))
+}
+
+/// Returns how many approvals the project settings require before `command` may run in `environment`.
+/// Plans never change infrastructure and are therefore always auto-approved.
+pub async fn get_required_approvals(
+    handler: &GenericCloudHandler,
+    environment: &str,
+    command: &str,
+) -> Result<u32, anyhow::Error> {
+    if command != "apply" && command != "destroy" {
+        return Ok(0);
+    }
+    let project = handler
+        .get_current_project()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read approval policies: {}", e))?;
+    Ok(project.settings.required_approvals(environment))
+}
+
+/// Returns the OCI artifact verification policy of the handler's project, the default
+/// policy if the project has no settings
+pub async fn get_artifact_verification_policy(
+    handler: &GenericCloudHandler,
+) -> Result<ArtifactVerificationPolicy, anyhow::Error> {
+    let project_id = handler.get_project_id();
+    let projects = handler
+        .get_all_projects()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read artifact verification policy: {}", e))?;
+    let policy = projects
+        .into_iter()
+        .find(|project| project.project_id == project_id)
+        .map(|project| project.settings.artifact_verification)
+        .unwrap_or_default();
+    Ok(policy)
+}
+
+/// Records the job as pending approval instead of starting it, returning the job id it is tracked under
+pub async fn submit_pending_approval(
+    handler: &GenericCloudHandler,
+    payload_with_variables: &ApiInfraPayloadWithVariables,
+    required_approvals: u32,
+) -> Result<String, anyhow::Error> {
+    let payload = &payload_with_variables.payload;
+    let job_id = format!("pending-approval-{}", uuid::Uuid::new_v4());
+    info!(
+        "{} of {} in {} requires {} approval(s), job {} is pending approval",
+        payload.command, payload.deployment_id, payload.environment, required_approvals, job_id
+    );
+    let mut metadata = serde_json::Map::new();
+    metadata.insert("required_approvals".to_string(), required_approvals.into());
+    // Kept with the pending event so the job can be started as submitted once approved
+    metadata.insert(
+        "submission".to_string(),
+        serde_json::to_value(payload_with_variables)?,
+    );
+    insert_job_event(
+        handler,
+        payload_with_variables,
+        &job_id,
+        DeploymentStatus::PendingApproval,
+        metadata,
+        "",
+    )
+    .await?;
+    // Approvers are not notified of held jobs, so the drift webhooks of the deployment are told
+    // that the remediation of its drift waits for them
+    if payload.trigger_reason.as_deref() == Some(DRIFT_REMEDIATION_REASON) {
+        notify_webhooks(
+            handler,
+            payload,
+            WebhookEvent::DriftDetected,
+            &job_id,
+            &DeploymentStatus::PendingApproval,
+            &format!(
+                "Drift has occurred for {} in {}, its remediation is pending {} approval(s)",
+                payload.deployment_id, payload.environment, required_approvals
+            ),
+        )
+        .await;
+    }
+    Ok(job_id)
+}
+
 pub async fn insert_request_event(
     handler: &GenericCloudHandler,
     payload_with_variables: &ApiInfraPayloadWithVariables,
     job_id: &str,
+) -> Result<(), anyhow::Error> {
+    insert_job_event(
+        handler,
+        payload_with_variables,
+        job_id,
+        DeploymentStatus::Requested,
+        serde_json::Map::new(),
+        "",
+    )
+    .await
+}
+
+pub(crate) async fn insert_job_event(
+    handler: &GenericCloudHandler,
+    payload_with_variables: &ApiInfraPayloadWithVariables,
+    job_id: &str,
+    status: DeploymentStatus,
+    mut metadata: serde_json::Map<String, serde_json::Value>,
+    error_text: &str,
 ) -> Result<(), anyhow::Error> {
     let payload = &payload_with_variables.payload;
-    let status_handler = DeploymentStatusHandler::new(
+    let mut status_handler = DeploymentStatusHandler::new(
         &payload.command,
         &payload.module,
         &payload.module_version,
         &payload.module_type,
         &payload.module_track,
-        DeploymentStatus::Requested,
+        status,
         &payload.environment,
         &payload.deployment_id,
         &payload.project_id,
@@ -621,6 +1248,16 @@ pub async fn insert_request_event(
         payload.memory.clone(),
         payload.reference.clone(),
     );
+    if !error_text.is_empty() {
+        status_handler.set_error_text(error_text.to_string());
+    }
+    if let Some(trigger_reason) = &payload.trigger_reason {
+        metadata.insert("trigger_reason".to_string(), trigger_reason.clone().into());
+    }
+    if !metadata.is_empty() {
+        status_handler.set_metadata(serde_json::Value::Object(metadata));
+    }
+    status_handler.set_idempotency_key(payload.idempotency_key.clone());
     status_handler.send_event(handler).await;
     status_handler.send_deployment(handler).await?;
     Ok(())
@@ -653,8 +1290,9 @@ pub async fn is_deployment_in_progress(
         }
     };
 
-    if deployment.status.is_busy() {
-        if job_check {
+    // A queued job has no runner to check yet, it is started once its project has a free slot
+    if deployment.status.is_busy() || deployment.status == DeploymentStatus::Queued {
+        if job_check && deployment.status.is_busy() {
             warn!(
                 "Deployment is currently in process according to deployment: {}",
                 deployment.status
@@ -735,7 +1373,7 @@ pub async fn is_deployment_plan_in_progress(
         }
     };
 
-    let in_progress = deployment.status.is_busy();
+    let in_progress = deployment.status.is_busy() || deployment.status == DeploymentStatus::Queued;
     let job_id = deployment.job_id.clone();
 
     (in_progress, job_id, Some(deployment.clone()))
@@ -802,11 +1440,379 @@ pub async fn check_module_deprecation(
     }
 }
 
+/// Checks the version blocklist: a yanked module/stack version can only be used by the
+/// deployments already running it, while new claims and upgrades to it are refused with the
+/// recommended replacement version
+pub async fn check_module_yank(
+    handler: &GenericCloudHandler,
+    module_resp: &env_defs::ModuleResp,
+    is_stack: bool,
+    deployment_id: &str,
+    environment: &str,
+) -> Result<(), anyhow::Error> {
+    if module_resp.yanked.is_none() {
+        return Ok(());
+    }
+
+    let existing_deployment = if http_client::is_http_mode_enabled() {
+        let value = http_client::http_describe_deployment(
+            handler.get_project_id(),
+            handler.get_region(),
+            environment,
+            deployment_id,
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to describe deployment: {}", e))?;
+        if value.is_null() {
+            None
+        } else {
+            Some(
+                serde_json::from_value::<DeploymentResp>(value)
+                    .map_err(|e| anyhow::anyhow!("Failed to parse deployment: {}", e))?,
+            )
+        }
+    } else {
+        handler
+            .get_deployment(deployment_id, environment, false)
+            .await?
+    };
+    let deployed_version = existing_deployment
+        .as_ref()
+        .map(|d| d.module_version.as_str());
+
+    match yanked_version_error(module_resp, is_stack, deployed_version) {
+        Some(error_msg) => Err(anyhow::anyhow!(error_msg)),
+        None => {
+            if deployed_version.is_some() {
+                warn!(
+                    "{} {} version {} is yanked but allowing deployment {} which already runs it",
+                    if is_stack { "Stack" } else { "Module" },
+                    module_resp.module,
+                    module_resp.version,
+                    deployment_id
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Error for a claim using the version of `module_resp` when it is yanked, None when the version
+/// isn't yanked or is the one the deployment already runs
+fn yanked_version_error(
+    module_resp: &env_defs::ModuleResp,
+    is_stack: bool,
+    deployed_version: Option<&str>,
+) -> Option<String> {
+    let yank = module_resp.yanked.as_ref()?;
+    if deployed_version == Some(module_resp.version.as_str()) {
+        return None;
+    }
+
+    let mut error_msg = format!(
+        "{} {} version {} has been yanked and cannot be used for new claims or upgrades.",
+        if is_stack { "Stack" } else { "Module" },
+        module_resp.module,
+        module_resp.version
+    );
+    if let Some(reason) = &yank.reason {
+        error_msg.push_str(&format!("\nReason: {}", reason));
+    }
+    match &yank.replacement_version {
+        Some(replacement) => error_msg.push_str(&format!("\nUse version {} instead.", replacement)),
+        None => error_msg.push_str("\nPlease use a different version."),
+    }
+    Some(error_msg)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
 
+    fn dependent(id: &str) -> Dependent {
+        Dependent {
+            project_id: "123456789012".to_string(),
+            region: "eu-west-1".to_string(),
+            dependent_id: id.to_string(),
+            environment: "cli/default".to_string(),
+            on_output_change: DependencyTrigger::default(),
+        }
+    }
+
+    #[test]
+    fn test_value_from_dependencies() {
+        let variables = serde_json::json!({
+            "vpcId": { "valueFrom": { "deployment": "network/main", "output": "vpcId" } },
+            "subnetIds": { "valueFrom": { "deployment": "network/main", "output": "subnetIds" } },
+            "zoneId": {
+                "valueFrom": { "deployment": "dns/shared", "output": "zoneId", "environment": "cli/shared" }
+            },
+            "bucketName": "my-bucket",
+        });
+        let dependencies =
+            value_from_dependencies(&variables, "123", "us-west-2", "cli/dev", "app/main").unwrap();
+        let edges: Vec<(&str, &str)> = dependencies
+            .iter()
+            .map(|d| (d.deployment_id.as_str(), d.environment.as_str()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![("network/main", "cli/dev"), ("dns/shared", "cli/shared")]
+        );
+        assert_eq!(
+            dependencies[0].on_output_change,
+            DependencyTrigger::PlanOnly
+        );
+
+        let own_output = serde_json::json!({
+            "vpcId": { "valueFrom": { "deployment": "app/main", "output": "vpcId" } },
+        });
+        assert!(
+            value_from_dependencies(&own_output, "123", "us-west-2", "cli/dev", "app/main")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_dependents_to_trigger() {
+        let mut plan = dependent("app/plan");
+        plan.on_output_change = DependencyTrigger::PlanOnly;
+        let mut apply = dependent("app/apply");
+        apply.on_output_change = DependencyTrigger::AutoApply;
+        let mut ignore = dependent("app/ignore");
+        ignore.on_output_change = DependencyTrigger::Ignore;
+        let dependents = vec![plan, apply, ignore];
+        let previous_output = serde_json::json!({ "vpcId": { "value": "vpc-1" } });
+        let output = serde_json::json!({ "vpcId": { "value": "vpc-2" } });
+
+        let triggered: Vec<(&str, Option<&str>)> =
+            dependents_to_trigger(&previous_output, &output, &dependents)
+                .into_iter()
+                .map(|(dependent, command)| (dependent.dependent_id.as_str(), command))
+                .collect();
+        assert_eq!(
+            triggered,
+            vec![
+                ("app/plan", Some("plan")),
+                ("app/apply", Some("apply")),
+                ("app/ignore", None),
+            ]
+        );
+
+        assert!(dependents_to_trigger(&output, &output, &dependents).is_empty());
+        assert!(dependents_to_trigger(&previous_output, &output, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_yanked_version_error() {
+        let mut module = env_defs::ModuleResp {
+            module: "s3bucket".to_string(),
+            version: "1.2.0".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(yanked_version_error(&module, false, None), None);
+
+        module.yanked = Some(env_defs::ModuleYank {
+            reason: Some("Deletes the bucket policy".to_string()),
+            replacement_version: Some("1.2.1".to_string()),
+            yanked_by: "alice".to_string(),
+            epoch: 0,
+        });
+        assert_eq!(
+            yanked_version_error(&module, false, None).unwrap(),
+            "Module s3bucket version 1.2.0 has been yanked and cannot be used for new claims or upgrades.\n\
+            Reason: Deletes the bucket policy\n\
+            Use version 1.2.1 instead."
+        );
+        // Deployments already running the yanked version keep using it, but can't upgrade to it
+        assert_eq!(yanked_version_error(&module, false, Some("1.2.0")), None);
+        assert!(yanked_version_error(&module, false, Some("1.1.0")).is_some());
+    }
+
+    #[test]
+    fn test_order_cascade_destroy_dependents_first() {
+        // vpc <- subnet <- instance, and instance also depends on vpc directly
+        let (vpc, subnet, instance) = (
+            dependent("vpc/main"),
+            dependent("subnet/main"),
+            dependent("instance/main"),
+        );
+        let dependents = HashMap::from([
+            (cascade_key(&vpc), vec![subnet.clone(), instance.clone()]),
+            (cascade_key(&subnet), vec![instance.clone()]),
+            (cascade_key(&instance), vec![]),
+        ]);
+
+        let order: Vec<String> = order_cascade_destroy(&vpc, &dependents)
+            .unwrap()
+            .into_iter()
+            .map(|d| d.dependent_id)
+            .collect();
+        assert_eq!(order, vec!["instance/main", "subnet/main", "vpc/main"]);
+    }
+
+    #[test]
+    fn test_order_cascade_destroy_cycle() {
+        let (a, b) = (dependent("a/main"), dependent("b/main"));
+        let dependents = HashMap::from([
+            (cascade_key(&a), vec![b.clone()]),
+            (cascade_key(&b), vec![a.clone()]),
+        ]);
+        assert!(order_cascade_destroy(&a, &dependents).is_err());
+    }
+
+    fn submission(variables: serde_json::Value) -> ApiInfraPayloadWithVariables {
+        ApiInfraPayloadWithVariables {
+            payload: ApiInfraPayload {
+                command: "apply".to_string(),
+                flags: vec![],
+                module: "s3bucket".to_string(),
+                module_version: "0.1.0".to_string(),
+                module_type: "module".to_string(),
+                module_track: "stable".to_string(),
+                name: "bucket".to_string(),
+                environment: "github-org-repo/prod".to_string(),
+                deployment_id: "s3bucket/bucket".to_string(),
+                project_id: "123456789012".to_string(),
+                region: "eu-west-1".to_string(),
+                drift_detection: serde_json::from_value(serde_json::json!({})).unwrap(),
+                next_drift_check_epoch: -1,
+                annotations: serde_json::json!({}),
+                dependencies: vec![],
+                initiated_by: "user@example.com".to_string(),
+                cpu: "1024".to_string(),
+                memory: "2048".to_string(),
+                reference: "".to_string(),
+                extra_data: ExtraData::None,
+                trigger_reason: None,
+                plan_job_id: None,
+                targets: vec![],
+                idempotency_key: None,
+                approved_by: vec![],
+            },
+            variables,
+        }
+    }
+
+    #[test]
+    fn test_with_idempotency_key() {
+        let key = |submission: &ApiInfraPayloadWithVariables| {
+            with_idempotency_key(submission)
+                .payload
+                .idempotency_key
+                .unwrap()
+        };
+        let submission = submission(serde_json::json!({"bucket_name": "logs"}));
+        assert_eq!(key(&submission), key(&submission.clone()));
+
+        let mut retried = submission.clone();
+        retried.payload.initiated_by = "gitops".to_string();
+        assert_eq!(key(&retried), key(&submission));
+
+        let mut changed = submission.clone();
+        changed.variables = serde_json::json!({"bucket_name": "audit"});
+        assert_ne!(key(&changed), key(&submission));
+
+        let mut destroy = submission.clone();
+        destroy.payload.command = "destroy".to_string();
+        assert_ne!(key(&destroy), key(&submission));
+
+        let mut provided = submission.clone();
+        provided.payload.idempotency_key = Some("delivery-1".to_string());
+        assert_eq!(key(&provided), "delivery-1");
+    }
+
+    #[test]
+    fn test_is_identical_submission() {
+        let payload = with_idempotency_key(&submission(serde_json::json!({}))).payload;
+        let mut deployment: DeploymentResp = serde_json::from_value(serde_json::json!({
+            "epoch": 1,
+            "deployment_id": "s3bucket/bucket",
+            "status": "initiated",
+            "job_id": "job-1",
+            "environment": "github-org-repo/prod",
+            "project_id": "123456789012",
+            "region": "eu-west-1",
+            "module": "s3bucket",
+            "module_version": "0.1.0",
+            "module_type": "module",
+            "module_track": "stable",
+            "drift_detection": {},
+            "next_drift_check_epoch": -1,
+            "has_drifted": false,
+            "variables": {},
+            "output": {},
+            "policy_results": [],
+            "error_text": "",
+            "deleted": false,
+            "dependencies": [],
+            "initiated_by": "",
+            "cpu": "",
+            "memory": "",
+            "reference": "",
+            "tf_resources": null,
+        }))
+        .unwrap();
+        assert!(!is_identical_submission(Some(&deployment), &payload));
+        assert!(!is_identical_submission(None, &payload));
+
+        deployment.idempotency_key = payload.idempotency_key.clone();
+        assert!(is_identical_submission(Some(&deployment), &payload));
+    }
+
+    #[test]
+    fn test_break_glass_claim() {
+        let module: env_defs::ModuleResp = serde_json::from_value(serde_json::json!({
+            "track": "stable",
+            "track_version": "stable#000.001.000",
+            "version": "0.1.0",
+            "timestamp": "2024-01-01T00:00:00Z",
+            "module": "s3bucket",
+            "module_name": "S3Bucket",
+            "module_type": "module",
+            "description": "",
+            "reference": "",
+            "manifest": {
+                "apiVersion": "infraweave.io/v1",
+                "kind": "Module",
+                "metadata": {"name": "s3bucket"},
+                "spec": {"moduleName": "S3Bucket", "version": "0.1.0", "description": "", "reference": ""},
+            },
+            "tf_outputs": [],
+            "tf_variables": [],
+            "tf_required_providers": [],
+            "tf_lock_providers": [],
+            "tf_extra_environment_variables": [],
+            "s3_key": "",
+            "stack_data": null,
+            "version_diff": null,
+            "cpu": "1024",
+            "memory": "2048",
+        }))
+        .unwrap();
+        let variables = serde_json::json!({"bucket_name": "my-bucket", "tags": {"team_name": "a"}});
+
+        let claim =
+            break_glass_claim(&module, "emergency", "eu-west-1", &variables, "laptop").unwrap();
+
+        let manifest: DeploymentManifest = serde_yaml::from_value(claim.clone()).unwrap();
+        assert_eq!(manifest.kind, "S3Bucket");
+        assert_eq!(manifest.metadata.name, "emergency");
+        assert_eq!(manifest.spec.module_version.as_deref(), Some("0.1.0"));
+        assert_eq!(
+            manifest.spec.reference.as_deref(),
+            Some("break-glass:laptop")
+        );
+        assert_eq!(claim["spec"]["variables"]["bucketName"], "my-bucket");
+        // Only the first level is converted, map keys are passed as given
+        assert_eq!(claim["spec"]["variables"]["tags"]["team_name"], "a");
+
+        assert!(
+            break_glass_claim(&module, "x", "eu-west-1", &serde_json::json!([]), "laptop").is_err()
+        );
+    }
+
     #[test]
     fn test_claim_correct_casing() {
         let yaml_manifest = r#"
@@ -0,0 +1,691 @@
+use std::collections::HashSet;
+
+use env_defs::{
+    get_deployment_identifier, sort_job_queue, ApiInfraPayloadWithVariables, CloudProvider,
+    DeploymentStatus, EventData, JobPriority, JobQueueEntry, JobQueueState,
+};
+use env_utils::{get_epoch, merge_json_dicts};
+use log::{info, warn};
+use serde_json::json;
+
+use crate::interface::GenericCloudHandler;
+use crate::logic::api_infra::{insert_job_event, mutate_infra};
+
+const QUEUED_JOB_PREFIX: &str = "queued-";
+const RESERVED_JOB_PREFIX: &str = "reserved-";
+/// How long a slot stays reserved for a job that was admitted but never started, e.g. because
+/// starting it failed
+const SLOT_RESERVATION_TIMEOUT_MS: u128 = 10 * 60 * 1000;
+
+/// Whether a submitted job may start, given the concurrent job limit of its project
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobAdmission {
+    /// The project doesn't limit its concurrent jobs
+    Unlimited,
+    /// The project had a free slot, which is reserved for the job until it holds it once started
+    Start(u32),
+    /// The project runs as many jobs as it allows, the job waits in the queue under this job id
+    Queued(String),
+}
+
+/// Where a queued job is, as followed by `plan --follow`
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueuedJobState {
+    /// Waiting in the queue at `position` (starting at 1) of `length` queued jobs
+    Waiting { position: usize, length: usize },
+    /// Left the queue and was started as this job
+    Started(String),
+    /// Neither queued nor started, e.g. because its queue entry was removed
+    Gone,
+}
+
+/// Whether the job id is the one a job is tracked under while it waits in the queue
+pub fn is_queued_job_id(job_id: &str) -> bool {
+    job_id.starts_with(QUEUED_JOB_PREFIX)
+}
+
+/// Jobs the project of the handler may run at the same time in its region, None when unlimited
+pub async fn get_max_concurrent_jobs(
+    handler: &GenericCloudHandler,
+) -> Result<Option<u32>, anyhow::Error> {
+    let project_id = handler.get_project_id();
+    let projects = handler
+        .get_all_projects()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read concurrent job limit: {}", e))?;
+    let max_concurrent_jobs = projects
+        .iter()
+        .find(|project| project.project_id == project_id)
+        .and_then(|project| project.settings.max_concurrent_jobs);
+    Ok(max_concurrent_jobs)
+}
+
+/// Queues the submission instead of starting it when its project already runs as many jobs as
+/// it allows. The queued job is started by the runner of a job of the project that finishes
+pub async fn admit_job(
+    handler: &GenericCloudHandler,
+    submission: &ApiInfraPayloadWithVariables,
+) -> Result<JobAdmission, anyhow::Error> {
+    let Some(max_concurrent_jobs) = get_max_concurrent_jobs(handler).await? else {
+        return Ok(JobAdmission::Unlimited);
+    };
+    let queue = handler.get_job_queue().await?;
+    if count_running_jobs(handler, &queue, max_concurrent_jobs).await < max_concurrent_jobs as usize
+    {
+        // Jobs admitted at the same time read the same queue, only those that reserve a slot start
+        if let Some(slot) =
+            reserve_job_slot(handler, submission, &queue, max_concurrent_jobs).await?
+        {
+            return Ok(JobAdmission::Start(slot));
+        }
+    }
+
+    let payload = &submission.payload;
+    let job_id = format!("{}{}", QUEUED_JOB_PREFIX, uuid::Uuid::new_v4());
+    let priority = JobPriority::of(submission);
+    info!(
+        "Project runs its maximum of {} job(s), {} of {} in {} is queued as {} job {}",
+        max_concurrent_jobs,
+        payload.command,
+        payload.deployment_id,
+        payload.environment,
+        priority,
+        job_id
+    );
+    let mut metadata = serde_json::Map::new();
+    metadata.insert("priority".to_string(), json!(priority));
+    insert_job_event(
+        handler,
+        submission,
+        &job_id,
+        DeploymentStatus::Queued,
+        metadata,
+        "",
+    )
+    .await?;
+    put_queue_entry(
+        handler,
+        queue_entry(submission, &job_id, JobQueueState::Queued),
+    )
+    .await?;
+
+    // A job that finished while the queue was read has not seen this one, so look for a free slot again
+    start_queued_jobs(handler).await?;
+    Ok(JobAdmission::Queued(job_id))
+}
+
+/// Records that the started job holds the slot reserved for it until its runner releases it
+pub async fn hold_job_slot(
+    handler: &GenericCloudHandler,
+    submission: &ApiInfraPayloadWithVariables,
+    slot: u32,
+    job_id: &str,
+) -> Result<(), anyhow::Error> {
+    put_queue_entry(handler, running_entry(submission, job_id, slot)).await
+}
+
+/// Releases the slot held by the finished job and starts the queued jobs that fit in the free
+/// slots, returning the job ids they were started as
+pub async fn release_job_slot(
+    handler: &GenericCloudHandler,
+    job_id: &str,
+) -> Result<Vec<String>, anyhow::Error> {
+    let queue = handler.get_job_queue().await?;
+    if queue.is_empty() {
+        return Ok(vec![]);
+    }
+    if let Some(entry) = queue
+        .iter()
+        .find(|entry| entry.state == JobQueueState::Running && entry.job_id == job_id)
+    {
+        delete_queue_entry(handler, &queue_entry_sk(entry)).await?;
+    }
+    start_queued_jobs(handler).await
+}
+
+/// Starts queued jobs while the project has free slots, highest priority first and otherwise in
+/// the order they were submitted. Returns the job ids they were started as
+pub async fn start_queued_jobs(
+    handler: &GenericCloudHandler,
+) -> Result<Vec<String>, anyhow::Error> {
+    let queue = handler.get_job_queue().await?;
+    let mut queued: Vec<JobQueueEntry> = queue
+        .iter()
+        .filter(|entry| entry.state == JobQueueState::Queued)
+        .cloned()
+        .collect();
+    if queued.is_empty() {
+        return Ok(vec![]);
+    }
+    sort_job_queue(&mut queued);
+
+    // Without a limit, e.g. after it was removed from the project settings, all queued jobs start
+    let max_concurrent_jobs = get_max_concurrent_jobs(handler).await?;
+    let mut running = match max_concurrent_jobs {
+        Some(max_concurrent_jobs) => count_running_jobs(handler, &queue, max_concurrent_jobs).await,
+        None => 0,
+    };
+
+    let mut started = vec![];
+    for entry in queued {
+        if max_concurrent_jobs.is_some_and(|max| running >= max as usize) {
+            break;
+        }
+        let Some(submission) = &entry.submission else {
+            warn!(
+                "Queued job {} has no submission to start, removing it",
+                entry.job_id
+            );
+            delete_queue_entry(handler, &queue_entry_sk(&entry)).await?;
+            continue;
+        };
+        // Jobs admitted or started at the same time may have taken the free slots
+        let slot = match max_concurrent_jobs {
+            Some(max_concurrent_jobs) => {
+                match reserve_job_slot(handler, submission, &queue, max_concurrent_jobs).await? {
+                    Some(slot) => Some(slot),
+                    None => break,
+                }
+            }
+            None => None,
+        };
+        // Runners that finish at the same time read the same queue, only the one that claims the
+        // entry starts the job
+        if !claim_queue_entry(handler, &entry.job_id).await {
+            info!("Queued job {} was claimed by another runner", entry.job_id);
+            if let Some(slot) = slot {
+                delete_queue_entry(handler, &slot_sk(slot)).await?;
+            }
+            continue;
+        }
+        let job_id = match start_claimed_job(handler, submission).await {
+            Ok(job_id) => job_id,
+            Err(e) => {
+                put_queue_entry(handler, entry.clone()).await?;
+                if let Some(slot) = slot {
+                    delete_queue_entry(handler, &slot_sk(slot)).await?;
+                }
+                return Err(e);
+            }
+        };
+
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("queued_job_id".to_string(), json!(entry.job_id));
+        insert_job_event(
+            handler,
+            submission,
+            &job_id,
+            DeploymentStatus::Requested,
+            metadata,
+            "",
+        )
+        .await?;
+        if let Some(slot) = slot {
+            hold_job_slot(handler, submission, slot, &job_id).await?;
+        }
+        info!("Queued job {} was started as job {}", entry.job_id, job_id);
+        running += 1;
+        started.push(job_id);
+    }
+    Ok(started)
+}
+
+async fn start_claimed_job(
+    handler: &GenericCloudHandler,
+    submission: &ApiInfraPayloadWithVariables,
+) -> Result<String, anyhow::Error> {
+    let resp = mutate_infra(handler, submission.payload.clone()).await?;
+    let job_id = resp.payload["job_id"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("No job_id in response"))?
+        .to_string();
+    Ok(job_id)
+}
+
+/// Running and queued jobs of the project in the region of the handler, queued jobs in the order
+/// they will start. Submissions are left out, they contain the variables of the claims
+pub async fn get_job_queue(
+    handler: &GenericCloudHandler,
+) -> Result<Vec<JobQueueEntry>, anyhow::Error> {
+    let mut entries = handler.get_job_queue().await?;
+    for entry in entries.iter_mut() {
+        entry.submission = None;
+    }
+    Ok(ordered_job_queue(entries))
+}
+
+/// Where the queued job is, from the job queue of its project and the events of its deployment
+pub fn queued_job_state(
+    queue: &[JobQueueEntry],
+    events: &[EventData],
+    job_id: &str,
+) -> QueuedJobState {
+    if let Some(started) = events.iter().find(|event| {
+        event
+            .metadata
+            .get("queued_job_id")
+            .and_then(|id| id.as_str())
+            == Some(job_id)
+    }) {
+        return QueuedJobState::Started(started.job_id.clone());
+    }
+    let mut queued: Vec<&JobQueueEntry> = queue
+        .iter()
+        .filter(|entry| entry.state == JobQueueState::Queued)
+        .collect();
+    queued.sort_by_key(|entry| (entry.priority, entry.epoch));
+    match queued.iter().position(|entry| entry.job_id == job_id) {
+        Some(index) => QueuedJobState::Waiting {
+            position: index + 1,
+            length: queued.len(),
+        },
+        None => QueuedJobState::Gone,
+    }
+}
+
+fn ordered_job_queue(entries: Vec<JobQueueEntry>) -> Vec<JobQueueEntry> {
+    let (mut running, mut queued): (Vec<JobQueueEntry>, Vec<JobQueueEntry>) = entries
+        .into_iter()
+        .partition(|entry| entry.state == JobQueueState::Running);
+    running.sort_by_key(|entry| entry.epoch);
+    sort_job_queue(&mut queued);
+    running.extend(queued);
+    running
+}
+
+/// Slots held by running jobs. When the project seems to be at its limit, the slots of jobs that
+/// ended without releasing them, e.g. because their runner was stopped, are freed
+async fn count_running_jobs(
+    handler: &GenericCloudHandler,
+    queue: &[JobQueueEntry],
+    max_concurrent_jobs: u32,
+) -> usize {
+    let running: Vec<&JobQueueEntry> = queue
+        .iter()
+        .filter(|entry| entry.state == JobQueueState::Running)
+        .collect();
+    if running.len() < max_concurrent_jobs as usize {
+        return running.len();
+    }
+
+    let mut count = 0;
+    for entry in running {
+        if entry.job_id.starts_with(RESERVED_JOB_PREFIX) {
+            if get_epoch().saturating_sub(entry.epoch) < SLOT_RESERVATION_TIMEOUT_MS {
+                count += 1;
+                continue;
+            }
+            warn!(
+                "Slot {} was reserved for a job that was never started, releasing it",
+                queue_entry_sk(entry)
+            );
+            if let Err(e) = delete_queue_entry(handler, &queue_entry_sk(entry)).await {
+                warn!("Failed to release {}: {}", queue_entry_sk(entry), e);
+            }
+            continue;
+        }
+        match handler.get_job_status(&entry.job_id).await {
+            Ok(Some(status)) if !status.is_running => {
+                warn!(
+                    "Job {} ended without releasing its slot, releasing it",
+                    entry.job_id
+                );
+                if let Err(e) = delete_queue_entry(handler, &queue_entry_sk(entry)).await {
+                    warn!("Failed to release slot of job {}: {}", entry.job_id, e);
+                }
+            }
+            _ => count += 1,
+        }
+    }
+    count
+}
+
+fn queue_entry(
+    submission: &ApiInfraPayloadWithVariables,
+    job_id: &str,
+    state: JobQueueState,
+) -> JobQueueEntry {
+    let payload = &submission.payload;
+    JobQueueEntry {
+        job_id: job_id.to_string(),
+        state,
+        priority: JobPriority::of(submission),
+        deployment_id: payload.deployment_id.clone(),
+        environment: payload.environment.clone(),
+        command: payload.command.clone(),
+        initiated_by: payload.initiated_by.clone(),
+        epoch: get_epoch(),
+        slot: None,
+        submission: Some(submission.clone()),
+    }
+}
+
+fn running_entry(
+    submission: &ApiInfraPayloadWithVariables,
+    job_id: &str,
+    slot: u32,
+) -> JobQueueEntry {
+    let mut entry = queue_entry(submission, job_id, JobQueueState::Running);
+    entry.slot = Some(slot);
+    entry.submission = None;
+    entry
+}
+
+/// Reserves a slot that no running job holds, None when all slots of the project are taken.
+/// The reservation is a conditional write, so that jobs admitted at the same time can't reserve
+/// the same slot
+async fn reserve_job_slot(
+    handler: &GenericCloudHandler,
+    submission: &ApiInfraPayloadWithVariables,
+    queue: &[JobQueueEntry],
+    max_concurrent_jobs: u32,
+) -> Result<Option<u32>, anyhow::Error> {
+    let held: HashSet<u32> = queue
+        .iter()
+        .filter(|entry| entry.state == JobQueueState::Running)
+        .filter_map(|entry| entry.slot)
+        .collect();
+    let job_id = format!("{}{}", RESERVED_JOB_PREFIX, uuid::Uuid::new_v4());
+    for slot in (0..max_concurrent_jobs).filter(|slot| !held.contains(slot)) {
+        let items = json!([{
+            "Put": {
+                "TableName": "deployments",
+                "Item": queue_item(handler, &running_entry(submission, &job_id, slot))?,
+                "ConditionExpression": "attribute_not_exists(PK)",
+            }
+        }]);
+        match handler
+            .run_function(&env_defs::transact_write_event(&items))
+            .await
+        {
+            Ok(_) => return Ok(Some(slot)),
+            Err(e) => info!("Could not reserve slot {}: {}", slot, e),
+        }
+    }
+    Ok(None)
+}
+
+fn queue_pk(handler: &GenericCloudHandler) -> String {
+    format!(
+        "JOBQUEUE#{}",
+        get_deployment_identifier(handler.get_project_id(), handler.get_region(), "", "")
+    )
+}
+
+/// Running jobs are stored under the slot they hold, queued jobs and running jobs recorded before
+/// slots were reserved under their job id
+fn queue_entry_sk(entry: &JobQueueEntry) -> String {
+    match entry.slot {
+        Some(slot) => slot_sk(slot),
+        None => format!("JOB#{}", entry.job_id),
+    }
+}
+
+fn slot_sk(slot: u32) -> String {
+    format!("SLOT#{}", slot)
+}
+
+fn queue_item(
+    handler: &GenericCloudHandler,
+    entry: &JobQueueEntry,
+) -> Result<serde_json::Value, anyhow::Error> {
+    let mut item = json!({
+        "PK": queue_pk(handler),
+        "SK": queue_entry_sk(entry),
+    });
+    merge_json_dicts(&mut item, &serde_json::to_value(entry)?);
+    Ok(item)
+}
+
+async fn put_queue_entry(
+    handler: &GenericCloudHandler,
+    entry: JobQueueEntry,
+) -> Result<(), anyhow::Error> {
+    let item = queue_item(handler, &entry)?;
+    handler
+        .run_function(&env_defs::insert_db_event("deployments", &item))
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to update job queue: {}", e))?;
+    Ok(())
+}
+
+async fn delete_queue_entry(handler: &GenericCloudHandler, sk: &str) -> Result<(), anyhow::Error> {
+    let items = json!([{
+        "Delete": {
+            "TableName": "deployments",
+            "Key": {
+                "PK": queue_pk(handler),
+                "SK": sk,
+            }
+        }
+    }]);
+    handler
+        .run_function(&env_defs::transact_write_event(&items))
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to update job queue: {}", e))?;
+    Ok(())
+}
+
+/// Removes the queue entry of the job if it still exists, false when another runner already did
+async fn claim_queue_entry(handler: &GenericCloudHandler, job_id: &str) -> bool {
+    let items = json!([{
+        "Delete": {
+            "TableName": "deployments",
+            "Key": {
+                "PK": queue_pk(handler),
+                "SK": format!("JOB#{}", job_id),
+            },
+            "ConditionExpression": "attribute_exists(PK)",
+        }
+    }]);
+    match handler
+        .run_function(&env_defs::transact_write_event(&items))
+        .await
+    {
+        Ok(_) => true,
+        Err(e) => {
+            info!("Could not claim queued job {}: {}", job_id, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    use env_defs::GenericFunctionResponse;
+
+    use super::*;
+    use crate::interface::TestCloudProvider;
+
+    fn entry(
+        job_id: &str,
+        state: JobQueueState,
+        priority: JobPriority,
+        epoch: u128,
+    ) -> JobQueueEntry {
+        JobQueueEntry {
+            job_id: job_id.to_string(),
+            state,
+            priority,
+            deployment_id: "s3bucket/bucket".to_string(),
+            environment: "cli/dev".to_string(),
+            command: "plan".to_string(),
+            initiated_by: "alice".to_string(),
+            epoch,
+            slot: None,
+            submission: None,
+        }
+    }
+
+    #[test]
+    fn test_queued_job_state() {
+        let queue = ordered_job_queue(vec![
+            entry(
+                "queued-drift",
+                JobQueueState::Queued,
+                JobPriority::Driftcheck,
+                1,
+            ),
+            entry("job-1", JobQueueState::Running, JobPriority::Gitops, 0),
+            entry(
+                "queued-cli",
+                JobQueueState::Queued,
+                JobPriority::Interactive,
+                3,
+            ),
+            entry(
+                "queued-gitops",
+                JobQueueState::Queued,
+                JobPriority::Gitops,
+                2,
+            ),
+        ]);
+        let order: Vec<&str> = queue.iter().map(|e| e.job_id.as_str()).collect();
+        assert_eq!(
+            order,
+            vec!["job-1", "queued-cli", "queued-gitops", "queued-drift"]
+        );
+
+        assert_eq!(
+            queued_job_state(&queue, &[], "queued-gitops"),
+            QueuedJobState::Waiting {
+                position: 2,
+                length: 3
+            }
+        );
+        assert_eq!(
+            queued_job_state(&queue, &[], "queued-unknown"),
+            QueuedJobState::Gone
+        );
+
+        let started: EventData = serde_json::from_value(json!({
+            "deployment_id": "s3bucket/bucket",
+            "project_id": "123456789012",
+            "region": "eu-west-1",
+            "environment": "cli/dev",
+            "event": "plan",
+            "epoch": 4,
+            "error_text": "",
+            "id": "s3bucket-s3bucket/bucket-4-plan",
+            "job_id": "job-2",
+            "metadata": { "queued_job_id": "queued-unknown" },
+            "drift_detection": {},
+            "next_drift_check_epoch": -1,
+            "has_drifted": false,
+            "module": "s3bucket",
+            "module_version": "0.1.0",
+            "name": "bucket",
+            "status": "requested",
+            "timestamp": "2026-01-01T00:00:00Z",
+            "output": {},
+            "policy_results": [],
+            "initiated_by": "alice",
+            "event_duration": 0,
+        }))
+        .unwrap();
+        assert_eq!(
+            queued_job_state(&queue, &[started], "queued-unknown"),
+            QueuedJobState::Started("job-2".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_second_claim_of_queued_job_fails() {
+        let claims = Arc::new(AtomicU32::new(0));
+        let counter = claims.clone();
+        let mut mock = TestCloudProvider::new();
+        mock.expect_get_project_id()
+            .return_const("123456789012".to_string());
+        mock.expect_get_region()
+            .return_const("eu-west-1".to_string());
+        mock.expect_run_function().returning(move |payload| {
+            assert_eq!(
+                payload["items"][0]["Delete"]["ConditionExpression"],
+                "attribute_exists(PK)"
+            );
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                Ok(GenericFunctionResponse { payload: json!({}) })
+            } else {
+                Err(anyhow::anyhow!(
+                    "ConditionalCheckFailed: the conditional request failed"
+                ))
+            }
+        });
+        let handler = GenericCloudHandler::with_provider(Arc::new(mock), None);
+
+        assert!(claim_queue_entry(&handler, "queued-1").await);
+        assert!(!claim_queue_entry(&handler, "queued-1").await);
+        assert_eq!(claims.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_reserve_job_slot_skips_taken_slots() {
+        let submission: ApiInfraPayloadWithVariables = serde_json::from_value(json!({
+            "payload": {
+                "command": "plan",
+                "flags": [],
+                "module": "s3bucket",
+                "module_version": "0.1.0",
+                "module_type": "module",
+                "module_track": "dev",
+                "name": "bucket",
+                "environment": "cli/dev",
+                "deployment_id": "s3bucket/bucket",
+                "project_id": "123456789012",
+                "region": "eu-west-1",
+                "drift_detection": {},
+                "next_drift_check_epoch": -1,
+                "annotations": {},
+                "dependencies": [],
+                "initiated_by": "alice",
+                "cpu": "1024",
+                "memory": "2048",
+                "reference": "",
+                "extra_data": null,
+            },
+            "variables": {},
+        }))
+        .unwrap();
+        let mut mock = TestCloudProvider::new();
+        mock.expect_get_project_id()
+            .return_const("123456789012".to_string());
+        mock.expect_get_region()
+            .return_const("eu-west-1".to_string());
+        mock.expect_run_function().returning(|payload| {
+            let put = &payload["items"][0]["Put"];
+            assert_eq!(put["ConditionExpression"], "attribute_not_exists(PK)");
+            assert_ne!(
+                put["Item"]["SK"], "SLOT#0",
+                "slot 0 is held by a running job"
+            );
+            // Another job reserved slot 1 since the queue was read
+            if put["Item"]["SK"] == "SLOT#1" {
+                Err(anyhow::anyhow!(
+                    "ConditionalCheckFailed: the conditional request failed"
+                ))
+            } else {
+                Ok(GenericFunctionResponse { payload: json!({}) })
+            }
+        });
+        let handler = GenericCloudHandler::with_provider(Arc::new(mock), None);
+
+        let mut running = entry("job-1", JobQueueState::Running, JobPriority::Gitops, 0);
+        running.slot = Some(0);
+        let queue = vec![running];
+        assert_eq!(
+            reserve_job_slot(&handler, &submission, &queue, 3)
+                .await
+                .unwrap(),
+            Some(2)
+        );
+        assert_eq!(
+            reserve_job_slot(&handler, &submission, &queue, 2)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use env_defs::{auto_runner_size, get_deployment_identifier, CloudProvider, JobStats, ModuleResp};
+use log::{info, warn};
+use serde_json::json;
+
+use crate::interface::GenericCloudHandler;
+
+/// Most recent jobs of a module that are kept, older ones are removed when a job is recorded
+const MAX_JOB_STATS: usize = 50;
+
+/// Records the duration and peak memory of a finished job of the module, keeping the most recent
+/// `MAX_JOB_STATS` jobs of the module
+pub async fn record_job_stats(
+    handler: &GenericCloudHandler,
+    stats: &JobStats,
+) -> Result<(), anyhow::Error> {
+    let pk = stats_pk(handler, &stats.module);
+    let mut item = serde_json::to_value(stats)?;
+    item["PK"] = json!(pk);
+    item["SK"] = json!(stats_sk(stats));
+    let mut transaction_items = vec![json!({
+        "Put": {
+            "TableName": "deployments",
+            "Item": item,
+        }
+    })];
+
+    let mut history = handler.get_job_stats(&stats.module).await?;
+    history.sort_by_key(|s| std::cmp::Reverse(s.epoch));
+    for old in history.iter().skip(MAX_JOB_STATS - 1) {
+        transaction_items.push(json!({
+            "Delete": {
+                "TableName": "deployments",
+                "Key": {
+                    "PK": pk,
+                    "SK": stats_sk(old),
+                }
+            }
+        }));
+    }
+
+    handler
+        .run_function(&env_defs::transact_write_event(&json!(transaction_items)))
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to record job stats: {}", e))?;
+    Ok(())
+}
+
+/// Cpu and memory for a new job of the module. Modules setting them in their manifest keep
+/// those, otherwise they are sized from the history of the module when the project enables
+/// `settings.runner_sizing.auto`, falling back to the defaults of the module
+pub async fn runner_size(handler: &GenericCloudHandler, module: &ModuleResp) -> (String, String) {
+    let defaults = (module.cpu.clone(), module.memory.clone());
+    if module.manifest.spec.cpu.is_some() || module.manifest.spec.memory.is_some() {
+        return defaults;
+    }
+
+    let project_id = handler.get_project_id();
+    let settings = match handler.get_all_projects().await {
+        Ok(projects) => projects
+            .into_iter()
+            .find(|project| project.project_id == project_id)
+            .map(|project| project.settings.runner_sizing),
+        Err(e) => {
+            warn!("Failed to read runner sizing settings: {}", e);
+            None
+        }
+    };
+    let Some(settings) = settings.filter(|settings| settings.auto) else {
+        return defaults;
+    };
+
+    match handler.get_job_stats(&module.module).await {
+        Ok(history) => match auto_runner_size(&history, settings.min_samples as usize) {
+            Some((cpu, memory)) => {
+                info!(
+                    "Sized job of module {} from {} recorded job(s): cpu {}, memory {}",
+                    module.module,
+                    history.len(),
+                    cpu,
+                    memory
+                );
+                (cpu, memory)
+            }
+            None => defaults,
+        },
+        Err(e) => {
+            warn!(
+                "Failed to read job stats of module {}: {}",
+                module.module, e
+            );
+            defaults
+        }
+    }
+}
+
+fn stats_pk(handler: &GenericCloudHandler, module: &str) -> String {
+    format!(
+        "JOBSTATS#{}{}",
+        get_deployment_identifier(handler.get_project_id(), handler.get_region(), "", ""),
+        module
+    )
+}
+
+fn stats_sk(stats: &JobStats) -> String {
+    format!("JOB#{}#{}", stats.epoch, stats.job_id)
+}
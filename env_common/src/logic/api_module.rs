@@ -3,14 +3,16 @@ use base64::engine::general_purpose::STANDARD as base64;
 use base64::Engine;
 use env_defs::{
     get_module_identifier, CloudProvider, DeploymentManifest, DeploymentMetadata, DeploymentSpec,
-    ModuleManifest, ModuleResp, OciArtifactSet, ProviderResp, TfLockProvider, TfOutput, TfVariable,
+    ModuleManifest, ModuleResp, ModuleYank, OciArtifactSet, ProviderResp, TfLockProvider, TfOutput,
+    TfVariable,
 };
 use env_utils::{
-    convert_module_example_variables_to_camel_case, copy_dir_recursive,
-    generate_module_example_deployment, get_providers_from_lockfile, get_terraform_lockfile,
-    get_tf_required_providers_from_tf_files, get_timestamp, get_variables_from_tf_files,
-    merge_json_dicts, read_tf_from_zip, run_terraform_provider_lock, semver_parse, tempdir,
-    validate_module_schema, validate_tf_backend_not_set, validate_tf_extra_environment_variables,
+    artifact_digest, convert_module_example_variables_to_camel_case, copy_dir_recursive,
+    generate_module_example_deployment, get_epoch, get_module_doc_file,
+    get_providers_from_lockfile, get_terraform_lockfile, get_tf_required_providers_from_tf_files,
+    get_timestamp, get_variables_from_tf_files, merge_json_dicts, read_tf_from_zip,
+    run_terraform_provider_lock, semver_parse, tempdir, validate_module_schema,
+    validate_tf_backend_not_set, validate_tf_extra_environment_variables,
     verify_output_name_roundtrip, verify_variable_name_roundtrip, zero_pad_semver,
 };
 use futures::stream::{self, StreamExt};
@@ -170,6 +172,10 @@ pub async fn publish_module(
             variables: serde_yaml::Mapping::with_capacity(0),
             dependencies: None,
             drift_detection: None,
+            var_file: None,
+            targets: vec![],
+            depends_on: vec![],
+            outputs_to: None,
         },
     };
     let module_call_builder = Body::builder()
@@ -180,7 +186,7 @@ pub async fn publish_module(
                     .iter()
                     .map(|block| {
                         let name = block.labels().first().unwrap().as_str().to_string();
-                        return (name.clone(), name.clone());
+                        (name.clone(), Some(name))
                     })
                     .collect(),
                 &deployment,
@@ -230,6 +236,37 @@ pub async fn publish_module(
     .await
 }
 
+/// README and changelog are stored on the module version record, keep them well below the item size limit
+const MAX_MODULE_DOC_BYTES: usize = 64 * 1024;
+
+/// Reads `filename` from the module zip, truncated to `MAX_MODULE_DOC_BYTES`
+fn read_module_doc(zip_file: &[u8], filename: &str) -> Option<String> {
+    match get_module_doc_file(zip_file, filename) {
+        Ok(content) => content.map(|content| truncate_module_doc(content, filename)),
+        Err(e) => {
+            warn!("Failed to read {} from module zip: {}", filename, e);
+            None
+        }
+    }
+}
+
+fn truncate_module_doc(mut content: String, filename: &str) -> String {
+    if content.len() <= MAX_MODULE_DOC_BYTES {
+        return content;
+    }
+    warn!(
+        "{} is larger than {} bytes and is truncated",
+        filename, MAX_MODULE_DOC_BYTES
+    );
+    let mut end = MAX_MODULE_DOC_BYTES;
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    content.truncate(end);
+    content.push_str("\n\n_Truncated, see the module source for the full document._\n");
+    content
+}
+
 fn validate_providers(tf_providers: &Vec<ProviderResp>) {
     let mut provider_map: HashMap<String, Vec<&ProviderResp>> = HashMap::new();
     tf_providers.iter().for_each(|p| {
@@ -262,6 +299,12 @@ pub async fn publish_module_from_zip(
     oci_artifact_set: Option<OciArtifactSet>,
     module_variables: Option<Vec<TfVariable>>,
 ) -> Result<(), ModuleError> {
+    // Manifests published from OCI artifacts skip the local validations
+    module_yaml
+        .spec
+        .validate_owners()
+        .map_err(ModuleError::ValidationError)?;
+
     // Encode the zip file content to Base64
     let zip_base64 = base64.encode(&zip_file);
 
@@ -354,6 +397,11 @@ pub async fn publish_module_from_zip(
         ModuleError::InvalidOutputNaming(format!("Module '{}': {}", module_yaml.metadata.name, e))
     })?;
 
+    if let Some(ui) = &module_yaml.spec.ui {
+        ui.validate(&tf_variables)
+            .map_err(ModuleError::ValidationError)?;
+    }
+
     let module = module_yaml.metadata.name.clone();
     let version = match module_yaml.spec.version.clone() {
         Some(version) => version,
@@ -455,6 +503,11 @@ pub async fn publish_module_from_zip(
         memory: module_yaml.spec.memory.unwrap_or_else(get_default_memory),
         deprecated: false,
         deprecated_message: None,
+        readme: read_module_doc(zip_file, "README.md"),
+        changelog: read_module_doc(zip_file, "CHANGELOG.md"),
+        digest: Some(artifact_digest(zip_file)),
+        oci_reference: None,
+        yanked: None,
     };
 
     // HTTP API mode: send built module to server for upload/storage only
@@ -621,6 +674,17 @@ pub async fn upload_module(
     module: &ModuleResp,
     zip_base64: &String,
 ) -> anyhow::Result<(), anyhow::Error> {
+    // Refuse a zip that isn't the one the digest was recorded for, e.g. altered on its way to the server
+    if let Some(digest) = &module.digest {
+        let zip = base64.decode(zip_base64)?;
+        if artifact_digest(&zip) != *digest {
+            return Err(anyhow::anyhow!(
+                "Module zip does not match its recorded digest {}",
+                digest
+            ));
+        }
+    }
+
     match handler
         .upload_file_base64(&module.s3_key, "modules", zip_base64)
         .await
@@ -633,6 +697,17 @@ pub async fn upload_module(
         }
     }
 
+    let sbom = env_utils::generate_module_sbom(module);
+    handler
+        .upload_file_base64(
+            &sbom_key(&module.s3_key),
+            "modules",
+            &base64.encode(serde_json::to_vec(&sbom)?),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to upload SBOM to S3: {}", e))?;
+    info!("Successfully uploaded module SBOM to storage");
+
     match insert_module(handler, module).await {
         Ok(_) => {
             info!(
@@ -653,6 +728,11 @@ pub async fn upload_module(
     Ok(())
 }
 
+/// Storage key of the SBOM stored next to a module zip, "{module}/{module}-{version}.sbom.json"
+fn sbom_key(s3_key: &str) -> String {
+    format!("{}.sbom.json", s3_key.trim_end_matches(".zip"))
+}
+
 /// Server-side publish: validates version ordering and name uniqueness, then uploads to all regions.
 /// Called by the internal-api when receiving a module publish request over HTTP.
 pub async fn server_publish_module(
@@ -815,23 +895,231 @@ pub async fn deprecate_module(
         }
     }
 
+    let mut updated_module = existing_module.clone();
+    updated_module.deprecated = true;
+    updated_module.deprecated_message = message.map(|s| s.to_string());
+
+    info!("Deprecating module in all regions...");
+    put_module_version_in_all_regions(handler, &updated_module).await?;
+
+    info!(
+        "Successfully deprecated module {} version {} in track {} in all regions",
+        module, version, track
+    );
+
+    Ok(())
+}
+
+/// Yanks a published module version: deployments already running it keep doing so, but new
+/// claims are refused and pointed at the replacement version. Without `replacement_version` the
+/// newest version of the track that is neither yanked nor deprecated is recommended. `yanked_by`
+/// defaults to the user of the handler
+pub async fn yank_module(
+    handler: &GenericCloudHandler,
+    module: &str,
+    track: &str,
+    version: &str,
+    reason: Option<&str>,
+    replacement_version: Option<&str>,
+    yanked_by: Option<&str>,
+) -> anyhow::Result<ModuleYank> {
+    yank_version(
+        handler,
+        false,
+        module,
+        track,
+        version,
+        reason,
+        replacement_version,
+        yanked_by,
+    )
+    .await
+}
+
+/// Lifts the yank of a module version, so new claims can use it again
+pub async fn unyank_module(
+    handler: &GenericCloudHandler,
+    module: &str,
+    track: &str,
+    version: &str,
+) -> anyhow::Result<()> {
+    unyank_version(handler, false, module, track, version).await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn yank_version(
+    handler: &GenericCloudHandler,
+    is_stack: bool,
+    module: &str,
+    track: &str,
+    version: &str,
+    reason: Option<&str>,
+    replacement_version: Option<&str>,
+    yanked_by: Option<&str>,
+) -> anyhow::Result<ModuleYank> {
+    let kind = if is_stack { "Stack" } else { "Module" };
+    info!(
+        "Yanking {}: {}, track: {}, version: {}",
+        kind.to_lowercase(),
+        module,
+        track,
+        version
+    );
+
+    let existing_module = get_version(handler, is_stack, module, track, version).await?;
+    if existing_module.yanked.is_some() {
+        return Err(anyhow!(
+            "{} {} version {} is already yanked",
+            kind,
+            module,
+            version
+        ));
+    }
+
+    let versions = if is_stack {
+        handler.get_all_stack_versions(module, track).await?
+    } else {
+        handler.get_all_module_versions(module, track).await?
+    };
+    let replacement_version = match replacement_version {
+        Some(replacement) => {
+            let usable = versions
+                .iter()
+                .any(|v| v.version == replacement && v.version != version && v.yanked.is_none());
+            if !usable {
+                return Err(anyhow!(
+                    "Replacement version {} of {} {} is not a published version of track {} that isn't yanked",
+                    replacement,
+                    kind.to_lowercase(),
+                    module,
+                    track
+                ));
+            }
+            Some(replacement.to_string())
+        }
+        None => newest_usable_version(&versions, version),
+    };
+
+    let yank = ModuleYank {
+        reason: reason.map(|s| s.to_string()),
+        replacement_version,
+        yanked_by: match yanked_by {
+            Some(user) => user.to_string(),
+            None => handler
+                .get_user_id()
+                .await
+                .unwrap_or_else(|_| "unknown".to_string()),
+        },
+        epoch: get_epoch(),
+    };
+    let mut updated_module = existing_module.clone();
+    updated_module.yanked = Some(yank.clone());
+
+    info!("Yanking {} in all regions...", kind.to_lowercase());
+    put_module_version_in_all_regions(handler, &updated_module).await?;
+    Ok(yank)
+}
+
+pub(crate) async fn unyank_version(
+    handler: &GenericCloudHandler,
+    is_stack: bool,
+    module: &str,
+    track: &str,
+    version: &str,
+) -> anyhow::Result<()> {
+    let mut existing_module = get_version(handler, is_stack, module, track, version).await?;
+    if existing_module.yanked.take().is_none() {
+        return Err(anyhow!(
+            "{} {} version {} is not yanked",
+            if is_stack { "Stack" } else { "Module" },
+            module,
+            version
+        ));
+    }
+    put_module_version_in_all_regions(handler, &existing_module).await
+}
+
+async fn get_version(
+    handler: &GenericCloudHandler,
+    is_stack: bool,
+    module: &str,
+    track: &str,
+    version: &str,
+) -> anyhow::Result<ModuleResp> {
+    let existing = if is_stack {
+        handler.get_stack_version(module, track, version).await?
+    } else {
+        handler.get_module_version(module, track, version).await?
+    };
+    existing.ok_or_else(|| {
+        anyhow!(
+            "{} {} version {} not found in track {}",
+            if is_stack { "Stack" } else { "Module" },
+            module,
+            version,
+            track
+        )
+    })
+}
+
+/// Newest version other than `yanked_version` that is neither yanked nor deprecated
+fn newest_usable_version(versions: &[ModuleResp], yanked_version: &str) -> Option<String> {
+    versions
+        .iter()
+        .filter(|v| v.version != yanked_version && v.yanked.is_none() && !v.deprecated)
+        .filter_map(|v| {
+            semver_parse(&v.version)
+                .ok()
+                .map(|parsed| (parsed, &v.version))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, version)| version.clone())
+}
+
+/// Records the digest-pinned reference of the OCI artifact a module version was pushed to
+pub async fn set_module_oci_reference(
+    handler: &GenericCloudHandler,
+    module: &str,
+    track: &str,
+    version: &str,
+    oci_reference: &str,
+) -> anyhow::Result<()> {
+    let mut existing_module = match handler.get_module_version(module, track, version).await? {
+        Some(module) => module,
+        None => {
+            return Err(anyhow!(
+                "Module {} version {} not found in track {}",
+                module,
+                version,
+                track
+            ));
+        }
+    };
+    existing_module.oci_reference = Some(oci_reference.to_string());
+    put_module_version_in_all_regions(handler, &existing_module).await
+}
+
+/// Overwrites the record of a module version in all regions, e.g. after changing its metadata
+async fn put_module_version_in_all_regions(
+    handler: &GenericCloudHandler,
+    module: &ModuleResp,
+) -> anyhow::Result<()> {
     let module_table_placeholder = "modules";
     let mut transaction_items = vec![];
 
-    let id: String = format!("MODULE#{}", get_module_identifier(module, track));
+    let id: String = format!(
+        "MODULE#{}",
+        get_module_identifier(&module.module, &module.track)
+    );
 
     // Update the specific version record
     let mut module_payload = serde_json::to_value(serde_json::json!({
         "PK": id.clone(),
-        "SK": format!("VERSION#{}", zero_pad_semver(version, 3)?),
+        "SK": format!("VERSION#{}", zero_pad_semver(&module.version, 3)?),
     }))
     .unwrap();
 
-    // Serialize the existing module with deprecated flag set to true and optional message
-    let mut updated_module = existing_module.clone();
-    updated_module.deprecated = true;
-    updated_module.deprecated_message = message.map(|s| s.to_string());
-    let module_value = serde_json::to_value(&updated_module)?;
+    let module_value = serde_json::to_value(module)?;
     merge_json_dicts(&mut module_payload, &module_value);
 
     transaction_items.push(serde_json::json!({
@@ -844,37 +1132,26 @@ pub async fn deprecate_module(
     let items = serde_json::to_value(&transaction_items)?;
     let payload = env_defs::transact_write_event(&items);
 
-    // Get all regions to update deprecation status across all of them
     let all_regions = handler.get_all_regions().await?;
-
-    info!("Deprecating module in all regions...");
-
-    // Update module in all regions
     for region in all_regions.iter() {
         let region_handler = handler.copy_with_region(region).await;
 
         match region_handler.run_function(&payload).await {
             Ok(_) => {
                 info!(
-                    "Successfully deprecated module {} version {} in track {} in region {}",
-                    module, version, track, region
+                    "Successfully updated module {} version {} in track {} in region {}",
+                    module.module, module.version, module.track, region
                 );
             }
             Err(e) => {
                 return Err(anyhow!(
-                    "Failed to deprecate module in region {}: {}",
+                    "Failed to update module in region {}: {}",
                     region,
                     e
                 ));
             }
         }
     }
-
-    info!(
-        "Successfully deprecated module {} version {} in track {} in all regions",
-        module, version, track
-    );
-
     Ok(())
 }
 
@@ -1107,6 +1384,7 @@ mod tests {
                 sensitive: false,
                 nullable: false,
                 _type: serde_json::Value::String("string".to_string()),
+                validations: vec![],
             },
             TfVariable {
                 name: "tags".to_string(),
@@ -1115,6 +1393,7 @@ mod tests {
                 sensitive: false,
                 nullable: false,
                 _type: serde_json::Value::String("map".to_string()),
+                validations: vec![],
             },
             TfVariable {
                 name: "port_mapping".to_string(),
@@ -1123,6 +1402,7 @@ mod tests {
                 sensitive: false,
                 nullable: false,
                 _type: serde_json::Value::String("list".to_string()),
+                validations: vec![],
             },
         ];
         let example_variables = serde_yaml::from_str::<serde_yaml::Value>(
@@ -1152,6 +1432,7 @@ port_mapping:
                 sensitive: false,
                 nullable: false,
                 _type: serde_json::Value::String("string".to_string()),
+                validations: vec![],
             },
             TfVariable {
                 name: "bucket_name".to_string(),
@@ -1160,6 +1441,7 @@ port_mapping:
                 sensitive: false,
                 nullable: false,
                 _type: serde_json::Value::String("string".to_string()),
+                validations: vec![],
             },
         ];
         let example_variables = serde_yaml::from_str::<serde_yaml::Value>(
@@ -1183,6 +1465,7 @@ bucket_name: some-bucket-name
                 sensitive: false,
                 nullable: false,
                 _type: serde_json::Value::String("string".to_string()),
+                validations: vec![],
             },
             TfVariable {
                 name: "bucket_name".to_string(),
@@ -1191,6 +1474,7 @@ bucket_name: some-bucket-name
                 sensitive: false,
                 nullable: false,
                 _type: serde_json::Value::String("string".to_string()),
+                validations: vec![],
             },
         ];
         let example_variables = serde_yaml::from_str::<serde_yaml::Value>(
@@ -1214,6 +1498,7 @@ bucket_name: some-bucket-name
                 sensitive: false,
                 nullable: true,
                 _type: serde_json::Value::String("string".to_string()),
+                validations: vec![],
             },
             TfVariable {
                 name: "bucket_name".to_string(),
@@ -1222,6 +1507,7 @@ bucket_name: some-bucket-name
                 sensitive: false,
                 nullable: false,
                 _type: serde_json::Value::String("string".to_string()),
+                validations: vec![],
             },
         ];
         let example_variables = serde_yaml::from_str::<serde_yaml::Value>(
@@ -1244,6 +1530,7 @@ bucket_name: some-bucket-name
             sensitive: false,
             nullable: false,
             _type: serde_json::Value::String("string".to_string()),
+            validations: vec![],
         }];
         let example_variables = serde_yaml::from_str::<serde_yaml::Value>(
             r#"
@@ -1270,6 +1557,7 @@ port_mapping:
             sensitive: false,
             nullable: false,
             _type: serde_json::Value::String("string".to_string()),
+            validations: vec![],
         }];
         let example_variables = serde_yaml::from_str::<serde_yaml::Value>(
             r#"
@@ -1447,4 +1735,16 @@ bucketName: some-bucket-name
         ];
         validate_providers(&tf_providers);
     }
+
+    #[test]
+    fn test_truncate_module_doc() {
+        let readme = "# S3Bucket\n".to_string();
+        assert_eq!(truncate_module_doc(readme.clone(), "README.md"), readme);
+
+        let long = "é".repeat(MAX_MODULE_DOC_BYTES);
+        let truncated = truncate_module_doc(long, "README.md");
+        assert!(truncated.starts_with("éé"));
+        assert!(truncated.ends_with("_Truncated, see the module source for the full document._\n"));
+        assert!(truncated.len() < MAX_MODULE_DOC_BYTES + 100);
+    }
 }
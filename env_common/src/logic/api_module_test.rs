@@ -99,3 +99,90 @@ mod test_deprecate_module {
             .contains("Cannot deprecate the latest version"));
     }
 }
+
+mod test_yank_module {
+    use std::sync::Arc;
+
+    use env_defs::{ModuleResp, ModuleYank};
+    use tokio::test;
+
+    use crate::interface::{GenericCloudHandler, TestCloudProvider};
+    use crate::logic::{unyank_module, yank_module};
+
+    fn module(version: &str, yanked: bool) -> ModuleResp {
+        ModuleResp {
+            version: version.to_string(),
+            yanked: yanked.then(|| ModuleYank {
+                reason: Some("broken".to_string()),
+                replacement_version: None,
+                yanked_by: "someone".to_string(),
+                epoch: 0,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    async fn err_when_module_version_not_found() {
+        let mut mock = TestCloudProvider::new();
+        mock.expect_get_module_version()
+            .returning(|_m: &str, _t: &str, _v: &str| Ok(None));
+
+        let handler = GenericCloudHandler::with_provider(Arc::new(mock), None);
+        let result = yank_module(&handler, "my-mod", "stable", "1.0.0", None, None, None).await;
+
+        assert!(result.unwrap_err().to_string().contains("not found"));
+    }
+
+    #[test]
+    async fn err_when_already_yanked() {
+        let mut mock = TestCloudProvider::new();
+        mock.expect_get_module_version()
+            .returning(|_m: &str, _t: &str, _v: &str| Ok(Some(module("1.0.0", true))));
+
+        let handler = GenericCloudHandler::with_provider(Arc::new(mock), None);
+        let result = yank_module(&handler, "my-mod", "stable", "1.0.0", None, None, None).await;
+
+        assert!(result.unwrap_err().to_string().contains("already yanked"));
+    }
+
+    #[test]
+    async fn err_when_replacement_is_yanked() {
+        let mut mock = TestCloudProvider::new();
+        mock.expect_get_module_version()
+            .returning(|_m: &str, _t: &str, _v: &str| Ok(Some(module("1.0.0", false))));
+        mock.expect_get_all_module_versions()
+            .returning(|_m: &str, _t: &str| {
+                Ok(vec![module("1.0.0", false), module("1.0.1", true)])
+            });
+
+        let handler = GenericCloudHandler::with_provider(Arc::new(mock), None);
+        let result = yank_module(
+            &handler,
+            "my-mod",
+            "stable",
+            "1.0.0",
+            None,
+            Some("1.0.1"),
+            None,
+        )
+        .await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Replacement version 1.0.1"));
+    }
+
+    #[test]
+    async fn err_when_unyanking_version_that_is_not_yanked() {
+        let mut mock = TestCloudProvider::new();
+        mock.expect_get_module_version()
+            .returning(|_m: &str, _t: &str, _v: &str| Ok(Some(module("1.0.0", false))));
+
+        let handler = GenericCloudHandler::with_provider(Arc::new(mock), None);
+        let result = unyank_module(&handler, "my-mod", "stable", "1.0.0").await;
+
+        assert!(result.unwrap_err().to_string().contains("is not yanked"));
+    }
+}
@@ -0,0 +1,489 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use env_defs::{
+    CloudProvider, DeploymentResp, DeploymentStatus, ExtraData, ModuleExample, ModuleResp,
+};
+use log::{error, info, warn};
+use serde_json::Value;
+
+use super::{destroy_infra, get_deployment_outputs, run_claim};
+use crate::interface::GenericCloudHandler;
+
+const JOB_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How `run_module_tests` deploys and verifies the examples of a module
+#[derive(Debug, Clone)]
+pub struct ModuleTestOptions {
+    pub region: String,
+    /// Names of the examples to test, all examples if empty
+    pub examples: Vec<String>,
+    /// Script run after each apply, see `run_assertions`
+    pub assert_script: Option<PathBuf>,
+    /// Maximum time to wait for each apply and destroy job
+    pub job_timeout: Duration,
+}
+
+/// Result of testing a single module example
+#[derive(Debug, Clone)]
+pub struct ModuleTestResult {
+    pub example: String,
+    pub deployment_id: String,
+    pub duration: Duration,
+    /// Why the apply or the assertions failed
+    pub failure: Option<String>,
+    /// Why the ephemeral deployment could not be destroyed, it has to be cleaned up by hand
+    pub cleanup_failure: Option<String>,
+}
+
+impl ModuleTestResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none() && self.cleanup_failure.is_none()
+    }
+}
+
+/// Deploys each example of `module` into an ephemeral environment, runs the assertion script
+/// against its outputs and destroys it again. The deployment is destroyed whether the test
+/// passed or not, also when the run is interrupted with Ctrl-C
+pub async fn run_module_tests(
+    handler: &GenericCloudHandler,
+    module: &ModuleResp,
+    options: &ModuleTestOptions,
+) -> Result<Vec<ModuleTestResult>, anyhow::Error> {
+    let examples = select_examples(module, &options.examples)?;
+    let run_id = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+    let environment = format!("module-test/{}", run_id);
+    info!(
+        "Testing {} example(s) of {} version {} in environment {}",
+        examples.len(),
+        module.module,
+        module.version,
+        environment
+    );
+
+    let mut results = vec![];
+    for example in examples {
+        let claim = example_test_claim(module, example, &options.region, &run_id);
+        let deployment_id = format!(
+            "{}/{}",
+            module.module_name.to_lowercase(),
+            claim["metadata"]["name"].as_str().unwrap_or_default()
+        );
+        let start = Instant::now();
+
+        let test = test_example(
+            handler,
+            example,
+            &claim,
+            &deployment_id,
+            &environment,
+            options,
+        );
+        let failure = tokio::select! {
+            failure = test => failure,
+            _ = tokio::signal::ctrl_c() => {
+                warn!("Interrupted, destroying {} before exiting", deployment_id);
+                let timeout = options.job_timeout;
+                if let Err(e) =
+                    destroy_test_deployment(handler, &deployment_id, &environment, timeout).await
+                {
+                    error!("Failed to destroy {}, clean it up by hand: {}", deployment_id, e);
+                }
+                return Err(anyhow::anyhow!("Module test was interrupted"));
+            }
+        };
+        let cleanup_failure =
+            destroy_test_deployment(handler, &deployment_id, &environment, options.job_timeout)
+                .await
+                .err()
+                .map(|e| e.to_string());
+
+        let result = ModuleTestResult {
+            example: example.name.clone(),
+            deployment_id,
+            duration: start.elapsed(),
+            failure,
+            cleanup_failure,
+        };
+        if result.passed() {
+            info!("Example {} passed", result.example);
+        } else {
+            error!("Example {} failed", result.example);
+        }
+        results.push(result);
+    }
+    Ok(results)
+}
+
+fn select_examples<'a>(
+    module: &'a ModuleResp,
+    names: &[String],
+) -> Result<Vec<&'a ModuleExample>, anyhow::Error> {
+    let examples = module.manifest.spec.examples.as_deref().unwrap_or_default();
+    if examples.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Module {} version {} has no examples to test",
+            module.module,
+            module.version
+        ));
+    }
+    if names.is_empty() {
+        return Ok(examples.iter().collect());
+    }
+    names
+        .iter()
+        .map(|name| {
+            examples
+                .iter()
+                .find(|example| &example.name == name)
+                .ok_or_else(|| anyhow::anyhow!("Module {} has no example {}", module.module, name))
+        })
+        .collect()
+}
+
+/// Claim deploying `example` under a name unique to this test run
+fn example_test_claim(
+    module: &ModuleResp,
+    example: &ModuleExample,
+    region: &str,
+    run_id: &str,
+) -> serde_yaml::Value {
+    let example_name: String = example
+        .name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let mut name = format!("test-{}", example_name.trim_matches('-'));
+    name.truncate(63 - run_id.len() - 1);
+    let name = format!("{}-{}", name.trim_end_matches('-'), run_id);
+
+    let mut claim = serde_yaml::Mapping::new();
+    claim.insert("apiVersion".into(), "infraweave.io/v1".into());
+    claim.insert("kind".into(), module.module_name.clone().into());
+    let mut metadata = serde_yaml::Mapping::new();
+    metadata.insert("name".into(), name.into());
+    claim.insert("metadata".into(), metadata.into());
+    let mut spec = serde_yaml::Mapping::new();
+    spec.insert("moduleVersion".into(), module.version.clone().into());
+    spec.insert("region".into(), region.into());
+    spec.insert("variables".into(), example.variables.clone());
+    claim.insert("spec".into(), spec.into());
+    serde_yaml::Value::Mapping(claim)
+}
+
+/// Applies the claim and runs the assertions, returning why the test failed if it did
+async fn test_example(
+    handler: &GenericCloudHandler,
+    example: &ModuleExample,
+    claim: &serde_yaml::Value,
+    deployment_id: &str,
+    environment: &str,
+    options: &ModuleTestOptions,
+) -> Option<String> {
+    info!("Applying example {} as {}", example.name, deployment_id);
+    let (job_id, _, _) = match run_claim(
+        handler,
+        claim,
+        environment,
+        "apply",
+        vec![],
+        ExtraData::None,
+        "",
+    )
+    .await
+    {
+        Ok(submitted) => submitted,
+        Err(e) => return Some(format!("Failed to submit apply: {}", e)),
+    };
+    if let Err(e) = wait_for_job(
+        handler,
+        deployment_id,
+        environment,
+        &job_id,
+        options.job_timeout,
+    )
+    .await
+    {
+        return Some(e.to_string());
+    }
+
+    if let Some(script) = &options.assert_script {
+        info!(
+            "Running assertions {:?} for example {}",
+            script, example.name
+        );
+        if let Err(e) = run_assertions(handler, script, example, deployment_id, environment).await {
+            return Some(e.to_string());
+        }
+    }
+    None
+}
+
+/// Waits until `job_id` of the deployment has finished, failing unless it was successful
+async fn wait_for_job(
+    handler: &GenericCloudHandler,
+    deployment_id: &str,
+    environment: &str,
+    job_id: &str,
+    timeout: Duration,
+) -> Result<DeploymentResp, anyhow::Error> {
+    let start = Instant::now();
+    loop {
+        let deployment = handler
+            .get_deployment(deployment_id, environment, true)
+            .await?
+            .filter(|d| d.job_id == job_id);
+        if let Some(deployment) = deployment {
+            if deployment.status == DeploymentStatus::PendingApproval {
+                return Err(anyhow::anyhow!(
+                    "Job {} is waiting for approval, which module tests cannot give",
+                    job_id
+                ));
+            }
+            if deployment.status.is_final() {
+                if deployment.status != DeploymentStatus::Successful {
+                    return Err(anyhow::anyhow!(
+                        "Job {} finished with status {}: {}",
+                        job_id,
+                        deployment.status,
+                        deployment.error_text
+                    ));
+                }
+                return Ok(deployment);
+            }
+        }
+        if start.elapsed() > timeout {
+            return Err(anyhow::anyhow!(
+                "Job {} did not finish within {} minutes",
+                job_id,
+                timeout.as_secs() / 60
+            ));
+        }
+        tokio::time::sleep(JOB_POLL_INTERVAL).await;
+    }
+}
+
+/// Destroys the ephemeral deployment if it exists, waiting for a running job to finish first
+async fn destroy_test_deployment(
+    handler: &GenericCloudHandler,
+    deployment_id: &str,
+    environment: &str,
+    timeout: Duration,
+) -> Result<(), anyhow::Error> {
+    let Some(deployment) = handler
+        .get_deployment(deployment_id, environment, false)
+        .await?
+    else {
+        return Ok(());
+    };
+    if deployment.status.is_busy() {
+        // The outcome of the running job doesn't matter, only that it no longer holds the state
+        let _ = wait_for_job(
+            handler,
+            deployment_id,
+            environment,
+            &deployment.job_id,
+            timeout,
+        )
+        .await;
+    }
+
+    info!("Destroying {}", deployment_id);
+    let job_id = destroy_infra(handler, deployment_id, environment, ExtraData::None, None).await?;
+    wait_for_job(handler, deployment_id, environment, &job_id, timeout)
+        .await
+        .map(|_| ())
+        .map_err(|e| anyhow::anyhow!("Failed to destroy {}: {}", deployment_id, e))
+}
+
+/// Runs `script` with the outputs of the deployment as a JSON object of `{name: value}` in the
+/// file at `INFRAWEAVE_TEST_OUTPUTS`, failing the test if it exits with a non-zero code
+async fn run_assertions(
+    handler: &GenericCloudHandler,
+    script: &Path,
+    example: &ModuleExample,
+    deployment_id: &str,
+    environment: &str,
+) -> Result<(), anyhow::Error> {
+    let outputs = get_deployment_outputs(handler, deployment_id, environment, true).await?;
+    let values: serde_json::Map<String, Value> = outputs
+        .as_object()
+        .map(|outputs| {
+            outputs
+                .iter()
+                .map(|(name, output)| {
+                    (
+                        name.clone(),
+                        output.get("value").cloned().unwrap_or(Value::Null),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let outputs_file = std::env::temp_dir().join(format!(
+        "infraweave-test-{}-outputs.json",
+        deployment_id.replace('/', "-")
+    ));
+    std::fs::write(&outputs_file, serde_json::to_vec_pretty(&values)?)?;
+    let output = tokio::process::Command::new(script)
+        .env("INFRAWEAVE_TEST_EXAMPLE", &example.name)
+        .env("INFRAWEAVE_TEST_DEPLOYMENT_ID", deployment_id)
+        .env("INFRAWEAVE_TEST_ENVIRONMENT", environment)
+        .env("INFRAWEAVE_TEST_OUTPUTS", &outputs_file)
+        .output()
+        .await;
+    let _ = std::fs::remove_file(&outputs_file);
+    let output =
+        output.map_err(|e| anyhow::anyhow!("Failed to run assertions {:?}: {}", script, e))?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Assertions failed ({}):\n{}{}",
+            output.status,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(())
+}
+
+/// JUnit XML report of a module test run, one test case per example
+pub fn module_test_junit_report(module: &ModuleResp, results: &[ModuleTestResult]) -> String {
+    let failures = results.iter().filter(|r| !r.passed()).count();
+    let total_time: f64 = results.iter().map(|r| r.duration.as_secs_f64()).sum();
+    let suite_name = format!("{} {}", module.module, module.version);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        xml_escape(&suite_name),
+        results.len(),
+        failures,
+        total_time
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        xml_escape(&suite_name),
+        results.len(),
+        failures,
+        total_time
+    ));
+    for result in results {
+        xml.push_str(&format!(
+            "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+            xml_escape(&module.module),
+            xml_escape(&result.example),
+            result.duration.as_secs_f64()
+        ));
+        if result.passed() {
+            xml.push_str("/>\n");
+            continue;
+        }
+        xml.push_str(">\n");
+        if let Some(failure) = &result.failure {
+            xml.push_str(&format!(
+                "      <failure message=\"Example failed\">{}</failure>\n",
+                xml_escape(failure)
+            ));
+        }
+        if let Some(cleanup_failure) = &result.cleanup_failure {
+            xml.push_str(&format!(
+                "      <failure message=\"Cleanup of {} failed\">{}</failure>\n",
+                xml_escape(&result.deployment_id),
+                xml_escape(cleanup_failure)
+            ));
+        }
+        xml.push_str("    </testcase>\n");
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module() -> ModuleResp {
+        let mut module = ModuleResp {
+            module: "s3bucket".to_string(),
+            module_name: "S3Bucket".to_string(),
+            version: "0.1.4-dev".to_string(),
+            ..Default::default()
+        };
+        module.manifest.spec.examples = Some(vec![
+            ModuleExample {
+                name: "Simple_Bucket".to_string(),
+                description: "".to_string(),
+                variables: serde_yaml::from_str("bucketName: my-bucket").unwrap(),
+            },
+            ModuleExample {
+                name: "versioned".to_string(),
+                description: "".to_string(),
+                variables: serde_yaml::from_str("enableVersioning: true").unwrap(),
+            },
+        ]);
+        module
+    }
+
+    #[test]
+    fn test_example_test_claim() {
+        let module = module();
+        let example = &module.manifest.spec.examples.as_ref().unwrap()[0];
+        let claim = example_test_claim(&module, example, "eu-central-1", "1a2b3c4d");
+        assert_eq!(claim["kind"], "S3Bucket");
+        assert_eq!(claim["metadata"]["name"], "test-simple-bucket-1a2b3c4d");
+        assert_eq!(claim["spec"]["moduleVersion"], "0.1.4-dev");
+        assert_eq!(claim["spec"]["region"], "eu-central-1");
+        assert_eq!(claim["spec"]["variables"]["bucketName"], "my-bucket");
+    }
+
+    #[test]
+    fn test_select_examples() {
+        let module = module();
+        assert_eq!(select_examples(&module, &[]).unwrap().len(), 2);
+        let selected = select_examples(&module, &["versioned".to_string()]).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].name, "versioned");
+        assert!(select_examples(&module, &["missing".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_module_test_junit_report() {
+        let results = vec![
+            ModuleTestResult {
+                example: "simple".to_string(),
+                deployment_id: "s3bucket/test-simple-1a2b3c4d".to_string(),
+                duration: Duration::from_secs(90),
+                failure: None,
+                cleanup_failure: None,
+            },
+            ModuleTestResult {
+                example: "versioned".to_string(),
+                deployment_id: "s3bucket/test-versioned-1a2b3c4d".to_string(),
+                duration: Duration::from_secs(30),
+                failure: Some("Assertions failed: expected <true>".to_string()),
+                cleanup_failure: None,
+            },
+        ];
+        let report = module_test_junit_report(&module(), &results);
+        assert!(report.contains(
+            "<testsuite name=\"s3bucket 0.1.4-dev\" tests=\"2\" failures=\"1\" time=\"120.000\">"
+        ));
+        assert!(
+            report.contains("<testcase classname=\"s3bucket\" name=\"simple\" time=\"90.000\"/>")
+        );
+        assert!(report.contains(
+            "<failure message=\"Example failed\">Assertions failed: expected &lt;true&gt;</failure>"
+        ));
+    }
+}
@@ -1,6 +1,7 @@
 use base64::engine::general_purpose::STANDARD as base64;
 use base64::Engine;
 use env_defs::ModuleResp;
+use env_utils::artifact_digest;
 use std::collections::BTreeMap;
 
 use oci_client::{
@@ -10,6 +11,42 @@ use oci_client::{
 };
 use serde_json;
 
+const MODULE_MEDIA_TYPE: &str = "application/vnd.infraweave.module.v1.zip";
+// Key of Docker Hub in the docker config
+const DOCKER_HUB_CONFIG_KEY: &str = "https://index.docker.io/v1/";
+
+/// Module pulled from an OCI registry, along with the zip it was published with
+pub struct PulledModule {
+    pub module: ModuleResp,
+    pub zip: Vec<u8>,
+    /// Digest-pinned reference of the pulled artifact
+    pub reference: String,
+}
+
+/// Registries that are authenticated with a token of the cloud provider rather than a password
+#[derive(Debug, PartialEq)]
+enum RegistryKind {
+    Ecr { region: String },
+    Acr,
+    Other,
+}
+
+impl RegistryKind {
+    fn of(host: &str) -> RegistryKind {
+        // <account>.dkr.ecr.<region>.amazonaws.com, or .amazonaws.com.cn in the China regions
+        let parts: Vec<&str> = host.split('.').collect();
+        if parts.len() >= 6 && parts[1] == "dkr" && parts[2] == "ecr" && parts[4] == "amazonaws" {
+            return RegistryKind::Ecr {
+                region: parts[3].to_string(),
+            };
+        }
+        if host.ends_with(".azurecr.io") {
+            return RegistryKind::Acr;
+        }
+        RegistryKind::Other
+    }
+}
+
 #[derive(Clone)]
 pub struct OCIRegistryProvider {
     pub registry: String,
@@ -26,19 +63,21 @@ impl OCIRegistryProvider {
         }
     }
 
+    /// Pushes the module to the registry, tagged `<module>-<version>`, and returns the
+    /// digest-pinned reference of the artifact
     pub async fn upload_module(
         &self,
         module: &ModuleResp,
         zip_base64: &String,
-    ) -> anyhow::Result<(), anyhow::Error> {
-        let (client, auth) = self.get_client_auth();
+    ) -> anyhow::Result<String, anyhow::Error> {
         let full_path = format!(
             "{}:{}",
             self.registry,
             format!("{}-{}", module.module, module.version.replace("+", "-"))
         );
         println!("Pushing to: {}", full_path);
-        let reference: Reference = full_path.parse().unwrap();
+        let reference: Reference = full_path.parse()?;
+        let (client, auth) = self.get_client_auth(reference.registry()).await?;
 
         let mut ann = BTreeMap::new();
         ann.insert(
@@ -55,11 +94,7 @@ impl OCIRegistryProvider {
         );
         let zip_bytes = base64.decode(zip_base64)?;
 
-        let zip_layer = ImageLayer::new(
-            zip_bytes.clone(),
-            "application/vnd.infraweave.module.v1.zip".to_string(),
-            None,
-        );
+        let zip_layer = ImageLayer::new(zip_bytes.clone(), MODULE_MEDIA_TYPE.to_string(), None);
 
         let diff_id = env_utils::get_diff_id_from_zip(&zip_bytes)?;
 
@@ -93,33 +128,124 @@ impl OCIRegistryProvider {
             .map_err(|e| anyhow::anyhow!("Failed to write url to file {}: {}", path_file, e))?;
         println!("✓ Stored oci artifact url in: {}", path_file);
 
-        Ok(())
-    }
+        let sbom = env_utils::generate_module_sbom(module);
+        self.push_supplementary_artifact(
+            &client,
+            &auth,
+            &manifest_digest,
+            "sbom",
+            serde_json::to_vec(&sbom)?,
+            env_utils::SBOM_MEDIA_TYPE,
+        )
+        .await?;
 
-    pub async fn get_oci(&self, oci_path: &str) -> anyhow::Result<ModuleResp, anyhow::Error> {
-        let (client, auth) = self.get_client_auth();
+        if provenance_enabled() {
+            let provenance = env_utils::generate_provenance_attestation(
+                module,
+                &self.registry,
+                &manifest_digest,
+                "https://infraweave.io/cli",
+            );
+            self.push_supplementary_artifact(
+                &client,
+                &auth,
+                &manifest_digest,
+                "att",
+                serde_json::to_vec(&provenance)?,
+                env_utils::ATTESTATION_MEDIA_TYPE,
+            )
+            .await?;
+        }
 
-        let oci_path = self.registry.clone() + "/" + oci_path;
+        Ok(format!("{}@{}", self.registry, manifest_digest))
+    }
 
-        println!("Pulling from: {}", oci_path);
-        let reference: Reference = oci_path.parse().unwrap();
+    /// Pushes an artifact describing the module, tagged `<digest>.<suffix>` next to it
+    async fn push_supplementary_artifact(
+        &self,
+        client: &Client,
+        auth: &RegistryAuth,
+        manifest_digest: &str,
+        suffix: &str,
+        data: Vec<u8>,
+        media_type: &str,
+    ) -> anyhow::Result<(), anyhow::Error> {
+        let full_path = format!(
+            "{}:{}.{}",
+            self.registry,
+            manifest_digest.replace(':', "-"),
+            suffix
+        );
+        let reference: Reference = full_path.parse()?;
+        let layer = ImageLayer::new(data, media_type.to_string(), None);
+        let config = Config {
+            data: b"{}".to_vec(),
+            media_type: "application/vnd.oci.empty.v1+json".to_string(),
+            annotations: None,
+        };
+        client
+            .push(&reference, &[layer], config, auth, None)
+            .await?;
+        println!("✓ Pushed {} to: {}", suffix, full_path);
+        Ok(())
+    }
 
-        let artifact = client.pull(&reference, &auth, vec![]).await?;
+    /// Pulls the module artifact at `reference`, such as `ghcr.io/org/modules:s3bucket-0.1.4` or
+    /// `ghcr.io/org/modules@sha256:<hex>`, and checks the zip against the digest recorded for the
+    /// module when it was published
+    pub async fn pull_module(&self, reference: &str) -> anyhow::Result<PulledModule> {
+        let reference: Reference = reference.parse()?;
+        let (client, auth) = self.get_client_auth(reference.registry()).await?;
 
-        let config_bytes = &artifact.config.data;
-        let config = serde_json::from_slice::<serde_json::Value>(config_bytes)?;
-        let module: ModuleResp = serde_json::from_value(config["module"].clone())?;
+        // Pull by digest, so the artifact can't change between resolving the tag and pulling it
+        let manifest_digest = client.fetch_manifest_digest(&reference, &auth).await?;
+        if let Some(pinned_digest) = reference.digest() {
+            if pinned_digest != manifest_digest {
+                return Err(anyhow::anyhow!(
+                    "Artifact {} has digest {}, expected {}",
+                    reference,
+                    manifest_digest,
+                    pinned_digest
+                ));
+            }
+        }
+        let pinned = reference.clone_with_digest(manifest_digest.clone());
+        println!("Pulling from: {}", pinned);
 
-        println!("Extracted module: {:?}", module);
+        let artifact = client.pull(&pinned, &auth, vec![MODULE_MEDIA_TYPE]).await?;
+        let config = serde_json::from_slice::<serde_json::Value>(&artifact.config.data)?;
+        let module: ModuleResp = serde_json::from_value(config["module"].clone())
+            .map_err(|e| anyhow::anyhow!("Artifact {} is not a module: {}", pinned, e))?;
+        let zip = artifact
+            .layers
+            .iter()
+            .find(|layer| layer.media_type == MODULE_MEDIA_TYPE)
+            .map(|layer| layer.data.to_vec())
+            .ok_or_else(|| anyhow::anyhow!("Artifact {} has no module zip", pinned))?;
 
-        let zip_bytes = &artifact.layers[0].data;
-        let base64_zip = base64.encode(zip_bytes);
-        println!("Base64 zip: {}", base64_zip);
+        if let Some(digest) = &module.digest {
+            if artifact_digest(&zip) != *digest {
+                return Err(anyhow::anyhow!(
+                    "Module zip of {} does not match its recorded digest {}",
+                    pinned,
+                    digest
+                ));
+            }
+        }
 
-        Ok(module.clone())
+        Ok(PulledModule {
+            module,
+            zip,
+            reference: format!(
+                "{}/{}@{}",
+                pinned.registry(),
+                pinned.repository(),
+                manifest_digest
+            ),
+        })
     }
 
-    fn get_client_auth(&self) -> (Client, RegistryAuth) {
+    async fn get_client_auth(&self, host: &str) -> anyhow::Result<(Client, RegistryAuth)> {
         let protocol = if std::env::var("OCI_REGISTRY_ALLOW_HTTP").is_ok() {
             oci_client::client::ClientProtocol::Http
         } else {
@@ -131,15 +257,124 @@ impl OCIRegistryProvider {
             ..Default::default()
         };
         let client = Client::new(config);
-        let auth = match &self.username {
-            None => RegistryAuth::Anonymous,
-            Some(username) => {
-                if self.password.is_none() || self.password.as_ref().unwrap().is_empty() {
-                    panic!("Password is required for authenticated push");
-                }
-                RegistryAuth::Basic(username.clone(), self.password.clone().unwrap())
+        let auth = self.get_registry_auth(host).await?;
+        Ok((client, auth))
+    }
+
+    /// Credentials for the registry at `host`, in order: the configured username and password, a
+    /// token of the cloud provider for ECR and ACR, the docker config, and otherwise anonymous
+    async fn get_registry_auth(&self, host: &str) -> anyhow::Result<RegistryAuth> {
+        if let Some(username) = &self.username {
+            let password = self
+                .password
+                .clone()
+                .filter(|password| !password.is_empty())
+                .ok_or_else(|| {
+                    anyhow::anyhow!("OCI_REGISTRY_PASSWORD is required with OCI_REGISTRY_USERNAME")
+                })?;
+            return Ok(RegistryAuth::Basic(username.clone(), password));
+        }
+        let credentials = match RegistryKind::of(host) {
+            RegistryKind::Ecr { region } => Some(env_aws::get_ecr_credentials(&region).await?),
+            RegistryKind::Acr => Some(env_azure::get_acr_credentials(host).await?),
+            RegistryKind::Other => {
+                read_docker_config().and_then(|config| docker_config_credentials(&config, host))
             }
         };
-        (client, auth)
+        Ok(match credentials {
+            Some((username, password)) => RegistryAuth::Basic(username, password),
+            None => RegistryAuth::Anonymous,
+        })
+    }
+}
+
+/// `config.json` in `DOCKER_CONFIG`, or in `~/.docker` if not set
+fn read_docker_config() -> Option<serde_json::Value> {
+    let directory = match std::env::var("DOCKER_CONFIG") {
+        Ok(directory) => std::path::PathBuf::from(directory),
+        Err(_) => std::path::PathBuf::from(std::env::var("HOME").ok()?).join(".docker"),
+    };
+    let content = std::fs::read_to_string(directory.join("config.json")).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Username and password stored for `host` in `auths` of a docker config, as written by
+/// `docker login`. Credential helpers are not supported
+fn docker_config_credentials(config: &serde_json::Value, host: &str) -> Option<(String, String)> {
+    let auths = config.get("auths")?.as_object()?;
+    let entry = auths.iter().find_map(|(key, entry)| {
+        let key_host = key
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .split('/')
+            .next()
+            .unwrap_or_default();
+        let matches = key_host == host
+            || (key == DOCKER_HUB_CONFIG_KEY && (host == "docker.io" || host == "index.docker.io"));
+        matches.then_some(entry)
+    })?;
+
+    if let Some(auth) = entry.get("auth").and_then(|auth| auth.as_str()) {
+        let decoded = String::from_utf8(base64.decode(auth).ok()?).ok()?;
+        let (username, password) = decoded.split_once(':')?;
+        return Some((username.to_string(), password.to_string()));
+    }
+    Some((
+        entry.get("username")?.as_str()?.to_string(),
+        entry.get("password")?.as_str()?.to_string(),
+    ))
+}
+
+fn provenance_enabled() -> bool {
+    std::env::var("OCI_REGISTRY_PROVENANCE")
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_registry_kind() {
+        assert_eq!(
+            RegistryKind::of("123456789012.dkr.ecr.eu-west-1.amazonaws.com"),
+            RegistryKind::Ecr {
+                region: "eu-west-1".to_string()
+            }
+        );
+        assert_eq!(
+            RegistryKind::of("123456789012.dkr.ecr.cn-north-1.amazonaws.com.cn"),
+            RegistryKind::Ecr {
+                region: "cn-north-1".to_string()
+            }
+        );
+        assert_eq!(RegistryKind::of("infraweave.azurecr.io"), RegistryKind::Acr);
+        assert_eq!(RegistryKind::of("ghcr.io"), RegistryKind::Other);
+    }
+
+    #[test]
+    fn test_docker_config_credentials() {
+        let config = serde_json::json!({
+            "auths": {
+                "ghcr.io": { "auth": base64.encode("octocat:ghp_token") },
+                "https://registry.example.com/v2/": { "username": "user", "password": "secret" },
+                "https://index.docker.io/v1/": { "auth": base64.encode("hubuser:hubtoken") },
+            }
+        });
+        assert_eq!(
+            docker_config_credentials(&config, "ghcr.io"),
+            Some(("octocat".to_string(), "ghp_token".to_string()))
+        );
+        assert_eq!(
+            docker_config_credentials(&config, "registry.example.com"),
+            Some(("user".to_string(), "secret".to_string()))
+        );
+        assert_eq!(
+            docker_config_credentials(&config, "docker.io"),
+            Some(("hubuser".to_string(), "hubtoken".to_string()))
+        );
+        assert_eq!(docker_config_credentials(&config, "quay.io"), None);
     }
 }
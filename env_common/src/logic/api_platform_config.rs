@@ -0,0 +1,392 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use env_defs::{
+    get_policy_identifier, CloudProvider, PlatformConfigManifest, PolicyManifest, PolicyResp,
+    ProjectData,
+};
+use serde_json::{json, Value};
+
+use crate::interface::GenericCloudHandler;
+use crate::logic::api_policy::publish_policy;
+
+const CONFIG_TABLE: &str = "config";
+const POLICY_TABLE: &str = "policies";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigAction {
+    Create,
+    Update,
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ConfigTarget {
+    Record {
+        pk: String,
+        sk: String,
+    },
+    Policy {
+        environment: String,
+        path: Option<PathBuf>,
+    },
+}
+
+/// A difference between the platform configuration file and the current state
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    /// Kind of object, `project` or `policy`
+    pub kind: &'static str,
+    pub name: String,
+    pub action: ConfigAction,
+    pub current: Option<Value>,
+    pub desired: Option<Value>,
+    target: ConfigTarget,
+}
+
+struct RecordKind {
+    kind: &'static str,
+    pk: &'static str,
+    sk_prefix: &'static str,
+    /// Brings a stored record to the form the configuration file serializes to, so that fields
+    /// added with defaults after the record was written do not show up as changes
+    normalize: fn(Value) -> Value,
+}
+
+const PROJECTS: RecordKind = RecordKind {
+    kind: "project",
+    pk: "PROJECTS",
+    sk_prefix: "PROJECT#",
+    normalize: normalize_project,
+};
+
+pub fn read_platform_config(path: &str) -> Result<PlatformConfigManifest, anyhow::Error> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read platform config {}", path))?;
+    let manifest: PlatformConfigManifest = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse platform config {}", path))?;
+    if manifest.kind != "PlatformConfig" {
+        return Err(anyhow!(
+            "Expected kind PlatformConfig in {}, found {}",
+            path,
+            manifest.kind
+        ));
+    }
+    Ok(manifest)
+}
+
+/// Compares the platform configuration with the current state. Projects are read using
+/// `central_handler`, policies using `handler`. Sections missing from the configuration are not
+/// compared, so they are never planned for deletion. `base_dir` is the directory the policy paths
+/// in the configuration are relative to.
+pub async fn plan_platform_config(
+    handler: &GenericCloudHandler,
+    central_handler: &GenericCloudHandler,
+    manifest: &PlatformConfigManifest,
+    base_dir: &Path,
+) -> Result<Vec<ConfigChange>, anyhow::Error> {
+    let spec = &manifest.spec;
+    let mut changes = vec![];
+
+    if let Some(projects) = &spec.projects {
+        let desired = projects
+            .iter()
+            .map(|p| Ok((p.project_id.clone(), serde_json::to_value(p)?)))
+            .collect::<Result<Vec<_>, serde_json::Error>>()?;
+        let current = central_handler.get_config_items(PROJECTS.pk).await?;
+        changes.extend(diff_records(&PROJECTS, current, desired)?);
+    }
+
+    for bundle in spec.policy_bundles.iter().flatten() {
+        let mut desired = vec![];
+        for policy_path in &bundle.policies {
+            let path = base_dir.join(policy_path);
+            let policy_yaml_path = path.join("policy.yaml");
+            let content = std::fs::read_to_string(&policy_yaml_path).with_context(|| {
+                format!("Failed to read {}", policy_yaml_path.to_string_lossy())
+            })?;
+            let policy: PolicyManifest = serde_yaml::from_str(&content).with_context(|| {
+                format!("Failed to parse {}", policy_yaml_path.to_string_lossy())
+            })?;
+            desired.push((path, policy));
+        }
+        let current = handler
+            .get_all_policies(&bundle.environment)
+            .await?
+            .into_iter()
+            .filter(|p| p.environment == bundle.environment)
+            .collect();
+        changes.extend(diff_policies(&bundle.environment, current, desired));
+    }
+
+    Ok(changes)
+}
+
+/// Applies the changes from [plan_platform_config] with the same handlers
+pub async fn apply_platform_config(
+    handler: &GenericCloudHandler,
+    central_handler: &GenericCloudHandler,
+    changes: &[ConfigChange],
+) -> Result<(), anyhow::Error> {
+    for change in changes {
+        match &change.target {
+            ConfigTarget::Record { pk, sk } => {
+                let item = match (change.action, &change.desired) {
+                    (ConfigAction::Delete, _) => json!({
+                        "Delete": {
+                            "TableName": CONFIG_TABLE,
+                            "Key": { "PK": pk, "SK": sk }
+                        }
+                    }),
+                    (_, Some(desired)) => {
+                        let mut record = json!({ "PK": pk, "SK": sk });
+                        env_utils::merge_json_dicts(&mut record, desired);
+                        json!({
+                            "Put": {
+                                "TableName": CONFIG_TABLE,
+                                "Item": record
+                            }
+                        })
+                    }
+                    (_, None) => unreachable!("created and updated records have a desired state"),
+                };
+                transact_write(central_handler, vec![item]).await?;
+            }
+            ConfigTarget::Policy {
+                environment,
+                path: Some(path),
+            } => {
+                publish_policy(handler, &path.to_string_lossy(), environment).await?;
+            }
+            ConfigTarget::Policy {
+                environment,
+                path: None,
+            } => {
+                let item = json!({
+                    "Delete": {
+                        "TableName": POLICY_TABLE,
+                        "Key": {
+                            "PK": "CURRENT",
+                            "SK": format!("POLICY#{}", get_policy_identifier(&change.name, environment)),
+                        }
+                    }
+                });
+                for region in handler.get_all_regions().await? {
+                    let region_handler = handler.copy_with_region(&region).await;
+                    transact_write(&region_handler, vec![item.clone()]).await?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn transact_write(
+    handler: &GenericCloudHandler,
+    items: Vec<Value>,
+) -> Result<(), anyhow::Error> {
+    let payload = env_defs::transact_write_event(&Value::Array(items));
+    match handler.run_function(&payload).await {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Failed to write platform config: {}", e)),
+    }
+}
+
+/// Stored projects lack the fields added after they were written, e.g. `settings`
+fn normalize_project(record: Value) -> Value {
+    serde_json::from_value::<ProjectData>(record.clone())
+        .and_then(|project| serde_json::to_value(project))
+        .unwrap_or(record)
+}
+
+fn diff_records(
+    record_kind: &RecordKind,
+    current: Vec<Value>,
+    desired: Vec<(String, Value)>,
+) -> Result<Vec<ConfigChange>, anyhow::Error> {
+    let mut current_by_name = BTreeMap::new();
+    for mut item in current {
+        let name = item
+            .get("SK")
+            .and_then(|sk| sk.as_str())
+            .and_then(|sk| sk.strip_prefix(record_kind.sk_prefix))
+            .ok_or_else(|| anyhow!("Unexpected {} record: {}", record_kind.kind, item))?
+            .to_string();
+        if let Some(object) = item.as_object_mut() {
+            object.remove("PK");
+            object.remove("SK");
+        }
+        current_by_name.insert(name, (record_kind.normalize)(item));
+    }
+
+    let target = |name: &str| ConfigTarget::Record {
+        pk: record_kind.pk.to_string(),
+        sk: format!("{}{}", record_kind.sk_prefix, name),
+    };
+
+    let mut changes = vec![];
+    let mut seen = BTreeSet::new();
+    for (name, desired) in desired {
+        if !seen.insert(name.clone()) {
+            return Err(anyhow!("Duplicate {} {}", record_kind.kind, name));
+        }
+        let action = match current_by_name.remove(&name) {
+            None => Some((ConfigAction::Create, None)),
+            Some(current) if current != desired => Some((ConfigAction::Update, Some(current))),
+            Some(_) => None,
+        };
+        if let Some((action, current)) = action {
+            changes.push(ConfigChange {
+                kind: record_kind.kind,
+                target: target(&name),
+                name,
+                action,
+                current,
+                desired: Some(desired),
+            });
+        }
+    }
+    for (name, current) in current_by_name {
+        changes.push(ConfigChange {
+            kind: record_kind.kind,
+            target: target(&name),
+            name,
+            action: ConfigAction::Delete,
+            current: Some(current),
+            desired: None,
+        });
+    }
+    Ok(changes)
+}
+
+fn diff_policies(
+    environment: &str,
+    current: Vec<PolicyResp>,
+    desired: Vec<(PathBuf, PolicyManifest)>,
+) -> Vec<ConfigChange> {
+    let mut current_by_name: BTreeMap<String, PolicyResp> =
+        current.into_iter().map(|p| (p.policy.clone(), p)).collect();
+
+    let mut changes = vec![];
+    for (path, policy) in desired {
+        let name = policy.metadata.name.clone();
+        let desired_state = json!({ "environment": environment, "version": policy.spec.version });
+        let action = match current_by_name.remove(&name) {
+            None => Some((ConfigAction::Create, None)),
+            Some(current) if current.version != policy.spec.version => Some((
+                ConfigAction::Update,
+                Some(json!({ "environment": environment, "version": current.version })),
+            )),
+            Some(_) => None,
+        };
+        if let Some((action, current)) = action {
+            changes.push(ConfigChange {
+                kind: "policy",
+                name,
+                action,
+                current,
+                desired: Some(desired_state),
+                target: ConfigTarget::Policy {
+                    environment: environment.to_string(),
+                    path: Some(path),
+                },
+            });
+        }
+    }
+    for (name, current) in current_by_name {
+        changes.push(ConfigChange {
+            kind: "policy",
+            name,
+            action: ConfigAction::Delete,
+            current: Some(json!({ "environment": environment, "version": current.version })),
+            desired: None,
+            target: ConfigTarget::Policy {
+                environment: environment.to_string(),
+                path: None,
+            },
+        });
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn project(project_id: &str, description: &str) -> Value {
+        serde_json::to_value(ProjectData {
+            project_id: project_id.to_string(),
+            name: project_id.to_string(),
+            description: description.to_string(),
+            regions: vec!["eu-west-1".to_string()],
+            repositories: vec![],
+            settings: Default::default(),
+        })
+        .unwrap()
+    }
+
+    fn record(project_id: &str, description: &str) -> Value {
+        let mut record = json!({"PK": "PROJECTS", "SK": format!("PROJECT#{}", project_id)});
+        env_utils::merge_json_dicts(&mut record, &project(project_id, description));
+        record
+    }
+
+    #[test]
+    fn test_diff_records() {
+        let current = vec![
+            record("stable", ""),
+            record("beta", "old"),
+            record("alpha", ""),
+        ];
+        let desired = vec![
+            ("stable".to_string(), project("stable", "")),
+            ("beta".to_string(), project("beta", "new")),
+            ("dev".to_string(), project("dev", "")),
+        ];
+
+        let changes = diff_records(&PROJECTS, current, desired).unwrap();
+        let summary: Vec<(&str, ConfigAction)> = changes
+            .iter()
+            .map(|c| (c.name.as_str(), c.action))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("beta", ConfigAction::Update),
+                ("dev", ConfigAction::Create),
+                ("alpha", ConfigAction::Delete),
+            ]
+        );
+        assert_eq!(
+            changes[2].target,
+            ConfigTarget::Record {
+                pk: "PROJECTS".to_string(),
+                sk: "PROJECT#alpha".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_diff_records_ignores_missing_project_settings() {
+        let mut current = record("dev", "");
+        current.as_object_mut().unwrap().remove("settings");
+        let desired = vec![("dev".to_string(), project("dev", ""))];
+
+        assert_eq!(
+            diff_records(&PROJECTS, vec![current], desired).unwrap(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_diff_records_rejects_duplicates() {
+        let desired = vec![
+            ("dev".to_string(), project("dev", "")),
+            ("dev".to_string(), project("dev", "")),
+        ];
+        assert!(diff_records(&PROJECTS, vec![], desired).is_err());
+    }
+}
@@ -3,7 +3,8 @@ use base64::Engine;
 use std::path::Path;
 
 use env_defs::{
-    get_policy_identifier, CloudProvider, GenericFunctionResponse, PolicyManifest, PolicyResp,
+    get_policy_identifier, CloudProvider, GenericFunctionResponse, PolicyManifest,
+    PolicyPackManifest, PolicyPackResp, PolicyResp,
 };
 use env_utils::{
     get_timestamp, merge_json_dicts, semver_parse, validate_policy_schema, zero_pad_semver,
@@ -142,6 +143,247 @@ pub async fn publish_policy(
     Ok(())
 }
 
+pub async fn publish_policy_pack(
+    handler: &GenericCloudHandler,
+    manifest_path: &str,
+    environment: &str,
+) -> anyhow::Result<(), anyhow::Error> {
+    let manifest_file = Path::new(&manifest_path).join("policypack.yaml");
+    let manifest = std::fs::read_to_string(&manifest_file)
+        .map_err(|e| anyhow::anyhow!("Failed to read policy pack manifest file: {}", e))?;
+    let manifest: PolicyPackManifest = serde_yaml::from_str(&manifest)
+        .map_err(|e| anyhow::anyhow!("Failed to parse policy pack manifest: {}", e))?;
+
+    if manifest.kind != "PolicyPack" {
+        return Err(anyhow::anyhow!(
+            "Expected kind PolicyPack in policy pack manifest, found {}",
+            manifest.kind
+        ));
+    }
+    let manifest_version = semver_parse(&manifest.spec.version)?;
+    if manifest.spec.policies.is_empty() {
+        return Err(anyhow::anyhow!("Policy pack does not contain any policies"));
+    }
+
+    // The policies of the pack must be published to the environment beforehand
+    for policy in &manifest.spec.policies {
+        let found = match &policy.version {
+            Some(version) => handler.get_policy(&policy.name, environment, version).await,
+            None => {
+                handler
+                    .get_newest_policy_version(&policy.name, environment)
+                    .await
+            }
+        };
+        if found.is_err() {
+            return Err(anyhow::anyhow!(
+                "Policy {} {}is not published in environment {}",
+                policy.name,
+                policy
+                    .version
+                    .as_ref()
+                    .map(|version| format!("version {} ", version))
+                    .unwrap_or_default(),
+                environment
+            ));
+        }
+    }
+
+    let policy_pack = PolicyPackResp {
+        environment: environment.to_string(),
+        environment_version: format!(
+            "{}#{}",
+            environment,
+            zero_pad_semver(&manifest.spec.version, 3)?
+        ),
+        version: manifest.spec.version.clone(),
+        timestamp: get_timestamp(),
+        policy_pack: manifest.metadata.name.clone(),
+        pack_name: manifest.spec.pack_name.clone(),
+        description: manifest.spec.description.clone(),
+        policies: manifest.spec.policies.clone(),
+        assignments: manifest.spec.assignments.clone(),
+    };
+
+    if let Ok(latest) = handler
+        .get_newest_policy_pack_version(&policy_pack.policy_pack, environment)
+        .await
+    {
+        let latest_version = semver_parse(&latest.version)?;
+        if manifest_version <= latest_version {
+            return Err(anyhow::anyhow!(
+                "Policy pack version {} is not newer than the latest version {} in environment {}",
+                manifest_version,
+                latest_version,
+                environment
+            ));
+        }
+    }
+
+    let all_regions = handler.get_all_regions().await?;
+    println!("Publishing policy pack to all regions...");
+    for region in all_regions.iter() {
+        let region_handler = handler.copy_with_region(region).await;
+        insert_policy_pack(&region_handler, &policy_pack)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!("Failed to insert policy pack in region {}: {}", region, e)
+            })?;
+        println!(
+            "Successfully published policy pack {} in region {}",
+            policy_pack.policy_pack, region
+        );
+    }
+
+    println!(
+        "Publishing version {} of policy pack {} completed in all regions",
+        policy_pack.version, policy_pack.policy_pack
+    );
+
+    Ok(())
+}
+
+/// Policies to evaluate for a deployment, each with the policy pack it is evaluated for.
+/// Policies that are not part of any pack apply to all deployments, while the policies of a
+/// pack only apply to the deployments the pack is assigned to.
+pub async fn get_applicable_policies(
+    handler: &GenericCloudHandler,
+    policy_environment: &str,
+    project_id: &str,
+    environment: &str,
+    track: &str,
+) -> Result<Vec<(PolicyResp, Option<String>)>, anyhow::Error> {
+    let policies = handler.get_all_policies(policy_environment).await?;
+    let policy_packs = handler.get_all_policy_packs(policy_environment).await?;
+
+    let mut applicable = select_policies(&policies, &policy_packs, project_id, environment, track);
+    // Policies pinned to another version than the newest are read separately
+    for (pack, policy_name, version) in
+        pinned_policy_versions(&policies, &policy_packs, project_id, environment, track)
+    {
+        let policy = handler
+            .get_policy(&policy_name, policy_environment, &version)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Policy {} version {} of policy pack {} not found: {}",
+                    policy_name,
+                    version,
+                    pack,
+                    e
+                )
+            })?;
+        applicable.push((policy, Some(pack)));
+    }
+    Ok(applicable)
+}
+
+fn applicable_packs<'a>(
+    policy_packs: &'a [PolicyPackResp],
+    project_id: &'a str,
+    environment: &'a str,
+    track: &'a str,
+) -> impl Iterator<Item = &'a PolicyPackResp> {
+    policy_packs
+        .iter()
+        .filter(move |pack| pack.applies_to(project_id, environment, track))
+}
+
+/// Newest versions of the policies that apply to a deployment
+fn select_policies(
+    policies: &[PolicyResp],
+    policy_packs: &[PolicyPackResp],
+    project_id: &str,
+    environment: &str,
+    track: &str,
+) -> Vec<(PolicyResp, Option<String>)> {
+    let mut selected: Vec<(PolicyResp, Option<String>)> = policies
+        .iter()
+        .filter(|policy| {
+            !policy_packs
+                .iter()
+                .flat_map(|pack| &pack.policies)
+                .any(|p| p.name == policy.policy)
+        })
+        .map(|policy| (policy.clone(), None))
+        .collect();
+
+    for pack in applicable_packs(policy_packs, project_id, environment, track) {
+        for pack_policy in &pack.policies {
+            let newest = policies.iter().find(|p| {
+                p.policy == pack_policy.name
+                    && pack_policy.version.as_ref().is_none_or(|v| *v == p.version)
+            });
+            if let Some(policy) = newest {
+                selected.push((policy.clone(), Some(pack.policy_pack.clone())));
+            }
+        }
+    }
+    selected
+}
+
+/// `(pack, policy, version)` of the policies of applicable packs that are pinned to another
+/// version than the newest
+fn pinned_policy_versions(
+    policies: &[PolicyResp],
+    policy_packs: &[PolicyPackResp],
+    project_id: &str,
+    environment: &str,
+    track: &str,
+) -> Vec<(String, String, String)> {
+    applicable_packs(policy_packs, project_id, environment, track)
+        .flat_map(|pack| {
+            pack.policies.iter().filter_map(|pack_policy| {
+                let version = pack_policy.version.as_ref()?;
+                let is_newest = policies
+                    .iter()
+                    .any(|p| p.policy == pack_policy.name && p.version == *version);
+                (!is_newest).then(|| {
+                    (
+                        pack.policy_pack.clone(),
+                        pack_policy.name.clone(),
+                        version.clone(),
+                    )
+                })
+            })
+        })
+        .collect()
+}
+
+async fn insert_policy_pack<T: CloudProvider>(
+    handler: &T,
+    policy_pack: &PolicyPackResp,
+) -> anyhow::Result<()> {
+    let id = format!(
+        "POLICYPACK#{}",
+        get_policy_identifier(&policy_pack.policy_pack, &policy_pack.environment)
+    );
+    let policy_pack_value = serde_json::to_value(policy_pack)?;
+
+    let mut version_payload = serde_json::json!({
+        "PK": id.clone(),
+        "SK": format!("VERSION#{}", zero_pad_semver(&policy_pack.version, 3)?),
+    });
+    merge_json_dicts(&mut version_payload, &policy_pack_value);
+
+    let mut current_payload = serde_json::json!({
+        "PK": "CURRENT",
+        "SK": id,
+    });
+    merge_json_dicts(&mut current_payload, &policy_pack_value);
+
+    let transaction_items = serde_json::json!([
+        { "Put": { "TableName": "policies", "Item": version_payload } },
+        { "Put": { "TableName": "policies", "Item": current_payload } },
+    ]);
+    let payload = env_defs::transact_write_event(&transaction_items);
+
+    match handler.run_function(&payload).await {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow::anyhow!("Failed to insert policy pack: {}", e)),
+    }
+}
+
 async fn upload_file_base64<T: CloudProvider>(
     handler: &T,
     key: &String,
@@ -214,3 +456,118 @@ async fn insert_policy<T: CloudProvider>(
         Err(e) => Err(anyhow::anyhow!("Failed to insert policy: {}", e)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(name: &str, version: &str) -> PolicyResp {
+        serde_json::from_value(serde_json::json!({
+            "environment": "stable",
+            "environment_version": "",
+            "version": version,
+            "timestamp": "",
+            "policy_name": name,
+            "policy": name,
+            "description": "",
+            "reference": "",
+            "data": {},
+            "manifest": {
+                "metadata": { "name": name },
+                "apiVersion": "infraweave.io/v1",
+                "kind": "Policy",
+                "spec": {
+                    "policyName": name,
+                    "version": version,
+                    "description": "",
+                    "reference": "",
+                    "data": {}
+                }
+            },
+            "s3_key": "",
+        }))
+        .unwrap()
+    }
+
+    fn pack(name: &str, policies: serde_json::Value, environments: &[&str]) -> PolicyPackResp {
+        serde_json::from_value(serde_json::json!({
+            "environment": "stable",
+            "environment_version": "",
+            "version": "1.0.0",
+            "timestamp": "",
+            "policy_pack": name,
+            "pack_name": name,
+            "description": "",
+            "policies": policies,
+            "assignments": [{ "environments": environments }],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_select_policies_by_pack_assignment() {
+        let policies = vec![
+            policy("region", "1.0.0"),
+            policy("encryption", "2.0.0"),
+            policy("tagging", "1.1.0"),
+        ];
+        let packs = vec![
+            pack(
+                "pci",
+                serde_json::json!([{ "name": "encryption" }, { "name": "tagging", "version": "1.0.0" }]),
+                &["prod*"],
+            ),
+            pack(
+                "sandbox",
+                serde_json::json!([{ "name": "tagging" }]),
+                &["dev"],
+            ),
+        ];
+
+        let names = |selected: Vec<(PolicyResp, Option<String>)>| -> Vec<(String, Option<String>)> {
+            selected
+                .into_iter()
+                .map(|(policy, pack)| (policy.policy, pack))
+                .collect()
+        };
+
+        // Policies outside of packs apply everywhere, packed policies only where assigned
+        assert_eq!(
+            names(select_policies(
+                &policies, &packs, "123", "cli/prod", "stable"
+            )),
+            vec![
+                ("region".to_string(), None),
+                ("encryption".to_string(), Some("pci".to_string())),
+            ]
+        );
+        assert_eq!(
+            pinned_policy_versions(&policies, &packs, "123", "cli/prod", "stable"),
+            vec![(
+                "pci".to_string(),
+                "tagging".to_string(),
+                "1.0.0".to_string()
+            )]
+        );
+        assert_eq!(
+            names(select_policies(
+                &policies, &packs, "123", "cli/dev", "stable"
+            )),
+            vec![
+                ("region".to_string(), None),
+                ("tagging".to_string(), Some("sandbox".to_string())),
+            ]
+        );
+        assert!(pinned_policy_versions(&policies, &packs, "123", "cli/dev", "stable").is_empty());
+        assert_eq!(
+            names(select_policies(
+                &policies,
+                &packs,
+                "123",
+                "cli/staging",
+                "stable"
+            )),
+            vec![("region".to_string(), None)]
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use std::path::{Path, PathBuf};
+
+use env_defs::{PolicyManifest, PolicyTarget};
+use env_utils::merge_json_dicts;
+use regex::Regex;
+use serde_json::{json, Value};
+
+use super::api_claim_policy::evaluate_policy;
+
+/// Fixtures ending with this are expected to be denied by the policy
+pub const DENY_FIXTURE_SUFFIX: &str = ".deny.json";
+
+/// Result of a rego test rule, or of evaluating the policy against a fixture
+#[derive(Debug, Clone)]
+pub struct PolicyTestResult {
+    /// `data.<package>.<rule>` of a test rule, or the file name of a fixture
+    pub name: String,
+    pub passed: bool,
+    /// Violations found in a fixture, or why a test rule could not be evaluated
+    pub details: Option<String>,
+}
+
+/// Environment variables plan policies see below `data.env`, as stored by the runner
+pub fn policy_env_data() -> Value {
+    json!({
+        "env": {
+            "AWS_DEFAULT_REGION": std::env::var("AWS_DEFAULT_REGION").unwrap_or_default(),
+            "AWS_REGION": std::env::var("AWS_REGION").unwrap_or_default(),
+        }
+    })
+}
+
+/// Runs the policy in `policy_dir` locally, with the data document it is evaluated with once
+/// published: first every `test_` rule of its rego files, then every JSON fixture in
+/// `fixtures_dir` as input, a Terraform plan (`terraform show -json`) or a claim depending on the
+/// target of the policy. A fixture passes when no package denies it, or when one does if its
+/// name ends with `.deny.json`
+pub fn run_policy_tests(
+    policy_dir: &Path,
+    fixtures_dir: Option<&Path>,
+) -> Result<Vec<PolicyTestResult>, anyhow::Error> {
+    let manifest_path = policy_dir.join("policy.yaml");
+    let manifest = std::fs::read_to_string(&manifest_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", manifest_path.display(), e))?;
+    let manifest: PolicyManifest = serde_yaml::from_str(&manifest)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", manifest_path.display(), e))?;
+
+    let mut data = manifest.spec.data.clone();
+    if manifest.spec.target == PolicyTarget::Plan {
+        let mut env_data = policy_env_data();
+        merge_json_dicts(&mut env_data, &data);
+        data = env_data;
+    }
+
+    let rego_files = files_with_extension(policy_dir, "rego")?
+        .into_iter()
+        .map(|path| Ok((path.display().to_string(), std::fs::read_to_string(&path)?)))
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+    if rego_files.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No rego files found in {}",
+            policy_dir.display()
+        ));
+    }
+
+    let mut engine = regorus::Engine::new();
+    for (name, rego) in &rego_files {
+        engine.add_policy(name.clone(), rego.clone())?;
+    }
+    engine.add_data(regorus::Value::from_json_str(&data.to_string())?)?;
+
+    let mut results = vec![];
+    for rule in test_rules(&rego_files) {
+        let result = match engine.eval_query(rule.clone(), false) {
+            // A test fails when its body is false or undefined
+            Ok(query_results) => PolicyTestResult {
+                passed: query_results
+                    .result
+                    .first()
+                    .and_then(|result| result.expressions.first())
+                    .is_some_and(|expression| expression.value == regorus::Value::Bool(true)),
+                name: rule,
+                details: None,
+            },
+            Err(e) => PolicyTestResult {
+                name: rule,
+                passed: false,
+                details: Some(e.to_string()),
+            },
+        };
+        results.push(result);
+    }
+
+    if let Some(fixtures_dir) = fixtures_dir {
+        for fixture in files_with_extension(fixtures_dir, "json")? {
+            let name = fixture
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let input: Value = serde_json::from_str(&std::fs::read_to_string(&fixture)?)
+                .map_err(|e| anyhow::anyhow!("Fixture {} is not valid JSON: {}", name, e))?;
+            let violations = evaluate_policy(&rego_files, &data, &input)?;
+            let denied = violations.as_object().is_some_and(|v| !v.is_empty());
+            results.push(PolicyTestResult {
+                passed: denied == name.ends_with(DENY_FIXTURE_SUFFIX),
+                details: denied.then(|| violations.to_string()),
+                name,
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+// Files in `dir` with the extension, sorted by name. Like the runner, subdirectories are not read
+fn files_with_extension(dir: &Path, extension: &str) -> Result<Vec<PathBuf>, anyhow::Error> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|e| e == extension))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+// Queries of the `test_` rules of every package, in the order they are defined
+fn test_rules(rego_files: &[(String, String)]) -> Vec<String> {
+    let package_re = Regex::new(r"(?m)^package\s+([\w.]+)").unwrap();
+    let rule_re = Regex::new(r"(?m)^(test_\w+)").unwrap();
+    let mut rules = vec![];
+    for (_, rego) in rego_files {
+        let Some(package) = package_re.captures(rego) else {
+            continue;
+        };
+        for caps in rule_re.captures_iter(rego) {
+            let rule = format!("data.{}.{}", &package[1], &caps[1]);
+            if !rules.contains(&rule) {
+                rules.push(rule);
+            }
+        }
+    }
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: &str = r#"
+apiVersion: infraweave.io/v1
+kind: Policy
+metadata:
+  name: regions
+spec:
+  policyName: regions
+  version: 0.1.0
+  description: Only allows resources in approved regions
+  reference: https://github.com/infraweave-io/policies
+  data:
+    allowed_regions: ["eu-west-1"]
+"#;
+
+    const REGO: &str = r#"
+package infraweave.regions
+
+import rego.v1
+
+deny contains msg if {
+    some change in input.resource_changes
+    region := change.change.after.region
+    not region in data.allowed_regions
+    # Not sprintf, which in regorus loses the space following a verb
+    msg := concat(" ", [change.address, "is in", region])
+}
+
+test_allowed_region if {
+    count(deny) == 0 with input as {"resource_changes": [{"address": "a", "change": {"after": {"region": "eu-west-1"}}}]}
+}
+
+test_other_region if {
+    count(deny) == 0 with input as {"resource_changes": [{"address": "a", "change": {"after": {"region": "us-east-1"}}}]}
+}
+"#;
+
+    fn plan(region: &str) -> String {
+        json!({
+            "resource_changes": [
+                {"address": "aws_s3_bucket.this", "change": {"after": {"region": region}}}
+            ]
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_run_policy_tests() {
+        let dir = env_utils::tempdir().unwrap();
+        let fixtures = dir.path().join("fixtures");
+        std::fs::create_dir(&fixtures).unwrap();
+        std::fs::write(dir.path().join("policy.yaml"), POLICY).unwrap();
+        std::fs::write(dir.path().join("regions.rego"), REGO).unwrap();
+        std::fs::write(fixtures.join("allowed.json"), plan("eu-west-1")).unwrap();
+        std::fs::write(fixtures.join("other.deny.json"), plan("us-east-1")).unwrap();
+        std::fs::write(fixtures.join("wrong.json"), plan("us-east-1")).unwrap();
+
+        let results = run_policy_tests(dir.path(), Some(&fixtures)).unwrap();
+        let outcomes: Vec<(&str, bool)> = results
+            .iter()
+            .map(|result| (result.name.as_str(), result.passed))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("data.infraweave.regions.test_allowed_region", true),
+                ("data.infraweave.regions.test_other_region", false),
+                ("allowed.json", true),
+                ("other.deny.json", true),
+                ("wrong.json", false),
+            ]
+        );
+        assert!(results[4]
+            .details
+            .as_ref()
+            .unwrap()
+            .contains("aws_s3_bucket.this is in us-east-1"));
+    }
+
+    #[test]
+    fn test_test_rules() {
+        let rego_files = vec![
+            ("a.rego".to_string(), REGO.to_string()),
+            (
+                "b.rego".to_string(),
+                "package infraweave.other\n\ntest_x if { true }\ntest_x if { false }\n".to_string(),
+            ),
+        ];
+        assert_eq!(
+            test_rules(&rego_files),
+            vec![
+                "data.infraweave.regions.test_allowed_region",
+                "data.infraweave.regions.test_other_region",
+                "data.infraweave.other.test_x",
+            ]
+        );
+    }
+}
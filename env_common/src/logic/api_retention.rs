@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+use base64::engine::general_purpose::STANDARD as base64;
+use base64::Engine;
+use env_defs::{
+    get_change_record_identifier, get_change_record_part_sk, CloudProvider, EventData,
+    InfraChangeRecord,
+};
+use env_utils::get_epoch;
+use flate2::{write::GzEncoder, Compression};
+use serde_json::{json, Value};
+
+use crate::interface::GenericCloudHandler;
+use crate::logic::api_change_record::change_record_pk_prefix;
+
+const EVENTS_TABLE: &str = "events";
+const CHANGE_RECORDS_TABLE: &str = "change_records";
+const CHANGE_RECORDS_BUCKET: &str = "change_records";
+// Stays below the item limit of a DynamoDB transaction
+const DELETE_BATCH_SIZE: usize = 25;
+const DAY_MILLIS: u128 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Default)]
+pub struct PruneOptions {
+    /// Overrides `retention.days` of the project settings
+    pub retention_days: Option<u32>,
+    /// Overrides `retention.archive` of the project settings
+    pub archive: Option<bool>,
+    /// Only count what would be pruned
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct PruneSummary {
+    pub cutoff_epoch: u128,
+    pub events: usize,
+    pub change_records: usize,
+    /// Storage key of the archive, if one was written
+    pub archive_key: Option<String>,
+}
+
+/// Removes the change records and events in the region of the handler that are older than the
+/// retention of the project, archiving them as compressed JSON first unless archiving is disabled.
+/// The plan files the change records point to are left to the lifecycle rules of the storage
+pub async fn prune_history(
+    handler: &GenericCloudHandler,
+    options: &PruneOptions,
+) -> Result<PruneSummary, anyhow::Error> {
+    let retention = handler.get_current_project().await?.settings.retention;
+    let retention_days = options.retention_days.unwrap_or(retention.days);
+    let archive = options.archive.unwrap_or(retention.archive);
+    if retention_days == 0 {
+        return Err(anyhow::anyhow!("Retention must be at least one day"));
+    }
+
+    let cutoff_epoch = get_epoch().saturating_sub(retention_days as u128 * DAY_MILLIS);
+    let events = handler.get_event_items_before(cutoff_epoch).await?;
+    let change_records = get_change_records_of_events(handler, &events).await?;
+
+    let mut summary = PruneSummary {
+        cutoff_epoch,
+        events: events.len(),
+        change_records: change_records.len(),
+        archive_key: None,
+    };
+    if options.dry_run || (events.is_empty() && change_records.is_empty()) {
+        return Ok(summary);
+    }
+
+    if archive {
+        let key = archive_key(handler.get_project_id(), handler.get_region(), cutoff_epoch);
+        let compressed = compress_archive(&json!({
+            "project_id": handler.get_project_id(),
+            "region": handler.get_region(),
+            "cutoff_epoch": cutoff_epoch,
+            "events": events,
+            "change_records": change_records.values().collect::<Vec<_>>(),
+        }))?;
+        handler
+            .upload_file_base64(&key, CHANGE_RECORDS_BUCKET, &base64.encode(compressed))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to archive to {}: {}", key, e))?;
+        summary.archive_key = Some(key);
+    }
+
+    let mut deletes: Vec<Value> = vec![];
+    for ((pk, sk), record) in &change_records {
+        deletes.push(delete_item(CHANGE_RECORDS_TABLE, pk, sk));
+        // Records over the item size limit are stored in parts next to the record
+        for part in 0..record.chunks.unwrap_or(0) {
+            let part_sk = get_change_record_part_sk(sk, part);
+            deletes.push(delete_item(CHANGE_RECORDS_TABLE, pk, &part_sk));
+        }
+    }
+    for event in &events {
+        if let (Some(pk), Some(sk)) = (
+            event.get("PK").and_then(Value::as_str),
+            event.get("SK").and_then(Value::as_str),
+        ) {
+            deletes.push(delete_item(EVENTS_TABLE, pk, sk));
+        }
+    }
+    for batch in deletes.chunks(DELETE_BATCH_SIZE) {
+        handler
+            .transact_write(&Value::Array(batch.to_vec()))
+            .await?;
+    }
+
+    Ok(summary)
+}
+
+/// Change records of the jobs the events belong to, keyed by their partition and sort key
+async fn get_change_records_of_events(
+    handler: &GenericCloudHandler,
+    events: &[Value],
+) -> Result<BTreeMap<(String, String), InfraChangeRecord>, anyhow::Error> {
+    let jobs: BTreeSet<(String, String, String, String, String)> = events
+        .iter()
+        .filter_map(|item| serde_json::from_value::<EventData>(item.clone()).ok())
+        .filter(|event| !event.job_id.is_empty())
+        .map(|event| {
+            (
+                event.project_id,
+                event.region,
+                event.environment,
+                event.deployment_id,
+                event.job_id,
+            )
+        })
+        .collect();
+
+    let mut change_records = BTreeMap::new();
+    for (project_id, region, environment, deployment_id, job_id) in jobs {
+        // A job has at most one record of each kind, a drift check has both a plan and a drift record
+        for change_type in ["MUTATE", "PLAN", "DRIFT"] {
+            let Ok(record) = handler
+                .get_change_record(&environment, &deployment_id, &job_id, change_type)
+                .await
+            else {
+                continue;
+            };
+            let pk = format!(
+                "{}#{}",
+                change_record_pk_prefix(&record.change_type),
+                get_change_record_identifier(&project_id, &region, &deployment_id, &environment)
+            );
+            change_records.insert((pk, record.job_id.clone()), record);
+        }
+    }
+    Ok(change_records)
+}
+
+fn delete_item(table: &str, pk: &str, sk: &str) -> Value {
+    json!({
+        "Delete": {
+            "TableName": table,
+            "Key": {
+                "PK": pk,
+                "SK": sk,
+            }
+        }
+    })
+}
+
+fn archive_key(project_id: &str, region: &str, cutoff_epoch: u128) -> String {
+    format!(
+        "archive/{}/{}/{}-{}.json.gz",
+        project_id,
+        region,
+        cutoff_epoch,
+        get_epoch()
+    )
+}
+
+fn compress_archive(archive: &Value) -> Result<Vec<u8>, anyhow::Error> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&serde_json::to_vec(archive)?)?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_compress_archive_roundtrip() {
+        let archive = json!({
+            "cutoff_epoch": 1700000000000u64,
+            "events": [{ "PK": "EVENT#abc", "SK": "1690000000000" }],
+            "change_records": [],
+        });
+        let compressed = compress_archive(&archive).unwrap();
+
+        let mut json = String::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_string(&mut json)
+            .unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), archive);
+    }
+
+    #[test]
+    fn test_delete_item() {
+        assert_eq!(
+            delete_item(EVENTS_TABLE, "EVENT#abc", "1690000000000"),
+            json!({
+                "Delete": {
+                    "TableName": "events",
+                    "Key": { "PK": "EVENT#abc", "SK": "1690000000000" }
+                }
+            })
+        );
+    }
+}
@@ -2,15 +2,15 @@ use anyhow::{anyhow, Result};
 use base64::engine::general_purpose::STANDARD as base64;
 use base64::Engine;
 use env_defs::{
-    get_module_identifier, CloudProvider, DeploymentManifest, ModuleExample, ModuleManifest,
-    ModuleResp, OciArtifactSet, Provider, ProviderResp, StackManifest, TfLockProvider, TfOutput,
-    TfRequiredProvider, TfVariable,
+    get_module_identifier, CloudProvider, DeploymentManifest, MemberVariables, ModuleExample,
+    ModuleManifest, ModuleResp, ModuleYank, OciArtifactSet, Provider, ProviderResp, StackManifest,
+    TfLockProvider, TfOutput, TfRequiredProvider, TfVariable, VariableExposure,
 };
 use env_utils::{
     clean_root, get_providers_from_lockfile, get_timestamp, get_version_track, indent,
-    merge_json_dicts, read_stack_directory, read_tf_directory, read_tf_from_zip,
-    run_terraform_provider_lock, semver_parse, tempdir, to_camel_case, to_snake_case,
-    zero_pad_semver,
+    is_required_variable, merge_json_dicts, read_stack_directory, read_tf_directory,
+    read_tf_from_zip, run_terraform_provider_lock, semver_parse, tempdir, to_camel_case,
+    to_snake_case, zero_pad_semver,
 };
 use futures::stream::{self, StreamExt};
 use hcl::{Attribute, Block, Expression, Identifier, Value as HclValue};
@@ -40,11 +40,13 @@ use crate::{
     interface::GenericCloudHandler,
     logic::{
         api_infra::{get_default_cpu, get_default_memory},
-        api_module::{compare_latest_version, upload_module},
+        api_module::{compare_latest_version, unyank_version, upload_module, yank_version},
         api_provider::upload_provider_cache,
         tf_input_resolver::TfInputResolver,
         tf_provider_mgmt::TfProviderMgmt,
-        tf_root_module::{module_block, providers, variables},
+        tf_root_module::{
+            claim_variable, module_block, nested_stack_terraform_block, providers, variables,
+        },
         utils::{ensure_track_matches_version, ModuleType},
     },
 };
@@ -62,6 +64,7 @@ pub async fn publish_stack(
 
     validate_stack_name(&stack_manifest)?;
     validate_stack_kind(&stack_manifest)?;
+    validate_stack_owners(&stack_manifest)?;
 
     if version_arg.is_some() {
         // In case a version argument is provided
@@ -72,7 +75,7 @@ pub async fn publish_stack(
         stack_manifest.spec.version = Some(version_arg.unwrap().to_string());
     }
     let claims = get_claims_in_stack(manifest_path)?;
-    let claim_modules = get_modules_in_stack(handler, &claims).await;
+    let claim_modules = get_modules_in_stack(handler, &claims).await?;
 
     validate_claim_modules(&claim_modules)?;
 
@@ -102,14 +105,23 @@ pub async fn publish_stack(
         }
     }
 
-    let variable_collection = collect_module_variables_with_stack(
+    let mut variable_collection = collect_module_variables_with_stack(
         &claim_modules,
         stack_manifest.spec.stack_variable_definitions.as_ref(),
     );
+    let member_variable_bindings = apply_member_variables(
+        &mut variable_collection,
+        stack_manifest.spec.member_variables.as_ref(),
+    )?;
     let output_collection = collect_module_outputs(&claim_modules);
 
+    // Internal and renamed member variables have no stack variable of their own to refer to
     let tf_input_resolver = TfInputResolver::new(
-        variable_collection.keys().cloned().collect(),
+        variable_collection
+            .keys()
+            .filter(|key| !member_variable_bindings.contains_key(*key))
+            .cloned()
+            .collect(),
         output_collection.keys().cloned().collect(),
     );
 
@@ -214,7 +226,7 @@ pub async fn publish_stack(
     }
 
     // Collect modules
-    for (_, module) in claim_modules.iter().cloned() {
+    for (claim, module) in claim_modules.iter().cloned() {
         let zip_data = if http_client::is_http_mode_enabled() {
             // TODO: Implement http_download_module_zip
             todo!("Implement http_download_module_zip")
@@ -224,6 +236,13 @@ pub async fn publish_stack(
                 .await?;
             env_utils::download_zip_to_vec(&url).await?
         };
+        if module.module_type == "stack" {
+            // Nested stacks are unpacked into their own directory and called as a module
+            let stack_dir = temp_dir.join(format!("{}-{}", claim.kind, module.version));
+            env_utils::unzip_vec_to(&zip_data, &stack_dir)?;
+            prepare_nested_stack_dir(&stack_dir, &module)?;
+            continue;
+        }
         env_utils::unzip_vec_to(&zip_data, &temp_dir)?;
         // Clean modules(remove provider) "iw-generated-providers.tf"
         clean_root(&temp_dir).expect(&format!(
@@ -234,7 +253,8 @@ pub async fn publish_stack(
 
     // Create list of all dependencies between modules
     // Maps every "{{ ModuleName::DeploymentName::OutputName }}" to the output key such as "module.DeploymentName.OutputName"
-    let dependency_map = generate_dependency_map(&variable_collection, &output_collection)?;
+    let mut dependency_map = generate_dependency_map(&variable_collection, &output_collection)?;
+    dependency_map.extend(member_variable_dependencies(&member_variable_bindings));
 
     for (variable_name, tf_variable) in variable_collection.clone() {
         if dependency_map.contains_key(&variable_name) {
@@ -260,7 +280,8 @@ pub async fn publish_stack(
     }
 
     for (output_name, tf_output) in output_collection.clone() {
-        let value: Vec<&str> = output_name.split("__").collect();
+        // Split on the claim name only, outputs of nested stacks contain "__" themselves
+        let value: Vec<&str> = output_name.splitn(2, "__").collect();
         tf_provider_mgmt.add_block(
             &TfOutput {
                 name: output_name.clone(),
@@ -299,14 +320,20 @@ pub async fn publish_stack(
                     .tf_variables
                     .iter()
                     .map(|tf_var| {
-                        (
-                            tf_var.name.clone(),
-                            format!(
+                        let variable_name =
+                            get_variable_name(&deployment.metadata.name, &tf_var.name);
+                        let stack_variable = match member_variable_bindings.get(&variable_name) {
+                            Some(MemberVariableBinding::Internal(_)) => None,
+                            Some(MemberVariableBinding::StackVariable(stack_variable)) => {
+                                Some(stack_variable.clone())
+                            }
+                            None => Some(format!(
                                 "{}__{}",
                                 deployment.metadata.name.clone(),
                                 tf_var.name.clone()
-                            ),
-                        )
+                            )),
+                        };
+                        (tf_var.name.clone(), stack_variable)
                     })
                     .collect(),
                 &deployment,
@@ -453,6 +480,8 @@ pub async fn publish_stack(
                     .unwrap_or_else(get_default_memory),
             ),
             providers: providers,
+            owners: stack_manifest.spec.owners.clone(),
+            ui: None,
         },
         api_version: stack_manifest.api_version.clone(),
     };
@@ -460,11 +489,12 @@ pub async fn publish_stack(
     let stack_data = Some(env_defs::ModuleStackData {
         modules: claim_modules
             .iter()
-            .map(|(_d, m)| env_defs::StackModule {
+            .map(|(d, m)| env_defs::StackModule {
                 module: m.module.clone(),
                 version: m.version.clone(),
                 track: m.track.clone(),
                 s3_key: m.s3_key.clone(),
+                claim: d.metadata.name.clone(),
             })
             .collect(),
     });
@@ -499,7 +529,7 @@ pub async fn publish_stack(
         .unwrap_or(&get_default_memory())
         .to_string();
 
-    let module = ModuleResp {
+    let mut module = ModuleResp {
         track: track.to_string(),
         track_version: format!(
             "{}#{}",
@@ -531,6 +561,11 @@ pub async fn publish_stack(
         tf_providers: stack_providers,
         deprecated: false,
         deprecated_message: None,
+        readme: None,
+        changelog: None,
+        digest: None,
+        oci_reference: None,
+        yanked: None,
     };
 
     let stack_zip = match env_utils::get_zip_file(
@@ -546,6 +581,7 @@ pub async fn publish_stack(
     };
 
     let zip_base64 = base64.encode(&stack_zip);
+    module.digest = Some(env_utils::artifact_digest(&stack_zip));
 
     // In HTTP mode the server validates on its side, so skip client-side validation reads.
     if !http_client::is_http_mode_enabled() {
@@ -686,6 +722,51 @@ pub async fn publish_stack(
     Ok(())
 }
 
+/// Turns an unpacked stack zip into a child module of the stack being published.
+///
+/// The module calls (main.tf) are kept as is, while providers.tf is reduced to the stack's
+/// own variables and outputs, since provider configurations are passed from the parent stack.
+fn prepare_nested_stack_dir(stack_dir: &Path, stack: &ModuleResp) -> Result<(), ModuleError> {
+    let providers_tf_path = stack_dir.join("providers.tf");
+    let providers_tf = std::fs::read_to_string(&providers_tf_path).map_err(|e| anyhow!(e))?;
+    let body = hcl::parse(&providers_tf).map_err(|e| {
+        ModuleError::ValidationError(format!(
+            "Unable to read terraform code from nested stack {}: {}",
+            stack.module, e
+        ))
+    })?;
+
+    let provider_variables: HashSet<&str> = stack
+        .tf_providers
+        .iter()
+        .flat_map(|provider| provider.tf_variables.iter().map(|v| v.name.as_str()))
+        .collect();
+    let stack_blocks = body.blocks().filter(|block| match block.identifier() {
+        "variable" => block
+            .labels()
+            .first()
+            .is_some_and(|label| !provider_variables.contains(label.as_str())),
+        "output" => true,
+        _ => false,
+    });
+
+    let nested_stack_tf = hcl::format::to_string(
+        &hcl::Body::builder()
+            .add_block(nested_stack_terraform_block(stack))
+            .add_blocks(stack_blocks.cloned())
+            .build(),
+    )
+    .map_err(|e| anyhow!(e))?;
+    debug!("Nested stack {} setup:\n{}", stack.module, &nested_stack_tf);
+    std::fs::write(&providers_tf_path, nested_stack_tf).map_err(|e| anyhow!(e))?;
+
+    let lock_file_path = stack_dir.join(".terraform.lock.hcl");
+    if lock_file_path.exists() {
+        std::fs::remove_file(lock_file_path).map_err(|e| anyhow!(e))?;
+    }
+    Ok(())
+}
+
 fn validate_stack_name(stack_manifest: &StackManifest) -> anyhow::Result<(), ModuleError> {
     let name = stack_manifest.metadata.name.clone();
     let stack_name = stack_manifest.spec.stack_name.clone();
@@ -896,6 +977,39 @@ pub async fn deprecate_stack(
     Ok(())
 }
 
+/// Yanks a published stack version, see [`yank_module`](super::yank_module)
+pub async fn yank_stack(
+    handler: &GenericCloudHandler,
+    stack: &str,
+    track: &str,
+    version: &str,
+    reason: Option<&str>,
+    replacement_version: Option<&str>,
+    yanked_by: Option<&str>,
+) -> anyhow::Result<ModuleYank> {
+    yank_version(
+        handler,
+        true,
+        stack,
+        track,
+        version,
+        reason,
+        replacement_version,
+        yanked_by,
+    )
+    .await
+}
+
+/// Lifts the yank of a stack version, so new claims can use it again
+pub async fn unyank_stack(
+    handler: &GenericCloudHandler,
+    stack: &str,
+    track: &str,
+    version: &str,
+) -> anyhow::Result<()> {
+    unyank_version(handler, true, stack, track, version).await
+}
+
 fn validate_stack_kind(stack_manifest: &StackManifest) -> anyhow::Result<(), ModuleError> {
     let kind = stack_manifest.kind.clone();
     if kind != "Stack" {
@@ -907,25 +1021,195 @@ fn validate_stack_kind(stack_manifest: &StackManifest) -> anyhow::Result<(), Mod
     Ok(())
 }
 
+fn validate_stack_owners(stack_manifest: &StackManifest) -> anyhow::Result<(), ModuleError> {
+    stack_manifest
+        .spec
+        .owners
+        .iter()
+        .try_for_each(|owner| env_defs::validate_owner(owner))
+        .map_err(ModuleError::ValidationError)
+}
+
 pub async fn get_stack_preview(
     handler: &GenericCloudHandler,
     manifest_path: &String,
 ) -> anyhow::Result<String, anyhow::Error> {
-    println!("Preview stack from {}", manifest_path);
+    let claim_modules = get_stack_preview_modules(handler, manifest_path).await?;
+    let stack_manifest = get_stack_manifest(manifest_path);
+    let module_stack_data = generate_full_terraform_module(
+        &claim_modules,
+        stack_manifest.spec.member_variables.as_ref(),
+    )?;
+    Ok(stack_preview_content(&module_stack_data))
+}
 
-    let claims = get_claims_in_stack(manifest_path)?;
-    let claim_modules = get_modules_in_stack(handler, &claims).await;
+async fn get_stack_preview_modules(
+    handler: &GenericCloudHandler,
+    manifest_path: &String,
+) -> anyhow::Result<Vec<(DeploymentManifest, ModuleResp)>, anyhow::Error> {
+    info!("Preview stack from {}", manifest_path);
 
-    let module_stack_data = generate_full_terraform_module(&claim_modules)?;
+    let claims = get_claims_in_stack(manifest_path)?;
+    Ok(get_modules_in_stack(handler, &claims).await?)
+}
 
-    let tf_content = format!(
+fn stack_preview_content(module_stack_data: &ModuleStackData) -> String {
+    format!(
         "{}\n{}\n{}",
         &module_stack_data.terraform_module_code,
         &module_stack_data.terraform_variable_code,
         &module_stack_data.terraform_output_code
-    );
+    )
+}
 
-    Ok(tf_content)
+/// Returns the composed stack as a configuration-only JSON document, shaped like the
+/// `configuration` section of `terraform show -json`, so it can be graphed without a plan.
+/// Each module call carries the variables and outputs of its module, so the graph can show
+/// which output of one claim feeds which variable of another
+pub async fn get_stack_preview_configuration(
+    handler: &GenericCloudHandler,
+    manifest_path: &String,
+) -> anyhow::Result<JsonValue, anyhow::Error> {
+    let claim_modules = get_stack_preview_modules(handler, manifest_path).await?;
+    let stack_manifest = get_stack_manifest(manifest_path);
+    let module_stack_data = generate_full_terraform_module(
+        &claim_modules,
+        stack_manifest.spec.member_variables.as_ref(),
+    )?;
+    let mut configuration = stack_configuration(&stack_preview_content(&module_stack_data))?;
+    add_module_interfaces(&mut configuration, &claim_modules);
+    Ok(configuration)
+}
+
+// Adds the variables and outputs of each claim's module to its module call, in the shape
+// Terraform uses for the `module` of a module call
+fn add_module_interfaces(
+    configuration: &mut JsonValue,
+    claim_modules: &[(DeploymentManifest, ModuleResp)],
+) {
+    for (claim, module) in claim_modules {
+        let name = to_snake_case(&claim.metadata.name);
+        let Some(module_call) = configuration["root_module"]["module_calls"].get_mut(&name) else {
+            continue;
+        };
+        let variables: serde_json::Map<String, JsonValue> = module
+            .tf_variables
+            .iter()
+            .map(|variable| (variable.name.clone(), serde_json::json!({})))
+            .collect();
+        let outputs: serde_json::Map<String, JsonValue> = module
+            .tf_outputs
+            .iter()
+            .map(|output| {
+                let references = reference_regex_matches(&output.value);
+                (
+                    output.name.clone(),
+                    serde_json::json!({ "expression": { "references": references } }),
+                )
+            })
+            .collect();
+        module_call["module"] = serde_json::json!({
+            "variables": variables,
+            "outputs": outputs,
+        });
+    }
+}
+
+fn stack_configuration(tf_content: &str) -> Result<JsonValue> {
+    let body = hcl::parse(tf_content)?;
+
+    let mut module_calls = serde_json::Map::new();
+    let mut variables = serde_json::Map::new();
+    let mut outputs = serde_json::Map::new();
+
+    for block in body.blocks() {
+        let name = match block.labels().first() {
+            Some(label) => label.as_str().to_string(),
+            None => continue,
+        };
+        match block.identifier() {
+            "module" => {
+                let mut source = None;
+                let mut depends_on = vec![];
+                let mut expressions = serde_json::Map::new();
+                for attr in block.body().attributes() {
+                    match (attr.key(), attr.expr()) {
+                        ("source", Expression::String(s)) => source = Some(s.clone()),
+                        ("depends_on", expr) => depends_on = expression_references(expr),
+                        (key, expr) => {
+                            expressions.insert(
+                                key.to_string(),
+                                serde_json::json!({ "references": expression_references(expr) }),
+                            );
+                        }
+                    }
+                }
+                let mut module_call =
+                    serde_json::json!({ "source": source, "expressions": expressions });
+                if !depends_on.is_empty() {
+                    module_call["depends_on"] = serde_json::json!(depends_on);
+                }
+                module_calls.insert(name, module_call);
+            }
+            "variable" => {
+                let mut variable = serde_json::Map::new();
+                for attr in block.body().attributes() {
+                    if let ("description", Expression::String(s)) = (attr.key(), attr.expr()) {
+                        variable.insert("description".to_string(), JsonValue::String(s.clone()));
+                    }
+                }
+                variables.insert(name, JsonValue::Object(variable));
+            }
+            "output" => {
+                let references = block
+                    .body()
+                    .attributes()
+                    .find(|attr| attr.key() == "value")
+                    .map(|attr| expression_references(attr.expr()))
+                    .unwrap_or_default();
+                outputs.insert(
+                    name,
+                    serde_json::json!({ "expression": { "references": references } }),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    Ok(serde_json::json!({
+        "root_module": {
+            "module_calls": module_calls,
+            "variables": variables,
+            "outputs": outputs,
+        }
+    }))
+}
+
+// Collects module and variable references from an expression, including ones inside templates,
+// in the same form Terraform reports them (e.g. ["module.bucket1.bucket_arn", "module.bucket1"])
+fn expression_references(expr: &Expression) -> Vec<String> {
+    reference_regex_matches(&hcl::format::to_string(expr).unwrap_or_default())
+}
+
+fn reference_regex_matches(formatted: &str) -> Vec<String> {
+    let reference_regex =
+        Regex::new(r"\b(module|var)\.([A-Za-z_][A-Za-z0-9_-]*)(\.[A-Za-z_][A-Za-z0-9_-]*)?")
+            .unwrap();
+
+    let mut references: Vec<String> = vec![];
+    for caps in reference_regex.captures_iter(formatted) {
+        let base = format!("{}.{}", &caps[1], &caps[2]);
+        if let Some(attribute) = caps.get(3) {
+            let full = format!("{}{}", base, attribute.as_str());
+            if !references.contains(&full) {
+                references.push(full);
+            }
+        }
+        if !references.contains(&base) {
+            references.push(base);
+        }
+    }
+    references
 }
 
 fn get_stack_manifest(manifest_path: &str) -> StackManifest {
@@ -938,7 +1222,7 @@ fn get_stack_manifest(manifest_path: &str) -> StackManifest {
 }
 
 fn get_claims_in_stack(manifest_path: &str) -> Result<Vec<DeploymentManifest>, anyhow::Error> {
-    println!("Reading stack claim manifests in {}", manifest_path);
+    info!("Reading stack claim manifests in {}", manifest_path);
     let claims = read_stack_directory(Path::new(manifest_path))?;
     Ok(claims)
 }
@@ -946,66 +1230,67 @@ fn get_claims_in_stack(manifest_path: &str) -> Result<Vec<DeploymentManifest>, a
 async fn get_modules_in_stack(
     handler: &GenericCloudHandler,
     deployment_manifests: &Vec<DeploymentManifest>,
-) -> Vec<(DeploymentManifest, ModuleResp)> {
-    println!("Getting modules for deployment manifests");
+) -> Result<Vec<(DeploymentManifest, ModuleResp)>, ModuleError> {
+    info!("Getting modules for deployment manifests");
     let mut claim_modules: Vec<(DeploymentManifest, ModuleResp)> = vec![];
 
     for claim in deployment_manifests {
-        let module_version = match &claim.spec.module_version {
-            Some(version) => version, // We expect module version to be set for all claims
-            None => {
-                println!("Module version is not set in claim {}", claim.metadata.name);
-                std::process::exit(1); // TODO: should propagate error up instead of exiting
-            }
-        };
-        assert_eq!(claim.spec.stack_version, None); // Stack version should not be set in claims
-        let track = match get_version_track(module_version) {
-            Ok(track) => track,
-            Err(e) => {
-                println!(
-                    "Could not find track for claim {}, error: {}",
-                    claim.metadata.name, e
-                );
-                std::process::exit(1); // TODO: should propagate error up instead of exiting
-            }
-        };
+        // Claims reference either a module or, for nested stacks, another published stack
+        let (module_version, is_stack) =
+            match (&claim.spec.module_version, &claim.spec.stack_version) {
+                (Some(version), None) => (version, false),
+                (None, Some(version)) => (version, true),
+                _ => {
+                    return Err(ModuleError::ValidationError(format!(
+                        "Exactly one of moduleVersion or stackVersion must be set in claim {}",
+                        claim.metadata.name
+                    )));
+                }
+            };
+        let track = get_version_track(module_version).map_err(|e| {
+            ModuleError::ValidationError(format!(
+                "Could not find track for claim {}, error: {}",
+                claim.metadata.name, e
+            ))
+        })?;
         let module = claim.kind.to_lowercase();
         let version = module_version.to_string();
-        let module_resp = match handler.get_module_version(&module, &track, &version).await {
-            Ok(result) => match result {
-                Some(m) => m,
-                None => {
-                    println!(
-                        "No module found with name: {} and version: {}",
-                        &module, &version
-                    );
-                    std::process::exit(1);
-                }
-            },
-            Err(e) => {
-                println!("{}", e);
-                std::process::exit(1);
-            }
-        };
+        let module_resp = if is_stack {
+            handler.get_stack_version(&module, &track, &version).await?
+        } else {
+            handler
+                .get_module_version(&module, &track, &version)
+                .await?
+        }
+        .ok_or_else(|| ModuleError::ModuleVersionNotFound(version.clone(), module.clone()))?;
         claim_modules.push((claim.clone(), module_resp));
     }
 
-    claim_modules
+    Ok(claim_modules)
 }
 
 pub fn generate_full_terraform_module(
     claim_modules: &Vec<(DeploymentManifest, ModuleResp)>,
+    member_variables: Option<&MemberVariables>,
 ) -> Result<ModuleStackData, ModuleError> {
-    let variable_collection = collect_module_variables(claim_modules);
+    let mut variable_collection = collect_module_variables(claim_modules);
+    let member_variable_bindings =
+        apply_member_variables(&mut variable_collection, member_variables)?;
     let output_collection = collect_module_outputs(claim_modules);
     let module_collection = collect_modules(claim_modules);
+    let module_dependencies = collect_module_dependencies(claim_modules);
 
     // Create list of all dependencies between modules
     // Maps every "{{ ModuleName::DeploymentName::OutputName }}" to the output key such as "module.DeploymentName.OutputName"
-    let dependency_map = generate_dependency_map(&variable_collection, &output_collection)?;
+    let mut dependency_map = generate_dependency_map(&variable_collection, &output_collection)?;
+    dependency_map.extend(member_variable_dependencies(&member_variable_bindings));
 
-    let (terraform_module_code, providers) =
-        generate_terraform_modules(&module_collection, &variable_collection, &dependency_map);
+    let (terraform_module_code, providers) = generate_terraform_modules(
+        &module_collection,
+        &variable_collection,
+        &dependency_map,
+        &module_dependencies,
+    );
 
     let tf_extra_environment_variables = claim_modules
         .iter()
@@ -1108,6 +1393,7 @@ fn generate_terraform_modules(
     module_collection: &HashMap<String, ModuleResp>,
     variable_collection: &HashMap<String, TfVariable>,
     dependency_map: &HashMap<String, String>,
+    module_dependencies: &HashMap<String, Vec<String>>,
 ) -> (String, Vec<TfRequiredProvider>) {
     let mut terraform_modules = vec![];
 
@@ -1119,6 +1405,10 @@ fn generate_terraform_modules(
             module,
             variable_collection,
             dependency_map,
+            module_dependencies
+                .get(claim_name)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
         );
         terraform_modules.push(module_str);
     }
@@ -1134,6 +1424,7 @@ fn generate_terraform_module_single(
     module: &ModuleResp,
     variable_collection: &HashMap<String, TfVariable>,
     dependency_map: &HashMap<String, String>,
+    depends_on: &[String],
 ) -> String {
     let mut module_str = String::new();
     let source = module
@@ -1155,7 +1446,7 @@ fn generate_terraform_module_single(
         variable_collection.iter().collect(); // Not necessary, but for consistent ordering of variables
 
     for (variable_name, _variable_value) in variable_collection {
-        let parts = variable_name.split("__").collect::<Vec<&str>>();
+        let parts = variable_name.splitn(2, "__").collect::<Vec<&str>>();
         let part_claim_name = parts[0];
         let part_var_name = parts[1];
 
@@ -1187,6 +1478,13 @@ fn generate_terraform_module_single(
             let variable_str = format!("\n  {} = var.{}", var, var);
             module_str.push_str(&variable_str);
         });
+    if !depends_on.is_empty() {
+        let modules = depends_on
+            .iter()
+            .map(|claim_name| format!("module.{}", claim_name))
+            .collect::<Vec<_>>();
+        module_str.push_str(&format!("\n  depends_on = [{}]", modules.join(", ")));
+    }
     module_str.push_str("\n}");
     module_str
 }
@@ -1213,7 +1511,7 @@ fn generate_terraform_output_single(
     _dependency_map: &HashMap<String, String>,
 ) -> String {
     let var_name = output_name;
-    let parts = var_name.split("__").collect::<Vec<&str>>();
+    let parts = var_name.splitn(2, "__").collect::<Vec<&str>>();
     let claim_name = parts[0];
     let output_name = parts[1];
     format!(
@@ -1349,7 +1647,7 @@ fn generate_dependency_map(
                 let field = parts[2];
 
                 // field in claim: bucketName, in module input/output: bucket_name
-                let field_snake_case = to_snake_case(field);
+                let field_snake_case = field_to_snake_case(field);
 
                 // Handle Stack::variables::* references specially
                 if kind == "Stack" && claim_name == "variables" {
@@ -1438,6 +1736,27 @@ fn collect_modules(
     modules
 }
 
+/// Claims listed in `dependsOn` of each claim, keyed and valued by module name like `collect_modules`
+fn collect_module_dependencies(
+    claim_modules: &[(DeploymentManifest, ModuleResp)],
+) -> HashMap<String, Vec<String>> {
+    claim_modules
+        .iter()
+        .filter(|(claim, _)| !claim.spec.depends_on.is_empty())
+        .map(|(claim, _)| {
+            let mut depends_on: Vec<String> = claim
+                .spec
+                .depends_on
+                .iter()
+                .map(|c| to_snake_case(c))
+                .collect();
+            depends_on.sort();
+            depends_on.dedup();
+            (to_snake_case(&claim.metadata.name), depends_on)
+        })
+        .collect()
+}
+
 fn get_variable_name(claim_name: &str, variable_name: &str) -> String {
     format!("{}__{}", to_snake_case(claim_name), variable_name)
 }
@@ -1480,18 +1799,16 @@ fn collect_module_variables_with_stack(
         for tf_var in &module.tf_variables {
             let var_name = get_variable_name(&claim.metadata.name, &tf_var.name);
 
-            // In claim: bucketName, in module: bucket_name
-            let camelcase_var_name = to_camel_case(&tf_var.name);
-            let new_tf_var =
-                match claim_variables.get(&serde_yaml::Value::String(camelcase_var_name)) {
-                    Some(value) => {
-                        // Variable defined in claim, use claim value
-                        let mut temp_tf_var = tf_var.clone();
-                        temp_tf_var.default = Some(serde_json::to_value(value).unwrap());
-                        temp_tf_var
-                    }
-                    None => tf_var.clone(),
-                };
+            // In claim: bucketName (or bucket1: { bucketName } for nested stacks), in module: bucket_name
+            let new_tf_var = match claim_variable(claim_variables, &tf_var.name) {
+                Some(value) => {
+                    // Variable defined in claim, use claim value
+                    let mut temp_tf_var = tf_var.clone();
+                    temp_tf_var.default = Some(serde_json::to_value(value).unwrap());
+                    temp_tf_var
+                }
+                None => tf_var.clone(),
+            };
 
             variables.insert(var_name, new_tf_var);
         }
@@ -1508,6 +1825,194 @@ fn collect_module_variables_with_stack(
     variables
 }
 
+/// How a member variable is set in the stack when it doesn't become a stack variable named
+/// after its claim
+#[derive(Debug, Clone, PartialEq)]
+enum MemberVariableBinding {
+    /// Fixed to the value in the claim, or the module default
+    Internal(JsonValue),
+    /// Set from the stack variable with this key, such as `stack__region`
+    StackVariable(String),
+}
+
+// Applies `memberVariables` of the stack manifest to the collected variables. Renamed variables
+// are added as stack variables and listed descriptions replace the ones of the modules.
+// Variables set from references to other claims are left as they are
+fn apply_member_variables(
+    variable_collection: &mut HashMap<String, TfVariable>,
+    member_variables: Option<&MemberVariables>,
+) -> Result<HashMap<String, MemberVariableBinding>, ModuleError> {
+    let mut bindings = HashMap::new();
+    let member_variables = match member_variables {
+        Some(member_variables) => member_variables,
+        None => return Ok(bindings),
+    };
+    let defined_stack_variables: HashSet<String> = variable_collection
+        .keys()
+        .filter(|key| key.starts_with("stack__"))
+        .cloned()
+        .collect();
+    // Variables other claims refer to must stay stack variables under their own name
+    let referenced_variables = referenced_member_variables(variable_collection);
+    let mut listed = HashSet::new();
+
+    for member_variable in &member_variables.variables {
+        let (claim_name, variable_name) =
+            member_variable.variable.split_once('.').ok_or_else(|| {
+                ModuleError::ValidationError(format!(
+                    "Member variable '{}' must be written as <claim>.<variableName>",
+                    member_variable.variable
+                ))
+            })?;
+        let key = get_variable_name(claim_name, &to_snake_case(variable_name));
+        if !listed.insert(key.clone()) {
+            return Err(ModuleError::ValidationError(format!(
+                "Member variable '{}' is listed more than once",
+                member_variable.variable
+            )));
+        }
+        let tf_variable = variable_collection.get_mut(&key).ok_or_else(|| {
+            ModuleError::ValidationError(format!(
+                "Member variable '{}' does not exist in any claim of the stack",
+                member_variable.variable
+            ))
+        })?;
+        if is_set_from_reference(tf_variable) {
+            return Err(ModuleError::ValidationError(format!(
+                "Member variable '{}' is set from a reference in the claim and can't be listed",
+                member_variable.variable
+            )));
+        }
+        if referenced_variables.contains(&key)
+            && (member_variable.exposure == VariableExposure::Internal
+                || member_variable.name.is_some())
+        {
+            return Err(ModuleError::ValidationError(format!(
+                "Member variable '{}' is referenced by another claim and must be exposed under its own name",
+                member_variable.variable
+            )));
+        }
+        if let Some(description) = &member_variable.description {
+            tf_variable.description = description.clone();
+        }
+        let tf_variable = tf_variable.clone();
+
+        match (member_variable.exposure, &member_variable.name) {
+            (VariableExposure::Internal, Some(_)) => {
+                return Err(ModuleError::ValidationError(format!(
+                    "Member variable '{}' is internal and can't be exposed with a name",
+                    member_variable.variable
+                )));
+            }
+            (VariableExposure::Internal, None) => {
+                bindings.insert(key.clone(), internal_binding(&key, &tf_variable)?);
+            }
+            (VariableExposure::Exposed, Some(name)) => {
+                let stack_key = format!("stack__{}", to_snake_case(name));
+                if defined_stack_variables.contains(&stack_key) {
+                    return Err(ModuleError::ValidationError(format!(
+                        "Member variable '{}' is exposed as '{}', which is already in stackVariableDefinitions",
+                        member_variable.variable, name
+                    )));
+                }
+                // Several member variables can share a stack variable if they have the same type
+                match variable_collection.get(&stack_key) {
+                    Some(existing) if existing._type != tf_variable._type => {
+                        return Err(ModuleError::ValidationError(format!(
+                            "Member variables exposed as '{}' must have the same type",
+                            name
+                        )));
+                    }
+                    Some(_) => {}
+                    None => {
+                        variable_collection.insert(
+                            stack_key.clone(),
+                            TfVariable {
+                                name: to_snake_case(name),
+                                ..tf_variable
+                            },
+                        );
+                    }
+                }
+                bindings.insert(key, MemberVariableBinding::StackVariable(stack_key));
+            }
+            (VariableExposure::Exposed, None) => {}
+        }
+    }
+
+    // Required variables without a value in the claim have nothing to be fixed to and stay exposed
+    if member_variables.default == VariableExposure::Internal {
+        for (key, tf_variable) in variable_collection.iter() {
+            if listed.contains(key)
+                || referenced_variables.contains(key)
+                || key.starts_with("stack__")
+                || tf_variable.name.starts_with("INFRAWEAVE_")
+                || is_set_from_reference(tf_variable)
+                || is_required_variable(tf_variable)
+            {
+                continue;
+            }
+            bindings.insert(key.clone(), internal_binding(key, tf_variable)?);
+        }
+    }
+
+    Ok(bindings)
+}
+
+fn referenced_member_variables(
+    variable_collection: &HashMap<String, TfVariable>,
+) -> HashSet<String> {
+    let re = Regex::new(r"\{\{\s*\w+::(\w+)::(\w+)\s*\}\}").unwrap();
+    variable_collection
+        .values()
+        .filter_map(|tf_variable| tf_variable.default.as_ref())
+        .flat_map(|value| {
+            re.captures_iter(&value.to_string())
+                .map(|caps| get_variable_name(&caps[1], &to_snake_case(&caps[2])))
+                .collect::<Vec<_>>()
+        })
+        .filter(|key| variable_collection.contains_key(key))
+        .collect()
+}
+
+fn is_set_from_reference(tf_variable: &TfVariable) -> bool {
+    tf_variable
+        .default
+        .as_ref()
+        .is_some_and(|value| value.to_string().contains("{{"))
+}
+
+fn internal_binding(
+    key: &str,
+    tf_variable: &TfVariable,
+) -> Result<MemberVariableBinding, ModuleError> {
+    match &tf_variable.default {
+        Some(value) if !is_required_variable(tf_variable) => {
+            Ok(MemberVariableBinding::Internal(value.clone()))
+        }
+        _ => Err(ModuleError::ValidationError(format!(
+            "Member variable '{}' is internal but is not set in the claim and has no default",
+            key.replacen("__", ".", 1)
+        ))),
+    }
+}
+
+// Module inputs of bound member variables, in the format of the dependency map
+fn member_variable_dependencies(
+    bindings: &HashMap<String, MemberVariableBinding>,
+) -> HashMap<String, String> {
+    bindings
+        .iter()
+        .map(|(key, binding)| {
+            let input = match binding {
+                MemberVariableBinding::Internal(value) => value.to_string(),
+                MemberVariableBinding::StackVariable(stack_key) => format!("var.{}", stack_key),
+            };
+            (key.clone(), input)
+        })
+        .collect()
+}
+
 pub fn validate_claim_modules(
     claim_modules: &[(DeploymentManifest, ModuleResp)],
 ) -> Result<(), ModuleError> {
@@ -1531,15 +2036,29 @@ pub fn validate_claim_modules(
         } else {
             serde_json::to_value(&deployment_variables).unwrap()
         };
-        let variables = env_utils::convert_first_level_keys_to_snake_case(&provided_variables);
+        let is_stack = claim.spec.stack_version.is_some();
+        let variables = if is_stack {
+            // Variables of nested stacks are set per claim, same as in stack deployment claims
+            let dont_flatten: Vec<&String> = module
+                .tf_providers
+                .iter()
+                .flat_map(|p| p.tf_variables.iter().map(|v| &v.name))
+                .collect();
+            env_utils::flatten_and_convert_first_level_keys_to_snake_case(
+                &provided_variables,
+                "",
+                dont_flatten,
+            )
+        } else {
+            env_utils::convert_first_level_keys_to_snake_case(&provided_variables)
+        };
 
         env_utils::verify_variable_claim_casing(claim, &provided_variables)?;
 
         env_utils::verify_variable_existence_and_type(module, &variables)?;
 
-        // Verify moduleVersion is set
-        // TODO: (may support Stacks in future but need more testing)
-        if claim.spec.module_version.is_none() {
+        // Verify exactly one of moduleVersion or stackVersion (nested stack) is set
+        if claim.spec.module_version.is_some() == is_stack {
             return Err(ModuleError::ModuleVersionNotSet(
                 claim.metadata.name.clone(),
             ));
@@ -1617,6 +2136,14 @@ fn validate_dependencies(
                 ));
             }
         }
+        for dep_claim in &claim.spec.depends_on {
+            if !module_map.contains_key(dep_claim) {
+                return Err(ModuleError::DependsOnClaimNotFound(
+                    claim_name.clone(),
+                    dep_claim.clone(),
+                ));
+            }
+        }
     }
 
     // Build a dependency graph mapping each claim to the claims it depends on.
@@ -1646,6 +2173,11 @@ fn validate_dependencies(
                     .push(dep_claim);
             }
         }
+        // Explicit `dependsOn` orders claims the same way as references do
+        dependency_graph
+            .entry(claim_name.clone())
+            .or_default()
+            .extend(claim.spec.depends_on.iter().cloned());
     }
 
     // Run cycle detection on the graph.
@@ -1738,7 +2270,7 @@ fn extract_top_level_deps(vars: &serde_json::Value) -> Vec<(String, String, Stri
                         deps.push((
                             cap[1].to_string(),
                             cap[2].to_string(),
-                            to_snake_case(&cap[3]),
+                            field_to_snake_case(&cap[3]),
                         ));
                     }
                 }
@@ -1756,7 +2288,7 @@ fn extract_top_level_deps(vars: &serde_json::Value) -> Vec<(String, String, Stri
                                 deps.push((
                                     cap[1].to_string(),
                                     cap[2].to_string(),
-                                    to_snake_case(&cap[3]),
+                                    field_to_snake_case(&cap[3]),
                                 ));
                             }
                         }
@@ -1775,11 +2307,21 @@ fn extract_from_str(s: &str, deps: &mut Vec<(String, String, String)>, re: &Rege
         deps.push((
             cap[1].to_string(),
             cap[2].to_string(),
-            to_snake_case(&cap[3]),
+            field_to_snake_case(&cap[3]),
         ));
     }
 }
 
+/// Converts a referenced field to snake_case, keeping the "__" separator used by outputs and
+/// variables of nested stacks (e.g. bucket1__bucketArn => bucket1__bucket_arn)
+fn field_to_snake_case(field: &str) -> String {
+    field
+        .split("__")
+        .map(to_snake_case)
+        .collect::<Vec<String>>()
+        .join("__")
+}
+
 /// Check that the named claim exists and exports the named output or variable
 fn claim_reference_exists(
     module_map: &HashMap<String, &ModuleResp>,
@@ -1928,6 +2470,7 @@ mod tests {
                 sensitive: false,
                 nullable: false,
                 _type: serde_json::Value::String("string".to_string()),
+                validations: vec![],
             },
             TfVariable {
                 name: "bucket1a__tags".to_string(),
@@ -1936,6 +2479,7 @@ mod tests {
                 sensitive: false,
                 nullable: true,
                 _type: serde_json::Value::String("map".to_string()),
+                validations: vec![],
             },
             TfVariable {
                 name: "bucket2__port_mapping".to_string(),
@@ -1944,6 +2488,7 @@ mod tests {
                 sensitive: false,
                 nullable: true,
                 _type: serde_json::Value::String("list".to_string()),
+                validations: vec![],
             },
         ];
         let example_variables = serde_yaml::from_str::<serde_yaml::Value>(
@@ -1971,6 +2516,7 @@ mod tests {
             sensitive: false,
             nullable: false,
             _type: serde_json::Value::String("string".to_string()),
+            validations: vec![],
         }];
         let example_variables = serde_yaml::from_str::<serde_yaml::Value>(
             r#"
@@ -1994,6 +2540,7 @@ mod tests {
                 sensitive: false,
                 nullable: false,
                 _type: serde_json::Value::String("string".to_string()),
+                validations: vec![],
             },
             TfVariable {
                 name: "bucket1a__tags".to_string(),
@@ -2002,6 +2549,7 @@ mod tests {
                 sensitive: false,
                 nullable: true,
                 _type: serde_json::Value::String("map".to_string()),
+                validations: vec![],
             },
         ];
         let example_variables = serde_yaml::from_str::<serde_yaml::Value>(
@@ -2027,6 +2575,97 @@ mod tests {
         assert_eq!(to_camel_case("bucket_name"), "bucketName");
     }
 
+    #[test]
+    fn test_field_to_snake_case_nested_stack_output() {
+        assert_eq!(field_to_snake_case("bucketArn"), "bucket_arn");
+        assert_eq!(
+            field_to_snake_case("bucket1__bucketArn"),
+            "bucket1__bucket_arn"
+        );
+    }
+
+    #[test]
+    fn test_claim_variable_nested_stack() {
+        let claim_variables = serde_yaml::from_str::<serde_yaml::Mapping>(
+            r#"
+            bucketName: top-level
+            bucket1:
+                bucketName: nested
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            claim_variable(&claim_variables, "bucket_name"),
+            Some(&serde_yaml::Value::String("top-level".to_string()))
+        );
+        assert_eq!(
+            claim_variable(&claim_variables, "bucket1__bucket_name"),
+            Some(&serde_yaml::Value::String("nested".to_string()))
+        );
+        assert_eq!(
+            claim_variable(&claim_variables, "bucket2__bucket_name"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_stack_configuration_references() {
+        let tf_content = r#"
+module "bucket1" {
+  source      = "./S3Bucket-0.1.0"
+  bucket_name = var.bucket1__bucket_name
+}
+
+module "bucket2" {
+  source     = "./S3Bucket-0.1.0"
+  tags       = { "Arn" = "${module.bucket1.bucket_arn}-suffix" }
+  depends_on = [module.bucket1]
+}
+
+variable "bucket1__bucket_name" {
+  type        = string
+  description = "Name of the bucket"
+}
+
+output "bucket2__bucket_arn" {
+  value = module.bucket2.bucket_arn
+}
+"#;
+
+        let configuration = stack_configuration(tf_content).unwrap();
+        let root = &configuration["root_module"];
+
+        assert_eq!(
+            root["module_calls"]["bucket1"],
+            json!({
+                "source": "./S3Bucket-0.1.0",
+                "expressions": {
+                    "bucket_name": { "references": ["var.bucket1__bucket_name"] }
+                }
+            })
+        );
+        assert_eq!(
+            root["module_calls"]["bucket2"]["expressions"],
+            json!({
+                "tags": { "references": ["module.bucket1.bucket_arn", "module.bucket1"] }
+            })
+        );
+        assert_eq!(
+            root["module_calls"]["bucket2"]["depends_on"],
+            json!(["module.bucket1"])
+        );
+        assert_eq!(
+            root["variables"]["bucket1__bucket_name"],
+            json!({ "description": "Name of the bucket" })
+        );
+        assert_eq!(
+            root["outputs"]["bucket2__bucket_arn"],
+            json!({
+                "expression": { "references": ["module.bucket2.bucket_arn", "module.bucket2"] }
+            })
+        );
+    }
+
     #[test]
     fn test_collect_module_variables() {
         let claim_modules = get_example_claim_modules();
@@ -2049,6 +2688,7 @@ mod tests {
                         description: "Name of the S3 bucket".to_string(),
                         nullable: false,
                         sensitive: false,
+                        validations: vec![],
                     },
                 ),
                 (
@@ -2060,6 +2700,7 @@ mod tests {
                         description: "Some arbitrary input list".to_string(),
                         nullable: true,
                         sensitive: false,
+                        validations: vec![],
                     },
                 ),
                 (
@@ -2077,6 +2718,7 @@ mod tests {
                         description: "Tags to apply to the S3 bucket".to_string(),
                         nullable: true,
                         sensitive: false,
+                        validations: vec![],
                     },
                 ),
                 (
@@ -2088,6 +2730,7 @@ mod tests {
                         description: "Name of the S3 bucket".to_string(),
                         nullable: false,
                         sensitive: false,
+                        validations: vec![],
                     },
                 ),
                 (
@@ -2099,6 +2742,7 @@ mod tests {
                         description: "Some arbitrary input list".to_string(),
                         nullable: true,
                         sensitive: false,
+                        validations: vec![],
                     },
                 ),
                 (
@@ -2117,6 +2761,7 @@ mod tests {
                         description: "Tags to apply to the S3 bucket".to_string(),
                         nullable: true,
                         sensitive: false,
+                        validations: vec![],
                     },
                 ),
             ]);
@@ -2406,6 +3051,7 @@ output "bucket2__list_of_strings" {
             &generated_module_collection,
             &generated_variable_collection,
             &generated_dependency_map,
+            &HashMap::new(),
         );
 
         // Two versions exist (5.81.0 and 5.95.0), ensure the latest is used
@@ -2450,7 +3096,7 @@ module "bucket2" {
         let claim_modules = get_example_claim_modules();
 
         // Call the function under test
-        let module_stack_data = generate_full_terraform_module(&claim_modules).unwrap();
+        let module_stack_data = generate_full_terraform_module(&claim_modules, None).unwrap();
         let generated_terraform_module = format!(
             "{}\n{}\n{}",
             module_stack_data.terraform_module_code,
@@ -2539,6 +3185,72 @@ output "bucket2__list_of_strings" {
         assert_eq!(generated_terraform_module, expected_terraform_module);
     }
 
+    #[test]
+    fn test_generate_full_terraform_module_member_variables() {
+        let claim_modules = get_example_claim_modules();
+        let member_variables: MemberVariables = serde_yaml::from_str(
+            r#"
+            variables:
+              - variable: bucket1a.tags
+                exposure: internal
+              - variable: bucket1a.inputList
+                name: inputs
+                description: Inputs of the first bucket
+            "#,
+        )
+        .unwrap();
+
+        let module_stack_data =
+            generate_full_terraform_module(&claim_modules, Some(&member_variables)).unwrap();
+        let modules = &module_stack_data.terraform_module_code;
+        let variables = &module_stack_data.terraform_variable_code;
+
+        assert!(modules.contains("input_list = var.stack__inputs"));
+        assert!(modules.contains("tags = {\n  \"AnotherTag\" = \"something\""));
+        assert!(modules.contains("bucket_name = var.bucket1a__bucket_name"));
+        assert!(variables.contains("variable \"stack__inputs\""));
+        assert!(variables.contains("description = \"Inputs of the first bucket\""));
+        assert!(variables.contains("variable \"bucket1a__bucket_name\""));
+        assert!(!variables.contains("variable \"bucket1a__input_list\""));
+        assert!(!variables.contains("variable \"bucket1a__tags\""));
+    }
+
+    #[test]
+    fn test_apply_member_variables() {
+        let claim_modules = get_example_claim_modules();
+        let apply = |yaml: &str| {
+            let member_variables: MemberVariables = serde_yaml::from_str(yaml).unwrap();
+            apply_member_variables(
+                &mut collect_module_variables(&claim_modules),
+                Some(&member_variables),
+            )
+        };
+
+        // Referenced by bucket2
+        assert!(apply("variables: [{variable: bucket1a.bucketName, exposure: internal}]").is_err());
+        // Set from a reference
+        assert!(apply("variables: [{variable: bucket2.tags, name: tags}]").is_err());
+        assert!(apply("variables: [{variable: bucket1a.missing}]").is_err());
+        assert!(apply("variables: [{variable: bucket1a}]").is_err());
+        // Not set in the claim and without a default in the module
+        assert!(apply(
+            "default: internal\nvariables: [{variable: bucket1a.inputList, exposure: internal}]"
+        )
+        .is_err());
+
+        let bindings = apply("default: internal").unwrap();
+        assert_eq!(
+            bindings.get("bucket1a__tags"),
+            Some(&MemberVariableBinding::Internal(json!({
+                "AnotherTag": "something",
+                "Test": "hej"
+            })))
+        );
+        assert!(!bindings.contains_key("bucket1a__bucket_name"));
+        assert!(!bindings.contains_key("bucket1a__input_list"));
+        assert!(!bindings.contains_key("bucket2__tags"));
+    }
+
     #[test]
     fn test_validate_claim_modules_valid() {
         let yaml_manifest_bucket2 = r#"
@@ -2589,6 +3301,8 @@ output "bucket2__list_of_strings" {
                         providers: vec![Provider {
                             name: "aws-v5-default".to_string(),
                         }],
+                        owners: vec![],
+                        ui: None,
                     },
                 },
                 tf_outputs: vec![],
@@ -2602,6 +3316,7 @@ output "bucket2__list_of_strings" {
                         _type: Value::String("string".to_string()),
                         nullable: false,
                         sensitive: false,
+                        validations: vec![],
                     },
                     TfVariable {
                         _type: Value::String("map(string)".to_string()),
@@ -2613,6 +3328,7 @@ output "bucket2__list_of_strings" {
                         .unwrap(),
                         nullable: true,
                         sensitive: false,
+                        validations: vec![],
                     },
                 ],
                 tf_extra_environment_variables: vec![],
@@ -2623,6 +3339,11 @@ output "bucket2__list_of_strings" {
                 tf_providers: vec![example_provider_aws()],
                 deprecated: false,
                 deprecated_message: None,
+                readme: None,
+                changelog: None,
+                digest: None,
+                oci_reference: None,
+                yanked: None,
             },
         )];
 
@@ -2893,6 +3614,8 @@ output "bucket2__list_of_strings" {
                         providers: vec![Provider {
                             name: "aws-v5-default".to_string(),
                         }],
+                        owners: vec![],
+                        ui: None,
                     },
                 },
                 tf_outputs: vec![],
@@ -2906,6 +3629,7 @@ output "bucket2__list_of_strings" {
                         _type: Value::String("string".to_string()),
                         nullable: false,
                         sensitive: false,
+                        validations: vec![],
                     },
                     TfVariable {
                         _type: Value::String("map(string)".to_string()),
@@ -2917,6 +3641,7 @@ output "bucket2__list_of_strings" {
                         .unwrap(),
                         nullable: true,
                         sensitive: false,
+                        validations: vec![],
                     },
                 ],
                 tf_extra_environment_variables: vec![],
@@ -2927,6 +3652,11 @@ output "bucket2__list_of_strings" {
                 tf_providers: vec![example_provider_aws()],
                 deprecated: false,
                 deprecated_message: None,
+                readme: None,
+                changelog: None,
+                digest: None,
+                oci_reference: None,
+                yanked: None,
             },
         )];
 
@@ -3007,6 +3737,8 @@ output "bucket2__list_of_strings" {
                     providers: vec![Provider {
                         name: "aws-v5-default".to_string(),
                     }],
+                    owners: vec![],
+                    ui: None,
                 },
             },
             tf_outputs: vec![],
@@ -3020,6 +3752,7 @@ output "bucket2__list_of_strings" {
                     _type: Value::String("string".to_string()),
                     nullable: false,
                     sensitive: false,
+                    validations: vec![],
                 },
                 TfVariable {
                     _type: Value::String("map(string)".to_string()),
@@ -3031,6 +3764,7 @@ output "bucket2__list_of_strings" {
                     .unwrap(),
                     nullable: true,
                     sensitive: false,
+                    validations: vec![],
                 },
             ],
             tf_extra_environment_variables: vec![],
@@ -3041,6 +3775,11 @@ output "bucket2__list_of_strings" {
             tf_providers: vec![example_provider_aws()],
             deprecated: false,
             deprecated_message: None,
+            readme: None,
+            changelog: None,
+            digest: None,
+            oci_reference: None,
+            yanked: None,
         };
 
         let claim_modules = [
@@ -3123,6 +3862,8 @@ output "bucket2__list_of_strings" {
                     providers: vec![Provider {
                         name: "aws-v5-default".to_string(),
                     }],
+                    owners: vec![],
+                    ui: None,
                 },
             },
             tf_outputs: vec![],
@@ -3136,6 +3877,7 @@ output "bucket2__list_of_strings" {
                     _type: Value::String("string".to_string()),
                     nullable: false,
                     sensitive: false,
+                    validations: vec![],
                 },
                 TfVariable {
                     _type: Value::String("map(string)".to_string()),
@@ -3147,6 +3889,7 @@ output "bucket2__list_of_strings" {
                     .unwrap(),
                     nullable: true,
                     sensitive: false,
+                    validations: vec![],
                 },
             ],
             tf_extra_environment_variables: vec![],
@@ -3157,6 +3900,11 @@ output "bucket2__list_of_strings" {
             tf_providers: vec![example_provider_aws()],
             deprecated: false,
             deprecated_message: None,
+            readme: None,
+            changelog: None,
+            digest: None,
+            oci_reference: None,
+            yanked: None,
         };
 
         let claim_modules = [
@@ -3247,6 +3995,8 @@ output "bucket2__list_of_strings" {
                     providers: vec![Provider {
                         name: "aws-v5-default".to_string(),
                     }],
+                    owners: vec![],
+                    ui: None,
                 },
             },
             tf_outputs: vec![],
@@ -3260,6 +4010,7 @@ output "bucket2__list_of_strings" {
                     _type: Value::String("string".to_string()),
                     nullable: false,
                     sensitive: false,
+                    validations: vec![],
                 },
                 TfVariable {
                     _type: Value::String("map(string)".to_string()),
@@ -3271,6 +4022,7 @@ output "bucket2__list_of_strings" {
                     .unwrap(),
                     nullable: true,
                     sensitive: false,
+                    validations: vec![],
                 },
             ],
             tf_extra_environment_variables: vec![],
@@ -3281,6 +4033,11 @@ output "bucket2__list_of_strings" {
             tf_providers: vec![example_provider_aws()],
             deprecated: false,
             deprecated_message: None,
+            readme: None,
+            changelog: None,
+            digest: None,
+            oci_reference: None,
+            yanked: None,
         };
 
         let claim_modules = [
@@ -3371,6 +4128,8 @@ output "bucket2__list_of_strings" {
                     providers: vec![Provider {
                         name: "aws-v5-default".to_string(),
                     }],
+                    owners: vec![],
+                    ui: None,
                 },
             },
             tf_outputs: vec![TfOutput {
@@ -3389,6 +4148,7 @@ output "bucket2__list_of_strings" {
                     _type: Value::String("string".to_string()),
                     nullable: false,
                     sensitive: false,
+                    validations: vec![],
                 },
                 TfVariable {
                     _type: Value::String("map(string)".to_string()),
@@ -3400,6 +4160,7 @@ output "bucket2__list_of_strings" {
                     .unwrap(),
                     nullable: true,
                     sensitive: false,
+                    validations: vec![],
                 },
             ],
             tf_extra_environment_variables: vec![],
@@ -3410,6 +4171,11 @@ output "bucket2__list_of_strings" {
             tf_providers: vec![example_provider_aws()],
             deprecated: false,
             deprecated_message: None,
+            readme: None,
+            changelog: None,
+            digest: None,
+            oci_reference: None,
+            yanked: None,
         };
 
         let claim_modules = [
@@ -3485,6 +4251,8 @@ output "bucket2__list_of_strings" {
                     providers: vec![Provider {
                         name: "aws-v5-default".to_string(),
                     }],
+                    owners: vec![],
+                    ui: None,
                 },
             },
             tf_outputs: vec![TfOutput {
@@ -3503,6 +4271,7 @@ output "bucket2__list_of_strings" {
                     _type: Value::String("string".to_string()),
                     nullable: false,
                     sensitive: false,
+                    validations: vec![],
                 },
                 TfVariable {
                     _type: Value::String("map(string)".to_string()),
@@ -3514,6 +4283,7 @@ output "bucket2__list_of_strings" {
                     .unwrap(),
                     nullable: true,
                     sensitive: false,
+                    validations: vec![],
                 },
             ],
             tf_extra_environment_variables: vec![],
@@ -3524,6 +4294,11 @@ output "bucket2__list_of_strings" {
             tf_providers: vec![example_provider_aws()],
             deprecated: false,
             deprecated_message: None,
+            readme: None,
+            changelog: None,
+            digest: None,
+            oci_reference: None,
+            yanked: None,
         };
 
         let claim_modules = [
@@ -3642,6 +4417,8 @@ output "bucket2__list_of_strings" {
                     providers: vec![Provider {
                         name: "aws-v5-default".to_string(),
                     }],
+                    owners: vec![],
+                    ui: None,
                 },
             },
             tf_outputs: vec![TfOutput {
@@ -3660,6 +4437,7 @@ output "bucket2__list_of_strings" {
                     _type: Value::String("string".to_string()),
                     nullable: false,
                     sensitive: false,
+                    validations: vec![],
                 },
                 TfVariable {
                     _type: Value::String("map(string)".to_string()),
@@ -3671,6 +4449,7 @@ output "bucket2__list_of_strings" {
                     .unwrap(),
                     nullable: true,
                     sensitive: false,
+                    validations: vec![],
                 },
             ],
             tf_extra_environment_variables: vec![],
@@ -3681,6 +4460,11 @@ output "bucket2__list_of_strings" {
             tf_providers: vec![example_provider_aws()],
             deprecated: false,
             deprecated_message: None,
+            readme: None,
+            changelog: None,
+            digest: None,
+            oci_reference: None,
+            yanked: None,
         };
 
         let claim_modules = [
@@ -3767,6 +4551,8 @@ output "bucket2__list_of_strings" {
                     providers: vec![Provider {
                         name: "aws-v5-default".to_string(),
                     }],
+                    owners: vec![],
+                    ui: None,
                 },
             },
             tf_outputs: vec![TfOutput {
@@ -3784,6 +4570,7 @@ output "bucket2__list_of_strings" {
                 _type: Value::String("string".to_string()),
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             }],
             tf_extra_environment_variables: vec![],
             stack_data: None,
@@ -3793,6 +4580,11 @@ output "bucket2__list_of_strings" {
             tf_providers: vec![example_provider_aws()],
             deprecated: false,
             deprecated_message: None,
+            readme: None,
+            changelog: None,
+            digest: None,
+            oci_reference: None,
+            yanked: None,
         };
 
         // ModuleResp for the EC2 instance.
@@ -3825,6 +4617,8 @@ output "bucket2__list_of_strings" {
                     providers: vec![Provider {
                         name: "aws-v5-default".to_string(),
                     }],
+                    owners: vec![],
+                    ui: None,
                 },
             },
             tf_outputs: vec![],
@@ -3838,6 +4632,7 @@ output "bucket2__list_of_strings" {
                     _type: Value::String("string".to_string()),
                     nullable: false,
                     sensitive: false,
+                    validations: vec![],
                 },
                 TfVariable {
                     name: "vpc_id".to_string(),
@@ -3846,6 +4641,7 @@ output "bucket2__list_of_strings" {
                     _type: Value::String("string".to_string()),
                     nullable: false,
                     sensitive: false,
+                    validations: vec![],
                 },
             ],
             tf_extra_environment_variables: vec![],
@@ -3856,6 +4652,11 @@ output "bucket2__list_of_strings" {
             tf_providers: vec![example_provider_aws()],
             deprecated: false,
             deprecated_message: None,
+            readme: None,
+            changelog: None,
+            digest: None,
+            oci_reference: None,
+            yanked: None,
         };
 
         let claim_modules = [
@@ -3917,6 +4718,8 @@ output "bucket2__list_of_strings" {
                         providers: vec![Provider {
                             name: "aws-v5-default".to_string(),
                         }],
+                        owners: vec![],
+                        ui: None,
                     },
                 },
                 tf_outputs: vec![],
@@ -3929,6 +4732,7 @@ output "bucket2__list_of_strings" {
                     _type: Value::String("string".to_string()),
                     nullable: false,
                     sensitive: false,
+                    validations: vec![],
                 }],
                 tf_extra_environment_variables: vec![],
                 stack_data: None,
@@ -3938,6 +4742,11 @@ output "bucket2__list_of_strings" {
                 tf_providers: vec![example_provider_aws()],
                 deprecated: false,
                 deprecated_message: None,
+                readme: None,
+                changelog: None,
+                digest: None,
+                oci_reference: None,
+                yanked: None,
             },
         )];
 
@@ -3992,6 +4801,8 @@ output "bucket2__list_of_strings" {
                         providers: vec![Provider {
                             name: "aws-v5-default".to_string(),
                         }],
+                        owners: vec![],
+                        ui: None,
                     },
                 },
                 tf_outputs: vec![],
@@ -4004,6 +4815,7 @@ output "bucket2__list_of_strings" {
                     _type: Value::String("string".to_string()),
                     nullable: false,
                     sensitive: false,
+                    validations: vec![],
                 }],
                 tf_extra_environment_variables: vec![],
                 stack_data: None,
@@ -4013,6 +4825,11 @@ output "bucket2__list_of_strings" {
                 tf_providers: vec![example_provider_aws()],
                 deprecated: false,
                 deprecated_message: None,
+                readme: None,
+                changelog: None,
+                digest: None,
+                oci_reference: None,
+                yanked: None,
             },
         )];
 
@@ -4020,6 +4837,79 @@ output "bucket2__list_of_strings" {
         assert_eq!(result.is_err(), true); // it is expecting camelCase, however it is entered as snake_case
     }
 
+    #[test]
+    fn test_generate_terraform_modules_depends_on() {
+        let mut claim_modules = get_example_claim_modules();
+        claim_modules[0].0.metadata.name = "bucket3".to_string();
+        claim_modules[0].0.spec.variables = serde_yaml::Mapping::new();
+        let mut bucket1a = claim_modules[0].clone();
+        bucket1a.0.metadata.name = "bucket1a".to_string();
+        claim_modules[0].0.spec.depends_on = vec!["bucket1a".to_string()];
+        claim_modules.push(bucket1a);
+
+        assert!(validate_dependencies(&claim_modules).is_ok());
+
+        let stack_data = generate_full_terraform_module(&claim_modules, None).unwrap();
+        assert!(stack_data.terraform_module_code.contains(
+            r#"module "bucket3" {
+  source = "./s3bucket-0.0.21"
+
+  bucket_name = var.bucket3__bucket_name
+  input_list = var.bucket3__input_list
+  tags = var.bucket3__tags
+  depends_on = [module.bucket1a]
+}"#
+        ));
+
+        let configuration = stack_configuration(&stack_data.terraform_module_code).unwrap();
+        assert_eq!(
+            configuration["root_module"]["module_calls"]["bucket3"]["depends_on"],
+            serde_json::json!(["module.bucket1a"])
+        );
+    }
+
+    #[test]
+    fn test_add_module_interfaces() {
+        let mut claim_modules = get_example_claim_modules();
+        claim_modules[0].1.tf_outputs[0].value = "\"arn:aws:s3:::${var.bucket_name}\"".to_string();
+
+        let stack_data = generate_full_terraform_module(&claim_modules, None).unwrap();
+        let mut configuration = stack_configuration(&stack_preview_content(&stack_data)).unwrap();
+        add_module_interfaces(&mut configuration, &claim_modules);
+
+        let module = &configuration["root_module"]["module_calls"]["bucket1a"]["module"];
+        assert_eq!(module["variables"]["bucket_name"], serde_json::json!({}));
+        assert_eq!(
+            module["outputs"]["bucket_arn"]["expression"]["references"],
+            serde_json::json!(["var.bucket_name"])
+        );
+        assert_eq!(
+            module["outputs"]["list_of_strings"]["expression"]["references"],
+            serde_json::json!([])
+        );
+    }
+
+    #[test]
+    fn test_validate_dependencies_depends_on() {
+        let mut claim_modules = get_example_claim_modules();
+        claim_modules[0].0.spec.depends_on = vec!["bucket3".to_string()];
+        let error = validate_dependencies(&claim_modules).unwrap_err();
+        assert!(
+            matches!(&error, ModuleError::DependsOnClaimNotFound(claim, dependency) if claim == "bucket1a" && dependency == "bucket3"),
+            "Unexpected error: {:?}",
+            error
+        );
+
+        // bucket2 references outputs of bucket1a, so bucket1a can't also depend on bucket2
+        claim_modules[0].0.spec.depends_on = vec!["bucket2".to_string()];
+        let error = validate_dependencies(&claim_modules).unwrap_err();
+        assert!(
+            matches!(error, ModuleError::CircularDependency(ref cycle) if cycle.len() == 2),
+            "Unexpected error: {:?}",
+            error
+        );
+    }
+
     fn get_example_claim_modules() -> Vec<(DeploymentManifest, ModuleResp)> {
         let yaml_manifest_bucket1a = r#"
     apiVersion: infraweave.io/v1
@@ -4098,6 +4988,8 @@ output "bucket2__list_of_strings" {
                     providers: vec![Provider {
                         name: "aws-v5-default".to_string(),
                     }],
+                    owners: vec![],
+                    ui: None,
                 },
             },
             tf_outputs: vec![
@@ -4133,6 +5025,7 @@ output "bucket2__list_of_strings" {
                     _type: Value::String("string".to_string()),
                     nullable: false,
                     sensitive: false,
+                    validations: vec![],
                 },
                 // TfVariable { default: None, name: "enable_acl".to_string(), description: "Enable ACL for the S3 bucket".to_string()), _type: Value::Bool(false), nullable: Some(false), sensitive: false },
                 TfVariable {
@@ -4145,6 +5038,7 @@ output "bucket2__list_of_strings" {
                     .unwrap(),
                     nullable: true,
                     sensitive: false,
+                    validations: vec![],
                 },
                 TfVariable {
                     default: None,
@@ -4153,6 +5047,7 @@ output "bucket2__list_of_strings" {
                     _type: Value::String("list(string)".to_string()),
                     nullable: true,
                     sensitive: false,
+                    validations: vec![],
                 },
             ],
             tf_extra_environment_variables: vec![],
@@ -4163,6 +5058,11 @@ output "bucket2__list_of_strings" {
             tf_providers: vec![example_provider_aws()],
             deprecated: false,
             deprecated_message: None,
+            readme: None,
+            changelog: None,
+            digest: None,
+            oci_reference: None,
+            yanked: None,
         }
     }
 
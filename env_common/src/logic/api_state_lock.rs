@@ -0,0 +1,180 @@
+use env_defs::{
+    ApiInfraPayload, ApiInfraPayloadWithVariables, CloudProvider, DeploymentResp, ExtraData,
+};
+use serde_json::{json, Value};
+
+use super::submit_claim_job;
+use crate::interface::GenericCloudHandler;
+
+/// Command of the runner job releasing the state lock of a deployment
+pub const FORCE_UNLOCK_COMMAND: &str = "force-unlock";
+
+/// Flag of a force-unlock job holding the ID of the lock to release
+pub const LOCK_ID_FLAG: &str = "-lock-id=";
+
+/// State lock terraform failed to acquire, as reported in the `Lock Info` of the error
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateLock {
+    pub id: String,
+    pub path: String,
+    pub operation: String,
+    /// User and host of the process holding the lock, e.g. `root@ip-10-0-1-12`
+    pub who: String,
+    pub created: String,
+}
+
+impl StateLock {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "path": self.path,
+            "operation": self.operation,
+            "who": self.who,
+            "created": self.created,
+        })
+    }
+
+    /// Explains who holds the lock and how to release it, for the error text of the deployment
+    pub fn describe(&self, deployment_id: &str, environment: &str) -> String {
+        format!(
+            "The state is locked by {} since {} ({}, lock ID {}). If no job of the deployment is running anymore, release the lock with `infraweave deployments unlock {} {} --lock-id {}`",
+            self.who, self.created, self.operation, self.id, environment, deployment_id, self.id
+        )
+    }
+}
+
+/// The lock from the output of a terraform command that failed to acquire the state lock,
+/// None if the command failed for another reason
+pub fn parse_state_lock(output: &str) -> Option<StateLock> {
+    if !output.contains("Error acquiring the state lock") {
+        return None;
+    }
+    let mut lock = StateLock::default();
+    let mut in_lock_info = false;
+    for line in output.lines() {
+        let line = line.trim();
+        if line == "Lock Info:" {
+            in_lock_info = true;
+            continue;
+        }
+        if !in_lock_info {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            break;
+        };
+        let value = value.trim().to_string();
+        match key {
+            "ID" => lock.id = value,
+            "Path" => lock.path = value,
+            "Operation" => lock.operation = value,
+            "Who" => lock.who = value,
+            "Created" => lock.created = value,
+            _ => {}
+        }
+    }
+    (!lock.id.is_empty()).then_some(lock)
+}
+
+/// Starts a runner job releasing the state lock of the deployment with `terraform force-unlock`.
+/// Like other jobs it is refused while a job of the deployment is running, which may still hold
+/// the lock. Returns the job id
+pub async fn force_unlock_deployment(
+    handler: &GenericCloudHandler,
+    deployment: &DeploymentResp,
+    lock_id: &str,
+) -> Result<String, anyhow::Error> {
+    if http_client::is_http_mode_enabled() {
+        return Err(anyhow::anyhow!(
+            "Unlocking deployments is not supported in HTTP mode"
+        ));
+    }
+    if lock_id.trim().is_empty() {
+        return Err(anyhow::anyhow!("The lock ID to release is required"));
+    }
+
+    let payload = ApiInfraPayload {
+        command: FORCE_UNLOCK_COMMAND.to_string(),
+        flags: vec![format!("{}{}", LOCK_ID_FLAG, lock_id.trim())],
+        module: deployment.module.clone(),
+        module_version: deployment.module_version.clone(),
+        module_type: deployment.module_type.clone(),
+        module_track: deployment.module_track.clone(),
+        name: String::new(),
+        environment: deployment.environment.clone(),
+        deployment_id: deployment.deployment_id.clone(),
+        project_id: deployment.project_id.clone(),
+        region: deployment.region.clone(),
+        drift_detection: deployment.drift_detection.clone(),
+        next_drift_check_epoch: -1,
+        annotations: json!({}),
+        dependencies: deployment.dependencies.clone(),
+        initiated_by: handler.get_user_id().await.unwrap_or("cli".into()),
+        cpu: deployment.cpu.clone(),
+        memory: deployment.memory.clone(),
+        reference: deployment.reference.clone(),
+        extra_data: ExtraData::None,
+        trigger_reason: None,
+        plan_job_id: None,
+        targets: vec![],
+        idempotency_key: None,
+        approved_by: vec![],
+    };
+    let payload_with_variables = ApiInfraPayloadWithVariables {
+        payload,
+        variables: deployment.variables.clone(),
+    };
+    submit_claim_job(handler, &payload_with_variables).await
+}
+
+/// The lock ID a force-unlock job was started with
+pub fn lock_id_flag(payload: &ApiInfraPayload) -> Option<&str> {
+    payload
+        .flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix(LOCK_ID_FLAG))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCK_ERROR: &str = r#"
+Error: Error acquiring the state lock
+
+Error message: operation error DynamoDB: PutItem, https response error
+StatusCode: 400, ConditionalCheckFailedException: The conditional request
+failed
+Lock Info:
+  ID:        4c6b1b5e-2c1d-8f6a-0b3e-6f4d2a1c9e7b
+  Path:      tf-state-123/cli/dev/s3bucket/my-bucket/terraform.tfstate
+  Operation: OperationTypeApply
+  Who:       root@ip-10-0-1-12
+  Version:   1.8.0
+  Created:   2026-10-14 09:12:45.123456789 +0000 UTC
+  Info:
+
+Terraform acquires a state lock to protect the state from being written
+by multiple users at the same time. Please resolve the issue above and try
+again. For most commands, you can disable locking with the "-lock=false"
+flag, but this is not recommended.
+"#;
+
+    #[test]
+    fn test_parse_state_lock() {
+        assert_eq!(
+            parse_state_lock(LOCK_ERROR),
+            Some(StateLock {
+                id: "4c6b1b5e-2c1d-8f6a-0b3e-6f4d2a1c9e7b".to_string(),
+                path: "tf-state-123/cli/dev/s3bucket/my-bucket/terraform.tfstate".to_string(),
+                operation: "OperationTypeApply".to_string(),
+                who: "root@ip-10-0-1-12".to_string(),
+                created: "2026-10-14 09:12:45.123456789 +0000 UTC".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_state_lock("Error: Invalid reference\n\nA reference to a resource type"),
+            None
+        );
+    }
+}
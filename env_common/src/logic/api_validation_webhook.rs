@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use env_defs::{CloudProvider, ValidationWebhook, ValidationWebhookFailureMode};
+use futures::future::join_all;
+use log::{info, warn};
+use serde_json::Value;
+
+use crate::interface::GenericCloudHandler;
+
+/// Response a validation webhook returns for a claim: `{"allowed": false, "message": "..."}`
+#[derive(Debug)]
+struct ValidationVerdict {
+    allowed: bool,
+    message: String,
+}
+
+/// Posts `request` (the rendered claim and module metadata) to the validation webhooks of the
+/// handler's project, failing with the reasons of every webhook that rejected the claim
+pub async fn run_validation_webhooks(
+    handler: &GenericCloudHandler,
+    request: &Value,
+) -> Result<(), anyhow::Error> {
+    let project_id = handler.get_project_id();
+    let projects = handler
+        .get_all_projects()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read validation webhooks: {}", e))?;
+    let webhooks = projects
+        .into_iter()
+        .find(|project| project.project_id == project_id)
+        .map(|project| project.settings.validation_webhooks)
+        .unwrap_or_default();
+    if webhooks.is_empty() {
+        return Ok(());
+    }
+
+    info!("Validating claim with {} webhook(s)", webhooks.len());
+    let client = reqwest::Client::new();
+    let verdicts = join_all(
+        webhooks
+            .iter()
+            .map(|webhook| call_validation_webhook(&client, webhook, request)),
+    )
+    .await;
+
+    aggregate_verdicts(webhooks.iter().zip(verdicts))
+}
+
+async fn call_validation_webhook(
+    client: &reqwest::Client,
+    webhook: &ValidationWebhook,
+    request: &Value,
+) -> Result<ValidationVerdict, anyhow::Error> {
+    let response = client
+        .post(&webhook.url)
+        .timeout(Duration::from_secs(webhook.timeout_seconds))
+        .json(request)
+        .send()
+        .await?
+        .error_for_status()?;
+    let body: Value = response.json().await?;
+    let allowed = body
+        .get("allowed")
+        .and_then(|v| v.as_bool())
+        .ok_or_else(|| anyhow::anyhow!("Response has no 'allowed' verdict"))?;
+    let message = body
+        .get("message")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    Ok(ValidationVerdict { allowed, message })
+}
+
+fn aggregate_verdicts<'a>(
+    verdicts: impl Iterator<
+        Item = (
+            &'a ValidationWebhook,
+            Result<ValidationVerdict, anyhow::Error>,
+        ),
+    >,
+) -> Result<(), anyhow::Error> {
+    let mut rejections = vec![];
+    for (webhook, verdict) in verdicts {
+        match verdict {
+            Ok(verdict) if verdict.allowed => {}
+            Ok(verdict) => rejections.push(format!("{}: {}", webhook.url, verdict.message)),
+            Err(e) => match webhook.failure_mode {
+                ValidationWebhookFailureMode::Fail => {
+                    rejections.push(format!("{}: webhook failed: {}", webhook.url, e))
+                }
+                ValidationWebhookFailureMode::Ignore => {
+                    warn!("Ignoring failed validation webhook {}: {}", webhook.url, e)
+                }
+            },
+        }
+    }
+
+    if rejections.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Claim was rejected by validation webhooks:\n{}",
+            rejections.join("\n")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn webhook(url: &str, failure_mode: ValidationWebhookFailureMode) -> ValidationWebhook {
+        ValidationWebhook {
+            url: url.to_string(),
+            timeout_seconds: 10,
+            failure_mode,
+        }
+    }
+
+    fn verdict(allowed: bool, message: &str) -> Result<ValidationVerdict, anyhow::Error> {
+        Ok(ValidationVerdict {
+            allowed,
+            message: message.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_aggregate_verdicts() {
+        let cmdb = webhook("https://cmdb", ValidationWebhookFailureMode::Fail);
+        let naming = webhook("https://naming", ValidationWebhookFailureMode::Ignore);
+
+        assert!(aggregate_verdicts(
+            vec![
+                (&cmdb, verdict(true, "")),
+                (&naming, Err(anyhow::anyhow!("timed out"))),
+            ]
+            .into_iter()
+        )
+        .is_ok());
+
+        let error = aggregate_verdicts(
+            vec![
+                (&cmdb, verdict(false, "not registered in the CMDB")),
+                (
+                    &naming,
+                    verdict(false, "name does not follow the convention"),
+                ),
+            ]
+            .into_iter(),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("https://cmdb: not registered in the CMDB"));
+        assert!(error.contains("https://naming: name does not follow the convention"));
+
+        let error =
+            aggregate_verdicts(vec![(&cmdb, Err(anyhow::anyhow!("timed out")))].into_iter())
+                .unwrap_err()
+                .to_string();
+        assert!(error.contains("https://cmdb: webhook failed: timed out"));
+    }
+}
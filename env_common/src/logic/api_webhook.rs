@@ -0,0 +1,322 @@
+use std::cmp::Reverse;
+use std::time::Duration;
+
+use env_defs::{
+    ApiInfraPayload, CloudProvider, DeploymentStatus, EventData, Webhook, WebhookDelivery,
+    WebhookDeliveryStatus, WebhookEvent,
+};
+use env_utils::{get_epoch, get_timestamp};
+use futures::future::join_all;
+use hmac::{Hmac, Mac};
+use log::{error, info, warn};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+use crate::interface::GenericCloudHandler;
+use crate::logic::api_event::insert_event;
+
+pub(crate) const WEBHOOK_DELIVERY_EVENT: &str = "webhook_delivery";
+const SIGNATURE_HEADER: &str = "X-Infraweave-Signature";
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Delay before the second attempt, doubled for every attempt after it
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Posts `event` of the job to the webhooks of the deployment subscribed to it and records the
+/// outcome of each delivery as an event of the deployment. A failing webhook never fails the job,
+/// it is retried and then recorded as dead-lettered with the payload that could not be delivered
+pub async fn notify_webhooks(
+    handler: &GenericCloudHandler,
+    payload: &ApiInfraPayload,
+    event: WebhookEvent,
+    job_id: &str,
+    status: &DeploymentStatus,
+    message: &str,
+) -> Vec<WebhookDelivery> {
+    let webhooks: Vec<(&Webhook, &String)> = payload
+        .drift_detection
+        .webhooks
+        .iter()
+        .filter(|webhook| webhook.events.contains(&event))
+        .filter_map(|webhook| match &webhook.url {
+            Some(url) => Some((webhook, url)),
+            None => {
+                warn!("Webhook URL not provided");
+                None
+            }
+        })
+        .collect();
+    if webhooks.is_empty() {
+        return vec![];
+    }
+
+    info!("Sending {} to {} webhook(s)", event, webhooks.len());
+    let context = webhook_context(payload, event, job_id, status, message);
+    let client = reqwest::Client::new();
+    let deliveries = join_all(
+        webhooks
+            .iter()
+            .map(|(webhook, url)| deliver_webhook(&client, webhook, url, event, job_id, &context)),
+    )
+    .await;
+
+    for delivery in &deliveries {
+        match delivery.status {
+            WebhookDeliveryStatus::Delivered => info!("Webhook {} delivered", delivery.url),
+            WebhookDeliveryStatus::DeadLetter => error!(
+                "Webhook {} failed after {} attempt(s): {}",
+                delivery.url, delivery.attempts, delivery.error
+            ),
+        }
+        if let Err(e) = record_delivery(handler, payload, status, delivery).await {
+            error!("Failed to record webhook delivery: {}", e);
+        }
+    }
+    deliveries
+}
+
+/// Deliveries to the webhooks of a deployment, newest first
+pub async fn get_webhook_deliveries(
+    handler: &GenericCloudHandler,
+    deployment_id: &str,
+    environment: &str,
+) -> Result<Vec<WebhookDelivery>, anyhow::Error> {
+    let events = handler.get_events(deployment_id, environment).await?;
+    let mut deliveries: Vec<WebhookDelivery> = events
+        .into_iter()
+        .filter(|event| event.event == WEBHOOK_DELIVERY_EVENT)
+        .filter_map(|event| serde_json::from_value(event.metadata).ok())
+        .collect();
+    deliveries.sort_by_key(|delivery| Reverse(delivery.epoch));
+    Ok(deliveries)
+}
+
+/// Fields of the default payload, which templates can refer to as `{{field}}`. `text` makes the
+/// default payload readable by Slack and Teams incoming webhooks
+fn webhook_context(
+    payload: &ApiInfraPayload,
+    event: WebhookEvent,
+    job_id: &str,
+    status: &DeploymentStatus,
+    message: &str,
+) -> Value {
+    json!({
+        "event": event.to_string(),
+        "text": message,
+        "deployment_id": payload.deployment_id,
+        "environment": payload.environment,
+        "project_id": payload.project_id,
+        "region": payload.region,
+        "module": payload.module,
+        "module_version": payload.module_version,
+        "name": payload.name,
+        "command": payload.command,
+        "job_id": job_id,
+        "status": status.to_string(),
+        "initiated_by": payload.initiated_by,
+    })
+}
+
+/// Body posted to the webhook: its template with the placeholders filled in, or the context as JSON.
+/// Values are escaped so that placeholders inside JSON strings keep the template valid JSON
+fn render_body(webhook: &Webhook, context: &Value) -> String {
+    let Some(template) = &webhook.template else {
+        return context.to_string();
+    };
+    let mut body = template.clone();
+    if let Some(fields) = context.as_object() {
+        for (field, value) in fields {
+            let value = match value {
+                Value::String(s) => serde_json::to_string(s)
+                    .map(|quoted| quoted[1..quoted.len() - 1].to_string())
+                    .unwrap_or_default(),
+                other => other.to_string(),
+            };
+            body = body.replace(&format!("{{{{{}}}}}", field), &value);
+        }
+    }
+    body
+}
+
+/// `sha256=<hex HMAC-SHA256 of the body>`, the same scheme GitHub uses to sign its webhooks
+pub(crate) fn sign_body(secret: &str, body: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(body.as_bytes());
+    let signature: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("sha256={}", signature)
+}
+
+async fn deliver_webhook(
+    client: &reqwest::Client,
+    webhook: &Webhook,
+    url: &str,
+    event: WebhookEvent,
+    job_id: &str,
+    context: &Value,
+) -> WebhookDelivery {
+    let body = render_body(webhook, context);
+    let mut delivery = WebhookDelivery {
+        url: url.to_string(),
+        event,
+        job_id: job_id.to_string(),
+        status: WebhookDeliveryStatus::DeadLetter,
+        attempts: 0,
+        response_status: None,
+        error: String::new(),
+        payload: body.clone(),
+        epoch: get_epoch(),
+    };
+
+    let signature = match &webhook.secret_env {
+        Some(variable) => match std::env::var(variable) {
+            Ok(secret) => Some(sign_body(&secret, &body)),
+            Err(_) => {
+                delivery.error = format!(
+                    "Signing secret environment variable {} is not set",
+                    variable
+                );
+                return delivery;
+            }
+        },
+        None => None,
+    };
+
+    let max_attempts = webhook.max_attempts.max(1);
+    while delivery.attempts < max_attempts {
+        if delivery.attempts > 0 {
+            tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(delivery.attempts - 1)).await;
+        }
+        delivery.attempts += 1;
+
+        let mut request = client
+            .post(url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .header("Content-Type", "application/json")
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+        match request.send().await {
+            Ok(response) => {
+                let status = response.status();
+                delivery.response_status = Some(status.as_u16());
+                if status.is_success() {
+                    delivery.status = WebhookDeliveryStatus::Delivered;
+                    delivery.error = String::new();
+                    break;
+                }
+                delivery.error = format!("Webhook responded with {}", status);
+                // Client errors other than throttling won't succeed on a retry
+                if status.is_client_error() && status.as_u16() != 429 {
+                    break;
+                }
+            }
+            Err(e) => delivery.error = e.to_string(),
+        }
+        warn!(
+            "Attempt {} of {} to deliver webhook {} failed: {}",
+            delivery.attempts, max_attempts, url, delivery.error
+        );
+    }
+    delivery
+}
+
+async fn record_delivery(
+    handler: &GenericCloudHandler,
+    payload: &ApiInfraPayload,
+    status: &DeploymentStatus,
+    delivery: &WebhookDelivery,
+) -> Result<String, anyhow::Error> {
+    let epoch = get_epoch();
+    let event = EventData {
+        deployment_id: payload.deployment_id.clone(),
+        project_id: payload.project_id.clone(),
+        region: payload.region.clone(),
+        environment: payload.environment.clone(),
+        event: WEBHOOK_DELIVERY_EVENT.to_string(),
+        epoch,
+        error_text: delivery.error.clone(),
+        id: format!(
+            "{}-{}-{}-{}",
+            payload.module, payload.deployment_id, epoch, WEBHOOK_DELIVERY_EVENT
+        ),
+        job_id: delivery.job_id.clone(),
+        metadata: serde_json::to_value(delivery)?,
+        drift_detection: payload.drift_detection.clone(),
+        next_drift_check_epoch: payload.next_drift_check_epoch,
+        has_drifted: delivery.event == WebhookEvent::DriftDetected,
+        module: payload.module.clone(),
+        module_version: payload.module_version.clone(),
+        name: payload.name.clone(),
+        status: status.clone(),
+        timestamp: get_timestamp(),
+        output: json!({}),
+        policy_results: vec![],
+        initiated_by: payload.initiated_by.clone(),
+        event_duration: 0,
+    };
+    insert_event(handler, event).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn webhook(template: Option<&str>) -> Webhook {
+        serde_json::from_value(json!({
+            "url": "https://hooks.example.com/infraweave",
+            "template": template,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_webhook_defaults() {
+        let webhook = webhook(None);
+        assert_eq!(webhook.events, vec![WebhookEvent::DriftDetected]);
+        assert_eq!(webhook.max_attempts, 3);
+        assert_eq!(webhook.secret_env, None);
+    }
+
+    #[test]
+    fn test_render_body() {
+        let context = json!({
+            "event": "applyFailed",
+            "text": "Apply of \"bucket\" failed",
+            "deployment_id": "s3bucket/bucket",
+            "attempt": 2,
+        });
+
+        assert_eq!(
+            serde_json::from_str::<Value>(&render_body(&webhook(None), &context)).unwrap(),
+            context
+        );
+
+        let body = render_body(
+            &webhook(Some(
+                r#"{"content": "{{deployment_id}}: {{text}} ({{attempt}}) {{unknown}}"}"#,
+            )),
+            &context,
+        );
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            body["content"],
+            "s3bucket/bucket: Apply of \"bucket\" failed (2) {{unknown}}"
+        );
+    }
+
+    #[test]
+    fn test_sign_body() {
+        assert_eq!(
+            sign_body("key", "The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+}
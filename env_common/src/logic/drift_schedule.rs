@@ -0,0 +1,171 @@
+use anyhow::{anyhow, Result};
+use env_defs::DriftDetection;
+use env_utils::CronSchedule;
+use humantime::parse_duration;
+
+/// Blackout windows a check is moved past before giving up, so overlapping windows that cover
+/// every run of the schedule don't loop forever
+const MAX_BLACKOUT_SHIFTS: usize = 100;
+
+/// Checks that the schedule, interval and blackout windows of the drift detection can be parsed
+pub fn validate_drift_detection(drift_detection: &DriftDetection) -> Result<()> {
+    match &drift_detection.schedule {
+        Some(schedule) => {
+            CronSchedule::parse(schedule)?;
+        }
+        None if drift_detection.enabled => {
+            parse_duration(&drift_detection.interval).map_err(|e| {
+                anyhow!(
+                    "Invalid drift detection interval '{}': {}",
+                    drift_detection.interval,
+                    e
+                )
+            })?;
+        }
+        None => {}
+    }
+    for window in &drift_detection.blackout_windows {
+        CronSchedule::parse(&window.schedule)?;
+        parse_duration(&window.duration).map_err(|e| {
+            anyhow!(
+                "Invalid blackout window duration '{}': {}",
+                window.duration,
+                e
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Epoch in milliseconds of the next drift check after `now`, from the cron schedule if set and
+/// otherwise the interval, moved past any blackout window it falls into. None if no time outside
+/// the blackout windows matches the schedule
+pub fn next_drift_check_epoch(drift_detection: &DriftDetection, now: u128) -> Result<Option<u128>> {
+    validate_drift_detection(drift_detection)?;
+    let schedule = drift_detection
+        .schedule
+        .as_deref()
+        .map(CronSchedule::parse)
+        .transpose()?;
+    let blackout_windows = drift_detection
+        .blackout_windows
+        .iter()
+        .map(|window| {
+            Ok((
+                CronSchedule::parse(&window.schedule)?,
+                parse_duration(&window.duration)?.as_millis(),
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut next = match &schedule {
+        Some(schedule) => schedule.next_after(now),
+        None => Some(now + parse_duration(&drift_detection.interval)?.as_millis()),
+    };
+    for _ in 0..MAX_BLACKOUT_SHIFTS {
+        let candidate = match next {
+            Some(candidate) => candidate,
+            None => return Ok(None),
+        };
+        // The window covering the candidate is the latest one starting at or before it
+        let blackout_end = blackout_windows
+            .iter()
+            .filter_map(|(window, duration)| {
+                let start = window.next_after(candidate.saturating_sub(*duration))?;
+                (start <= candidate && *duration > 0).then_some(start + duration)
+            })
+            .max();
+        next = match (blackout_end, &schedule) {
+            (None, _) => return Ok(Some(candidate)),
+            (Some(end), Some(schedule)) => schedule.next_after(end - 1),
+            (Some(end), None) => Some(end),
+        };
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use env_defs::BlackoutWindow;
+
+    // 2024-03-15T10:07:30Z, a Friday
+    const NOW: u128 = 1_710_497_250_000;
+    const HOUR: u128 = 60 * 60 * 1000;
+
+    fn drift_detection(
+        interval: &str,
+        schedule: Option<&str>,
+        blackout_windows: &[(&str, &str)],
+    ) -> DriftDetection {
+        DriftDetection {
+            enabled: true,
+            interval: interval.to_string(),
+            auto_remediate: false,
+            webhooks: vec![],
+            schedule: schedule.map(str::to_string),
+            blackout_windows: blackout_windows
+                .iter()
+                .map(|(schedule, duration)| BlackoutWindow {
+                    schedule: schedule.to_string(),
+                    duration: duration.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn next(drift_detection: &DriftDetection) -> Option<String> {
+        next_drift_check_epoch(drift_detection, NOW)
+            .unwrap()
+            .map(env_utils::epoch_to_timestamp)
+    }
+
+    #[test]
+    fn test_next_drift_check_epoch_interval() {
+        let dd = drift_detection("2h", None, &[]);
+        assert_eq!(
+            next_drift_check_epoch(&dd, NOW).unwrap(),
+            Some(NOW + 2 * HOUR)
+        );
+    }
+
+    #[test]
+    fn test_next_drift_check_epoch_schedule() {
+        let dd = drift_detection("2h", Some("0 6 * * *"), &[]);
+        assert_eq!(next(&dd).unwrap(), "2024-03-16T06:00:00.000Z");
+    }
+
+    #[test]
+    fn test_next_drift_check_epoch_blackout_window() {
+        // Weekend freeze from Friday 22:00 until Monday 06:00
+        let weekend = [("0 22 * * fri", "56h")];
+
+        let dd = drift_detection("2h", Some("0 */4 * * *"), &weekend);
+        assert_eq!(next(&dd).unwrap(), "2024-03-15T12:00:00.000Z");
+
+        let dd = drift_detection("14h", None, &weekend);
+        assert_eq!(next(&dd).unwrap(), "2024-03-18T06:00:00.000Z");
+
+        let dd = drift_detection("2h", Some("0 23 * * *"), &weekend);
+        assert_eq!(next(&dd).unwrap(), "2024-03-18T23:00:00.000Z");
+    }
+
+    #[test]
+    fn test_next_drift_check_epoch_always_blacked_out() {
+        let dd = drift_detection("2h", Some("0 12 * * *"), &[("0 0 * * *", "24h")]);
+        assert_eq!(next(&dd), None);
+    }
+
+    #[test]
+    fn test_validate_drift_detection() {
+        assert!(validate_drift_detection(&drift_detection("1h", Some("0 6 * * *"), &[])).is_ok());
+        assert!(validate_drift_detection(&drift_detection("1h", Some("0 6 * *"), &[])).is_err());
+        assert!(validate_drift_detection(&drift_detection("soon", None, &[])).is_err());
+        assert!(validate_drift_detection(&drift_detection(
+            "1h",
+            None,
+            &[("0 22 * * fri", "all weekend")]
+        ))
+        .is_err());
+    }
+}
@@ -1,17 +1,31 @@
+mod api_approval;
+mod api_audit;
 mod api_change_record;
+mod api_claim_policy;
 mod api_deployment;
 mod api_event;
+mod api_import;
 mod api_infra;
+mod api_job_queue;
+mod api_job_stats;
 mod api_log;
 mod api_module;
 #[cfg(test)]
 mod api_module_test;
+mod api_module_testing;
 mod api_notification;
 mod api_oci_registry;
+mod api_platform_config;
 mod api_policy;
+mod api_policy_testing;
 mod api_provider;
+mod api_retention;
 mod api_stack;
+mod api_state_lock;
+mod api_validation_webhook;
+mod api_webhook;
 mod common;
+mod drift_schedule;
 mod tf_input_resolver;
 mod tf_provider_mgmt;
 mod tf_root_module;
@@ -20,34 +34,87 @@ mod utils;
 pub use api_module::{
     compare_latest_version, deprecate_module, download_to_vec_from_modules,
     get_modules_download_url, precheck_module, publish_module, publish_module_from_zip,
-    server_publish_module, upload_module,
+    server_publish_module, set_module_oci_reference, unyank_module, upload_module, yank_module,
+};
+
+pub use api_module_testing::{
+    module_test_junit_report, run_module_tests, ModuleTestOptions, ModuleTestResult,
 };
 
 pub use utils::ModuleType;
 
-pub use api_stack::{deprecate_stack, get_stack_preview, publish_stack, server_publish_stack};
+pub use api_stack::{
+    deprecate_stack, get_stack_preview, get_stack_preview_configuration, publish_stack,
+    server_publish_stack, unyank_stack, yank_stack,
+};
 
-pub use api_deployment::set_deployment;
+pub use api_deployment::{get_deployment_outputs, resolve_value_from_refs, set_deployment};
 
 pub use api_event::insert_event;
 
+pub use api_audit::{
+    audit_event, get_audit_events, sign_audit_log, verify_audit_log, AUDIT_SIGNING_KEY_ENV,
+};
+
+pub use api_import::{import_deployment, IMPORT_COMMAND};
+
+pub use api_state_lock::{
+    force_unlock_deployment, lock_id_flag, parse_state_lock, StateLock, FORCE_UNLOCK_COMMAND,
+};
+
+pub use api_policy_testing::{
+    policy_env_data, run_policy_tests, PolicyTestResult, DENY_FIXTURE_SUFFIX,
+};
+
+pub use drift_schedule::{next_drift_check_epoch, validate_drift_detection};
+
 pub use api_notification::publish_notification;
 
 pub use api_infra::{
-    check_module_deprecation, destroy_infra, driftcheck_infra, get_deployment_details,
-    insert_request_event, is_deployment_in_progress, is_deployment_plan_in_progress, mutate_infra,
-    run_claim, submit_claim_job, validate_and_prepare_claim,
+    apply_plan_infra, break_glass_claim, check_module_deprecation, check_module_yank,
+    dependents_to_trigger, destroy_infra, driftcheck_infra, get_artifact_verification_policy,
+    get_cascade_destroy_order, get_deployment_details, get_required_approvals,
+    insert_request_event, is_deployment_in_progress, is_deployment_plan_in_progress,
+    is_identical_submission, mutate_infra, run_break_glass_module, run_claim, submit_claim_job,
+    submit_pending_approval, trigger_dependent_infra, validate_and_prepare_claim,
+    with_idempotency_key, BREAK_GLASS_TRIGGER_REASON,
 };
 
-pub use api_change_record::{insert_infra_change_record, upload_file_to_change_records};
+pub use api_change_record::{
+    download_file_from_change_records, get_drift_report, insert_infra_change_record,
+    upload_file_to_change_records,
+};
+
+pub use api_job_queue::{
+    admit_job, get_job_queue, get_max_concurrent_jobs, hold_job_slot, is_queued_job_id,
+    queued_job_state, release_job_slot, start_queued_jobs, JobAdmission, QueuedJobState,
+};
+
+pub use api_job_stats::{record_job_stats, runner_size};
+
+pub use api_approval::{
+    decide_approval, get_pending_approvals, start_approved_job, ApprovalOutcome,
+};
 
 pub use api_log::read_logs;
 
-pub use api_policy::publish_policy;
+pub use api_policy::{get_applicable_policies, publish_policy, publish_policy_pack};
+
+pub use api_claim_policy::run_claim_policy_checks;
+
+pub use api_validation_webhook::run_validation_webhooks;
+
+pub use api_webhook::{get_webhook_deliveries, notify_webhooks};
+
+pub use api_platform_config::{
+    apply_platform_config, plan_platform_config, read_platform_config, ConfigAction, ConfigChange,
+};
+
+pub use api_retention::{prune_history, PruneOptions, PruneSummary};
 
 pub use common::{PROJECT_ID, REGION};
 
-pub use api_oci_registry::OCIRegistryProvider;
+pub use api_oci_registry::{OCIRegistryProvider, PulledModule};
 
 pub use api_provider::{
     download_provider_to_vec, publish_provider, upload_provider, upload_provider_cache,
@@ -1,4 +1,4 @@
-use env_defs::{DeploymentManifest, ProviderResp};
+use env_defs::{DeploymentManifest, ModuleResp, ProviderResp};
 use env_utils::to_camel_case;
 use hcl::{
     expr::{Traversal, TraversalOperator, Variable},
@@ -21,7 +21,12 @@ pub fn module_block(
             Expression::String(format!(
                 "./{}-{}",
                 deployment.kind.clone(),
-                deployment.spec.module_version.clone().unwrap()
+                deployment
+                    .spec
+                    .module_version
+                    .clone()
+                    .or(deployment.spec.stack_version.clone())
+                    .unwrap()
             )),
         ))
         .add_attributes(variables.clone())
@@ -36,22 +41,20 @@ pub fn module_block(
         .build()
 }
 
+/// Module inputs are paired with the variable they are set from. Inputs without one are fixed to
+/// the claim value, or left to the module default if the claim doesn't set them
 pub fn variables(
-    module_inputs: &Vec<(String, String)>,
+    module_inputs: &Vec<(String, Option<String>)>,
     deployment: &DeploymentManifest,
     input_resolver: &TfInputResolver,
 ) -> Vec<Attribute> {
     let mut return_val: Vec<Attribute> = Vec::new();
     for (input_name, fq_input_name) in module_inputs {
-        if let Some(val) = deployment
-            .spec
-            .variables
-            .get(&serde_yaml::Value::String(to_camel_case(input_name)))
-        {
+        if let Some(val) = claim_variable(&deployment.spec.variables, input_name) {
             let mut expr = input_resolver
                 .resolve(val.clone())
                 .unwrap_or_else(|e| panic!("{e}"));
-            if can_be_variable(&expr) {
+            if let Some(fq_input_name) = fq_input_name.as_ref().filter(|_| can_be_variable(&expr)) {
                 expr = Expression::from(
                     hcl::expr::Traversal::builder(Variable::new("var").unwrap())
                         .attr(fq_input_name.to_string())
@@ -65,7 +68,7 @@ pub fn variables(
                 deployment.metadata.name
             );
             return_val.push(Attribute::new(input_name.to_string(), expr));
-        } else {
+        } else if let Some(fq_input_name) = fq_input_name {
             return_val.push(Attribute::new(
                 input_name.to_string(),
                 Expression::from(
@@ -79,6 +82,21 @@ pub fn variables(
     return_val
 }
 
+/// Looks up the claim value for a module input. Inputs of nested stacks are named
+/// `<claim>__<variable>` and are set as a mapping per claim, like in stack deployment claims.
+pub fn claim_variable<'a>(
+    variables: &'a serde_yaml::Mapping,
+    input_name: &str,
+) -> Option<&'a serde_yaml::Value> {
+    match input_name.split_once("__") {
+        Some((claim_name, nested_input_name)) => variables
+            .get(serde_yaml::Value::String(to_camel_case(claim_name)))
+            .and_then(|value| value.as_mapping())
+            .and_then(|mapping| claim_variable(mapping, nested_input_name)),
+        None => variables.get(serde_yaml::Value::String(to_camel_case(input_name))),
+    }
+}
+
 // TODO: Check this, I believe that Expression::Array, Expression::Object can never be variable. Since the assignment will be wonky, I think.
 fn can_be_variable(expr: &Expression) -> bool {
     match expr {
@@ -104,6 +122,49 @@ pub fn providers(provider_resps: &Vec<ProviderResp>) -> Vec<(ObjectKey, Expressi
         .collect::<Vec<(ObjectKey, Expression)>>()
 }
 
+/// Builds the `terraform` block for a stack used as a child module in another stack.
+///
+/// Provider configurations are inherited from the parent stack, so the nested stack only
+/// declares which providers (and aliases) it expects to be passed in.
+pub fn nested_stack_terraform_block(stack: &ModuleResp) -> Block {
+    let configuration_names: Vec<String> = stack
+        .tf_providers
+        .iter()
+        .map(|provider_resp| provider_resp.manifest.spec.configuration_name())
+        .collect();
+
+    let mut required_providers = Block::builder("required_providers");
+    for required_provider in &stack.tf_required_providers {
+        let configuration_aliases: Vec<Expression> = configuration_names
+            .iter()
+            .filter(|name| {
+                name.split_once('.')
+                    .is_some_and(|(provider, _)| provider == required_provider.name)
+            })
+            .map(|name| config_name_to_expression(name.clone()))
+            .collect();
+
+        let mut attributes = vec![(
+            ObjectKey::Identifier(Identifier::new("source").unwrap()),
+            Expression::String(required_provider.source.clone()),
+        )];
+        if !configuration_aliases.is_empty() {
+            attributes.push((
+                ObjectKey::Identifier(Identifier::new("configuration_aliases").unwrap()),
+                Expression::Array(configuration_aliases),
+            ));
+        }
+        required_providers = required_providers.add_attribute(Attribute::new(
+            required_provider.name.clone(),
+            Expression::Object(Object::from(attributes)),
+        ));
+    }
+
+    Block::builder("terraform")
+        .add_block(required_providers.build())
+        .build()
+}
+
 fn config_name_to_expression(provider_name: String) -> Expression {
     let parts: Vec<&str> = provider_name.split(".").collect();
     let first = Expression::Variable(Variable::new(parts[0]).unwrap());
@@ -0,0 +1,434 @@
+// Evaluates the DynamoDB key condition and filter expressions of the queries in env_aws against
+// items stored as JSON, so the local backend can reuse the same queries
+use std::cmp::Ordering;
+
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Placeholder(String),
+    LParen,
+    RParen,
+    Comma,
+    Op(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Path(Vec<String>),
+    Value(Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Compare(Operand, String, Operand),
+    Between(Operand, Operand, Operand),
+    BeginsWith(Operand, Operand),
+    Contains(Operand, Operand),
+    AttributeExists(Operand),
+    AttributeNotExists(Operand),
+}
+
+impl Expression {
+    /// Parses `expression`, resolving `#name` and `:value` placeholders from the query
+    pub fn parse(
+        expression: &str,
+        names: Option<&Map<String, Value>>,
+        values: Option<&Map<String, Value>>,
+    ) -> Result<Expression> {
+        let mut parser = Parser {
+            tokens: tokenize(expression)?,
+            position: 0,
+            names,
+            values,
+        };
+        let parsed = parser.or()?;
+        if parser.position != parser.tokens.len() {
+            return Err(anyhow!(
+                "Unexpected {:?} in expression \"{}\"",
+                parser.tokens[parser.position],
+                expression
+            ));
+        }
+        Ok(parsed)
+    }
+
+    pub fn matches(&self, item: &Value) -> bool {
+        match self {
+            Expression::And(lhs, rhs) => lhs.matches(item) && rhs.matches(item),
+            Expression::Or(lhs, rhs) => lhs.matches(item) || rhs.matches(item),
+            Expression::Not(inner) => !inner.matches(item),
+            Expression::Compare(lhs, op, rhs) => {
+                let (lhs, rhs) = (lhs.resolve(item), rhs.resolve(item));
+                if op == "<>" {
+                    return lhs != rhs;
+                }
+                let (Some(lhs), Some(rhs)) = (lhs, rhs) else {
+                    return false;
+                };
+                match compare(lhs, rhs) {
+                    Some(ordering) => match op.as_str() {
+                        "=" => ordering == Ordering::Equal,
+                        "<" => ordering == Ordering::Less,
+                        "<=" => ordering != Ordering::Greater,
+                        ">" => ordering == Ordering::Greater,
+                        ">=" => ordering != Ordering::Less,
+                        _ => false,
+                    },
+                    None => false,
+                }
+            }
+            Expression::Between(operand, low, high) => {
+                match (operand.resolve(item), low.resolve(item), high.resolve(item)) {
+                    (Some(value), Some(low), Some(high)) => {
+                        compare(value, low).is_some_and(|o| o != Ordering::Less)
+                            && compare(value, high).is_some_and(|o| o != Ordering::Greater)
+                    }
+                    _ => false,
+                }
+            }
+            Expression::BeginsWith(operand, prefix) => {
+                match (operand.resolve(item), prefix.resolve(item)) {
+                    (Some(Value::String(value)), Some(Value::String(prefix))) => {
+                        value.starts_with(prefix.as_str())
+                    }
+                    _ => false,
+                }
+            }
+            Expression::Contains(operand, needle) => {
+                match (operand.resolve(item), needle.resolve(item)) {
+                    (Some(Value::String(value)), Some(Value::String(needle))) => {
+                        value.contains(needle.as_str())
+                    }
+                    (Some(Value::Array(values)), Some(needle)) => values.contains(needle),
+                    _ => false,
+                }
+            }
+            Expression::AttributeExists(operand) => operand.resolve(item).is_some(),
+            Expression::AttributeNotExists(operand) => operand.resolve(item).is_none(),
+        }
+    }
+
+    /// The value `attribute` must equal for the whole expression to match, used to narrow down
+    /// the items read from the store
+    pub fn required_value(&self, attribute: &str) -> Option<&Value> {
+        match self {
+            Expression::And(lhs, rhs) => lhs
+                .required_value(attribute)
+                .or_else(|| rhs.required_value(attribute)),
+            Expression::Compare(Operand::Path(path), op, Operand::Value(value))
+                if op == "=" && path.len() == 1 && path[0] == attribute =>
+            {
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Attributes the expression compares, in order, e.g. `["PK", "SK"]` for a key condition
+    pub fn attributes(&self) -> Vec<String> {
+        let operand_attribute = |operand: &Operand| match operand {
+            Operand::Path(path) => Some(path.join(".")),
+            Operand::Value(_) => None,
+        };
+        match self {
+            Expression::And(lhs, rhs) | Expression::Or(lhs, rhs) => {
+                let mut attributes = lhs.attributes();
+                attributes.extend(rhs.attributes());
+                attributes
+            }
+            Expression::Not(inner) => inner.attributes(),
+            Expression::Compare(operand, _, _)
+            | Expression::Between(operand, _, _)
+            | Expression::BeginsWith(operand, _)
+            | Expression::Contains(operand, _)
+            | Expression::AttributeExists(operand)
+            | Expression::AttributeNotExists(operand) => {
+                operand_attribute(operand).into_iter().collect()
+            }
+        }
+    }
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, item: &'a Value) -> Option<&'a Value> {
+        match self {
+            Operand::Value(value) => Some(value),
+            Operand::Path(path) => path
+                .iter()
+                .try_fold(item, |value, segment| value.get(segment))
+                .filter(|value| !value.is_null()),
+        }
+    }
+}
+
+/// Ordering of two values of the same type, as DynamoDB only compares strings with strings and
+/// numbers with numbers
+pub fn compare(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (Value::String(lhs), Value::String(rhs)) => Some(lhs.cmp(rhs)),
+        (Value::Number(lhs), Value::Number(rhs)) => lhs.as_f64()?.partial_cmp(&rhs.as_f64()?),
+        (lhs, rhs) => (lhs == rhs).then_some(Ordering::Equal),
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = expression.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | ',' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    _ => Token::Comma,
+                });
+            }
+            '=' => {
+                chars.next();
+                tokens.push(Token::Op("=".to_string()));
+            }
+            '<' | '>' => {
+                chars.next();
+                let mut op = c.to_string();
+                if let Some(&next) = chars.peek() {
+                    if next == '=' || (c == '<' && next == '>') {
+                        op.push(next);
+                        chars.next();
+                    }
+                }
+                tokens.push(Token::Op(op));
+            }
+            c if c == ':' || c == '#' || c == '_' || c.is_alphanumeric() => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == ':' || c == '#' || c == '_' || c == '.' || c.is_alphanumeric() {
+                        word.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if word.starts_with(':') {
+                    tokens.push(Token::Placeholder(word));
+                } else {
+                    tokens.push(Token::Name(word));
+                }
+            }
+            other => {
+                return Err(anyhow!(
+                    "Unexpected character '{}' in expression \"{}\"",
+                    other,
+                    expression
+                ))
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    names: Option<&'a Map<String, Value>>,
+    values: Option<&'a Map<String, Value>>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unexpected end of expression"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Name(name)) if name.eq_ignore_ascii_case(keyword))
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        let token = self.next()?;
+        if token != expected {
+            return Err(anyhow!("Expected {:?}, found {:?}", expected, token));
+        }
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<Expression> {
+        let mut lhs = self.and()?;
+        while self.is_keyword("OR") {
+            self.position += 1;
+            lhs = Expression::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expression> {
+        let mut lhs = self.not()?;
+        while self.is_keyword("AND") {
+            self.position += 1;
+            lhs = Expression::And(Box::new(lhs), Box::new(self.not()?));
+        }
+        Ok(lhs)
+    }
+
+    fn not(&mut self) -> Result<Expression> {
+        if self.is_keyword("NOT") {
+            self.position += 1;
+            return Ok(Expression::Not(Box::new(self.not()?)));
+        }
+        self.condition()
+    }
+
+    fn condition(&mut self) -> Result<Expression> {
+        if self.peek() == Some(&Token::LParen) {
+            self.position += 1;
+            let inner = self.or()?;
+            self.expect(Token::RParen)?;
+            return Ok(inner);
+        }
+
+        if let (Some(Token::Name(name)), Some(Token::LParen)) =
+            (self.peek(), self.tokens.get(self.position + 1))
+        {
+            let function = name.to_lowercase();
+            self.position += 2;
+            let mut args = vec![self.operand()?];
+            while self.peek() == Some(&Token::Comma) {
+                self.position += 1;
+                args.push(self.operand()?);
+            }
+            self.expect(Token::RParen)?;
+            let mut args = args.into_iter();
+            let mut arg = || {
+                args.next()
+                    .ok_or_else(|| anyhow!("Missing argument for {}", function))
+            };
+            return Ok(match function.as_str() {
+                "begins_with" => Expression::BeginsWith(arg()?, arg()?),
+                "contains" => Expression::Contains(arg()?, arg()?),
+                "attribute_exists" => Expression::AttributeExists(arg()?),
+                "attribute_not_exists" => Expression::AttributeNotExists(arg()?),
+                _ => return Err(anyhow!("Unsupported function {}", function)),
+            });
+        }
+
+        let lhs = self.operand()?;
+        if self.is_keyword("BETWEEN") {
+            self.position += 1;
+            let low = self.operand()?;
+            if !self.is_keyword("AND") {
+                return Err(anyhow!("Expected AND in BETWEEN condition"));
+            }
+            self.position += 1;
+            let high = self.operand()?;
+            return Ok(Expression::Between(lhs, low, high));
+        }
+        match self.next()? {
+            Token::Op(op) => Ok(Expression::Compare(lhs, op, self.operand()?)),
+            other => Err(anyhow!("Expected comparison, found {:?}", other)),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        match self.next()? {
+            Token::Placeholder(placeholder) => self
+                .values
+                .and_then(|values| values.get(&placeholder))
+                .cloned()
+                .map(Operand::Value)
+                .ok_or_else(|| anyhow!("Missing value for {}", placeholder)),
+            Token::Name(name) => name
+                .split('.')
+                .map(|segment| match segment.strip_prefix('#') {
+                    Some(_) => self
+                        .names
+                        .and_then(|names| names.get(segment))
+                        .and_then(|name| name.as_str())
+                        .map(str::to_string)
+                        .ok_or_else(|| anyhow!("Missing name for {}", segment)),
+                    None => Ok(segment.to_string()),
+                })
+                .collect::<Result<Vec<_>>>()
+                .map(Operand::Path),
+            other => Err(anyhow!("Expected attribute or value, found {:?}", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn parse(expression: &str, names: Value, values: Value) -> Expression {
+        Expression::parse(expression, names.as_object(), values.as_object()).unwrap()
+    }
+
+    #[test]
+    fn test_key_condition() {
+        let expression = parse(
+            "PK = :module AND begins_with(SK, :sk)",
+            json!({}),
+            json!({":module": "MODULE#s3bucket", ":sk": "VERSION#"}),
+        );
+        assert!(expression.matches(&json!({"PK": "MODULE#s3bucket", "SK": "VERSION#000.001.000"})));
+        assert!(!expression.matches(&json!({"PK": "MODULE#s3bucket", "SK": "LATEST"})));
+        assert!(!expression.matches(&json!({"PK": "MODULE#other", "SK": "VERSION#000.001.000"})));
+        assert_eq!(
+            expression.required_value("PK"),
+            Some(&json!("MODULE#s3bucket"))
+        );
+        assert_eq!(expression.attributes(), vec!["PK", "SK"]);
+    }
+
+    #[test]
+    fn test_filter_expression() {
+        let expression = parse(
+            "(attribute_not_exists(deprecated) OR deprecated = :false) AND (NOT begins_with(version, :dev_prefix)) AND #filter0 <> :filter0",
+            json!({"#filter0": "status"}),
+            json!({":false": false, ":dev_prefix": "0.0.0-dev", ":filter0": "failed"}),
+        );
+        assert!(expression.matches(&json!({"version": "0.1.0", "status": "successful"})));
+        assert!(expression.matches(&json!({"version": "0.1.0", "deprecated": false})));
+        assert!(!expression.matches(&json!({"version": "0.1.0", "deprecated": true})));
+        assert!(!expression.matches(&json!({"version": "0.0.0-dev1"})));
+        assert!(!expression.matches(&json!({"version": "0.1.0", "status": "failed"})));
+    }
+
+    #[test]
+    fn test_between() {
+        let expression = parse(
+            "deleted_SK_base = :base AND next_drift_check_epoch BETWEEN :start AND :end",
+            json!({}),
+            json!({":base": "DEPLOYMENT", ":start": 0, ":end": 100}),
+        );
+        assert!(expression
+            .matches(&json!({"deleted_SK_base": "DEPLOYMENT", "next_drift_check_epoch": 50})));
+        assert!(!expression
+            .matches(&json!({"deleted_SK_base": "DEPLOYMENT", "next_drift_check_epoch": 150})));
+        assert!(!expression.matches(&json!({"deleted_SK_base": "DEPLOYMENT"})));
+    }
+
+    #[test]
+    fn test_missing_placeholder() {
+        assert!(Expression::parse("PK = :pk", None, None).is_err());
+    }
+}
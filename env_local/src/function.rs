@@ -0,0 +1,78 @@
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde_json::{json, Value};
+
+use crate::{runner, store::LocalStore};
+
+/// Handles the events otherwise sent to the API function of the cloud, see `env_defs::events`
+pub async fn run_function(store: &LocalStore, payload: &Value) -> Result<Value> {
+    let event = payload["event"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Missing event in payload"))?;
+    let data = &payload["data"];
+    let table = || {
+        payload["table"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Missing table for {}", event))
+    };
+    let file = || match (data["bucket_name"].as_str(), data["key"].as_str()) {
+        (Some(bucket), Some(key)) => Ok((bucket, key)),
+        _ => Err(anyhow!("Missing bucket_name or key for {}", event)),
+    };
+
+    match event {
+        "insert_db" => {
+            store.put_item(table()?, data)?;
+            Ok(json!({}))
+        }
+        "transact_write" => {
+            store.transact_write(&payload["items"])?;
+            Ok(json!({}))
+        }
+        "read_db" => Ok(json!({ "Items": store.query(table()?, &data["query"])? })),
+        "upload_file_base64" => {
+            let (bucket, key) = file()?;
+            let content = BASE64.decode(data["base64_content"].as_str().unwrap_or_default())?;
+            store.write_file(bucket, key, &content)?;
+            Ok(json!({}))
+        }
+        "upload_file_url" => {
+            let (bucket, key) = file()?;
+            let url = data["url"]
+                .as_str()
+                .ok_or_else(|| anyhow!("Missing url for {}", event))?;
+            let content = env_utils::download_zip_to_vec(url).await?;
+            store.write_file(bucket, key, &content)?;
+            Ok(json!({}))
+        }
+        "generate_presigned_url" => {
+            let (bucket, key) = file()?;
+            Ok(json!({ "url": store.file_url(bucket, key) }))
+        }
+        "start_runner" => Ok(json!({ "job_id": runner::start_runner(store, data)? })),
+        "get_job_status" => runner::get_job_status(
+            store,
+            data["job_id"]
+                .as_str()
+                .ok_or_else(|| anyhow!("Missing job_id"))?,
+        ),
+        "read_logs" => runner::read_logs(
+            store,
+            data["job_id"]
+                .as_str()
+                .ok_or_else(|| anyhow!("Missing job_id"))?,
+        ),
+        "get_environment_variables" => Ok(json!({})),
+        "publish_notification" => {
+            log::info!(
+                "Notification (not published by the local backend): {}",
+                data
+            );
+            Ok(json!({}))
+        }
+        _ => Err(anyhow!(
+            "Unsupported event for the local backend: {}",
+            event
+        )),
+    }
+}
@@ -0,0 +1,10 @@
+mod expression;
+mod function;
+mod provider;
+mod runner;
+mod store;
+mod utils;
+
+pub use provider::LocalCloudProvider;
+pub use store::LocalStore;
+pub use utils::{get_local_dir, get_project_id, get_region};
@@ -0,0 +1,565 @@
+use async_trait::async_trait;
+use env_defs::{
+    CloudProvider, Dependent, DeploymentResp, EventData, GenericFunctionResponse,
+    InfraChangeRecord, JobQueueEntry, JobStats, JobStatus, ModuleResp, PolicyPackResp, PolicyResp,
+    ProjectData, ProviderResp,
+};
+use env_utils::{
+    _get_change_records, _get_dependents, _get_deployment, _get_deployment_and_dependents,
+    _get_deployments, _get_events, _get_job_queue, _get_job_stats, _get_module_optional,
+    _get_modules, _get_policies, _get_policy, _get_policy_pack, _get_policy_packs,
+    _get_provider_optional, _get_providers, get_projects,
+};
+use serde_json::{json, Value};
+use std::{future::Future, pin::Pin};
+
+use crate::{function, store::LocalStore};
+
+/// Backend storing everything on this machine, for running InfraWeave offline in integration
+/// tests and demos. Selected with `INFRAWEAVE_BACKEND=local`.
+///
+/// Reads use the same queries as AWS, evaluated against a SQLite database, and jobs run the
+/// runner as a local process with Terraform's local backend
+#[derive(Clone)]
+pub struct LocalCloudProvider {
+    pub project_id: String,
+    pub region: String,
+    pub function_endpoint: Option<String>,
+    pub store: LocalStore,
+}
+
+impl LocalCloudProvider {
+    fn state_key(&self, environment: &str, deployment_id: &str) -> String {
+        format!(
+            "{}{}/{}/terraform.tfstate",
+            self.get_storage_basepath(),
+            environment,
+            deployment_id
+        )
+    }
+
+    fn default_project(&self) -> ProjectData {
+        ProjectData {
+            project_id: self.project_id.clone(),
+            name: self.project_id.clone(),
+            description: "Local project".to_string(),
+            regions: vec![self.region.clone()],
+            repositories: vec![],
+            settings: Default::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl CloudProvider for LocalCloudProvider {
+    fn get_project_id(&self) -> &str {
+        &self.project_id
+    }
+    async fn get_user_id(&self) -> Result<String, anyhow::Error> {
+        Ok(crate::utils::get_user_id())
+    }
+    fn get_region(&self) -> &str {
+        &self.region
+    }
+    fn get_function_endpoint(&self) -> Option<String> {
+        self.function_endpoint.clone()
+    }
+    fn get_cloud_provider(&self) -> &str {
+        "local"
+    }
+    fn get_backend_provider(&self) -> &str {
+        "local"
+    }
+    fn get_storage_basepath(&self) -> String {
+        format!("{}/", self.project_id)
+    }
+    async fn get_backend_provider_arguments(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+    ) -> serde_json::Value {
+        json!({
+            "path": self.store.state_path(&self.state_key(environment, deployment_id)),
+        })
+    }
+    async fn set_backend(
+        &self,
+        exec: &mut tokio::process::Command,
+        deployment_id: &str,
+        environment: &str,
+    ) {
+        let path = self
+            .store
+            .state_path(&self.state_key(environment, deployment_id));
+        if let Some(parent) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                log::error!("Failed to create {}: {}", parent.display(), e);
+            }
+        }
+        exec.arg(format!("-backend-config=path={}", path.display()));
+    }
+    async fn get_current_job_id(&self) -> Result<String, anyhow::Error> {
+        std::env::var("INFRAWEAVE_JOB_ID")
+            .map_err(|_| anyhow::anyhow!("INFRAWEAVE_JOB_ID is not set, not running as a job"))
+    }
+    async fn get_project_map(&self) -> Result<Value, anyhow::Error> {
+        self.read_db_generic("config", &env_aws::get_project_map_query())
+            .await
+            .map(|mut items| items.pop().unwrap_or_else(|| json!({"data": {}})))
+    }
+    async fn get_all_regions(&self) -> Result<Vec<String>, anyhow::Error> {
+        // All regions share the same store, so writes only need to go to one of them
+        Ok(vec![self.region.clone()])
+    }
+    async fn run_function(
+        &self,
+        payload: &Value,
+    ) -> Result<GenericFunctionResponse, anyhow::Error> {
+        function::run_function(&self.store, payload)
+            .await
+            .map(|payload| GenericFunctionResponse { payload })
+    }
+    fn read_db_generic(
+        &self,
+        table: &str,
+        query: &Value,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<Value>, anyhow::Error>> + Send>> {
+        let result = self.store.query(table, query);
+        Box::pin(async move { result })
+    }
+    async fn get_latest_module_version(
+        &self,
+        module: &str,
+        track: &str,
+    ) -> Result<Option<ModuleResp>, anyhow::Error> {
+        _get_module_optional(
+            self,
+            env_aws::get_latest_module_version_query(module, track),
+        )
+        .await
+    }
+    async fn get_latest_stack_version(
+        &self,
+        stack: &str,
+        track: &str,
+    ) -> Result<Option<ModuleResp>, anyhow::Error> {
+        _get_module_optional(self, env_aws::get_latest_stack_version_query(stack, track)).await
+    }
+    async fn get_job_status(&self, job_id: &str) -> Result<Option<JobStatus>, anyhow::Error> {
+        let response = self
+            .run_function(&env_defs::get_job_status_event(job_id))
+            .await?;
+        Ok(Some(serde_json::from_value(response.payload)?))
+    }
+    async fn get_latest_provider_version(
+        &self,
+        provider: &str,
+    ) -> Result<Option<ProviderResp>, anyhow::Error> {
+        _get_provider_optional(self, env_aws::get_latest_provider_version_query(provider)).await
+    }
+    async fn generate_presigned_url(
+        &self,
+        key: &str,
+        bucket: &str,
+    ) -> Result<String, anyhow::Error> {
+        Ok(self.store.file_url(bucket, key))
+    }
+    async fn upload_file_base64(
+        &self,
+        key: &str,
+        bucket: &str,
+        base64_content: &str,
+    ) -> Result<(), anyhow::Error> {
+        let event = env_defs::upload_file_base64_event(key, bucket, base64_content);
+        self.run_function(&event).await?;
+        Ok(())
+    }
+    async fn upload_file_url(
+        &self,
+        key: &str,
+        bucket: &str,
+        url: &str,
+    ) -> Result<(), anyhow::Error> {
+        let event = env_defs::upload_file_url_event(key, bucket, url);
+        self.run_function(&event).await?;
+        Ok(())
+    }
+    async fn transact_write(&self, items: &serde_json::Value) -> Result<(), anyhow::Error> {
+        self.store.transact_write(items)
+    }
+    async fn get_all_latest_module(&self, track: &str) -> Result<Vec<ModuleResp>, anyhow::Error> {
+        _get_modules(
+            self,
+            env_aws::get_all_latest_modules_query(track, false, false),
+        )
+        .await
+    }
+    async fn get_all_latest_stack(&self, track: &str) -> Result<Vec<ModuleResp>, anyhow::Error> {
+        _get_modules(
+            self,
+            env_aws::get_all_latest_stacks_query(track, false, false),
+        )
+        .await
+    }
+    async fn get_all_latest_provider(&self) -> Result<Vec<ProviderResp>, anyhow::Error> {
+        _get_providers(self, env_aws::get_all_latest_providers_query()).await
+    }
+    async fn get_all_module_versions(
+        &self,
+        module: &str,
+        track: &str,
+    ) -> Result<Vec<ModuleResp>, anyhow::Error> {
+        _get_modules(
+            self,
+            env_aws::get_all_module_versions_query(module, track, false, false),
+        )
+        .await
+    }
+    async fn get_all_stack_versions(
+        &self,
+        stack: &str,
+        track: &str,
+    ) -> Result<Vec<ModuleResp>, anyhow::Error> {
+        _get_modules(
+            self,
+            env_aws::get_all_stack_versions_query(stack, track, false, false),
+        )
+        .await
+    }
+    async fn get_module_version(
+        &self,
+        module: &str,
+        track: &str,
+        version: &str,
+    ) -> Result<Option<ModuleResp>, anyhow::Error> {
+        _get_module_optional(
+            self,
+            env_aws::get_module_version_query(module, track, version),
+        )
+        .await
+    }
+    async fn get_stack_version(
+        &self,
+        stack: &str,
+        track: &str,
+        version: &str,
+    ) -> Result<Option<ModuleResp>, anyhow::Error> {
+        _get_module_optional(
+            self,
+            env_aws::get_stack_version_query(stack, track, version),
+        )
+        .await
+    }
+    // Deployment
+    async fn get_all_deployments(
+        &self,
+        environment: &str,
+        include_deleted: bool,
+    ) -> Result<Vec<DeploymentResp>, anyhow::Error> {
+        _get_deployments(
+            self,
+            env_aws::get_all_deployments_query(
+                &self.project_id,
+                &self.region,
+                environment,
+                include_deleted,
+            ),
+        )
+        .await
+    }
+    async fn get_deployment_and_dependents(
+        &self,
+        deployment_id: &str,
+        environment: &str,
+        include_deleted: bool,
+    ) -> Result<(Option<DeploymentResp>, Vec<Dependent>), anyhow::Error> {
+        _get_deployment_and_dependents(
+            self,
+            env_aws::get_deployment_and_dependents_query(
+                &self.project_id,
+                &self.region,
+                deployment_id,
+                environment,
+                include_deleted,
+            ),
+        )
+        .await
+    }
+    async fn get_deployment(
+        &self,
+        deployment_id: &str,
+        environment: &str,
+        include_deleted: bool,
+    ) -> Result<Option<DeploymentResp>, anyhow::Error> {
+        _get_deployment(
+            self,
+            env_aws::get_deployment_query(
+                &self.project_id,
+                &self.region,
+                deployment_id,
+                environment,
+                include_deleted,
+            ),
+        )
+        .await
+    }
+    async fn get_deployments_using_module(
+        &self,
+        module: &str,
+        environment: &str,
+        include_deleted: bool,
+    ) -> Result<Vec<DeploymentResp>, anyhow::Error> {
+        _get_deployments(
+            self,
+            env_aws::get_deployments_using_module_query(
+                &self.project_id,
+                &self.region,
+                module,
+                environment,
+                include_deleted,
+            ),
+        )
+        .await
+    }
+    async fn get_plan_deployment(
+        &self,
+        deployment_id: &str,
+        environment: &str,
+        job_id: &str,
+    ) -> Result<Option<DeploymentResp>, anyhow::Error> {
+        _get_deployment(
+            self,
+            env_aws::get_plan_deployment_query(
+                &self.project_id,
+                &self.region,
+                deployment_id,
+                environment,
+                job_id,
+            ),
+        )
+        .await
+    }
+    async fn get_dependents(
+        &self,
+        deployment_id: &str,
+        environment: &str,
+    ) -> Result<Vec<Dependent>, anyhow::Error> {
+        _get_dependents(
+            self,
+            env_aws::get_dependents_query(
+                &self.project_id,
+                &self.region,
+                deployment_id,
+                environment,
+            ),
+        )
+        .await
+    }
+    async fn get_deployments_to_driftcheck(&self) -> Result<Vec<DeploymentResp>, anyhow::Error> {
+        _get_deployments(
+            self,
+            env_aws::get_deployments_to_driftcheck_query(&self.project_id, &self.region),
+        )
+        .await
+    }
+    async fn get_job_queue(&self) -> Result<Vec<JobQueueEntry>, anyhow::Error> {
+        _get_job_queue(
+            self,
+            env_aws::get_job_queue_query(&self.project_id, &self.region),
+        )
+        .await
+    }
+    async fn get_job_stats(&self, module: &str) -> Result<Vec<JobStats>, anyhow::Error> {
+        _get_job_stats(
+            self,
+            env_aws::get_job_stats_query(&self.project_id, &self.region, module),
+        )
+        .await
+    }
+    async fn get_all_projects(&self) -> Result<Vec<ProjectData>, anyhow::Error> {
+        let projects = get_projects(self, env_aws::get_all_projects_query()).await?;
+        if projects.is_empty() {
+            return Ok(vec![self.default_project()]);
+        }
+        Ok(projects)
+    }
+    async fn get_current_project(&self) -> Result<ProjectData, anyhow::Error> {
+        // Projects can be registered in the config table, otherwise there is a single one
+        Ok(
+            get_projects(self, env_aws::get_current_project_query(&self.project_id))
+                .await?
+                .pop()
+                .unwrap_or_else(|| self.default_project()),
+        )
+    }
+    async fn get_config_items(&self, kind: &str) -> Result<Vec<Value>, anyhow::Error> {
+        self.read_db_generic("config", &env_aws::get_config_items_query(kind))
+            .await
+    }
+    // Event
+    async fn get_events(
+        &self,
+        deployment_id: &str,
+        environment: &str,
+    ) -> Result<Vec<EventData>, anyhow::Error> {
+        _get_events(
+            self,
+            env_aws::get_events_query(
+                &self.project_id,
+                &self.region,
+                deployment_id,
+                environment,
+                None,
+            ),
+        )
+        .await
+    }
+    async fn get_all_events_between(
+        &self,
+        start_epoch: u128,
+        end_epoch: u128,
+    ) -> Result<Vec<EventData>, anyhow::Error> {
+        _get_events(
+            self,
+            env_aws::get_all_events_between_query(&self.region, start_epoch, end_epoch),
+        )
+        .await
+    }
+    async fn get_event_items_before(&self, end_epoch: u128) -> Result<Vec<Value>, anyhow::Error> {
+        self.read_db_generic(
+            "events",
+            &env_aws::get_all_events_between_query(&self.region, 0, end_epoch),
+        )
+        .await
+    }
+    // Change record
+    async fn get_change_record(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+        job_id: &str,
+        change_type: &str,
+    ) -> Result<InfraChangeRecord, anyhow::Error> {
+        _get_change_records(
+            self,
+            env_aws::get_change_records_query(
+                &self.project_id,
+                &self.region,
+                environment,
+                deployment_id,
+                job_id,
+                change_type,
+            ),
+            env_aws::get_change_record_parts_query(
+                &self.project_id,
+                &self.region,
+                environment,
+                deployment_id,
+                job_id,
+                change_type,
+            ),
+        )
+        .await
+    }
+    // Policy
+    async fn get_newest_policy_version(
+        &self,
+        policy: &str,
+        environment: &str,
+    ) -> Result<PolicyResp, anyhow::Error> {
+        _get_policy(
+            self,
+            env_aws::get_newest_policy_version_query(policy, environment),
+        )
+        .await
+    }
+    async fn get_all_policies(&self, environment: &str) -> Result<Vec<PolicyResp>, anyhow::Error> {
+        _get_policies(self, env_aws::get_all_policies_query(environment)).await
+    }
+    async fn get_policy_download_url(&self, key: &str) -> Result<String, anyhow::Error> {
+        Ok(self.store.file_url("policies", key))
+    }
+    async fn get_policy(
+        &self,
+        policy: &str,
+        environment: &str,
+        version: &str,
+    ) -> Result<PolicyResp, anyhow::Error> {
+        _get_policy(
+            self,
+            env_aws::get_policy_query(policy, environment, version),
+        )
+        .await
+    }
+    async fn get_newest_policy_pack_version(
+        &self,
+        policy_pack: &str,
+        environment: &str,
+    ) -> Result<PolicyPackResp, anyhow::Error> {
+        _get_policy_pack(
+            self,
+            env_aws::get_newest_policy_pack_version_query(policy_pack, environment),
+        )
+        .await
+    }
+    async fn get_all_policy_packs(
+        &self,
+        environment: &str,
+    ) -> Result<Vec<PolicyPackResp>, anyhow::Error> {
+        _get_policy_packs(self, env_aws::get_all_policy_packs_query(environment)).await
+    }
+    async fn get_environment_variables(&self) -> Result<serde_json::Value, anyhow::Error> {
+        Ok(json!({}))
+    }
+
+    async fn read_state_file(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        let path = self
+            .store
+            .state_path(&self.state_key(environment, deployment_id));
+        std::fs::read(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read state {}: {}", path.display(), e))
+    }
+
+    async fn download_state_file(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+        output: Option<String>,
+    ) -> Result<(), anyhow::Error> {
+        let data = self.read_state_file(environment, deployment_id).await?;
+
+        if let Some(output_path) = output {
+            std::fs::write(output_path, &data)?;
+        } else {
+            let state_str = String::from_utf8_lossy(&data);
+            println!("{}", state_str);
+        }
+
+        Ok(())
+    }
+
+    async fn write_state_file(
+        &self,
+        environment: &str,
+        deployment_id: &str,
+        data: &[u8],
+    ) -> Result<(), anyhow::Error> {
+        let path = self
+            .store
+            .state_path(&self.state_key(environment, deployment_id));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, data)
+            .map_err(|e| anyhow::anyhow!("Failed to write state {}: {}", path.display(), e))
+    }
+
+    async fn read_state_file_at(&self, address: &str) -> Result<Vec<u8>, anyhow::Error> {
+        let path = address.strip_prefix("file://").unwrap_or(address);
+        std::fs::read(path).map_err(|e| anyhow::anyhow!("Failed to read state {}: {}", path, e))
+    }
+}
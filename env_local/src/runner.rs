@@ -0,0 +1,89 @@
+use std::fs::File;
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+
+use crate::store::LocalStore;
+
+/// Starts the runner as a process on this machine, with its output written to the log of the
+/// job. `INFRAWEAVE_LOCAL_RUNNER` sets the runner binary, `terraform_runner` on the PATH by default
+pub fn start_runner(store: &LocalStore, data: &Value) -> Result<String> {
+    let job_id = uuid::Uuid::new_v4().simple().to_string();
+    let job_dir = store.job_dir(&job_id);
+    let workspace = job_dir.join("workspace");
+    std::fs::create_dir_all(&workspace)
+        .with_context(|| format!("Failed to create {}", workspace.display()))?;
+    let log = File::create(job_dir.join("runner.log"))?;
+
+    let runner =
+        std::env::var("INFRAWEAVE_LOCAL_RUNNER").unwrap_or_else(|_| "terraform_runner".to_string());
+    let mut command = Command::new(&runner);
+    command
+        .current_dir(&workspace)
+        .env("PAYLOAD", serde_json::to_string(data)?)
+        .env("INFRAWEAVE_BACKEND", "local")
+        .env("INFRAWEAVE_LOCAL_DIR", store.root())
+        .env("INFRAWEAVE_JOB_ID", &job_id)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    if let Some(project_id) = data.get("project_id").and_then(|v| v.as_str()) {
+        command.env("INFRAWEAVE_LOCAL_PROJECT_ID", project_id);
+    }
+    if let Some(region) = data.get("region").and_then(|v| v.as_str()) {
+        command.env("REGION", region);
+    }
+    if let Some(environment) = data.get("environment").and_then(|v| v.as_object()) {
+        for (key, value) in environment {
+            command.env(key, value.as_str().unwrap_or(""));
+        }
+    }
+
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to start runner {}", runner))?;
+    std::fs::write(job_dir.join("pid"), child.id().to_string())?;
+    log::info!("Started local runner for job {} ({})", job_id, runner);
+
+    // Reaps the runner if it finishes while this process is still running
+    std::thread::spawn(move || child.wait());
+    Ok(job_id)
+}
+
+pub fn get_job_status(store: &LocalStore, job_id: &str) -> Result<Value> {
+    let pid = std::fs::read_to_string(store.job_dir(job_id).join("pid"))
+        .map_err(|_| anyhow!("Job {} not found", job_id))?;
+    Ok(json!({
+        "job_id": job_id,
+        "is_running": is_running(pid.trim()),
+    }))
+}
+
+#[cfg(unix)]
+fn is_running(pid: &str) -> bool {
+    Command::new("kill")
+        .args(["-0", pid])
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(not(unix))]
+fn is_running(pid: &str) -> bool {
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(pid))
+}
+
+pub fn read_logs(store: &LocalStore, job_id: &str) -> Result<Value> {
+    let path = store.job_dir(job_id).join("runner.log");
+    let logs = std::fs::read_to_string(&path)
+        .with_context(|| format!("No logs found for job {}", job_id))?;
+    let events: Vec<Value> = logs
+        .lines()
+        .map(|line| json!({ "message": line }))
+        .collect();
+    Ok(json!({ "events": events }))
+}
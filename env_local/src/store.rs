@@ -0,0 +1,302 @@
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use rusqlite::{params, Connection};
+use serde_json::Value;
+
+use crate::expression::{compare, Expression};
+
+/// Items and files of the local backend, kept in a directory with a SQLite database for the
+/// items of all tables and a folder per bucket for the files
+#[derive(Clone, Debug)]
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    pub fn new(root: &Path) -> Self {
+        LocalStore {
+            root: root.to_path_buf(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn connection(&self) -> Result<Connection> {
+        std::fs::create_dir_all(&self.root)
+            .with_context(|| format!("Failed to create {}", self.root.display()))?;
+        let connection = Connection::open(self.root.join("infraweave.db"))?;
+        // Runners started by the backend write to the same database
+        connection.busy_timeout(std::time::Duration::from_secs(10))?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS items (
+                tbl TEXT NOT NULL,
+                pk TEXT NOT NULL,
+                sk TEXT NOT NULL,
+                item TEXT NOT NULL,
+                PRIMARY KEY (tbl, pk, sk)
+            );",
+        )?;
+        Ok(connection)
+    }
+
+    pub fn put_item(&self, table: &str, item: &Value) -> Result<()> {
+        put_item(&self.connection()?, table, item)
+    }
+
+    /// Applies the `Put` and `Delete` items of a DynamoDB transaction, all or none of them
+    pub fn transact_write(&self, items: &Value) -> Result<()> {
+        let items = items
+            .as_array()
+            .ok_or_else(|| anyhow!("Transaction items must be an array"))?;
+        let mut connection = self.connection()?;
+        let transaction = connection.transaction()?;
+        for item in items {
+            if let Some(put) = item.get("Put") {
+                // Only the absence condition used to reserve an item is supported
+                if put.get("ConditionExpression").is_some() {
+                    let (pk, sk) = key(&put["Item"])?;
+                    let exists: bool = transaction.query_row(
+                        "SELECT EXISTS(SELECT 1 FROM items WHERE tbl = ?1 AND pk = ?2 AND sk = ?3)",
+                        params![table_name(put)?, pk, sk],
+                        |row| row.get(0),
+                    )?;
+                    if exists {
+                        return Err(anyhow!(
+                            "Conditional check failed, item {} / {} already exists",
+                            pk,
+                            sk
+                        ));
+                    }
+                }
+                put_item(&transaction, table_name(put)?, &put["Item"])?;
+            } else if let Some(delete) = item.get("Delete") {
+                let (pk, sk) = key(&delete["Key"])?;
+                let deleted = transaction.execute(
+                    "DELETE FROM items WHERE tbl = ?1 AND pk = ?2 AND sk = ?3",
+                    params![table_name(delete)?, pk, sk],
+                )?;
+                // Only the existence condition used to claim an item is supported
+                if deleted == 0 && delete.get("ConditionExpression").is_some() {
+                    return Err(anyhow!(
+                        "Conditional check failed, no item {} / {} to delete",
+                        pk,
+                        sk
+                    ));
+                }
+            } else {
+                return Err(anyhow!("Unsupported transaction item: {}", item));
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Items of `table` matching a query in the format of the env_aws query builders
+    pub fn query(&self, table: &str, query: &Value) -> Result<Vec<Value>> {
+        let names = query
+            .get("ExpressionAttributeNames")
+            .and_then(|v| v.as_object());
+        let values = query
+            .get("ExpressionAttributeValues")
+            .and_then(|v| v.as_object());
+        let key_condition = query
+            .get("KeyConditionExpression")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Query has no KeyConditionExpression"))?;
+        let key_condition = Expression::parse(key_condition, names, values)?;
+        let filter = match query.get("FilterExpression").and_then(|v| v.as_str()) {
+            Some(filter) => Some(Expression::parse(filter, names, values)?),
+            None => None,
+        };
+
+        let connection = self.connection()?;
+        let rows: Vec<String> = match key_condition.required_value("PK").and_then(|v| v.as_str()) {
+            Some(pk) if query.get("IndexName").is_none() => connection
+                .prepare("SELECT item FROM items WHERE tbl = ?1 AND pk = ?2")?
+                .query_map(params![table, pk], |row| row.get(0))?
+                .collect::<Result<_, _>>()?,
+            _ => connection
+                .prepare("SELECT item FROM items WHERE tbl = ?1")?
+                .query_map(params![table], |row| row.get(0))?
+                .collect::<Result<_, _>>()?,
+        };
+        let mut items = rows
+            .iter()
+            .map(|row| serde_json::from_str::<Value>(row))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|item| key_condition.matches(item))
+            .collect::<Vec<_>>();
+
+        // Results are ordered by the sort key of the table or index, the second key attribute
+        let sort_key = key_condition
+            .attributes()
+            .get(1)
+            .cloned()
+            .unwrap_or_else(|| "SK".to_string());
+        items.sort_by(|a, b| match (a.get(&sort_key), b.get(&sort_key)) {
+            (Some(a), Some(b)) => compare(a, b).unwrap_or(Ordering::Equal),
+            _ => Ordering::Equal,
+        });
+        if query.get("ScanIndexForward") == Some(&Value::Bool(false)) {
+            items.reverse();
+        }
+        // As in DynamoDB, the limit applies to the items read before they are filtered
+        if let Some(limit) = query.get("Limit").and_then(|v| v.as_u64()) {
+            items.truncate(limit as usize);
+        }
+        if let Some(filter) = filter {
+            items.retain(|item| filter.matches(item));
+        }
+        Ok(items)
+    }
+
+    fn file_path(&self, bucket: &str, key: &str) -> PathBuf {
+        self.root.join("files").join(bucket).join(key)
+    }
+
+    pub fn write_file(&self, bucket: &str, key: &str, content: &[u8]) -> Result<()> {
+        let path = self.file_path(bucket, key);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// URL of a file in place of a presigned URL, downloads in env_utils read `file://` URLs
+    pub fn file_url(&self, bucket: &str, key: &str) -> String {
+        format!("file://{}", self.file_path(bucket, key).display())
+    }
+
+    pub fn job_dir(&self, job_id: &str) -> PathBuf {
+        self.root.join("jobs").join(job_id)
+    }
+
+    pub fn state_path(&self, key: &str) -> PathBuf {
+        self.root.join("state").join(key)
+    }
+}
+
+fn table_name(operation: &Value) -> Result<&str> {
+    operation["TableName"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Missing TableName in {}", operation))
+}
+
+fn key(item: &Value) -> Result<(&str, &str)> {
+    match (item["PK"].as_str(), item["SK"].as_str()) {
+        (Some(pk), Some(sk)) => Ok((pk, sk)),
+        _ => Err(anyhow!("Item has no PK and SK: {}", item)),
+    }
+}
+
+fn put_item(connection: &Connection, table: &str, item: &Value) -> Result<()> {
+    let (pk, sk) = key(item)?;
+    connection.execute(
+        "INSERT OR REPLACE INTO items (tbl, pk, sk, item) VALUES (?1, ?2, ?3, ?4)",
+        params![table, pk, sk, item.to_string()],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_query_module_versions() {
+        let directory = tempfile::tempdir().unwrap();
+        let store = LocalStore::new(directory.path());
+        store
+            .transact_write(&json!([
+                {"Put": {"TableName": "modules", "Item": {"PK": "MODULE#dev::s3bucket", "SK": "VERSION#000.001.000", "version": "0.1.0"}}},
+                {"Put": {"TableName": "modules", "Item": {"PK": "MODULE#dev::s3bucket", "SK": "VERSION#000.002.000", "version": "0.2.0", "deprecated": true}}},
+                {"Put": {"TableName": "modules", "Item": {"PK": "MODULE#dev::s3bucket", "SK": "VERSION#000.003.000", "version": "0.3.0"}}},
+                {"Put": {"TableName": "modules", "Item": {"PK": "LATEST_MODULE", "SK": "MODULE#dev::s3bucket", "version": "0.3.0"}}},
+            ]))
+            .unwrap();
+
+        let versions = |items: Vec<Value>| -> Vec<String> {
+            items
+                .iter()
+                .map(|item| item["version"].as_str().unwrap().to_string())
+                .collect()
+        };
+        let query = env_aws::get_all_module_versions_query("s3bucket", "dev", false, false);
+        assert_eq!(
+            versions(store.query("modules", &query).unwrap()),
+            vec!["0.3.0", "0.1.0"]
+        );
+        let query = env_aws::get_module_version_query("s3bucket", "dev", "0.2.0");
+        assert_eq!(
+            versions(store.query("modules", &query).unwrap()),
+            vec!["0.2.0"]
+        );
+        let query = env_aws::get_latest_module_version_query("s3bucket", "dev");
+        assert_eq!(
+            versions(store.query("modules", &query).unwrap()),
+            vec!["0.3.0"]
+        );
+
+        store
+            .transact_write(&json!([
+                {"Delete": {"TableName": "modules", "Key": {"PK": "MODULE#dev::s3bucket", "SK": "VERSION#000.003.000"}}},
+            ]))
+            .unwrap();
+        let query = env_aws::get_all_module_versions_query("s3bucket", "dev", true, false);
+        assert_eq!(
+            versions(store.query("modules", &query).unwrap()),
+            vec!["0.2.0", "0.1.0"]
+        );
+    }
+
+    #[test]
+    fn test_transaction_is_atomic() {
+        let directory = tempfile::tempdir().unwrap();
+        let store = LocalStore::new(directory.path());
+        let result = store.transact_write(&json!([
+            {"Put": {"TableName": "modules", "Item": {"PK": "LATEST_MODULE", "SK": "MODULE#dev::s3bucket"}}},
+            {"Put": {"TableName": "modules", "Item": {"PK": "missing-sk"}}},
+        ]));
+        assert!(result.is_err());
+        let query = json!({
+            "KeyConditionExpression": "PK = :latest",
+            "ExpressionAttributeValues": {":latest": "LATEST_MODULE"},
+        });
+        assert_eq!(store.query("modules", &query).unwrap(), Vec::<Value>::new());
+    }
+
+    #[test]
+    fn test_conditional_delete_of_missing_item_fails() {
+        let directory = tempfile::tempdir().unwrap();
+        let store = LocalStore::new(directory.path());
+        store
+            .transact_write(&json!([
+                {"Put": {"TableName": "deployments", "Item": {"PK": "JOBQUEUE#dev", "SK": "JOB#queued-1"}}},
+            ]))
+            .unwrap();
+        let claim = json!([
+            {"Delete": {"TableName": "deployments", "Key": {"PK": "JOBQUEUE#dev", "SK": "JOB#queued-1"}, "ConditionExpression": "attribute_exists(PK)"}},
+        ]);
+        assert!(store.transact_write(&claim).is_ok());
+        assert!(store.transact_write(&claim).is_err());
+    }
+
+    #[test]
+    fn test_conditional_put_of_existing_item_fails() {
+        let directory = tempfile::tempdir().unwrap();
+        let store = LocalStore::new(directory.path());
+        let reserve = json!([
+            {"Put": {"TableName": "deployments", "Item": {"PK": "JOBQUEUE#dev", "SK": "SLOT#0"}, "ConditionExpression": "attribute_not_exists(PK)"}},
+        ]);
+        assert!(store.transact_write(&reserve).is_ok());
+        assert!(store.transact_write(&reserve).is_err());
+    }
+}
@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+
+/// Directory of the local backend, `INFRAWEAVE_LOCAL_DIR` or `~/.infraweave/local`
+pub fn get_local_dir() -> PathBuf {
+    match std::env::var("INFRAWEAVE_LOCAL_DIR") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => dirs::home_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join(".infraweave")
+            .join("local"),
+    }
+}
+
+pub fn get_project_id() -> String {
+    std::env::var("INFRAWEAVE_LOCAL_PROJECT_ID").unwrap_or_else(|_| "local".to_string())
+}
+
+pub fn get_region() -> String {
+    std::env::var("REGION").unwrap_or_else(|_| "local".to_string())
+}
+
+pub fn get_user_id() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "local".to_string())
+}
@@ -0,0 +1,119 @@
+use regex::Regex;
+
+/// Locations GitHub reads the CODEOWNERS file from, in order of precedence
+pub const CODEOWNERS_PATHS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// Owners of `path` according to a CODEOWNERS file, where the last matching rule wins
+pub fn owners_for_path(codeowners: &str, path: &str) -> Vec<String> {
+    codeowners
+        .lines()
+        .rev()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .find_map(|line| {
+            let mut parts = line.split_whitespace();
+            let pattern = parts.next()?;
+            pattern_matches(pattern, path).then(|| parts.map(str::to_string).collect())
+        })
+        .unwrap_or_default()
+}
+
+/// Matches a CODEOWNERS pattern against a file path relative to the repository root, following
+/// the gitignore rules CODEOWNERS uses
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    // Patterns with a slash other than a trailing one are relative to the root
+    let anchored = pattern.trim_end_matches('/').contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    let directory = pattern.ends_with('/');
+    let pattern = pattern.trim_end_matches('/');
+
+    let mut expr = String::from(if anchored { "^" } else { "^(.*/)?" });
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                expr.push_str(".*");
+            }
+            '*' => expr.push_str("[^/]*"),
+            '?' => expr.push_str("[^/]"),
+            c => expr.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    // A pattern matches the path itself or, like a directory, everything below it
+    expr.push_str(if directory { "/.*$" } else { "(/.*)?$" });
+
+    Regex::new(&expr).is_ok_and(|re| re.is_match(path))
+}
+
+/// Directory of a module in its repository, derived from a `reference` pointing into the
+/// repository such as `https://github.com/org/repo/tree/main/modules/s3bucket`
+pub fn module_dir_from_reference(reference: &str, repository_url: &str) -> Option<String> {
+    let rest = reference
+        .strip_prefix(repository_url.trim_end_matches('/'))?
+        .trim_start_matches('/');
+    let rest = rest
+        .strip_prefix("tree/")
+        .or_else(|| rest.strip_prefix("blob/"))?;
+    // Skip the branch, which is assumed to not contain a slash
+    let (_, dir) = rest.split_once('/').unwrap_or((rest, ""));
+    Some(dir.trim_end_matches('/').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODEOWNERS: &str = r#"
+# Default owners
+*                   @org/platform
+modules/            @org/modules   # all modules
+/modules/network/   @org/network network@example.com
+*.md                @org/docs
+"#;
+
+    #[test]
+    fn test_owners_for_path_last_match_wins() {
+        assert_eq!(
+            owners_for_path(CODEOWNERS, "main.tf"),
+            vec!["@org/platform"]
+        );
+        assert_eq!(
+            owners_for_path(CODEOWNERS, "modules/s3bucket/module.yaml"),
+            vec!["@org/modules"]
+        );
+        assert_eq!(
+            owners_for_path(CODEOWNERS, "modules/network/vpc/module.yaml"),
+            vec!["@org/network", "network@example.com"]
+        );
+        assert_eq!(
+            owners_for_path(CODEOWNERS, "modules/network/README.md"),
+            vec!["@org/docs"]
+        );
+        assert!(owners_for_path("", "module.yaml").is_empty());
+    }
+
+    #[test]
+    fn test_module_dir_from_reference() {
+        let repo = "https://github.com/org/repo";
+        assert_eq!(
+            module_dir_from_reference(
+                "https://github.com/org/repo/tree/main/modules/s3bucket",
+                repo
+            ),
+            Some("modules/s3bucket".to_string())
+        );
+        assert_eq!(
+            module_dir_from_reference("https://github.com/org/repo/tree/main", repo),
+            Some("".to_string())
+        );
+        assert_eq!(
+            module_dir_from_reference("https://github.com/org/repo", repo),
+            None
+        );
+        assert_eq!(
+            module_dir_from_reference("https://github.com/other/repo/tree/main/x", repo),
+            None
+        );
+    }
+}
@@ -6,14 +6,17 @@ use env_common::logic::{
     destroy_infra, get_deployment_details, publish_module_from_zip, publish_notification,
     run_claim, set_deployment,
 };
-use env_defs::{ArtifactType, CloudProvider, ModuleResp, OciArtifactSet};
+use env_defs::{
+    pretty_print_resource_changes, ArtifactType, CloudProvider, ModuleResp, OciArtifactSet,
+    ResourceAction, SanitizedResourceChange,
+};
 use env_defs::{
     CheckRun, CheckRunOutput, DeploymentManifest, ExtraData, GitHubCheckRun, Installation,
-    JobDetails, NotificationData, Owner, Repository, User,
+    JobDetails, ModuleManifest, NotificationData, Owner, Repository, User,
 };
 use env_utils::{
     convert_module_example_variables_to_snake_case, get_module_manifest_from_oci_targz,
-    get_module_zip_from_oci_targz,
+    get_module_zip_from_oci_targz, get_platforms_from_oci_targz,
 };
 use futures::stream::{self, StreamExt};
 use hmac::{Hmac, Mac};
@@ -30,13 +33,17 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use std::{env, error::Error};
 use subtle::ConstantTimeEq;
 
-use crate::{
-    get_project_id_for_repository_path, get_securestring_aws, group_files_by_manifest, FileChange,
-    ProcessedFiles,
+use crate::codeowners::{module_dir_from_reference, owners_for_path, CODEOWNERS_PATHS};
+use crate::provider::{
+    approval_section, get_approval_requirement, get_before_ref, get_changed_modules,
+    module_example_claims, process_webhook_files, resolve_var_file, GitProvider, WebhookPayload,
 };
+use crate::{get_project_id_for_repository_path, get_securestring_aws, group_files_by_manifest};
 
 const INFRAWEAVE_USER_AGENT: &str = "infraweave/gitops";
 const GITHUB_API_URL: &str = "https://api.github.com";
+// Larger graphs are left out of plan comments, which GitHub limits to 65536 characters
+const PLAN_COMMENT_GRAPH_LIMIT: usize = 40_000;
 
 // Create an alias for HMAC-SHA256.
 type HmacSha256 = Hmac<Sha256>;
@@ -158,6 +165,15 @@ fn detect_artifact_type_and_tag_from_webhook(
         return (ArtifactType::Signature, package_version.to_string());
     }
 
+    // Check for SBOM indicators
+    if package_name.ends_with(".sbom") || package_version.ends_with(".sbom") {
+        println!(
+            "🧾 Detected SBOM artifact based on package name or version: {} {}",
+            package_name, package_version
+        );
+        return (ArtifactType::Sbom, package_version.to_string());
+    }
+
     // Check container metadata if available
     if let Some(package) = package_info {
         if let Some(container_metadata) = &package.package_version.container_metadata {
@@ -178,6 +194,13 @@ fn detect_artifact_type_and_tag_from_webhook(
                         );
                         return (ArtifactType::Signature, tag.clone());
                     }
+                    if tag.ends_with(".sbom") {
+                        println!(
+                            "🧾 Detected SBOM artifact based on container metadata tag: {}",
+                            tag
+                        );
+                        return (ArtifactType::Sbom, tag.clone());
+                    }
                     println!("📦 Detected main package artifact with tag: {}", tag);
                     return (ArtifactType::MainPackage, tag.clone());
                 }
@@ -200,6 +223,13 @@ fn detect_artifact_type_and_tag_from_webhook(
                     );
                     return (ArtifactType::Signature, tag_name.clone());
                 }
+                if tag_name.ends_with(".sbom") {
+                    println!(
+                        "🧾 Detected SBOM artifact based on container metadata tag.name: {}",
+                        tag_name
+                    );
+                    return (ArtifactType::Sbom, tag_name.clone());
+                }
                 println!(
                     "📦 Detected main package artifact with tag.name: {}",
                     tag_name
@@ -221,24 +251,6 @@ struct FileContent {
     encoding: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct WebhookPayload {
-    #[serde(rename = "ref")]
-    _ref: String, // branch name
-    before: String, // commit SHA before the push
-    after: String,  // commit SHA after the push
-    commits: Vec<Commit>,
-}
-
-#[derive(Debug, Deserialize)]
-struct Commit {
-    // id: String,
-    // tree_id: String,
-    added: Vec<String>,
-    removed: Vec<String>,
-    modified: Vec<String>,
-}
-
 fn get_default_branch(owner: &str, repo: &str, token: &str) -> Result<String, Box<dyn Error>> {
     let client = Client::new();
 
@@ -280,6 +292,33 @@ fn get_default_branch_sha(owner: &str, repo: &str, token: &str) -> Result<String
     Ok(sha)
 }
 
+/// Number of the open pull request from `branch` of the same repository, if there is one
+fn get_open_pull_request_number(
+    owner: &str,
+    repo: &str,
+    branch: &str,
+    token: &str,
+) -> Result<Option<u64>, Box<dyn Error>> {
+    let client = Client::new();
+    let pulls_url = format!("{}/repos/{}/{}/pulls", GITHUB_API_URL, owner, repo);
+    let pulls: Value = client
+        .get(&pulls_url)
+        .query(&[
+            ("head", format!("{}:{}", owner, branch).as_str()),
+            ("state", "open"),
+        ])
+        .header("User-Agent", INFRAWEAVE_USER_AGENT)
+        .header("Authorization", format!("token {}", token))
+        .send()?
+        .error_for_status()?
+        .json()?;
+
+    Ok(pulls
+        .as_array()
+        .and_then(|pulls| pulls.first())
+        .and_then(|pull| pull["number"].as_u64()))
+}
+
 /// Fetch file content from GitHub for a commit reference
 /// If a 404 is returned, we treat that as "None" (file does not exist)
 fn get_file_content_option(
@@ -314,124 +353,38 @@ fn get_file_content_option(
     Ok(Some(content))
 }
 
-fn should_process_file(file_path: &str, prefix_filter: Option<&str>) -> bool {
-    let is_yaml = file_path.ends_with(".yaml") || file_path.ends_with(".yml");
+/// A GitHub repository accessed with an installation token
+struct GitHubRepo<'a> {
+    owner: &'a str,
+    repo: &'a str,
+    token: &'a str,
+    html_url: &'a str,
+}
 
-    if !is_yaml {
-        println!("Skipping non-YAML file: {}", file_path);
-        return false;
+impl GitProvider for GitHubRepo<'_> {
+    fn environment_prefix(&self) -> &'static str {
+        "github"
     }
 
-    if let Some(prefix) = prefix_filter {
-        let prefix = prefix.trim();
-        if !prefix.is_empty() {
-            let matches_prefix = file_path.starts_with(prefix);
-            if !matches_prefix {
-                println!(
-                    "Skipping file (doesn't match prefix '{}'): {}",
-                    prefix, file_path
-                );
-                return false;
-            }
-            println!(
-                "Processing file (matches prefix '{}'): {}",
-                prefix, file_path
-            );
-        }
+    fn get_default_branch(&self) -> Result<String, Box<dyn Error>> {
+        get_default_branch(self.owner, self.repo, self.token)
     }
 
-    true
-}
-
-fn process_webhook_files(
-    owner: &str,
-    repo: &str,
-    token: &str,
-    payload: &WebhookPayload,
-) -> Result<ProcessedFiles, Box<dyn Error>> {
-    let default_branch = get_default_branch(owner, repo, token)?;
-    let current_branch = payload
-        ._ref
-        .strip_prefix("refs/heads/")
-        .unwrap_or(&payload._ref);
-    let before_ref = if current_branch == default_branch {
-        // For main, compare with the previous commit.
-        payload.before.clone()
-    } else {
-        // For other branches, get the current commit SHA on main.
-        get_default_branch_sha(owner, repo, token)?
-    };
-    let after_ref = &payload.after;
-
-    let mut added = std::collections::HashSet::new();
-    let mut removed = std::collections::HashSet::new();
-    let mut modified = std::collections::HashSet::new();
-    for commit in &payload.commits {
-        for file in &commit.added {
-            added.insert(file.clone());
-        }
-        for file in &commit.removed {
-            removed.insert(file.clone());
-        }
-        for file in &commit.modified {
-            modified.insert(file.clone());
-        }
+    fn get_default_branch_sha(&self) -> Result<String, Box<dyn Error>> {
+        get_default_branch_sha(self.owner, self.repo, self.token)
     }
-    let mut all_files = std::collections::HashSet::new();
-    all_files.extend(added.iter().cloned());
-    all_files.extend(removed.iter().cloned());
-    all_files.extend(modified.iter().cloned());
 
-    let mut active_files = Vec::new();
-    let mut deleted_files = Vec::new();
-
-    let prefix_filter = env::var("GITOPS_FILE_PATH_PREFIX").ok();
-
-    for file in all_files {
-        if !should_process_file(&file, prefix_filter.as_deref()) {
-            continue;
-        }
+    fn get_file_content_option(
+        &self,
+        path: &str,
+        reference: &str,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        get_file_content_option(self.owner, self.repo, path, reference, self.token)
+    }
 
-        if modified.contains(&file) {
-            // For modified files, fetch both before and after.
-            let active_content = get_file_content_option(owner, repo, &file, after_ref, token)?
-                .unwrap_or_else(String::new);
-            let deleted_content = get_file_content_option(owner, repo, &file, &before_ref, token)?
-                .unwrap_or_else(String::new);
-            active_files.push(FileChange {
-                path: file.clone(),
-                content: active_content,
-            });
-            deleted_files.push(FileChange {
-                path: file.clone(),
-                content: deleted_content,
-            });
-        } else if added.contains(&file) {
-            // For added files, only after.
-            if let Some(active_content) =
-                get_file_content_option(owner, repo, &file, after_ref, token)?
-            {
-                active_files.push(FileChange {
-                    path: file.clone(),
-                    content: active_content,
-                });
-            }
-        } else if removed.contains(&file) {
-            // For removed files, only before.
-            if let Some(deleted_content) =
-                get_file_content_option(owner, repo, &file, &before_ref, token)?
-            {
-                deleted_files.push(FileChange {
-                    path: file.clone(),
-                    content: deleted_content,
-                });
-            }
-        }
+    fn file_url(&self, branch: &str, path: &str) -> String {
+        format!("{}/blob/{}/{}", self.html_url, branch, path)
     }
-    Ok(ProcessedFiles {
-        active_files,
-        deleted_files,
-    })
 }
 
 fn verify_signature(payload_body: &[u8], signature: &str, github_secret: &str) -> bool {
@@ -575,6 +528,7 @@ pub async fn handle_validate_github_event(event: &Value) -> Result<Value, anyhow
     let notification = NotificationData {
         subject: "validated_github_event".to_string(),
         message: event.clone(),
+        owners: vec![],
     };
 
     return match publish_notification(&handler, notification).await {
@@ -634,6 +588,8 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
     };
     let sender_login = payload["sender"]["login"].as_str().unwrap();
     let sender_profile_url = payload["sender"]["html_url"].as_str().unwrap();
+    // Set for pull_request events, looked up for pushes to other branches than the default one
+    let mut pull_request_number = payload["pull_request_number"].as_u64();
 
     let (project_id, project_id_found) =
         match get_project_id_for_repository_path(repo_full_name).await {
@@ -655,7 +611,38 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
 
     let payload: WebhookPayload = serde_json::from_str(body_str).unwrap();
 
-    let processed = process_webhook_files(owner, repo, &token, &payload).unwrap();
+    let github_repo = GitHubRepo {
+        owner,
+        repo,
+        token: &token,
+        html_url: repository_url,
+    };
+    let default_branch = github_repo
+        .get_default_branch()
+        .unwrap_or("main".to_string());
+
+    if pull_request_number.is_none() && branch != format!("refs/heads/{}", default_branch) {
+        let branch_name = branch.strip_prefix("refs/heads/").unwrap_or(branch);
+        pull_request_number = get_open_pull_request_number(owner, repo, branch_name, &token)
+            .unwrap_or_else(|e| {
+                println!("Error looking up pull request for {}: {}", branch_name, e);
+                None
+            });
+        // Plans for branches with an open pull request run on its pull_request events
+        let event_type = headers.get("x-github-event").and_then(|s| s.as_str());
+        if event_type == Some("push") && pull_request_number.is_some() {
+            println!(
+                "Skipping push to branch with an open pull request: {}",
+                branch
+            );
+            return Ok(json!({
+                "statusCode": 200,
+                "body": "Push to branch with an open pull request ignored",
+            }));
+        }
+    }
+
+    let processed = process_webhook_files(&github_repo, &payload).unwrap();
     println!("Processed files: {:?}", processed);
 
     let grouped = group_files_by_manifest(processed);
@@ -666,55 +653,58 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
         project_id, repo_full_name
     );
 
-    let default_branch = get_default_branch(owner, repo, &token).unwrap_or("main".to_string());
+    let check_run_template = GitHubCheckRun {
+        installation: Installation {
+            id: installation_id,
+        },
+        app_id: app_id.to_string(),
+        repository: Repository {
+            owner: Owner {
+                login: owner.to_string(),
+            },
+            name: repo.to_string(),
+            full_name: repo_full_name.to_string(),
+        },
+        check_run: CheckRun {
+            head_sha: payload.after.clone(),
+            status: "in_progress".to_string(),
+            name: "OVERRIDE".to_string(),
+            started_at: Some(Utc::now().to_rfc3339()),
+            completed_at: None,
+            conclusion: None,
+            details_url: None,
+            output: None,
+        },
+        job_details: JobDetails {
+            region: "OVERRIDE".to_string(),
+            environment: "OVERRIDE".to_string(),
+            deployment_id: "OVERRIDE".to_string(),
+            job_id: "OVERRIDE".to_string(),
+            change_type: "OVERRIDE".to_string(),
+            file_path: "OVERRIDE".to_string(),
+            error_text: "OVERRIDE".to_string(),
+            status: "OVERRIDE".to_string(),
+        },
+        user: User {
+            email: author_email.to_string(),
+            name: author_name.to_string(),
+            username: sender_login.to_string(),
+            profile_url: sender_profile_url.to_string(),
+        },
+        pull_request_number,
+    };
 
     stream::iter(grouped)
         .for_each_concurrent(None, |group| {
             // TODO: make smaller functions of below code
             let payload = &payload;
+            let check_run_template = &check_run_template;
+            let github_repo = &github_repo;
             let default_branch = &default_branch;
             let private_key_pem = &private_key_pem;
             let project_id = &project_id;
             async move {
-                let mut extra_data = ExtraData::GitHub(GitHubCheckRun {
-                    installation: Installation {
-                        id: installation_id,
-                    },
-                    app_id: app_id.to_string(),
-                    repository: Repository {
-                        owner: Owner {
-                            login: owner.to_string(),
-                        },
-                        name: repo.to_string(),
-                        full_name: repo_full_name.to_string(),
-                    },
-                    check_run: CheckRun {
-                        head_sha: payload.after.clone(),
-                        status: "in_progress".to_string(),
-                        name: "OVERRIDE".to_string(),
-                        started_at: Some(Utc::now().to_rfc3339()),
-                        completed_at: None,
-                        conclusion: None,
-                        details_url: None,
-                        output: None,
-                    },
-                    job_details: JobDetails {
-                        region: "OVERRIDE".to_string(),
-                        environment: "OVERRIDE".to_string(),
-                        deployment_id: "OVERRIDE".to_string(),
-                        job_id: "OVERRIDE".to_string(),
-                        change_type: "OVERRIDE".to_string(),
-                        file_path: "OVERRIDE".to_string(),
-                        error_text: "OVERRIDE".to_string(),
-                        status: "OVERRIDE".to_string(),
-                    },
-                    user: User {
-                        email: author_email.to_string(),
-                        name: author_name.to_string(),
-                        username: sender_login.to_string(),
-                        profile_url: sender_profile_url.to_string(),
-                    },
-                });
+                let mut extra_data = ExtraData::GitHub(check_run_template.clone());
                 if let Some((active, canonical)) = group.active {
                     if !project_id_found {
                         inform_missing_project_configuration(
@@ -726,7 +716,7 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
                         return; // Exit early if project id is not found
                     }
                     println!("Apply job for: {:?} from path: {}", group.key, active.path);
-                    let yaml = serde_yaml::from_str::<serde_yaml::Value>(&canonical).unwrap();
+                    let mut yaml = serde_yaml::from_str::<serde_yaml::Value>(&canonical).unwrap();
                     println!("YAML: {:?}", yaml);
                     let command = if branch != format!("refs/heads/{}", default_branch) {
                         "plan"
@@ -767,25 +757,30 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
                             annotations: None,
                         });
                     }
-                    match serde_yaml::from_value::<DeploymentManifest>(yaml.clone()) {
+                    // Variables from the variable file are part of the claim for the rest of the job
+                    let prepared = resolve_var_file(github_repo, &active.path, &mut yaml, &payload.after)
+                        .and_then(|_| Ok(serde_yaml::from_value::<DeploymentManifest>(yaml.clone())?));
+                    match prepared {
                         Ok(deployment_claim) => {
                             let region = &deployment_claim.spec.region;
                             let handler = GenericCloudHandler::workload(project_id, region).await;
                             let flags = vec![];
-                            let full_file_url = format!(
-                                "{}/blob/{}/{}",
-                                repository_url, default_branch, active.path
-                            );
+                            let full_file_url = github_repo.file_url(default_branch, &active.path);
                             let namespace = deployment_claim
                                 .metadata
                                 .namespace
                                 .unwrap_or("default".to_string());
                             // Prevent collision between repos by using repo_full_name
                             let repo_full_name_dash = repo_full_name.replace("/", "-").to_lowercase();
+                            let environment = format!("{}-{}/{}", github_repo.environment_prefix(), repo_full_name_dash, namespace);
+                            let required_approvals = get_approval_requirement(&handler, &environment).await;
+                            if let ExtraData::GitHub(ref mut github_check_run) = extra_data {
+                                add_check_run_text(github_check_run, approval_section(&environment, required_approvals, command));
+                            }
                             match run_claim(
                                 &handler,
                                 &yaml,
-                                &format!("github-{}/{}", repo_full_name_dash, namespace),
+                                &environment,
                                 command,
                                 flags,
                                 extra_data.clone(),
@@ -795,6 +790,11 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
                             {
                                 Ok(_) => {
                                     println!("Apply job completed");
+                                    if let ExtraData::GitHub(ref mut github_check_run) = extra_data {
+                                        if command != "plan" && required_approvals > 0 {
+                                            set_check_run_pending_approval(github_check_run, command);
+                                        }
+                                    }
                                 }
                                 Err(e) => {
                                     println!("Apply job failed: {:?}", e);
@@ -819,7 +819,7 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
                             }
                         }
                         Err(e) => {
-                            println!("Error parsing deployment manifest: {:?}", e);
+                            println!("Error preparing deployment manifest: {:?}", e);
                             println!("Apply job failed: {:?}", e);
                             if let ExtraData::GitHub(ref mut github_check_run) = extra_data {
                                 github_check_run.check_run.status = "completed".to_string();
@@ -852,7 +852,7 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
                         "Destroy job for: {:?} from path: {}",
                         group.key, deleted.path
                     );
-                    let yaml = serde_yaml::from_str::<serde_yaml::Value>(&canonical).unwrap();
+                    let mut yaml = serde_yaml::from_str::<serde_yaml::Value>(&canonical).unwrap();
                     println!("YAML: {:?}", yaml);
                     let command = if branch != format!("refs/heads/{}", default_branch) {
                         "plan"
@@ -893,7 +893,17 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
                             annotations: None,
                         });
                     }
-                    match serde_yaml::from_value::<DeploymentManifest>(yaml.clone()) {
+                    // Only a destroy plan uses the claim variables, destroy uses the latest known claim
+                    let prepared = match command {
+                        "plan" => get_before_ref(github_repo, payload)
+                            .map_err(|e| anyhow::anyhow!("{}", e))
+                            .and_then(|before_ref| {
+                                resolve_var_file(github_repo, &deleted.path, &mut yaml, &before_ref)
+                            }),
+                        _ => Ok(()),
+                    }
+                    .and_then(|_| Ok(serde_yaml::from_value::<DeploymentManifest>(yaml.clone())?));
+                    match prepared {
                         Ok(deployment_claim) => {
                             let region = &deployment_claim.spec.region;
                             let handler = GenericCloudHandler::workload(project_id, region).await;
@@ -902,10 +912,7 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
                             } else {
                                 vec![]
                             };
-                            let full_file_url = format!(
-                                "{}/blob/{}/{}",
-                                repository_url, default_branch, deleted.path
-                            );
+                            let full_file_url = github_repo.file_url(default_branch, &deleted.path);
 
                             let namespace = deployment_claim.clone()
                                 .metadata
@@ -913,10 +920,14 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
                                 .unwrap_or("default".to_string());
                             // Prevent collision between repos by using repo_full_name
                             let repo_full_name_dash = repo_full_name.replace("/", "-").to_lowercase();
-                            let environment = &format!("github-{}/{}", repo_full_name_dash, namespace);
+                            let environment = &format!("{}-{}/{}", github_repo.environment_prefix(), repo_full_name_dash, namespace);
 
                             let (_region, environment, deployment_id, _module, _name) =
                                 get_deployment_details(environment, deployment_claim.clone()).unwrap();
+                            let required_approvals = get_approval_requirement(&handler, &environment).await;
+                            if let ExtraData::GitHub(ref mut github_check_run) = extra_data {
+                                add_check_run_text(github_check_run, approval_section(&environment, required_approvals, command));
+                            }
 
                             // We now need to differentiate between plan and destroy here instead.
                             // This is changed because destroy infra function takes the latest known claim with valid variables.
@@ -926,6 +937,11 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
                                 {
                                     Ok(job_id) => {
                                         println!("Destroy job completed with job_id: {job_id}");
+                                        if let ExtraData::GitHub(ref mut github_check_run) = extra_data {
+                                            if required_approvals > 0 {
+                                                set_check_run_pending_approval(github_check_run, command);
+                                            }
+                                        }
                                     },
                                     Err(e) => {
                                         println!("Destroy job failed: {:?}", e);
@@ -952,7 +968,7 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
                                 match run_claim(
                                 &handler,
                                 &yaml,
-                                &format!("github-{}/{}", repo_full_name_dash, namespace),
+                                &format!("{}-{}/{}", github_repo.environment_prefix(), repo_full_name_dash, namespace),
                                 command,
                                 flags,
                                 extra_data.clone(),
@@ -987,7 +1003,7 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
                             }
                         }
                         Err(e) => {
-                            println!("Error parsing deployment manifest: {:?}", e);
+                            println!("Error preparing deployment manifest: {:?}", e);
                             println!("Destroy job failed: {:?}", e);
                             if let ExtraData::GitHub(ref mut github_check_run) = extra_data {
                                 github_check_run.check_run.status = "completed".to_string();
@@ -1025,10 +1041,7 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
                         Ok(deployment_claim) => {
                             let region = &deployment_claim.spec.region;
                             let handler = GenericCloudHandler::workload(project_id, region).await;
-                            let full_file_url = format!(
-                                "{}/blob/{}/{}",
-                                repository_url, default_branch, renamed.path
-                            );
+                            let full_file_url = github_repo.file_url(default_branch, &renamed.path);
 
                             let namespace = deployment_claim.clone()
                                 .metadata
@@ -1036,7 +1049,7 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
                                 .unwrap_or("default".to_string());
 
                                 let repo_full_name_dash = repo_full_name.replace("/", "-");
-                            let environment = &format!("github-{}/{}", repo_full_name_dash, namespace);
+                            let environment = &format!("{}-{}/{}", github_repo.environment_prefix(), repo_full_name_dash, namespace);
                             let (_region, environment, deployment_id, _module, name) =
                                 get_deployment_details(environment, deployment_claim.clone()).unwrap();
 
@@ -1104,12 +1117,180 @@ pub async fn handle_process_push_event(event: &Value) -> Result<Value, anyhow::E
         })
         .await;
 
+    // The examples of modules changed outside of the default branch are planned as tests of the change
+    if branch != format!("refs/heads/{}", default_branch) && project_id_found {
+        precheck_changed_modules(
+            &github_repo,
+            &payload,
+            &project_id,
+            repo_full_name,
+            &default_branch,
+            &check_run_template,
+            &private_key_pem,
+        )
+        .await;
+    }
+
     Ok(json!({
         "statusCode": 200,
         "body": "Processed successfully",
     }))
 }
 
+/// Plans each example of the modules changed by the push against the module version in its
+/// `module.yaml`. Every example gets a check run, which is completed with the plan summary
+/// when the plan job finishes
+async fn precheck_changed_modules(
+    github_repo: &GitHubRepo<'_>,
+    payload: &WebhookPayload,
+    project_id: &str,
+    repo_full_name: &str,
+    default_branch: &str,
+    check_run_template: &GitHubCheckRun,
+    private_key_pem: &str,
+) {
+    let modules = match get_changed_modules(github_repo, payload) {
+        Ok(modules) => modules,
+        Err(e) => {
+            println!("Error looking up changed modules: {}", e);
+            return;
+        }
+    };
+    if modules.is_empty() {
+        return;
+    }
+    // Examples have no region, they are planned in the region gitops runs in
+    let region = GenericCloudHandler::default()
+        .await
+        .get_region()
+        .to_string();
+    let handler = GenericCloudHandler::workload(project_id, &region).await;
+    let repo_full_name_dash = repo_full_name.replace("/", "-").to_lowercase();
+    let environment = format!(
+        "{}-{}/module-precheck",
+        github_repo.environment_prefix(),
+        repo_full_name_dash
+    );
+
+    for (manifest_path, manifest) in modules {
+        println!("Module precheck for: {}", manifest_path);
+        let mut module_check_run = check_run_template.clone();
+        module_check_run.job_details.file_path = manifest_path.clone();
+        module_check_run.check_run.name =
+            get_module_precheck_check_run_name(&manifest_path, &region);
+
+        let claims = serde_yaml::from_str::<ModuleManifest>(&manifest)
+            .map_err(anyhow::Error::from)
+            .and_then(|module| {
+                let claims = module_example_claims(&module, &region)?;
+                Ok((module, claims))
+            });
+        let (module, claims) = match claims {
+            Ok((module, claims)) if !claims.is_empty() => (module, claims),
+            Ok(_) => {
+                complete_check_run(
+                    &mut module_check_run,
+                    "neutral",
+                    "No examples to plan",
+                    "Consider adding examples to module.yaml to guide your users, they are planned for every change of the module",
+                    None,
+                );
+                post_module_precheck_check_run(module_check_run, private_key_pem).await;
+                continue;
+            }
+            Err(e) => {
+                complete_check_run(
+                    &mut module_check_run,
+                    "failure",
+                    "Module precheck failed",
+                    &format!("Failed to read the examples of {}", manifest_path),
+                    Some(format!("Error: {}", e)),
+                );
+                post_module_precheck_check_run(module_check_run, private_key_pem).await;
+                continue;
+            }
+        };
+
+        // The runner downloads the module, so the examples can only be planned once it is published
+        let version = module.spec.version.clone().unwrap_or_default();
+        let published = match env_utils::get_version_track(&version) {
+            Ok(track) => handler
+                .get_module_version(&module.metadata.name, &track, &version)
+                .await
+                .ok()
+                .flatten(),
+            Err(_) => None,
+        };
+        if published.is_none() {
+            complete_check_run(
+                &mut module_check_run,
+                "neutral",
+                "Module version not published",
+                &format!(
+                    "The examples of {} are planned against version {}, which is not published yet",
+                    module.metadata.name, version
+                ),
+                Some(format!(
+                    "Publish version {} of the module, e.g. as a prerelease from this branch, and re-run this check to plan its {} example(s).",
+                    version,
+                    claims.len()
+                )),
+            );
+            post_module_precheck_check_run(module_check_run, private_key_pem).await;
+            continue;
+        }
+
+        let full_file_url = github_repo.file_url(default_branch, &manifest_path);
+        for (example, claim) in claims {
+            let mut github_check_run = check_run_template.clone();
+            github_check_run.job_details.file_path = manifest_path.clone();
+            github_check_run.check_run.name =
+                get_check_run_name(&example, &manifest_path, &region, "module-precheck");
+            github_check_run.check_run.output = Some(CheckRunOutput {
+                title: "plan job initiated".into(),
+                summary: format!(
+                    "Planning example {} of {} version {}, please wait...",
+                    example, module.metadata.name, version
+                ),
+                text: Some(format!(
+                    r#"
+## Example claim
+
+```yaml
+{}
+```"#,
+                    serde_yaml::to_string(&claim).unwrap_or_default()
+                )),
+                annotations: None,
+            });
+            if let Err(e) = run_claim(
+                &handler,
+                &claim,
+                &environment,
+                "plan",
+                vec![],
+                ExtraData::GitHub(github_check_run.clone()),
+                &full_file_url,
+            )
+            .await
+            {
+                println!("Plan of example {} failed: {:?}", example, e);
+                complete_check_run(
+                    &mut github_check_run,
+                    "failure",
+                    "Plan job failed",
+                    &format!(
+                        "Failed to plan example {} of {}",
+                        example, module.metadata.name
+                    ),
+                    Some(format!("Error: {}", e)),
+                );
+            }
+            post_module_precheck_check_run(github_check_run, private_key_pem).await;
+        }
+    }
+}
+
 pub async fn handle_check_run_event(event: &Value) -> Result<Value, anyhow::Error> {
     let body_str = event.get("body").and_then(|b| b.as_str()).unwrap_or("");
     let payload: Value = serde_json::from_str(body_str).expect("Failed to parse JSON payload");
@@ -1122,6 +1303,139 @@ pub async fn handle_check_run_event(event: &Value) -> Result<Value, anyhow::Erro
     }
 }
 
+pub async fn handle_pull_request_event(event: &Value) -> Result<Value, anyhow::Error> {
+    println!("handle_pull_request_event: {:?}", event);
+    let body_str = event.get("body").and_then(|b| b.as_str()).unwrap_or("");
+    let payload: Value = serde_json::from_str(body_str)?;
+    let headers: Value = event.get("headers").unwrap_or(&json!({})).clone();
+
+    // Only new or reopened pull requests and pushes to them need a new plan
+    let action = payload["action"].as_str().unwrap_or("");
+    if !matches!(action, "opened" | "reopened" | "synchronize") {
+        println!("Skipping pull request action: {}", action);
+        return Ok(json!({
+            "statusCode": 200,
+            "body": format!("Pull request action {} ignored", action),
+        }));
+    }
+
+    if payload["pull_request"]["head"]["repo"]["full_name"] != payload["repository"]["full_name"] {
+        println!("Skipping pull request from a fork");
+        return Ok(json!({
+            "statusCode": 200,
+            "body": "Pull requests from forks are not supported",
+        }));
+    }
+
+    let push_payload = get_pull_request_push_data(&payload, &headers).await?;
+    let wrapped_event = json!({
+        "body": push_payload.to_string(), // Convert to string to mimic the original event
+        "headers": headers,
+    });
+
+    handle_process_push_event(&wrapped_event).await
+}
+
+/// Builds a push-shaped payload for a pull request from the diff between its base branch and head
+pub async fn get_pull_request_push_data(
+    body: &Value,
+    headers: &Value,
+) -> Result<Value, anyhow::Error> {
+    let pull_request = &body["pull_request"];
+    let number = pull_request["number"]
+        .as_u64()
+        .ok_or(anyhow::anyhow!("Missing pull request number"))?;
+    let head_sha = pull_request["head"]["sha"]
+        .as_str()
+        .ok_or(anyhow::anyhow!("Missing head sha"))?;
+    let head_branch = pull_request["head"]["ref"]
+        .as_str()
+        .ok_or(anyhow::anyhow!("Missing head ref"))?;
+    let base_branch = pull_request["base"]["ref"]
+        .as_str()
+        .ok_or(anyhow::anyhow!("Missing base ref"))?;
+    let owner = body["repository"]["owner"]["login"]
+        .as_str()
+        .ok_or(anyhow::anyhow!("Missing repository owner"))?;
+    let repo = body["repository"]["name"]
+        .as_str()
+        .ok_or(anyhow::anyhow!("Missing repository name"))?;
+    let installation_id = body["installation"]["id"]
+        .as_u64()
+        .ok_or(anyhow::anyhow!("Missing installation id"))?;
+    let app_id = headers
+        .get("x-github-hook-installation-target-id")
+        .and_then(|s| s.as_str())
+        .unwrap_or("");
+
+    let private_key =
+        get_securestring_aws(&std::env::var("GITHUB_PRIVATE_KEY_PARAMETER_STORE_KEY")?).await?;
+    let token = get_installation_token(installation_id, app_id, &private_key)
+        .map_err(|e| anyhow::anyhow!("Failed to get installation token: {}", e))?;
+
+    // https://docs.github.com/en/rest/commits/commits?apiVersion=2022-11-28#compare-two-commits
+    let url = format!(
+        "{}/repos/{}/{}/compare/{}...{}",
+        GITHUB_API_URL, owner, repo, base_branch, head_sha
+    );
+    let comparison = reqwest::blocking::Client::new()
+        .get(&url)
+        .header("User-Agent", INFRAWEAVE_USER_AGENT)
+        .header("Authorization", format!("token {}", token))
+        .send()?
+        .error_for_status()?
+        .json::<Value>()?;
+    let files = comparison["files"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let author = &pull_request["user"];
+    Ok(json!({
+        "ref": format!("refs/heads/{}", head_branch),
+        "before": pull_request["base"]["sha"],
+        "after": head_sha,
+        "commits": [commit_from_compare_files(files)],
+        "repository": body["repository"],
+        "installation": body["installation"],
+        "sender": body["sender"],
+        "head_commit": {
+            "author": {
+                "name": author["login"].as_str().unwrap_or(""),
+                "email": author["email"].as_str().unwrap_or(""),
+            },
+        },
+        "pull_request_number": number,
+    }))
+}
+
+/// Added, removed and modified files in the shape of a push commit from the files of a comparison
+fn commit_from_compare_files(files: &[Value]) -> Value {
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut modified = Vec::new();
+    for file in files {
+        let filename = file["filename"].as_str().unwrap_or("").to_string();
+        match file["status"].as_str().unwrap_or("") {
+            "added" | "copied" => added.push(filename),
+            "removed" => removed.push(filename),
+            "renamed" => {
+                // Renames are detected from file contents when grouping by manifest
+                if let Some(previous) = file["previous_filename"].as_str() {
+                    removed.push(previous.to_string());
+                }
+                added.push(filename);
+            }
+            _ => modified.push(filename),
+        }
+    }
+    json!({
+        "added": added,
+        "removed": removed,
+        "modified": modified,
+    })
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Package {
     pub id: u64,
@@ -1510,6 +1824,16 @@ pub async fn handle_package_publish_event(event: &Value) -> Result<Value, anyhow
             let handler = GenericCloudHandler::default().await;
             let all_regions = handler.get_all_regions().await?;
 
+            let codeowners = if env::var("CODEOWNERS_SYNC").is_ok_and(|v| v == "true") {
+                fetch_codeowners(
+                    &webhook.repository.owner.login,
+                    &webhook.repository.name,
+                    &token,
+                )
+            } else {
+                None
+            };
+
             let mut artifact_tasks = Vec::new();
             let mut main_package_digest: Option<String> = None;
 
@@ -1571,6 +1895,11 @@ pub async fn handle_package_publish_event(event: &Value) -> Result<Value, anyhow
                 if artifact_type_it == &ArtifactType::MainPackage {
                     let detected_tag_clone = detected_tag.clone();
                     let package_name_clone = package_name.clone();
+                    let repository = format!(
+                        "ghcr.io/{}/{}",
+                        owner.to_lowercase(),
+                        package_name.to_lowercase()
+                    );
                     let handler_clone = handler.clone();
 
                     let digest = main_package_digest.clone().ok_or_else(|| {
@@ -1580,8 +1909,11 @@ pub async fn handle_package_publish_event(event: &Value) -> Result<Value, anyhow
                     let main_package_task = process_main_package_artifact(
                         detected_tag_clone,
                         package_name_clone,
+                        repository,
                         handler_clone,
                         digest,
+                        codeowners.clone(),
+                        webhook.repository.html_url.clone(),
                     );
                     main_package_tasks.push(main_package_task);
                 }
@@ -1624,8 +1956,11 @@ pub async fn handle_package_publish_event(event: &Value) -> Result<Value, anyhow
 async fn process_main_package_artifact(
     detected_tag: String,
     package_name: String,
+    repository: String,
     handler: GenericCloudHandler,
     digest: String,
+    codeowners: Option<String>,
+    repository_url: String,
 ) -> Result<(), anyhow::Error> {
     let oci_tag = detected_tag.clone();
     let tag = oci_tag;
@@ -1633,6 +1968,7 @@ async fn process_main_package_artifact(
 
     let module_zip = get_module_zip_from_oci_targz(&artifact_path).unwrap();
     let mut module: ModuleResp = get_module_manifest_from_oci_targz(&artifact_path).unwrap();
+    let platforms = get_platforms_from_oci_targz(&artifact_path)?;
 
     // Restore original casing before going through normal publishing process
     if let Some(ref mut examples) = module.manifest.spec.examples {
@@ -1642,6 +1978,27 @@ async fn process_main_package_artifact(
         }
     }
 
+    // Owners set in the manifest take precedence over the ones in CODEOWNERS
+    if let (true, Some(codeowners)) = (module.manifest.spec.owners.is_empty(), &codeowners) {
+        let dir = module_dir_from_reference(&module.manifest.spec.reference, &repository_url)
+            .unwrap_or_default();
+        let manifest_file = if module.module_type == "stack" {
+            "stack.yaml"
+        } else {
+            "module.yaml"
+        };
+        let path = if dir.is_empty() {
+            manifest_file.to_string()
+        } else {
+            format!("{}/{}", dir, manifest_file)
+        };
+        module.manifest.spec.owners = owners_for_path(codeowners, &path);
+        println!(
+            "Owners of {} from CODEOWNERS: {:?}",
+            path, module.manifest.spec.owners
+        );
+    }
+
     match publish_module_from_zip(
         &handler,
         module.manifest,
@@ -1649,9 +2006,12 @@ async fn process_main_package_artifact(
         &module_zip,
         Some(OciArtifactSet {
             oci_artifact_path: "oci-artifacts/".to_string(),
+            repository: Some(repository),
             tag_main: tag,
             tag_attestation: Some(format!("{}.att", &digest.replace(':', "-"))),
             tag_signature: Some(format!("{}.sig", &digest.replace(':', "-"))),
+            tag_sbom: Some(format!("{}.sbom", &digest.replace(':', "-"))),
+            platforms,
             digest: digest,
         }),
         None,
@@ -1669,6 +2029,28 @@ async fn process_main_package_artifact(
     }
 }
 
+/// Reads the CODEOWNERS file of a repository on its default branch, if there is one
+fn fetch_codeowners(owner: &str, repo: &str, token: &str) -> Option<String> {
+    let branch = match get_default_branch(owner, repo, token) {
+        Ok(branch) => branch,
+        Err(e) => {
+            println!("Failed to get default branch to read CODEOWNERS: {}", e);
+            return None;
+        }
+    };
+    for path in CODEOWNERS_PATHS {
+        match get_file_content_option(owner, repo, path, &branch, token) {
+            Ok(Some(content)) => return Some(content),
+            Ok(None) => {}
+            Err(e) => {
+                println!("Failed to read {} in {}/{}: {}", path, owner, repo, e);
+                return None;
+            }
+        }
+    }
+    None
+}
+
 async fn upload_oci_artifact_to_all_regions(
     handler: GenericCloudHandler,
     artifact_path: String,
@@ -1834,10 +2216,64 @@ pub async fn get_check_run_rerequested_data(
     Ok(push_payload)
 }
 
+/// Appends a markdown section to the check run output text
+fn add_check_run_text(github_check_run: &mut GitHubCheckRun, section: Option<String>) {
+    if let (Some(section), Some(output)) = (section, github_check_run.check_run.output.as_mut()) {
+        output.text = Some(format!(
+            "{}{}",
+            output.text.as_deref().unwrap_or(""),
+            section
+        ));
+    }
+}
+
+/// Completes the check run as neutral, the job is not started until it has been approved
+fn set_check_run_pending_approval(github_check_run: &mut GitHubCheckRun, command: &str) {
+    github_check_run.check_run.status = "completed".to_string();
+    github_check_run.check_run.conclusion = Some("neutral".to_string());
+    github_check_run.check_run.completed_at = Some(Utc::now().to_rfc3339());
+    if let Some(output) = github_check_run.check_run.output.as_mut() {
+        output.title = format!("{} job pending approval", command);
+        output.summary = format!(
+            "The {} job for {} is waiting for approval",
+            command, github_check_run.check_run.name
+        );
+    }
+}
+
 fn get_check_run_name(name: &str, path: &str, region: &str, namespace: &str) -> String {
     format!("{} ({}) - {} ({})", name, region, path, namespace)
 }
 
+fn get_module_precheck_check_run_name(manifest_path: &str, region: &str) -> String {
+    format!("module precheck ({}) - {}", region, manifest_path)
+}
+
+fn complete_check_run(
+    github_check_run: &mut GitHubCheckRun,
+    conclusion: &str,
+    title: &str,
+    summary: &str,
+    text: Option<String>,
+) {
+    github_check_run.check_run.status = "completed".to_string();
+    github_check_run.check_run.conclusion = Some(conclusion.to_string());
+    github_check_run.check_run.completed_at = Some(Utc::now().to_rfc3339());
+    github_check_run.check_run.output = Some(CheckRunOutput {
+        title: title.to_string(),
+        summary: summary.to_string(),
+        text,
+        annotations: None,
+    });
+}
+
+async fn post_module_precheck_check_run(github_check_run: GitHubCheckRun, private_key_pem: &str) {
+    let name = github_check_run.check_run.name.clone();
+    if let Err(e) = post_check_run_from_payload(github_check_run, private_key_pem).await {
+        println!("Error posting check run {}: {}", name, e);
+    }
+}
+
 async fn inform_missing_project_configuration(
     extra_data: &mut ExtraData,
     name: &str,
@@ -1894,6 +2330,144 @@ pub async fn post_check_run_from_payload(
     Ok(check_run_result)
 }
 
+/// Hidden marker identifying the plan comment of a claim, so later plans update it
+fn plan_comment_marker(job_details: &JobDetails) -> String {
+    format!(
+        "<!-- infraweave-plan: {}/{} -->",
+        job_details.environment, job_details.deployment_id
+    )
+}
+
+/// Pull request comment for a plan job: the resource changes, a warning for resources that
+/// would be destroyed and the dependency graph of the plan when available
+pub fn get_plan_comment_body(
+    job_details: &JobDetails,
+    resource_changes: Option<&[SanitizedResourceChange]>,
+    mermaid_graph: Option<&str>,
+) -> String {
+    let mut body = format!(
+        "{}\n## InfraWeave plan for `{}`\n\nDeployment ID: **{}**\nEnvironment: **{}**\nJob: `{}`\n",
+        plan_comment_marker(job_details),
+        job_details.file_path,
+        job_details.deployment_id,
+        job_details.environment,
+        job_details.job_id,
+    );
+
+    if job_details.status != "success" {
+        body.push_str(&format!(
+            "\n### ❌ Plan failed\n\n```\n{}\n```\n",
+            job_details.error_text
+        ));
+        return body;
+    }
+
+    let Some(resource_changes) = resource_changes else {
+        body.push_str("\nThe resource changes of this plan are not available.\n");
+        return body;
+    };
+
+    let destructive: Vec<&SanitizedResourceChange> = resource_changes
+        .iter()
+        .filter(|change| {
+            matches!(
+                change.action,
+                ResourceAction::Delete | ResourceAction::Replace
+            )
+        })
+        .collect();
+    if !destructive.is_empty() {
+        body.push_str(&format!(
+            "\n> [!WARNING]\n> This plan destroys {} resource(s):\n",
+            destructive.len()
+        ));
+        for change in destructive {
+            let action = match change.action {
+                ResourceAction::Replace => "replaced",
+                _ => "deleted",
+            };
+            body.push_str(&format!("> - `{}` is {}\n", change.address, action));
+        }
+    }
+
+    body.push_str(&format!(
+        "\n### Resource changes\n\n```diff\n{}\n```\n",
+        pretty_print_resource_changes(resource_changes)
+    ));
+
+    if let Some(graph) = mermaid_graph.filter(|g| g.len() <= PLAN_COMMENT_GRAPH_LIMIT) {
+        body.push_str(&format!(
+            "\n<details>\n<summary>Dependency graph</summary>\n\n```mermaid\n{}\n```\n\n</details>\n",
+            graph
+        ));
+    }
+    body
+}
+
+/// Posts the plan comment of the job on the pull request it was triggered from, or updates the
+/// comment of an earlier plan of the same claim so each claim has a single comment
+pub async fn upsert_pull_request_comment_from_payload(
+    github_check_run: &GitHubCheckRun,
+    body: &str,
+    private_key_pem: &str,
+) -> Result<Value, Box<dyn Error>> {
+    let number = github_check_run
+        .pull_request_number
+        .ok_or("Job was not triggered from a pull request")?;
+    let token = get_installation_token(
+        github_check_run.installation.id,
+        &github_check_run.app_id,
+        private_key_pem,
+    )?;
+    let owner = github_check_run.repository.owner.login.as_str();
+    let repo = github_check_run.repository.name.as_str();
+    let marker = plan_comment_marker(&github_check_run.job_details);
+    let client = Client::new();
+
+    // https://docs.github.com/en/rest/issues/comments?apiVersion=2022-11-28#list-issue-comments
+    let comments_url = format!(
+        "{}/repos/{}/{}/issues/{}/comments",
+        GITHUB_API_URL, owner, repo, number
+    );
+    let mut existing = None;
+    for page in 1.. {
+        let comments: Vec<Value> = client
+            .get(&comments_url)
+            .query(&[("per_page", 100), ("page", page)])
+            .header("Authorization", format!("token {}", token))
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", INFRAWEAVE_USER_AGENT)
+            .send()?
+            .error_for_status()?
+            .json()?;
+        existing = comments
+            .iter()
+            .find(|c| c["body"].as_str().is_some_and(|b| b.starts_with(&marker)))
+            .and_then(|c| c["id"].as_u64());
+        if existing.is_some() || comments.len() < 100 {
+            break;
+        }
+    }
+
+    let request = match existing {
+        Some(comment_id) => client.patch(format!(
+            "{}/repos/{}/{}/issues/comments/{}",
+            GITHUB_API_URL, owner, repo, comment_id
+        )),
+        None => client.post(&comments_url),
+    };
+    let comment_result: Value = request
+        .header("Authorization", format!("token {}", token))
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", INFRAWEAVE_USER_AGENT)
+        .json(&json!({ "body": body }))
+        .send()?
+        .error_for_status()?
+        .json()?;
+
+    Ok(comment_result)
+}
+
 pub async fn poll_and_process_new_packages(
     org: &str,
     poll_interval_minutes: u64,
@@ -1961,169 +2535,81 @@ mod tests {
     }
 
     #[test]
-    fn test_should_process_file_yaml_extensions() {
-        // Test .yaml extension with no prefix filter
-        assert_eq!(should_process_file("deployment.yaml", None), true);
-        assert_eq!(should_process_file("infra/config.yaml", None), true);
-        assert_eq!(should_process_file("path/to/file.yaml", None), true);
-
-        // Test .yml extension
-        assert_eq!(should_process_file("deployment.yml", None), true);
-        assert_eq!(should_process_file("infra/config.yml", None), true);
-        assert_eq!(should_process_file("path/to/file.yml", None), true);
-
-        // Test non-YAML files
-        assert_eq!(should_process_file("README.md", None), false);
-        assert_eq!(should_process_file("script.sh", None), false);
-        assert_eq!(should_process_file("main.rs", None), false);
-        assert_eq!(should_process_file("config.json", None), false);
-        assert_eq!(should_process_file("Dockerfile", None), false);
-        assert_eq!(should_process_file("file.txt", None), false);
-        assert_eq!(should_process_file("infra/README.md", None), false);
-    }
-
-    #[test]
-    fn test_should_process_file_with_prefix() {
-        // Test with "infra/" prefix
-        let prefix = Some("infra/");
-
-        // Should process: YAML files in infra/
-        assert_eq!(should_process_file("infra/deployment.yaml", prefix), true);
-        assert_eq!(should_process_file("infra/config.yml", prefix), true);
-        assert_eq!(
-            should_process_file("infra/nested/service.yaml", prefix),
-            true
-        );
+    fn test_commit_from_compare_files() {
+        let files = serde_json::from_str::<Vec<Value>>(
+            r#"[
+                { "filename": "infra/new.yaml", "status": "added" },
+                { "filename": "infra/old.yaml", "status": "removed" },
+                { "filename": "infra/b.yaml", "previous_filename": "infra/a.yaml", "status": "renamed" },
+                { "filename": "infra/bucket.yaml", "status": "modified" }
+            ]"#,
+        )
+        .unwrap();
 
-        // Should NOT process: YAML files outside infra/
-        assert_eq!(should_process_file("deployment.yaml", prefix), false);
-        assert_eq!(should_process_file("config.yml", prefix), false);
-        assert_eq!(should_process_file("other/deployment.yaml", prefix), false);
-        assert_eq!(should_process_file("claims/service.yml", prefix), false);
+        let commit = commit_from_compare_files(&files);
 
-        // Should NOT process: non-YAML files in infra/
-        assert_eq!(should_process_file("infra/README.md", prefix), false);
-        assert_eq!(should_process_file("infra/script.sh", prefix), false);
+        assert_eq!(commit["added"], json!(["infra/new.yaml", "infra/b.yaml"]));
+        assert_eq!(commit["removed"], json!(["infra/old.yaml", "infra/a.yaml"]));
+        assert_eq!(commit["modified"], json!(["infra/bucket.yaml"]));
     }
 
-    #[test]
-    fn test_should_process_file_with_different_prefixes() {
-        // Test with "claims/" prefix
-        assert_eq!(
-            should_process_file("claims/deployment.yaml", Some("claims/")),
-            true
-        );
-        assert_eq!(
-            should_process_file("infra/deployment.yaml", Some("claims/")),
-            false
-        );
-        assert_eq!(
-            should_process_file("deployment.yaml", Some("claims/")),
-            false
-        );
-
-        // Test with nested prefix
-        assert_eq!(
-            should_process_file("config/production/service.yaml", Some("config/production/")),
-            true
-        );
-        assert_eq!(
-            should_process_file("config/service.yaml", Some("config/production/")),
-            false
-        );
-        assert_eq!(
-            should_process_file("production/service.yaml", Some("config/production/")),
-            false
-        );
+    fn plan_job_details(status: &str) -> JobDetails {
+        JobDetails {
+            region: "us-west-2".to_string(),
+            environment: "github-org-repo/dev".to_string(),
+            deployment_id: "s3bucket-bucket1".to_string(),
+            job_id: "job-1".to_string(),
+            change_type: "PLAN".to_string(),
+            file_path: "infra/bucket.yaml".to_string(),
+            status: status.to_string(),
+            error_text: "Error: invalid bucket name".to_string(),
+        }
+    }
 
-        // Test with prefix without trailing slash
-        assert_eq!(should_process_file("stacks/app.yaml", Some("stacks")), true);
-        assert_eq!(
-            should_process_file("stacks-old/app.yaml", Some("stacks")),
-            true
-        ); // starts_with matches
-        assert_eq!(
-            should_process_file("modules/app.yaml", Some("stacks")),
-            false
-        );
+    fn resource_change(address: &str, action: ResourceAction) -> SanitizedResourceChange {
+        SanitizedResourceChange {
+            address: address.to_string(),
+            resource_type: "aws_s3_bucket".to_string(),
+            name: "bucket".to_string(),
+            mode: Default::default(),
+            provider: None,
+            action,
+            action_reason: None,
+            index: None,
+            depends_on: None,
+            before: None,
+            after: None,
+            changes: None,
+        }
     }
 
     #[test]
-    fn test_should_process_file_empty_prefix() {
-        // Empty prefix should process all YAML files
-        assert_eq!(should_process_file("deployment.yaml", Some("")), true);
-        assert_eq!(should_process_file("infra/deployment.yaml", Some("")), true);
-        assert_eq!(should_process_file("any/path/config.yml", Some("")), true);
-        assert_eq!(should_process_file("README.md", Some("")), false);
-
-        // Whitespace-only prefix should also process all YAML files
-        assert_eq!(should_process_file("deployment.yaml", Some("   ")), true);
-        assert_eq!(
-            should_process_file("infra/deployment.yaml", Some("   ")),
-            true
+    fn test_plan_comment_body_warns_about_destroyed_resources() {
+        let changes = vec![
+            resource_change("aws_s3_bucket.logs", ResourceAction::Create),
+            resource_change("aws_s3_bucket.data", ResourceAction::Replace),
+        ];
+
+        let body = get_plan_comment_body(
+            &plan_job_details("success"),
+            Some(&changes),
+            Some("flowchart LR"),
         );
-    }
 
-    #[test]
-    fn test_should_process_file_no_prefix_env_var() {
-        // When prefix is None, should process all YAML files
-        assert_eq!(should_process_file("deployment.yaml", None), true);
-        assert_eq!(should_process_file("infra/deployment.yaml", None), true);
-        assert_eq!(should_process_file("claims/service.yml", None), true);
-        assert_eq!(should_process_file("any/path/file.yaml", None), true);
-        assert_eq!(should_process_file("README.md", None), false);
-        assert_eq!(should_process_file("script.sh", None), false);
+        assert!(body.starts_with("<!-- infraweave-plan: github-org-repo/dev/s3bucket-bucket1 -->"));
+        assert!(body.contains("This plan destroys 1 resource(s)"));
+        assert!(body.contains("`aws_s3_bucket.data` is replaced"));
+        assert!(!body.contains("`aws_s3_bucket.logs` is"));
+        assert!(body.contains(&pretty_print_resource_changes(&changes)));
+        assert!(body.contains("```mermaid\nflowchart LR\n```"));
     }
 
     #[test]
-    fn test_should_process_file_edge_cases() {
-        // Files with YAML-like names but wrong extension
-        assert_eq!(should_process_file("file.yaml.bak", None), false);
-        assert_eq!(should_process_file("yaml.txt", None), false);
-        assert_eq!(should_process_file("deployment.yaml.old", None), false);
-
-        // Hidden YAML files
-        assert_eq!(should_process_file(".github/workflows/ci.yaml", None), true);
-        assert_eq!(should_process_file(".config.yml", None), true);
-
-        // YAML files with no directory
-        assert_eq!(should_process_file("config.yaml", None), true);
-        assert_eq!(should_process_file("service.yml", None), true);
-
-        // Multiple extensions (only last matters)
-        assert_eq!(should_process_file("file.tar.gz", None), false);
-        assert_eq!(should_process_file("backup.yaml.gz", None), false);
-
-        // Test with prefix
-        assert_eq!(
-            should_process_file("infra/.hidden.yaml", Some("infra/")),
-            true
-        );
-        assert_eq!(should_process_file(".hidden.yaml", Some("infra/")), false);
-    }
+    fn test_plan_comment_body_for_failed_plan() {
+        let body = get_plan_comment_body(&plan_job_details("failed"), None, None);
 
-    #[test]
-    fn test_should_process_file_cross_boundary_moves() {
-        let prefix = Some("infra/");
-
-        // Scenario 1: File moved FROM outside TO inside the prefix
-        // Expected: Only the new location is processed (treated as ADD)
-        assert_eq!(should_process_file("other/deployment.yaml", prefix), false);
-        assert_eq!(should_process_file("infra/deployment.yaml", prefix), true);
-
-        // Scenario 2: File moved FROM inside TO outside the prefix
-        // Expected: Only the old location is processed (treated as DELETE)
-        assert_eq!(should_process_file("infra/deployment.yaml", prefix), true);
-        assert_eq!(should_process_file("other/deployment.yaml", prefix), false);
-
-        // Scenario 3: File moved within the prefix (normal rename)
-        // Expected: Both locations match, rename detection works normally
-        assert_eq!(should_process_file("infra/old.yaml", prefix), true);
-        assert_eq!(should_process_file("infra/new.yaml", prefix), true);
-
-        // Scenario 4: File moved outside the prefix (ignored)
-        // Expected: Neither location is processed, entire operation ignored
-        assert_eq!(should_process_file("other/old.yaml", prefix), false);
-        assert_eq!(should_process_file("other/new.yaml", prefix), false);
+        assert!(body.contains("Plan failed"));
+        assert!(body.contains("Error: invalid bucket name"));
+        assert!(!body.contains("Resource changes"));
     }
 }
@@ -0,0 +1,730 @@
+use chrono::Utc;
+use env_common::interface::GenericCloudHandler;
+use env_common::logic::{
+    destroy_infra, get_deployment_details, publish_notification, run_claim, set_deployment,
+};
+use env_defs::{
+    CloudProvider, DeploymentManifest, ExtraData, GitLabCheckRun, GitLabCommitStatus,
+    GitLabPipeline, GitLabProject, JobDetails, NotificationData, User,
+};
+use futures::stream::{self, StreamExt};
+use reqwest::blocking::{Client, Response};
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::{env, error::Error};
+use subtle::ConstantTimeEq;
+
+use crate::provider::{
+    get_approval_requirement, get_before_ref, process_webhook_files, resolve_var_file, Commit,
+    GitProvider, WebhookPayload,
+};
+use crate::{
+    get_project_id_for_repository_path, get_securestring_aws, group_files_by_manifest, FileChange,
+};
+
+const INFRAWEAVE_USER_AGENT: &str = "infraweave/gitops";
+const GITLAB_API_URL: &str = "https://gitlab.com/api/v4";
+// GitLab rejects commit status descriptions longer than this
+const COMMIT_STATUS_DESCRIPTION_LIMIT: usize = 255;
+
+/// Base URL of the GitLab API, overridable for self-managed instances
+fn gitlab_api_url() -> String {
+    env::var("GITLAB_API_URL").unwrap_or(GITLAB_API_URL.to_string())
+}
+
+/// A GitLab project accessed with an access token
+struct GitLabRepo<'a> {
+    project_id: i64,
+    web_url: &'a str,
+    token: &'a str,
+}
+
+impl GitLabRepo<'_> {
+    /// Builds a project API URL, percent-encoding each segment (file paths and branches may contain `/`)
+    fn api_url(&self, segments: &[&str]) -> Result<Url, Box<dyn Error>> {
+        let mut url = Url::parse(&format!(
+            "{}/projects/{}",
+            gitlab_api_url(),
+            self.project_id
+        ))?;
+        url.path_segments_mut()
+            .map_err(|_| "GitLab API URL cannot be a base")?
+            .extend(segments);
+        Ok(url)
+    }
+
+    fn get(&self, url: Url) -> Result<Response, Box<dyn Error>> {
+        Ok(Client::new()
+            .get(url)
+            .header("User-Agent", INFRAWEAVE_USER_AGENT)
+            .header("PRIVATE-TOKEN", self.token)
+            .send()?)
+    }
+}
+
+impl GitProvider for GitLabRepo<'_> {
+    fn environment_prefix(&self) -> &'static str {
+        "gitlab"
+    }
+
+    fn get_default_branch(&self) -> Result<String, Box<dyn Error>> {
+        let project_info: Value = self.get(self.api_url(&[])?)?.error_for_status()?.json()?;
+        let default_branch = project_info["default_branch"]
+            .as_str()
+            .ok_or("Missing default_branch in project info")?;
+
+        Ok(default_branch.to_string())
+    }
+
+    fn get_default_branch_sha(&self) -> Result<String, Box<dyn Error>> {
+        let default_branch = self.get_default_branch()?;
+
+        let branch_url = self.api_url(&["repository", "branches", &default_branch])?;
+        let branch_info: Value = self.get(branch_url)?.error_for_status()?.json()?;
+        let sha = branch_info["commit"]["id"]
+            .as_str()
+            .ok_or("Missing commit id in branch info")?
+            .to_string();
+
+        Ok(sha)
+    }
+
+    fn get_file_content_option(
+        &self,
+        path: &str,
+        reference: &str,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        let mut url = self.api_url(&["repository", "files", path, "raw"])?;
+        url.query_pairs_mut().append_pair("ref", reference);
+        let response = self.get(url)?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        Ok(Some(response.error_for_status()?.text()?))
+    }
+
+    fn file_url(&self, branch: &str, path: &str) -> String {
+        format!("{}/-/blob/{}/{}", self.web_url, branch, path)
+    }
+}
+
+fn verify_token(token: &str, gitlab_secret: &str) -> bool {
+    if token.is_empty() {
+        return false;
+    }
+
+    // Compare using constant-time equality check to prevent timing attacks.
+    token.as_bytes().ct_eq(gitlab_secret.as_bytes()).unwrap_u8() == 1
+}
+
+/// Read the GitLab access token used for API calls
+pub async fn get_gitlab_token() -> Result<String, anyhow::Error> {
+    let token_ssm_key = env::var("GITLAB_TOKEN_PARAMETER_STORE_KEY")
+        .expect("GITLAB_TOKEN_PARAMETER_STORE_KEY environment variable not set");
+    get_securestring_aws(&token_ssm_key).await
+}
+
+pub async fn handle_validate_gitlab_event(event: &Value) -> Result<Value, anyhow::Error> {
+    println!("Event: {:?}", event);
+
+    let token = event
+        .get("headers")
+        .and_then(|h| h.get("x-gitlab-token"))
+        .and_then(|s| s.as_str())
+        .unwrap_or("");
+
+    let gitlab_secret_parameter_store_key = env::var("GITLAB_SECRET_PARAMETER_STORE_KEY")
+        .expect("GITLAB_SECRET_PARAMETER_STORE_KEY environment variable not set");
+    let gitlab_secret = get_securestring_aws(&gitlab_secret_parameter_store_key).await?;
+
+    if !verify_token(token, &gitlab_secret) {
+        return Err(anyhow::anyhow!("Invalid token"));
+    }
+
+    let handler = GenericCloudHandler::default().await;
+
+    let notification = NotificationData {
+        subject: "validated_gitlab_event".to_string(),
+        message: event.clone(),
+        owners: vec![],
+    };
+
+    match publish_notification(&handler, notification).await {
+        Ok(_) => {
+            println!("Notification published");
+            Ok(json!({
+                "statusCode": 200,
+                "body": "Validated successfully and forwarded for processing",
+            }))
+        }
+        Err(e) => {
+            println!("Error publishing notification: {:?}", e);
+            Err(anyhow::anyhow!("Error publishing notification: {:?}", e))
+        }
+    }
+}
+
+fn gitlab_project(project: &Value) -> Result<GitLabProject, anyhow::Error> {
+    Ok(GitLabProject {
+        id: project["id"]
+            .as_i64()
+            .ok_or(anyhow::anyhow!("Missing project id"))?,
+        name: project["name"].as_str().unwrap_or("").to_string(),
+        path_with_namespace: project["path_with_namespace"]
+            .as_str()
+            .ok_or(anyhow::anyhow!("Missing project path_with_namespace"))?
+            .to_string(),
+        web_url: project["web_url"].as_str().unwrap_or("").to_string(),
+    })
+}
+
+/// Profile URL of a user on the same GitLab instance as the project
+fn user_profile_url(project: &GitLabProject, username: &str) -> String {
+    let instance_url = project
+        .web_url
+        .strip_suffix(&project.path_with_namespace)
+        .unwrap_or(&project.web_url);
+    format!("{}{}", instance_url, username)
+}
+
+pub async fn handle_process_gitlab_push_event(event: &Value) -> Result<Value, anyhow::Error> {
+    println!("handle_process_gitlab_push_event: {:?}", event);
+    let body_str = event.get("body").and_then(|b| b.as_str()).unwrap_or("");
+    let payload: Value = serde_json::from_str(body_str)?;
+
+    let project = gitlab_project(&payload["project"])?;
+    let branch = payload["ref"].as_str().unwrap_or("");
+    let default_branch = payload["project"]["default_branch"]
+        .as_str()
+        .unwrap_or("main");
+
+    // Plans for other branches run on merge request events, running them here as well would duplicate them
+    if branch != format!("refs/heads/{}", default_branch) {
+        println!("Skipping push to non-default branch: {}", branch);
+        return Ok(json!({
+            "statusCode": 200,
+            "body": "Push to non-default branch ignored",
+        }));
+    }
+
+    let username = payload["user_username"].as_str().unwrap_or("");
+    let user = User {
+        email: payload["user_email"]
+            .as_str()
+            .unwrap_or("Unknown author email")
+            .to_string(),
+        name: payload["user_name"]
+            .as_str()
+            .unwrap_or("Unknown author name")
+            .to_string(),
+        username: username.to_string(),
+        profile_url: user_profile_url(&project, username),
+    };
+
+    let push_payload: WebhookPayload = serde_json::from_str(body_str)?;
+
+    process_gitlab_changes(project, user, push_payload, None).await
+}
+
+pub async fn handle_process_gitlab_merge_request_event(
+    event: &Value,
+) -> Result<Value, anyhow::Error> {
+    println!("handle_process_gitlab_merge_request_event: {:?}", event);
+    let body_str = event.get("body").and_then(|b| b.as_str()).unwrap_or("");
+    let payload: Value = serde_json::from_str(body_str)?;
+    let attributes = &payload["object_attributes"];
+
+    // Only new or reopened merge requests and updates that add commits need a new plan
+    let action = attributes["action"].as_str().unwrap_or("");
+    let has_new_commits = attributes.get("oldrev").is_some_and(|v| !v.is_null());
+    if !(matches!(action, "open" | "reopen") || (action == "update" && has_new_commits)) {
+        println!("Skipping merge request action: {}", action);
+        return Ok(json!({
+            "statusCode": 200,
+            "body": format!("Merge request action {} ignored", action),
+        }));
+    }
+
+    if attributes["source_project_id"] != attributes["target_project_id"] {
+        println!("Skipping merge request from a fork");
+        return Ok(json!({
+            "statusCode": 200,
+            "body": "Merge requests from forks are not supported",
+        }));
+    }
+
+    let project = gitlab_project(&payload["project"])?;
+    let iid = attributes["iid"]
+        .as_i64()
+        .ok_or(anyhow::anyhow!("Missing merge request iid"))?;
+    let source_branch = attributes["source_branch"]
+        .as_str()
+        .ok_or(anyhow::anyhow!("Missing source_branch"))?;
+    let head_sha = attributes["last_commit"]["id"]
+        .as_str()
+        .ok_or(anyhow::anyhow!("Missing last_commit id"))?;
+
+    let username = payload["user"]["username"].as_str().unwrap_or("");
+    let user = User {
+        email: payload["user"]["email"]
+            .as_str()
+            .unwrap_or("Unknown author email")
+            .to_string(),
+        name: payload["user"]["name"]
+            .as_str()
+            .unwrap_or("Unknown author name")
+            .to_string(),
+        username: username.to_string(),
+        profile_url: user_profile_url(&project, username),
+    };
+
+    let token = get_gitlab_token().await?;
+    let repo = GitLabRepo {
+        project_id: project.id,
+        web_url: &project.web_url,
+        token: &token,
+    };
+    let push_payload = merge_request_push_payload(&repo, iid, source_branch, head_sha)
+        .map_err(|e| anyhow::anyhow!("Failed to compare merge request changes: {}", e))?;
+
+    process_gitlab_changes(project, user, push_payload, Some(iid)).await
+}
+
+/// Builds a push-shaped payload for a merge request from the diff between its base, where it
+/// branched off its target branch, and its head
+fn merge_request_push_payload(
+    repo: &GitLabRepo,
+    iid: i64,
+    source_branch: &str,
+    head_sha: &str,
+) -> Result<WebhookPayload, Box<dyn Error>> {
+    let merge_request: Value = repo
+        .get(repo.api_url(&["merge_requests", &iid.to_string()])?)?
+        .error_for_status()?
+        .json()?;
+    let base_sha = merge_request["diff_refs"]["base_sha"]
+        .as_str()
+        .ok_or("Missing diff_refs.base_sha in merge request")?;
+
+    let mut url = repo.api_url(&["repository", "compare"])?;
+    url.query_pairs_mut()
+        .append_pair("from", base_sha)
+        .append_pair("to", head_sha);
+    let comparison: Value = repo.get(url)?.error_for_status()?.json()?;
+    let diffs = comparison["diffs"]
+        .as_array()
+        .ok_or("Missing diffs in comparison")?;
+
+    Ok(WebhookPayload {
+        _ref: format!("refs/heads/{}", source_branch),
+        before: base_sha.to_string(),
+        after: head_sha.to_string(),
+        commits: vec![commit_from_compare_diffs(diffs)],
+        base_ref: Some(base_sha.to_string()),
+    })
+}
+
+fn commit_from_compare_diffs(diffs: &[Value]) -> Commit {
+    let mut commit = Commit {
+        added: vec![],
+        removed: vec![],
+        modified: vec![],
+    };
+    for diff in diffs {
+        let old_path = diff["old_path"].as_str().unwrap_or("").to_string();
+        let new_path = diff["new_path"].as_str().unwrap_or("").to_string();
+        if diff["new_file"].as_bool().unwrap_or(false) {
+            commit.added.push(new_path);
+        } else if diff["deleted_file"].as_bool().unwrap_or(false) {
+            commit.removed.push(old_path);
+        } else if diff["renamed_file"].as_bool().unwrap_or(false) {
+            // Renames are detected from file contents when grouping by manifest
+            commit.removed.push(old_path);
+            commit.added.push(new_path);
+        } else {
+            commit.modified.push(new_path);
+        }
+    }
+    commit
+}
+
+enum FileAction {
+    Apply,
+    Destroy,
+    Rename,
+}
+
+async fn process_gitlab_changes(
+    project: GitLabProject,
+    user: User,
+    payload: WebhookPayload,
+    merge_request_iid: Option<i64>,
+) -> Result<Value, anyhow::Error> {
+    let (project_id, project_id_found) =
+        match get_project_id_for_repository_path(&project.path_with_namespace).await {
+            Ok(project_id) => (project_id, true),
+            Err(e) => {
+                println!("Error getting project id: {:?}", e);
+                ("NOT_FOUND_FOR_REPO".to_string(), false)
+            }
+        };
+
+    let token = get_gitlab_token().await?;
+    let repo = GitLabRepo {
+        project_id: project.id,
+        web_url: &project.web_url,
+        token: &token,
+    };
+
+    let processed = process_webhook_files(&repo, &payload)
+        .map_err(|e| anyhow::anyhow!("Failed to process changed files: {}", e))?;
+    let before_ref = get_before_ref(&repo, &payload)
+        .map_err(|e| anyhow::anyhow!("Failed to get previous commit: {}", e))?;
+    println!("Processed files: {:?}", processed);
+
+    let grouped = group_files_by_manifest(processed);
+    println!("Grouped files: {:?}", grouped);
+
+    let default_branch = repo.get_default_branch().unwrap_or("main".to_string());
+    let environment_prefix = format!(
+        "{}-{}",
+        repo.environment_prefix(),
+        project.path_with_namespace.replace("/", "-").to_lowercase()
+    );
+    let now = Utc::now().to_rfc3339();
+    let pipeline = GitLabPipeline {
+        id: 0,
+        sha: payload.after.clone(),
+        status: "running".to_string(),
+        web_url: format!("{}/-/commit/{}", project.web_url, payload.after),
+        created_at: now.clone(),
+        updated_at: now,
+    };
+
+    stream::iter(grouped)
+        .for_each_concurrent(None, |group| {
+            let repo = &repo;
+            let default_branch = &default_branch;
+            let environment_prefix = &environment_prefix;
+            let project_id = &project_id;
+            let token = &token;
+            let before_ref = &before_ref;
+            let mut gitlab_run = GitLabCheckRun {
+                project: project.clone(),
+                pipeline: pipeline.clone(),
+                job_details: JobDetails {
+                    region: "OVERRIDE".to_string(),
+                    environment: "OVERRIDE".to_string(),
+                    deployment_id: "OVERRIDE".to_string(),
+                    job_id: "OVERRIDE".to_string(),
+                    change_type: "OVERRIDE".to_string(),
+                    file_path: "OVERRIDE".to_string(),
+                    error_text: "OVERRIDE".to_string(),
+                    status: "OVERRIDE".to_string(),
+                },
+                commit_status: GitLabCommitStatus {
+                    name: "OVERRIDE".to_string(),
+                    state: "running".to_string(),
+                    description: None,
+                    target_url: None,
+                },
+                user: Some(user.clone()),
+                merge_request_iid,
+            };
+            async move {
+                let (file, canonical, action) = if let Some((active, canonical)) = group.active {
+                    (active, canonical, FileAction::Apply)
+                } else if let Some((deleted, canonical)) = group.deleted {
+                    (deleted, canonical, FileAction::Destroy)
+                } else if let Some((renamed, canonical)) = group.renamed {
+                    (renamed, canonical, FileAction::Rename)
+                } else {
+                    println!("Group with key {:?} has no file!", group.key);
+                    return;
+                };
+
+                gitlab_run.job_details.file_path = file.path.clone();
+                gitlab_run.commit_status.name = file.path.clone();
+                gitlab_run.commit_status.target_url =
+                    Some(repo.file_url(default_branch, &file.path));
+                // Deleted claims and their variable files are only found in the previous commit
+                let reference = match action {
+                    FileAction::Destroy => before_ref.clone(),
+                    _ => gitlab_run.pipeline.sha.clone(),
+                };
+
+                if !project_id_found {
+                    gitlab_run.commit_status.state = "failed".to_string();
+                    gitlab_run.commit_status.description = Some(
+                        "This repository is not yet configured, please assign it to a project_id and region".to_string(),
+                    );
+                } else if let Err(e) = run_gitlab_file_action(
+                    &mut gitlab_run,
+                    &file,
+                    &canonical,
+                    action,
+                    &reference,
+                    project_id,
+                    environment_prefix,
+                    repo,
+                    default_branch,
+                    token,
+                )
+                .await
+                {
+                    println!("Job failed for {}: {:?}", file.path, e);
+                    gitlab_run.commit_status.state = "failed".to_string();
+                    gitlab_run.commit_status.description = Some(format!("Error: {}", e));
+                }
+
+                if let Err(e) = post_commit_status_from_payload(&gitlab_run, token).await {
+                    println!("Error posting commit status: {}", e);
+                }
+            }
+        })
+        .await;
+
+    Ok(json!({
+        "statusCode": 200,
+        "body": "Processed successfully",
+    }))
+}
+
+/// Starts the job for a changed claim file and fills in the commit status to report
+#[allow(clippy::too_many_arguments)]
+async fn run_gitlab_file_action(
+    gitlab_run: &mut GitLabCheckRun,
+    file: &FileChange,
+    canonical: &str,
+    action: FileAction,
+    reference: &str,
+    project_id: &str,
+    environment_prefix: &str,
+    repo: &GitLabRepo<'_>,
+    default_branch: &str,
+    token: &str,
+) -> Result<(), anyhow::Error> {
+    let mut yaml = serde_yaml::from_str::<serde_yaml::Value>(canonical)?;
+    let deployment_claim = serde_yaml::from_value::<DeploymentManifest>(yaml.clone())?;
+    let region = deployment_claim.spec.region.clone();
+    let namespace = deployment_claim
+        .metadata
+        .namespace
+        .clone()
+        .unwrap_or("default".to_string());
+    let environment = format!("{}/{}", environment_prefix, namespace);
+    let full_file_url = repo.file_url(default_branch, &file.path);
+    let plan_only = gitlab_run.merge_request_iid.is_some();
+
+    gitlab_run.commit_status.name = get_commit_status_name(
+        &deployment_claim.metadata.name,
+        &file.path,
+        &region,
+        &namespace,
+    );
+
+    let handler = GenericCloudHandler::workload(project_id, &region).await;
+
+    if let FileAction::Rename = action {
+        let (_region, environment, deployment_id, _module, _name) =
+            get_deployment_details(&environment, deployment_claim)?;
+        let mut deployment = handler
+            .get_deployment(&deployment_id, &environment, false)
+            .await?
+            .ok_or(anyhow::anyhow!("Deployment {} not found", deployment_id))?;
+        deployment.reference = full_file_url;
+        set_deployment(&handler, &deployment, false).await?;
+
+        gitlab_run.commit_status.state = "success".to_string();
+        gitlab_run.commit_status.description = Some(format!(
+            "File renamed, only the reference has been updated for {}",
+            deployment_id
+        ));
+        return Ok(());
+    }
+
+    let command = match (&action, plan_only) {
+        (_, true) => "plan",
+        (FileAction::Destroy, false) => "destroy",
+        _ => "apply",
+    };
+
+    // Destroy uses the latest known claim, so only the other jobs need the variable file
+    if command != "destroy" {
+        resolve_var_file(repo, &file.path, &mut yaml, reference)?;
+    }
+
+    let required_approvals = get_approval_requirement(&handler, &environment).await;
+    let pending_approval = command != "plan" && required_approvals > 0;
+
+    // Report the job as running before starting it, so the runner's final status is never overwritten.
+    // A job pending approval starts no runner, so its status stays pending until it is approved
+    gitlab_run.commit_status.state = match pending_approval {
+        true => "pending".to_string(),
+        false => "running".to_string(),
+    };
+    gitlab_run.commit_status.description = Some(match (pending_approval, required_approvals) {
+        (true, _) => format!(
+            "{} job pending approval, requires {} approval(s)",
+            command, required_approvals
+        ),
+        (false, 0) => format!("{} job initiated", command),
+        (false, _) => format!(
+            "{} job initiated, changes require {} approval(s) before they are applied",
+            command, required_approvals
+        ),
+    });
+    if let Err(e) = post_commit_status_from_payload(gitlab_run, token).await {
+        println!("Error posting commit status: {}", e);
+    }
+
+    let extra_data = ExtraData::GitLab(gitlab_run.clone());
+    match action {
+        FileAction::Destroy if !plan_only => {
+            // Destroy uses the latest known claim, since Terraform can not destroy infra with a
+            // manifest that has the wrong variables
+            let (_region, environment, deployment_id, _module, _name) =
+                get_deployment_details(&environment, deployment_claim)?;
+            let job_id =
+                destroy_infra(&handler, &deployment_id, &environment, extra_data, None).await?;
+            println!("Destroy job started with job_id: {job_id}");
+        }
+        _ => {
+            let flags = match action {
+                FileAction::Destroy => vec!["-destroy".to_string()],
+                _ => vec![],
+            };
+            run_claim(
+                &handler,
+                &yaml,
+                &environment,
+                command,
+                flags,
+                extra_data,
+                &full_file_url,
+            )
+            .await?;
+            println!("{command} job started");
+        }
+    }
+
+    // The runner posts the final status once the job completes
+    Ok(())
+}
+
+fn get_commit_status_name(name: &str, path: &str, region: &str, namespace: &str) -> String {
+    format!("{} ({}) - {} ({})", name, region, path, namespace)
+}
+
+pub async fn post_commit_status_from_payload(
+    gitlab_run: &GitLabCheckRun,
+    token: &str,
+) -> Result<Value, Box<dyn Error>> {
+    let repo = GitLabRepo {
+        project_id: gitlab_run.project.id,
+        web_url: &gitlab_run.project.web_url,
+        token,
+    };
+
+    let mut commit_status = gitlab_run.commit_status.clone();
+    if let Some(description) = commit_status.description.as_mut() {
+        if description.chars().count() > COMMIT_STATUS_DESCRIPTION_LIMIT {
+            *description = description
+                .chars()
+                .take(COMMIT_STATUS_DESCRIPTION_LIMIT)
+                .collect();
+        }
+    }
+    let body = serde_json::to_value(commit_status)?;
+
+    println!("GitLab commit status: {:?}", body);
+
+    // https://docs.gitlab.com/api/commits/#set-the-pipeline-status-of-a-commit
+    let status_url = repo.api_url(&["statuses", &gitlab_run.pipeline.sha])?;
+    let status_response = Client::new()
+        .post(status_url)
+        .header("PRIVATE-TOKEN", token)
+        .header("User-Agent", INFRAWEAVE_USER_AGENT)
+        .json(&body)
+        .send()?
+        .error_for_status()?;
+    let status_result: Value = status_response.json()?;
+
+    Ok(status_result)
+}
+
+/// Comment on the merge request the job was triggered from, since commit statuses can not hold plan output
+pub async fn post_merge_request_note_from_payload(
+    gitlab_run: &GitLabCheckRun,
+    note: &str,
+    token: &str,
+) -> Result<Value, Box<dyn Error>> {
+    let iid = gitlab_run
+        .merge_request_iid
+        .ok_or("Job was not triggered from a merge request")?;
+    let repo = GitLabRepo {
+        project_id: gitlab_run.project.id,
+        web_url: &gitlab_run.project.web_url,
+        token,
+    };
+
+    let note_url = repo.api_url(&["merge_requests", &iid.to_string(), "notes"])?;
+    let note_response = Client::new()
+        .post(note_url)
+        .header("PRIVATE-TOKEN", token)
+        .header("User-Agent", INFRAWEAVE_USER_AGENT)
+        .json(&json!({ "body": note }))
+        .send()?
+        .error_for_status()?;
+    let note_result: Value = note_response.json()?;
+
+    Ok(note_result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_gitlab_token_verification() {
+        assert!(verify_token("my-gitlab-secret", "my-gitlab-secret"));
+        assert!(!verify_token("other-secret", "my-gitlab-secret"));
+        assert!(!verify_token("", ""));
+    }
+
+    #[test]
+    fn test_commit_from_compare_diffs() {
+        let diffs = serde_json::from_str::<Vec<Value>>(
+            r#"[
+                { "old_path": "infra/new.yaml", "new_path": "infra/new.yaml", "new_file": true, "renamed_file": false, "deleted_file": false },
+                { "old_path": "infra/old.yaml", "new_path": "infra/old.yaml", "new_file": false, "renamed_file": false, "deleted_file": true },
+                { "old_path": "infra/a.yaml", "new_path": "infra/b.yaml", "new_file": false, "renamed_file": true, "deleted_file": false },
+                { "old_path": "infra/bucket.yaml", "new_path": "infra/bucket.yaml", "new_file": false, "renamed_file": false, "deleted_file": false }
+            ]"#,
+        )
+        .unwrap();
+
+        let commit = commit_from_compare_diffs(&diffs);
+
+        assert_eq!(commit.added, vec!["infra/new.yaml", "infra/b.yaml"]);
+        assert_eq!(commit.removed, vec!["infra/old.yaml", "infra/a.yaml"]);
+        assert_eq!(commit.modified, vec!["infra/bucket.yaml"]);
+    }
+
+    #[test]
+    fn test_user_profile_url() {
+        let project = GitLabProject {
+            id: 1,
+            name: "infra".to_string(),
+            path_with_namespace: "group/infra".to_string(),
+            web_url: "https://gitlab.example.com/group/infra".to_string(),
+        };
+        assert_eq!(
+            user_profile_url(&project, "jdoe"),
+            "https://gitlab.example.com/jdoe"
+        );
+    }
+}
@@ -12,25 +12,37 @@ fn extract_manifest_changes(file: &FileChange) -> Vec<ManifestChange> {
             continue;
         }
         doc_index += 1;
-        match serde_yaml::from_str::<DeploymentManifest>(doc) {
-            Ok(manifest) => {
-                let key = GroupKey {
-                    api_version: manifest.api_version.clone(),
-                    kind: manifest.kind.clone(),
-                    name: manifest.metadata.name.clone(),
-                    namespace: manifest
-                        .metadata
-                        .namespace
-                        .clone()
-                        .unwrap_or_else(|| "default".to_string()),
-                    region: manifest.spec.region.clone(),
-                };
-                if let Ok(canonical) = serde_yaml::to_string(&manifest) {
-                    changes.push(ManifestChange {
-                        key,
-                        content: canonical,
-                        file: file.clone(),
-                    });
+        // A claim listing several regions is tracked as one manifest per region
+        let manifests = serde_yaml::from_str::<serde_yaml::Value>(doc)
+            .map_err(anyhow::Error::from)
+            .and_then(|yaml| env_utils::expand_claim_regions(&yaml))
+            .and_then(|claims| {
+                claims
+                    .into_iter()
+                    .map(|claim| Ok(serde_yaml::from_value::<DeploymentManifest>(claim)?))
+                    .collect::<Result<Vec<_>, anyhow::Error>>()
+            });
+        match manifests {
+            Ok(manifests) => {
+                for manifest in manifests {
+                    let key = GroupKey {
+                        api_version: manifest.api_version.clone(),
+                        kind: manifest.kind.clone(),
+                        name: manifest.metadata.name.clone(),
+                        namespace: manifest
+                            .metadata
+                            .namespace
+                            .clone()
+                            .unwrap_or_else(|| "default".to_string()),
+                        region: manifest.spec.region.clone(),
+                    };
+                    if let Ok(canonical) = serde_yaml::to_string(&manifest) {
+                        changes.push(ManifestChange {
+                            key,
+                            content: canonical,
+                            file: file.clone(),
+                        });
+                    }
                 }
             }
             Err(e) => {
@@ -919,4 +931,46 @@ spec:
             }
         });
     }
+
+    #[test]
+    fn test_region_list_removed_region() {
+        let deleted = FileChange {
+            path: "multi_region.yaml".to_string(),
+            content: valid_manifest(
+                "infraweave.io/v1",
+                "Minimal",
+                "multi",
+                Some("default"),
+                "1.0.0",
+                "[us-west-2, eu-central-1]",
+            ),
+        };
+
+        let active = FileChange {
+            path: "multi_region.yaml".to_string(),
+            content: valid_manifest(
+                "infraweave.io/v1",
+                "Minimal",
+                "multi",
+                Some("default"),
+                "1.0.0",
+                "[us-west-2]",
+            ),
+        };
+
+        let processed = ProcessedFiles {
+            active_files: vec![active.clone()],
+            deleted_files: vec![deleted.clone()],
+        };
+
+        let groups = group_files_by_manifest(processed);
+
+        // The unchanged region is skipped, the removed region is destroyed
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert!(group.active.is_none());
+        assert!(group.deleted.is_some());
+        assert_eq!(group.key.name, "multi-eu-central-1");
+        assert_eq!(group.key.region, "eu-central-1");
+    }
 }
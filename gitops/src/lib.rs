@@ -1,18 +1,27 @@
+mod codeowners;
 mod defs;
 pub mod diff;
 pub mod git_utils;
 mod github;
+mod gitlab;
 mod gitops;
 mod project;
+mod provider;
 mod secret;
 
 pub use defs::{FileChange, ProcessedFiles};
 pub use diff::get_diff;
 pub use git_utils::{get_changed_files, get_file_content};
 pub use github::{
-    get_new_packages, handle_check_run_event, handle_package_publish_event,
-    handle_process_push_event, handle_validate_github_event, poll_and_process_new_packages,
-    post_check_run_from_payload,
+    get_new_packages, get_plan_comment_body, handle_check_run_event, handle_package_publish_event,
+    handle_process_push_event, handle_pull_request_event, handle_validate_github_event,
+    poll_and_process_new_packages, post_check_run_from_payload,
+    upsert_pull_request_comment_from_payload,
+};
+pub use gitlab::{
+    get_gitlab_token, handle_process_gitlab_merge_request_event, handle_process_gitlab_push_event,
+    handle_validate_gitlab_event, post_commit_status_from_payload,
+    post_merge_request_note_from_payload,
 };
 pub use gitops::group_files_by_manifest;
 pub use project::get_project_id_for_repository_path;
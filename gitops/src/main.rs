@@ -2,12 +2,15 @@ use std::env;
 
 use aws_lambda_events::event::sqs::SqsEvent;
 use env_common::interface::{initialize_project_id_and_region, GenericCloudHandler};
-use env_defs::{CheckRunOutput, CloudProvider, ExtraData};
+use env_defs::{CheckRunOutput, CloudProvider, ExtraData, InfraChangeRecord, JobDetails};
 use env_utils::setup_logging;
 use gitops::{
-    get_project_id_for_repository_path, get_securestring_aws, handle_check_run_event,
-    handle_package_publish_event, handle_process_push_event, handle_validate_github_event,
-    post_check_run_from_payload,
+    get_gitlab_token, get_plan_comment_body, get_project_id_for_repository_path,
+    get_securestring_aws, handle_check_run_event, handle_package_publish_event,
+    handle_process_gitlab_merge_request_event, handle_process_gitlab_push_event,
+    handle_process_push_event, handle_pull_request_event, handle_validate_github_event,
+    handle_validate_gitlab_event, post_check_run_from_payload, post_commit_status_from_payload,
+    post_merge_request_note_from_payload, upsert_pull_request_comment_from_payload,
 };
 use lambda_runtime::{service_fn, Error, LambdaEvent};
 use log::info;
@@ -47,6 +50,9 @@ async fn handle_sqs_run_response(sqs_event: SqsEvent) -> Result<Value, Error> {
             "validated_github_event" => {
                 process_validated_github_event(payload).await?;
             }
+            "validated_gitlab_event" => {
+                process_validated_gitlab_event(payload).await?;
+            }
             "runner_event" => {
                 process_runner_event(payload).await?;
             }
@@ -93,6 +99,17 @@ async fn process_validated_github_event(payload: Value) -> Result<Value, Error>
                     }
                 }
             }
+            "pull_request" => {
+                return match handle_pull_request_event(&payload).await {
+                    Ok(response) => Ok(response),
+                    Err(e) => {
+                        println!("Error handling pull_request event: {}", e);
+                        Ok(
+                            serde_json::json!({ "status": format!("Error handling pull_request event: {}", e) }),
+                        )
+                    }
+                }
+            }
             "registry_package" => {
                 return match handle_package_publish_event(&payload).await {
                     Ok(response) => Ok(response),
@@ -121,6 +138,140 @@ async fn process_validated_github_event(payload: Value) -> Result<Value, Error>
     )
 }
 
+async fn process_validated_gitlab_event(payload: Value) -> Result<Value, Error> {
+    if let Some(event_type) = &payload
+        .get("headers")
+        .and_then(|headers| headers.get("x-gitlab-event"))
+        .and_then(|value| value.as_str())
+    {
+        let result = match *event_type {
+            "Push Hook" => handle_process_gitlab_push_event(&payload).await,
+            "Merge Request Hook" => handle_process_gitlab_merge_request_event(&payload).await,
+            _ => {
+                println!("Unsupported event type: {}", event_type);
+                println!("Event: {}", serde_json::to_string(&payload).unwrap());
+                return Ok(
+                    serde_json::json!({ "status": format!("Unsupported event type: {}", event_type) }),
+                );
+            }
+        };
+        return match result {
+            Ok(response) => Ok(response),
+            Err(e) => {
+                println!("Error handling {}: {}", event_type, e);
+                Ok(serde_json::json!({ "status": format!("Error handling {}: {}", event_type, e) }))
+            }
+        };
+    } else {
+        println!("No x-gitlab-event header found in payload");
+    }
+
+    Ok(
+        serde_json::json!({ "status": format!("Unknown validated gitlab event in processor: {}", payload) }),
+    )
+}
+
+/// Plan output for successful jobs, the error for failed ones
+async fn get_job_information(handler: &GenericCloudHandler, job_details: &JobDetails) -> String {
+    if job_details.status == "success" {
+        let change_record = handler
+            .get_change_record(
+                &job_details.environment,
+                &job_details.deployment_id,
+                &job_details.job_id,
+                &job_details.change_type,
+            )
+            .await
+            .expect("Failed to get change record");
+        change_record.plan_std_output
+    } else {
+        job_details.error_text.clone()
+    }
+}
+
+/// Pull request comment for a plan job, with the dependency graph when the plan has one
+async fn get_plan_comment(handler: &GenericCloudHandler, job_details: &JobDetails) -> String {
+    let change_record = if job_details.status == "success" {
+        handler
+            .get_change_record(
+                &job_details.environment,
+                &job_details.deployment_id,
+                &job_details.job_id,
+                &job_details.change_type,
+            )
+            .await
+            .map_err(|e| println!("Failed to get change record: {}", e))
+            .ok()
+    } else {
+        None
+    };
+    let mermaid_graph = match &change_record {
+        Some(change_record) => get_plan_mermaid_graph(handler, change_record)
+            .await
+            .map_err(|e| println!("Failed to build plan graph: {}", e))
+            .ok(),
+        None => None,
+    };
+    get_plan_comment_body(
+        job_details,
+        change_record
+            .as_ref()
+            .map(|change_record| change_record.resource_changes.as_slice()),
+        mermaid_graph.as_deref(),
+    )
+}
+
+async fn get_plan_mermaid_graph(
+    handler: &GenericCloudHandler,
+    change_record: &InfraChangeRecord,
+) -> Result<String, anyhow::Error> {
+    // The runner stores the DOT graph next to the plan, e.g. xxx_plan_output.json -> xxx_graph.dot
+    let plan_key = &change_record.plan_raw_json_key;
+    let graph_key = plan_key.replace("_plan_output.json", "_graph.dot");
+    if graph_key == *plan_key {
+        return Err(anyhow::anyhow!("Unknown plan key format: {}", plan_key));
+    }
+
+    let plan_content = download_change_record_file(handler, plan_key).await?;
+    let graph_content = download_change_record_file(handler, &graph_key).await?;
+    let graph = graph::process_graph(
+        &plan_content,
+        &graph_content,
+        false,
+        None,
+        &graph::GraphOptions::default(),
+    )?;
+    Ok(graph.to_mermaid())
+}
+
+async fn download_change_record_file(
+    handler: &GenericCloudHandler,
+    key: &str,
+) -> Result<String, anyhow::Error> {
+    let url = handler
+        .generate_presigned_url(key, "change_records")
+        .await?;
+    Ok(reqwest::get(&url).await?.error_for_status()?.text().await?)
+}
+
+fn get_job_details_text(job_details: &JobDetails, information: &str) -> String {
+    format!(
+        r#"
+# Job Details
+File: **{}**
+Deployment ID: **{}**
+Environment: **{}**
+
+## Information
+
+```diff
+{}
+```
+                "#,
+        job_details.file_path, job_details.deployment_id, job_details.environment, information
+    )
+}
+
 async fn process_runner_event(payload: Value) -> Result<Value, Error> {
     let event: ExtraData = match serde_json::from_value(payload.clone()) {
         Ok(event) => event,
@@ -147,23 +298,7 @@ async fn process_runner_event(payload: Value) -> Result<Value, Error> {
 
             let status = github_event.job_details.status.as_str();
 
-            let information = if status == "success" {
-                github_event.check_run.conclusion = Some("success".into());
-
-                let change_record = handler
-                    .get_change_record(
-                        &github_event.job_details.environment,
-                        &github_event.job_details.deployment_id,
-                        &github_event.job_details.job_id,
-                        &github_event.job_details.change_type,
-                    )
-                    .await
-                    .expect("Failed to get change record");
-                change_record.plan_std_output
-            } else {
-                github_event.check_run.conclusion = Some("failure".into());
-                github_event.job_details.error_text.clone()
-            };
+            let information = get_job_information(&handler, &github_event.job_details).await;
 
             // Process GitHub event.
             github_event.check_run.status = "completed".into();
@@ -177,23 +312,9 @@ async fn process_runner_event(payload: Value) -> Result<Value, Error> {
             github_event.check_run.output = Some(CheckRunOutput {
                 title: format!("{} job completed", github_event.job_details.change_type),
                 summary: format!("Job completed with {}", status),
-                text: Some(format!(
-                    r#"
-# Job Details
-File: **{}**
-Deployment ID: **{}**
-Environment: **{}**
-
-## Information
-
-```diff
-{}
-```
-                "#,
-                    github_event.job_details.file_path,
-                    github_event.job_details.deployment_id,
-                    github_event.job_details.environment,
-                    information
+                text: Some(get_job_details_text(
+                    &github_event.job_details,
+                    &information,
                 )),
                 annotations: None,
             });
@@ -202,7 +323,29 @@ Environment: **{}**
             let private_key_pem_ssm_key = env::var("GITHUB_PRIVATE_KEY_PARAMETER_STORE_KEY")
                 .expect("GITHUB_PRIVATE_KEY_PARAMETER_STORE_KEY environment variable not set");
             let private_key_pem = get_securestring_aws(&private_key_pem_ssm_key).await?; // Read here to avoid multiple reads of the same secret
-                                                                                         // https://docs.github.com/en/rest/checks/runs?apiVersion=2022-11-28#update-a-check-run
+
+            // Check runs only show the plan in the checks tab, so it is also commented on the pull request
+            if github_event.pull_request_number.is_some()
+                && github_event.job_details.change_type == "PLAN"
+            {
+                let comment = get_plan_comment(&handler, &github_event.job_details).await;
+                match upsert_pull_request_comment_from_payload(
+                    &github_event,
+                    &comment,
+                    &private_key_pem,
+                )
+                .await
+                {
+                    Ok(resp) => {
+                        info!("Pull request comment posted: {}", resp["html_url"]);
+                    }
+                    Err(e) => {
+                        info!("Error posting pull request comment: {}", e);
+                    }
+                }
+            }
+
+            // https://docs.github.com/en/rest/checks/runs?apiVersion=2022-11-28#update-a-check-run
             match post_check_run_from_payload(github_event, &private_key_pem).await {
                 Ok(resp) => {
                     info!("Check run posted: {}", resp);
@@ -212,8 +355,55 @@ Environment: **{}**
                 }
             }
         }
-        ExtraData::GitLab(_gitlab_event) => {
-            // Process GitLab event.
+        ExtraData::GitLab(mut gitlab_event) => {
+            println!("GitLab Event: {:?}", gitlab_event);
+
+            let project_id =
+                get_project_id_for_repository_path(&gitlab_event.project.path_with_namespace)
+                    .await?;
+            let region = &gitlab_event.job_details.region;
+            println!(
+                "Found project id: {}, region: {} for path: {}",
+                project_id, region, &gitlab_event.project.path_with_namespace
+            );
+            let handler = GenericCloudHandler::workload(&project_id, region).await;
+
+            let status = gitlab_event.job_details.status.clone();
+            let information = get_job_information(&handler, &gitlab_event.job_details).await;
+
+            gitlab_event.commit_status.state = if status == "success" {
+                "success".into()
+            } else {
+                "failed".into()
+            };
+            gitlab_event.commit_status.description = Some(format!(
+                "{} job completed with {}",
+                gitlab_event.job_details.change_type, status
+            ));
+            gitlab_event.pipeline.status = gitlab_event.commit_status.state.clone();
+            gitlab_event.pipeline.updated_at = chrono::Utc::now().to_rfc3339();
+
+            let token = get_gitlab_token().await?;
+            // Commit statuses only hold a short description, so the plan output goes on the merge request
+            if gitlab_event.merge_request_iid.is_some() {
+                let note = get_job_details_text(&gitlab_event.job_details, &information);
+                match post_merge_request_note_from_payload(&gitlab_event, &note, &token).await {
+                    Ok(resp) => {
+                        info!("Merge request note posted: {}", resp);
+                    }
+                    Err(e) => {
+                        info!("Error posting merge request note: {}", e);
+                    }
+                }
+            }
+            match post_commit_status_from_payload(&gitlab_event, &token).await {
+                Ok(resp) => {
+                    info!("Commit status posted: {}", resp);
+                }
+                Err(e) => {
+                    info!("Error posting commit status: {}", e);
+                }
+            }
         }
         ExtraData::None => {
             // No event data found.
@@ -238,7 +428,7 @@ async fn validator_func(event: LambdaEvent<Value>) -> Result<Value, Error> {
         .and_then(|value| value.as_str())
     {
         match event_type {
-            &"push" | &"check_run" | &"registry_package" => {
+            &"push" | &"check_run" | &"pull_request" | &"registry_package" => {
                 // add more supported events here
                 return match handle_validate_github_event(&_generic_event).await {
                     Ok(response) => Ok(response),
@@ -260,7 +450,32 @@ async fn validator_func(event: LambdaEvent<Value>) -> Result<Value, Error> {
         }
     }
 
-    // TODO: add support for gitlab and other git-providers
+    if let Some(event_type) = &_generic_event
+        .get("headers")
+        .and_then(|headers| headers.get("x-gitlab-event"))
+        .and_then(|value| value.as_str())
+    {
+        match *event_type {
+            "Push Hook" | "Merge Request Hook" => {
+                return match handle_validate_gitlab_event(&_generic_event).await {
+                    Ok(response) => Ok(response),
+                    Err(e) => {
+                        println!("Error validating event type {}: {}", event_type, e);
+                        Ok(
+                            serde_json::json!({ "status": format!("Error validating event type {}: {}", event_type, e) }),
+                        )
+                    }
+                };
+            }
+            _ => {
+                println!("Unsupported event type: {}", event_type);
+                return Ok(
+                    serde_json::json!({ "status": format!("Unsupported event type: {}", event_type) }),
+                );
+            }
+        }
+    }
+
     Ok(serde_json::json!({ "status": "Request in validator without action" }))
 }
 
@@ -0,0 +1,662 @@
+use env_common::interface::GenericCloudHandler;
+use env_common::logic::get_required_approvals;
+use env_defs::ModuleManifest;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::{env, error::Error};
+
+use crate::{FileChange, ProcessedFiles};
+
+/// Repository access the gitops flow needs from a git hosting provider.
+pub(crate) trait GitProvider {
+    /// Prefix of the environments created for claims in this provider's repositories, e.g. `github`
+    fn environment_prefix(&self) -> &'static str;
+
+    fn get_default_branch(&self) -> Result<String, Box<dyn Error>>;
+
+    fn get_default_branch_sha(&self) -> Result<String, Box<dyn Error>>;
+
+    /// Fetch file content for a commit reference, `None` if the file does not exist there
+    fn get_file_content_option(
+        &self,
+        path: &str,
+        reference: &str,
+    ) -> Result<Option<String>, Box<dyn Error>>;
+
+    /// Browser URL of a file on a branch, stored as the deployment reference
+    fn file_url(&self, branch: &str, path: &str) -> String;
+}
+
+/// Push payload fields that GitHub and GitLab push webhooks have in common
+#[derive(Debug, Deserialize)]
+pub(crate) struct WebhookPayload {
+    #[serde(rename = "ref")]
+    pub _ref: String, // branch name
+    pub before: String, // commit SHA before the push
+    pub after: String,  // commit SHA after the push
+    pub commits: Vec<Commit>,
+    /// Commit a merge request is compared against, instead of the default branch
+    #[serde(skip)]
+    pub base_ref: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Commit {
+    // id: String,
+    // tree_id: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+pub(crate) fn should_process_file(file_path: &str, prefix_filter: Option<&str>) -> bool {
+    let is_yaml = file_path.ends_with(".yaml") || file_path.ends_with(".yml");
+
+    if !is_yaml {
+        println!("Skipping non-YAML file: {}", file_path);
+        return false;
+    }
+
+    if let Some(prefix) = prefix_filter {
+        let prefix = prefix.trim();
+        if !prefix.is_empty() {
+            let matches_prefix = file_path.starts_with(prefix);
+            if !matches_prefix {
+                println!(
+                    "Skipping file (doesn't match prefix '{}'): {}",
+                    prefix, file_path
+                );
+                return false;
+            }
+            println!(
+                "Processing file (matches prefix '{}'): {}",
+                prefix, file_path
+            );
+        }
+    }
+
+    true
+}
+
+/// Reference that deleted and previous file contents are read from
+pub(crate) fn get_before_ref(
+    provider: &dyn GitProvider,
+    payload: &WebhookPayload,
+) -> Result<String, Box<dyn Error>> {
+    if let Some(base_ref) = &payload.base_ref {
+        return Ok(base_ref.clone());
+    }
+    let default_branch = provider.get_default_branch()?;
+    let current_branch = payload
+        ._ref
+        .strip_prefix("refs/heads/")
+        .unwrap_or(&payload._ref);
+    if current_branch == default_branch {
+        // For main, compare with the previous commit.
+        Ok(payload.before.clone())
+    } else {
+        // For other branches, get the current commit SHA on main.
+        provider.get_default_branch_sha()
+    }
+}
+
+pub(crate) fn process_webhook_files(
+    provider: &dyn GitProvider,
+    payload: &WebhookPayload,
+) -> Result<ProcessedFiles, Box<dyn Error>> {
+    let before_ref = get_before_ref(provider, payload)?;
+    let after_ref = &payload.after;
+
+    let mut added = std::collections::HashSet::new();
+    let mut removed = std::collections::HashSet::new();
+    let mut modified = std::collections::HashSet::new();
+    for commit in &payload.commits {
+        for file in &commit.added {
+            added.insert(file.clone());
+        }
+        for file in &commit.removed {
+            removed.insert(file.clone());
+        }
+        for file in &commit.modified {
+            modified.insert(file.clone());
+        }
+    }
+    let mut all_files = std::collections::HashSet::new();
+    all_files.extend(added.iter().cloned());
+    all_files.extend(removed.iter().cloned());
+    all_files.extend(modified.iter().cloned());
+
+    let mut active_files = Vec::new();
+    let mut deleted_files = Vec::new();
+
+    let prefix_filter = env::var("GITOPS_FILE_PATH_PREFIX").ok();
+
+    for file in all_files {
+        if !should_process_file(&file, prefix_filter.as_deref()) {
+            continue;
+        }
+
+        if modified.contains(&file) {
+            // For modified files, fetch both before and after.
+            let active_content = provider
+                .get_file_content_option(&file, after_ref)?
+                .unwrap_or_else(String::new);
+            let deleted_content = provider
+                .get_file_content_option(&file, &before_ref)?
+                .unwrap_or_else(String::new);
+            active_files.push(FileChange {
+                path: file.clone(),
+                content: active_content,
+            });
+            deleted_files.push(FileChange {
+                path: file.clone(),
+                content: deleted_content,
+            });
+        } else if added.contains(&file) {
+            // For added files, only after.
+            if let Some(active_content) = provider.get_file_content_option(&file, after_ref)? {
+                active_files.push(FileChange {
+                    path: file.clone(),
+                    content: active_content,
+                });
+            }
+        } else if removed.contains(&file) {
+            // For removed files, only before.
+            if let Some(deleted_content) = provider.get_file_content_option(&file, &before_ref)? {
+                deleted_files.push(FileChange {
+                    path: file.clone(),
+                    content: deleted_content,
+                });
+            }
+        }
+    }
+    Ok(ProcessedFiles {
+        active_files,
+        deleted_files,
+    })
+}
+
+/// Whether a change to the file changes the module in its directory
+fn is_module_file(file_path: &str) -> bool {
+    let file_name = file_path.rsplit('/').next().unwrap_or(file_path);
+    file_name.ends_with(".tf") || file_name.ends_with(".hcl") || file_name == "module.yaml"
+}
+
+/// Directories a module containing the file could be placed in, its own directory first
+fn parent_directories(file_path: &str) -> Vec<String> {
+    let mut parts: Vec<&str> = file_path.split('/').collect();
+    let mut directories = vec![];
+    while parts.pop().is_some() {
+        directories.push(parts.join("/"));
+    }
+    directories
+}
+
+fn module_manifest_path(directory: &str) -> String {
+    if directory.is_empty() {
+        "module.yaml".to_string()
+    } else {
+        format!("{}/module.yaml", directory)
+    }
+}
+
+/// Modules with files added or modified by the push, as the path of their `module.yaml`
+/// and its content at the pushed commit
+pub(crate) fn get_changed_modules(
+    provider: &dyn GitProvider,
+    payload: &WebhookPayload,
+) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+    let mut manifests: HashMap<String, Option<String>> = HashMap::new();
+    let mut modules = BTreeMap::new();
+    let changed_files = payload
+        .commits
+        .iter()
+        .flat_map(|commit| commit.added.iter().chain(commit.modified.iter()))
+        .filter(|file| is_module_file(file));
+    for file in changed_files {
+        for directory in parent_directories(file) {
+            let path = module_manifest_path(&directory);
+            if !manifests.contains_key(&path) {
+                let content = provider.get_file_content_option(&path, &payload.after)?;
+                manifests.insert(path.clone(), content);
+            }
+            // The nearest module.yaml is the module the file belongs to
+            if let Some(Some(content)) = manifests.get(&path) {
+                modules.insert(path, content.clone());
+                break;
+            }
+        }
+    }
+    Ok(modules)
+}
+
+/// Claims deploying each example of the module in `region`, by example name
+pub(crate) fn module_example_claims(
+    manifest: &ModuleManifest,
+    region: &str,
+) -> Result<Vec<(String, serde_yaml::Value)>, anyhow::Error> {
+    if manifest.spec.version.is_none() {
+        return Err(anyhow::anyhow!(
+            "Module {} has no version",
+            manifest.metadata.name
+        ));
+    }
+    let examples = manifest.spec.examples.clone().unwrap_or_default();
+    Ok(examples
+        .iter()
+        .map(|example| {
+            let mut claim = env_utils::generate_module_example_deployment(&manifest.spec, example);
+            // Variables of the examples in module.yaml are snake_case, the ones of claims camelCase
+            claim["spec"]["variables"] =
+                env_utils::convert_module_example_variables_to_camel_case(&example.variables);
+            claim["spec"]["region"] = serde_yaml::Value::String(region.to_string());
+            (example.name.clone(), claim)
+        })
+        .collect())
+}
+
+/// Merges the variable file referenced by `spec.varFile` into the claim variables,
+/// reading it at `reference` relative to the claim file
+pub(crate) fn resolve_var_file(
+    provider: &dyn GitProvider,
+    claim_path: &str,
+    yaml: &mut serde_yaml::Value,
+    reference: &str,
+) -> Result<(), anyhow::Error> {
+    let var_file = match yaml["spec"]["varFile"].as_str() {
+        Some(var_file) => var_file.to_string(),
+        None => return Ok(()),
+    };
+    let path = relative_to_claim(claim_path, &var_file);
+    let contents = provider
+        .get_file_content_option(&path, reference)
+        .map_err(|e| anyhow::anyhow!("Failed to read variable file {}: {}", path, e))?
+        .ok_or_else(|| anyhow::anyhow!("Variable file {} not found at {}", path, reference))?;
+    env_utils::merge_var_file_into_claim(yaml, &path, &contents)
+}
+
+/// Repository path of `path` relative to the directory of the claim file
+fn relative_to_claim(claim_path: &str, path: &str) -> String {
+    let mut parts: Vec<&str> = claim_path.split('/').collect();
+    parts.pop();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// Approvals the project settings require before changes are applied to `environment`
+pub(crate) async fn get_approval_requirement(
+    handler: &GenericCloudHandler,
+    environment: &str,
+) -> u32 {
+    match get_required_approvals(handler, environment, "apply").await {
+        Ok(required_approvals) => required_approvals,
+        Err(e) => {
+            println!(
+                "Failed to get approval requirement for {}: {}",
+                environment, e
+            );
+            0
+        }
+    }
+}
+
+/// Markdown section for check runs and merge request notes describing the approval requirement
+pub(crate) fn approval_section(
+    environment: &str,
+    required_approvals: u32,
+    command: &str,
+) -> Option<String> {
+    if required_approvals == 0 {
+        return None;
+    }
+    let requirement = format!(
+        "Changes to `{}` require {} approval(s) before they are applied",
+        environment, required_approvals
+    );
+    let section = if command == "plan" {
+        format!(
+            "{}, the job will be pending approval once this is merged.",
+            requirement
+        )
+    } else {
+        format!("{}, the {} job is pending approval.", requirement, command)
+    };
+    Some(format!("\n\n## Approval\n\n{}", section))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_should_process_file_yaml_extensions() {
+        // Test .yaml extension with no prefix filter
+        assert_eq!(should_process_file("deployment.yaml", None), true);
+        assert_eq!(should_process_file("infra/config.yaml", None), true);
+        assert_eq!(should_process_file("path/to/file.yaml", None), true);
+
+        // Test .yml extension
+        assert_eq!(should_process_file("deployment.yml", None), true);
+        assert_eq!(should_process_file("infra/config.yml", None), true);
+        assert_eq!(should_process_file("path/to/file.yml", None), true);
+
+        // Test non-YAML files
+        assert_eq!(should_process_file("README.md", None), false);
+        assert_eq!(should_process_file("script.sh", None), false);
+        assert_eq!(should_process_file("main.rs", None), false);
+        assert_eq!(should_process_file("config.json", None), false);
+        assert_eq!(should_process_file("Dockerfile", None), false);
+        assert_eq!(should_process_file("file.txt", None), false);
+        assert_eq!(should_process_file("infra/README.md", None), false);
+    }
+
+    #[test]
+    fn test_should_process_file_with_prefix() {
+        // Test with "infra/" prefix
+        let prefix = Some("infra/");
+
+        // Should process: YAML files in infra/
+        assert_eq!(should_process_file("infra/deployment.yaml", prefix), true);
+        assert_eq!(should_process_file("infra/config.yml", prefix), true);
+        assert_eq!(
+            should_process_file("infra/nested/service.yaml", prefix),
+            true
+        );
+
+        // Should NOT process: YAML files outside infra/
+        assert_eq!(should_process_file("deployment.yaml", prefix), false);
+        assert_eq!(should_process_file("config.yml", prefix), false);
+        assert_eq!(should_process_file("other/deployment.yaml", prefix), false);
+        assert_eq!(should_process_file("claims/service.yml", prefix), false);
+
+        // Should NOT process: non-YAML files in infra/
+        assert_eq!(should_process_file("infra/README.md", prefix), false);
+        assert_eq!(should_process_file("infra/script.sh", prefix), false);
+    }
+
+    #[test]
+    fn test_should_process_file_with_different_prefixes() {
+        // Test with "claims/" prefix
+        assert_eq!(
+            should_process_file("claims/deployment.yaml", Some("claims/")),
+            true
+        );
+        assert_eq!(
+            should_process_file("infra/deployment.yaml", Some("claims/")),
+            false
+        );
+        assert_eq!(
+            should_process_file("deployment.yaml", Some("claims/")),
+            false
+        );
+
+        // Test with nested prefix
+        assert_eq!(
+            should_process_file("config/production/service.yaml", Some("config/production/")),
+            true
+        );
+        assert_eq!(
+            should_process_file("config/service.yaml", Some("config/production/")),
+            false
+        );
+        assert_eq!(
+            should_process_file("production/service.yaml", Some("config/production/")),
+            false
+        );
+
+        // Test with prefix without trailing slash
+        assert_eq!(should_process_file("stacks/app.yaml", Some("stacks")), true);
+        assert_eq!(
+            should_process_file("stacks-old/app.yaml", Some("stacks")),
+            true
+        ); // starts_with matches
+        assert_eq!(
+            should_process_file("modules/app.yaml", Some("stacks")),
+            false
+        );
+    }
+
+    #[test]
+    fn test_should_process_file_empty_prefix() {
+        // Empty prefix should process all YAML files
+        assert_eq!(should_process_file("deployment.yaml", Some("")), true);
+        assert_eq!(should_process_file("infra/deployment.yaml", Some("")), true);
+        assert_eq!(should_process_file("any/path/config.yml", Some("")), true);
+        assert_eq!(should_process_file("README.md", Some("")), false);
+
+        // Whitespace-only prefix should also process all YAML files
+        assert_eq!(should_process_file("deployment.yaml", Some("   ")), true);
+        assert_eq!(
+            should_process_file("infra/deployment.yaml", Some("   ")),
+            true
+        );
+    }
+
+    #[test]
+    fn test_should_process_file_no_prefix_env_var() {
+        // When prefix is None, should process all YAML files
+        assert_eq!(should_process_file("deployment.yaml", None), true);
+        assert_eq!(should_process_file("infra/deployment.yaml", None), true);
+        assert_eq!(should_process_file("claims/service.yml", None), true);
+        assert_eq!(should_process_file("any/path/file.yaml", None), true);
+        assert_eq!(should_process_file("README.md", None), false);
+        assert_eq!(should_process_file("script.sh", None), false);
+    }
+
+    #[test]
+    fn test_should_process_file_edge_cases() {
+        // Files with YAML-like names but wrong extension
+        assert_eq!(should_process_file("file.yaml.bak", None), false);
+        assert_eq!(should_process_file("yaml.txt", None), false);
+        assert_eq!(should_process_file("deployment.yaml.old", None), false);
+
+        // Hidden YAML files
+        assert_eq!(should_process_file(".github/workflows/ci.yaml", None), true);
+        assert_eq!(should_process_file(".config.yml", None), true);
+
+        // YAML files with no directory
+        assert_eq!(should_process_file("config.yaml", None), true);
+        assert_eq!(should_process_file("service.yml", None), true);
+
+        // Multiple extensions (only last matters)
+        assert_eq!(should_process_file("file.tar.gz", None), false);
+        assert_eq!(should_process_file("backup.yaml.gz", None), false);
+
+        // Test with prefix
+        assert_eq!(
+            should_process_file("infra/.hidden.yaml", Some("infra/")),
+            true
+        );
+        assert_eq!(should_process_file(".hidden.yaml", Some("infra/")), false);
+    }
+
+    #[test]
+    fn test_should_process_file_cross_boundary_moves() {
+        let prefix = Some("infra/");
+
+        // Scenario 1: File moved FROM outside TO inside the prefix
+        // Expected: Only the new location is processed (treated as ADD)
+        assert_eq!(should_process_file("other/deployment.yaml", prefix), false);
+        assert_eq!(should_process_file("infra/deployment.yaml", prefix), true);
+
+        // Scenario 2: File moved FROM inside TO outside the prefix
+        // Expected: Only the old location is processed (treated as DELETE)
+        assert_eq!(should_process_file("infra/deployment.yaml", prefix), true);
+        assert_eq!(should_process_file("other/deployment.yaml", prefix), false);
+
+        // Scenario 3: File moved within the prefix (normal rename)
+        // Expected: Both locations match, rename detection works normally
+        assert_eq!(should_process_file("infra/old.yaml", prefix), true);
+        assert_eq!(should_process_file("infra/new.yaml", prefix), true);
+
+        // Scenario 4: File moved outside the prefix (ignored)
+        // Expected: Neither location is processed, entire operation ignored
+        assert_eq!(should_process_file("other/old.yaml", prefix), false);
+        assert_eq!(should_process_file("other/new.yaml", prefix), false);
+    }
+
+    #[test]
+    fn test_relative_to_claim() {
+        assert_eq!(
+            relative_to_claim("claims/bucket.yaml", "bucket.tfvars"),
+            "claims/bucket.tfvars"
+        );
+        assert_eq!(
+            relative_to_claim("claims/dev/bucket.yaml", "../vars/./bucket.tfvars"),
+            "claims/vars/bucket.tfvars"
+        );
+        assert_eq!(
+            relative_to_claim("bucket.yaml", "bucket.tfvars"),
+            "bucket.tfvars"
+        );
+    }
+
+    struct FilesProvider(HashMap<&'static str, &'static str>);
+
+    impl GitProvider for FilesProvider {
+        fn environment_prefix(&self) -> &'static str {
+            "test"
+        }
+
+        fn get_default_branch(&self) -> Result<String, Box<dyn Error>> {
+            Ok("main".to_string())
+        }
+
+        fn get_default_branch_sha(&self) -> Result<String, Box<dyn Error>> {
+            Ok("main-sha".to_string())
+        }
+
+        fn get_file_content_option(
+            &self,
+            path: &str,
+            _reference: &str,
+        ) -> Result<Option<String>, Box<dyn Error>> {
+            Ok(self.0.get(path).map(|content| content.to_string()))
+        }
+
+        fn file_url(&self, branch: &str, path: &str) -> String {
+            format!("{}/{}", branch, path)
+        }
+    }
+
+    const MODULE_YAML: &str = r#"
+apiVersion: infraweave.io/v1
+kind: Module
+metadata:
+  name: s3bucket
+spec:
+  moduleName: S3Bucket
+  version: 0.1.2-dev
+  description: S3 bucket
+  reference: https://github.com/org/repo
+  examples:
+    - name: simple-bucket
+      description: A bucket with tags
+      variables:
+        bucket_name: my-bucket
+        tags:
+          Team: platform
+"#;
+
+    #[test]
+    fn test_parent_directories() {
+        assert_eq!(
+            parent_directories("modules/s3bucket/main.tf"),
+            vec!["modules/s3bucket", "modules", ""]
+        );
+        assert_eq!(parent_directories("main.tf"), vec![""]);
+    }
+
+    #[test]
+    fn test_get_before_ref() {
+        let provider = FilesProvider(HashMap::new());
+        let mut payload = WebhookPayload {
+            _ref: "refs/heads/main".to_string(),
+            before: "before".to_string(),
+            after: "after".to_string(),
+            commits: vec![],
+            base_ref: None,
+        };
+        assert_eq!(get_before_ref(&provider, &payload).unwrap(), "before");
+        payload._ref = "refs/heads/feature".to_string();
+        assert_eq!(get_before_ref(&provider, &payload).unwrap(), "main-sha");
+        // A merge request into another branch than the default one is compared against its base
+        payload.base_ref = Some("base-sha".to_string());
+        assert_eq!(get_before_ref(&provider, &payload).unwrap(), "base-sha");
+    }
+
+    #[test]
+    fn test_get_changed_modules() {
+        let provider = FilesProvider(HashMap::from([
+            ("modules/s3bucket/module.yaml", MODULE_YAML),
+            ("modules/vpc/module.yaml", MODULE_YAML),
+        ]));
+        let payload = WebhookPayload {
+            _ref: "refs/heads/feature".to_string(),
+            before: "before".to_string(),
+            after: "after".to_string(),
+            commits: vec![Commit {
+                added: vec!["modules/s3bucket/nested/outputs.tf".to_string()],
+                removed: vec!["modules/vpc/main.tf".to_string()],
+                modified: vec![
+                    "modules/s3bucket/main.tf".to_string(),
+                    "modules/s3bucket/README.md".to_string(),
+                    "claims/bucket.yaml".to_string(),
+                ],
+            }],
+            base_ref: None,
+        };
+        let modules = get_changed_modules(&provider, &payload).unwrap();
+        assert_eq!(
+            modules.keys().collect::<Vec<_>>(),
+            vec!["modules/s3bucket/module.yaml"]
+        );
+    }
+
+    #[test]
+    fn test_module_example_claims() {
+        let manifest: ModuleManifest = serde_yaml::from_str(MODULE_YAML).unwrap();
+        let claims = module_example_claims(&manifest, "eu-west-1").unwrap();
+        assert_eq!(claims.len(), 1);
+        let (name, claim) = &claims[0];
+        assert_eq!(name, "simple-bucket");
+        assert_eq!(claim["kind"].as_str(), Some("S3Bucket"));
+        assert_eq!(claim["metadata"]["name"].as_str(), Some("simple-bucket"));
+        assert_eq!(claim["spec"]["moduleVersion"].as_str(), Some("0.1.2-dev"));
+        assert_eq!(claim["spec"]["region"].as_str(), Some("eu-west-1"));
+        assert_eq!(
+            claim["spec"]["variables"]["bucketName"].as_str(),
+            Some("my-bucket")
+        );
+        assert_eq!(
+            claim["spec"]["variables"]["tags"]["Team"].as_str(),
+            Some("platform")
+        );
+
+        let mut unversioned = manifest.clone();
+        unversioned.spec.version = None;
+        assert!(module_example_claims(&unversioned, "eu-west-1").is_err());
+    }
+
+    #[test]
+    fn test_approval_section() {
+        assert_eq!(approval_section("github-org-repo/dev", 0, "apply"), None);
+        assert_eq!(
+            approval_section("github-org-repo/prod", 2, "plan").unwrap(),
+            "\n\n## Approval\n\nChanges to `github-org-repo/prod` require 2 approval(s) before they are applied, the job will be pending approval once this is merged."
+        );
+        assert_eq!(
+            approval_section("github-org-repo/prod", 1, "destroy").unwrap(),
+            "\n\n## Approval\n\nChanges to `github-org-repo/prod` require 1 approval(s) before they are applied, the destroy job is pending approval."
+        );
+    }
+}
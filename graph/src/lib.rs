@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Deserialize, Debug)]
 pub struct Plan {
@@ -43,11 +43,18 @@ pub struct StateResource {
     #[serde(rename = "type")]
     pub resource_type: String,
     pub values: Option<serde_json::Value>,
+    pub provider_name: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct Configuration {
     pub root_module: ModuleConfig,
+    pub provider_config: Option<HashMap<String, ProviderConfig>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ProviderConfig {
+    pub name: String,
 }
 
 #[derive(Deserialize, Debug)]
@@ -55,21 +62,28 @@ pub struct ModuleConfig {
     pub resources: Option<Vec<ResourceConfig>>,
     pub module_calls: Option<HashMap<String, ModuleCall>>,
     pub outputs: Option<HashMap<String, OutputConfig>>,
+    pub variables: Option<HashMap<String, serde_json::Value>>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct OutputConfig {
     pub expression: Option<serde_json::Value>,
+    #[serde(default)]
+    pub sensitive: bool,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct ModuleCall {
+    pub source: Option<String>,
+    pub expressions: Option<HashMap<String, serde_json::Value>>,
     pub module: Option<ModuleConfig>,
+    pub depends_on: Option<Vec<String>>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct ResourceConfig {
     pub address: String,
+    pub provider_config_key: Option<String>,
     pub expressions: Option<HashMap<String, serde_json::Value>>,
     pub count_expression: Option<serde_json::Value>,
     pub for_each_expression: Option<serde_json::Value>,
@@ -81,6 +95,7 @@ pub struct ResourceChange {
     #[serde(rename = "type")]
     pub resource_type: String,
     pub mode: Option<String>,
+    pub provider_name: Option<String>,
     pub change: Change,
 }
 
@@ -107,6 +122,9 @@ pub struct OutputNodeData {
     pub hcl: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub values: Option<serde_json::Value>,
+    /// Local name of the provider of a resource or data source, such as `aws` or `kubernetes`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -157,10 +175,106 @@ pub struct OutputEdge {
     pub attributes: Option<Vec<String>>,
 }
 
+/// Graph returned by `process_graph` and `process_configuration`, in a stable order: groups come
+/// first sorted by id, so a parent group always precedes the groups nested in it, then the other
+/// nodes sorted by id. Edges are sorted by source and then target, and their ids come from `edge_id`
 #[derive(Serialize, Debug)]
 pub struct OutputGraph {
     pub nodes: Vec<OutputNode>,
     pub edges: Vec<OutputEdge>,
+    pub summary: GraphSummary,
+}
+
+/// Resources and data sources of an `OutputGraph`, counted by instance, so `count` and
+/// `for_each` resources add one per instance
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphSummary {
+    pub total: usize,
+    /// Keyed by the action of the node, such as `create` or `create, delete`. Resources without
+    /// action, as when rendering a state, count as `no-op`
+    pub by_action: BTreeMap<String, usize>,
+    /// Keyed by the local name of the provider, resources without known provider are left out
+    pub by_provider: BTreeMap<String, usize>,
+    /// Keyed by the module the resources are declared in, `root` for the root module
+    pub by_module: BTreeMap<String, usize>,
+}
+
+/// Noise filters applied by `process_graph`, all enabled by default
+#[derive(Debug, Clone)]
+pub struct GraphOptions {
+    /// Drop resources and data sources in the DOT graph that have no entry in the plan or state
+    pub drop_unplanned_resources: bool,
+    /// Resolve module outputs away, connecting their dependents directly to their sources
+    pub hide_module_outputs: bool,
+    /// Hide locals that don't depend on anything else in the graph
+    pub hide_constant_locals: bool,
+}
+
+impl Default for GraphOptions {
+    fn default() -> Self {
+        GraphOptions {
+            drop_unplanned_resources: true,
+            hide_module_outputs: true,
+            hide_constant_locals: true,
+        }
+    }
+}
+
+/// Nodes kept by `filter_graph`. An empty include list keeps every node, and a node matching
+/// any exclude list is dropped. Groups aren't matched themselves, they are kept while they
+/// contain a kept node
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GraphFilter {
+    /// Module paths such as `module.vpc`, matching the nodes declared in the module, its
+    /// instances and its nested modules
+    pub include_modules: Vec<String>,
+    pub exclude_modules: Vec<String>,
+    /// Node types such as `resource`, `data`, `var` or `output`
+    pub include_types: Vec<String>,
+    pub exclude_types: Vec<String>,
+    /// Actions such as `create` or `delete`. A node planned with several actions, as a replaced
+    /// resource with `create, delete`, matches each of them. Nodes without action are `no-op`
+    pub include_actions: Vec<String>,
+    pub exclude_actions: Vec<String>,
+}
+
+impl GraphFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == GraphFilter::default()
+    }
+
+    fn matches(&self, id: &str, data: &OutputNodeData) -> bool {
+        let in_module = |module: &String| {
+            id.strip_prefix(module.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+        };
+        let actions: Vec<&str> = match &data.action {
+            Some(action) => action.split(", ").collect(),
+            None => vec!["no-op"],
+        };
+        let has_action = |list: &[String]| actions.iter().any(|a| list.iter().any(|l| l == a));
+
+        (self.include_modules.is_empty() || self.include_modules.iter().any(in_module))
+            && !self.exclude_modules.iter().any(in_module)
+            && (self.include_types.is_empty() || self.include_types.contains(&data.node_type))
+            && !self.exclude_types.contains(&data.node_type)
+            && (self.include_actions.is_empty() || has_action(&self.include_actions))
+            && !has_action(&self.exclude_actions)
+    }
+}
+
+/// Id of the edge from `source` to `target`, the same for the same pair on every run and build.
+/// Formatted as `e_` followed by the 64-bit FNV-1a hash of both ids in hex
+pub fn edge_id(source: &str, target: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    // The separator can't occur in node ids, so ("a.b", "c") and ("a", "b.c") hash differently
+    for byte in source.bytes().chain([0]).chain(target.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("e_{:016x}", hash)
 }
 
 fn determine_block_type(address: &str, is_data: bool) -> String {
@@ -258,9 +372,10 @@ fn extract_parent_modules(
                     label: module_id.clone(),
                     node_type: "module".to_string(),
                     action: None,
-                    count: None, // Group has no count
+                    count: None, // Set to the number of nested resources by `OutputGraph::new`
                     values: None,
                     hcl: None,
+                    provider: None,
                 },
                 position: OutputNodePosition { x: 0, y: 0 },
                 style: OutputNodeStyle {
@@ -355,11 +470,14 @@ fn create_node(
     resource_map: &HashMap<String, Vec<&ResourceChange>>,
     output_map: &HashMap<String, Change>,
     state_values_map: &HashMap<String, serde_json::Value>,
+    output_values_map: &HashMap<String, serde_json::Value>,
     known_modules: &mut HashSet<String>,
     nodes: &mut Vec<OutputNode>,
     include_values: bool,
     hcl: Option<String>,
     active_plan_addresses: &HashSet<String>,
+    provider_map: &HashMap<String, String>,
+    options: &GraphOptions,
 ) -> Option<OutputNode> {
     // Filter out noise nodes
     if address == "root" || address.starts_with("provider[") || address.starts_with("meta.") {
@@ -480,10 +598,14 @@ fn create_node(
                     };
                     (Some(action_str), false, None, values_val)
                 } else {
-                    // Output not found in change map. Outputs are handled separately at the top level
-                    // of StateValues and are not processed during per-resource recursion.
-                    // Treat as no-op if not present in the output change map.
-                    (Some("no-op".to_string()), false, None, None)
+                    // Output not found in change map, e.g. when rendering a state.
+                    // Treat as no-op and take its value from the outputs of the state, if any.
+                    let values_val = if include_values {
+                        output_values_map.get(&address).cloned()
+                    } else {
+                        None
+                    };
+                    (Some("no-op".to_string()), false, None, values_val)
                 }
             } else {
                 // If NOT in resource_map and NOT an output...
@@ -492,12 +614,25 @@ fn create_node(
                     // Check if it really exists in the plan state/values, even if no change
                     if active_plan_addresses.contains(&address) {
                         (Some("read".to_string()), true, None, None)
+                    } else if !options.drop_unplanned_resources {
+                        (None, true, None, None)
                     } else {
                         // It's a ghost data source from the graph that wasn't evaluated/read.
                         return None;
                     }
-                } else if temp_type == "var" || temp_type == "local" || temp_type == "output" {
+                } else if temp_type == "output" {
+                    // Module output, with the value resolved from the state when possible
+                    let values_val = if include_values {
+                        output_values_map.get(&address).cloned()
+                    } else {
+                        None
+                    };
+                    (Some("n/a".to_string()), false, None, values_val)
+                } else if temp_type == "var" || temp_type == "local" {
                     (Some("n/a".to_string()), false, None, None)
+                } else if !options.drop_unplanned_resources {
+                    // Ghost resource kept on request, e.g. for destroy previews
+                    (None, false, None, None)
                 } else {
                     // Strictly filter out resources/data that have no plan entry.
                     return None;
@@ -537,6 +672,7 @@ fn create_node(
             count,
             values,
             hcl,
+            provider: provider_map.get(&address).cloned(),
         },
         position: OutputNodePosition { x: 0, y: 0 },
     })
@@ -634,6 +770,126 @@ fn strip_attribute_path(reference: &str) -> String {
     }
 }
 
+fn mask_sensitive(value: &serde_json::Value, sensitive: bool) -> serde_json::Value {
+    if sensitive {
+        serde_json::Value::String("(sensitive)".to_string())
+    } else {
+        value.clone()
+    }
+}
+
+// Resolve the values of module outputs (e.g. "module.vpc.output.vpc_id") that are a constant or
+// refer to a single attribute of a resource or of a nested module output.
+// Nested modules are resolved first so that their outputs can be referred to by their parents.
+fn collect_module_output_values(
+    module: &ModuleConfig,
+    module_path: &str,
+    state_values_map: &HashMap<String, serde_json::Value>,
+    output_values: &mut HashMap<String, serde_json::Value>,
+) {
+    if let Some(calls) = &module.module_calls {
+        for (name, call) in calls {
+            if let Some(submodule) = &call.module {
+                let sub_path = if module_path.is_empty() {
+                    format!("module.{}", name)
+                } else {
+                    format!("{}.module.{}", module_path, name)
+                };
+                collect_module_output_values(submodule, &sub_path, state_values_map, output_values);
+            }
+        }
+    }
+
+    // Root outputs are read from the state itself
+    if module_path.is_empty() {
+        return;
+    }
+
+    if let Some(outputs) = &module.outputs {
+        for (name, output_config) in outputs {
+            let value = output_config.expression.as_ref().and_then(|expr| {
+                resolve_output_expression(expr, module_path, state_values_map, output_values)
+            });
+            if let Some(value) = value {
+                output_values.insert(
+                    format!("{}.output.{}", module_path, name),
+                    mask_sensitive(&value, output_config.sensitive),
+                );
+            }
+        }
+    }
+}
+
+fn resolve_output_expression(
+    expr: &serde_json::Value,
+    module_path: &str,
+    state_values_map: &HashMap<String, serde_json::Value>,
+    output_values: &HashMap<String, serde_json::Value>,
+) -> Option<serde_json::Value> {
+    if let Some(constant) = expr.get("constant_value") {
+        return Some(constant.clone());
+    }
+
+    let mut references = Vec::new();
+    extract_references(expr, &mut references);
+
+    // Terraform lists every prefix of a reference as well, e.g.
+    // ["aws_instance.web[0].id", "aws_instance.web[0]", "aws_instance.web"], keep the longest
+    let filtered_refs: Vec<&String> = references
+        .iter()
+        .filter(|ref_a| {
+            !references.iter().any(|ref_b| {
+                ref_b != *ref_a
+                    && (ref_b.starts_with(&format!("{}.", ref_a))
+                        || ref_b.starts_with(&format!("{}[", ref_a)))
+            })
+        })
+        .collect();
+    let [reference] = filtered_refs.as_slice() else {
+        return None;
+    };
+
+    let parts: Vec<&str> = reference.split('.').collect();
+    let (value, attribute_path) = match parts[0] {
+        "module" if parts.len() >= 3 => {
+            let address = format!("{}.module.{}.output.{}", module_path, parts[1], parts[2]);
+            (output_values.get(&address)?, &parts[3..])
+        }
+        "var" | "local" | "path" | "count" | "each" | "self" | "terraform" => return None,
+        "data" if parts.len() >= 3 => {
+            let address = format!("{}.{}", module_path, parts[..3].join("."));
+            (state_values_map.get(&address)?, &parts[3..])
+        }
+        _ if parts.len() >= 2 => {
+            let address = format!("{}.{}", module_path, parts[..2].join("."));
+            (state_values_map.get(&address)?, &parts[2..])
+        }
+        _ => return None,
+    };
+
+    lookup_attribute(value, attribute_path)
+}
+
+// Follow an attribute path such as ["ingress[0]", "cidr_blocks"] into a value
+fn lookup_attribute(value: &serde_json::Value, path: &[&str]) -> Option<serde_json::Value> {
+    let mut current = value;
+    for segment in path {
+        let mut pieces = segment.split('[');
+        let key = pieces.next().unwrap_or_default();
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        for index in pieces {
+            let index = index.trim_end_matches(']').trim_matches('"');
+            current = match index.parse::<usize>() {
+                Ok(i) => current.get(i)?,
+                Err(_) => current.get(index)?,
+            };
+        }
+    }
+    Some(current.clone())
+}
+
 fn traverse_configuration(
     module: &ModuleConfig,
     parent_path: &str,
@@ -775,6 +1031,7 @@ pub fn process_graph(
     dot_content: &str,
     include_values: bool,
     source_dir: Option<std::path::PathBuf>,
+    options: &GraphOptions,
 ) -> Result<OutputGraph> {
     // 1. Parse Plan File
     let plan: Plan = serde_json::from_str(plan_json).context("Failed to parse plan file")?;
@@ -787,12 +1044,36 @@ pub fn process_graph(
     // Collect all known active addresses from Plan (changes + state)
     let mut active_plan_addresses: HashSet<String> = HashSet::new();
     let mut state_values_map: HashMap<String, serde_json::Value> = HashMap::new();
+    let mut output_values_map: HashMap<String, serde_json::Value> = HashMap::new();
 
     // Check if we are in "State" mode (direct values with no changes)
     if let Some(values) = &plan.values {
         // Collect everything from root module
         collect_state_addresses(&values.root_module, &mut active_plan_addresses);
         collect_state_values(&values.root_module, &mut state_values_map);
+
+        if include_values {
+            // The state only holds the root outputs, module outputs are resolved from
+            // the configuration against the values of the resources in the state
+            if let Some(config) = &plan.configuration {
+                collect_module_output_values(
+                    &config.root_module,
+                    "",
+                    &state_values_map,
+                    &mut output_values_map,
+                );
+            }
+            if let Some(outputs) = &values.outputs {
+                for (name, output) in outputs {
+                    if let Some(value) = &output.value {
+                        output_values_map.insert(
+                            format!("output.{}", name),
+                            mask_sensitive(value, output.sensitive),
+                        );
+                    }
+                }
+            }
+        }
     }
 
     if let Some(changes) = &plan.resource_changes {
@@ -824,6 +1105,29 @@ pub fn process_graph(
         }
     }
 
+    // Provider of each resource, from the configuration when it has the resource and otherwise
+    // from the provider recorded in the changes or the state
+    let mut provider_map: HashMap<String, String> = HashMap::new();
+    if let Some(values) = &plan.values {
+        collect_state_providers(&values.root_module, &index_strip_regex, &mut provider_map);
+    }
+    if let Some(changes) = &plan.resource_changes {
+        for change in changes {
+            if let Some(provider_name) = &change.provider_name {
+                let base = index_strip_regex.replace(&change.address, "").to_string();
+                provider_map.insert(base, provider_local_name(provider_name));
+            }
+        }
+    }
+    if let Some(config) = &plan.configuration {
+        collect_configuration_providers(
+            &config.root_module,
+            "",
+            config.provider_config.as_ref(),
+            &mut provider_map,
+        );
+    }
+
     // 2. Parse DOT File
     let node_regex = Regex::new(r#"^[\t\s]*"(.+?)"\s*\[label\s*=\s*"(.+?)""#)
         .context("Failed to compile node regex")?;
@@ -865,11 +1169,14 @@ pub fn process_graph(
             &resource_map,
             &output_map,
             &state_values_map,
+            &output_values_map,
             &mut known_modules,
             &mut final_nodes,
             include_values,
             hcl,
             &active_plan_addresses,
+            &provider_map,
+            options,
         ) {
             dot_node_to_address.insert(dot_id, address);
             final_nodes.push(node);
@@ -896,11 +1203,14 @@ pub fn process_graph(
                 &resource_map,
                 &output_map,
                 &state_values_map,
+                &output_values_map,
                 &mut known_modules,
                 &mut final_nodes,
                 include_values,
                 hcl,
                 &active_plan_addresses,
+                &provider_map,
+                options,
             ) {
                 dot_node_to_address.insert(dot_id.clone(), address);
                 final_nodes.push(node);
@@ -1018,6 +1328,7 @@ pub fn process_graph(
                 count: None,
                 values: None,
                 hcl: None,
+                provider: None,
             },
             position: OutputNodePosition { x: 0, y: 0 },
             style: OutputNodeStyle {
@@ -1047,6 +1358,7 @@ pub fn process_graph(
                 count: None,
                 values: None,
                 hcl: None,
+                provider: None,
             },
             position: OutputNodePosition { x: 0, y: 0 },
             style: OutputNodeStyle {
@@ -1058,8 +1370,6 @@ pub fn process_graph(
         });
     }
 
-    let mut edge_counter = 0;
-
     // Simplification Phase
     // 1. Build Adjacency List (Dependent -> Dependencies) to traverse 'up'
     // DOT raw_edges are typically: Dependent (Source) -> Dependency (Target).
@@ -1095,6 +1405,7 @@ pub fn process_graph(
         types: &HashMap<String, String>,
         cache: &mut HashMap<String, Vec<String>>,
         stack: &mut HashSet<String>,
+        options: &GraphOptions,
     ) -> Vec<String> {
         // Break cycles
         if stack.contains(node) {
@@ -1109,6 +1420,10 @@ pub fn process_graph(
             .cloned()
             .unwrap_or_else(|| determine_block_type(node, false));
         let n_type = n_type_string.as_str();
+        let is_module_output = n_type == "output" && !node.starts_with("output.");
+        if is_module_output && !options.hide_module_outputs {
+            return vec![node.to_string()];
+        }
 
         // We simplify 'var', 'local', and 'output' nodes that act as pass-throughs
         if n_type == "var" || n_type == "local" || n_type == "output" {
@@ -1130,6 +1445,8 @@ pub fn process_graph(
                             // Module outputs: should have been resolved away, return empty
                             return vec![];
                         }
+                    } else if !options.hide_constant_locals {
+                        return vec![node.to_string()];
                     } else {
                         // locals that resolve to nothing are hidden (constants)
                         return vec![];
@@ -1141,7 +1458,7 @@ pub fn process_graph(
                 stack.insert(node.to_string());
                 let mut resolved = Vec::new();
                 for d in my_deps {
-                    resolved.extend(resolve_dependencies(d, deps, types, cache, stack));
+                    resolved.extend(resolve_dependencies(d, deps, types, cache, stack, options));
                 }
                 stack.remove(node);
 
@@ -1168,6 +1485,8 @@ pub fn process_graph(
                         } else {
                             return vec![];
                         }
+                    } else if n_type == "local" && !options.hide_constant_locals {
+                        return vec![node.to_string()];
                     } else {
                         return vec![];
                     }
@@ -1192,6 +1511,8 @@ pub fn process_graph(
                     } else {
                         return vec![];
                     }
+                } else if n_type == "local" && !options.hide_constant_locals {
+                    return vec![node.to_string()];
                 } else {
                     return vec![];
                 }
@@ -1297,9 +1618,11 @@ pub fn process_graph(
         // We do NOT want to simplify root outputs (e.g. "output.foo"), because they are 'sinks'
         // that we want to visualize. Module outputs are intermediates.
         let is_root_output = node.starts_with("output.");
+        let keep_module_output = n_type == "output" && !options.hide_module_outputs;
 
-        let is_simplifiable =
-            (n_type == "var" || n_type == "local" || n_type == "output") && !is_root_output;
+        let is_simplifiable = (n_type == "var" || n_type == "local" || n_type == "output")
+            && !is_root_output
+            && !keep_module_output;
         let has_deps = deps.contains_key(node) && !deps[node].is_empty();
 
         if is_simplifiable {
@@ -1308,7 +1631,7 @@ pub fn process_graph(
             }
             // If it is a constant local (no deps), we hide it.
             // Vars and constant Outputs are kept as sources, BUT only if Vars are root variables.
-            if n_type == "local" {
+            if n_type == "local" && options.hide_constant_locals {
                 continue;
             }
             if n_type == "var" && !node.starts_with("var.") {
@@ -1323,7 +1646,7 @@ pub fn process_graph(
         if let Some(my_deps) = deps.get(node) {
             for dep in my_deps {
                 let resolved_sources =
-                    resolve_dependencies(dep, &deps, &node_types, &mut cache, &mut stack);
+                    resolve_dependencies(dep, &deps, &node_types, &mut cache, &mut stack, options);
                 for source in resolved_sources {
                     if source != *node {
                         simplified_edges.push((node.clone(), source.clone(), dep.clone())); // Dependent, Dependency, Via
@@ -1407,7 +1730,6 @@ pub fn process_graph(
     }
 
     for ((source, target), attributes_set) in current_edges {
-        edge_counter += 1;
         let mut attributes: Vec<String> = attributes_set.into_iter().collect();
         attributes.sort();
 
@@ -1418,7 +1740,7 @@ pub fn process_graph(
         };
 
         final_edges.push(OutputEdge {
-            id: format!("e_{}", edge_counter),
+            id: edge_id(&source, &target),
             source,
             target,
             attributes: attributes_opt,
@@ -1432,8 +1754,11 @@ pub fn process_graph(
             OutputNode::Resource {
                 ref id, ref data, ..
             } => {
-                // Always hide module outputs (non-root outputs) - they should be simplified away
-                if data.node_type == "output" && !id.starts_with("output.") {
+                // Hide module outputs (non-root outputs) - they should be simplified away
+                if options.hide_module_outputs
+                    && data.node_type == "output"
+                    && !id.starts_with("output.")
+                {
                     continue;
                 }
 
@@ -1514,132 +1839,815 @@ pub fn process_graph(
 
     let output_nodes: Vec<OutputNode> = unique_nodes.into_values().collect();
 
-    Ok(OutputGraph {
-        nodes: output_nodes,
-        edges: final_edges,
-    })
+    Ok(OutputGraph::new(output_nodes, final_edges))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde_json::json;
+/// Counts the resources of the graph and sets the number of resources nested in each group,
+/// directly or in nested groups, as the `count` of the group
+fn summarize(nodes: &mut [OutputNode]) -> GraphSummary {
+    let mut summary = GraphSummary::default();
+    let parents: HashMap<String, Option<String>> = nodes
+        .iter()
+        .map(|node| match node {
+            OutputNode::Group { id, parent_id, .. }
+            | OutputNode::Resource { id, parent_id, .. } => (id.clone(), parent_id.clone()),
+        })
+        .collect();
+    let mut group_counts: HashMap<String, usize> = HashMap::new();
+
+    for node in nodes.iter() {
+        let OutputNode::Resource {
+            parent_id, data, ..
+        } = node
+        else {
+            continue;
+        };
+        if data.node_type != "resource" && data.node_type != "data" {
+            continue;
+        }
+        let instances = data.count.unwrap_or(1);
+        summary.total += instances;
+        let action = data.action.clone().unwrap_or_else(|| "no-op".to_string());
+        *summary.by_action.entry(action).or_default() += instances;
+        if let Some(provider) = &data.provider {
+            *summary.by_provider.entry(provider.clone()).or_default() += instances;
+        }
+        let module = parent_id.clone().unwrap_or_else(|| "root".to_string());
+        *summary.by_module.entry(module).or_default() += instances;
 
-    #[test]
-    fn test_multiple_indices() {
-        let plan_json = r#"{
-            "resource_changes": [
-                {
-                    "address": "module.vpc.aws_subnet.public[0]",
-                    "type": "aws_subnet",
-                    "change": { "actions": ["create"] }
-                },
-                {
-                    "address": "module.vpc.aws_subnet.public[1]",
-                    "type": "aws_subnet",
-                    "change": { "actions": ["delete"] }
-                }
-            ]
-        }"#;
+        let mut group = parent_id.clone();
+        while let Some(id) = group {
+            *group_counts.entry(id.clone()).or_default() += instances;
+            group = parents.get(&id).cloned().flatten();
+        }
+    }
 
-        // Graph dot has the base name
-        let dot_content = r#"
-            digraph {
-                "[root] module.vpc.aws_subnet.public" [label = "module.vpc.aws_subnet.public"]
-            }
-        "#;
+    for node in nodes.iter_mut() {
+        if let OutputNode::Group { id, data, .. } = node {
+            data.count = group_counts.get(id.as_str()).copied();
+        }
+    }
+    summary
+}
 
-        let graph = process_graph(plan_json, dot_content, false, None).unwrap();
-        let node = graph
-            .nodes
-            .iter()
-            .find(|n| match n {
-                OutputNode::Resource { id, .. } => id == "module.vpc.aws_subnet.public",
-                _ => false,
-            })
-            .expect("Node should exist");
+/// Keeps the nodes of the graph matching the filter, along with the groups still containing any of
+/// them. Dependencies through dropped nodes are connected directly, so an edge runs from each kept
+/// node to every kept node that depended on it through dropped ones, with the attributes of the
+/// edge into the dependent
+pub fn filter_graph(graph: OutputGraph, filter: &GraphFilter) -> OutputGraph {
+    if filter.is_empty() {
+        return graph;
+    }
 
-        match node {
-            OutputNode::Resource { data, .. } => {
-                // Actions should be aggregated and sorted
-                assert_eq!(data.action, Some("create, delete".to_string()));
+    let parents: HashMap<String, Option<String>> = graph
+        .nodes
+        .iter()
+        .map(|node| match node {
+            OutputNode::Group { id, parent_id, .. }
+            | OutputNode::Resource { id, parent_id, .. } => (id.clone(), parent_id.clone()),
+        })
+        .collect();
+    let mut kept: HashSet<String> = HashSet::new();
+    for node in &graph.nodes {
+        let OutputNode::Resource {
+            id,
+            parent_id,
+            data,
+            ..
+        } = node
+        else {
+            continue;
+        };
+        if !filter.matches(id, data) {
+            continue;
+        }
+        kept.insert(id.clone());
+        let mut group = parent_id.clone();
+        while let Some(id) = group {
+            if !kept.insert(id.clone()) {
+                break;
             }
-            _ => panic!("Wrong node type"),
+            group = parents.get(&id).cloned().flatten();
         }
     }
 
-    #[test]
-    fn test_action_resolution() {
-        let plan_json = r#"{
-            "resource_changes": [
-                {
-                    "address": "data.aws_ami.ubuntu",
-                    "type": "aws_ami",
-                    "mode": "data",
-                    "change": { "actions": ["read"] }
-                },
-                {
-                    "address": "aws_instance.foo",
-                    "type": "aws_instance",
-                    "change": { "actions": ["no-op"] }
-                },
-                {
-                    "address": "aws_instance.bar",
-                    "type": "aws_instance",
-                    "change": { "actions": ["update"] }
+    let mut incoming: HashMap<&str, Vec<&OutputEdge>> = HashMap::new();
+    for edge in &graph.edges {
+        incoming.entry(edge.target.as_str()).or_default().push(edge);
+    }
+    let mut bridged: BTreeMap<(String, String), HashSet<String>> = BTreeMap::new();
+    for target in &kept {
+        for edge in incoming.get(target.as_str()).into_iter().flatten() {
+            let mut sources = HashSet::new();
+            let mut visited = HashSet::new();
+            let mut pending = vec![edge.source.as_str()];
+            while let Some(source) = pending.pop() {
+                if !visited.insert(source) {
+                    continue;
                 }
-            ]
-        }"#;
+                if kept.contains(source) {
+                    sources.insert(source);
+                } else {
+                    pending.extend(
+                        incoming
+                            .get(source)
+                            .into_iter()
+                            .flatten()
+                            .map(|e| e.source.as_str()),
+                    );
+                }
+            }
+            for source in sources.into_iter().filter(|s| *s != target.as_str()) {
+                bridged
+                    .entry((source.to_string(), target.clone()))
+                    .or_default()
+                    .extend(edge.attributes.iter().flatten().cloned());
+            }
+        }
+    }
 
-        let dot_content = r#"
-            digraph {
-                "[root] data.aws_ami.ubuntu" [label = "data.aws_ami.ubuntu"]
-                "[root] aws_instance.foo" [label = "aws_instance.foo"]
-                "[root] aws_instance.bar" [label = "aws_instance.bar"]
-                "[root] aws_instance.baz" [label = "aws_instance.baz"]
-                "[root] aws_instance.bar" -> "[root] data.aws_ami.ubuntu"
+    let edges = bridged
+        .into_iter()
+        .map(|((source, target), attributes)| {
+            let mut attributes: Vec<String> = attributes.into_iter().collect();
+            attributes.sort();
+            OutputEdge {
+                id: edge_id(&source, &target),
+                source,
+                target,
+                attributes: (!attributes.is_empty()).then_some(attributes),
             }
-        "#;
+        })
+        .collect();
+    let nodes = graph
+        .nodes
+        .into_iter()
+        .filter(|node| match node {
+            OutputNode::Group { id, .. } | OutputNode::Resource { id, .. } => kept.contains(id),
+        })
+        .collect();
+    OutputGraph::new(nodes, edges)
+}
 
-        let graph = process_graph(plan_json, dot_content, false, None).unwrap();
+/// Node of an `OutputGraph` as seen by the renderers: its id, data and parent group
+type TreeNode<'a> = (&'a String, &'a OutputNodeData, Option<&'a String>);
+
+impl OutputGraph {
+    /// Builds a graph from nodes and edges, sorted into the stable order of the graph
+    pub fn new(mut nodes: Vec<OutputNode>, mut edges: Vec<OutputEdge>) -> Self {
+        nodes.sort_by(|a, b| match (a, b) {
+            (OutputNode::Group { id: a, .. }, OutputNode::Group { id: b, .. })
+            | (OutputNode::Resource { id: a, .. }, OutputNode::Resource { id: b, .. }) => a.cmp(b),
+            (OutputNode::Group { .. }, OutputNode::Resource { .. }) => std::cmp::Ordering::Less,
+            (OutputNode::Resource { .. }, OutputNode::Group { .. }) => std::cmp::Ordering::Greater,
+        });
+        edges.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+        let summary = summarize(&mut nodes);
+        OutputGraph {
+            nodes,
+            edges,
+            summary,
+        }
+    }
 
-        // 1. Data Source
-        let data_node = graph
+    /// Nodes sorted by id, the indexes of the nodes nested in each group and the top-level nodes.
+    /// A node whose parent isn't in the graph is rendered at the top level
+    fn node_tree(&self) -> (Vec<TreeNode<'_>>, HashMap<&str, Vec<usize>>, Vec<usize>) {
+        let mut node_list: Vec<TreeNode<'_>> = self
             .nodes
             .iter()
-            .find(|n| match n {
-                OutputNode::Resource { id, .. } => id == "data.aws_ami.ubuntu",
-                _ => false,
+            .map(|node| match node {
+                OutputNode::Group {
+                    id,
+                    data,
+                    parent_id,
+                    ..
+                }
+                | OutputNode::Resource {
+                    id,
+                    data,
+                    parent_id,
+                    ..
+                } => (id, data, parent_id.as_ref()),
             })
-            .unwrap();
-        match data_node {
-            OutputNode::Resource { data, .. } => {
-                assert_eq!(data.node_type, "data");
-                assert_eq!(data.action, Some("read".to_string()));
+            .collect();
+        node_list.sort_by(|a, b| a.0.cmp(b.0));
+
+        let ids: HashSet<&str> = node_list.iter().map(|(id, _, _)| id.as_str()).collect();
+        let mut children: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut roots = vec![];
+        for (idx, (_, _, parent_id)) in node_list.iter().enumerate() {
+            match parent_id.filter(|parent| ids.contains(parent.as_str())) {
+                Some(parent) => children.entry(parent.as_str()).or_default().push(idx),
+                None => roots.push(idx),
             }
-            _ => panic!(),
-        };
+        }
+        (node_list, children, roots)
+    }
 
-        // 2. Explicit No-Op
-        let foo_node = graph
-            .nodes
-            .iter()
-            .find(|n| match n {
-                OutputNode::Resource { id, .. } => id == "aws_instance.foo",
-                _ => false,
-            })
-            .unwrap();
-        match foo_node {
-            OutputNode::Resource { data, .. } => {
-                assert_eq!(data.node_type, "resource");
-                // "no-op" is preserved if it is the only action
-                assert_eq!(data.action, Some("no-op".to_string()));
+    /// Renders the graph as a Mermaid flowchart, with edges pointing from dependency to dependent.
+    /// Groups containing other nodes are rendered as subgraphs around them
+    pub fn to_mermaid(&self) -> String {
+        let (node_list, children, roots) = self.node_tree();
+        let mut mermaid_ids: HashMap<&str, String> = HashMap::new();
+        for (idx, (id, _, _)) in node_list.iter().enumerate() {
+            mermaid_ids.insert(id.as_str(), format!("n{}", idx));
+        }
+
+        fn render_node(
+            idx: usize,
+            depth: usize,
+            node_list: &[TreeNode<'_>],
+            children: &HashMap<&str, Vec<usize>>,
+            lines: &mut Vec<String>,
+        ) {
+            let (id, data, _) = node_list[idx];
+            let indent = "    ".repeat(depth);
+            let label = data.label.replace('"', "#quot;");
+            if let Some(nested) = children.get(id.as_str()) {
+                lines.push(format!("{}subgraph n{} [\"{}\"]", indent, idx, label));
+                for &child in nested {
+                    render_node(child, depth + 1, node_list, children, lines);
+                }
+                lines.push(format!("{}end", indent));
+                return;
             }
-            _ => panic!(),
-        };
+            let shape = match data.node_type.as_str() {
+                "var" => format!("([\"{}\"])", label),
+                "output" => format!("[[\"{}\"]]", label),
+                "module" => format!("{{{{\"{}\"}}}}", label),
+                _ => format!("[\"{}\"]", label),
+            };
+            lines.push(format!("{}n{}{}", indent, idx, shape));
+        }
 
-        // 3. Implicit No-Op (Missing from plan) - PRUNED
-        let baz_node = graph.nodes.iter().find(|n| match n {
+        let mut lines = vec!["flowchart LR".to_string()];
+        for idx in roots {
+            render_node(idx, 1, &node_list, &children, &mut lines);
+        }
+
+        for edge in &self.edges {
+            let (Some(source), Some(target)) = (
+                mermaid_ids.get(edge.source.as_str()),
+                mermaid_ids.get(edge.target.as_str()),
+            ) else {
+                continue;
+            };
+            match &edge.attributes {
+                Some(attributes) => lines.push(format!(
+                    "    {} -->|\"{}\"| {}",
+                    source,
+                    attributes.join(", "),
+                    target
+                )),
+                None => lines.push(format!("    {} --> {}", source, target)),
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    /// Renders the graph in the Graphviz DOT language, with edges pointing from dependency to
+    /// dependent. Groups containing other nodes are rendered as clusters around them, and edges
+    /// to or from a cluster are drawn to its border from an invisible anchor node inside it
+    pub fn to_dot(&self) -> String {
+        let (node_list, children, roots) = self.node_tree();
+        fn quote(value: &str) -> String {
+            format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+
+        fn render_node(
+            idx: usize,
+            depth: usize,
+            node_list: &[TreeNode<'_>],
+            children: &HashMap<&str, Vec<usize>>,
+            lines: &mut Vec<String>,
+        ) {
+            let (id, data, _) = node_list[idx];
+            let indent = "    ".repeat(depth);
+            if let Some(nested) = children.get(id.as_str()) {
+                lines.push(format!("{}subgraph cluster_n{} {{", indent, idx));
+                lines.push(format!("{}    label={};", indent, quote(&data.label)));
+                lines.push(format!(
+                    "{}    {} [shape=point, style=invis];",
+                    indent,
+                    quote(id)
+                ));
+                for &child in nested {
+                    render_node(child, depth + 1, node_list, children, lines);
+                }
+                lines.push(format!("{}}}", indent));
+                return;
+            }
+            let shape = match data.node_type.as_str() {
+                "var" => "ellipse",
+                "output" => "note",
+                "module" => "hexagon",
+                _ => "box",
+            };
+            lines.push(format!(
+                "{}{} [label={}, shape={}];",
+                indent,
+                quote(id),
+                quote(&data.label),
+                shape
+            ));
+        }
+
+        let mut lines = vec![
+            "digraph {".to_string(),
+            "    rankdir=LR;".to_string(),
+            "    compound=true;".to_string(),
+        ];
+        for idx in roots {
+            render_node(idx, 1, &node_list, &children, &mut lines);
+        }
+
+        let clusters: HashMap<&str, usize> = node_list
+            .iter()
+            .enumerate()
+            .filter(|(_, (id, _, _))| children.contains_key(id.as_str()))
+            .map(|(idx, (id, _, _))| (id.as_str(), idx))
+            .collect();
+        let ids: HashSet<&str> = node_list.iter().map(|(id, _, _)| id.as_str()).collect();
+        for edge in &self.edges {
+            if !ids.contains(edge.source.as_str()) || !ids.contains(edge.target.as_str()) {
+                continue;
+            }
+            let mut attributes = vec![];
+            if let Some(labels) = &edge.attributes {
+                attributes.push(format!("label={}", quote(&labels.join(", "))));
+            }
+            if let Some(idx) = clusters.get(edge.source.as_str()) {
+                attributes.push(format!("ltail=cluster_n{}", idx));
+            }
+            if let Some(idx) = clusters.get(edge.target.as_str()) {
+                attributes.push(format!("lhead=cluster_n{}", idx));
+            }
+            let attributes = if attributes.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", attributes.join(", "))
+            };
+            lines.push(format!(
+                "    {} -> {}{};",
+                quote(&edge.source),
+                quote(&edge.target),
+                attributes
+            ));
+        }
+
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+}
+
+// Maps a configuration reference to the node it points at in a configuration-only graph
+// E.g., "module.bucket1.bucket_arn" -> "module.bucket1", "var.region" -> "var.region"
+fn configuration_reference_node(reference: &str) -> String {
+    let parts: Vec<&str> = reference.split('.').collect();
+    if parts.len() >= 2 && (parts[0] == "module" || parts[0] == "var" || parts[0] == "local") {
+        let name = parts[1].split('[').next().unwrap_or(parts[1]);
+        format!("{}.{}", parts[0], name)
+    } else {
+        strip_attribute_path(reference)
+    }
+}
+
+// Splits a reference to a module output into the module node and the output name
+// E.g., "module.bucket1.bucket_arn" -> ("module.bucket1", "bucket_arn")
+fn module_output_reference(reference: &str) -> Option<(String, String)> {
+    let parts: Vec<&str> = reference.split('.').collect();
+    if parts.len() >= 3 && parts[0] == "module" {
+        let output = parts[2].split('[').next().unwrap_or(parts[2]);
+        Some((configuration_reference_node(reference), output.to_string()))
+    } else {
+        None
+    }
+}
+
+// Nodes an expression depends on in a configuration-only graph, where module outputs are nodes
+// inside their module. A bare "module.x" is dropped when one of its outputs is referenced too, as
+// Terraform reports both for "module.x.output"
+fn configuration_dependencies(expression: &serde_json::Value) -> Vec<String> {
+    let mut references = Vec::new();
+    extract_references(expression, &mut references);
+    references
+        .iter()
+        .filter_map(|reference| match module_output_reference(reference) {
+            Some((module, output)) => Some(format!("{}.output.{}", module, output)),
+            None if reference.starts_with("module.")
+                && references
+                    .iter()
+                    .any(|other| other.starts_with(&format!("{}.", reference))) =>
+            {
+                None
+            }
+            None => Some(configuration_reference_node(reference)),
+        })
+        .collect()
+}
+
+// Variable or output of a module call, rendered inside the group of the module
+fn module_interface_node(module_id: &str, node_type: &str, name: &str) -> OutputNode {
+    OutputNode::Resource {
+        id: format!("{}.{}.{}", module_id, node_type, name),
+        parent_id: Some(module_id.to_string()),
+        data: OutputNodeData {
+            label: format!("{}.{}", node_type, name),
+            node_type: node_type.to_string(),
+            action: None,
+            count: None,
+            hcl: None,
+            values: None,
+            provider: None,
+        },
+        position: OutputNodePosition { x: 0, y: 0 },
+    }
+}
+
+/// Builds a graph from the configuration section of `terraform show -json` alone, without a plan
+/// or DOT graph. The root module's variables and outputs are nodes and its module calls are
+/// groups holding the variables and outputs of the module, so edges show which output feeds which
+/// variable across module boundaries. Outputs are taken from the called module's configuration
+/// when present, and otherwise from the references to them. `depends_on` between module calls
+/// becomes an edge between their groups.
+pub fn process_configuration(configuration_json: &str) -> Result<OutputGraph> {
+    let configuration: Configuration =
+        serde_json::from_str(configuration_json).context("Failed to parse configuration")?;
+    let root = &configuration.root_module;
+
+    let mut nodes = Vec::new();
+    let mut known_modules: HashSet<String> = HashSet::new();
+    // (dependency, dependent) pairs, matching the orientation of the edges
+    let mut dependencies: HashSet<(String, String)> = HashSet::new();
+
+    // Outputs of module calls referenced anywhere in the root module
+    let mut referenced_outputs: HashMap<String, HashSet<String>> = HashMap::new();
+    let expressions = root
+        .module_calls
+        .iter()
+        .flat_map(|calls| calls.values())
+        .filter_map(|call| call.expressions.as_ref())
+        .flat_map(|exprs| exprs.values())
+        .chain(
+            root.outputs
+                .iter()
+                .flat_map(|outputs| outputs.values())
+                .filter_map(|output| output.expression.as_ref()),
+        );
+    for expression in expressions {
+        let mut references = Vec::new();
+        extract_references(expression, &mut references);
+        for (module, output) in references.iter().filter_map(|r| module_output_reference(r)) {
+            referenced_outputs.entry(module).or_default().insert(output);
+        }
+    }
+
+    if let Some(variables) = &root.variables {
+        let mut names: Vec<&String> = variables.keys().collect();
+        names.sort();
+        for name in names {
+            let id = format!("var.{}", name);
+            let variable = &variables[name];
+            nodes.push(OutputNode::Resource {
+                id: id.clone(),
+                parent_id: None,
+                data: OutputNodeData {
+                    label: id,
+                    node_type: "var".to_string(),
+                    action: None,
+                    count: None,
+                    hcl: None,
+                    values: variable
+                        .as_object()
+                        .is_some_and(|o| !o.is_empty())
+                        .then(|| variable.clone()),
+                    provider: None,
+                },
+                position: OutputNodePosition { x: 0, y: 0 },
+            });
+        }
+    }
+
+    if let Some(calls) = &root.module_calls {
+        let mut names: Vec<&String> = calls.keys().collect();
+        names.sort();
+        for name in names {
+            let id = format!("module.{}", name);
+            let call = &calls[name];
+            let module = call.module.as_ref();
+            extract_parent_modules(&id, &mut known_modules, &mut nodes);
+
+            let mut variables: Vec<&String> = module
+                .and_then(|m| m.variables.as_ref())
+                .into_iter()
+                .flat_map(|variables| variables.keys())
+                .chain(call.expressions.iter().flat_map(|exprs| exprs.keys()))
+                .collect();
+            variables.sort();
+            variables.dedup();
+            for variable in variables {
+                let node = module_interface_node(&id, "var", variable);
+                if let Some(expr) = call.expressions.as_ref().and_then(|e| e.get(variable)) {
+                    let variable_id = format!("{}.var.{}", id, variable);
+                    for dependency in configuration_dependencies(expr) {
+                        dependencies.insert((dependency, variable_id.clone()));
+                    }
+                }
+                nodes.push(node);
+            }
+
+            let module_outputs = module.and_then(|m| m.outputs.as_ref());
+            let mut outputs: Vec<&String> = module_outputs
+                .into_iter()
+                .flat_map(|outputs| outputs.keys())
+                .chain(referenced_outputs.get(&id).into_iter().flatten())
+                .collect();
+            outputs.sort();
+            outputs.dedup();
+            for output in outputs {
+                let output_id = format!("{}.output.{}", id, output);
+                // Inside the module, variable references resolve to the module's own variables
+                let expression = module_outputs
+                    .and_then(|outputs| outputs.get(output))
+                    .and_then(|output| output.expression.as_ref());
+                if let Some(expr) = expression {
+                    let mut references = Vec::new();
+                    extract_references(expr, &mut references);
+                    for reference in references.iter().filter(|r| r.starts_with("var.")) {
+                        dependencies.insert((
+                            format!("{}.{}", id, configuration_reference_node(reference)),
+                            output_id.clone(),
+                        ));
+                    }
+                }
+                nodes.push(module_interface_node(&id, "output", output));
+            }
+
+            for dependency in call.depends_on.iter().flatten() {
+                dependencies.insert((configuration_reference_node(dependency), id.clone()));
+            }
+        }
+    }
+
+    if let Some(outputs) = &root.outputs {
+        let mut names: Vec<&String> = outputs.keys().collect();
+        names.sort();
+        for name in names {
+            let id = format!("output.{}", name);
+            nodes.push(OutputNode::Resource {
+                id: id.clone(),
+                parent_id: None,
+                data: OutputNodeData {
+                    label: id.clone(),
+                    node_type: "output".to_string(),
+                    action: None,
+                    count: None,
+                    hcl: None,
+                    values: None,
+                    provider: None,
+                },
+                position: OutputNodePosition { x: 0, y: 0 },
+            });
+            if let Some(expr) = &outputs[name].expression {
+                for dependency in configuration_dependencies(expr) {
+                    dependencies.insert((dependency, id.clone()));
+                }
+            }
+        }
+    }
+
+    let node_ids: HashSet<String> = nodes
+        .iter()
+        .map(|node| match node {
+            OutputNode::Group { id, .. } => id.clone(),
+            OutputNode::Resource { id, .. } => id.clone(),
+        })
+        .collect();
+
+    let edges = dependencies
+        .into_iter()
+        .filter(|(source, target)| {
+            source != target && node_ids.contains(source) && node_ids.contains(target)
+        })
+        .map(|(source, target)| OutputEdge {
+            id: edge_id(&source, &target),
+            source,
+            target,
+            attributes: None,
+        })
+        .collect();
+
+    Ok(OutputGraph::new(nodes, edges))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_find_hcl_block_variables_outputs_and_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(
+            root.join("main.tf"),
+            r#"variable "bucket_name" {
+  type = string
+}
+
+module "storage" {
+  source = "./modules/storage"
+  name   = var.bucket_name
+}
+
+output "bucket_arn" {
+  value = module.storage.arn
+}
+"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("modules/storage")).unwrap();
+        std::fs::write(
+            root.join("modules/storage/main.tf"),
+            r#"variable "name" {
+  type = string
+}
+
+output "arn" {
+  value = "arn:${var.name}"
+}
+"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join(".terraform/modules")).unwrap();
+        std::fs::write(
+            root.join(".terraform/modules/modules.json"),
+            r#"{"Modules":[{"Key":"","Source":"","Dir":"."},{"Key":"storage","Source":"./modules/storage","Dir":"modules/storage"}]}"#,
+        )
+        .unwrap();
+
+        let mut cache = HashMap::new();
+        let block = |address: &str, cache: &mut HashMap<std::path::PathBuf, String>| {
+            find_hcl_block(root, address, cache)
+        };
+
+        assert_eq!(
+            block("var.bucket_name", &mut cache).as_deref(),
+            Some("variable \"bucket_name\" {\n  type = string\n}")
+        );
+        assert!(
+            block("output.bucket_arn", &mut cache)
+                .unwrap()
+                .starts_with("output \"bucket_arn\"")
+        );
+        assert!(
+            block("module.storage", &mut cache)
+                .unwrap()
+                .contains("source = \"./modules/storage\"")
+        );
+        assert_eq!(
+            block("module.storage.var.name", &mut cache).as_deref(),
+            Some("variable \"name\" {\n  type = string\n}")
+        );
+        assert!(
+            block("module.storage.output.arn", &mut cache)
+                .unwrap()
+                .contains("arn:${var.name}")
+        );
+        assert_eq!(block("var.missing", &mut cache), None);
+    }
+
+    #[test]
+    fn test_multiple_indices() {
+        let plan_json = r#"{
+            "resource_changes": [
+                {
+                    "address": "module.vpc.aws_subnet.public[0]",
+                    "type": "aws_subnet",
+                    "change": { "actions": ["create"] }
+                },
+                {
+                    "address": "module.vpc.aws_subnet.public[1]",
+                    "type": "aws_subnet",
+                    "change": { "actions": ["delete"] }
+                }
+            ]
+        }"#;
+
+        // Graph dot has the base name
+        let dot_content = r#"
+            digraph {
+                "[root] module.vpc.aws_subnet.public" [label = "module.vpc.aws_subnet.public"]
+            }
+        "#;
+
+        let graph = process_graph(
+            plan_json,
+            dot_content,
+            false,
+            None,
+            &GraphOptions::default(),
+        )
+        .unwrap();
+        let node = graph
+            .nodes
+            .iter()
+            .find(|n| match n {
+                OutputNode::Resource { id, .. } => id == "module.vpc.aws_subnet.public",
+                _ => false,
+            })
+            .expect("Node should exist");
+
+        match node {
+            OutputNode::Resource { data, .. } => {
+                // Actions should be aggregated and sorted
+                assert_eq!(data.action, Some("create, delete".to_string()));
+            }
+            _ => panic!("Wrong node type"),
+        }
+    }
+
+    #[test]
+    fn test_action_resolution() {
+        let plan_json = r#"{
+            "resource_changes": [
+                {
+                    "address": "data.aws_ami.ubuntu",
+                    "type": "aws_ami",
+                    "mode": "data",
+                    "change": { "actions": ["read"] }
+                },
+                {
+                    "address": "aws_instance.foo",
+                    "type": "aws_instance",
+                    "change": { "actions": ["no-op"] }
+                },
+                {
+                    "address": "aws_instance.bar",
+                    "type": "aws_instance",
+                    "change": { "actions": ["update"] }
+                }
+            ]
+        }"#;
+
+        let dot_content = r#"
+            digraph {
+                "[root] data.aws_ami.ubuntu" [label = "data.aws_ami.ubuntu"]
+                "[root] aws_instance.foo" [label = "aws_instance.foo"]
+                "[root] aws_instance.bar" [label = "aws_instance.bar"]
+                "[root] aws_instance.baz" [label = "aws_instance.baz"]
+                "[root] aws_instance.bar" -> "[root] data.aws_ami.ubuntu"
+            }
+        "#;
+
+        let graph = process_graph(
+            plan_json,
+            dot_content,
+            false,
+            None,
+            &GraphOptions::default(),
+        )
+        .unwrap();
+
+        // 1. Data Source
+        let data_node = graph
+            .nodes
+            .iter()
+            .find(|n| match n {
+                OutputNode::Resource { id, .. } => id == "data.aws_ami.ubuntu",
+                _ => false,
+            })
+            .unwrap();
+        match data_node {
+            OutputNode::Resource { data, .. } => {
+                assert_eq!(data.node_type, "data");
+                assert_eq!(data.action, Some("read".to_string()));
+            }
+            _ => panic!(),
+        };
+
+        // 2. Explicit No-Op
+        let foo_node = graph
+            .nodes
+            .iter()
+            .find(|n| match n {
+                OutputNode::Resource { id, .. } => id == "aws_instance.foo",
+                _ => false,
+            })
+            .unwrap();
+        match foo_node {
+            OutputNode::Resource { data, .. } => {
+                assert_eq!(data.node_type, "resource");
+                // "no-op" is preserved if it is the only action
+                assert_eq!(data.action, Some("no-op".to_string()));
+            }
+            _ => panic!(),
+        };
+
+        // 3. Implicit No-Op (Missing from plan) - PRUNED
+        let baz_node = graph.nodes.iter().find(|n| match n {
             OutputNode::Resource { id, .. } => id == "aws_instance.baz",
             _ => false,
         });
@@ -1647,6 +2655,87 @@ mod tests {
         assert!(baz_node.is_none());
     }
 
+    #[test]
+    fn test_graph_options_keep_noise() {
+        let plan_json = r#"{
+            "resource_changes": [
+                {
+                    "address": "aws_instance.foo",
+                    "type": "aws_instance",
+                    "change": { "actions": ["delete"] }
+                },
+                {
+                    "address": "module.vpc.aws_vpc.this",
+                    "type": "aws_vpc",
+                    "change": { "actions": ["delete"] }
+                }
+            ]
+        }"#;
+
+        let dot_content = r#"
+            digraph {
+                "[root] aws_instance.foo" [label = "aws_instance.foo"]
+                "[root] aws_instance.ghost" [label = "aws_instance.ghost"]
+                "[root] local.name" [label = "local.name"]
+                "[root] module.vpc.aws_vpc.this" [label = "module.vpc.aws_vpc.this"]
+                "[root] module.vpc.output.vpc_id" [label = "module.vpc.output.vpc_id"]
+                "[root] aws_instance.foo" -> "[root] local.name"
+                "[root] aws_instance.foo" -> "[root] module.vpc.output.vpc_id"
+                "[root] module.vpc.output.vpc_id" -> "[root] module.vpc.aws_vpc.this"
+            }
+        "#;
+
+        let node_ids = |graph: &OutputGraph| -> HashSet<String> {
+            graph
+                .nodes
+                .iter()
+                .filter_map(|n| match n {
+                    OutputNode::Resource { id, .. } => Some(id.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let graph = process_graph(
+            plan_json,
+            dot_content,
+            false,
+            None,
+            &GraphOptions::default(),
+        )
+        .unwrap();
+        let ids = node_ids(&graph);
+        assert!(!ids.contains("aws_instance.ghost"));
+        assert!(!ids.contains("local.name"));
+        assert!(!ids.contains("module.vpc.output.vpc_id"));
+        assert!(
+            graph
+                .edges
+                .iter()
+                .any(|e| e.source == "module.vpc.aws_vpc.this" && e.target == "aws_instance.foo")
+        );
+
+        let options = GraphOptions {
+            drop_unplanned_resources: false,
+            hide_module_outputs: false,
+            hide_constant_locals: false,
+        };
+        let graph = process_graph(plan_json, dot_content, false, None, &options).unwrap();
+        let ids = node_ids(&graph);
+        assert!(ids.contains("aws_instance.ghost"));
+        assert!(ids.contains("local.name"));
+        assert!(ids.contains("module.vpc.output.vpc_id"));
+        assert!(
+            graph
+                .edges
+                .iter()
+                .any(|e| e.source == "module.vpc.output.vpc_id" && e.target == "aws_instance.foo")
+        );
+        assert!(graph.edges.iter().any(
+            |e| e.source == "module.vpc.aws_vpc.this" && e.target == "module.vpc.output.vpc_id"
+        ));
+    }
+
     #[test]
     fn test_include_values() {
         let plan_json = r#"{
@@ -1669,7 +2758,8 @@ mod tests {
         "#;
 
         // Check with include_values = true
-        let graph = process_graph(plan_json, dot_content, true, None).unwrap();
+        let graph =
+            process_graph(plan_json, dot_content, true, None, &GraphOptions::default()).unwrap();
         let node = graph
             .nodes
             .iter()
@@ -1689,7 +2779,14 @@ mod tests {
         }
 
         // Check with include_values = false
-        let graph = process_graph(plan_json, dot_content, false, None).unwrap();
+        let graph = process_graph(
+            plan_json,
+            dot_content,
+            false,
+            None,
+            &GraphOptions::default(),
+        )
+        .unwrap();
         let node = graph
             .nodes
             .iter()
@@ -1707,6 +2804,199 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_resource_providers() {
+        let plan_json = r#"{
+            "resource_changes": [
+                {
+                    "address": "aws_s3_bucket.logs",
+                    "type": "aws_s3_bucket",
+                    "provider_name": "registry.terraform.io/hashicorp/aws",
+                    "change": { "actions": ["create"] }
+                },
+                {
+                    "address": "module.app.helm_release.app",
+                    "type": "helm_release",
+                    "provider_name": "registry.terraform.io/hashicorp/helm",
+                    "change": { "actions": ["create"] }
+                },
+                {
+                    "address": "module.app.kubernetes_namespace.app",
+                    "type": "kubernetes_namespace",
+                    "change": { "actions": ["create"] }
+                }
+            ],
+            "configuration": {
+                "provider_config": {
+                    "aws.east": { "name": "aws", "alias": "east" }
+                },
+                "root_module": {
+                    "resources": [
+                        { "address": "aws_s3_bucket.logs", "provider_config_key": "aws.east" }
+                    ],
+                    "module_calls": {
+                        "app": {
+                            "module": {
+                                "resources": [
+                                    {
+                                        "address": "kubernetes_namespace.app",
+                                        "provider_config_key": "module.app:kubernetes"
+                                    }
+                                ]
+                            }
+                        }
+                    }
+                }
+            }
+        }"#;
+
+        let dot_content = r#"
+            digraph {
+                "[root] aws_s3_bucket.logs" [label = "aws_s3_bucket.logs"]
+                "[root] module.app.helm_release.app" [label = "module.app.helm_release.app"]
+                "[root] module.app.kubernetes_namespace.app" [label = "module.app.kubernetes_namespace.app"]
+                "[root] var.region" [label = "var.region"]
+                "[root] aws_s3_bucket.logs" -> "[root] var.region"
+            }
+        "#;
+
+        let graph = process_graph(
+            plan_json,
+            dot_content,
+            false,
+            None,
+            &GraphOptions::default(),
+        )
+        .unwrap();
+        let providers: HashMap<&str, Option<&str>> = graph
+            .nodes
+            .iter()
+            .filter_map(|n| match n {
+                OutputNode::Resource { id, data, .. } => {
+                    Some((id.as_str(), data.provider.as_deref()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(providers["aws_s3_bucket.logs"], Some("aws"));
+        assert_eq!(providers["module.app.helm_release.app"], Some("helm"));
+        assert_eq!(
+            providers["module.app.kubernetes_namespace.app"],
+            Some("kubernetes")
+        );
+        assert_eq!(providers["var.region"], None);
+    }
+
+    #[test]
+    fn test_include_state_output_values() {
+        let state_json = r#"{
+            "values": {
+                "outputs": {
+                    "bucket_arn": { "sensitive": false, "value": "arn:aws:s3:::logs" },
+                    "password": { "sensitive": true, "value": "hunter2" }
+                },
+                "root_module": {
+                    "child_modules": [
+                        {
+                            "resources": [
+                                {
+                                    "address": "module.storage.aws_s3_bucket.this[0]",
+                                    "mode": "managed",
+                                    "type": "aws_s3_bucket",
+                                    "values": { "arn": "arn:aws:s3:::logs", "tags": { "Name": "logs" } }
+                                }
+                            ]
+                        }
+                    ]
+                }
+            },
+            "configuration": {
+                "root_module": {
+                    "module_calls": {
+                        "storage": {
+                            "module": {
+                                "outputs": {
+                                    "arn": {
+                                        "expression": {
+                                            "references": [
+                                                "aws_s3_bucket.this[0].arn",
+                                                "aws_s3_bucket.this[0]",
+                                                "aws_s3_bucket.this"
+                                            ]
+                                        }
+                                    },
+                                    "name": {
+                                        "expression": {
+                                            "references": ["aws_s3_bucket.this[0].tags.Name"]
+                                        },
+                                        "sensitive": true
+                                    },
+                                    "region": {
+                                        "expression": { "references": ["var.region"] }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }"#;
+
+        let dot_content = r#"
+            digraph {
+                "[root] module.storage.aws_s3_bucket.this" [label = "module.storage.aws_s3_bucket.this"]
+                "[root] module.storage.output.arn" [label = "module.storage.output.arn"]
+                "[root] module.storage.output.name" [label = "module.storage.output.name"]
+                "[root] module.storage.output.region" [label = "module.storage.output.region"]
+                "[root] output.bucket_arn" [label = "output.bucket_arn"]
+                "[root] output.password" [label = "output.password"]
+                "[root] output.bucket_arn" -> "[root] module.storage.output.arn"
+                "[root] output.password" -> "[root] module.storage.output.name"
+                "[root] output.password" -> "[root] module.storage.output.region"
+                "[root] module.storage.output.arn" -> "[root] module.storage.aws_s3_bucket.this"
+                "[root] module.storage.output.name" -> "[root] module.storage.aws_s3_bucket.this"
+            }
+        "#;
+
+        let options = GraphOptions {
+            hide_module_outputs: false,
+            ..GraphOptions::default()
+        };
+        let values_of = |graph: &OutputGraph, address: &str| {
+            graph.nodes.iter().find_map(|n| match n {
+                OutputNode::Resource { id, data, .. } if id == address => Some(data.values.clone()),
+                _ => None,
+            })
+        };
+
+        let graph = process_graph(state_json, dot_content, true, None, &options).unwrap();
+        assert_eq!(
+            values_of(&graph, "output.bucket_arn"),
+            Some(Some(json!("arn:aws:s3:::logs")))
+        );
+        assert_eq!(
+            values_of(&graph, "output.password"),
+            Some(Some(json!("(sensitive)")))
+        );
+        assert_eq!(
+            values_of(&graph, "module.storage.output.arn"),
+            Some(Some(json!("arn:aws:s3:::logs")))
+        );
+        assert_eq!(
+            values_of(&graph, "module.storage.output.name"),
+            Some(Some(json!("(sensitive)")))
+        );
+        assert_eq!(
+            values_of(&graph, "module.storage.output.region"),
+            Some(None)
+        );
+
+        let graph = process_graph(state_json, dot_content, false, None, &options).unwrap();
+        assert_eq!(values_of(&graph, "output.bucket_arn"), Some(None));
+        assert_eq!(values_of(&graph, "module.storage.output.arn"), Some(None));
+    }
+
     #[test]
     fn test_count_expression_attribute_extraction() {
         // Setup: A resource with a count expression depending on "var.azs"
@@ -1715,6 +3005,7 @@ mod tests {
 
         let resource_config = ResourceConfig {
             address: resource_name.to_string(),
+            provider_config_key: None,
             expressions: None,
             count_expression: Some(json!({
                 "references": [dependency]
@@ -1726,6 +3017,7 @@ mod tests {
             resources: Some(vec![resource_config]),
             module_calls: None,
             outputs: None,
+            variables: None,
         };
 
         let mut edge_attributes = HashMap::new();
@@ -1754,6 +3046,7 @@ mod tests {
 
         let resource_config = ResourceConfig {
             address: resource_name.to_string(),
+            provider_config_key: None,
             expressions: None,
             count_expression: None,
             for_each_expression: Some(json!({
@@ -1765,6 +3058,7 @@ mod tests {
             resources: Some(vec![resource_config]),
             module_calls: None,
             outputs: None,
+            variables: None,
         };
 
         let mut edge_attributes = HashMap::new();
@@ -1783,6 +3077,396 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_process_configuration_stack() {
+        let configuration_json = r#"{
+            "root_module": {
+                "module_calls": {
+                    "bucket1": {
+                        "source": "./S3Bucket-0.1.0",
+                        "expressions": {
+                            "bucket_name": { "references": ["var.bucket1__bucket_name"] }
+                        },
+                        "module": {
+                            "variables": { "bucket_name": {}, "tags": {} },
+                            "outputs": {
+                                "bucket_arn": {
+                                    "expression": { "references": ["var.bucket_name"] }
+                                }
+                            }
+                        }
+                    },
+                    "bucket2": {
+                        "source": "./S3Bucket-0.1.0",
+                        "expressions": {
+                            "tags": { "references": ["module.bucket1.bucket_arn", "module.bucket1"] }
+                        },
+                        "depends_on": ["module.bucket1"]
+                    }
+                },
+                "variables": {
+                    "bucket1__bucket_name": {}
+                },
+                "outputs": {
+                    "bucket2__bucket_arn": {
+                        "expression": { "references": ["module.bucket2.bucket_arn", "module.bucket2"] }
+                    }
+                }
+            }
+        }"#;
+
+        let graph = process_configuration(configuration_json).unwrap();
+
+        let mut ids: Vec<(&str, Option<&str>)> = graph
+            .nodes
+            .iter()
+            .map(|n| match n {
+                OutputNode::Group { id, parent_id, .. }
+                | OutputNode::Resource { id, parent_id, .. } => (id.as_str(), parent_id.as_deref()),
+            })
+            .collect();
+        ids.sort();
+        let (bucket1, bucket2) = (Some("module.bucket1"), Some("module.bucket2"));
+        assert_eq!(
+            ids,
+            vec![
+                ("module.bucket1", None),
+                ("module.bucket1.output.bucket_arn", bucket1),
+                ("module.bucket1.var.bucket_name", bucket1),
+                ("module.bucket1.var.tags", bucket1),
+                ("module.bucket2", None),
+                ("module.bucket2.output.bucket_arn", bucket2),
+                ("module.bucket2.var.tags", bucket2),
+                ("output.bucket2__bucket_arn", None),
+                ("var.bucket1__bucket_name", None),
+            ]
+        );
+
+        let edges: Vec<(&str, &str)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.source.as_str(), e.target.as_str()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("module.bucket1", "module.bucket2"),
+                (
+                    "module.bucket1.output.bucket_arn",
+                    "module.bucket2.var.tags"
+                ),
+                (
+                    "module.bucket1.var.bucket_name",
+                    "module.bucket1.output.bucket_arn"
+                ),
+                (
+                    "module.bucket2.output.bucket_arn",
+                    "output.bucket2__bucket_arn"
+                ),
+                ("var.bucket1__bucket_name", "module.bucket1.var.bucket_name"),
+            ]
+        );
+
+        let mermaid = graph.to_mermaid();
+        assert!(mermaid.starts_with("flowchart LR"));
+        assert!(mermaid.contains("    subgraph n0 [\"module.bucket1\"]\n        n1[["));
+        assert!(mermaid.contains("        n1[[\"output.bucket_arn\"]]"));
+        assert!(mermaid.contains("        n2([\"var.bucket_name\"])"));
+        assert!(mermaid.contains("    n0 --> n4"));
+        assert!(mermaid.contains("    n1 --> n6"));
+    }
+
+    #[test]
+    fn test_stable_order_and_edge_ids() {
+        assert_eq!(
+            edge_id("var.bucket1__bucket_name", "module.bucket1"),
+            "e_1c6ba6cee4433428"
+        );
+        assert_ne!(edge_id("a.b", "c"), edge_id("a", "b.c"));
+        assert_ne!(edge_id("a", "b"), edge_id("b", "a"));
+
+        let node = |id: &str, parent_id: Option<&str>| OutputNode::Resource {
+            id: id.to_string(),
+            parent_id: parent_id.map(str::to_string),
+            data: OutputNodeData {
+                label: id.to_string(),
+                node_type: "resource".to_string(),
+                action: None,
+                count: None,
+                hcl: None,
+                values: None,
+                provider: None,
+            },
+            position: OutputNodePosition { x: 0, y: 0 },
+        };
+        let edge = |source: &str, target: &str| OutputEdge {
+            id: edge_id(source, target),
+            source: source.to_string(),
+            target: target.to_string(),
+            attributes: None,
+        };
+        let mut nodes = vec![
+            node("var.b", Some("root_variables")),
+            node("module.a.aws_s3_bucket.b", Some("module.a")),
+            node("var.a", Some("root_variables")),
+        ];
+        let mut known_modules = HashSet::new();
+        extract_parent_modules("module.a.module.c", &mut known_modules, &mut nodes);
+        let graph = OutputGraph::new(
+            nodes,
+            vec![
+                edge("var.b", "module.a.aws_s3_bucket.b"),
+                edge("var.a", "module.a.aws_s3_bucket.b"),
+            ],
+        );
+
+        let ids: Vec<&str> = graph
+            .nodes
+            .iter()
+            .map(|n| match n {
+                OutputNode::Group { id, .. } | OutputNode::Resource { id, .. } => id.as_str(),
+            })
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                "module.a",
+                "module.a.module.c",
+                "module.a.aws_s3_bucket.b",
+                "var.a",
+                "var.b",
+            ]
+        );
+        let sources: Vec<&str> = graph.edges.iter().map(|e| e.source.as_str()).collect();
+        assert_eq!(sources, vec!["var.a", "var.b"]);
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph {\n    rankdir=LR;"));
+        assert!(dot.contains("    subgraph cluster_n0 {\n"));
+        assert!(dot.contains(
+            "        \"module.a.aws_s3_bucket.b\" [label=\"module.a.aws_s3_bucket.b\", shape=box];"
+        ));
+        assert!(dot.contains("    \"var.a\" [label=\"var.a\", shape=box];"));
+        assert!(dot.contains("    \"var.a\" -> \"module.a.aws_s3_bucket.b\";"));
+        assert!(dot.ends_with("\n}"));
+    }
+
+    #[test]
+    fn test_graph_summary() {
+        let node = |id: &str, node_type: &str, action: Option<&str>, count: Option<usize>| {
+            let mut known_modules = HashSet::new();
+            let mut groups = vec![];
+            let parent_id = extract_parent_modules(id, &mut known_modules, &mut groups);
+            OutputNode::Resource {
+                id: id.to_string(),
+                parent_id,
+                data: OutputNodeData {
+                    label: id.to_string(),
+                    node_type: node_type.to_string(),
+                    action: action.map(str::to_string),
+                    count,
+                    hcl: None,
+                    values: None,
+                    provider: (node_type != "var").then(|| "aws".to_string()),
+                },
+                position: OutputNodePosition { x: 0, y: 0 },
+            }
+        };
+        let mut nodes = vec![
+            node("aws_vpc.main", "resource", Some("create"), None),
+            node(
+                "module.app.aws_s3_bucket.logs",
+                "resource",
+                Some("create"),
+                Some(3),
+            ),
+            node(
+                "module.app.module.db.aws_db_instance.main",
+                "resource",
+                Some("create, delete"),
+                None,
+            ),
+            node(
+                "module.app.data.aws_region.current",
+                "data",
+                Some("read"),
+                None,
+            ),
+            node("var.region", "var", Some("n/a"), None),
+        ];
+        let mut known_modules = HashSet::new();
+        extract_parent_modules("module.app.module.db", &mut known_modules, &mut nodes);
+
+        let graph = OutputGraph::new(nodes, vec![]);
+        assert_eq!(graph.summary.total, 6);
+        assert_eq!(
+            graph.summary.by_action,
+            BTreeMap::from([
+                ("create".to_string(), 4),
+                ("create, delete".to_string(), 1),
+                ("read".to_string(), 1),
+            ])
+        );
+        assert_eq!(
+            graph.summary.by_provider,
+            BTreeMap::from([("aws".to_string(), 6)])
+        );
+        assert_eq!(
+            graph.summary.by_module,
+            BTreeMap::from([
+                ("module.app".to_string(), 4),
+                ("module.app.module.db".to_string(), 1),
+                ("root".to_string(), 1),
+            ])
+        );
+
+        let group_counts: Vec<(&str, Option<usize>)> = graph
+            .nodes
+            .iter()
+            .filter_map(|n| match n {
+                OutputNode::Group { id, data, .. } => Some((id.as_str(), data.count)),
+                OutputNode::Resource { .. } => None,
+            })
+            .collect();
+        assert_eq!(
+            group_counts,
+            vec![("module.app", Some(5)), ("module.app.module.db", Some(1))]
+        );
+        let json = serde_json::to_value(&graph).unwrap();
+        assert_eq!(json["nodes"][0]["data"]["count"], 5);
+        assert_eq!(json["summary"]["byAction"]["create"], 4);
+    }
+
+    #[test]
+    fn test_filter_graph() {
+        let node = |id: &str, node_type: &str, action: Option<&str>| {
+            let mut known_modules = HashSet::new();
+            let mut groups = vec![];
+            let parent_id = extract_parent_modules(id, &mut known_modules, &mut groups);
+            OutputNode::Resource {
+                id: id.to_string(),
+                parent_id,
+                data: OutputNodeData {
+                    label: id.to_string(),
+                    node_type: node_type.to_string(),
+                    action: action.map(str::to_string),
+                    count: None,
+                    hcl: None,
+                    values: None,
+                    provider: None,
+                },
+                position: OutputNodePosition { x: 0, y: 0 },
+            }
+        };
+        let edge = |source: &str, target: &str, attribute: &str| OutputEdge {
+            id: edge_id(source, target),
+            source: source.to_string(),
+            target: target.to_string(),
+            attributes: Some(vec![attribute.to_string()]),
+        };
+        let graph = || {
+            let mut nodes = vec![
+                node("var.cidr", "var", None),
+                node(
+                    "module.vpc.aws_vpc.main",
+                    "resource",
+                    Some("create, delete"),
+                ),
+                node("module.vpc.aws_subnet.a", "resource", Some("update")),
+                node(
+                    "module.vpc.module.nat.aws_eip.nat",
+                    "resource",
+                    Some("delete"),
+                ),
+                node("module.vpc2.aws_vpc.main", "resource", Some("delete")),
+            ];
+            let mut known_modules = HashSet::new();
+            extract_parent_modules("module.vpc.module.nat", &mut known_modules, &mut nodes);
+            extract_parent_modules("module.vpc2", &mut known_modules, &mut nodes);
+            OutputGraph::new(
+                nodes,
+                vec![
+                    edge("var.cidr", "module.vpc.aws_vpc.main", "cidr_block"),
+                    edge(
+                        "module.vpc.aws_vpc.main",
+                        "module.vpc.aws_subnet.a",
+                        "vpc_id",
+                    ),
+                    edge(
+                        "module.vpc.aws_subnet.a",
+                        "module.vpc.module.nat.aws_eip.nat",
+                        "subnet_id",
+                    ),
+                ],
+            )
+        };
+        let ids = |graph: &OutputGraph| -> Vec<String> {
+            graph
+                .nodes
+                .iter()
+                .map(|n| match n {
+                    OutputNode::Group { id, .. } | OutputNode::Resource { id, .. } => id.clone(),
+                })
+                .collect()
+        };
+
+        // Only destructive changes in module.vpc, which doesn't match module.vpc2
+        let filtered = filter_graph(
+            graph(),
+            &GraphFilter {
+                include_modules: vec!["module.vpc".to_string()],
+                include_actions: vec!["delete".to_string()],
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            ids(&filtered),
+            vec![
+                "module.vpc",
+                "module.vpc.module.nat",
+                "module.vpc.aws_vpc.main",
+                "module.vpc.module.nat.aws_eip.nat",
+            ]
+        );
+        // The dropped subnet is bridged, keeping the attribute of the edge into the dependent
+        assert_eq!(filtered.edges.len(), 1);
+        assert_eq!(filtered.edges[0].source, "module.vpc.aws_vpc.main");
+        assert_eq!(
+            filtered.edges[0].target,
+            "module.vpc.module.nat.aws_eip.nat"
+        );
+        assert_eq!(
+            filtered.edges[0].attributes,
+            Some(vec!["subnet_id".to_string()])
+        );
+        assert_eq!(filtered.summary.total, 2);
+
+        let filtered = filter_graph(
+            graph(),
+            &GraphFilter {
+                exclude_modules: vec!["module.vpc.module.nat".to_string()],
+                exclude_types: vec!["var".to_string()],
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            ids(&filtered),
+            vec![
+                "module.vpc",
+                "module.vpc2",
+                "module.vpc.aws_subnet.a",
+                "module.vpc.aws_vpc.main",
+                "module.vpc2.aws_vpc.main",
+            ]
+        );
+        assert_eq!(filtered.edges.len(), 1);
+
+        assert_eq!(
+            ids(&filter_graph(graph(), &GraphFilter::default())),
+            ids(&graph())
+        );
+    }
+
     #[test]
     fn test_value_merging() {
         let after = json!({
@@ -1879,8 +3563,13 @@ fn extract_block_from_content(
     resource_type: &str,
     name: &str,
 ) -> Option<String> {
-    let pattern = if node_type == "module" {
-        format!(r#"(?m)^[\t ]*module\s+"{}"\s*\{{"#, regex::escape(name))
+    // Blocks with a single label: module "x", variable "x" and output "x"
+    let pattern = if matches!(node_type, "module" | "variable" | "output") {
+        format!(
+            r#"(?m)^[\t ]*{}\s+"{}"\s*\{{"#,
+            node_type,
+            regex::escape(name)
+        )
     } else {
         format!(
             r#"(?m)^[\t ]*{}\s+"{}"\s+"{}"\s*\{{"#,
@@ -1947,8 +3636,6 @@ fn find_hcl_block(
     address: &str,
     cache: &mut HashMap<std::path::PathBuf, String>,
 ) -> Option<String> {
-    let module_dir = get_module_dir(root_dir, address)?;
-
     let parts: Vec<&str> = address.split('.').collect();
     let mut local_parts = Vec::new();
     let mut i = 0;
@@ -1961,23 +3648,21 @@ fn find_hcl_block(
         }
     }
 
-    if local_parts.is_empty() {
-        return None;
-    }
-
-    let (node_type, res_type, name) = if local_parts[0] == "data" {
-        if local_parts.len() < 3 {
-            return None;
-        }
-        ("data", local_parts[1], local_parts[2])
-    } else if local_parts[0] == "var" || local_parts[0] == "output" {
-        return None;
+    // A module call is declared in the directory of its parent module
+    let module_dir = if local_parts.is_empty() {
+        let parent_address = parts[..parts.len().saturating_sub(2)].join(".");
+        get_module_dir(root_dir, &parent_address)?
     } else {
-        if local_parts.len() == 2 {
-            ("resource", local_parts[0], local_parts[1])
-        } else {
-            return None;
-        }
+        get_module_dir(root_dir, address)?
+    };
+
+    let (node_type, res_type, name) = match local_parts.as_slice() {
+        [] => ("module", "", *parts.last()?),
+        ["data", res_type, name] => ("data", *res_type, *name),
+        ["var", name] => ("variable", "", *name),
+        ["output", name] => ("output", "", *name),
+        [res_type, name] => ("resource", *res_type, *name),
+        _ => return None,
     };
 
     if let Ok(entries) = std::fs::read_dir(&module_dir) {
@@ -2005,6 +3690,77 @@ fn find_hcl_block(
     None
 }
 
+// "registry.terraform.io/hashicorp/aws" -> "aws"
+fn provider_local_name(provider_name: &str) -> String {
+    provider_name
+        .rsplit('/')
+        .next()
+        .unwrap_or(provider_name)
+        .to_string()
+}
+
+// Resolves the `provider_config_key` of each resource against `provider_config`. Keys missing
+// from it, such as "module.vpc:aws.east", fall back to the provider name in the key itself
+fn collect_configuration_providers(
+    module: &ModuleConfig,
+    parent_path: &str,
+    provider_config: Option<&HashMap<String, ProviderConfig>>,
+    providers: &mut HashMap<String, String>,
+) {
+    if let Some(resources) = &module.resources {
+        for res in resources {
+            let Some(key) = &res.provider_config_key else {
+                continue;
+            };
+            let provider = match provider_config.and_then(|config| config.get(key)) {
+                Some(config) => config.name.clone(),
+                None => {
+                    let name = key.rsplit(':').next().unwrap_or(key);
+                    name.split('.').next().unwrap_or(name).to_string()
+                }
+            };
+            let full_address = if parent_path.is_empty() {
+                res.address.clone()
+            } else {
+                format!("{}.{}", parent_path, res.address)
+            };
+            providers.insert(full_address, provider);
+        }
+    }
+    if let Some(calls) = &module.module_calls {
+        for (name, call) in calls {
+            if let Some(submodule) = &call.module {
+                let sub_path = if parent_path.is_empty() {
+                    format!("module.{}", name)
+                } else {
+                    format!("{}.module.{}", parent_path, name)
+                };
+                collect_configuration_providers(submodule, &sub_path, provider_config, providers);
+            }
+        }
+    }
+}
+
+fn collect_state_providers(
+    module: &StateModule,
+    index_strip_regex: &Regex,
+    providers: &mut HashMap<String, String>,
+) {
+    if let Some(resources) = &module.resources {
+        for res in resources {
+            if let Some(provider_name) = &res.provider_name {
+                let base = index_strip_regex.replace(&res.address, "").to_string();
+                providers.insert(base, provider_local_name(provider_name));
+            }
+        }
+    }
+    if let Some(children) = &module.child_modules {
+        for child in children {
+            collect_state_providers(child, index_strip_regex, providers);
+        }
+    }
+}
+
 fn collect_state_addresses(module: &StateModule, addresses: &mut HashSet<String>) {
     if let Some(resources) = &module.resources {
         for res in resources {
@@ -3,7 +3,7 @@ use std::fs;
 use std::path::Path;
 use std::process::Command;
 
-use graph::{OutputGraph, OutputNode, process_graph};
+use graph::{GraphOptions, OutputGraph, OutputNode, process_graph};
 
 // Helper function to run fixture and process graph
 fn run_fixture(fixture_name: &str, use_state: bool) -> OutputGraph {
@@ -89,6 +89,7 @@ fn run_fixture(fixture_name: &str, use_state: bool) -> OutputGraph {
         &graph_dot,
         use_state,
         Some(target_dir.to_path_buf()),
+        &GraphOptions::default(),
     )
     .expect("process_graph failed");
 
@@ -1085,8 +1086,14 @@ fn test_state_file_mode() {
     "#;
 
     // Process with include_values = true
-    let graph =
-        process_graph(state_json, dot_content, true, None).expect("Graph processing failed");
+    let graph = process_graph(
+        state_json,
+        dot_content,
+        true,
+        None,
+        &GraphOptions::default(),
+    )
+    .expect("Graph processing failed");
 
     // Verify Managed Resource
     let prod_node = graph
@@ -1171,6 +1178,7 @@ fn test_realistic_eks_module_outputs() {
         &dot_content,
         false,
         Some(fixture_path.to_path_buf()),
+        &GraphOptions::default(),
     )
     .expect("Failed to process graph");
 
@@ -11,6 +11,8 @@ pub const LOCAL_TOKEN: &str = "local";
 
 /// Maximum size of the request log before it is truncated, in bytes.
 const REQUEST_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+/// Items requested per page from paginated list endpoints
+const LIST_PAGE_SIZE: i64 = 100;
 
 /// Append a request entry to ~/.infraweave/request-log. On non-2xx responses
 /// also echo the trace id to stderr so failures are immediately visible
@@ -436,22 +438,54 @@ pub async fn http_post(path: &str, body: &Value) -> Result<Value> {
         .context("Failed to parse JSON response")
 }
 
+/// Get the items of all pages of a paginated list endpoint, following `next_token` until the last page
+async fn http_get_all_pages(path: &str) -> Result<Vec<Value>> {
+    let mut items = Vec::new();
+    let mut next_token: Option<String> = None;
+    loop {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        let mut page_path = format!("{}{}limit={}", path, separator, LIST_PAGE_SIZE);
+        if let Some(token) = &next_token {
+            // Tokens are standard base64, where these are the only characters to escape
+            let token = token
+                .replace('+', "%2B")
+                .replace('/', "%2F")
+                .replace('=', "%3D");
+            page_path.push_str(&format!("&next_token={}", token));
+        }
+
+        let response = http_get(&page_path).await?;
+        // Servers without pagination return every item as a plain array
+        if let Some(all_items) = response.as_array() {
+            return Ok(all_items.clone());
+        }
+        let page = response
+            .get("items")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| anyhow!("Expected paginated response from {}", path))?;
+        items.extend(page.iter().cloned());
+
+        next_token = response
+            .get("next_token")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        if next_token.is_none() {
+            return Ok(items);
+        }
+    }
+}
+
 /// Get all latest modules via HTTP API
 pub async fn http_get_all_latest_modules(_track: &str) -> Result<Vec<ModuleResp>> {
     // Note: The HTTP API doesn't filter by track, it returns all latest modules
     // Track filtering happens client-side
     let path = "/api/v1/modules";
-    let response = http_get(path).await?;
+    let items = http_get_all_pages(path).await?;
 
-    // Parse the response - it should be an array of modules
-    if let Some(items) = response.as_array() {
-        items
-            .iter()
-            .map(|item| serde_json::from_value(item.clone()).context("Failed to parse module"))
-            .collect()
-    } else {
-        Err(anyhow!("Expected array response from modules endpoint"))
-    }
+    items
+        .into_iter()
+        .map(|item| serde_json::from_value(item).context("Failed to parse module"))
+        .collect()
 }
 
 /// Get all latest stacks via HTTP API
@@ -592,13 +626,7 @@ pub async fn http_get_policy_version(
 /// Get deployments for a project/region via HTTP API
 pub async fn http_get_deployments(project: &str, region: &str) -> Result<Vec<Value>> {
     let path = format!("/api/v1/deployments/{}/{}", project, region);
-    let response = http_get(&path).await?;
-
-    if let Some(items) = response.as_array() {
-        Ok(items.clone())
-    } else {
-        Err(anyhow!("Expected array response from deployments endpoint"))
-    }
+    http_get_all_pages(&path).await
 }
 
 /// Get a specific deployment via HTTP API
@@ -627,6 +655,65 @@ pub async fn http_describe_deployment(
     }
 }
 
+/// Get the terraform outputs of a deployment via HTTP API, `unmask` requires the unmask outputs permission
+pub async fn http_get_deployment_outputs(
+    project: &str,
+    region: &str,
+    environment: &str,
+    deployment_id: &str,
+    unmask: bool,
+) -> Result<Value> {
+    let mut path = format!(
+        "/api/v1/deployment/{}/{}/{}/{}/outputs",
+        project, region, environment, deployment_id
+    );
+    if unmask {
+        path.push_str("?unmask=true");
+    }
+    http_get(&path).await
+}
+
+/// Get the jobs pending approval via HTTP API, optionally limited to one environment
+pub async fn http_get_pending_approvals(
+    project: &str,
+    region: &str,
+    environment: Option<&str>,
+) -> Result<Value> {
+    let mut path = format!("/api/v1/approvals/{}/{}", project, region);
+    if let Some(environment) = environment {
+        path.push_str(&format!("?environment={}", environment));
+    }
+    http_get(&path).await
+}
+
+/// Get the running and queued jobs of a project via HTTP API, queued jobs in the order they start
+pub async fn http_get_job_queue(project: &str, region: &str) -> Result<Value> {
+    http_get(&format!("/api/v1/job_queue/{}/{}", project, region)).await
+}
+
+/// Approve or reject the job of a deployment that is pending approval via HTTP API
+pub async fn http_decide_approval(
+    project: &str,
+    region: &str,
+    environment: &str,
+    deployment_id: &str,
+    comment: &str,
+    approve: bool,
+) -> Result<Value> {
+    let path = format!(
+        "/api/v1/approvals/{}/{}/{}",
+        project,
+        region,
+        if approve { "approve" } else { "reject" }
+    );
+    let body = serde_json::json!({
+        "environment": environment,
+        "deployment_id": deployment_id,
+        "comment": comment,
+    });
+    http_post(&path, &body).await
+}
+
 pub async fn http_get_plan_deployment(
     project: &str,
     region: &str,
@@ -694,13 +781,54 @@ pub async fn http_get_change_record(
         job_id,
         change_type
     );
-    let path = format!(
-        "/api/v1/change_record/{}/{}/{}/{}/{}/{}",
-        project, region, environment, deployment_id, job_id, change_type
+    let path = change_record_path(
+        project,
+        region,
+        environment,
+        deployment_id,
+        job_id,
+        change_type,
     );
     http_get(&path).await
 }
 
+/// Link to a change record on the API, just its path when no API endpoint is configured
+pub fn change_record_url(
+    project: &str,
+    region: &str,
+    environment: &str,
+    deployment_id: &str,
+    job_id: &str,
+    change_type: &str,
+) -> String {
+    let path = change_record_path(
+        project,
+        region,
+        environment,
+        deployment_id,
+        job_id,
+        change_type,
+    );
+    match get_api_endpoint() {
+        Ok(endpoint) => format!("{}{}", endpoint.trim_end_matches('/'), path),
+        Err(_) => path,
+    }
+}
+
+fn change_record_path(
+    project: &str,
+    region: &str,
+    environment: &str,
+    deployment_id: &str,
+    job_id: &str,
+    change_type: &str,
+) -> String {
+    format!(
+        "/api/v1/change_record/{}/{}/{}/{}/{}/{}",
+        project, region, environment, deployment_id, job_id, change_type
+    )
+}
+
 pub async fn http_get_job_status(project: &str, region: &str, job_id: &str) -> Result<Value> {
     let path = format!("/api/v1/job_status/{}/{}/{}", project, region, job_id);
     http_get(&path).await
@@ -784,6 +912,28 @@ pub async fn http_deprecate_module(
         .context("Failed to parse JSON response")
 }
 
+/// Yank a module version, or lift its yank with `undo` in the body, via HTTP API
+pub async fn http_yank_module(
+    track: &str,
+    module: &str,
+    version: &str,
+    body: &Value,
+) -> Result<Value> {
+    let path = format!("/api/v1/module/{}/{}/{}/yank", track, module, version);
+    http_post(&path, body).await
+}
+
+/// Yank a stack version, or lift its yank with `undo` in the body, via HTTP API
+pub async fn http_yank_stack(
+    track: &str,
+    stack: &str,
+    version: &str,
+    body: &Value,
+) -> Result<Value> {
+    let path = format!("/api/v1/stack/{}/{}/{}/yank", track, stack, version);
+    http_post(&path, body).await
+}
+
 /// Publish a provider via HTTP API
 pub async fn http_publish_provider(zip_base64: &str, provider_json: &Value) -> Result<Value> {
     let path = "/api/v1/provider/publish";
@@ -2,15 +2,16 @@ mod client;
 pub mod http_auth;
 
 pub use client::{
-    get_token_identity, http_check_deployment_progress, http_deprecate_module,
-    http_deprecate_stack, http_describe_deployment, http_download_provider,
+    change_record_url, get_token_identity, http_check_deployment_progress, http_decide_approval,
+    http_deprecate_module, http_deprecate_stack, http_describe_deployment, http_download_provider,
     http_get_all_latest_modules, http_get_all_latest_providers, http_get_all_latest_stacks,
     http_get_all_projects, http_get_all_versions_for_module, http_get_all_versions_for_stack,
-    http_get_change_record, http_get_deployments, http_get_events, http_get_job_status,
-    http_get_latest_module_version, http_get_latest_provider_version,
-    http_get_latest_stack_version, http_get_logs, http_get_module_version,
-    http_get_plan_deployment, http_get_policies, http_get_policy_version, http_get_stack_version,
+    http_get_change_record, http_get_deployment_outputs, http_get_deployments, http_get_events,
+    http_get_job_queue, http_get_job_status, http_get_latest_module_version,
+    http_get_latest_provider_version, http_get_latest_stack_version, http_get_logs,
+    http_get_module_version, http_get_pending_approvals, http_get_plan_deployment,
+    http_get_policies, http_get_policy_version, http_get_stack_version,
     http_is_deployment_plan_in_progress, http_post, http_publish_module, http_publish_provider,
-    http_publish_stack, http_submit_claim_job, is_http_mode_enabled, is_not_found_error,
-    LOCAL_TOKEN,
+    http_publish_stack, http_submit_claim_job, http_yank_module, http_yank_stack,
+    is_http_mode_enabled, is_not_found_error, LOCAL_TOKEN,
 };
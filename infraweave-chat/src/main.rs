@@ -22,7 +22,13 @@ async fn main() -> Result<()> {
         .unwrap_or(8090);
 
     let llm = build_llm().await?;
-    let tools = Arc::new(infraweave_tools::registry());
+    // The chat has no confirmation step, so it only gets the read-only tools
+    let tools = Arc::new(
+        infraweave_tools::registry()
+            .into_iter()
+            .filter(|t| t.def().effect == infraweave_tools::ToolEffect::ReadOnly)
+            .collect::<Vec<_>>(),
+    );
 
     let state = handler::AppState {
         llm,
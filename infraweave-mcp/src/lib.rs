@@ -10,13 +10,14 @@
 //! ```
 
 use anyhow::{anyhow, Context, Result};
-use infraweave_tools::{registry, ApiClient, Tool, ToolContext};
+use infraweave_tools::{registry, ApiClient, Tool, ToolContext, ToolEffect};
 use rmcp::{
     handler::server::ServerHandler,
     model::{
         CallToolRequestParams, CallToolResult, Content, Implementation, InitializeRequestParams,
-        InitializeResult, ListToolsResult, PaginatedRequestParams, ProtocolVersion,
-        ServerCapabilities, ServerInfo, Tool as McpTool, ToolAnnotations, ToolsCapability,
+        InitializeResult, ListToolsResult, PaginatedRequestParams, ProgressNotificationParam,
+        ProtocolVersion, ServerCapabilities, ServerInfo, Tool as McpTool, ToolAnnotations,
+        ToolsCapability,
     },
     service::{RequestContext, RoleServer},
     ErrorData as McpError, ServiceExt,
@@ -32,7 +33,7 @@ pub async fn run() -> Result<()> {
 
     let api = ApiClient::new(endpoint, token).context("could not build API client")?;
 
-    let mut tool_ctx = ToolContext::new(api);
+    let mut tool_ctx = ToolContext::new(api).with_initiated_by("infraweave-mcp");
     if let Ok(p) = std::env::var("INFRAWEAVE_DEFAULT_PROJECT") {
         tool_ctx = tool_ctx.with_project(p);
     }
@@ -129,8 +130,9 @@ impl ServerHandler for InfraWeaveServer {
             )
             .with_instructions(
                 "Curated tools for inspecting InfraWeave modules, stacks, deployments, and \
-                 debugging deployment failures. Pass `project`, `region`, `environment`, or \
-                 `track` arguments where required.",
+                 debugging deployment failures, and for planning, applying and destroying \
+                 deployments. Pass `project`, `region`, `environment`, or `track` arguments \
+                 where required. Confirm with the user before applying or destroying.",
             )
     }
 
@@ -158,11 +160,8 @@ impl ServerHandler for InfraWeaveServer {
                 // ToolDef.input_schema is a JSON object (`{"type":"object", ...}`).
                 // rmcp wants it as a JsonObject (serde_json::Map<String, Value>).
                 let schema = def.input_schema.as_object().cloned().unwrap_or_default();
-                McpTool::new(def.name, def.description, schema).with_annotations(
-                    // All current tools are read-only inspections - flag that so
-                    // clients can surface them without confirmation prompts.
-                    ToolAnnotations::new().read_only(true),
-                )
+                McpTool::new(def.name, def.description, schema)
+                    .with_annotations(tool_annotations(def.effect))
             })
             .collect();
         Ok(ListToolsResult::with_all_items(tools))
@@ -171,7 +170,7 @@ impl ServerHandler for InfraWeaveServer {
     async fn call_tool(
         &self,
         request: CallToolRequestParams,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
         let Some(tool) = self
             .tools
@@ -189,9 +188,40 @@ impl ServerHandler for InfraWeaveServer {
             .map(Value::Object)
             .unwrap_or(Value::Object(Default::default()));
 
-        match tool.execute(&self.tool_ctx, args).await {
+        // Forward the progress of long-running tools (e.g. job status changes) as
+        // MCP progress notifications when the client asked for them
+        let mut tool_ctx = self.tool_ctx.clone();
+        if let Some(progress_token) = context.meta.get_progress_token() {
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+            let peer = context.peer.clone();
+            tokio::spawn(async move {
+                let mut progress = 0.0;
+                while let Some(message) = rx.recv().await {
+                    progress += 1.0;
+                    let param = ProgressNotificationParam::new(progress_token.clone(), progress)
+                        .with_message(message);
+                    if let Err(e) = peer.notify_progress(param).await {
+                        eprintln!("[MCP] failed to send progress: {e}");
+                    }
+                }
+            });
+            tool_ctx = tool_ctx.with_progress(move |message| {
+                let _ = tx.send(message.to_string());
+            });
+        }
+
+        match tool.execute(&tool_ctx, args).await {
             Ok(text) => Ok(CallToolResult::success(vec![Content::text(text)])),
             Err(e) => Ok(CallToolResult::error(vec![Content::text(format!("{e:#}"))])),
         }
     }
 }
+
+/// Hints for clients, which prompt for confirmation before tools that aren't read-only.
+fn tool_annotations(effect: ToolEffect) -> ToolAnnotations {
+    match effect {
+        ToolEffect::ReadOnly => ToolAnnotations::new().read_only(true),
+        ToolEffect::Mutating => ToolAnnotations::new().read_only(false).destructive(false),
+        ToolEffect::Destructive => ToolAnnotations::new().read_only(false).destructive(true),
+    }
+}
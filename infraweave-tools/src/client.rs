@@ -48,6 +48,26 @@ impl ApiClient {
         resp.json().await.context("invalid JSON response")
     }
 
+    pub async fn post_json(&self, path: &str, body: &Value) -> Result<Value> {
+        let url = format!("{}{}", self.endpoint, path);
+        let resp = self
+            .http
+            .post(&url)
+            .bearer_auth(&self.token)
+            .header("Accept", "application/json")
+            .json(body)
+            .send()
+            .await
+            .with_context(|| format!("POST {url} failed"))?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(anyhow!("POST {url} -> {status}: {body}"));
+        }
+        resp.json().await.context("invalid JSON response")
+    }
+
     pub async fn get_bytes(&self, path: &str) -> Result<Vec<u8>> {
         let url = format!("{}{}", self.endpoint, path);
         let resp = self
@@ -1,5 +1,11 @@
+use std::sync::Arc;
+
 use crate::ApiClient;
 
+/// Receives progress messages from long-running tools, e.g. the status changes
+/// of a job, so the caller can stream them to the user as they happen.
+pub type ProgressSink = Arc<dyn Fn(&str) + Send + Sync>;
+
 /// Per-request context handed to every tool invocation.
 ///
 /// `default_*` fields let the chat layer inject sensible defaults so the LLM
@@ -12,6 +18,9 @@ pub struct ToolContext {
     pub default_region: Option<String>,
     pub default_environment: Option<String>,
     pub default_track: Option<String>,
+    /// Recorded as `initiated_by` on the jobs tools start.
+    pub initiated_by: String,
+    pub progress: Option<ProgressSink>,
 }
 
 impl ToolContext {
@@ -22,6 +31,8 @@ impl ToolContext {
             default_region: None,
             default_environment: None,
             default_track: None,
+            initiated_by: "infraweave-tools".to_string(),
+            progress: None,
         }
     }
 
@@ -44,4 +55,21 @@ impl ToolContext {
         self.default_track = Some(track.into());
         self
     }
+
+    pub fn with_initiated_by(mut self, initiated_by: impl Into<String>) -> Self {
+        self.initiated_by = initiated_by.into();
+        self
+    }
+
+    pub fn with_progress(mut self, progress: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Sends a progress message to the sink, if the caller set one.
+    pub fn report_progress(&self, message: &str) {
+        if let Some(progress) = &self.progress {
+            progress(message);
+        }
+    }
 }
@@ -14,6 +14,6 @@ mod tool;
 mod tools;
 
 pub use client::ApiClient;
-pub use context::ToolContext;
-pub use tool::{Tool, ToolDef};
+pub use context::{ProgressSink, ToolContext};
+pub use tool::{Tool, ToolDef, ToolEffect};
 pub use tools::registry;
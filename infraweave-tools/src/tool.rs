@@ -13,6 +13,19 @@ pub struct ToolDef {
     pub name: &'static str,
     pub description: &'static str,
     pub input_schema: Value,
+    pub effect: ToolEffect,
+}
+
+/// What calling a tool does to the platform. Clients use it to decide whether
+/// to ask the user for confirmation before running the tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolEffect {
+    /// Only reads, safe to run without asking.
+    ReadOnly,
+    /// Starts jobs, but leaves the infrastructure unchanged (e.g. a plan).
+    Mutating,
+    /// Can change or delete infrastructure (e.g. apply, destroy).
+    Destructive,
 }
 
 /// A single curated tool. `execute` returns a markdown string - narrative,
@@ -5,7 +5,7 @@ use serde_json::{json, Value};
 use std::fmt::Write;
 
 use super::common::{environment, opt_str, project, region, validate_project_region};
-use crate::{Tool, ToolContext, ToolDef};
+use crate::{Tool, ToolContext, ToolDef, ToolEffect};
 
 pub struct ListDeployments;
 
@@ -32,6 +32,7 @@ impl Tool for ListDeployments {
                     "failures_only": { "type": "boolean", "description": "Optional: only return deployments in a failure state." }
                 }
             }),
+            effect: ToolEffect::ReadOnly,
         }
     }
 
@@ -123,6 +124,7 @@ impl Tool for DebugDeployment {
                 },
                 "required": ["deployment_id", "environment_id"]
             }),
+            effect: ToolEffect::ReadOnly,
         }
     }
 
@@ -0,0 +1,298 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use env_defs::{
+    pretty_print_resource_changes, ApiInfraPayload, ApiInfraPayloadWithVariables, DeploymentResp,
+    DeploymentStatus, EventData, ExtraData, InfraChangeRecord,
+};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use super::common::{environment, opt_str, project, region, validate_project_region};
+use crate::{Tool, ToolContext, ToolDef, ToolEffect};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_WAIT: Duration = Duration::from_secs(30 * 60);
+
+pub struct PlanDeployment;
+pub struct ApplyDeployment;
+pub struct DestroyDeployment;
+
+fn job_input_schema(with_variables: bool) -> Value {
+    let mut schema = json!({
+        "type": "object",
+        "properties": {
+            "deployment_id": { "type": "string" },
+            "environment_id": {
+                "type": "string",
+                "description": "Exact InfraWeave environment_id from a previous deployment result. Do not use a display name or conversational alias."
+            },
+            "project_id": {
+                "type": "string",
+                "description": "Exact InfraWeave project_id from list_projects or a previous deployment result. Do not use the project display name or account alias."
+            },
+            "region": {
+                "type": "string",
+                "description": "Exact configured cloud provider region id for the project. For AWS, use values like us-west-2 or eu-central-1; never broad aliases like us, eu, west, or production."
+            },
+            "wait": {
+                "type": "boolean",
+                "description": "Optional: wait for the job to finish and report its progress (default true). When false, only the job_id is returned."
+            }
+        },
+        "required": ["deployment_id", "environment_id"]
+    });
+    if with_variables {
+        schema["properties"]["variables"] = json!({
+            "type": "object",
+            "description": "Optional: variable values to change, keyed by the variable names of the deployment. Variables left out keep their current values."
+        });
+    }
+    schema
+}
+
+#[async_trait]
+impl Tool for PlanDeployment {
+    fn def(&self) -> ToolDef {
+        ToolDef {
+            name: "plan_deployment",
+            description: "Start a plan job for an existing deployment, optionally with changed \
+                variables, and report the resources it would create, change or destroy. \
+                Nothing is changed. Use this before apply_deployment to show the user the impact.",
+            input_schema: job_input_schema(true),
+            effect: ToolEffect::Mutating,
+        }
+    }
+
+    async fn execute(&self, ctx: &ToolContext, args: Value) -> Result<String> {
+        run_job(ctx, &args, "plan").await
+    }
+}
+
+#[async_trait]
+impl Tool for ApplyDeployment {
+    fn def(&self) -> ToolDef {
+        ToolDef {
+            name: "apply_deployment",
+            description: "Apply an existing deployment, optionally with changed variables. This \
+                changes real infrastructure: only call it after the user explicitly confirmed, \
+                ideally after showing them the result of plan_deployment.",
+            input_schema: job_input_schema(true),
+            effect: ToolEffect::Destructive,
+        }
+    }
+
+    async fn execute(&self, ctx: &ToolContext, args: Value) -> Result<String> {
+        run_job(ctx, &args, "apply").await
+    }
+}
+
+#[async_trait]
+impl Tool for DestroyDeployment {
+    fn def(&self) -> ToolDef {
+        ToolDef {
+            name: "destroy_deployment",
+            description: "Destroy all resources of a deployment. This deletes real \
+                infrastructure: only call it after the user explicitly confirmed the exact \
+                deployment_id and environment_id.",
+            input_schema: job_input_schema(false),
+            effect: ToolEffect::Destructive,
+        }
+    }
+
+    async fn execute(&self, ctx: &ToolContext, args: Value) -> Result<String> {
+        run_job(ctx, &args, "destroy").await
+    }
+}
+
+/// Submits `command` for the deployment with its current module version and variables, then
+/// follows the events of the job until it finishes, reporting each status change as progress
+async fn run_job(ctx: &ToolContext, args: &Value, command: &str) -> Result<String> {
+    let deployment_id = opt_str(args, "deployment_id").context("`deployment_id` is required")?;
+    let environment = environment(args, ctx)?;
+    let project = project(args, ctx)?;
+    let region = region(args, ctx)?;
+    validate_project_region(ctx, &project, &region).await?;
+    let wait = args.get("wait").and_then(|v| v.as_bool()).unwrap_or(true);
+
+    let dep_path = format!("/api/v1/deployment/{project}/{region}/{environment}/{deployment_id}");
+    let Some(dep_value) = ctx.api.get_optional(&dep_path).await? else {
+        return Ok(format!(
+            "No deployment `{deployment_id}` found in `{project}` / `{region}` / `{environment}`. \
+             Only existing deployments can be planned, applied or destroyed."
+        ));
+    };
+    let deployment: DeploymentResp =
+        serde_json::from_value(dep_value).context("could not parse deployment")?;
+    if deployment.status.is_busy() {
+        return Ok(format!(
+            "Deployment `{deployment_id}` has a job in progress (`{}`, status **{}**). Wait for it \
+             to finish before starting another one.",
+            deployment.job_id, deployment.status
+        ));
+    }
+
+    let variables = merge_variables(deployment.variables.clone(), args.get("variables"))?;
+    let submission = ApiInfraPayloadWithVariables {
+        payload: job_payload(&deployment, command, &ctx.initiated_by),
+        variables,
+    };
+    let resp = ctx
+        .api
+        .post_json("/api/v1/claim/run", &serde_json::to_value(&submission)?)
+        .await?;
+    let job_id = resp
+        .get("job_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow!("no job_id in response"))?
+        .to_string();
+
+    if resp.get("status").and_then(|v| v.as_str())
+        == Some(DeploymentStatus::PendingApproval.to_string().as_str())
+    {
+        return Ok(format!(
+            "The {command} job `{job_id}` of `{deployment_id}` is **pending approval**, as \
+             required for `{environment}`. It starts once approvers sign off with \
+             `infraweave approvals approve {environment} {deployment_id}`."
+        ));
+    }
+    ctx.report_progress(&format!(
+        "Started {command} job {job_id} for {deployment_id}"
+    ));
+    if !wait {
+        return Ok(format!(
+            "Started {command} job `{job_id}` for `{deployment_id}`. Use debug_deployment to \
+             follow it."
+        ));
+    }
+
+    let events_path = format!("/api/v1/events/{project}/{region}/{environment}/{deployment_id}");
+    let started = Instant::now();
+    let mut seen = HashSet::new();
+    let mut out = format!("## {command} job `{job_id}` for `{deployment_id}`\n\n");
+    let final_event = loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let mut events: Vec<EventData> = ctx
+            .api
+            .get_json(&events_path)
+            .await
+            .ok()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default();
+        events.retain(|e| e.job_id == job_id);
+        events.sort_by_key(|e| e.epoch);
+
+        let mut finished = None;
+        for event in events {
+            if seen.insert(event.id.clone()) {
+                let line = format!("{} - {} ({})", event.timestamp, event.status, event.event);
+                ctx.report_progress(&line);
+                let _ = writeln!(out, "- {line}");
+            }
+            if event.status.is_final() {
+                finished = Some(event);
+            }
+        }
+        if let Some(event) = finished {
+            break event;
+        }
+        if started.elapsed() > MAX_WAIT {
+            let _ = writeln!(
+                out,
+                "\nThe job is still running after {} minutes, use debug_deployment to follow it.",
+                MAX_WAIT.as_secs() / 60
+            );
+            return Ok(out);
+        }
+    };
+
+    let _ = writeln!(out, "\n**Result:** {}", final_event.status);
+    if !final_event.error_text.is_empty() {
+        let _ = writeln!(out, "\n### Error\n```\n{}\n```", final_event.error_text);
+    }
+    let change_type = if command == "plan" { "PLAN" } else { "MUTATE" };
+    let record_path = format!(
+        "/api/v1/change_record/{project}/{region}/{environment}/{deployment_id}/{job_id}/{change_type}"
+    );
+    if let Some(record) = ctx
+        .api
+        .get_optional(&record_path)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|v| serde_json::from_value::<InfraChangeRecord>(v).ok())
+    {
+        let _ = writeln!(
+            out,
+            "\n### Resource changes\n```\n{}\n```",
+            pretty_print_resource_changes(&record.resource_changes)
+        );
+    }
+    Ok(out)
+}
+
+/// Payload rerunning the deployment as it is, like a dependent deployment triggered by an
+/// upstream change
+fn job_payload(deployment: &DeploymentResp, command: &str, initiated_by: &str) -> ApiInfraPayload {
+    ApiInfraPayload {
+        command: command.to_string(),
+        flags: vec![],
+        module: deployment.module.to_lowercase(),
+        module_version: deployment.module_version.clone(),
+        module_type: deployment.module_type.clone(),
+        module_track: deployment.module_track.clone(),
+        name: String::new(),
+        environment: deployment.environment.clone(),
+        deployment_id: deployment.deployment_id.clone(),
+        project_id: deployment.project_id.clone(),
+        region: deployment.region.clone(),
+        drift_detection: deployment.drift_detection.clone(),
+        next_drift_check_epoch: -1,
+        annotations: json!({}),
+        dependencies: deployment.dependencies.clone(),
+        initiated_by: initiated_by.to_string(),
+        cpu: deployment.cpu.clone(),
+        memory: deployment.memory.clone(),
+        reference: deployment.reference.clone(),
+        extra_data: ExtraData::None,
+        trigger_reason: None,
+        plan_job_id: None,
+        targets: vec![],
+        idempotency_key: None,
+        approved_by: vec![],
+    }
+}
+
+fn merge_variables(mut variables: Value, changes: Option<&Value>) -> Result<Value> {
+    let Some(changes) = changes.filter(|c| !c.is_null()) else {
+        return Ok(variables);
+    };
+    let changes = changes
+        .as_object()
+        .ok_or_else(|| anyhow!("`variables` must be an object of variable names and values"))?;
+    if !variables.is_object() {
+        variables = json!({});
+    }
+    for (name, value) in changes {
+        variables[name] = value.clone();
+    }
+    Ok(variables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::merge_variables;
+    use serde_json::json;
+
+    #[test]
+    fn merge_variables_overrides_only_given_variables() {
+        let current = json!({ "bucket_name": "logs", "tags": { "team": "a" } });
+
+        let merged = merge_variables(current.clone(), Some(&json!({ "tags": {} }))).unwrap();
+
+        assert_eq!(merged, json!({ "bucket_name": "logs", "tags": {} }));
+        assert_eq!(merge_variables(current.clone(), None).unwrap(), current);
+        assert!(merge_variables(current, Some(&json!(["tags"]))).is_err());
+    }
+}
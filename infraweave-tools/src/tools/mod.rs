@@ -3,6 +3,7 @@ use crate::Tool;
 mod archive_diff;
 mod common;
 mod deployments;
+mod jobs;
 mod modules;
 mod projects;
 mod stacks;
@@ -19,6 +20,9 @@ pub fn registry() -> Vec<Box<dyn Tool>> {
         Box::new(stacks::DiffStackVersions),
         Box::new(deployments::ListDeployments),
         Box::new(deployments::DebugDeployment),
+        Box::new(jobs::PlanDeployment),
+        Box::new(jobs::ApplyDeployment),
+        Box::new(jobs::DestroyDeployment),
         Box::new(projects::ListProjects),
     ]
 }
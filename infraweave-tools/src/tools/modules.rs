@@ -6,7 +6,7 @@ use std::fmt::Write;
 
 use super::archive_diff;
 use super::common::{latest_by_semver, opt_str, track};
-use crate::{Tool, ToolContext, ToolDef};
+use crate::{Tool, ToolContext, ToolDef, ToolEffect};
 
 pub struct ListModules;
 
@@ -25,6 +25,7 @@ impl Tool for ListModules {
                     "search": { "type": "string", "description": "Optional case-insensitive substring to filter module names by." }
                 }
             }),
+            effect: ToolEffect::ReadOnly,
         }
     }
 
@@ -96,6 +97,7 @@ impl Tool for DescribeModule {
                 },
                 "required": ["module"]
             }),
+            effect: ToolEffect::ReadOnly,
         }
     }
 
@@ -149,6 +151,7 @@ impl Tool for DiffModuleVersions {
                 },
                 "required": ["module", "previous_version", "version"]
             }),
+            effect: ToolEffect::ReadOnly,
         }
     }
 
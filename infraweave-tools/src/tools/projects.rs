@@ -4,7 +4,7 @@ use env_defs::ProjectData;
 use serde_json::{json, Value};
 use std::fmt::Write;
 
-use crate::{Tool, ToolContext, ToolDef};
+use crate::{Tool, ToolContext, ToolDef, ToolEffect};
 
 pub struct ListProjects;
 
@@ -17,6 +17,7 @@ impl Tool for ListProjects {
                 Use this when the user asks 'what projects do I have' or needs to disambiguate \
                 a project before another tool can run.",
             input_schema: json!({ "type": "object", "properties": {} }),
+            effect: ToolEffect::ReadOnly,
         }
     }
 
@@ -6,7 +6,7 @@ use std::fmt::Write;
 
 use super::archive_diff;
 use super::common::{latest_by_semver, opt_str, track};
-use crate::{Tool, ToolContext, ToolDef};
+use crate::{Tool, ToolContext, ToolDef, ToolEffect};
 
 pub struct ListStacks;
 
@@ -24,6 +24,7 @@ impl Tool for ListStacks {
                     "search": { "type": "string" }
                 }
             }),
+            effect: ToolEffect::ReadOnly,
         }
     }
 
@@ -92,6 +93,7 @@ impl Tool for DescribeStack {
                 },
                 "required": ["stack"]
             }),
+            effect: ToolEffect::ReadOnly,
         }
     }
 
@@ -205,6 +207,7 @@ impl Tool for DiffStackVersions {
                 },
                 "required": ["stack", "previous_version", "version"]
             }),
+            effect: ToolEffect::ReadOnly,
         }
     }
 
@@ -10,9 +10,17 @@ use env_defs::{
 };
 use log::info;
 use pyo3::Bound;
-use pyo3::{create_exception, exceptions::PyException, prelude::*, types::PyDict};
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyTimeoutError},
+    prelude::*,
+    types::PyDict,
+};
 use serde_json::Value;
-use std::{thread, time::Duration};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
 use tokio::runtime::Runtime;
 
 // Define a Python exception for deployment failures
@@ -326,6 +334,8 @@ pub struct Deployment {
     last_deployment: Option<DeploymentResp>,
     /// Whether the last operation resulted in an error
     has_error: bool,
+    /// Job submitted with `wait=False` that has not been waited for yet (job_id, command)
+    pending_job: Option<(String, String)>,
 }
 
 #[pymethods]
@@ -371,6 +381,7 @@ impl Deployment {
                     reference,
                     last_deployment: None,
                     has_error: false,
+                    pending_job: None,
                 })
             }
             (None, Some(stack)) => {
@@ -386,6 +397,7 @@ impl Deployment {
                     reference,
                     last_deployment: None,
                     has_error: false,
+                    pending_job: None,
                 })
             }
         }
@@ -513,12 +525,24 @@ impl Deployment {
     ///
     /// Returns a DeploymentResult containing the job ID and changes on success,
     /// or raises DeploymentFailure on error.
-    fn apply(&mut self) -> PyResult<DeploymentResult> {
+    ///
+    /// If `wait` is False, the job is only submitted and the result is returned
+    /// immediately; use `wait_for_completion()` or `stream_logs()` to follow it.
+    #[pyo3(signature = (wait=true))]
+    fn apply(&mut self, wait: bool) -> PyResult<DeploymentResult> {
         println!(
             "Applying {} in namespace {} ({})",
             self.name, self.namespace, self.region
         );
         let rt = Runtime::new().unwrap();
+        if !wait {
+            let job_id = self.submit("apply", &rt)?;
+            return Ok(DeploymentResult {
+                job_id: job_id.clone(),
+                command: "apply".to_string(),
+                base: self.result_base(job_id),
+            });
+        }
         let (job_id, status, deployment) = match rt.block_on(run_job("apply", self)) {
             Ok((job_id, status, deployment)) => (job_id, status, deployment),
             Err(e) => {
@@ -558,12 +582,23 @@ impl Deployment {
     ///
     /// Returns a PlanResult containing the job ID and methods to analyze the plan on success,
     /// or raises DeploymentFailure on error.
-    fn plan(&self) -> PyResult<PlanResult> {
+    ///
+    /// If `wait` is False, the job is only submitted and the result is returned
+    /// immediately; use `wait_for_completion()` or `stream_logs()` to follow it.
+    #[pyo3(signature = (wait=true))]
+    fn plan(&mut self, wait: bool) -> PyResult<PlanResult> {
         println!(
             "Planning {} in namespace {} ({})",
             self.name, self.namespace, self.region
         );
         let rt = Runtime::new().unwrap();
+        if !wait {
+            let job_id = self.submit("plan", &rt)?;
+            return Ok(PlanResult {
+                job_id: job_id.clone(),
+                base: self.result_base(job_id),
+            });
+        }
         let (job_id, status, deployment) = match rt.block_on(run_job("plan", self)) {
             Ok((job_id, status, deployment)) => (job_id, status, deployment),
             Err(e) => {
@@ -597,12 +632,19 @@ impl Deployment {
     /// Destroys the deployment, tearing down infrastructure.
     ///
     /// Returns the job ID string on success, or raises DeploymentFailure on error.
-    fn destroy(&mut self) -> PyResult<String> {
+    ///
+    /// If `wait` is False, the job is only submitted and its job ID is returned
+    /// immediately; use `wait_for_completion()` or `stream_logs()` to follow it.
+    #[pyo3(signature = (wait=true))]
+    fn destroy(&mut self, wait: bool) -> PyResult<String> {
         println!(
             "Destroying {} in namespace {} ({})",
             self.name, self.namespace, self.region
         );
         let rt = Runtime::new().unwrap();
+        if !wait {
+            return self.submit("destroy", &rt);
+        }
         let (job_id, status, deployment) = match rt.block_on(run_job("destroy", self)) {
             Ok((job_id, status, deployment)) => (job_id, status, deployment),
             Err(e) => {
@@ -627,6 +669,60 @@ impl Deployment {
         Ok((job_id).to_string())
     }
 
+    /// Blocks until the job submitted with `wait=False` has finished.
+    ///
+    /// # Arguments
+    /// * `timeout` - Optional maximum number of seconds to wait
+    /// * `poll_interval` - Seconds between status checks (default 10)
+    ///
+    /// Returns the final job status, raises DeploymentFailure if the job did not
+    /// succeed, or TimeoutError if `timeout` elapses first.
+    ///
+    /// # Example
+    /// ```python
+    /// result = bucket1.apply(wait=False)
+    /// print(f"Submitted job {result.job_id}")
+    /// bucket1.wait_for_completion(timeout=1800)
+    /// print(bucket1.outputs)
+    /// ```
+    #[pyo3(signature = (timeout=None, poll_interval=10))]
+    fn wait_for_completion(
+        &mut self,
+        py: Python,
+        timeout: Option<u64>,
+        poll_interval: u64,
+    ) -> PyResult<String> {
+        self.follow_pending_job(py, None, timeout, poll_interval)
+    }
+
+    /// Streams the log lines of the job submitted with `wait=False` until it finishes.
+    ///
+    /// `callback` is called with each new log line (str) as it becomes available.
+    ///
+    /// # Arguments
+    /// * `callback` - Callable receiving one log line at a time
+    /// * `timeout` - Optional maximum number of seconds to wait
+    /// * `poll_interval` - Seconds between log/status checks (default 5)
+    ///
+    /// Returns the final job status, raises DeploymentFailure if the job did not
+    /// succeed, or TimeoutError if `timeout` elapses first.
+    ///
+    /// # Example
+    /// ```python
+    /// bucket1.apply(wait=False)
+    /// bucket1.stream_logs(print)
+    /// ```
+    #[pyo3(signature = (callback, timeout=None, poll_interval=5))]
+    fn stream_logs(
+        &mut self,
+        py: Python,
+        callback: PyObject,
+        timeout: Option<u64>,
+        poll_interval: u64,
+    ) -> PyResult<String> {
+        self.follow_pending_job(py, Some(callback), timeout, poll_interval)
+    }
+
     /// Retrieves the outputs from the last deployment as a Python object.
     ///
     /// ## Example
@@ -683,9 +779,11 @@ impl Deployment {
         _exc_value: Option<PyObject>,
         _traceback: Option<PyObject>,
     ) -> PyResult<bool> {
-        // If a deployment was run or an error occurred, destroy it
-        if slf.last_deployment.is_some() || slf.has_error {
-            if let Err(e) = slf.destroy() {
+        // If a deployment was run or an error occurred, destroy it. A pending plan or destroy
+        // leaves nothing behind, only a pending apply may have created resources
+        let pending_apply = matches!(&slf.pending_job, Some((_, command)) if command == "apply");
+        if slf.last_deployment.is_some() || slf.has_error || pending_apply {
+            if let Err(e) = slf.destroy(true) {
                 eprintln!("Automatic {}.destroy() failed: {}", slf.name, e);
             }
         }
@@ -693,6 +791,116 @@ impl Deployment {
     }
 }
 
+impl Deployment {
+    fn result_base(&self, job_id: String) -> ResultBase {
+        ResultBase {
+            job_id,
+            environment_id: self.namespace.clone(),
+            deployment_id: self.deployment_id.clone(),
+            region: self.region.clone(),
+        }
+    }
+
+    /// Submits a job without waiting for it and remembers it as the pending job.
+    fn submit(&mut self, command: &str, rt: &Runtime) -> PyResult<String> {
+        match rt.block_on(submit_job(command, self)) {
+            Ok(job_id) => {
+                self.pending_job = Some((job_id.clone(), command.to_string()));
+                Ok(job_id)
+            }
+            Err(e) => {
+                if command != "plan" {
+                    self.has_error = true;
+                }
+                Err(DeploymentFailure::new_err(format!(
+                    "Failed to run {} for {}: {}",
+                    command, self.deployment_id, e
+                )))
+            }
+        }
+    }
+
+    /// Polls the pending job until it finishes, optionally forwarding new log lines to `callback`.
+    fn follow_pending_job(
+        &mut self,
+        py: Python,
+        callback: Option<PyObject>,
+        timeout: Option<u64>,
+        poll_interval: u64,
+    ) -> PyResult<String> {
+        let (job_id, command) = match &self.pending_job {
+            Some(pending) => pending.clone(),
+            None => {
+                return Err(PyException::new_err(
+                    "No pending job, run apply/plan/destroy with wait=False first",
+                ))
+            }
+        };
+        let rt = Runtime::new().unwrap();
+        let handler = rt.block_on(GenericCloudHandler::region(&self.region));
+        let started = Instant::now();
+        let mut seen_lines = 0;
+
+        let deployment = loop {
+            let (in_progress, deployment) = rt.block_on(poll_job(&command, self, &job_id));
+
+            if let Some(callback) = &callback {
+                // Logs may not be available yet right after the job has started
+                if let Ok(logs) = rt.block_on(handler.read_logs(&job_id)) {
+                    for log in logs.iter().skip(seen_lines) {
+                        callback.call1(py, (log.message.clone(),))?;
+                    }
+                    seen_lines = seen_lines.max(logs.len());
+                }
+            }
+
+            if !in_progress {
+                break deployment;
+            }
+            if let Some(timeout) = timeout {
+                if started.elapsed() >= Duration::from_secs(timeout) {
+                    return Err(PyTimeoutError::new_err(format!(
+                        "Timed out after {}s waiting for {} job {}",
+                        timeout, command, job_id
+                    )));
+                }
+            }
+            py.allow_threads(|| thread::sleep(Duration::from_secs(poll_interval)));
+            py.check_signals()?;
+        };
+
+        self.pending_job = None;
+        let status = match &deployment {
+            Some(deployment) => deployment.status.to_string(),
+            None => "unknown".to_string(),
+        };
+        if status != "successful" {
+            if command != "plan" {
+                self.has_error = true;
+            }
+            return Err(DeploymentFailure::new_err(format!(
+                "Job {} ({}) failed with status: {}, error: {}",
+                job_id,
+                command,
+                status,
+                deployment
+                    .as_ref()
+                    .map(|d| d.error_text.clone())
+                    .unwrap_or_else(|| "No error message".to_string())
+            )));
+        }
+        match command.as_str() {
+            "apply" => self.last_deployment = deployment,
+            "destroy" => self.last_deployment = None,
+            _ => {}
+        }
+        if command != "plan" {
+            self.has_error = false;
+        }
+        Ok(status)
+    }
+}
+
 /// Normalizes a namespace string by prefixing with `python/` if no slash present.
 pub fn get_namespace(namespace_arg: &str) -> String {
     if !namespace_arg.contains('/') {
@@ -702,14 +910,8 @@ pub fn get_namespace(namespace_arg: &str) -> String {
     }
 }
 
-/// Internal helper to drive a deployment job to completion.
-///
-/// Repeatedly polls `is_deployment_in_progress` every 10 seconds until done.
-/// Returns (job_id, status, deployment_resp, changes_output)
-async fn run_job(
-    command: &str,
-    deployment: &Deployment,
-) -> Result<(String, String, Option<DeploymentResp>), anyhow::Error> {
+/// Submits a deployment job and returns its job id without waiting for it.
+async fn submit_job(command: &str, deployment: &Deployment) -> Result<String, anyhow::Error> {
     let handler = &GenericCloudHandler::region(&deployment.region).await;
     let result = match command {
         "destroy" => {
@@ -726,37 +928,55 @@ async fn run_job(
         "plan" => plan_or_apply_deployment(command, deployment).await,
         _ => panic!("Invalid command"),
     };
-    let job_id = match result {
-        Ok(job_id) => job_id,
-        Err(e) => {
-            return Err(anyhow::anyhow!("{}", e));
-        }
-    };
+    result.map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+/// Checks once whether a submitted job is still running.
+///
+/// Returns (in_progress, deployment_resp)
+async fn poll_job(
+    command: &str,
+    deployment: &Deployment,
+    job_id: &str,
+) -> (bool, Option<DeploymentResp>) {
+    let handler = &GenericCloudHandler::region(&deployment.region).await;
+    if command == "plan" {
+        let (in_progress, _job_id, deployment) = is_deployment_plan_in_progress(
+            handler,
+            &deployment.deployment_id,
+            &deployment.namespace,
+            job_id,
+        )
+        .await;
+        (in_progress, deployment)
+    } else {
+        let (in_progress, _, _status, deployment) = is_deployment_in_progress(
+            handler,
+            &deployment.deployment_id,
+            &deployment.namespace,
+            false,
+            true,
+        )
+        .await;
+        (in_progress, deployment)
+    }
+}
+
+/// Internal helper to drive a deployment job to completion.
+///
+/// Repeatedly polls the job every 10 seconds until done.
+/// Returns (job_id, status, deployment_resp)
+async fn run_job(
+    command: &str,
+    deployment: &Deployment,
+) -> Result<(String, String, Option<DeploymentResp>), anyhow::Error> {
+    let job_id = submit_job(command, deployment).await?;
 
     let final_status: String;
     let deployment_result: Option<DeploymentResp>;
 
     loop {
-        let (in_progress, deployment_job_result) = if command == "plan" {
-            let (in_progress, _job_id, deployment) = is_deployment_plan_in_progress(
-                handler,
-                &deployment.deployment_id,
-                &deployment.namespace,
-                &job_id,
-            )
-            .await;
-            (in_progress, deployment)
-        } else {
-            let (in_progress, _, _status, deployment) = is_deployment_in_progress(
-                handler,
-                &deployment.deployment_id,
-                &deployment.namespace,
-                false,
-                true,
-            )
-            .await;
-            (in_progress, deployment)
-        };
+        let (in_progress, deployment_job_result) = poll_job(command, deployment, &job_id).await;
 
         if !in_progress {
             let status = match &deployment_job_result {
@@ -811,6 +1031,10 @@ async fn plan_or_apply_deployment(
         variables: variables_yaml_mapping,
         dependencies: None,
         drift_detection: None,
+        var_file: None,
+        targets: vec![],
+        depends_on: vec![],
+        outputs_to: None,
     };
 
     let deployment_manifest = DeploymentManifest {
@@ -185,6 +185,7 @@ def get_environment_variables(req: func.HttpRequest) -> func.HttpResponse:
         "RESOURCE_GROUP_NAME": "test-rg",
         "AZURE_SUBSCRIPTION_ID": "test-subscription-id",
         "LOCATION": "westus2",
+        "INFRAWEAVE_PLATFORM_COST_CENTER": "platform-test",
     }
     return func.HttpResponse(json.dumps(env_vars), status_code=200, mimetype="application/json")
 
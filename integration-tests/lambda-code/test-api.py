@@ -214,6 +214,7 @@ def get_environment_variables(event):
             "DYNAMODB_TF_LOCKS_TABLE_ARN": "arn:aws:dynamodb:us-west-2:123456789012:table/test-tf-locks",
             "TF_STATE_S3_BUCKET": "test-tf-state-bucket",
             "REGION": "us-west-2",
+            "INFRAWEAVE_PLATFORM_COST_CENTER": "platform-test",
         }
 
 def start_runner(event):
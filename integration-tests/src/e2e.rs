@@ -0,0 +1,365 @@
+//! End-to-end harness running publish → plan → apply → drift → destroy against an ephemeral sandbox.
+//!
+//! The sandbox is selected with `E2E_SANDBOX`:
+//! - `local` (default): the containers of [`test_scaffold`], with jobs run in-process by the terraform runner
+//! - `account`: an already bootstrapped sandbox account using the credentials of the environment,
+//!   guarded by `E2E_SANDBOX_ACCOUNT_ID` so it never runs against another account
+//!
+//! Everything a run creates is suffixed with its run id, and deployments left behind by a failing
+//! test are destroyed before the failure is reported.
+
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use env_common::errors::ModuleError;
+use env_common::interface::GenericCloudHandler;
+use env_common::logic::{is_deployment_plan_in_progress, run_claim};
+use env_defs::{CloudProvider, DeploymentResp, ExtraData};
+use futures::FutureExt;
+use serde::Deserialize;
+use terraform_runner::run_terraform_runner;
+
+use crate::scaffold::{integration_tests_dir, test_scaffold};
+
+const LOCAL_ENDPOINT: &str = "http://127.0.0.1:8080";
+const JOB_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SandboxKind {
+    Local,
+    Account,
+}
+
+impl SandboxKind {
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("E2E_SANDBOX").as_deref() {
+            Err(_) | Ok("") | Ok("local") => Ok(SandboxKind::Local),
+            Ok("account") => Ok(SandboxKind::Account),
+            Ok(other) => Err(anyhow!(
+                "Unsupported E2E_SANDBOX \"{}\", expected local or account",
+                other
+            )),
+        }
+    }
+}
+
+/// A short-lived sandbox for one end-to-end test
+#[derive(Clone)]
+pub struct E2eSandbox {
+    pub kind: SandboxKind,
+    pub handler: GenericCloudHandler,
+    /// Unique id of the run, used in names, versions and the environment
+    pub run_id: String,
+    pub environment: String,
+    // Claims that were applied and have to be destroyed on teardown
+    applied: Arc<Mutex<Vec<serde_yaml::Value>>>,
+}
+
+/// Runs `test` in a fresh sandbox and tears it down afterwards, also when the test panics
+pub async fn with_e2e_sandbox<F, Fut>(test: F)
+where
+    F: FnOnce(E2eSandbox) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let kind = SandboxKind::from_env().unwrap();
+    match kind {
+        SandboxKind::Local => {
+            test_scaffold(|| async move {
+                let handler = GenericCloudHandler::custom(LOCAL_ENDPOINT).await;
+                run_in_sandbox(E2eSandbox::new(kind, handler), test).await;
+            })
+            .await
+        }
+        SandboxKind::Account => {
+            let handler = GenericCloudHandler::default().await;
+            let expected_account = std::env::var("E2E_SANDBOX_ACCOUNT_ID")
+                .expect("E2E_SANDBOX_ACCOUNT_ID must be set to the sandbox account to run against");
+            assert_eq!(
+                handler.get_project_id(),
+                expected_account,
+                "Refusing to run end-to-end tests outside of the sandbox account"
+            );
+            run_in_sandbox(E2eSandbox::new(kind, handler), test).await;
+        }
+    }
+}
+
+async fn run_in_sandbox<F, Fut>(sandbox: E2eSandbox, test: F)
+where
+    F: FnOnce(E2eSandbox) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    println!(
+        "Running end-to-end test in {:?} sandbox, environment {}",
+        sandbox.kind, sandbox.environment
+    );
+    let result = AssertUnwindSafe(test(sandbox.clone())).catch_unwind().await;
+    sandbox.teardown().await;
+    if let Err(panic) = result {
+        std::panic::resume_unwind(panic);
+    }
+}
+
+impl E2eSandbox {
+    fn new(kind: SandboxKind, handler: GenericCloudHandler) -> Self {
+        let run_id = format!("{:08x}", rand::random::<u32>());
+        E2eSandbox {
+            kind,
+            handler,
+            environment: format!("e2e/{}", run_id),
+            run_id,
+            applied: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Version with the run id as build metadata, so runs in a shared account don't collide
+    pub fn version(&self, version: &str) -> String {
+        format!("{}+e2e.{}", version, self.run_id)
+    }
+
+    pub async fn publish_provider(&self, path: &str, version: &str) {
+        let path = integration_tests_dir().join(path);
+        ignore_existing(
+            env_common::publish_provider(&self.handler, path.to_str().unwrap(), Some(version))
+                .await,
+        );
+    }
+
+    pub async fn publish_module(&self, path: &str, track: &str, version: &str) {
+        let path = integration_tests_dir().join(path);
+        ignore_existing(
+            env_common::publish_module(
+                &self.handler,
+                path.to_str().unwrap(),
+                track,
+                Some(version),
+                None,
+            )
+            .await,
+        );
+    }
+
+    pub async fn publish_stack(&self, path: &str, track: &str, version: &str) {
+        let path = integration_tests_dir().join(path);
+        ignore_existing(
+            env_common::publish_stack(
+                &self.handler,
+                path.to_str().unwrap(),
+                track,
+                Some(version),
+                None,
+            )
+            .await,
+        );
+    }
+
+    /// Reads a claim from `claims/`, renamed for this run and pointed at `version` of its module
+    /// or stack. String variables in `unique_variables` get the run id as prefix, for resources
+    /// that need globally unique names
+    pub fn claim(
+        &self,
+        path: &str,
+        version: &str,
+        unique_variables: &[&[&str]],
+    ) -> serde_yaml::Value {
+        let content = std::fs::read_to_string(integration_tests_dir().join("claims").join(path))
+            .expect("Failed to read claim");
+        let mut claim = serde_yaml::Deserializer::from_str(&content)
+            .map(|doc| serde_yaml::Value::deserialize(doc).unwrap())
+            .next()
+            .expect("Claim file is empty");
+
+        let name = claim["metadata"]["name"].as_str().unwrap().to_string();
+        claim["metadata"]["name"] = format!("{}-{}", name, self.run_id).into();
+        let spec = &mut claim["spec"];
+        if spec.get("stackVersion").is_some() {
+            spec["stackVersion"] = version.into();
+        } else {
+            spec["moduleVersion"] = version.into();
+        }
+        spec["region"] = self.handler.get_region().into();
+        for variable_path in unique_variables {
+            let mut value = &mut spec["variables"];
+            for key in *variable_path {
+                value = &mut value[*key];
+            }
+            let unique = format!("e2e-{}-{}", self.run_id, value.as_str().unwrap());
+            *value = unique.into();
+        }
+        claim
+    }
+
+    pub async fn plan(&self, claim: &serde_yaml::Value) -> DeploymentResp {
+        self.run(claim, "plan", vec![]).await
+    }
+
+    pub async fn apply(&self, claim: &serde_yaml::Value) -> DeploymentResp {
+        self.applied.lock().unwrap().push(claim.clone());
+        self.run(claim, "apply", vec![]).await
+    }
+
+    pub async fn driftcheck(&self, claim: &serde_yaml::Value) -> DeploymentResp {
+        self.run(claim, "plan", vec!["-refresh-only".to_string()])
+            .await
+    }
+
+    /// Destroys the deployment of the claim, returning the record if any is left afterwards
+    pub async fn destroy(&self, claim: &serde_yaml::Value) -> Option<DeploymentResp> {
+        let (_, deployment_id) = self.run_job(claim, "destroy", vec![]).await.unwrap();
+        self.applied
+            .lock()
+            .unwrap()
+            .retain(|c| c["metadata"]["name"] != claim["metadata"]["name"]);
+        self.handler
+            .get_deployment(&deployment_id, &self.environment, false)
+            .await
+            .unwrap()
+            .filter(|deployment| !deployment.deleted)
+    }
+
+    async fn run(
+        &self,
+        claim: &serde_yaml::Value,
+        command: &str,
+        flags: Vec<String>,
+    ) -> DeploymentResp {
+        let (job_id, deployment_id) = self.run_job(claim, command, flags).await.unwrap();
+        if command == "plan" {
+            let (_, _, deployment) = is_deployment_plan_in_progress(
+                &self.handler,
+                &deployment_id,
+                &self.environment,
+                &job_id,
+            )
+            .await;
+            deployment.expect("Plan not found after job finished")
+        } else {
+            self.handler
+                .get_deployment(&deployment_id, &self.environment, false)
+                .await
+                .unwrap()
+                .expect("Deployment not found after job finished")
+        }
+    }
+
+    /// Starts the job for the claim and waits for it to finish, returning job id and deployment id
+    async fn run_job(
+        &self,
+        claim: &serde_yaml::Value,
+        command: &str,
+        flags: Vec<String>,
+    ) -> Result<(String, String)> {
+        let (job_id, deployment_id, payload_with_variables) = run_claim(
+            &self.handler,
+            claim,
+            &self.environment,
+            command,
+            flags,
+            ExtraData::None,
+            "",
+        )
+        .await?;
+        println!("Started {} of {} as job {}", command, deployment_id, job_id);
+
+        match self.kind {
+            SandboxKind::Local => {
+                let payload = serde_json::to_string(&payload_with_variables.payload)?;
+                self.run_local_job(&deployment_id, &payload).await?;
+            }
+            SandboxKind::Account => {
+                self.wait_for_job(command, &deployment_id, &job_id).await?;
+            }
+        }
+        Ok((job_id, deployment_id))
+    }
+
+    /// Runs the job in-process, as the local sandbox has no job runners
+    async fn run_local_job(&self, deployment_id: &str, payload: &str) -> Result<()> {
+        std::env::set_var("PAYLOAD", payload);
+        std::env::set_var("TF_BUCKET", "tf-state");
+        std::env::set_var("REGION", self.handler.get_region());
+        if self.handler.get_cloud_provider() == "azure" {
+            std::env::set_var("CONTAINER_GROUP_NAME", "running-test-job-id");
+            std::env::set_var("ACCOUNT_ID", "dummy-account-id");
+            std::env::set_var("STORAGE_ACCOUNT", "devstoreaccount1");
+            std::env::set_var("RESOURCE_GROUP_NAME", "dummy-resource-group");
+        }
+
+        let work_dir = tempfile::Builder::new()
+            .prefix(&format!(
+                "infraweave-e2e-{}-",
+                deployment_id.replace('/', "-")
+            ))
+            .tempdir()?;
+        let original_dir = std::env::current_dir()?;
+        std::env::set_current_dir(work_dir.path())?;
+        let result = run_terraform_runner(&self.handler).await;
+        std::env::set_current_dir(original_dir)?;
+        // The job status is read from the deployment, a failing job fails the assertions on it
+        if let Err(e) = result {
+            println!("Terraform runner failed: {:?}", e);
+        }
+        Ok(())
+    }
+
+    async fn wait_for_job(&self, command: &str, deployment_id: &str, job_id: &str) -> Result<()> {
+        let started = Instant::now();
+        loop {
+            let in_progress = if command == "plan" {
+                is_deployment_plan_in_progress(
+                    &self.handler,
+                    deployment_id,
+                    &self.environment,
+                    job_id,
+                )
+                .await
+                .0
+            } else {
+                let deployment = self
+                    .handler
+                    .get_deployment(deployment_id, &self.environment, true)
+                    .await?;
+                !matches!(deployment, Some(d) if d.job_id == job_id && !d.status.is_busy())
+            };
+            if !in_progress {
+                return Ok(());
+            }
+            if started.elapsed() > JOB_TIMEOUT {
+                return Err(anyhow!(
+                    "Job {} of {} did not finish within {:?}",
+                    job_id,
+                    deployment_id,
+                    JOB_TIMEOUT
+                ));
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Destroys whatever the test applied and did not destroy itself
+    async fn teardown(&self) {
+        let leftover: Vec<serde_yaml::Value> = self.applied.lock().unwrap().clone();
+        for claim in leftover.iter().rev() {
+            println!(
+                "Tearing down {} in {}",
+                claim["metadata"]["name"].as_str().unwrap_or_default(),
+                self.environment
+            );
+            if let Err(e) = self.run_job(claim, "destroy", vec![]).await {
+                eprintln!("Failed to tear down deployment: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Providers and modules shared between runs may already be published in a sandbox account
+fn ignore_existing(result: Result<(), ModuleError>) {
+    match result {
+        Ok(()) | Err(ModuleError::ModuleVersionExists(_, _)) => {}
+        Err(e) => panic!("Failed to publish: {:?}", e),
+    }
+}
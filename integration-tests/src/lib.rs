@@ -1 +1,3 @@
+#[cfg(feature = "e2e")]
+pub mod e2e;
 pub mod scaffold;
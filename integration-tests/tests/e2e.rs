@@ -0,0 +1,73 @@
+#![cfg(feature = "e2e")]
+
+#[cfg(test)]
+mod e2e_tests {
+    use env_defs::DeploymentStatus;
+    use integration_tests::e2e::with_e2e_sandbox;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn test_e2e_module_lifecycle() {
+        with_e2e_sandbox(|sandbox| async move {
+            let version = sandbox.version("0.1.2-dev");
+            sandbox.publish_provider("providers/aws-5/", "0.1.2").await;
+            sandbox
+                .publish_module("modules/s3bucket-dev/", "dev", &version)
+                .await;
+
+            let claim = sandbox.claim("s3bucket-dev-claim.yaml", &version, &[&["bucketName"]]);
+
+            let plan = sandbox.plan(&claim).await;
+            assert_eq!(plan.status, DeploymentStatus::Successful);
+
+            let deployment = sandbox.apply(&claim).await;
+            assert_eq!(deployment.status, DeploymentStatus::Successful);
+            assert_eq!(deployment.module_version, version);
+
+            let drift = sandbox.driftcheck(&claim).await;
+            assert_eq!(drift.status, DeploymentStatus::Successful);
+            assert!(!drift.has_drifted, "Deployment drifted right after apply");
+
+            let remaining = sandbox.destroy(&claim).await;
+            assert!(remaining.is_none(), "Deployment still exists after destroy");
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_e2e_stack_lifecycle() {
+        with_e2e_sandbox(|sandbox| async move {
+            let version = sandbox.version("0.1.0-dev");
+            sandbox.publish_provider("providers/aws-5/", "0.1.2").await;
+            sandbox
+                .publish_module("modules/s3bucket-dev/", "dev", "0.1.2-dev+test.10")
+                .await;
+            sandbox
+                .publish_module("modules/s3bucket-dev/", "dev", "0.1.3-dev+test.10")
+                .await;
+            sandbox
+                .publish_stack("stacks/bucketcollection-stack-vars/", "dev", &version)
+                .await;
+
+            let claim = sandbox.claim(
+                "bucketcollection-stack-vars-claim.yaml",
+                &version,
+                &[&["stack", "environment"]],
+            );
+
+            let plan = sandbox.plan(&claim).await;
+            assert_eq!(plan.status, DeploymentStatus::Successful);
+
+            let deployment = sandbox.apply(&claim).await;
+            assert_eq!(deployment.status, DeploymentStatus::Successful);
+
+            let drift = sandbox.driftcheck(&claim).await;
+            assert_eq!(drift.status, DeploymentStatus::Successful);
+            assert!(!drift.has_drifted, "Stack drifted right after apply");
+
+            let remaining = sandbox.destroy(&claim).await;
+            assert!(remaining.is_none(), "Stack still exists after destroy");
+        })
+        .await;
+    }
+}
@@ -0,0 +1,68 @@
+mod utils;
+use utils::test_scaffold;
+
+#[cfg(test)]
+mod environment_variables_tests {
+    use super::*;
+    use env_common::interface::GenericCloudHandler;
+    use env_defs::{CloudProvider, DriftDetection, ExtraData};
+    use env_utils::get_extra_environment_variables_all;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn test_get_environment_variables() {
+        test_scaffold(|| async move {
+            let lambda_endpoint_url = "http://127.0.0.1:8080";
+            let handler = GenericCloudHandler::custom(lambda_endpoint_url).await;
+
+            let variables = handler.get_environment_variables().await.unwrap();
+
+            assert_eq!(
+                variables.get("INFRAWEAVE_PLATFORM_COST_CENTER"),
+                Some(&serde_json::json!("platform-test"))
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_platform_variables_in_extra_environment_variables() {
+        test_scaffold(|| async move {
+            let lambda_endpoint_url = "http://127.0.0.1:8080";
+            let handler = GenericCloudHandler::custom(lambda_endpoint_url).await;
+
+            let variables = handler.get_environment_variables().await.unwrap();
+            let extra_variables = get_extra_environment_variables_all(
+                "s3bucket/my-bucket",
+                "playground",
+                "",
+                "0.1.2",
+                "module",
+                "dev",
+                &DriftDetection {
+                    enabled: false,
+                    interval: "1h".to_string(),
+                    auto_remediate: false,
+                    webhooks: vec![],
+                    schedule: None,
+                    blackout_windows: vec![],
+                },
+                &ExtraData::None,
+                &variables,
+            );
+
+            assert_eq!(
+                extra_variables.get("INFRAWEAVE_PLATFORM_COST_CENTER"),
+                Some(&"platform-test".to_string())
+            );
+            assert_eq!(
+                extra_variables.get("INFRAWEAVE_DEPLOYMENT_ID"),
+                Some(&"s3bucket/my-bucket".to_string())
+            );
+            // Only platform variables are passed on, not the settings of the API function
+            assert!(!extra_variables.contains_key("REGION"));
+            assert!(!extra_variables.contains_key("LOCATION"));
+        })
+        .await;
+    }
+}
@@ -77,7 +77,7 @@ mod operator_tests {
             }
 
             // Start the controllers
-            start_infraweave_controllers(&handler, client.clone());
+            let _controllers = start_infraweave_controllers(&handler, client.clone());
 
             sleep(Duration::from_secs(3)).await;
 
@@ -32,6 +32,7 @@ mod runner_tests {
                 None,
         Some(OciArtifactSet {
                     oci_artifact_path: "oci-artifacts/".to_string(),
+                    repository: None,
                     tag_main: "s3bucket-0.0.36-dev-test.198".to_string(),
                     tag_attestation: Some(
                         "sha256-1559cd5049bed772aa9a780a607e019d9a7e8a738787a23556cfdf7c41030f6e.att".to_string(),
@@ -39,6 +40,8 @@ mod runner_tests {
                     tag_signature: Some(
                         "sha256-1559cd5049bed772aa9a780a607e019d9a7e8a738787a23556cfdf7c41030f6e.sig".to_string(),
                     ),
+                    tag_sbom: None,
+                    platforms: vec![],
                     digest: "sha256:1559cd5049bed772aa9a780a607e019d9a7e8a738787a23556cfdf7c41030f6e".to_string(),
                 }),
             )
@@ -59,6 +59,19 @@ async fn query_all<Q: DatabaseQuery>(
     db.query_table(container, &query, region).await
 }
 
+// Helper to collect the attribute filters set in the payload, e.g. `status` and `module`
+fn attribute_filters<'a>(payload: &'a Value, attributes: &[&'a str]) -> Vec<(&'a str, &'a str)> {
+    attributes
+        .iter()
+        .filter_map(|attribute| {
+            payload
+                .get(*attribute)
+                .and_then(|v| v.as_str())
+                .map(|value| (*attribute, value))
+        })
+        .collect()
+}
+
 // Helper to query and return first item or error if not found
 async fn query_one<Q: DatabaseQuery>(
     db: &Q,
@@ -126,16 +139,18 @@ pub async fn get_deployments_impl<Q: DatabaseQuery>(
     db: &Q,
     payload: &Value,
     qb: impl Fn(&str, &str, &str, bool) -> Value,
+    filter: impl Fn(Value, &[(&str, &str)]) -> Value,
 ) -> Result<Value> {
     let region = get_param!(payload, "region");
     let include_deleted = payload
         .get("include_deleted")
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
+    let filters = attribute_filters(payload, &["status", "module"]);
 
     if let Some(projects) = payload.get("projects").and_then(|v| v.as_array()) {
         let futures = projects.iter().filter_map(|p| p.as_str()).map(|project| {
-            let query = qb(project, region, "", include_deleted);
+            let query = filter(qb(project, region, "", include_deleted), &filters);
             query_all(db, "deployments", query, Some(payload))
         });
 
@@ -157,7 +172,10 @@ pub async fn get_deployments_impl<Q: DatabaseQuery>(
         query_all(
             db,
             "deployments",
-            qb(get_param!(payload, "project"), region, "", include_deleted),
+            filter(
+                qb(get_param!(payload, "project"), region, "", include_deleted),
+                &filters,
+            ),
             Some(payload),
         )
         .await
@@ -168,6 +186,7 @@ pub async fn get_modules_impl<Q: DatabaseQuery>(
     db: &Q,
     payload: &Value,
     qb: impl Fn(&str, bool, bool) -> Value,
+    filter: impl Fn(Value, &[(&str, &str)]) -> Value,
 ) -> Result<Value> {
     let include_deprecated = payload
         .get("include_deprecated")
@@ -177,10 +196,11 @@ pub async fn get_modules_impl<Q: DatabaseQuery>(
         .get("include_dev000")
         .and_then(|v| v.as_bool())
         .unwrap_or(true);
+    let filters = attribute_filters(payload, &["module"]);
     query_all(
         db,
         "modules",
-        qb("", include_deprecated, include_dev000),
+        filter(qb("", include_deprecated, include_dev000), &filters),
         Some(payload),
     )
     .await
@@ -446,6 +466,7 @@ pub async fn get_change_record_impl<Q: DatabaseQuery>(
     db: &Q,
     payload: &Value,
     qb: impl Fn(&str, &str, &str, &str, &str, &str) -> Value,
+    parts_qb: impl Fn(&str, &str, &str, &str, &str, &str) -> Value,
 ) -> Result<Value> {
     let project = get_param!(payload, "project");
     let region = get_param!(payload, "region");
@@ -459,13 +480,14 @@ pub async fn get_change_record_impl<Q: DatabaseQuery>(
     let pk_prefix = match change_type.to_lowercase().as_str() {
         "apply" | "destroy" | "mutate" => "MUTATE",
         "plan" => "PLAN",
+        "drift" => "DRIFT",
         _ => change_type, // fallback to original if unknown
     };
 
     log::info!("get_change_record_impl: project={}, region={}, env={}, dep_id={}, job_id={}, change_type={} -> pk_prefix={}", 
         project, region, environment, deployment_id, job_id, change_type, pk_prefix);
 
-    let mut result = if pk_prefix != "PLAN" {
+    let mut result = if pk_prefix != "PLAN" && pk_prefix != "DRIFT" {
         let query = qb(
             project,
             region,
@@ -480,7 +502,7 @@ pub async fn get_change_record_impl<Q: DatabaseQuery>(
         );
         query_one(db, "change_records", query, Some(region)).await
     } else {
-        Err(anyhow!("Skipping MUTATE for PLAN"))
+        Err(anyhow!("Skipping MUTATE for {}", pk_prefix))
     };
 
     if result.is_err() {
@@ -500,10 +522,38 @@ pub async fn get_change_record_impl<Q: DatabaseQuery>(
         result = query_one(db, "change_records", query, Some(region)).await;
     }
 
-    result.map_err(|e| {
+    let record = result.map_err(|e| {
         log::error!("Change record not found: {}", e);
         anyhow!("Change record not found")
-    })
+    })?;
+
+    // Records over the item size limit of the database are stored in parts next to the record
+    if record.get("chunks").is_none() {
+        return Ok(record);
+    }
+    let record_prefix = record
+        .get("PK")
+        .and_then(|v| v.as_str())
+        .and_then(|pk| pk.split('#').next())
+        .unwrap_or(pk_prefix);
+    let query = parts_qb(
+        project,
+        region,
+        environment,
+        deployment_id,
+        job_id,
+        record_prefix,
+    );
+    let response = db
+        .query_table("change_records", &query, Some(region))
+        .await?;
+    let parts = response
+        .get("Items")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let change_record = env_defs::join_change_record_parts(&record, &parts)?;
+    Ok(serde_json::to_value(change_record)?)
 }
 pub async fn get_deployment_history_impl<Q: DatabaseQuery>(
     db: &Q,
@@ -0,0 +1,188 @@
+use axum::{
+    extract::Request,
+    http::{HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::Instrument;
+
+/// Header carrying the request ID, taken from the request when the caller sets it
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Body of every error response of the API
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    /// Stable identifier of the kind of error, e.g. `not_found`
+    pub code: String,
+    pub message: String,
+    /// Same as the `x-request-id` header of the response, to find the request in the logs
+    pub request_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+/// Error returned by a handler, sent as an [`ErrorResponse`] with the ID of the current request
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+    pub details: Option<Value>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorResponse {
+            code: error_code(self.status).to_string(),
+            message: self.message,
+            request_id: current_request_id(),
+            details: self.details,
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// Error code of a status, the lowercase name of the status for the ones the API returns
+pub fn error_code(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "bad_request",
+        StatusCode::UNAUTHORIZED => "unauthorized",
+        StatusCode::FORBIDDEN => "forbidden",
+        StatusCode::NOT_FOUND => "not_found",
+        StatusCode::METHOD_NOT_ALLOWED => "method_not_allowed",
+        StatusCode::CONFLICT => "conflict",
+        StatusCode::PAYLOAD_TOO_LARGE => "payload_too_large",
+        StatusCode::UNPROCESSABLE_ENTITY => "unprocessable_entity",
+        StatusCode::TOO_MANY_REQUESTS => "too_many_requests",
+        StatusCode::SERVICE_UNAVAILABLE => "service_unavailable",
+        StatusCode::GATEWAY_TIMEOUT => "gateway_timeout",
+        status if status.is_client_error() => "client_error",
+        _ => "internal_error",
+    }
+}
+
+/// ID of the request being handled, empty outside of [`request_id_middleware`]
+pub fn current_request_id() -> String {
+    REQUEST_ID.try_with(Clone::clone).unwrap_or_default()
+}
+
+/// Middleware giving every request an ID: the `x-request-id` of the caller when it is a valid
+/// ID, a new UUID otherwise. The ID is recorded on the tracing span of the request, set on
+/// error responses and returned in the `x-request-id` header
+pub async fn request_id_middleware(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        http_method = %request.method(),
+        http_path = %request.uri().path()
+    );
+    let mut response = REQUEST_ID
+        .scope(request_id.clone(), next.run(request))
+        .instrument(span)
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+// Caller provided IDs end up in logs and headers, so only short plain IDs are kept
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 128
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    async fn error_response(request: Request) -> (Response, ErrorResponse) {
+        let router = Router::new()
+            .route(
+                "/",
+                get(|| async { ApiError::new(StatusCode::NOT_FOUND, "Deployment not found") }),
+            )
+            .layer(middleware::from_fn(request_id_middleware));
+        let response = router.oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        (
+            Response::from_parts(parts, Body::empty()),
+            serde_json::from_slice(&body).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_error_response_has_request_id() {
+        let request = Request::builder()
+            .uri("/")
+            .header(REQUEST_ID_HEADER, "req-123")
+            .body(Body::empty())
+            .unwrap();
+        let (response, body) = error_response(request).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "req-123");
+        assert_eq!(
+            body,
+            ErrorResponse {
+                code: "not_found".to_string(),
+                message: "Deployment not found".to_string(),
+                request_id: "req-123".to_string(),
+                details: None,
+            }
+        );
+
+        let request = Request::builder()
+            .uri("/")
+            .header(REQUEST_ID_HEADER, "not a valid id")
+            .body(Body::empty())
+            .unwrap();
+        let (response, body) = error_response(request).await;
+        assert_ne!(body.request_id, "not a valid id");
+        assert_eq!(
+            response.headers()[REQUEST_ID_HEADER],
+            body.request_id.as_str()
+        );
+    }
+
+    #[test]
+    fn test_error_code() {
+        assert_eq!(error_code(StatusCode::FORBIDDEN), "forbidden");
+        assert_eq!(error_code(StatusCode::IM_A_TEAPOT), "client_error");
+        assert_eq!(error_code(StatusCode::BAD_GATEWAY), "internal_error");
+        assert_eq!(current_request_id(), "");
+    }
+}
@@ -387,24 +387,24 @@ pub async fn refresh_tokens(refresh_token: &str) -> Result<Value> {
     }))
 }
 
-/// Extract username from IAM ARN (AWS-specific utility)
+/// Extract username from IAM ARN (AWS-specific utility), in any partition
 #[cfg(feature = "aws")]
 pub fn extract_username_from_arn(arn: &str) -> String {
-    if arn.starts_with("arn:aws:iam::") {
+    match env_utils::parse_aws_arn(arn) {
         // IAM user: arn:aws:iam::123456789012:user/username
-        if let Some(user_part) = arn.strip_prefix("arn:aws:iam::") {
+        Some((_, "iam", _, _, user_part)) => {
             if let Some(username) = user_part.split('/').last() {
                 return username.to_string();
             }
         }
-    } else if arn.starts_with("arn:aws:sts::") {
         // Assumed role: arn:aws:sts::123456789012:assumed-role/role-name/session-name
-        if let Some(parts) = arn.strip_prefix("arn:aws:sts::") {
+        Some((_, "sts", _, _, parts)) => {
             let segments: Vec<&str> = parts.split('/').collect();
             if segments.len() >= 3 {
                 return segments.last().unwrap_or(&"unknown").to_string();
             }
         }
+        _ => {}
     }
 
     arn.split('/').last().unwrap_or("unknown").to_string()
@@ -443,7 +443,14 @@ pub fn extract_iam_identity(request_context: &Value) -> Result<String> {
 
         if let Some(account_id) = identity.get("accountId").and_then(|v| v.as_str()) {
             if let Some(user) = identity.get("user").and_then(|v| v.as_str()) {
-                return Ok(format!("arn:aws:iam::{}:user/{}", account_id, user));
+                let region = std::env::var("AWS_REGION").unwrap_or_default();
+                return Ok(env_utils::aws_arn(
+                    &region,
+                    "iam",
+                    "",
+                    account_id,
+                    &format!("user/{}", user),
+                ));
             }
         }
     }
@@ -471,6 +478,115 @@ pub fn publish_permissions_claim_key() -> String {
         .unwrap_or_else(|_| "custom:publish_permissions".to_string())
 }
 
+/// Return the JWT claim key used for the projects a user may read unmasked outputs in.
+///
+/// Configurable via `AUTH_UNMASK_OUTPUTS_CLAIM` env var.
+/// Defaults to `custom:unmask_outputs`.
+pub fn unmask_outputs_claim_key() -> String {
+    std::env::var("AUTH_UNMASK_OUTPUTS_CLAIM")
+        .unwrap_or_else(|_| "custom:unmask_outputs".to_string())
+}
+
+/// Return the JWT claim key used for the permissions a user has in each project.
+///
+/// Configurable via `AUTH_PROJECT_PERMISSIONS_CLAIM` env var.
+/// Defaults to `custom:project_permissions`.
+pub fn project_permissions_claim_key() -> String {
+    std::env::var("AUTH_PROJECT_PERMISSIONS_CLAIM")
+        .unwrap_or_else(|_| "custom:project_permissions".to_string())
+}
+
+/// Permission a route requires in the project it operates on.
+///
+/// Granting one permission also grants the ones it implies, see [`Permission::implies`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Read deployments, events, logs and jobs
+    Read,
+    /// Run plans
+    Plan,
+    /// Run applies and decide on jobs held for approval
+    Apply,
+    /// Run destroys
+    Destroy,
+    /// Publish and deprecate modules, stacks and providers
+    Publish,
+    /// Everything above
+    Admin,
+}
+
+impl Permission {
+    /// Permission a claim command requires, commands other than `plan` and `destroy` change
+    /// resources like an apply does
+    pub fn for_command(command: &str) -> Permission {
+        match command {
+            "plan" => Permission::Plan,
+            "destroy" => Permission::Destroy,
+            _ => Permission::Apply,
+        }
+    }
+
+    /// Whether a user granted this permission also has `required`
+    pub fn implies(self, required: Permission) -> bool {
+        match self {
+            Permission::Admin => true,
+            Permission::Apply | Permission::Destroy => {
+                matches!(required, Permission::Read | Permission::Plan) || self == required
+            }
+            Permission::Plan => matches!(required, Permission::Read | Permission::Plan),
+            Permission::Publish => matches!(required, Permission::Read | Permission::Publish),
+            Permission::Read => required == Permission::Read,
+        }
+    }
+}
+
+impl std::str::FromStr for Permission {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "read" => Ok(Permission::Read),
+            "plan" => Ok(Permission::Plan),
+            "apply" => Ok(Permission::Apply),
+            "destroy" => Ok(Permission::Destroy),
+            "publish" => Ok(Permission::Publish),
+            "admin" => Ok(Permission::Admin),
+            _ => Err(anyhow!("Unknown permission '{}'", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Permission::Read => write!(f, "read"),
+            Permission::Plan => write!(f, "plan"),
+            Permission::Apply => write!(f, "apply"),
+            Permission::Destroy => write!(f, "destroy"),
+            Permission::Publish => write!(f, "publish"),
+            Permission::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// Check if the project permissions claim grants `required` in a project.
+///
+/// The claim is a comma-separated list of `{project}:{permission}` grants, where the project
+/// is a project id or `*` for all projects, e.g. `123456789012:apply,*:read`. Grants with an
+/// unknown permission are ignored. Publishing isn't tied to a project and is checked with
+/// `project_id` set to `*`, so only `*:publish` and `*:admin` grant it.
+pub fn has_project_permission(grants: &str, project_id: &str, required: Permission) -> bool {
+    grants.split(',').any(|grant| {
+        let Some((project, permission)) = grant.trim().rsplit_once(':') else {
+            return false;
+        };
+        (project == "*" || project == project_id)
+            && permission
+                .parse::<Permission>()
+                .is_ok_and(|granted| granted.implies(required))
+    })
+}
+
 /// Return the ordered list of JWT claim keys to try when resolving user identity.
 ///
 /// Configurable via `AUTH_USERNAME_CLAIMS` env var (comma-separated).
@@ -505,6 +621,15 @@ mod tests {
         assert_eq!(extract_username_from_arn(arn), "session-name");
     }
 
+    #[cfg(feature = "aws")]
+    #[test]
+    fn test_extract_username_from_other_partitions() {
+        let arn = "arn:aws-us-gov:sts::123456789012:assumed-role/MyRole/session-name";
+        assert_eq!(extract_username_from_arn(arn), "session-name");
+        let arn = "arn:aws-cn:iam::123456789012:user/alice";
+        assert_eq!(extract_username_from_arn(arn), "alice");
+    }
+
     #[cfg(feature = "aws")]
     #[test]
     fn test_extract_username_from_plain_string() {
@@ -545,6 +670,74 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_project_permissions_claim_key_default() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        std::env::remove_var("AUTH_PROJECT_PERMISSIONS_CLAIM");
+        assert_eq!(
+            project_permissions_claim_key(),
+            "custom:project_permissions"
+        );
+    }
+
+    #[test]
+    fn test_has_project_permission() {
+        let grants = "123456789012:apply, 210987654321:read,*:plan";
+        assert!(has_project_permission(
+            grants,
+            "123456789012",
+            Permission::Apply
+        ));
+        assert!(!has_project_permission(
+            grants,
+            "123456789012",
+            Permission::Destroy
+        ));
+        assert!(has_project_permission(
+            grants,
+            "210987654321",
+            Permission::Plan
+        ));
+        assert!(!has_project_permission(
+            grants,
+            "210987654321",
+            Permission::Apply
+        ));
+        assert!(!has_project_permission(grants, "*", Permission::Publish));
+
+        assert!(has_project_permission(
+            "*:admin",
+            "123456789012",
+            Permission::Destroy
+        ));
+        assert!(has_project_permission("*:admin", "*", Permission::Publish));
+        assert!(has_project_permission(
+            "*:publish",
+            "*",
+            Permission::Publish
+        ));
+        assert!(!has_project_permission(
+            "123456789012:publish",
+            "*",
+            Permission::Publish
+        ));
+        assert!(!has_project_permission(
+            "123456789012:owner",
+            "123456789012",
+            Permission::Read
+        ));
+    }
+
+    #[test]
+    fn test_permission_for_command() {
+        assert_eq!(Permission::for_command("plan"), Permission::Plan);
+        assert_eq!(Permission::for_command("apply"), Permission::Apply);
+        assert_eq!(Permission::for_command("destroy"), Permission::Destroy);
+        assert!(Permission::Destroy.implies(Permission::Plan));
+        assert!(!Permission::Destroy.implies(Permission::Apply));
+        assert!(!Permission::Apply.implies(Permission::Destroy));
+    }
+
     #[test]
     fn test_username_claim_keys_default() {
         let _lock = ENV_LOCK.lock().unwrap();
@@ -34,6 +34,15 @@ mod auth_handler_tests {
         std::env::remove_var("AUTH_PUBLISH_PERMISSIONS_CLAIM");
     }
 
+    #[test]
+    fn test_unmask_outputs_claim_key_default() {
+        std::env::remove_var("AUTH_UNMASK_OUTPUTS_CLAIM");
+        assert_eq!(
+            auth_handler::unmask_outputs_claim_key(),
+            "custom:unmask_outputs"
+        );
+    }
+
     #[test]
     fn test_username_claim_keys_default() {
         std::env::remove_var("AUTH_USERNAME_CLAIMS");
@@ -158,13 +158,21 @@ pub async fn upload_file_base64(payload: &Value) -> Result<Value> {
     span.record("bucket", &bucket_name.as_str());
     span.record("key", &key);
 
-    env_aws_direct::upload_file_base64_direct(&bucket_name, key, content_base64, region).await?;
+    let version_id = env_aws_direct::upload_file_base64_direct(
+        &bucket_name,
+        key,
+        content_base64,
+        region,
+        env_aws_direct::object_lock_days(bucket_key),
+    )
+    .await?;
 
     info!("S3 upload from base64 completed");
 
     Ok(json!({
         "statusCode": 200,
-        "body": "File uploaded successfully"
+        "body": "File uploaded successfully",
+        "version_id": version_id
     }))
 }
 
@@ -435,8 +443,12 @@ pub async fn publish_notification(payload: &Value) -> Result<Value> {
         .and_then(|v| v.as_str())
         .ok_or_else(|| anyhow!("Missing 'message' parameter"))?;
     let subject = data.get("subject").and_then(|v| v.as_str());
+    let owners: Vec<String> = data
+        .get("owners")
+        .and_then(|o| serde_json::from_value(o.clone()).ok())
+        .unwrap_or_default();
 
-    env_aws_direct::publish_notification_direct(message, subject).await
+    env_aws_direct::publish_notification_direct(message, subject, &owners).await
 }
 
 pub async fn get_environment_variables(
@@ -168,7 +168,15 @@ pub async fn read_db(payload: &Value) -> Result<Value> {
         ));
     };
 
-    if let Some(limit) = query_data.get("Limit").and_then(|v| v.as_i64()) {
+    // Cosmos DB has no start key like DynamoDB, so the next page continues from an item offset
+    let offset = query_data
+        .get("ExclusiveStartKey")
+        .and_then(|v| v.get("offset"))
+        .and_then(|v| v.as_i64());
+    let limit = query_data.get("Limit").and_then(|v| v.as_i64());
+    if let (Some(limit), Some(offset)) = (limit, offset) {
+        query_str.push_str(&format!(" OFFSET {} LIMIT {}", offset, limit));
+    } else if let Some(limit) = limit {
         if query_str.to_uppercase().starts_with("SELECT *") {
             query_str = query_str.replacen("SELECT *", &format!("SELECT TOP {} *", limit), 1);
         } else if query_str.to_uppercase().starts_with("SELECT") {
@@ -212,10 +220,18 @@ pub async fn read_db(payload: &Value) -> Result<Value> {
     );
 
     let count = items.len();
-    Ok(json!({
+    let mut response = json!({
         "Items": items,
         "Count": count,
-    }))
+    });
+    if let Some(limit) = limit {
+        if count as i64 == limit {
+            use base64::{engine::general_purpose, Engine as _};
+            let key = json!({ "offset": offset.unwrap_or(0) + limit });
+            response["next_token"] = json!(general_purpose::STANDARD.encode(key.to_string()));
+        }
+    }
+    Ok(response)
 }
 
 pub async fn upload_file_base64(payload: &Value) -> Result<Value> {
@@ -560,11 +576,18 @@ pub async fn publish_notification(payload: &Value) -> Result<Value> {
 pub async fn get_environment_variables(
     _payload: &serde_json::Value,
 ) -> anyhow::Result<serde_json::Value> {
-    Ok(json!({
-        "COSMOS_DB_ENDPOINT": std::env::var("COSMOS_DB_ENDPOINT").ok(),
-        "AZURE_STORAGE_ACCOUNT": std::env::var("AZURE_STORAGE_ACCOUNT").ok(),
-        "INFRAWEAVE_ENV": std::env::var("INFRAWEAVE_ENV").ok(),
-    }))
+    let mut variables = env_utils::get_platform_environment_variables();
+    variables.extend(
+        json!({
+            "COSMOS_DB_ENDPOINT": std::env::var("COSMOS_DB_ENDPOINT").ok(),
+            "AZURE_STORAGE_ACCOUNT": std::env::var("AZURE_STORAGE_ACCOUNT").ok(),
+            "INFRAWEAVE_ENV": std::env::var("INFRAWEAVE_ENV").ok(),
+        })
+        .as_object()
+        .cloned()
+        .unwrap_or_default(),
+    );
+    Ok(serde_json::Value::Object(variables))
 }
 
 pub async fn download_file_as_string(container_name: &str, blob_name: &str) -> Result<String> {
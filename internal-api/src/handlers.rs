@@ -1,9 +1,11 @@
 use crate::api_common::{self, DatabaseQuery};
+use crate::api_error::ApiError;
 use crate::get_param;
 use crate::queries::*;
 use anyhow::{anyhow, Result};
 use axum::response::{IntoResponse, Response};
-use env_defs::CloudProvider;
+use env_defs::{CloudProvider, ModuleResp, StackInstanceModule};
+use env_utils::stack_instance_modules;
 use log::info;
 use serde_json::{json, Value};
 
@@ -19,8 +21,53 @@ use crate::azure_handlers::{download_file, download_file_as_string, AzureBackend
 use crate::common::get_env_var;
 
 pub async fn describe_deployment(payload: &Value) -> Result<Value> {
-    api_common::describe_deployment_impl(&Backend, payload, get_deployment_and_dependents_query)
-        .await
+    let mut deployment = api_common::describe_deployment_impl(
+        &Backend,
+        payload,
+        get_deployment_and_dependents_query,
+    )
+    .await?;
+    if deployment.get("module_type").and_then(|v| v.as_str()) == Some("stack") {
+        match stack_instance(&deployment).await {
+            Ok(modules) => deployment["stack_instance"] = json!(modules),
+            Err(e) => log::warn!("Failed to read the modules of the stack deployment: {}", e),
+        }
+    }
+    Ok(deployment)
+}
+
+/// Modules of a stack deployment, from the `stack_data` of the stack version it was deployed with
+async fn stack_instance(deployment: &Value) -> Result<Vec<StackInstanceModule>> {
+    let field = |key: &str| deployment.get(key).and_then(|v| v.as_str()).unwrap_or("");
+    let stack = get_stack_version(&json!({
+        "track": field("module_track"),
+        "stack_name": field("module"),
+        "stack_version": field("module_version"),
+    }))
+    .await?;
+    let stack: ModuleResp = serde_json::from_value(stack)?;
+    Ok(stack_instance_modules(
+        &stack,
+        deployment.get("output").unwrap_or(&Value::Null),
+    ))
+}
+
+/// Terraform outputs of a deployment, with sensitive values masked unless `unmask` is set.
+/// The router checks that the caller may unmask outputs before passing `unmask` on
+pub async fn get_deployment_outputs(payload: &Value) -> Result<Value> {
+    use env_common::interface::GenericCloudHandler;
+
+    let project = get_param!(payload, "project");
+    let region = get_param!(payload, "region");
+    let deployment_id = get_param!(payload, "deployment_id");
+    let environment = get_param!(payload, "environment");
+    let unmask = payload
+        .get("unmask")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let handler = GenericCloudHandler::workload(project, region).await;
+    env_common::logic::get_deployment_outputs(&handler, deployment_id, environment, unmask).await
 }
 
 pub async fn describe_plan_deployment(payload: &Value) -> Result<Value> {
@@ -28,11 +75,23 @@ pub async fn describe_plan_deployment(payload: &Value) -> Result<Value> {
 }
 
 pub async fn get_deployments(payload: &Value) -> Result<Value> {
-    api_common::get_deployments_impl(&Backend, payload, get_all_deployments_query).await
+    api_common::get_deployments_impl(
+        &Backend,
+        payload,
+        get_all_deployments_query,
+        with_attribute_filters,
+    )
+    .await
 }
 
 pub async fn get_modules(payload: &Value) -> Result<Value> {
-    api_common::get_modules_impl(&Backend, payload, get_all_latest_modules_query).await
+    api_common::get_modules_impl(
+        &Backend,
+        payload,
+        get_all_latest_modules_query,
+        with_attribute_filters,
+    )
+    .await
 }
 
 pub async fn get_projects(payload: &Value) -> Result<Value> {
@@ -97,6 +156,18 @@ pub async fn get_module_version(payload: &Value) -> Result<Value> {
     api_common::get_module_version_impl(&Backend, payload, get_module_version_query).await
 }
 
+/// README and changelog bundled with a module version, null for versions published without them
+pub async fn get_module_readme(payload: &Value) -> Result<Value> {
+    let module_version =
+        api_common::get_module_version_impl(&Backend, payload, get_module_version_query).await?;
+    Ok(json!({
+        "module": module_version.get("module"),
+        "version": module_version.get("version"),
+        "readme": module_version.get("readme"),
+        "changelog": module_version.get("changelog"),
+    }))
+}
+
 pub async fn get_module_download_url(payload: &Value) -> Result<Response> {
     let module_version =
         api_common::get_module_version_impl(&Backend, payload, get_module_version_query).await?;
@@ -196,7 +267,13 @@ pub async fn get_events(payload: &Value) -> Result<Value> {
 }
 
 pub async fn get_change_record(payload: &Value) -> Result<Value> {
-    api_common::get_change_record_impl(&Backend, payload, get_change_records_query).await
+    api_common::get_change_record_impl(
+        &Backend,
+        payload,
+        get_change_records_query,
+        get_change_record_parts_query,
+    )
+    .await
 }
 
 pub async fn get_deployment_history(payload: &Value) -> Result<Value> {
@@ -209,17 +286,48 @@ pub async fn get_deployment_history(payload: &Value) -> Result<Value> {
     .await
 }
 
+/// Filter of the graph endpoints from their comma-separated query parameters, such as
+/// `include_modules=module.vpc&include_actions=delete`
+fn graph_filter(payload: &Value) -> graph::GraphFilter {
+    let list = |key: &str| -> Vec<String> {
+        payload
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    graph::GraphFilter {
+        include_modules: list("include_modules"),
+        exclude_modules: list("exclude_modules"),
+        include_types: list("include_types"),
+        exclude_types: list("exclude_types"),
+        include_actions: list("include_actions"),
+        exclude_actions: list("exclude_actions"),
+    }
+}
+
 pub async fn get_change_record_graph(payload: &Value) -> Result<Response> {
     info!("get_change_record_graph payload: {:?}", payload);
-    let change_record =
-        match api_common::get_change_record_impl(&Backend, payload, get_change_records_query).await
-        {
-            Ok(cr) => cr,
-            Err(e) => {
-                log::error!("Failed to fetch change record: {:?}", e);
-                return Err(e);
-            }
-        };
+    let change_record = match api_common::get_change_record_impl(
+        &Backend,
+        payload,
+        get_change_records_query,
+        get_change_record_parts_query,
+    )
+    .await
+    {
+        Ok(cr) => cr,
+        Err(e) => {
+            log::error!("Failed to fetch change record: {:?}", e);
+            return Err(e);
+        }
+    };
 
     let plan_key = change_record
         .get("plan_raw_json_key")
@@ -266,8 +374,15 @@ pub async fn get_change_record_graph(payload: &Value) -> Result<Response> {
     info!("Graph content preview: {:.500}", graph_content);
 
     // let graph = json!({}); // Placeholder until tofu is imported
-    let graph = graph::process_graph(&plan_content, &graph_content, true, None)
-        .map_err(|e| anyhow!("Failed to process graph: {}", e))?;
+    let graph = graph::process_graph(
+        &plan_content,
+        &graph_content,
+        true,
+        None,
+        &graph::GraphOptions::default(),
+    )
+    .map_err(|e| anyhow!("Failed to process graph: {}", e))?;
+    let graph = graph::filter_graph(graph, &graph_filter(payload));
 
     info!(
         "Processed graph nodes: {}, edges: {}",
@@ -338,8 +453,15 @@ pub async fn get_deployment_graph(payload: &Value) -> Result<Response> {
     let graph_content = download_file_as_string(&container_name, &graph_key).await?;
 
     // let graph = json!({}); // Placeholder until tofu is imported
-    let graph = graph::process_graph(&state_content, &graph_content, true, None)
-        .map_err(|e| anyhow!("Failed to process graph: {}", e))?;
+    let graph = graph::process_graph(
+        &state_content,
+        &graph_content,
+        true,
+        None,
+        &graph::GraphOptions::default(),
+    )
+    .map_err(|e| anyhow!("Failed to process graph: {}", e))?;
+    let graph = graph::filter_graph(graph, &graph_filter(payload));
 
     info!(
         "Processed graph nodes: {}, edges: {}",
@@ -408,6 +530,85 @@ pub async fn deprecate_stack(payload: &Value) -> Result<Value> {
     }))
 }
 
+/// Yanks a module version, or lifts its yank when `undo` is set. The version record is updated
+/// in all regions
+pub async fn yank_module(payload: &Value) -> Result<Value> {
+    yank_version(payload, false).await
+}
+
+/// Yanks a stack version, or lifts its yank when `undo` is set
+pub async fn yank_stack(payload: &Value) -> Result<Value> {
+    yank_version(payload, true).await
+}
+
+async fn yank_version(payload: &Value, is_stack: bool) -> Result<Value> {
+    use env_common::interface::GenericCloudHandler;
+    use env_common::logic::{unyank_module, unyank_stack, yank_module, yank_stack};
+
+    let kind = if is_stack { "Stack" } else { "Module" };
+    let name = if is_stack {
+        get_param!(payload, "stack")
+    } else {
+        get_param!(payload, "module")
+    };
+    let track = get_param!(payload, "track");
+    let version = get_param!(payload, "version");
+    let yanked_by = get_param!(payload, "yanked_by");
+    let reason = payload.get("reason").and_then(|v| v.as_str());
+    let replacement_version = payload.get("replacement_version").and_then(|v| v.as_str());
+    let undo = payload
+        .get("undo")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let handler = GenericCloudHandler::default().await;
+    if undo {
+        if is_stack {
+            unyank_stack(&handler, name, track, version).await?;
+        } else {
+            unyank_module(&handler, name, track, version).await?;
+        }
+        info!("{} {} version {} is no longer yanked", kind, name, version);
+        return Ok(json!({
+            "success": true,
+            "message": format!("{} {} version {} in track {} is no longer yanked", kind, name, version, track),
+            "yanked": null
+        }));
+    }
+    let yank = if is_stack {
+        yank_stack(
+            &handler,
+            name,
+            track,
+            version,
+            reason,
+            replacement_version,
+            Some(yanked_by),
+        )
+        .await?
+    } else {
+        yank_module(
+            &handler,
+            name,
+            track,
+            version,
+            reason,
+            replacement_version,
+            Some(yanked_by),
+        )
+        .await?
+    };
+    info!(
+        "{} {} version {} yanked by {}",
+        kind, name, version, yanked_by
+    );
+    Ok(json!({
+        "success": true,
+        "message": format!("{} {} version {} in track {} has been yanked", kind, name, version, track),
+        "yanked": yank
+    }))
+}
+
 pub async fn publish_module(payload: &Value) -> Result<Value> {
     use env_defs::ModuleResp;
 
@@ -487,10 +688,7 @@ pub async fn handle_lambda_invocation(
 
     match env_aws_direct::run_function(&None, &payload, &project_id, &region).await {
         Ok(response) => (axum::http::StatusCode::OK, axum::Json(response.payload)).into_response(),
-        Err(e) => (
-            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-            axum::Json(json!({"error": e.to_string()})),
-        )
+        Err(e) => ApiError::new(axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
             .into_response(),
     }
 }
@@ -1,8 +1,11 @@
 use axum::{
-    extract::{Path, Query, Request},
+    extract::{Path, Query, Request, State},
     http::{HeaderMap, Method, StatusCode},
     middleware::{self, Next},
-    response::{IntoResponse, Json, Response},
+    response::{
+        sse::{KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
     routing::{get, post, put},
     Router,
 };
@@ -12,9 +15,14 @@ use serde_json::{json, Value};
 use std::collections::HashMap;
 use tower_http::cors::{Any, CorsLayer};
 
-use env_common::errors::ModuleError;
+use env_common::errors::{ApprovalError, ModuleError};
+use env_defs::CloudHandlerError;
 
+use crate::api_error::{request_id_middleware, ApiError};
+use crate::auth_handler::Permission;
 use crate::handlers;
+use crate::job_stream;
+use crate::summary;
 
 fn status_code_for_module_error(e: &ModuleError) -> StatusCode {
     match e {
@@ -33,6 +41,7 @@ fn status_code_for_module_error(e: &ModuleError) -> StatusCode {
         | ModuleError::DuplicateClaimNames(_)
         | ModuleError::CircularDependency(_)
         | ModuleError::SelfReferencingClaim(_, _, _)
+        | ModuleError::DependsOnClaimNotFound(_, _)
         | ModuleError::StackModuleNamespaceIsSet(_)
         | ModuleError::TerraformLockfileExists()
         | ModuleError::TerraformLockfileEmpty
@@ -43,13 +52,30 @@ fn status_code_for_module_error(e: &ModuleError) -> StatusCode {
         | ModuleError::StackClaimReferenceNotFound(_, _, _, _)
         | ModuleError::UnresolvedReference(_, _) => StatusCode::BAD_REQUEST,
         ModuleError::ModuleVersionNotFound(_, _) => StatusCode::NOT_FOUND,
+        ModuleError::Other(e) => e
+            .downcast_ref::<CloudHandlerError>()
+            .map(status_code_for_cloud_error)
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
         ModuleError::UploadModuleError(_)
         | ModuleError::ZipError(_)
-        | ModuleError::PublishError(_)
-        | ModuleError::Other(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        | ModuleError::PublishError(_) => StatusCode::INTERNAL_SERVER_ERROR,
     }
 }
 
+fn status_code_for_approval_error(e: &ApprovalError) -> StatusCode {
+    match e {
+        ApprovalError::NotPending(_, _) => StatusCode::NOT_FOUND,
+        ApprovalError::NotAuthorized(_, _) | ApprovalError::SelfApproval(_) => {
+            StatusCode::FORBIDDEN
+        }
+        ApprovalError::AlreadyApproved(_) => StatusCode::CONFLICT,
+    }
+}
+
+fn status_code_for_cloud_error(e: &CloudHandlerError) -> StatusCode {
+    StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
 // Helper function to handle responses consistently
 async fn handle_result(result: anyhow::Result<Value>) -> impl IntoResponse {
     match result {
@@ -85,6 +111,10 @@ async fn handle_result(result: anyhow::Result<Value>) -> impl IntoResponse {
             // Try to downcast to ModuleError for typed status code mapping
             let status = if let Some(module_err) = e.downcast_ref::<ModuleError>() {
                 status_code_for_module_error(module_err)
+            } else if let Some(approval_err) = e.downcast_ref::<ApprovalError>() {
+                status_code_for_approval_error(approval_err)
+            } else if let Some(cloud_err) = e.downcast_ref::<CloudHandlerError>() {
+                status_code_for_cloud_error(cloud_err)
             } else if err_msg.to_lowercase().contains("not found") {
                 StatusCode::NOT_FOUND
             } else {
@@ -95,26 +125,43 @@ async fn handle_result(result: anyhow::Result<Value>) -> impl IntoResponse {
                 error!("Request failed: {:?}", e);
             }
 
-            // Include full error details (including cause chain) for internal server errors
+            // Include the cause chain in the details of internal server errors
             // This aids debugging client-side when using the API directly
-            let response_msg = if status == StatusCode::INTERNAL_SERVER_ERROR {
-                format!("{:?}", e)
+            let error = ApiError::new(status, err_msg);
+            if status == StatusCode::INTERNAL_SERVER_ERROR {
+                let causes: Vec<String> = e.chain().skip(1).map(|c| c.to_string()).collect();
+                error
+                    .with_details(json!({ "causes": causes }))
+                    .into_response()
             } else {
-                err_msg
-            };
+                error.into_response()
+            }
+        }
+    }
+}
 
+// Helper to wrap list responses in a `{ items, next_token }` envelope for paginated requests
+async fn handle_paginated_result(result: anyhow::Result<Value>, paginated: bool) -> Response {
+    match result {
+        Ok(mut response) if paginated && response.get("Items").is_some() => {
+            let items = response["Items"].take();
+            let next_token = response.get("next_token").cloned().unwrap_or(Value::Null);
             (
-                status,
+                StatusCode::OK,
                 Json(json!({
-                    "error": response_msg
+                    "items": items,
+                    "next_token": next_token
                 })),
             )
                 .into_response()
         }
+        result => handle_result(result).await.into_response(),
     }
 }
 
+/// Middleware that enforces the permission a route requires in the projects of its path
 async fn auth_middleware(
+    State(required): State<Permission>,
     headers: HeaderMap,
     Path(params): Path<HashMap<String, String>>,
     request: Request,
@@ -124,7 +171,7 @@ async fn auth_middleware(
         for project in project_param.split(',') {
             let p = project.trim();
             if !p.is_empty() {
-                if let Err(e) = ensure_access(&headers, p).await {
+                if let Err(e) = ensure_access(&headers, p, required).await {
                     return e.into_response();
                 }
             }
@@ -136,19 +183,23 @@ async fn auth_middleware(
 /// Middleware that enforces publish permissions based on JWT claims.
 ///
 /// Extracts the resource type from the URL path and the resource name from
-/// either path parameters (for deprecate) or the request body (for publish).
+/// either path parameters (for deprecate and yank) or the request body (for publish).
 /// Checks the `custom:publish_permissions` JWT claim for a matching pattern.
 async fn publish_auth_middleware(headers: HeaderMap, request: Request, next: Next) -> Response {
     let path = request.uri().path().to_string();
     let method = request.method().clone();
 
     // Determine resource type and name from the request
-    let (resource_type, resource_name) = if method == Method::PUT && path.contains("/deprecate") {
-        // Deprecate routes:
+    let is_deprecate = method == Method::PUT && path.contains("/deprecate");
+    let is_yank = method == Method::POST && path.ends_with("/yank");
+    let (resource_type, resource_name) = if is_deprecate || is_yank {
+        // Deprecate and yank routes:
         //   /api/v1/module/:track/:module/:version/deprecate
         //   /api/v1/stack/:track/:stack/:version/deprecate
+        //   /api/v1/module/:track/:module/:version/yank
+        //   /api/v1/stack/:track/:stack/:version/yank
         let segments: Vec<&str> = path.split('/').collect();
-        // segments: ["", "api", "v1", "module"|"stack", track, name, version, "deprecate"]
+        // segments: ["", "api", "v1", "module"|"stack", track, name, version, "deprecate"|"yank"]
         if segments.len() >= 6 {
             let res_type = if segments[3] == "stack" {
                 "stack"
@@ -157,10 +208,7 @@ async fn publish_auth_middleware(headers: HeaderMap, request: Request, next: Nex
             };
             (res_type.to_string(), segments[5].to_string())
         } else {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({ "error": "Invalid deprecate path" })),
-            )
+            return ApiError::new(StatusCode::BAD_REQUEST, "Invalid deprecate or yank path")
                 .into_response();
         }
     } else if method == Method::POST {
@@ -169,21 +217,18 @@ async fn publish_auth_middleware(headers: HeaderMap, request: Request, next: Nex
         let bytes = match axum::body::to_bytes(body, 512 * 1024 * 1024).await {
             Ok(b) => b,
             Err(e) => {
-                return (
+                return ApiError::new(
                     StatusCode::BAD_REQUEST,
-                    Json(json!({ "error": format!("Failed to read request body: {}", e) })),
+                    format!("Failed to read request body: {}", e),
                 )
-                    .into_response();
+                .into_response();
             }
         };
 
         let body_json: Value = match serde_json::from_slice(&bytes) {
             Ok(v) => v,
             Err(e) => {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({ "error": format!("Invalid JSON body: {}", e) })),
-                )
+                return ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid JSON body: {}", e))
                     .into_response();
             }
         };
@@ -213,10 +258,7 @@ async fn publish_auth_middleware(headers: HeaderMap, request: Request, next: Nex
                 .to_string();
             ("provider".to_string(), name)
         } else {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({ "error": "Unknown publish endpoint" })),
-            )
+            return ApiError::new(StatusCode::BAD_REQUEST, "Unknown publish endpoint")
                 .into_response();
         };
 
@@ -228,14 +270,14 @@ async fn publish_auth_middleware(headers: HeaderMap, request: Request, next: Nex
         }
         return next.run(request).await;
     } else {
-        return (
+        return ApiError::new(
             StatusCode::METHOD_NOT_ALLOWED,
-            Json(json!({ "error": "Unsupported method for publish endpoint" })),
+            "Unsupported method for publish endpoint",
         )
-            .into_response();
+        .into_response();
     };
 
-    // Check permissions (for non-POST paths like deprecate)
+    // Check permissions (for paths naming the resource, like deprecate and yank)
     if let Err(e) = ensure_publish_access(&headers, &resource_type, &resource_name).await {
         return e.into_response();
     }
@@ -267,6 +309,8 @@ pub fn create_router() -> Router {
             "/api/v1/deployments/{project}/{region}",
             get(get_deployments),
         )
+        // Pre-aggregated summary for the home page of a UI
+        .route("/api/v1/summary/{project}/{region}", get(get_summary))
         .route(
             "/api/v1/deployments/module/{project}/{region}/{module}",
             get(get_deployments_for_module),
@@ -282,6 +326,11 @@ pub fn create_router() -> Router {
         )
         .route("/api/v1/logs/{project}/{region}/{job_id}", get(read_logs))
         .route("/api/v1/events/{project}/{region}/{*rest}", get(get_events))
+        // Deliveries to the webhooks of a deployment, including dead-lettered ones
+        .route(
+            "/api/v1/webhook_deliveries/{project}/{region}/{*rest}",
+            get(get_webhook_deliveries),
+        )
         .route(
             "/api/v1/change_record/{project}/{region}/{*rest}",
             get(get_change_record),
@@ -296,14 +345,45 @@ pub fn create_router() -> Router {
         )
         // Provider download route - returns base64 content (requires auth)
         .route("/api/v1/provider/download", post(download_provider))
-        // Plan/Apply/Destroy operations
+        // Plan/Apply/Destroy operations, the permission depends on the command in the body
         .route("/api/v1/claim/run", post(run_claim))
+        // Jobs held for approval
+        .route(
+            "/api/v1/approvals/{project}/{region}",
+            get(get_pending_approvals),
+        )
+        // Running and queued jobs of projects that limit their concurrent jobs
+        .route("/api/v1/job_queue/{project}/{region}", get(get_job_queue))
         // Job status route - use wildcard to handle ARNs with slashes
         .route(
             "/api/v1/job_status/{project}/{region}/{*rest}",
             get(get_job_status_http),
         )
-        .layer(middleware::from_fn(auth_middleware));
+        // Server-sent events of the job status and logs, use wildcard to handle ARNs with slashes
+        .route(
+            "/api/v1/stream/job/{project}/{region}/{*rest}",
+            get(stream_job),
+        )
+        .layer(middleware::from_fn_with_state(
+            Permission::Read,
+            auth_middleware,
+        ));
+
+    // Routes that start or end jobs of a project
+    let apply_protected_routes = Router::new()
+        // Deciding on a job held for approval starts or ends the job
+        .route(
+            "/api/v1/approvals/{project}/{region}/approve",
+            post(approve_job),
+        )
+        .route(
+            "/api/v1/approvals/{project}/{region}/reject",
+            post(reject_job),
+        )
+        .layer(middleware::from_fn_with_state(
+            Permission::Apply,
+            auth_middleware,
+        ));
 
     // Open routes / Global lookups
     let open_routes = Router::new()
@@ -329,6 +409,10 @@ pub fn create_router() -> Router {
             "/api/v1/module/{track}/{module_name}/{module_version}/download",
             get(get_module_download_url),
         )
+        .route(
+            "/api/v1/module/{track}/{module_name}/{module_version}/readme",
+            get(get_module_readme),
+        )
         .route(
             "/api/v1/stack/{track}/{stack_name}/{stack_version}",
             get(get_stack_version),
@@ -372,6 +456,15 @@ pub fn create_router() -> Router {
             "/api/v1/stack/{track}/{stack}/{version}/deprecate",
             put(deprecate_stack),
         )
+        // Module and stack yank routes
+        .route(
+            "/api/v1/module/{track}/{module}/{version}/yank",
+            post(yank_module),
+        )
+        .route(
+            "/api/v1/stack/{track}/{stack}/{version}/yank",
+            post(yank_stack),
+        )
         // Module publish route - accepts pre-built modules
         .route("/api/v1/module/publish", post(publish_module))
         // Stack publish route - accepts pre-built stacks (same format as modules)
@@ -382,9 +475,12 @@ pub fn create_router() -> Router {
 
     open_routes
         .merge(protected_routes)
+        .merge(apply_protected_routes)
         .merge(publish_protected_routes)
         // Add CORS layer
         .layer(cors)
+        // Outermost, so every response including the rejections of the other layers gets the ID
+        .layer(middleware::from_fn(request_id_middleware))
     // NOTE: CompressionLayer removed because API Gateway v2 HTTP API strips the
     // Content-Encoding header, causing clients to receive compressed data without
     // knowing it's compressed. Use CloudFront for compression instead.
@@ -428,10 +524,15 @@ fn extract_jwt_claims(headers: &HeaderMap) -> Option<Value> {
     }
 }
 
+/// Ensure the authenticated user may access a project and has the `required` permission in it.
+///
+/// Access to the project is based on the JWT allowed projects claim, the permission on the
+/// project permissions claim (see [`ensure_permission`]).
 async fn ensure_access(
     headers: &HeaderMap,
     project_id: &str,
-) -> Result<(), (StatusCode, axum::response::Json<serde_json::Value>)> {
+    required: Permission,
+) -> Result<(), ApiError> {
     if let Some(_user_id) = headers.get("x-auth-user").and_then(|v| v.to_str().ok()) {
         // Extract JWT claims from Authorization header
         if let Some(claims) = extract_jwt_claims(headers) {
@@ -445,13 +546,11 @@ async fn ensure_access(
                     .collect();
 
                 if allowed_projects.contains(&project_id.to_string()) {
-                    return Ok(());
+                    return ensure_permission(&claims, project_id, required);
                 } else {
-                    return Err((
+                    return Err(ApiError::new(
                         StatusCode::FORBIDDEN,
-                        Json(json!({
-                            "error": "Access denied to this project"
-                        })),
+                        "Access denied to this project",
                     ));
                 }
             }
@@ -462,11 +561,9 @@ async fn ensure_access(
             "User has no '{}' claim in JWT; denying access to project",
             crate::auth_handler::allowed_projects_claim_key(),
         );
-        Err((
+        Err(ApiError::new(
             StatusCode::FORBIDDEN,
-            Json(json!({
-                "error": "Access denied: no allowed_projects claim found in token"
-            })),
+            "Access denied: no allowed_projects claim found in token",
         ))
     } else {
         #[cfg(feature = "local")]
@@ -479,16 +576,44 @@ async fn ensure_access(
         }
         #[cfg(not(feature = "local"))]
         {
-            Err((
+            Err(ApiError::new(
                 StatusCode::UNAUTHORIZED,
-                Json(json!({
-                    "error": "Missing authentication user context"
-                })),
+                "Missing authentication user context",
             ))
         }
     }
 }
 
+/// Ensure the JWT project permissions claim grants `required` in a project.
+///
+/// The claim key is configurable via `AUTH_PROJECT_PERMISSIONS_CLAIM` env var
+/// (default: `custom:project_permissions`), see [`crate::auth_handler::has_project_permission`]
+/// for its format. Tokens without the claim have every permission in their allowed projects.
+fn ensure_permission(
+    claims: &Value,
+    project_id: &str,
+    required: Permission,
+) -> Result<(), ApiError> {
+    let claim_key = crate::auth_handler::project_permissions_claim_key();
+    match claims.get(&claim_key).and_then(|v| v.as_str()) {
+        Some(grants)
+            if !crate::auth_handler::has_project_permission(grants, project_id, required) =>
+        {
+            log::warn!(
+                "User denied {} permission in project {}",
+                required,
+                project_id
+            );
+            Err(ApiError::new(StatusCode::FORBIDDEN, format!(
+                        "You do not have the {} permission in project {}. Contact your administrator to update your project_permissions.",
+                        required,
+                        project_id
+                    )))
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Check if any of the user's publish permission patterns authorize the given resource.
 ///
 /// Patterns are comma-separated in the JWT publish permissions claim
@@ -562,7 +687,7 @@ async fn ensure_publish_access(
     headers: &HeaderMap,
     resource_type: &str,
     resource_name: &str,
-) -> Result<(), (StatusCode, axum::response::Json<serde_json::Value>)> {
+) -> Result<(), ApiError> {
     let resource_desc = format!("{}/{}", resource_type, resource_name);
 
     if let Some(_user_id) = headers.get("x-auth-user").and_then(|v| v.to_str().ok()) {
@@ -577,32 +702,24 @@ async fn ensure_publish_access(
                     .collect();
 
                 if matches_publish_permission(&permissions, resource_type, resource_name) {
+                    // Publishing isn't tied to a project, it needs a grant for all of them
+                    ensure_permission(&claims, "*", Permission::Publish)?;
                     log::info!("User authorized to publish {} via JWT claim", resource_desc);
                     return Ok(());
                 } else {
                     log::warn!("User denied publish access to {}", resource_desc,);
-                    return Err((
-                        StatusCode::FORBIDDEN,
-                        Json(json!({
-                            "error": format!(
+                    return Err(ApiError::new(StatusCode::FORBIDDEN, format!(
                                 "You do not have permission to publish {}. Your publish_permissions ({}) do not match this resource. Contact your administrator to update your permissions.",
                                 resource_desc,
                                 perms_str
-                            )
-                        })),
-                    ));
+                            )));
                 }
             }
         }
 
         // No publish_permissions claim found at all → deny
         log::warn!("User has no publish_permissions claim in JWT");
-        Err((
-            StatusCode::FORBIDDEN,
-            Json(json!({
-                "error": "You do not have permission to publish. No publish_permissions claim found. Contact your administrator to configure publish access."
-            })),
-        ))
+        Err(ApiError::new(StatusCode::FORBIDDEN, "You do not have permission to publish. No publish_permissions claim found. Contact your administrator to configure publish access."))
     } else {
         #[cfg(feature = "local")]
         {
@@ -614,11 +731,54 @@ async fn ensure_publish_access(
         }
         #[cfg(not(feature = "local"))]
         {
-            Err((
+            Err(ApiError::new(
                 StatusCode::UNAUTHORIZED,
-                Json(json!({
-                    "error": "Missing authentication user context"
-                })),
+                "Missing authentication user context",
+            ))
+        }
+    }
+}
+
+/// Ensure the authenticated user may read the unmasked sensitive outputs of deployments in a project.
+///
+/// Based on the JWT unmask outputs claim, a comma-separated list of project ids or `*` for all
+/// projects. The claim key is configurable via `AUTH_UNMASK_OUTPUTS_CLAIM` env var
+/// (default: `custom:unmask_outputs`). Without the claim, only masked outputs can be read.
+async fn ensure_unmask_access(headers: &HeaderMap, project_id: &str) -> Result<(), ApiError> {
+    if let Some(_user_id) = headers.get("x-auth-user").and_then(|v| v.to_str().ok()) {
+        if let Some(claims) = extract_jwt_claims(headers) {
+            let claim_key = crate::auth_handler::unmask_outputs_claim_key();
+            if let Some(projects) = claims.get(&claim_key).and_then(|v| v.as_str()) {
+                if projects
+                    .split(',')
+                    .map(|s| s.trim())
+                    .any(|p| p == "*" || p == project_id)
+                {
+                    log::info!(
+                        "User authorized to unmask outputs in project {}",
+                        project_id
+                    );
+                    return Ok(());
+                }
+            }
+        }
+
+        log::warn!("User denied unmasking outputs in project {}", project_id);
+        Err(ApiError::new(StatusCode::FORBIDDEN, "You do not have permission to read unmasked outputs in this project. Contact your administrator to configure the unmask_outputs permission."))
+    } else {
+        #[cfg(feature = "local")]
+        {
+            log::warn!(
+                "Missing x-auth-user header, allowing unmasked outputs in project {} (LOCAL MODE ONLY)",
+                project_id
+            );
+            Ok(())
+        }
+        #[cfg(not(feature = "local"))]
+        {
+            Err(ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "Missing authentication user context",
             ))
         }
     }
@@ -640,13 +800,14 @@ async fn describe_plan_deployment(
     let parts: Vec<&str> = rest.split('/').collect();
     if parts.len() < 3 {
         // minimal: env/dep/job_id (Assuming env and dep are at least 1 segment)
-        return (
+        return ApiError::new(
             StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": format!("Invalid path format. Expected .../environment/deployment/job_id, got {}", rest)
-            })),
+            format!(
+                "Invalid path format. Expected .../environment/deployment/job_id, got {}",
+                rest
+            ),
         )
-            .into_response();
+        .into_response();
     }
 
     let job_id = parts.last().unwrap().to_string();
@@ -659,13 +820,7 @@ async fn describe_plan_deployment(
 
     // Let's assume the standard 2-segment structure if possible, but match what describe_deployment does
     if parts.len() != 5 {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": format!("Invalid path format. Expected exactly 5 segments (env1/env2/dep1/dep2/job_id), got {}", parts.len())
-            })),
-        )
-            .into_response();
+        return ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid path format. Expected exactly 5 segments (env1/env2/dep1/dep2/job_id), got {}", parts.len())).into_response();
     }
 
     let environment = format!("{}/{}", parts[0], parts[1]);
@@ -686,7 +841,9 @@ async fn describe_plan_deployment(
 }
 
 async fn describe_deployment(
+    headers: HeaderMap,
     Path((project, region, rest)): Path<(String, String, String)>,
+    Query(query): Query<DeploymentOutputsQuery>,
 ) -> impl IntoResponse {
     // Middleware handles auth check
 
@@ -694,14 +851,36 @@ async fn describe_deployment(
     // Expected format: environment1/environment2/deployment1/deployment2
     let parts: Vec<&str> = rest.split('/').collect();
 
+    // Outputs of the deployment: environment1/environment2/deployment1/deployment2/outputs
+    if parts.len() == 5 && parts[4] == "outputs" {
+        if query.unmask {
+            if let Err(e) = ensure_unmask_access(&headers, &project).await {
+                return e.into_response();
+            }
+        }
+        return handle_result(
+            handlers::get_deployment_outputs(&json!({
+                "project": project,
+                "region": region,
+                "environment": format!("{}/{}", parts[0], parts[1]),
+                "deployment_id": format!("{}/{}", parts[2], parts[3]),
+                "unmask": query.unmask,
+            }))
+            .await,
+        )
+        .await
+        .into_response();
+    }
+
     if parts.len() != 4 {
-        return (
+        return ApiError::new(
             StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": format!("Invalid path format. Expected exactly 4 segments (env1/env2/dep1/dep2), got {}", parts.len())
-            })),
+            format!(
+                "Invalid path format. Expected exactly 4 segments (env1/env2/dep1/dep2), got {}",
+                parts.len()
+            ),
         )
-            .into_response();
+        .into_response();
     }
 
     let environment = format!("{}/{}", parts[0], parts[1]);
@@ -722,7 +901,7 @@ async fn describe_deployment(
 
 async fn get_deployments(
     Path((project, region)): Path<(String, String)>,
-    Query(query): Query<PaginationQuery>,
+    Query(query): Query<DeploymentPaginationQuery>,
 ) -> impl IntoResponse {
     let project_list: Vec<&str> = project
         .split(',')
@@ -741,16 +920,25 @@ async fn get_deployments(
         payload["project"] = json!(project);
     }
 
+    let paginated = query.limit.is_some() || query.next_token.is_some();
     if let Some(limit) = query.limit {
         payload["limit"] = json!(limit);
     }
     if let Some(next_token) = query.next_token {
         payload["next_token"] = json!(next_token);
     }
+    if let Some(status) = query.status {
+        payload["status"] = json!(status);
+    }
+    if let Some(module) = query.module {
+        payload["module"] = json!(module);
+    }
 
-    handle_result(handlers::get_deployments(&payload).await)
-        .await
-        .into_response()
+    handle_paginated_result(handlers::get_deployments(&payload).await, paginated).await
+}
+
+async fn get_summary(Path((project, region)): Path<(String, String)>) -> impl IntoResponse {
+    handle_result(summary::get_summary(&project, &region).await).await
 }
 
 async fn get_deployments_for_module(
@@ -808,12 +996,26 @@ async fn get_deployments_history(
         .into_response()
 }
 
+#[derive(Deserialize)]
+struct DeploymentOutputsQuery {
+    #[serde(default)]
+    unmask: bool,
+}
+
 #[derive(Deserialize)]
 struct PaginationQuery {
     limit: Option<i64>,
     next_token: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct DeploymentPaginationQuery {
+    limit: Option<i64>,
+    next_token: Option<String>,
+    status: Option<String>,
+    module: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct DeploymentHistoryQuery {
     limit: Option<i64>,
@@ -830,6 +1032,7 @@ struct ModulePaginationQuery {
     include_deprecated: Option<bool>,
     #[serde(default)]
     include_dev000: Option<bool>,
+    module: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -882,13 +1085,14 @@ async fn get_events(
     );
 
     if parts.len() != 4 {
-        return (
+        return ApiError::new(
             StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": format!("Invalid path format. Expected exactly 4 segments (env1/env2/dep1/dep2), got {}", parts.len())
-            })),
+            format!(
+                "Invalid path format. Expected exactly 4 segments (env1/env2/dep1/dep2), got {}",
+                parts.len()
+            ),
         )
-            .into_response();
+        .into_response();
     }
 
     let environment = format!("{}/{}", parts[0], parts[1]);
@@ -922,6 +1126,31 @@ async fn get_events(
         .into_response()
 }
 
+async fn get_webhook_deliveries(
+    Path((project, region, rest)): Path<(String, String, String)>,
+) -> impl IntoResponse {
+    // Expected format: environment1/environment2/deployment1/deployment2
+    let parts: Vec<&str> = rest.split('/').collect();
+    if parts.len() != 4 {
+        return ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Invalid path format. Expected exactly 4 segments (env1/env2/dep1/dep2), got {}",
+                parts.len()
+            ),
+        )
+        .into_response();
+    }
+    let environment = format!("{}/{}", parts[0], parts[1]);
+    let deployment_id = format!("{}/{}", parts[2], parts[3]);
+
+    let handler = env_common::interface::GenericCloudHandler::workload(&project, &region).await;
+    let result = env_common::logic::get_webhook_deliveries(&handler, &deployment_id, &environment)
+        .await
+        .map(|deliveries| json!(deliveries));
+    handle_result(result).await.into_response()
+}
+
 async fn get_change_record(
     Path((project, region, rest)): Path<(String, String, String)>,
 ) -> impl IntoResponse {
@@ -930,13 +1159,7 @@ async fn get_change_record(
     let parts: Vec<&str> = rest.split('/').collect();
 
     if parts.len() != 6 {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": format!("Invalid path format. Expected exactly 6 segments (env1/env2/dep1/dep2/job_id/change_type), got {}", parts.len())
-            })),
-        )
-            .into_response();
+        return ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid path format. Expected exactly 6 segments (env1/env2/dep1/dep2/job_id/change_type), got {}", parts.len())).into_response();
     }
 
     let environment = format!("{}/{}", parts[0], parts[1]);
@@ -961,19 +1184,14 @@ async fn get_change_record(
 
 async fn get_change_record_graph(
     Path((project, region, rest)): Path<(String, String, String)>,
+    Query(params): Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
     // Parse the rest parameter to extract environment, deployment_id, job_id, and change_type
     // Expected format: environment1/environment2/deployment1/deployment2/job_id/change_type
     let parts: Vec<&str> = rest.split('/').collect();
 
     if parts.len() != 6 {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": format!("Invalid path format. Expected exactly 6 segments (env1/env2/dep1/dep2/job_id/change_type), got {}", parts.len())
-            })),
-        )
-            .into_response();
+        return ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid path format. Expected exactly 6 segments (env1/env2/dep1/dep2/job_id/change_type), got {}", parts.len())).into_response();
     }
 
     let environment = format!("{}/{}", parts[0], parts[1]);
@@ -981,25 +1199,27 @@ async fn get_change_record_graph(
     let job_id = parts[4].to_string();
     let change_type = parts[5].to_string();
 
-    let result = handlers::get_change_record_graph(&json!({
+    let mut payload = json!({
         "project": project,
         "region": region,
         "environment": environment,
         "deployment_id": deployment_id,
         "job_id": job_id,
         "change_type": change_type
-    }))
-    .await;
+    });
+
+    // Merge the graph filter query params into payload
+    if let Some(obj) = payload.as_object_mut() {
+        for (k, v) in params {
+            obj.entry(k).or_insert(Value::String(v));
+        }
+    }
+
+    let result = handlers::get_change_record_graph(&payload).await;
 
     match result {
         Ok(response) => response,
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": format!("{}", e)
-            })),
-        )
-            .into_response(),
+        Err(e) => ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
 
@@ -1012,13 +1232,14 @@ async fn get_deployment_graph(
     let parts: Vec<&str> = rest.split('/').collect();
 
     if parts.len() != 4 {
-        return (
+        return ApiError::new(
             StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": format!("Invalid path format. Expected exactly 4 segments (env1/env2/dep1/dep2), got {}", parts.len())
-            })),
+            format!(
+                "Invalid path format. Expected exactly 4 segments (env1/env2/dep1/dep2), got {}",
+                parts.len()
+            ),
         )
-            .into_response();
+        .into_response();
     }
 
     let environment = format!("{}/{}", parts[0], parts[1]);
@@ -1042,17 +1263,12 @@ async fn get_deployment_graph(
 
     match result {
         Ok(response) => response,
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": format!("{}", e)
-            })),
-        )
-            .into_response(),
+        Err(e) => ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
 
 async fn get_modules(Query(query): Query<ModulePaginationQuery>) -> impl IntoResponse {
+    let paginated = query.limit.is_some() || query.next_token.is_some();
     let mut payload = json!({});
     if let Some(limit) = query.limit {
         payload["limit"] = json!(limit);
@@ -1066,7 +1282,10 @@ async fn get_modules(Query(query): Query<ModulePaginationQuery>) -> impl IntoRes
     if let Some(include_dev000) = query.include_dev000 {
         payload["include_dev000"] = json!(include_dev000);
     }
-    handle_result(handlers::get_modules(&payload).await).await
+    if let Some(module) = query.module {
+        payload["module"] = json!(module);
+    }
+    handle_paginated_result(handlers::get_modules(&payload).await, paginated).await
 }
 
 async fn get_projects(
@@ -1083,11 +1302,11 @@ async fn get_projects(
             }
             #[cfg(not(feature = "local"))]
             {
-                return (
+                return ApiError::new(
                     StatusCode::UNAUTHORIZED,
-                    Json(json!({ "error": "Missing authentication user context" })),
+                    "Missing authentication user context",
                 )
-                    .into_response();
+                .into_response();
             }
         }
     };
@@ -1200,6 +1419,20 @@ async fn get_module_version(
     .await
 }
 
+async fn get_module_readme(
+    Path((track, module_name, module_version)): Path<(String, String, String)>,
+) -> impl IntoResponse {
+    handle_result(
+        handlers::get_module_readme(&json!({
+            "track": track,
+            "module_name": module_name,
+            "module_version": module_version
+        }))
+        .await,
+    )
+    .await
+}
+
 async fn get_module_download_url(
     Path((track, module_name, module_version)): Path<(String, String, String)>,
 ) -> impl IntoResponse {
@@ -1212,13 +1445,7 @@ async fn get_module_download_url(
 
     match result {
         Ok(response) => response,
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": format!("{}", e)
-            })),
-        )
-            .into_response(),
+        Err(e) => ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
 
@@ -1248,13 +1475,7 @@ async fn get_stack_download_url(
 
     match result {
         Ok(response) => response,
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": format!("{}", e)
-            })),
-        )
-            .into_response(),
+        Err(e) => ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
 
@@ -1330,13 +1551,7 @@ async fn get_provider_download_url(
 
     match result {
         Ok(response) => response,
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": format!("{}", e)
-            })),
-        )
-            .into_response(),
+        Err(e) => ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
 
@@ -1377,6 +1592,85 @@ async fn deprecate_stack(
     .await
 }
 
+#[derive(Deserialize)]
+struct YankModuleBody {
+    reason: Option<String>,
+    replacement_version: Option<String>,
+    #[serde(default)]
+    undo: bool,
+}
+
+async fn yank_module(
+    headers: HeaderMap,
+    Path((track, module, version)): Path<(String, String, String)>,
+    Json(body): Json<YankModuleBody>,
+) -> Response {
+    let yanked_by = match yank_caller(&headers) {
+        Ok(user) => user,
+        Err(e) => return e.into_response(),
+    };
+    handle_result(
+        handlers::yank_module(&json!({
+            "track": track,
+            "module": module,
+            "version": version,
+            "reason": body.reason,
+            "replacement_version": body.replacement_version,
+            "undo": body.undo,
+            "yanked_by": yanked_by
+        }))
+        .await,
+    )
+    .await
+    .into_response()
+}
+
+async fn yank_stack(
+    headers: HeaderMap,
+    Path((track, stack, version)): Path<(String, String, String)>,
+    Json(body): Json<YankModuleBody>,
+) -> Response {
+    let yanked_by = match yank_caller(&headers) {
+        Ok(user) => user,
+        Err(e) => return e.into_response(),
+    };
+    handle_result(
+        handlers::yank_stack(&json!({
+            "track": track,
+            "stack": stack,
+            "version": version,
+            "reason": body.reason,
+            "replacement_version": body.replacement_version,
+            "undo": body.undo,
+            "yanked_by": yanked_by
+        }))
+        .await,
+    )
+    .await
+    .into_response()
+}
+
+/// The authenticated user yanking a version, recorded with the yank
+fn yank_caller(headers: &HeaderMap) -> Result<String, ApiError> {
+    match headers.get("x-auth-user").and_then(|v| v.to_str().ok()) {
+        Some(user) => Ok(user.to_string()),
+        None => {
+            #[cfg(feature = "local")]
+            {
+                log::warn!("Missing x-auth-user header, using 'local-user' (LOCAL MODE ONLY)");
+                Ok("local-user".to_string())
+            }
+            #[cfg(not(feature = "local"))]
+            {
+                Err(ApiError::new(
+                    StatusCode::UNAUTHORIZED,
+                    "Missing authentication user context",
+                ))
+            }
+        }
+    }
+}
+
 #[derive(Deserialize)]
 struct PublishModuleBody {
     zip_base64: String,
@@ -1443,35 +1737,123 @@ async fn publish_provider(Json(body): Json<PublishProviderBody>) -> impl IntoRes
     .await
 }
 
-async fn run_claim(Json(body): Json<Value>) -> impl IntoResponse {
+async fn run_claim(headers: HeaderMap, Json(body): Json<Value>) -> Response {
     log::info!("Received run_claim request");
     // Body is ApiInfraPayloadWithVariables
     // Manually extract payload and variables from the JSON value
     let payload_value = match body.get("payload") {
         Some(p) => p.clone(),
-        None => return handle_result(Err(anyhow::anyhow!("Missing 'payload' field"))).await,
+        None => {
+            return handle_result(Err(anyhow::anyhow!("Missing 'payload' field")))
+                .await
+                .into_response()
+        }
     };
 
     let variables = match body.get("variables") {
         Some(v) => v.clone(),
-        None => return handle_result(Err(anyhow::anyhow!("Missing 'variables' field"))).await,
+        None => {
+            return handle_result(Err(anyhow::anyhow!("Missing 'variables' field")))
+                .await
+                .into_response()
+        }
     };
 
-    let payload: env_defs::ApiInfraPayload = match serde_json::from_value(payload_value.clone()) {
+    let payload: env_defs::ApiInfraPayload = match serde_json::from_value(payload_value) {
         Ok(p) => p,
-        Err(e) => return handle_result(Err(anyhow::anyhow!("Invalid payload: {}", e))).await,
+        Err(e) => {
+            return handle_result(Err(anyhow::anyhow!("Invalid payload: {}", e)))
+                .await
+                .into_response()
+        }
     };
+    if let Err(e) = ensure_access(
+        &headers,
+        &payload.project_id,
+        Permission::for_command(&payload.command),
+    )
+    .await
+    {
+        return e.into_response();
+    }
 
-    // Launch runner with ApiInfraPayload only (no variables to avoid size limits)
-    let result = handlers::start_runner(&json!({
-        "data": payload_value
-    }))
-    .await;
+    // Older clients don't send an idempotency key, derive it here so their jobs get one too
+    let payload =
+        env_common::logic::with_idempotency_key(&env_defs::ApiInfraPayloadWithVariables {
+            payload,
+            variables: variables.clone(),
+        })
+        .payload;
+
+    if let Some(job_id) = identical_job_in_progress(&payload).await {
+        log::info!(
+            "An identical submission is already in progress as job {}",
+            job_id
+        );
+        return handle_result(Ok(json!({ "job_id": job_id })))
+            .await
+            .into_response();
+    }
+
+    match pending_approval(&payload, &variables).await {
+        Ok(Some(job_id)) => {
+            return handle_result(Ok(json!({
+                "job_id": job_id,
+                "status": env_defs::DeploymentStatus::PendingApproval.to_string()
+            })))
+            .await
+            .into_response()
+        }
+        Ok(None) => {}
+        Err(e) => return handle_result(Err(e)).await.into_response(),
+    }
 
-    let task_arn = match result {
-        Ok(resp) => resp["task_arn"].as_str().unwrap_or("").to_string(),
-        Err(e) => return handle_result(Err(e)).await,
+    handle_result(start_or_queue_job(&payload, &variables).await)
+        .await
+        .into_response()
+}
+
+/// Launches a runner for the job, or queues the job when its project already runs as many jobs
+/// as it allows
+async fn start_or_queue_job(
+    payload: &env_defs::ApiInfraPayload,
+    variables: &serde_json::Value,
+) -> Result<Value, anyhow::Error> {
+    use env_common::interface::GenericCloudHandler;
+    use env_common::logic::JobAdmission;
+
+    let handler = GenericCloudHandler::workload(&payload.project_id, &payload.region).await;
+    let payload_with_variables = env_defs::ApiInfraPayloadWithVariables {
+        payload: payload.clone(),
+        variables: variables.clone(),
     };
+    let admission = env_common::logic::admit_job(&handler, &payload_with_variables).await?;
+    if let JobAdmission::Queued(job_id) = admission {
+        return Ok(json!({
+            "job_id": job_id,
+            "status": env_defs::DeploymentStatus::Queued.to_string()
+        }));
+    }
+
+    let resp = launch_runner(payload, variables).await?;
+    if let JobAdmission::Start(slot) = admission {
+        let job_id = resp["job_id"].as_str().unwrap_or_default();
+        env_common::logic::hold_job_slot(&handler, &payload_with_variables, slot, job_id).await?;
+    }
+    Ok(resp)
+}
+
+/// Launches a runner for the job and records its request, returning its task arn and job id
+async fn launch_runner(
+    payload: &env_defs::ApiInfraPayload,
+    variables: &serde_json::Value,
+) -> Result<Value, anyhow::Error> {
+    // Launch runner with ApiInfraPayload only (no variables to avoid size limits)
+    let resp = handlers::start_runner(&json!({
+        "data": payload
+    }))
+    .await?;
+    let task_arn = resp["task_arn"].as_str().unwrap_or("").to_string();
 
     // Extract task ID from ARN: arn:aws:ecs:region:account:task/cluster/TASK_ID
     let task_id = task_arn.split('/').last().unwrap_or(&task_arn).to_string();
@@ -1480,16 +1862,56 @@ async fn run_claim(Json(body): Json<Value>) -> impl IntoResponse {
 
     // Insert deployment record with variables into database using task ID
     // This allows the runner to query the deployment and get variables
-    if let Err(e) = insert_deployment_record(&payload, &variables, &task_id).await {
+    if let Err(e) = insert_deployment_record(payload, variables, &task_id).await {
         log::error!("Failed to insert deployment record: {}", e);
-        return handle_result(Err(e)).await;
+        return Err(e);
     }
 
-    handle_result(Ok(json!({
+    Ok(json!({
         "task_arn": task_arn,
         "job_id": task_id
-    })))
-    .await
+    }))
+}
+
+/// Job of a submission with the same idempotency key that is still running for the deployment
+async fn identical_job_in_progress(payload: &env_defs::ApiInfraPayload) -> Option<String> {
+    use env_common::interface::GenericCloudHandler;
+
+    let handler = GenericCloudHandler::workload(&payload.project_id, &payload.region).await;
+    let (in_progress, job_id, _, deployment) = env_common::logic::is_deployment_in_progress(
+        &handler,
+        &payload.deployment_id,
+        &payload.environment,
+        true,
+        false,
+    )
+    .await;
+    (in_progress && env_common::logic::is_identical_submission(deployment.as_ref(), payload))
+        .then_some(job_id)
+}
+
+/// Holds the job for approval instead of launching a runner when the project settings require it
+async fn pending_approval(
+    payload: &env_defs::ApiInfraPayload,
+    variables: &serde_json::Value,
+) -> Result<Option<String>, anyhow::Error> {
+    use env_common::interface::GenericCloudHandler;
+
+    let handler = GenericCloudHandler::workload(&payload.project_id, &payload.region).await;
+    let required_approvals =
+        env_common::get_required_approvals(&handler, &payload.environment, &payload.command)
+            .await?;
+    if required_approvals == 0 {
+        return Ok(None);
+    }
+
+    let payload_with_variables = env_defs::ApiInfraPayloadWithVariables {
+        payload: payload.clone(),
+        variables: variables.clone(),
+    };
+    env_common::submit_pending_approval(&handler, &payload_with_variables, required_approvals)
+        .await
+        .map(Some)
 }
 
 async fn insert_deployment_record(
@@ -1509,6 +1931,120 @@ async fn insert_deployment_record(
     env_common::insert_request_event(&handler, &payload_with_variables, job_id).await
 }
 
+#[derive(Deserialize)]
+struct ApprovalQuery {
+    environment: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ApprovalDecisionBody {
+    environment: String,
+    deployment_id: String,
+    #[serde(default)]
+    comment: String,
+}
+
+async fn get_pending_approvals(
+    Path((project, region)): Path<(String, String)>,
+    Query(query): Query<ApprovalQuery>,
+) -> impl IntoResponse {
+    let handler = env_common::interface::GenericCloudHandler::workload(&project, &region).await;
+    let result = env_common::logic::get_pending_approvals(
+        &handler,
+        query.environment.as_deref().unwrap_or(""),
+    )
+    .await
+    .map(|requests| json!(requests));
+    handle_result(result).await
+}
+
+async fn get_job_queue(Path((project, region)): Path<(String, String)>) -> impl IntoResponse {
+    let handler = env_common::interface::GenericCloudHandler::workload(&project, &region).await;
+    let result = env_common::logic::get_job_queue(&handler)
+        .await
+        .map(|entries| json!(entries));
+    handle_result(result).await
+}
+
+async fn approve_job(
+    headers: HeaderMap,
+    Path((project, region)): Path<(String, String)>,
+    Json(body): Json<ApprovalDecisionBody>,
+) -> impl IntoResponse {
+    decide_approval(&headers, &project, &region, body, true).await
+}
+
+async fn reject_job(
+    headers: HeaderMap,
+    Path((project, region)): Path<(String, String)>,
+    Json(body): Json<ApprovalDecisionBody>,
+) -> impl IntoResponse {
+    decide_approval(&headers, &project, &region, body, false).await
+}
+
+/// Records the decision of the authenticated user and launches the job once it is approved
+async fn decide_approval(
+    headers: &HeaderMap,
+    project: &str,
+    region: &str,
+    body: ApprovalDecisionBody,
+    approved: bool,
+) -> Response {
+    use env_common::logic::ApprovalOutcome;
+
+    let approver = match headers.get("x-auth-user").and_then(|v| v.to_str().ok()) {
+        Some(user) => user.to_string(),
+        None => {
+            #[cfg(feature = "local")]
+            {
+                log::warn!("Missing x-auth-user header, using 'local-user' (LOCAL MODE ONLY)");
+                "local-user".to_string()
+            }
+            #[cfg(not(feature = "local"))]
+            {
+                return ApiError::new(
+                    StatusCode::UNAUTHORIZED,
+                    "Missing authentication user context",
+                )
+                .into_response();
+            }
+        }
+    };
+
+    let handler = env_common::interface::GenericCloudHandler::workload(project, region).await;
+    let outcome = env_common::logic::decide_approval(
+        &handler,
+        &body.deployment_id,
+        &body.environment,
+        &approver,
+        approved,
+        &body.comment,
+    )
+    .await;
+    let result = match outcome {
+        Ok(ApprovalOutcome::Approved(submission)) => {
+            start_or_queue_job(&submission.payload, &submission.variables)
+                .await
+                .map(|mut resp| {
+                    resp["status"] = json!("approved");
+                    resp
+                })
+        }
+        Ok(ApprovalOutcome::Pending(request)) => Ok(json!({
+            "job_id": request.job_id,
+            "status": env_defs::DeploymentStatus::PendingApproval.to_string(),
+            "approvals": request.approvers().len(),
+            "required_approvals": request.required_approvals,
+        })),
+        Ok(ApprovalOutcome::Rejected(request)) => Ok(json!({
+            "job_id": request.job_id,
+            "status": env_defs::DeploymentStatus::Rejected.to_string(),
+        })),
+        Err(e) => Err(e),
+    };
+    handle_result(result).await.into_response()
+}
+
 async fn get_job_status_http(
     Path((project, region, rest)): Path<(String, String, String)>,
 ) -> impl IntoResponse {
@@ -1527,6 +2063,15 @@ async fn get_job_status_http(
     handle_result(result).await
 }
 
+async fn stream_job(
+    Path((project, region, rest)): Path<(String, String, String)>,
+) -> impl IntoResponse {
+    let job_id = rest.trim_start_matches('/').to_string();
+    log::info!("stream_job called for job: {}", job_id);
+
+    Sse::new(job_stream::job_events(project, region, job_id)).keep_alive(KeepAlive::default())
+}
+
 // Token bridge handler - generates OIDC sign-in URL or exchanges code for tokens.
 // Works with any OIDC-compliant identity provider (Cognito, Azure AD, Okta, Auth0, etc.).
 // Requires OIDC_ISSUER_URL + OIDC_CLIENT_ID (or explicit endpoint env vars) to be configured.
@@ -1547,13 +2092,11 @@ async fn handle_auth_token(headers: HeaderMap, Json(body): Json<Value>) -> impl
         match auth_handler::exchange_code_for_tokens(code, redirect_uri, code_verifier).await {
             Ok(token_response) => return (StatusCode::OK, Json(token_response)).into_response(),
             Err(e) => {
-                return (
+                return ApiError::new(
                     StatusCode::BAD_REQUEST,
-                    Json(json!({
-                        "error": format!("Failed to exchange code for tokens: {}", e)
-                    })),
+                    format!("Failed to exchange code for tokens: {}", e),
                 )
-                    .into_response();
+                .into_response();
             }
         }
     }
@@ -1563,13 +2106,7 @@ async fn handle_auth_token(headers: HeaderMap, Json(body): Json<Value>) -> impl
         let refresh_token = match body.get("refresh_token").and_then(|v| v.as_str()) {
             Some(rt) => rt,
             None => {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({
-                        "success": false,
-                        "error": "Missing refresh_token field"
-                    })),
-                )
+                return ApiError::new(StatusCode::BAD_REQUEST, "Missing refresh_token field")
                     .into_response();
             }
         };
@@ -1583,13 +2120,7 @@ async fn handle_auth_token(headers: HeaderMap, Json(body): Json<Value>) -> impl
                 } else {
                     StatusCode::INTERNAL_SERVER_ERROR
                 };
-                return (
-                    status,
-                    Json(json!({
-                        "success": false,
-                        "error": format!("Failed to refresh tokens: {}", e)
-                    })),
-                )
+                return ApiError::new(status, format!("Failed to refresh tokens: {}", e))
                     .into_response();
             }
         }
@@ -1618,13 +2149,11 @@ async fn handle_auth_token(headers: HeaderMap, Json(body): Json<Value>) -> impl
         .await
     {
         Ok(response) => (StatusCode::OK, Json(response)).into_response(),
-        Err(e) => (
+        Err(e) => ApiError::new(
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": format!("Failed to generate sign-in URL: {}", e)
-            })),
+            format!("Failed to generate sign-in URL: {}", e),
         )
-            .into_response(),
+        .into_response(),
     }
 }
 
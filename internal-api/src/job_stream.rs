@@ -0,0 +1,204 @@
+use std::convert::Infallible;
+use std::time::Duration;
+
+use axum::response::sse::Event;
+use futures::stream::{self, Stream, StreamExt};
+use serde_json::json;
+
+use crate::handlers;
+
+/// Interval between polls of the job status and logs behind a job stream
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Job statuses after which the job produces no more logs, across ECS and Container Instances
+const TERMINAL_STATUSES: [&str; 4] = ["STOPPED", "SUCCEEDED", "FAILED", "TERMINATED"];
+
+pub fn is_terminal_status(status: &str) -> bool {
+    TERMINAL_STATUSES.contains(&status.to_uppercase().as_str())
+}
+
+struct JobStream {
+    job_id: String,
+    project: String,
+    region: String,
+    /// Token to continue reading logs from, when the cloud paginates them
+    next_token: Option<String>,
+    /// Length of the logs already sent, when the cloud returns all of them on every read
+    sent_len: usize,
+    status: Option<String>,
+    done: bool,
+}
+
+impl JobStream {
+    async fn poll(&mut self) -> Vec<Event> {
+        let mut events = vec![];
+
+        let status_payload = json!({
+            "data": {
+                "job_id": self.job_id,
+                "project": self.project,
+                "region": self.region
+            }
+        });
+        let terminal = match handlers::get_job_status(&status_payload).await {
+            Ok(job_status) => {
+                let status = job_status
+                    .get("status")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("UNKNOWN")
+                    .to_string();
+                let terminal = is_terminal_status(&status);
+                if self.status.as_deref() != Some(status.as_str()) {
+                    events.push(
+                        Event::default()
+                            .event("status")
+                            .data(job_status.to_string()),
+                    );
+                    self.status = Some(status);
+                }
+                terminal
+            }
+            Err(e) => {
+                log::warn!("Failed to get status of job {}: {}", self.job_id, e);
+                false
+            }
+        };
+
+        // Logs are read after the status, and until there are no more for a finished job, so that
+        // its last lines are not lost
+        loop {
+            let lines = self.read_new_logs().await;
+            let read_all = lines.is_empty() || !terminal;
+            events.extend(
+                lines
+                    .into_iter()
+                    .map(|line| Event::default().event("log").data(line)),
+            );
+            if read_all {
+                break;
+            }
+        }
+
+        if terminal {
+            events.push(Event::default().event("done").data(json!({}).to_string()));
+            self.done = true;
+        }
+        events
+    }
+
+    async fn read_new_logs(&mut self) -> Vec<String> {
+        let mut data = json!({
+            "job_id": self.job_id,
+            "project_id": self.project,
+            "region": self.region
+        });
+        if let Some(next_token) = &self.next_token {
+            data["next_token"] = json!(next_token);
+        }
+
+        let result = match handlers::read_logs(&json!({ "data": data })).await {
+            Ok(result) => result,
+            Err(e) => {
+                log::warn!("Failed to read logs of job {}: {}", self.job_id, e);
+                return vec![];
+            }
+        };
+        let logs = result.get("logs").and_then(|v| v.as_str()).unwrap_or("");
+
+        match result.get("nextForwardToken").and_then(|v| v.as_str()) {
+            Some(token) => {
+                self.next_token = Some(token.to_string());
+                log_lines(logs)
+            }
+            None if self.next_token.is_some() => log_lines(logs),
+            None => {
+                let (lines, sent_len) = unsent_log_lines(logs, self.sent_len);
+                self.sent_len = sent_len;
+                lines
+            }
+        }
+    }
+}
+
+fn log_lines(logs: &str) -> Vec<String> {
+    logs.lines().map(str::to_string).collect()
+}
+
+/// Lines of `logs` that were not sent yet, for logs that are returned in full on every read,
+/// together with the new length of the sent logs. Only complete lines are sent, and logs that
+/// got shorter (such as a truncated tail) are sent again from the start.
+pub fn unsent_log_lines(logs: &str, sent_len: usize) -> (Vec<String>, usize) {
+    let sent_len = if sent_len > logs.len() || !logs.is_char_boundary(sent_len) {
+        0
+    } else {
+        sent_len
+    };
+    let unsent = &logs[sent_len..];
+    let complete_len = unsent.rfind('\n').map_or(0, |i| i + 1);
+    (log_lines(&unsent[..complete_len]), sent_len + complete_len)
+}
+
+/// Server-sent events of a job: a `status` event with the job status whenever it changes, a `log`
+/// event for every log line, and a `done` event once the job has stopped, which ends the stream.
+pub fn job_events(
+    project: String,
+    region: String,
+    job_id: String,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let state = JobStream {
+        job_id,
+        project,
+        region,
+        next_token: None,
+        sent_len: 0,
+        status: None,
+        done: false,
+    };
+
+    stream::unfold((state, true), |(mut state, first)| async move {
+        if state.done {
+            return None;
+        }
+        if !first {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        let events = state.poll().await;
+        Some((stream::iter(events.into_iter().map(Ok)), (state, false)))
+    })
+    .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_terminal_status() {
+        assert!(is_terminal_status("STOPPED"));
+        assert!(is_terminal_status("Succeeded"));
+        assert!(is_terminal_status("Terminated"));
+        assert!(!is_terminal_status("RUNNING"));
+        assert!(!is_terminal_status("DEPROVISIONING"));
+        assert!(!is_terminal_status("UNKNOWN"));
+    }
+
+    #[test]
+    fn test_unsent_log_lines() {
+        let (lines, sent_len) = unsent_log_lines("init\nplan", 0);
+        assert_eq!(lines, vec!["init"]);
+        assert_eq!(sent_len, 5);
+
+        let (lines, sent_len) = unsent_log_lines("init\nplan\napply\n", sent_len);
+        assert_eq!(lines, vec!["plan", "apply"]);
+        assert_eq!(sent_len, 16);
+
+        let (lines, sent_len) = unsent_log_lines("init\nplan\napply\n", sent_len);
+        assert!(lines.is_empty());
+        assert_eq!(sent_len, 16);
+
+        // A shorter tail than what was sent is sent again
+        let (lines, sent_len) = unsent_log_lines("apply\n", sent_len);
+        assert_eq!(lines, vec!["apply"]);
+        assert_eq!(sent_len, 6);
+    }
+}
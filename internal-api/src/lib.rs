@@ -1,4 +1,5 @@
 mod api_common;
+pub mod api_error;
 pub mod auth_handler;
 #[cfg(feature = "aws")]
 pub mod aws_handlers;
@@ -7,8 +8,10 @@ pub mod azure_handlers;
 mod common;
 pub mod handlers;
 pub mod http_router;
+mod job_stream;
 #[cfg(feature = "local")]
 pub mod local_setup;
 mod queries;
+mod summary;
 
 pub use common::CloudRuntime;
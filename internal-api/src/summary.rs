@@ -0,0 +1,223 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use env_defs::DeploymentResp;
+use serde_json::{json, Value};
+
+use crate::handlers;
+
+/// Number of entries in each of the lists of the summary
+const SUMMARY_LIST_LENGTH: usize = 10;
+
+/// Seconds a summary is served from the cache unless `SUMMARY_CACHE_TTL_SECONDS` is set
+const DEFAULT_SUMMARY_CACHE_TTL_SECONDS: u64 = 30;
+
+type SummaryCache = Mutex<HashMap<String, (Instant, Value)>>;
+
+static SUMMARY_CACHE: OnceLock<SummaryCache> = OnceLock::new();
+
+fn summary_cache_ttl() -> Duration {
+    let seconds = std::env::var("SUMMARY_CACHE_TTL_SECONDS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_SUMMARY_CACHE_TTL_SECONDS);
+    Duration::from_secs(seconds)
+}
+
+/// Summary of the deployments of a project in a region and of the latest published modules, for
+/// the home page of a UI. Summaries are cached, so the underlying lists are read at most once per
+/// cache TTL for each project and region.
+pub async fn get_summary(project: &str, region: &str) -> Result<Value> {
+    let cache_key = format!("{}::{}", project, region);
+    let cache = SUMMARY_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some((created, summary)) = cache.lock().unwrap().get(&cache_key) {
+        if created.elapsed() < summary_cache_ttl() {
+            return Ok(summary.clone());
+        }
+    }
+
+    let deployments_payload = json!({ "project": project, "region": region });
+    let modules_payload = json!({});
+    let (deployments, modules) = futures::try_join!(
+        handlers::get_deployments(&deployments_payload),
+        handlers::get_modules(&modules_payload),
+    )?;
+
+    let deployments: Vec<DeploymentResp> = items(&deployments)
+        .iter()
+        .filter_map(|d| match serde_json::from_value(d.clone()) {
+            Ok(deployment) => Some(deployment),
+            Err(e) => {
+                log::warn!(
+                    "Summary skipped a deployment that did not deserialize: {}",
+                    e
+                );
+                None
+            }
+        })
+        .collect();
+    let summary = summarize(&deployments, items(&modules));
+
+    cache
+        .lock()
+        .unwrap()
+        .insert(cache_key, (Instant::now(), summary.clone()));
+    Ok(summary)
+}
+
+fn items(response: &Value) -> &[Value] {
+    response
+        .get("Items")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn deployment_entry(deployment: &DeploymentResp) -> Value {
+    json!({
+        "deployment_id": deployment.deployment_id,
+        "environment": deployment.environment,
+        "module": deployment.module,
+        "module_version": deployment.module_version,
+        "status": deployment.status.to_string(),
+        "job_id": deployment.job_id,
+        "initiated_by": deployment.initiated_by,
+        "error_text": deployment.error_text,
+        "epoch": deployment.epoch,
+    })
+}
+
+/// Most recent deployments first, limited to the length of the summary lists
+fn latest_entries<'a>(deployments: impl Iterator<Item = &'a DeploymentResp>) -> Vec<Value> {
+    let mut deployments: Vec<&DeploymentResp> = deployments.collect();
+    deployments.sort_by_key(|deployment| Reverse(deployment.epoch));
+    deployments
+        .into_iter()
+        .take(SUMMARY_LIST_LENGTH)
+        .map(deployment_entry)
+        .collect()
+}
+
+pub fn summarize(deployments: &[DeploymentResp], modules: &[Value]) -> Value {
+    let deployments: Vec<&DeploymentResp> = deployments.iter().filter(|d| !d.deleted).collect();
+
+    let mut by_status: BTreeMap<String, usize> = BTreeMap::new();
+    for deployment in &deployments {
+        *by_status.entry(deployment.status.to_string()).or_default() += 1;
+    }
+
+    // Module records are summarized from their fields, without parsing the full manifest
+    let mut latest_modules: Vec<&Value> = modules.iter().collect();
+    fn timestamp(module: &Value) -> &str {
+        module
+            .get("timestamp")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+    }
+    latest_modules.sort_by(|a, b| timestamp(b).cmp(timestamp(a)));
+    let latest_modules: Vec<Value> = latest_modules
+        .into_iter()
+        .take(SUMMARY_LIST_LENGTH)
+        .map(|m| {
+            json!({
+                "module": m.get("module"),
+                "module_name": m.get("module_name"),
+                "module_type": m.get("module_type"),
+                "track": m.get("track"),
+                "version": m.get("version"),
+                "timestamp": m.get("timestamp"),
+            })
+        })
+        .collect();
+
+    let recent_failures = latest_entries(
+        deployments
+            .iter()
+            .copied()
+            .filter(|d| d.status.is_failure()),
+    );
+    let drifted_deployments = latest_entries(deployments.iter().copied().filter(|d| d.has_drifted));
+    let running_jobs = latest_entries(deployments.iter().copied().filter(|d| d.status.is_busy()));
+
+    json!({
+        "total_deployments": deployments.len(),
+        "deployments_by_status": by_status,
+        "recent_failures": recent_failures,
+        "drifted_deployments": drifted_deployments,
+        "running_jobs": running_jobs,
+        "latest_modules": latest_modules,
+        "generated_at": env_utils::get_timestamp(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deployment(id: &str, status: &str, epoch: u128, has_drifted: bool) -> DeploymentResp {
+        serde_json::from_value(json!({
+            "epoch": epoch,
+            "deployment_id": id,
+            "status": status,
+            "job_id": format!("job-{}", id),
+            "environment": "github-org-repo/prod",
+            "project_id": "123456789012",
+            "region": "eu-west-1",
+            "module": "s3bucket",
+            "module_version": "0.1.0",
+            "module_type": "module",
+            "module_track": "stable",
+            "drift_detection": {},
+            "next_drift_check_epoch": -1,
+            "has_drifted": has_drifted,
+            "variables": {},
+            "output": {},
+            "policy_results": [],
+            "error_text": "",
+            "deleted": false,
+            "dependencies": [],
+            "initiated_by": "",
+            "cpu": "",
+            "memory": "",
+            "reference": "",
+            "tf_resources": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_summarize() {
+        let deployments = vec![
+            deployment("s3bucket/a", "successful", 1, false),
+            deployment("s3bucket/b", "failed_plan", 2, false),
+            deployment("s3bucket/c", "error", 3, false),
+            deployment("s3bucket/d", "successful", 4, true),
+            deployment("s3bucket/e", "initiated", 5, false),
+        ];
+        let modules = vec![
+            json!({"module": "s3bucket", "version": "0.1.0", "timestamp": "2024-01-01T00:00:00Z"}),
+            json!({"module": "vpc", "version": "1.0.0", "timestamp": "2024-02-01T00:00:00Z"}),
+        ];
+
+        let summary = summarize(&deployments, &modules);
+
+        assert_eq!(summary["total_deployments"], 5);
+        assert_eq!(summary["deployments_by_status"]["successful"], 2);
+        assert_eq!(summary["deployments_by_status"]["failed_plan"], 1);
+        let ids = |key: &str| -> Vec<String> {
+            summary[key]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|d| d["deployment_id"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(ids("recent_failures"), vec!["s3bucket/c", "s3bucket/b"]);
+        assert_eq!(ids("drifted_deployments"), vec!["s3bucket/d"]);
+        assert_eq!(ids("running_jobs"), vec!["s3bucket/e"]);
+        assert_eq!(summary["latest_modules"][0]["module"], "vpc");
+    }
+}
@@ -0,0 +1,232 @@
+use kube::api::DynamicObject;
+use serde_json::{json, Value};
+
+use crate::defs::{DEGRADED_CONDITION, PROGRESSING_CONDITION, READY_CONDITION};
+
+/// Longest message written to a condition, the full job output is in `status.logs`
+const MAX_CONDITION_MESSAGE_LEN: usize = 1024;
+
+/// `status.conditions` of the resource
+pub fn current_conditions(resource: &DynamicObject) -> Vec<Value> {
+    resource
+        .data
+        .get("status")
+        .and_then(|s| s.get("conditions"))
+        .and_then(|c| c.as_array())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Replaces the condition of `condition_type`, keeping its `lastTransitionTime` if the status
+/// did not change as Kubernetes conventions require
+pub fn set_condition(
+    conditions: &mut Vec<Value>,
+    condition_type: &str,
+    status: &str,
+    reason: &str,
+    message: &str,
+    now: &str,
+) {
+    let existing = conditions
+        .iter()
+        .position(|c| c.get("type").and_then(|t| t.as_str()) == Some(condition_type));
+    let last_transition_time = existing
+        .map(|i| &conditions[i])
+        .filter(|c| c.get("status").and_then(|s| s.as_str()) == Some(status))
+        .and_then(|c| c.get("lastTransitionTime").and_then(|t| t.as_str()))
+        .unwrap_or(now)
+        .to_string();
+    let condition = json!({
+        "type": condition_type,
+        "status": status,
+        "reason": reason,
+        "message": truncate_message(message),
+        "lastTransitionTime": last_transition_time,
+    });
+    match existing {
+        Some(i) => conditions[i] = condition,
+        None => conditions.push(condition),
+    }
+}
+
+/// Conditions of the resource updated for a job in `resource_status`, e.g. `Apply - successful`
+/// or `Delete - in progress`. `message` is used for the failed condition
+pub fn job_conditions(
+    resource: &DynamicObject,
+    resource_status: &str,
+    message: &str,
+    now: &str,
+) -> Vec<Value> {
+    let mut conditions = current_conditions(resource);
+    let (command, state) = resource_status
+        .split_once(" - ")
+        .unwrap_or(("Apply", resource_status));
+    let deleting = command == "Delete";
+    let mut set = |condition_type, status, reason, message: &str| {
+        set_condition(
+            &mut conditions,
+            condition_type,
+            status,
+            reason,
+            message,
+            now,
+        )
+    };
+
+    match state {
+        "successful" if deleting => {
+            set(
+                READY_CONDITION,
+                "False",
+                "Deleted",
+                "Resources are destroyed",
+            );
+            set(
+                PROGRESSING_CONDITION,
+                "False",
+                "Deleted",
+                "Destroy completed",
+            );
+            set(DEGRADED_CONDITION, "False", "Deleted", "Destroy completed");
+        }
+        "successful" => {
+            set(
+                READY_CONDITION,
+                "True",
+                "Applied",
+                "Resources match the claim",
+            );
+            set(PROGRESSING_CONDITION, "False", "Applied", "Apply completed");
+            set(DEGRADED_CONDITION, "False", "Applied", "Apply completed");
+        }
+        "failed" | "error" => {
+            let reason = if deleting {
+                "DestroyFailed"
+            } else {
+                "ApplyFailed"
+            };
+            let summary = format!("{} {}", command, state);
+            set(READY_CONDITION, "False", reason, &summary);
+            set(PROGRESSING_CONDITION, "False", reason, &summary);
+            set(DEGRADED_CONDITION, "True", reason, message);
+        }
+        _ => {
+            // Ready and Degraded describe the last completed job until this one completes
+            let reason = if deleting { "Deleting" } else { "Applying" };
+            set(PROGRESSING_CONDITION, "True", reason, resource_status);
+            if deleting {
+                set(
+                    READY_CONDITION,
+                    "False",
+                    reason,
+                    "Resources are being destroyed",
+                );
+            }
+        }
+    }
+    conditions
+}
+
+fn truncate_message(message: &str) -> String {
+    let message = message.trim();
+    if message.len() <= MAX_CONDITION_MESSAGE_LEN {
+        return message.to_string();
+    }
+    let mut end = MAX_CONDITION_MESSAGE_LEN;
+    while !message.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}...", &message[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kube::api::ApiResource;
+    use pretty_assertions::assert_eq;
+
+    const EARLIER: &str = "2024-01-01T00:00:00Z";
+    const NOW: &str = "2024-01-02T00:00:00Z";
+
+    fn claim(status: Value) -> DynamicObject {
+        let api_resource = ApiResource {
+            group: "infraweave.io".to_string(),
+            version: "v1".to_string(),
+            api_version: "infraweave.io/v1".to_string(),
+            kind: "S3Bucket".to_string(),
+            plural: "s3buckets".to_string(),
+        };
+        DynamicObject::new("my-bucket", &api_resource).data(json!({ "status": status }))
+    }
+
+    fn condition<'a>(conditions: &'a [Value], condition_type: &str) -> &'a Value {
+        conditions
+            .iter()
+            .find(|c| c["type"] == condition_type)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_set_condition_keeps_transition_time_when_unchanged() {
+        let mut conditions = vec![json!({
+            "type": "Ready", "status": "True", "reason": "Applied", "lastTransitionTime": EARLIER,
+        })];
+
+        set_condition(&mut conditions, "Ready", "True", "Applied", "", NOW);
+        assert_eq!(conditions[0]["lastTransitionTime"], EARLIER);
+
+        set_condition(&mut conditions, "Ready", "False", "ApplyFailed", "", NOW);
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(conditions[0]["status"], "False");
+        assert_eq!(conditions[0]["lastTransitionTime"], NOW);
+    }
+
+    #[test]
+    fn test_job_conditions_apply_lifecycle() {
+        let resource = claim(json!({
+            "conditions": [{ "type": "Suspended", "status": "False", "lastTransitionTime": EARLIER }]
+        }));
+
+        let conditions = job_conditions(&resource, "Apply - in progress", "", NOW);
+        assert_eq!(condition(&conditions, "Progressing")["status"], "True");
+        assert!(!conditions.iter().any(|c| c["type"] == "Ready"));
+
+        let resource = claim(json!({ "conditions": conditions }));
+        let conditions = job_conditions(&resource, "Apply - failed", "ERROR: bucket exists", NOW);
+        assert_eq!(condition(&conditions, "Ready")["status"], "False");
+        assert_eq!(condition(&conditions, "Progressing")["status"], "False");
+        assert_eq!(condition(&conditions, "Degraded")["status"], "True");
+        assert_eq!(
+            condition(&conditions, "Degraded")["message"],
+            "ERROR: bucket exists"
+        );
+
+        let resource = claim(json!({ "conditions": conditions }));
+        let conditions = job_conditions(&resource, "Apply - successful", "", NOW);
+        assert_eq!(condition(&conditions, "Ready")["status"], "True");
+        assert_eq!(condition(&conditions, "Degraded")["status"], "False");
+        assert_eq!(
+            condition(&conditions, "Suspended")["lastTransitionTime"],
+            EARLIER
+        );
+        assert_eq!(conditions.len(), 4);
+    }
+
+    #[test]
+    fn test_job_conditions_delete_not_ready() {
+        let resource = claim(json!({
+            "conditions": [{ "type": "Ready", "status": "True", "lastTransitionTime": EARLIER }]
+        }));
+        let conditions = job_conditions(&resource, "Delete - initiated", "", NOW);
+        assert_eq!(condition(&conditions, "Ready")["status"], "False");
+        assert_eq!(condition(&conditions, "Ready")["reason"], "Deleting");
+    }
+
+    #[test]
+    fn test_truncate_message() {
+        let message = "é".repeat(MAX_CONDITION_MESSAGE_LEN);
+        let truncated = truncate_message(&message);
+        assert!(truncated.ends_with("..."));
+        assert!(truncated.len() <= MAX_CONDITION_MESSAGE_LEN + 3);
+    }
+}
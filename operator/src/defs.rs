@@ -2,3 +2,11 @@ pub const FINALIZER_NAME: &str = "deletion-handler.finalizer.infraweave.io";
 pub const KUBERNETES_GROUP: &str = "infraweave.io";
 pub const OPERATOR_NAME: &str = "infraweave-operator";
 pub const NAMESPACE: &str = "default";
+/// Annotation that pauses reconciliation of a claim while set to "true"
+pub const SUSPEND_ANNOTATION: &str = "infraweave.io/suspend";
+/// Annotation setting the deletion policy of a claim, `Delete`, `Retain` or `Orphan`
+pub const DELETION_POLICY_ANNOTATION: &str = "infraweave.io/deletion-policy";
+pub const SUSPENDED_CONDITION: &str = "Suspended";
+pub const READY_CONDITION: &str = "Ready";
+pub const PROGRESSING_CONDITION: &str = "Progressing";
+pub const DEGRADED_CONDITION: &str = "Degraded";
@@ -0,0 +1,118 @@
+use anyhow::Result;
+use env_common::interface::GenericCloudHandler;
+use env_common::logic::set_deployment;
+use env_defs::CloudProvider;
+use kube::api::DynamicObject;
+use kube::ResourceExt;
+
+use crate::defs::DELETION_POLICY_ANNOTATION;
+
+/// What happens to the resources of a claim when the claim is deleted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeletionPolicy {
+    /// Destroy the resources, the claim is removed once the destroy job succeeded
+    Delete,
+    /// Keep the resources and the deployment, a new claim with the same name takes it over again
+    Retain,
+    /// Keep the resources but remove the deployment from InfraWeave, which stops managing them
+    Orphan,
+}
+
+impl DeletionPolicy {
+    fn parse(value: &str) -> Result<DeletionPolicy> {
+        match value.trim() {
+            "Delete" => Ok(DeletionPolicy::Delete),
+            "Retain" => Ok(DeletionPolicy::Retain),
+            "Orphan" => Ok(DeletionPolicy::Orphan),
+            other => Err(anyhow::anyhow!(
+                "Invalid deletion policy '{}', expected Delete, Retain or Orphan",
+                other
+            )),
+        }
+    }
+}
+
+/// Deletion policy of the claim, from the `infraweave.io/deletion-policy` annotation or else
+/// `spec.deletionPolicy`, `Delete` if neither is set. The annotation can be set on a claim that
+/// is managed elsewhere, such as by GitOps, right before deleting it
+pub fn deletion_policy(resource: &DynamicObject) -> Result<DeletionPolicy> {
+    if let Some(policy) = resource.annotations().get(DELETION_POLICY_ANNOTATION) {
+        return DeletionPolicy::parse(policy);
+    }
+    match resource
+        .data
+        .get("spec")
+        .and_then(|s| s.get("deletionPolicy"))
+    {
+        None | Some(serde_json::Value::Null) => Ok(DeletionPolicy::Delete),
+        Some(serde_json::Value::String(policy)) => DeletionPolicy::parse(policy),
+        Some(other) => Err(anyhow::anyhow!("Invalid deletionPolicy: {}", other)),
+    }
+}
+
+/// Marks the deployment as deleted without destroying its resources, which are left as they are
+/// along with their terraform state
+pub async fn orphan_deployment(
+    handler: &GenericCloudHandler,
+    deployment_id: &str,
+    environment: &str,
+) -> Result<()> {
+    let Some(mut deployment) = handler
+        .get_deployment(deployment_id, environment, false)
+        .await?
+    else {
+        return Ok(());
+    };
+    deployment.deleted = true;
+    set_deployment(handler, &deployment, false).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kube::api::ApiResource;
+    use serde_json::json;
+
+    fn claim(annotation: Option<&str>, spec: serde_json::Value) -> DynamicObject {
+        let api_resource = ApiResource {
+            api_version: "infraweave.io/v1".to_string(),
+            group: "infraweave.io".to_string(),
+            version: "v1".to_string(),
+            kind: "S3Bucket".to_string(),
+            plural: "s3buckets".to_string(),
+        };
+        let mut resource =
+            DynamicObject::new("my-bucket", &api_resource).data(json!({ "spec": spec }));
+        if let Some(policy) = annotation {
+            resource.metadata.annotations = Some(
+                [(DELETION_POLICY_ANNOTATION.to_string(), policy.to_string())]
+                    .into_iter()
+                    .collect(),
+            );
+        }
+        resource
+    }
+
+    #[test]
+    fn test_deletion_policy() {
+        assert_eq!(
+            deletion_policy(&claim(None, json!({}))).unwrap(),
+            DeletionPolicy::Delete
+        );
+        assert_eq!(
+            deletion_policy(&claim(None, json!({ "deletionPolicy": "Retain" }))).unwrap(),
+            DeletionPolicy::Retain
+        );
+        // The annotation takes precedence over the spec
+        assert_eq!(
+            deletion_policy(&claim(
+                Some("Orphan"),
+                json!({ "deletionPolicy": "Retain" })
+            ))
+            .unwrap(),
+            DeletionPolicy::Orphan
+        );
+        assert!(deletion_policy(&claim(Some("Keep"), json!({}))).is_err());
+        assert!(deletion_policy(&claim(None, json!({ "deletionPolicy": 1 }))).is_err());
+    }
+}
@@ -1,5 +1,9 @@
 pub mod apply;
+pub mod conditions;
 pub mod defs;
+pub mod deletion;
 pub mod operator;
+pub mod outputs;
+pub mod shutdown;
 pub mod validation;
 pub mod webhook;
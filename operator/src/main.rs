@@ -3,9 +3,13 @@ use log::info;
 use std::env;
 
 mod apply;
+mod conditions;
 mod defs;
+mod deletion;
 mod logging;
 mod operator;
+mod outputs;
+mod shutdown;
 mod validation;
 mod webhook;
 
@@ -15,10 +15,19 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::time;
 
+use futures::channel::oneshot;
+use futures::future::{BoxFuture, FutureExt, Shared};
 use futures::stream::StreamExt;
 
 use crate::apply::apply_module_crd;
-use crate::defs::{FINALIZER_NAME, KUBERNETES_GROUP, NAMESPACE, OPERATOR_NAME};
+use crate::conditions::{current_conditions, job_conditions, set_condition};
+use crate::defs::{
+    FINALIZER_NAME, KUBERNETES_GROUP, NAMESPACE, OPERATOR_NAME, SUSPENDED_CONDITION,
+    SUSPEND_ANNOTATION,
+};
+use crate::deletion::{deletion_policy, orphan_deployment, DeletionPolicy};
+use crate::outputs::{project_outputs, publish_status_outputs, remove_projected_outputs};
+use crate::shutdown::{shutdown_signal, SHUTDOWN_GRACE_PERIOD};
 
 use kube::api::{Patch, PatchParams};
 use serde_json::json;
@@ -35,17 +44,40 @@ pub async fn start_operator(handler: &GenericCloudHandler) -> anyhow::Result<()>
     let client: KubeClient = initialize_kube_client().await?;
     let leadership = create_lease_lock(client.clone());
 
-    let mut controllers_started = false;
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
 
     loop {
-        if acquire_leadership_and_run_once(handler, &leadership, &client, &mut controllers_started)
-            .await
-        {
-            renew_leadership(&leadership).await;
-        } else {
+        if !try_acquire_leadership(&leadership).await {
             println!("There is already a leader, waiting for it to release leadership");
-            time::sleep(Duration::from_secs(15)).await;
+            tokio::select! {
+                _ = time::sleep(Duration::from_secs(15)) => continue,
+                _ = &mut shutdown => return Ok(()),
+            }
+        }
+
+        println!("Leadership acquired!");
+        if let Err(e) = list_and_apply_modules(handler, client.clone()).await {
+            eprintln!("Failed to apply module CRDs: {:?}", e);
+        }
+        let controllers = start_infraweave_controllers(handler, client.clone());
+
+        let shutting_down = tokio::select! {
+            _ = renew_leadership(&leadership) => false,
+            _ = &mut shutdown => true,
+        };
+
+        // Stop reconciling before another replica takes over, letting reconciles in flight finish
+        controllers.stop().await;
+
+        if shutting_down {
+            // Release the lease so another replica takes over without waiting for it to expire
+            if let Err(e) = leadership.step_down().await {
+                eprintln!("Failed to release leadership: {:?}", e);
+            }
+            return Ok(());
         }
+        println!("Lost leadership, stopped controllers until it is acquired again");
     }
 }
 
@@ -66,40 +98,34 @@ fn get_holder_id() -> String {
     format!("{}-{}", OPERATOR_NAME, pod_name)
 }
 
-async fn acquire_leadership_and_run_once(
-    handler: &GenericCloudHandler,
-    leadership: &LeaseLock,
-    client: &KubeClient,
-    controllers_started: &mut bool,
-) -> bool {
-    let lease = leadership.try_acquire_or_renew().await.unwrap();
-
-    if lease.acquired_lease {
-        println!("Leadership acquired!");
-        list_and_apply_modules(handler, client.clone())
-            .await
-            .unwrap();
-
-        if !*controllers_started {
-            start_infraweave_controllers(handler, client.clone());
-            *controllers_started = true;
+async fn try_acquire_leadership(leadership: &LeaseLock) -> bool {
+    match leadership.try_acquire_or_renew().await {
+        Ok(lease) => lease.acquired_lease,
+        Err(e) => {
+            eprintln!("Failed to acquire leadership: {:?}", e);
+            false
         }
-
-        return true;
     }
-    false
 }
 
+/// Renews the lease until leadership is lost, to another replica or because it can't be renewed
 async fn renew_leadership(leadership: &LeaseLock) {
     let mut renew_interval = time::interval(Duration::from_secs(10));
 
     loop {
         renew_interval.tick().await;
-        if let Err(e) = leadership.try_acquire_or_renew().await {
-            eprintln!("Lost leadership due to error: {:?}", e);
-            break; // Exit if lease renewal fails
-        } else {
-            println!("Leadership renewed for {}", OPERATOR_NAME);
+        match leadership.try_acquire_or_renew().await {
+            Ok(lease) if lease.acquired_lease => {
+                println!("Leadership renewed for {}", OPERATOR_NAME);
+            }
+            Ok(_) => {
+                eprintln!("Lost leadership to another replica");
+                break;
+            }
+            Err(e) => {
+                eprintln!("Lost leadership due to error: {:?}", e);
+                break; // Exit if lease renewal fails
+            }
         }
     }
 }
@@ -191,13 +217,42 @@ async fn crd_already_exists(client: &KubeClient, crd_name: &str) -> bool {
     matches!(crds.get(crd_name).await, Ok(_))
 }
 
+/// Completes when the controllers are stopped, cloned for the controller of each CRD
+type ShutdownTrigger = Shared<BoxFuture<'static, ()>>;
+
+/// Controllers started by [`start_infraweave_controllers`], which stop when this is dropped
+#[must_use = "the controllers stop when this is dropped"]
+pub struct RunningControllers {
+    shutdown: oneshot::Sender<()>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl RunningControllers {
+    /// Stops watching for changes and waits for the reconciles in flight to finish, for at most
+    /// the shutdown grace period
+    pub async fn stop(self) {
+        let _ = self.shutdown.send(());
+        if time::timeout(SHUTDOWN_GRACE_PERIOD, self.task)
+            .await
+            .is_err()
+        {
+            eprintln!("Reconciles did not finish within the shutdown grace period");
+        }
+    }
+}
+
 /// Starts controllers for all infraweave.io CRDs
 /// Uses kube_runtime::Controller for proper reconciliation with requeue
-pub fn start_infraweave_controllers(handler: &GenericCloudHandler, client: KubeClient) {
+pub fn start_infraweave_controllers(
+    handler: &GenericCloudHandler,
+    client: KubeClient,
+) -> RunningControllers {
     let handler = handler.clone();
     let client_clone = client.clone();
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+    let shutdown: ShutdownTrigger = shutdown_receiver.map(|_| ()).boxed().shared();
 
-    tokio::spawn(async move {
+    let task = tokio::spawn(async move {
         println!("Starting infraweave.io controllers");
 
         let cluster_id =
@@ -210,7 +265,7 @@ pub fn start_infraweave_controllers(handler: &GenericCloudHandler, client: KubeC
         });
 
         loop {
-            match run_controllers(ctx.clone(), client_clone.clone()).await {
+            match run_controllers(ctx.clone(), client_clone.clone(), shutdown.clone()).await {
                 Ok(_) => {
                     println!("Controllers terminated normally");
                     break;
@@ -232,18 +287,31 @@ pub fn start_infraweave_controllers(handler: &GenericCloudHandler, client: KubeC
                         break;
                     }
                     eprintln!("Controllers failed: {}. Restarting in 10s...", e);
-                    tokio::time::sleep(Duration::from_secs(10)).await;
+                    tokio::select! {
+                        _ = tokio::time::sleep(Duration::from_secs(10)) => {}
+                        _ = shutdown.clone() => break,
+                    }
                 }
             }
         }
 
         println!("Controller task has stopped");
     });
+
+    RunningControllers {
+        shutdown: shutdown_sender,
+        task,
+    }
 }
 
 /// Runs controllers for all infraweave.io CRDs
-/// Each CRD gets its own Controller instance that manages reconciliation
-async fn run_controllers(ctx: Arc<Context>, client: kube::Client) -> anyhow::Result<()> {
+/// Each CRD gets its own Controller instance that manages reconciliation, until `shutdown`
+/// completes and the reconciles in flight have finished
+async fn run_controllers(
+    ctx: Arc<Context>,
+    client: kube::Client,
+    shutdown: ShutdownTrigger,
+) -> anyhow::Result<()> {
     let crds: Api<CustomResourceDefinition> = Api::all(client.clone());
     let crd_list = crds.list(&Default::default()).await?;
 
@@ -276,6 +344,7 @@ async fn run_controllers(ctx: Arc<Context>, client: kube::Client) -> anyhow::Res
         // Use Controller::new_with which allows specifying the DynamicType (ApiResource)
         // This bypasses the DynamicType: Default requirement
         let controller_future = Controller::new_with(api, WatcherConfig::default(), api_resource)
+            .graceful_shutdown_on(shutdown.clone())
             .run(
                 move |obj, ctx| reconcile(obj, ctx, kind_clone.clone()),
                 error_policy,
@@ -343,6 +412,28 @@ async fn reconcile(
         return Ok(Action::requeue(Duration::from_secs(1)));
     }
 
+    // Suspended claims are not applied or retried until the annotation is removed, deletion
+    // is still handled so that the finalizer does not block it
+    let suspended = is_suspended(&resource);
+    let marked_suspended = suspended_condition_status(&resource) == Some("True");
+    if suspended {
+        if !marked_suspended {
+            println!("Suspending reconciliation of {} {}", kind, name);
+            set_suspended_condition(&ctx.client, &resource, &api_resource, true)
+                .await
+                .map_err(to_kube_err)?;
+        } else {
+            println!("{} {} is suspended, skipping reconciliation", kind, name);
+        }
+        return Ok(Action::await_change());
+    }
+    if marked_suspended {
+        println!("Resuming reconciliation of {} {}", kind, name);
+        set_suspended_condition(&ctx.client, &resource, &api_resource, false)
+            .await
+            .map_err(to_kube_err)?;
+    }
+
     // Reconcile the resource (non-blocking)
     // The reconcile function will fetch fresh resource state and determine if work is needed
     reconcile_resource_nonblocking(
@@ -898,6 +989,27 @@ async fn reconcile_resource_nonblocking(
             .and_then(|r| r.as_i64())
             .unwrap_or(0);
 
+        // Written before the jobId is cleared so that a failure is retried on the next check
+        project_outputs(
+            handler,
+            client,
+            &fresh_resource,
+            api_resource,
+            current_deployment_id,
+            environment,
+        )
+        .await?;
+        publish_status_outputs(
+            handler,
+            client,
+            &fresh_resource,
+            api_resource,
+            current_deployment_id,
+            environment,
+            current_job_id,
+        )
+        .await?;
+
         // Update status with success and clear jobId
         update_resource_status(
             client.clone(),
@@ -951,6 +1063,39 @@ async fn handle_resource_deletion_nonblocking(
 
     println!("Handling deletion of {} {}", kind, name);
 
+    let policy = match deletion_policy(resource) {
+        Ok(policy) => policy,
+        Err(e) => {
+            // The finalizer stays until the policy is fixed, rather than guessing what to do
+            // with the resources
+            eprintln!("Not deleting {} {}: {}", kind, name, e);
+            update_resource_status(
+                client.clone(),
+                resource,
+                api_resource,
+                "Delete - error",
+                get_timestamp().as_str(),
+                &e.to_string(),
+                "",
+            )
+            .await?;
+            return Ok(Action::await_change());
+        }
+    };
+    if policy != DeletionPolicy::Delete {
+        if policy == DeletionPolicy::Orphan {
+            let deployment_id = format!("{}/{}", kind.to_lowercase(), name);
+            orphan_deployment(handler, &deployment_id, environment).await?;
+        }
+        println!(
+            "Deletion policy of {} {} is {:?}, leaving its resources in place",
+            kind, name, policy
+        );
+        remove_projected_outputs(client, resource).await?;
+        remove_finalizer(client, resource, api_resource).await?;
+        return Ok(Action::await_change());
+    }
+
     // Read current status from CRD
     let current_job_id = resource
         .data
@@ -1029,7 +1174,18 @@ async fn handle_resource_deletion_nonblocking(
             }
             Err(e) => {
                 eprintln!("Failed to start destroy job: {}", e);
-                return Err(e);
+                // Shown on the claim, the destroy is submitted again on the next attempt
+                update_resource_status(
+                    client.clone(),
+                    &fresh_resource,
+                    api_resource,
+                    "Delete - error",
+                    get_timestamp().as_str(),
+                    &format!("Failed to start destroy job: {}", e),
+                    "",
+                )
+                .await?;
+                return Ok(Action::requeue(Duration::from_secs(60)));
             }
         }
     }
@@ -1284,6 +1440,8 @@ async fn handle_resource_deletion_nonblocking(
     if depl_status == "successful" {
         println!("Destroy successful, removing finalizer from {}", name);
 
+        remove_projected_outputs(client, resource).await?;
+
         // Update status with success and clear jobId
         update_resource_status(
             client.clone(),
@@ -1296,34 +1454,44 @@ async fn handle_resource_deletion_nonblocking(
         )
         .await?;
 
-        let finalizers: Vec<String> = resource
-            .finalizers()
-            .iter()
-            .filter(|f| *f != FINALIZER_NAME)
-            .cloned()
-            .collect();
+        remove_finalizer(client, resource, api_resource).await?;
+    }
 
-        let patch_params = PatchParams::default();
-        let patch = json!({
-            "metadata": {
-                "finalizers": finalizers
-            }
-        });
+    Ok(Action::await_change())
+}
 
-        let namespaced_api =
-            Api::<DynamicObject>::namespaced_with(client.clone(), &namespace, api_resource);
-        namespaced_api
-            .patch(
-                &resource.metadata.name.clone().unwrap(),
-                &patch_params,
-                &Patch::Merge(&patch),
-            )
-            .await?;
+/// Removes the finalizer of the operator, letting Kubernetes delete the resource
+async fn remove_finalizer(
+    client: &kube::Client,
+    resource: &DynamicObject,
+    api_resource: &ApiResource,
+) -> anyhow::Result<()> {
+    let name = resource.metadata.name.as_ref().unwrap();
+    let namespace = resource
+        .namespace()
+        .unwrap_or_else(|| "default".to_string());
+    let finalizers: Vec<String> = resource
+        .finalizers()
+        .iter()
+        .filter(|f| *f != FINALIZER_NAME)
+        .cloned()
+        .collect();
 
-        println!("Removed finalizer from {}", name);
-    }
+    let patch_params = PatchParams::default();
+    let patch = json!({
+        "metadata": {
+            "finalizers": finalizers
+        }
+    });
 
-    Ok(Action::await_change())
+    let namespaced_api =
+        Api::<DynamicObject>::namespaced_with(client.clone(), &namespace, api_resource);
+    namespaced_api
+        .patch(name, &patch_params, &Patch::Merge(&patch))
+        .await?;
+
+    println!("Removed finalizer from {}", name);
+    Ok(())
 }
 
 /// Fetches existing deployments and creates CRDs for them
@@ -1546,6 +1714,7 @@ async fn update_resource_status(
             "lastGeneration": resource.metadata.generation.unwrap_or_default(),
             "logs": message,
             "retryCount": retry_count,
+            "conditions": job_conditions(resource, status, message, &now),
         }
     });
 
@@ -1661,6 +1830,87 @@ async fn reset_retry_count(
     Ok(())
 }
 
+/// Whether reconciliation of the claim is paused with the `infraweave.io/suspend: "true"` annotation
+fn is_suspended(resource: &DynamicObject) -> bool {
+    resource
+        .annotations()
+        .get(SUSPEND_ANNOTATION)
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case("true"))
+}
+
+fn suspended_condition_status(resource: &DynamicObject) -> Option<&str> {
+    resource
+        .data
+        .get("status")
+        .and_then(|s| s.get("conditions"))
+        .and_then(|c| c.as_array())?
+        .iter()
+        .find(|c| c.get("type").and_then(|t| t.as_str()) == Some(SUSPENDED_CONDITION))
+        .and_then(|c| c.get("status"))
+        .and_then(|s| s.as_str())
+}
+
+/// Conditions of the resource with the Suspended condition replaced
+fn with_suspended_condition(
+    resource: &DynamicObject,
+    suspended: bool,
+    now: &str,
+) -> Vec<serde_json::Value> {
+    let mut conditions = current_conditions(resource);
+    if suspended {
+        set_condition(
+            &mut conditions,
+            SUSPENDED_CONDITION,
+            "True",
+            "SuspendAnnotation",
+            &format!(
+                "Reconciliation is paused by the {} annotation",
+                SUSPEND_ANNOTATION
+            ),
+            now,
+        );
+    } else {
+        set_condition(
+            &mut conditions,
+            SUSPENDED_CONDITION,
+            "False",
+            "Resumed",
+            "Reconciliation resumed",
+            now,
+        );
+    }
+    conditions
+}
+
+async fn set_suspended_condition(
+    client: &kube::Client,
+    resource: &DynamicObject,
+    api_resource: &ApiResource,
+    suspended: bool,
+) -> Result<(), anyhow::Error> {
+    let namespace = resource
+        .namespace()
+        .unwrap_or_else(|| "default".to_string());
+    let namespaced_api =
+        Api::<DynamicObject>::namespaced_with(client.clone(), &namespace, api_resource);
+
+    let status_patch = json!({
+        "status": {
+            "conditions": with_suspended_condition(resource, suspended, &get_timestamp()),
+            "lastCheck": get_timestamp(),
+        }
+    });
+
+    namespaced_api
+        .patch_status(
+            &resource.metadata.name.clone().unwrap(),
+            &PatchParams::default(),
+            &Patch::Merge(&status_patch),
+        )
+        .await?;
+    Ok(())
+}
+
 fn to_kube_err(e: anyhow::Error) -> kube::Error {
     kube::Error::Service(Box::new(std::io::Error::new(
         std::io::ErrorKind::Other,
@@ -1694,6 +1944,8 @@ mod tests {
                         cpu: None,
                         memory: None,
                         providers: Vec::with_capacity(0),
+                        owners: vec![],
+                        ui: None,
                     },
                     api_version: "infraweave.io/v1".to_string(),
                     kind: "TestModule".to_string(),
@@ -1718,6 +1970,11 @@ mod tests {
                 memory: "2048".to_string(),
                 deprecated: false,
                 deprecated_message: None,
+                readme: None,
+                changelog: None,
+                digest: None,
+                oci_reference: None,
+                yanked: None,
             },
             &DeploymentResp {
                 epoch: 0,
@@ -1744,8 +2001,11 @@ mod tests {
                     interval: "1h".to_string(),
                     auto_remediate: false,
                     webhooks: vec![],
+                    schedule: None,
+                    blackout_windows: vec![],
                 },
                 next_drift_check_epoch: -1,
+                last_drift_check_epoch: None,
                 has_drifted: false,
                 output: serde_json::json!({}),
                 policy_results: vec![],
@@ -1757,6 +2017,10 @@ mod tests {
                 memory: "2048".to_string(),
                 reference: "https://github.com/somerepo/somepath/here.yaml".to_string(),
                 tf_resources: None,
+                module_digest: None,
+                idempotency_key: None,
+                drift_report: None,
+                stack_instance: None,
             },
         );
         let expected_claim = r#"
@@ -1786,4 +2050,55 @@ status:
             serde_yaml::from_str::<serde_yaml::Value>(expected_claim).unwrap();
         assert_eq!(claim_yaml, expected_claim_yaml);
     }
+
+    fn claim(annotations: &[(&str, &str)], status: serde_json::Value) -> DynamicObject {
+        let mut resource = DynamicObject::new("test-deployment", &get_api_resource("TestModule"))
+            .data(json!({ "status": status }));
+        resource.metadata.annotations = Some(
+            annotations
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        );
+        resource
+    }
+
+    #[test]
+    fn test_is_suspended() {
+        assert!(is_suspended(&claim(
+            &[(SUSPEND_ANNOTATION, "true")],
+            json!({})
+        )));
+        assert!(is_suspended(&claim(
+            &[(SUSPEND_ANNOTATION, "True")],
+            json!({})
+        )));
+        assert!(!is_suspended(&claim(
+            &[(SUSPEND_ANNOTATION, "false")],
+            json!({})
+        )));
+        assert!(!is_suspended(&claim(&[], json!({}))));
+    }
+
+    #[test]
+    fn test_with_suspended_condition_replaces_existing() {
+        let resource = claim(
+            &[],
+            json!({
+                "conditions": [
+                    { "type": "Ready", "status": "True" },
+                    { "type": "Suspended", "status": "True" },
+                ]
+            }),
+        );
+        assert_eq!(suspended_condition_status(&resource), Some("True"));
+
+        let conditions = with_suspended_condition(&resource, false, "2024-01-01T00:00:00Z");
+
+        assert_eq!(conditions.len(), 2);
+        assert_eq!(conditions[0]["type"], "Ready");
+        assert_eq!(conditions[1]["type"], "Suspended");
+        assert_eq!(conditions[1]["status"], "False");
+        assert_eq!(conditions[1]["reason"], "Resumed");
+    }
 }
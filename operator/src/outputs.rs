@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use env_common::interface::GenericCloudHandler;
+use env_common::logic::get_deployment_outputs;
+use env_defs::{OutputsTo, OutputsToKind};
+use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference};
+use k8s_openapi::ByteString;
+use kube::api::{ApiResource, DeleteParams, DynamicObject, Patch, PatchParams};
+use kube::{Api, ResourceExt};
+use serde_json::{json, Value};
+
+use crate::defs::OPERATOR_NAME;
+
+/// Label set on the Secrets and ConfigMaps the operator writes outputs to
+pub const OUTPUTS_FOR_LABEL: &str = "infraweave.io/outputs-for";
+
+/// `spec.outputsTo` of the claim, if set
+pub fn outputs_to(resource: &DynamicObject) -> Result<Option<OutputsTo>> {
+    parse_outputs_to(resource.data.get("spec").and_then(|s| s.get("outputsTo")))
+}
+
+/// Object the outputs were last written to, as recorded in `status.outputsTo`
+fn projected_outputs_to(resource: &DynamicObject) -> Option<OutputsTo> {
+    parse_outputs_to(resource.data.get("status").and_then(|s| s.get("outputsTo")))
+        .ok()
+        .flatten()
+}
+
+fn parse_outputs_to(value: Option<&Value>) -> Result<Option<OutputsTo>> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => serde_json::from_value(value.clone())
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("Invalid outputsTo: {}", e)),
+    }
+}
+
+/// Writes the outputs of the deployment to the Secret or ConfigMap in `spec.outputsTo` of the
+/// claim, removing the object written before if `spec.outputsTo` was changed or removed
+pub async fn project_outputs(
+    handler: &GenericCloudHandler,
+    client: &kube::Client,
+    resource: &DynamicObject,
+    api_resource: &ApiResource,
+    deployment_id: &str,
+    environment: &str,
+) -> Result<()> {
+    let target = outputs_to(resource)?;
+    let previous = projected_outputs_to(resource);
+    let namespace = resource
+        .namespace()
+        .unwrap_or_else(|| "default".to_string());
+
+    if let Some(previous) = &previous {
+        let moved =
+            !matches!(&target, Some(t) if t.kind == previous.kind && t.name == previous.name);
+        if moved {
+            delete_outputs_object(client, &namespace, previous).await?;
+        }
+    }
+
+    if let Some(target) = &target {
+        // Sensitive outputs are read from the state file, but only ever written to Secrets
+        let include_sensitive = target.kind == OutputsToKind::Secret;
+        let outputs =
+            get_deployment_outputs(handler, deployment_id, environment, include_sensitive).await?;
+        let data = projection_data(&outputs, target)?;
+        let metadata = ObjectMeta {
+            name: Some(target.name.clone()),
+            namespace: Some(namespace.clone()),
+            labels: Some(BTreeMap::from([(
+                OUTPUTS_FOR_LABEL.to_string(),
+                resource.name_any(),
+            )])),
+            owner_references: owner_reference(resource, api_resource).map(|r| vec![r]),
+            ..Default::default()
+        };
+        let patch_params = PatchParams::apply(OPERATOR_NAME).force();
+        match target.kind {
+            OutputsToKind::Secret => {
+                let secret = Secret {
+                    metadata,
+                    type_: Some("Opaque".to_string()),
+                    data: Some(
+                        data.into_iter()
+                            .map(|(k, v)| (k, ByteString(v.into_bytes())))
+                            .collect(),
+                    ),
+                    ..Default::default()
+                };
+                Api::<Secret>::namespaced(client.clone(), &namespace)
+                    .patch(&target.name, &patch_params, &Patch::Apply(&secret))
+                    .await?;
+            }
+            OutputsToKind::ConfigMap => {
+                let config_map = ConfigMap {
+                    metadata,
+                    data: Some(data),
+                    ..Default::default()
+                };
+                Api::<ConfigMap>::namespaced(client.clone(), &namespace)
+                    .patch(&target.name, &patch_params, &Patch::Apply(&config_map))
+                    .await?;
+            }
+        }
+        println!(
+            "Wrote outputs of {} to {:?} {}/{}",
+            deployment_id, target.kind, namespace, target.name
+        );
+    }
+
+    if target != previous {
+        let status_patch = json!({
+            "status": {
+                "outputsTo": target,
+            }
+        });
+        Api::<DynamicObject>::namespaced_with(client.clone(), &namespace, api_resource)
+            .patch_status(
+                &resource.name_any(),
+                &PatchParams::default(),
+                &Patch::Merge(&status_patch),
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+/// Records the completed apply job and the non-sensitive outputs of the deployment in
+/// `status.lastAppliedJobId` and `status.outputs` of the claim
+pub async fn publish_status_outputs(
+    handler: &GenericCloudHandler,
+    client: &kube::Client,
+    resource: &DynamicObject,
+    api_resource: &ApiResource,
+    deployment_id: &str,
+    environment: &str,
+    job_id: &str,
+) -> Result<()> {
+    let outputs = get_deployment_outputs(handler, deployment_id, environment, false).await?;
+    let previous = resource.data.get("status").and_then(|s| s.get("outputs"));
+    let status_patch = json!({
+        "status": {
+            "lastAppliedJobId": job_id,
+            "outputs": status_outputs_patch(previous, &outputs),
+        }
+    });
+    let namespace = resource
+        .namespace()
+        .unwrap_or_else(|| "default".to_string());
+    Api::<DynamicObject>::namespaced_with(client.clone(), &namespace, api_resource)
+        .patch_status(
+            &resource.name_any(),
+            &PatchParams::default(),
+            &Patch::Merge(&status_patch),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Merge patch of `status.outputs` holding the values of the non-sensitive outputs, with outputs
+/// that no longer exist or became sensitive set to null so the patch removes them
+fn status_outputs_patch(previous: Option<&Value>, outputs: &Value) -> Value {
+    let mut patch = serde_json::Map::new();
+    if let Some(previous) = previous.and_then(|p| p.as_object()) {
+        for name in previous.keys() {
+            patch.insert(name.clone(), Value::Null);
+        }
+    }
+    for (name, output) in outputs.as_object().into_iter().flatten() {
+        let sensitive = output
+            .get("sensitive")
+            .and_then(|s| s.as_bool())
+            .unwrap_or(false);
+        if !sensitive {
+            patch.insert(
+                name.clone(),
+                output.get("value").cloned().unwrap_or(Value::Null),
+            );
+        }
+    }
+    Value::Object(patch)
+}
+
+/// Removes the Secret or ConfigMap the outputs were written to, used once the claim is destroyed
+pub async fn remove_projected_outputs(
+    client: &kube::Client,
+    resource: &DynamicObject,
+) -> Result<()> {
+    let Some(previous) = projected_outputs_to(resource) else {
+        return Ok(());
+    };
+    let namespace = resource
+        .namespace()
+        .unwrap_or_else(|| "default".to_string());
+    delete_outputs_object(client, &namespace, &previous).await
+}
+
+async fn delete_outputs_object(
+    client: &kube::Client,
+    namespace: &str,
+    outputs_to: &OutputsTo,
+) -> Result<()> {
+    let result = match outputs_to.kind {
+        OutputsToKind::Secret => Api::<Secret>::namespaced(client.clone(), namespace)
+            .delete(&outputs_to.name, &DeleteParams::default())
+            .await
+            .map(|_| ()),
+        OutputsToKind::ConfigMap => Api::<ConfigMap>::namespaced(client.clone(), namespace)
+            .delete(&outputs_to.name, &DeleteParams::default())
+            .await
+            .map(|_| ()),
+    };
+    match result {
+        Ok(()) => {
+            println!(
+                "Deleted {:?} {}/{} holding outputs",
+                outputs_to.kind, namespace, outputs_to.name
+            );
+            Ok(())
+        }
+        Err(kube::Error::Api(e)) if e.code == 404 => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The claim owns the objects its outputs are written to, so they are garbage collected with it
+fn owner_reference(resource: &DynamicObject, api_resource: &ApiResource) -> Option<OwnerReference> {
+    Some(OwnerReference {
+        api_version: api_resource.api_version.clone(),
+        kind: api_resource.kind.clone(),
+        name: resource.name_any(),
+        uid: resource.metadata.uid.clone()?,
+        ..Default::default()
+    })
+}
+
+/// Values of the selected outputs keyed by output name, strings as-is and other values as JSON.
+/// Sensitive outputs are left out unless `outputs_to` is a Secret
+fn projection_data(outputs: &Value, outputs_to: &OutputsTo) -> Result<BTreeMap<String, String>> {
+    let outputs = outputs.as_object().cloned().unwrap_or_default();
+    let include_sensitive = outputs_to.kind == OutputsToKind::Secret;
+
+    let names: Vec<String> = if outputs_to.outputs.is_empty() {
+        outputs.keys().cloned().collect()
+    } else {
+        outputs_to.outputs.clone()
+    };
+
+    let mut data = BTreeMap::new();
+    for name in names {
+        let output = outputs
+            .get(&name)
+            .ok_or_else(|| anyhow::anyhow!("Output {} in outputsTo does not exist", name))?;
+        let sensitive = output
+            .get("sensitive")
+            .and_then(|s| s.as_bool())
+            .unwrap_or(false);
+        if sensitive && !include_sensitive {
+            if outputs_to.outputs.is_empty() {
+                continue;
+            }
+            return Err(anyhow::anyhow!(
+                "Output {} is sensitive and can only be written to a Secret",
+                name
+            ));
+        }
+        let value = match output.get("value") {
+            Some(Value::String(s)) => s.clone(),
+            Some(value) => value.to_string(),
+            None => String::new(),
+        };
+        data.insert(name, value);
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn outputs() -> Value {
+        json!({
+            "bucket_name": { "value": "my-bucket", "type": "string", "sensitive": false },
+            "ports": { "value": [80, 443], "type": ["list", "number"], "sensitive": false },
+            "password": { "value": "hunter2", "type": "string", "sensitive": true },
+        })
+    }
+
+    fn target(kind: OutputsToKind, outputs: &[&str]) -> OutputsTo {
+        OutputsTo {
+            kind,
+            name: "bucket-outputs".to_string(),
+            outputs: outputs.iter().map(|o| o.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_projection_data_secret_includes_sensitive() {
+        let data = projection_data(&outputs(), &target(OutputsToKind::Secret, &[])).unwrap();
+        assert_eq!(
+            data,
+            BTreeMap::from([
+                ("bucket_name".to_string(), "my-bucket".to_string()),
+                ("password".to_string(), "hunter2".to_string()),
+                ("ports".to_string(), "[80,443]".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_projection_data_config_map_skips_sensitive() {
+        let data = projection_data(&outputs(), &target(OutputsToKind::ConfigMap, &[])).unwrap();
+        assert_eq!(
+            data.keys().collect::<Vec<_>>(),
+            vec!["bucket_name", "ports"]
+        );
+
+        let error = projection_data(&outputs(), &target(OutputsToKind::ConfigMap, &["password"]))
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("can only be written to a Secret"));
+    }
+
+    #[test]
+    fn test_status_outputs_patch() {
+        let previous = json!({ "bucket_name": "old-bucket", "removed": "value" });
+        assert_eq!(
+            status_outputs_patch(Some(&previous), &outputs()),
+            json!({
+                "bucket_name": "my-bucket",
+                "ports": [80, 443],
+                "removed": null,
+            })
+        );
+        assert_eq!(status_outputs_patch(None, &json!({})), json!({}));
+    }
+
+    #[test]
+    fn test_projection_data_selected_outputs() {
+        let data = projection_data(
+            &outputs(),
+            &target(OutputsToKind::ConfigMap, &["bucket_name"]),
+        )
+        .unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data["bucket_name"], "my-bucket");
+
+        assert!(
+            projection_data(&outputs(), &target(OutputsToKind::ConfigMap, &["missing"])).is_err()
+        );
+    }
+}
@@ -0,0 +1,15 @@
+use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
+
+/// Time given to requests and reconciles in flight to finish once shutdown starts
+pub const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+/// Resolves on SIGTERM, which Kubernetes sends when the pod is stopped, or on Ctrl+C
+pub async fn shutdown_signal() {
+    let mut terminate =
+        signal(SignalKind::terminate()).expect("Failed to install the SIGTERM handler");
+    tokio::select! {
+        _ = terminate.recv() => println!("Received SIGTERM, shutting down"),
+        _ = tokio::signal::ctrl_c() => println!("Received Ctrl+C, shutting down"),
+    }
+}
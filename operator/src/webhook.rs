@@ -11,6 +11,7 @@ use std::io::{BufReader, Cursor};
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use crate::shutdown::{shutdown_signal, SHUTDOWN_GRACE_PERIOD};
 use crate::validation::validate_claim;
 
 /// Kubernetes AdmissionReview request structure
@@ -177,8 +178,15 @@ pub async fn start_webhook_server(
 
         let rustls_config = RustlsConfig::from_config(Arc::new(tls_config));
 
-        // Start HTTPS server
+        // Start HTTPS server, finishing the requests in flight on shutdown
+        let handle = axum_server::Handle::new();
+        let shutdown_handle = handle.clone();
+        tokio::spawn(async move {
+            shutdown_signal().await;
+            shutdown_handle.graceful_shutdown(Some(SHUTDOWN_GRACE_PERIOD));
+        });
         axum_server::bind_rustls(addr.parse()?, rustls_config)
+            .handle(handle)
             .serve(app.into_make_service())
             .await?;
     } else {
@@ -191,7 +199,9 @@ pub async fn start_webhook_server(
 
         // Fallback to HTTP for development/testing
         let listener = tokio::net::TcpListener::bind(&addr).await?;
-        axum::serve(listener, app).await?;
+        axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown_signal())
+            .await?;
     }
 
     Ok(())
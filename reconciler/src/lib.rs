@@ -1 +1,283 @@
+use std::time::Duration;
 
+use env_defs::{environment_matches, DeploymentResp};
+use rand::seq::SliceRandom;
+use rand::RngExt;
+
+const DEFAULT_CONCURRENCY: usize = 10;
+const DEFAULT_MAX_PER_RUN: usize = 100;
+const DEFAULT_PRIORITY_ENVIRONMENTS: &str = "prod*";
+const DEFAULT_MAX_JITTER_SECONDS: u64 = 5;
+
+/// Limits for launching drift checks in one reconciler run
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriftSweepConfig {
+    /// Drift checks requested at the same time
+    pub concurrency: usize,
+    /// Drift checks requested per run, the remaining due deployments spill over to the next run
+    pub max_per_run: usize,
+    /// Environment patterns in priority order, deployments in no listed environment come last
+    pub priority_environments: Vec<String>,
+    /// Upper bound of the random delay before each drift check is requested
+    pub max_jitter: Duration,
+}
+
+impl Default for DriftSweepConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_CONCURRENCY,
+            max_per_run: DEFAULT_MAX_PER_RUN,
+            priority_environments: parse_patterns(DEFAULT_PRIORITY_ENVIRONMENTS),
+            max_jitter: Duration::from_secs(DEFAULT_MAX_JITTER_SECONDS),
+        }
+    }
+}
+
+impl DriftSweepConfig {
+    /// Reads `DRIFT_CHECK_CONCURRENCY`, `DRIFT_CHECK_MAX_PER_RUN`,
+    /// `DRIFT_CHECK_PRIORITY_ENVIRONMENTS` and `DRIFT_CHECK_MAX_JITTER_SECONDS`, falling back to the
+    /// defaults when unset or invalid
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let number = |name: &str, default: usize| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse::<usize>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default)
+        };
+        Self {
+            concurrency: number("DRIFT_CHECK_CONCURRENCY", default.concurrency),
+            max_per_run: number("DRIFT_CHECK_MAX_PER_RUN", default.max_per_run),
+            priority_environments: std::env::var("DRIFT_CHECK_PRIORITY_ENVIRONMENTS")
+                .map(|v| parse_patterns(&v))
+                .unwrap_or(default.priority_environments),
+            // Zero is a valid jitter, it disables the delay
+            max_jitter: std::env::var("DRIFT_CHECK_MAX_JITTER_SECONDS")
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(default.max_jitter),
+        }
+    }
+
+    /// Random delay before requesting a drift check, so the checks of a run reach the runners
+    /// spread out instead of all at once
+    pub fn jitter(&self) -> Duration {
+        let max_millis = self.max_jitter.as_millis() as u64;
+        if max_millis == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_millis(rand::rng().random_range(0..=max_millis))
+    }
+
+    fn priority(&self, environment: &str) -> usize {
+        self.priority_environments
+            .iter()
+            .position(|pattern| environment_matches(pattern, environment))
+            .unwrap_or(self.priority_environments.len())
+    }
+
+    /// Splits the due deployments into the ones to check in this run, in priority order and most
+    /// overdue first within a priority, and the ones deferred to the next run
+    pub fn select(
+        &self,
+        mut deployments: Vec<DeploymentResp>,
+    ) -> (Vec<DeploymentResp>, Vec<DeploymentResp>) {
+        deployments.sort_by_key(|d| (self.priority(&d.environment), d.next_drift_check_epoch));
+        let deferred = deployments.split_off(self.max_per_run.min(deployments.len()));
+        (deployments, deferred)
+    }
+
+    /// Groups the selected deployments by priority, highest first, each group in random order.
+    /// A group is launched after the one before it, so shuffling and jitter never let a lower
+    /// priority deployment go ahead of a higher priority one
+    pub fn priority_classes(&self, deployments: Vec<DeploymentResp>) -> Vec<Vec<DeploymentResp>> {
+        let mut classes: Vec<(usize, Vec<DeploymentResp>)> = vec![];
+        for deployment in deployments {
+            let priority = self.priority(&deployment.environment);
+            match classes.last_mut() {
+                Some((last, class)) if *last == priority => class.push(deployment),
+                _ => classes.push((priority, vec![deployment])),
+            }
+        }
+        let mut rng = rand::rng();
+        classes
+            .into_iter()
+            .map(|(_, mut class)| {
+                class.shuffle(&mut rng);
+                class
+            })
+            .collect()
+    }
+}
+
+fn parse_patterns(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Counts of one drift sweep
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DriftSweepMetrics {
+    /// Deployments due for a drift check
+    pub queued: usize,
+    /// Drift checks requested successfully
+    pub executed: usize,
+    /// Drift checks that failed to be requested
+    pub failed: usize,
+    /// Deployments skipped because a job is already running for them
+    pub skipped: usize,
+    /// Due deployments left for the next run
+    pub deferred: usize,
+}
+
+impl DriftSweepMetrics {
+    /// CloudWatch embedded metric format record, turned into metrics when written to the Lambda log
+    pub fn to_embedded_metrics(&self, timestamp_ms: u128) -> serde_json::Value {
+        serde_json::json!({
+            "_aws": {
+                "Timestamp": timestamp_ms,
+                "CloudWatchMetrics": [{
+                    "Namespace": "InfraWeave/Reconciler",
+                    "Dimensions": [[]],
+                    "Metrics": [
+                        {"Name": "DriftChecksQueued", "Unit": "Count"},
+                        {"Name": "DriftChecksExecuted", "Unit": "Count"},
+                        {"Name": "DriftChecksFailed", "Unit": "Count"},
+                        {"Name": "DriftChecksSkipped", "Unit": "Count"},
+                        {"Name": "DriftChecksDeferred", "Unit": "Count"},
+                    ],
+                }],
+            },
+            "DriftChecksQueued": self.queued,
+            "DriftChecksExecuted": self.executed,
+            "DriftChecksFailed": self.failed,
+            "DriftChecksSkipped": self.skipped,
+            "DriftChecksDeferred": self.deferred,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deployment(id: &str, environment: &str, next_drift_check_epoch: i128) -> DeploymentResp {
+        serde_json::from_value(serde_json::json!({
+            "epoch": 0,
+            "deployment_id": id,
+            "status": "successful",
+            "job_id": "",
+            "environment": environment,
+            "project_id": "123456789012",
+            "region": "eu-west-1",
+            "module": "s3bucket",
+            "module_version": "0.1.0",
+            "module_type": "module",
+            "module_track": "stable",
+            "drift_detection": {},
+            "next_drift_check_epoch": next_drift_check_epoch,
+            "has_drifted": false,
+            "variables": {},
+            "output": {},
+            "policy_results": [],
+            "error_text": "",
+            "deleted": false,
+            "dependencies": [],
+            "initiated_by": "",
+            "cpu": "",
+            "memory": "",
+            "reference": "",
+            "tf_resources": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_select_prioritizes_and_defers() {
+        let config = DriftSweepConfig {
+            concurrency: 2,
+            max_per_run: 3,
+            priority_environments: vec!["prod*".to_string(), "staging".to_string()],
+            max_jitter: Duration::ZERO,
+        };
+        let deployments = vec![
+            deployment("s3bucket/dev", "github-org-repo/dev", 100),
+            deployment("s3bucket/staging", "github-org-repo/staging", 300),
+            deployment("s3bucket/prod-late", "github-org-repo/production", 200),
+            deployment("s3bucket/prod-early", "github-org-repo/prod", 50),
+        ];
+
+        let (selected, deferred) = config.select(deployments);
+        let ids: Vec<&str> = selected.iter().map(|d| d.deployment_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "s3bucket/prod-early",
+                "s3bucket/prod-late",
+                "s3bucket/staging"
+            ]
+        );
+        assert_eq!(deferred.len(), 1);
+        assert_eq!(deferred[0].deployment_id, "s3bucket/dev");
+    }
+
+    #[test]
+    fn test_priority_classes_keep_priority_order() {
+        let config = DriftSweepConfig {
+            priority_environments: vec!["prod*".to_string(), "staging".to_string()],
+            ..Default::default()
+        };
+        let deployments = vec![
+            deployment("s3bucket/prod-a", "github-org-repo/prod", 50),
+            deployment("s3bucket/prod-b", "github-org-repo/production", 60),
+            deployment("s3bucket/staging", "github-org-repo/staging", 10),
+            deployment("s3bucket/dev", "github-org-repo/dev", 20),
+        ];
+
+        let classes = config.priority_classes(deployments);
+        let mut ids: Vec<Vec<&str>> = classes
+            .iter()
+            .map(|class| class.iter().map(|d| d.deployment_id.as_str()).collect())
+            .collect();
+        ids[0].sort();
+        assert_eq!(
+            ids,
+            vec![
+                vec!["s3bucket/prod-a", "s3bucket/prod-b"],
+                vec!["s3bucket/staging"],
+                vec!["s3bucket/dev"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_jitter_is_bounded() {
+        let config = DriftSweepConfig {
+            max_jitter: Duration::from_millis(50),
+            ..Default::default()
+        };
+        for _ in 0..100 {
+            assert!(config.jitter() <= Duration::from_millis(50));
+        }
+        let config = DriftSweepConfig {
+            max_jitter: Duration::ZERO,
+            ..Default::default()
+        };
+        assert_eq!(config.jitter(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_parse_patterns() {
+        assert_eq!(
+            parse_patterns(" prod*, ,staging "),
+            vec!["prod*", "staging"]
+        );
+        assert!(parse_patterns("").is_empty());
+    }
+}
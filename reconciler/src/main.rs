@@ -1,12 +1,20 @@
 use env_common::interface::{initialize_project_id_and_region, GenericCloudHandler};
-use env_common::logic::driftcheck_infra;
-use env_defs::{CloudProvider, ExtraData};
-use env_utils::setup_logging;
-use futures::future::join_all;
+use env_common::logic::{driftcheck_infra, is_deployment_in_progress};
+use env_defs::{CloudProvider, DeploymentResp, ExtraData};
+use env_utils::{get_epoch, setup_logging};
+use futures::stream::{self, StreamExt};
 use lambda_runtime::{service_fn, Error, LambdaEvent};
 use log::{error, info};
+use reconciler::{DriftSweepConfig, DriftSweepMetrics};
 use serde_json::{json, Value};
 
+enum DriftCheckOutcome {
+    Requested,
+    Failed,
+    /// A job is already running for the deployment
+    Skipped,
+}
+
 async fn func(event: LambdaEvent<Value>) -> Result<Value, Error> {
     let (_event, _context) = event.into_parts();
 
@@ -26,17 +34,103 @@ async fn func(event: LambdaEvent<Value>) -> Result<Value, Error> {
         }
     };
 
-    // Launch drift checks for each deployment asynchronously to run them in parallel
-    let drift_checks = deployments.clone().into_iter().map(|deployment| {
+    // Check the most important deployments first and leave the rest for the next run, they
+    // stay due since their next drift check epoch is only moved once a check is requested
+    let config = DriftSweepConfig::from_env();
+    let queued = deployments.len();
+    let (deployments, deferred) = config.select(deployments);
+    if !deferred.is_empty() {
+        info!(
+            "Deferring {} drift checks to the next run (limit {} per run)",
+            deferred.len(),
+            config.max_per_run
+        );
+    }
+
+    // Launch drift checks one priority class after the other, in random order after a random
+    // delay each within a class, with at most `concurrency` requests in flight, so the runners
+    // are not hit by all checks of a run at once
+    let mut results: Vec<DriftCheckOutcome> = vec![];
+    for class in config.priority_classes(deployments.clone()) {
+        results.extend(launch_drift_checks(&config, class).await);
+    }
+
+    let count =
+        |outcome: fn(&DriftCheckOutcome) -> bool| results.iter().filter(|r| outcome(r)).count();
+    let metrics = DriftSweepMetrics {
+        queued,
+        executed: count(|r| matches!(r, DriftCheckOutcome::Requested)),
+        failed: count(|r| matches!(r, DriftCheckOutcome::Failed)),
+        skipped: count(|r| matches!(r, DriftCheckOutcome::Skipped)),
+        deferred: deferred.len(),
+    };
+    println!("{}", metrics.to_embedded_metrics(get_epoch()));
+
+    let drift_checked_deployment_ids = deployments
+        .into_iter()
+        .map(|deployment| {
+            json!({
+                "deployment_id": deployment.deployment_id,
+                "environment": deployment.environment,
+                // "seconds_since_last_driftcheck": deployment.next_drift_check_epoch - get_epoch(),
+
+            })
+        })
+        .collect::<Vec<Value>>();
+
+    let deferred_deployment_ids = deferred
+        .into_iter()
+        .map(|deployment| {
+            json!({
+                "deployment_id": deployment.deployment_id,
+                "environment": deployment.environment,
+            })
+        })
+        .collect::<Vec<Value>>();
+
+    let response = json!({
+        "status": "successful",
+        "drift_checked_deployments": drift_checked_deployment_ids,
+        "deferred_deployments": deferred_deployment_ids,
+        "metrics": {
+            "queued": metrics.queued,
+            "executed": metrics.executed,
+            "failed": metrics.failed,
+            "skipped": metrics.skipped,
+            "deferred": metrics.deferred,
+        },
+    });
+    println!("{}", serde_json::to_string_pretty(&response).unwrap());
+    Ok(response)
+}
+
+/// Requests drift checks of `deployments` after a random delay each, with at most `concurrency`
+/// requests in flight
+async fn launch_drift_checks(
+    config: &DriftSweepConfig,
+    deployments: Vec<DeploymentResp>,
+) -> Vec<DriftCheckOutcome> {
+    let drift_checks = deployments.into_iter().map(|deployment| {
         let deployment_id = deployment.deployment_id.clone();
         let environment = deployment.environment.clone();
         async move {
+            tokio::time::sleep(config.jitter()).await;
             println!(
                 "Deploymentid: {}, environment: {}",
                 deployment_id, environment
             );
             let remediate = deployment.drift_detection.auto_remediate;
             let handler = GenericCloudHandler::default().await;
+            let (in_progress, job_id, _, _) =
+                is_deployment_in_progress(&handler, &deployment_id, &environment, true, false)
+                    .await;
+            if in_progress {
+                info!(
+                    "Skipping drift check of {} in {}, job {} is already running",
+                    deployment_id, environment, job_id
+                );
+                return DriftCheckOutcome::Skipped;
+            }
             match driftcheck_infra(
                 &handler,
                 &deployment_id,
@@ -48,34 +142,20 @@ async fn func(event: LambdaEvent<Value>) -> Result<Value, Error> {
             {
                 Ok(_) => {
                     info!("Successfully requested drift check");
+                    DriftCheckOutcome::Requested
                 }
                 Err(e) => {
                     error!("Failed to request drift check: {}", e);
+                    DriftCheckOutcome::Failed
                 }
             }
         }
     });
 
-    join_all(drift_checks).await;
-
-    let drift_checked_deployment_ids = deployments
-        .into_iter()
-        .map(|deployment| {
-            json!({
-                "deployment_id": deployment.deployment_id,
-                "environment": deployment.environment,
-                // "seconds_since_last_driftcheck": deployment.next_drift_check_epoch - get_epoch(),
-
-            })
-        })
-        .collect::<Vec<Value>>();
-
-    let response = json!({
-        "status": "successful",
-        "drift_checked_deployments": drift_checked_deployment_ids,
-    });
-    println!("{}", serde_json::to_string_pretty(&response).unwrap());
-    Ok(response)
+    stream::iter(drift_checks)
+        .buffer_unordered(config.concurrency)
+        .collect()
+        .await
 }
 
 #[tokio::main]
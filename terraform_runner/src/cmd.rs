@@ -1,4 +1,5 @@
 use anyhow::{anyhow, Result};
+use env_utils::mask_secret_values;
 use std::collections::VecDeque;
 use tokio::io::{AsyncBufReadExt, BufReader};
 
@@ -36,6 +37,7 @@ pub async fn run_generic_command(
             stdout_line = stdout_reader.next_line(), if !stdout_done => {
                 match stdout_line {
                     Ok(Some(line)) => {
+                        let line = mask_secret_values(&line);
                         if echo_stdout {
                             log::info!("{}", line); // Print each line to stdout
                         }
@@ -57,6 +59,7 @@ pub async fn run_generic_command(
             stderr_line = stderr_reader.next_line(), if !stderr_done => {
                 match stderr_line {
                     Ok(Some(line)) => {
+                        let line = mask_secret_values(&line);
                         // Collect the line into the buffer
                         last_stderr_lines.push_back(line);
                         if last_stderr_lines.len() > max_output_lines {
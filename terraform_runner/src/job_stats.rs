@@ -0,0 +1,69 @@
+use env_common::interface::GenericCloudHandler;
+use env_common::logic::record_job_stats;
+use env_defs::{ApiInfraPayload, JobStats};
+use env_utils::get_epoch;
+use log::{error, info};
+use std::time::Instant;
+
+// Peak memory of the container, cgroup v2 first and then v1
+const PEAK_MEMORY_FILES: [&str; 2] = [
+    "/sys/fs/cgroup/memory.peak",
+    "/sys/fs/cgroup/memory/memory.max_usage_in_bytes",
+];
+
+/// Records how long the job took and the peak memory of the runner container, which the
+/// auto-sizing of later jobs of the module is based on
+pub async fn record_runner_job_stats(
+    handler: &GenericCloudHandler,
+    payload: &ApiInfraPayload,
+    job_id: &str,
+    started: Instant,
+) {
+    let stats = JobStats {
+        job_id: job_id.to_string(),
+        module: payload.module.clone(),
+        deployment_id: payload.deployment_id.clone(),
+        environment: payload.environment.clone(),
+        command: payload.command.clone(),
+        epoch: get_epoch(),
+        duration_seconds: started.elapsed().as_secs(),
+        peak_memory_mb: peak_memory_mb(),
+        cpu: payload.cpu.clone(),
+        memory: payload.memory.clone(),
+    };
+    info!(
+        "Job took {}s with a peak memory of {}",
+        stats.duration_seconds,
+        stats
+            .peak_memory_mb
+            .map(|mb| format!("{} MB", mb))
+            .unwrap_or_else(|| "unknown".to_string())
+    );
+    if let Err(e) = record_job_stats(handler, &stats).await {
+        error!("Failed to record job stats: {}", e);
+    }
+}
+
+fn peak_memory_mb() -> Option<u64> {
+    PEAK_MEMORY_FILES
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| parse_peak_memory_mb(&content))
+}
+
+fn parse_peak_memory_mb(content: &str) -> Option<u64> {
+    let bytes: u64 = content.trim().parse().ok()?;
+    Some(bytes.div_ceil(1024 * 1024))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_peak_memory_mb() {
+        assert_eq!(parse_peak_memory_mb("536870912\n"), Some(512));
+        assert_eq!(parse_peak_memory_mb("536870913"), Some(513));
+        assert_eq!(parse_peak_memory_mb("max"), None);
+    }
+}
@@ -1,12 +1,15 @@
 mod cmd;
 mod deployment;
+mod job_stats;
 mod module;
 mod opa;
 mod read;
 mod runner;
+mod secrets;
+mod storage;
 mod terraform;
 mod utils;
-mod webhook;
+mod workspace;
 
 pub use cmd::{run_generic_command, CommandResult};
 pub use deployment::get_initial_deployment;
@@ -16,10 +19,12 @@ pub use opa::{
 };
 pub use read::read_module_from_file;
 pub use runner::{run_terraform_runner, setup_misc};
+pub use secrets::{
+    resolve_secret_refs, AwsSecretsManagerStore, AwsSsmStore, AzureKeyVaultStore, SecretStore,
+};
 pub use terraform::{
     record_apply_destroy_changes, run_terraform_command, set_up_provider_mirror,
-    terraform_apply_destroy, terraform_init, terraform_output, terraform_plan, terraform_show,
-    terraform_state_list, terraform_validate,
+    terraform_apply_destroy, terraform_force_unlock, terraform_init, terraform_output,
+    terraform_plan, terraform_show, terraform_state_list, terraform_validate,
 };
 pub use utils::get_env_var;
-pub use webhook::post_webhook;
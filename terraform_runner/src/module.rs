@@ -1,7 +1,10 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use env_common::DeploymentStatusHandler;
-use env_defs::{ApiInfraPayload, CloudProvider, DeploymentStatus, ModuleResp, OciArtifactSet};
-use env_utils::get_module_zip_from_oci_targz;
+use env_defs::{
+    ApiInfraPayload, ArtifactPolicyViolation, ArtifactVerificationPolicy, CloudProvider,
+    DeploymentStatus, ModuleResp, OciArtifactSet,
+};
+use env_utils::{current_oci_platform, get_module_zip_from_oci_targz_for_platform};
 use log::{error, info};
 use std::path::Path;
 
@@ -47,12 +50,19 @@ pub async fn download_module_oci(
     handler: &GenericCloudHandler,
     oci_artifact_set: &OciArtifactSet,
     destination: &str,
+    policy: &ArtifactVerificationPolicy,
+    pinned_digest: Option<&str>,
 ) -> Result<ModuleResp, anyhow::Error> {
-    let files: Vec<String> = vec![
+    // Fail before downloading anything if the artifact can't satisfy the policy
+    policy.check(oci_artifact_set, pinned_digest)?;
+
+    let mut files: Vec<String> = vec![
         oci_artifact_set.tag_main.clone(),
-        oci_artifact_set.tag_signature.as_ref().unwrap().clone(),
         oci_artifact_set.tag_attestation.as_ref().unwrap().clone(),
     ];
+    if let Some(tag_signature) = &oci_artifact_set.tag_signature {
+        files.push(tag_signature.clone());
+    }
 
     log::info!("Downloading module oci files: {:?}", files);
     if !Path::new(destination).exists() {
@@ -84,12 +94,26 @@ pub async fn download_module_oci(
         }
     }
 
-    env_utils::verify_oci_artifacts_offline(oci_artifact_set, None)
-        .map_err(|e| anyhow::anyhow!("Error verifying OCI artifacts: {:?}", e))?;
+    env_utils::verify_oci_artifacts_offline(oci_artifact_set, None, policy)
+        .context("Error verifying OCI artifacts")?;
+
+    let platform = current_oci_platform(Some(handler.get_cloud_provider()));
+    if !oci_artifact_set.platforms.is_empty()
+        && !oci_artifact_set
+            .platforms
+            .iter()
+            .any(|p| p.matches(&platform))
+    {
+        return Err(anyhow::anyhow!(
+            "Module is not published for platform {}",
+            platform
+        ));
+    }
 
     let artifact_path = format!("{}/{}.tar.gz", destination, oci_artifact_set.tag_main);
-    let module_zip_bytes = get_module_zip_from_oci_targz(&artifact_path)
-        .map_err(|e| anyhow::anyhow!("Error extracting module zip from OCI tar.gz: {:?}", e))?;
+    let module_zip_bytes =
+        get_module_zip_from_oci_targz_for_platform(&artifact_path, Some(&platform))
+            .map_err(|e| anyhow::anyhow!("Error extracting module zip from OCI tar.gz: {:?}", e))?;
     let zip_destination = format!("{}/module.zip", destination);
     log::info!("Store zip bytes to: {}", zip_destination);
     env_utils::store_zip_bytes(&module_zip_bytes, Path::new(&zip_destination))
@@ -199,24 +223,41 @@ pub async fn download_module(
         log::info!(
             "OCI Artifact Mode is enabled, downloading OCI artifact and running verifications..."
         );
-        let module_oci = match download_module_oci(
-            handler,
-            &module_from_db.oci_artifact_set.clone().unwrap(),
-            "./",
-        )
-        .await
-        {
+        let oci_artifact_set = module_from_db.oci_artifact_set.clone().unwrap();
+        let pinned_digest = status_handler.get_module_digest().map(str::to_string);
+        // A failed policy lookup fails the job the same way as a failed download
+        let downloaded = match env_common::get_artifact_verification_policy(handler).await {
+            Ok(policy) => {
+                download_module_oci(
+                    handler,
+                    &oci_artifact_set,
+                    "./",
+                    &policy,
+                    pinned_digest.as_deref(),
+                )
+                .await
+            }
+            Err(e) => Err(e.context("Failed to read the artifact verification policy")),
+        };
+        let module_oci = match downloaded {
             Ok(module) => Ok(module),
             Err(e) => {
                 log::info!("Error preparing: {:?}", e);
-                let status = DeploymentStatus::FailedPrepare;
+                // Policy violations get their own status so they are not mistaken for transient errors
+                let status = if e.downcast_ref::<ArtifactPolicyViolation>().is_some() {
+                    DeploymentStatus::FailedIntegrityCheck
+                } else {
+                    DeploymentStatus::FailedPrepare
+                };
                 status_handler.set_status(status);
                 status_handler.set_event_duration();
+                status_handler.set_error_text(format!("{:#}", e));
                 status_handler.send_event(handler).await;
                 status_handler.send_deployment(handler).await?;
                 Err(anyhow!("Error running terraform init: {}", e))
             }
         }?;
+        status_handler.set_module_digest(Some(oci_artifact_set.digest.clone()));
 
         if compare_module_integrity(&module_oci, module_from_db, handler, status_handler).await? {
             log::info!("Passed integrity check: module metadata from OCI registry matches the module in the database");
@@ -243,7 +284,12 @@ async fn compare_module_integrity(
     let mut oci_value = serde_json::to_value(module_oci)?;
     let mut db_value = serde_json::to_value(module_from_db)?;
 
-    let ignored_fields = ["timestamp", "oci_artifact_set", "version_diff"];
+    let ignored_fields = [
+        "timestamp",
+        "oci_artifact_set",
+        "version_diff",
+        "oci_reference",
+    ];
 
     for field in &ignored_fields {
         oci_value.as_object_mut().unwrap().remove(*field);
@@ -1,8 +1,9 @@
 use env_common::interface::GenericCloudHandler;
+use env_common::logic::{get_applicable_policies, policy_env_data};
 use env_common::DeploymentStatusHandler;
-use env_defs::{CloudProvider, DeploymentStatus, PolicyResult};
+use env_defs::{ApiInfraPayload, CloudProvider, DeploymentStatus, PolicyResult, PolicyTarget};
 use serde_json::{json, Value};
-use std::{env, fs::File, path::Path, process::exit};
+use std::{fs::File, path::Path, process::exit};
 
 use crate::cmd::{run_generic_command, CommandResult};
 
@@ -93,6 +94,7 @@ pub fn get_all_rego_filenames_in_cwd() -> Vec<String> {
 
 #[tracing::instrument(skip_all)]
 pub async fn run_opa_policy_checks(
+    payload: &ApiInfraPayload,
     handler: &GenericCloudHandler,
     status_handler: &mut DeploymentStatusHandler<'_>,
 ) -> Result<(), anyhow::Error> {
@@ -108,13 +110,25 @@ pub async fn run_opa_policy_checks(
         "Finding all applicable policies for {}...",
         &policy_environment
     );
-    let policies = handler.get_all_policies(&policy_environment).await.unwrap();
+    let policies = get_applicable_policies(
+        handler,
+        &policy_environment,
+        &payload.project_id,
+        &payload.environment,
+        &payload.module_track,
+    )
+    .await
+    .unwrap();
 
     let mut policy_results: Vec<PolicyResult> = vec![];
     let mut failed_policy_evaluation = false;
 
     log::info!("Running OPA policy checks...");
-    for policy in policies {
+    // Claim policies were checked when the claim was submitted
+    let policies = policies
+        .into_iter()
+        .filter(|(policy, _)| policy.manifest.spec.target == PolicyTarget::Plan);
+    for (policy, policy_pack) in policies {
         download_policy(&policy).await;
 
         // Store policy input in a JSON file
@@ -176,6 +190,7 @@ pub async fn run_opa_policy_checks(
                     policy_name: policy.policy_name.clone(),
                     failed,
                     violations: policy_violations,
+                    policy_pack,
                 });
             }
             Err(e) => {
@@ -219,16 +234,8 @@ pub async fn run_opa_policy_checks(
 }
 
 fn store_env_as_json(file_path: &str) -> std::io::Result<()> {
-    // Important for OPA policy checks
-    let aws_default_region = env::var("AWS_DEFAULT_REGION").unwrap_or_else(|_| "".to_string());
-    let aws_region = env::var("AWS_REGION").unwrap_or_else(|_| "".to_string());
-
-    let env_vars = json!({
-        "env": {
-            "AWS_DEFAULT_REGION": aws_default_region,
-            "AWS_REGION": aws_region
-        }
-    });
+    // Important for OPA policy checks, `infraweave policy test` uses the same document
+    let env_vars = policy_env_data();
 
     let env_file_path = Path::new(file_path);
     let env_file = File::create(env_file_path).unwrap();
@@ -1,10 +1,13 @@
 use anyhow::{anyhow, Result};
 use env_common::interface::GenericCloudHandler;
-use env_common::logic::{driftcheck_infra, publish_notification};
+use env_common::logic::{
+    dependents_to_trigger, notify_webhooks, publish_notification, release_job_slot,
+    resolve_value_from_refs, trigger_dependent_infra, FORCE_UNLOCK_COMMAND,
+};
 use env_common::DeploymentStatusHandler;
 use env_defs::{
-    ApiInfraPayload, ApiInfraPayloadWithVariables, CloudProvider, Dependency, Dependent,
-    DeploymentResp, DeploymentStatus, ExtraData, JobDetails, NotificationData,
+    ApiInfraPayload, ApiInfraPayloadWithVariables, CloudProvider, Dependency, DeploymentResp,
+    DeploymentStatus, ExtraData, JobDetails, NotificationData, WebhookEvent,
 };
 use env_utils::{store_backend_file, store_tf_vars_json};
 use futures::future::join_all;
@@ -15,19 +18,25 @@ use std::any::Any;
 use std::env;
 use std::panic::AssertUnwindSafe;
 use std::process::exit;
+use std::time::Instant;
 use std::vec;
 
+use crate::job_stats::record_runner_job_stats;
 use crate::module::{download_module, get_module};
-use crate::terraform::terraform_graph;
+use crate::secrets::resolve_secret_refs;
+use crate::storage::JobStorage;
+use crate::terraform::{extra_environment_variables, terraform_graph};
+use crate::workspace::{cache_workspace, restore_workspace};
 use crate::{
     get_initial_deployment, record_apply_destroy_changes, run_opa_policy_checks,
-    set_up_provider_mirror, terraform_apply_destroy, terraform_init, terraform_output,
-    terraform_plan, terraform_show, terraform_state_list, terraform_validate,
+    set_up_provider_mirror, terraform_apply_destroy, terraform_force_unlock, terraform_init,
+    terraform_output, terraform_plan, terraform_show, terraform_state_list, terraform_validate,
 };
 
 pub async fn run_terraform_runner(
     handler: &GenericCloudHandler,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let started = Instant::now();
     let payload = parse_payload_env_var();
 
     // Skeleton with empty variables; real values are fetched from the DB
@@ -45,6 +54,25 @@ pub async fn run_terraform_runner(
     )
     .await;
     let completion = finish_runner_flow(handler, &mut status_handler, flow_result).await;
+    record_runner_job_stats(
+        handler,
+        &payload_with_variables.payload,
+        status_handler.get_job_id(),
+        started,
+    )
+    .await;
+    notify_apply_completion(
+        handler,
+        &payload_with_variables.payload,
+        &status_handler,
+        &completion,
+    )
+    .await;
+
+    // Lets the queued jobs of the project take the slot of this job
+    if let Err(e) = release_job_slot(handler, status_handler.get_job_id()).await {
+        error!("Failed to start queued jobs: {}", e);
+    }
 
     publish_runner_notification(
         handler,
@@ -69,10 +97,16 @@ async fn run_runner_flow_crash_guarded<'a>(
     status_handler: &mut DeploymentStatusHandler<'a>,
     payload: &'a ApiInfraPayload,
 ) -> Result<(), anyhow::Error> {
+    let job_storage = JobStorage::prepare(handler).await?;
+
     let outcome = AssertUnwindSafe(run_runner_inner(handler, status_handler, payload))
         .catch_unwind()
         .await;
 
+    if let Some(job_storage) = job_storage {
+        job_storage.cleanup();
+    }
+
     match outcome {
         Ok(result) => result,
         Err(panic) => Err(anyhow!(
@@ -142,6 +176,43 @@ async fn flush_failed_status_if_needed(
     }
 }
 
+async fn notify_apply_completion(
+    handler: &GenericCloudHandler,
+    payload: &ApiInfraPayload,
+    status_handler: &DeploymentStatusHandler<'_>,
+    completion: &RunnerCompletion,
+) {
+    if payload.command != "apply" {
+        return;
+    }
+    let (event, message) = if completion.error_text.is_empty() {
+        (
+            WebhookEvent::ApplySucceeded,
+            format!(
+                "Apply of {} in {} succeeded",
+                payload.deployment_id, payload.environment
+            ),
+        )
+    } else {
+        (
+            WebhookEvent::ApplyFailed,
+            format!(
+                "Apply of {} in {} failed: {}",
+                payload.deployment_id, payload.environment, completion.error_text
+            ),
+        )
+    };
+    notify_webhooks(
+        handler,
+        payload,
+        event,
+        status_handler.get_job_id(),
+        status_handler.get_status(),
+        &message,
+    )
+    .await;
+}
+
 async fn publish_runner_notification(
     handler: &GenericCloudHandler,
     payload: &ApiInfraPayload,
@@ -179,9 +250,23 @@ async fn publish_runner_notification(
         ExtraData::None => {}
     }
 
+    // Best effort, the notification is still sent without owners if the module can't be read
+    let owners = handler
+        .get_module_version(
+            &payload.module,
+            &payload.module_track,
+            &payload.module_version,
+        )
+        .await
+        .ok()
+        .flatten()
+        .map(|module| module.manifest.spec.owners)
+        .unwrap_or_default();
+
     let notification = NotificationData {
         subject: "runner_event".to_string(),
         message: serde_json::to_value(extra_data)?,
+        owners,
     };
     publish_notification(handler, notification).await?;
     Ok(())
@@ -197,8 +282,10 @@ async fn run_runner_inner<'a>(
     let (variables, job_id_for_variables) = fetch_deployment_variables(handler, payload).await?;
     status_handler.set_variables(variables.clone());
 
-    log::info!("Storing terraform variables in tf_vars.json...");
-    store_tf_vars_json(&variables, ".");
+    // An apply of a plan job writes them once the workspace of the plan is restored
+    if payload.plan_job_id.is_none() {
+        store_job_tf_vars(handler, payload, &variables).await?;
+    }
     store_backend_file(
         GenericCloudHandler::default().await.get_backend_provider(),
         ".",
@@ -215,7 +302,16 @@ async fn run_runner_inner<'a>(
     if let Some(d) = &initial_deployment {
         status_handler.set_output(d.output.clone());
         status_handler.set_policy_results(d.policy_results.clone());
+        // The digest stays pinned as long as the deployment runs the same module version
+        if d.module_version == payload.module_version {
+            status_handler.set_module_digest(d.module_digest.clone());
+        }
+    }
+    if let Some(trigger_reason) = &payload.trigger_reason {
+        status_handler.set_metadata(json!({ "trigger_reason": trigger_reason }));
     }
+    status_handler.set_idempotency_key(payload.idempotency_key.clone());
+    let previous_output = status_handler.get_output().clone();
 
     let job_id = get_current_job_id(handler, status_handler).await?;
     ensure_valid_job_id(status_handler, handler, &job_id, &job_id_for_variables).await?;
@@ -226,8 +322,36 @@ async fn run_runner_inner<'a>(
     }
     status_handler.send_event(handler).await;
     status_handler.send_deployment(handler).await?;
+    if command == "apply" {
+        notify_webhooks(
+            handler,
+            payload,
+            WebhookEvent::ApplyStarted,
+            &job_id,
+            status_handler.get_status(),
+            &format!(
+                "Apply of {} in {} started",
+                payload.deployment_id, payload.environment
+            ),
+        )
+        .await;
+    }
 
-    terraform_flow(handler, status_handler, payload, &job_id).await
+    terraform_flow(handler, status_handler, payload, &job_id, &previous_output).await
+}
+
+/// Writes the tfvars of the job. The deployment keeps the secret and output references, only the
+/// tfvars get their values, which is why they are left out of the cached workspace
+async fn store_job_tf_vars(
+    handler: &GenericCloudHandler,
+    payload: &ApiInfraPayload,
+    variables: &Value,
+) -> Result<(), anyhow::Error> {
+    let tf_vars = resolve_value_from_refs(handler, variables, &payload.environment).await?;
+    let tf_vars = resolve_secret_refs(&tf_vars).await?;
+    log::info!("Storing terraform variables in terraform.tfvars.json...");
+    store_tf_vars_json(&tf_vars, ".");
+    Ok(())
 }
 
 async fn terraform_flow<'a>(
@@ -235,6 +359,7 @@ async fn terraform_flow<'a>(
     status_handler: &mut DeploymentStatusHandler<'a>,
     payload: &'a ApiInfraPayload,
     job_id: &str,
+    previous_output: &Value,
 ) -> Result<(), anyhow::Error> {
     let command = &payload.command;
 
@@ -261,13 +386,55 @@ async fn terraform_flow<'a>(
         }
     }
 
-    download_module(handler, &module, status_handler).await?;
+    if command == FORCE_UNLOCK_COMMAND {
+        // Only the backend is needed to release the lock, nothing is planned or applied
+        download_module(handler, &module, status_handler).await?;
+        terraform_init(payload, handler, status_handler).await?;
+        terraform_force_unlock(payload, handler, status_handler).await?;
+
+        status_handler.set_status(DeploymentStatus::Successful);
+        status_handler.set_event_duration();
+        status_handler.set_last_event_epoch();
+        status_handler.send_event(handler).await;
+        status_handler.send_deployment(handler).await?;
+        return Ok(());
+    }
+
+    let extra_environment_variables = extra_environment_variables(handler, payload).await;
+
+    let plan_std_output = match &payload.plan_job_id {
+        Some(plan_job_id) => {
+            // Modules are restored with the workspace, init only restores the providers from the mirror
+            let plan_std_output =
+                restore_planned_workspace(handler, payload, plan_job_id, status_handler).await?;
+            terraform_init(payload, handler, status_handler).await?;
+            plan_std_output
+        }
+        None => {
+            download_module(handler, &module, status_handler).await?;
+
+            terraform_init(payload, handler, status_handler).await?;
 
-    terraform_init(payload, handler, status_handler).await?;
+            terraform_validate(payload, handler, status_handler).await?;
 
-    terraform_validate(payload, handler, status_handler).await?;
+            let plan_std_output = terraform_plan(
+                payload,
+                handler,
+                status_handler,
+                &extra_environment_variables,
+            )
+            .await?;
 
-    let plan_std_output = terraform_plan(payload, handler, status_handler).await?;
+            let refresh_only = payload.flags.iter().any(|e| e == "-refresh-only");
+            if command == "plan" && !refresh_only {
+                // Failing to cache only prevents applying this plan later, so the plan itself still succeeds
+                if let Err(e) = cache_workspace(handler, payload, job_id, &plan_std_output).await {
+                    log::warn!("Failed to cache workspace: {:?}", e);
+                }
+            }
+            plan_std_output
+        }
+    };
 
     terraform_show(
         payload,
@@ -282,10 +449,16 @@ async fn terraform_flow<'a>(
 
     terraform_graph(payload, job_id, handler, status_handler).await?;
 
-    run_opa_policy_checks(handler, status_handler).await?;
+    run_opa_policy_checks(payload, handler, status_handler).await?;
 
     if command == "apply" || command == "destroy" {
-        let apply_result = terraform_apply_destroy(payload, handler, status_handler).await;
+        let apply_result = terraform_apply_destroy(
+            payload,
+            handler,
+            status_handler,
+            &extra_environment_variables,
+        )
+        .await;
 
         terraform_show(
             payload,
@@ -369,51 +542,100 @@ async fn terraform_flow<'a>(
     status_handler.send_event(handler).await;
     status_handler.send_deployment(handler).await?;
 
-    // if !dependents.is_empty() {
-    //     _trigger_dependent_deployments(&dependents).await; // TODO: WIP: needs to launch with replaced variables
-    // }
+    if command == "apply" {
+        trigger_dependent_deployments(
+            handler,
+            payload,
+            previous_output,
+            status_handler.get_output(),
+        )
+        .await;
+    }
 
     Ok(())
 }
 
-async fn _trigger_dependent_deployments(dependent_deployments: &Vec<Dependent>) {
+async fn restore_planned_workspace(
+    handler: &GenericCloudHandler,
+    payload: &ApiInfraPayload,
+    plan_job_id: &str,
+    status_handler: &mut DeploymentStatusHandler<'_>,
+) -> Result<String, anyhow::Error> {
+    match restore_workspace(handler, payload, plan_job_id).await {
+        Ok(plan_std_output) => {
+            store_job_tf_vars(handler, payload, &status_handler.get_variables()).await?;
+            Ok(plan_std_output)
+        }
+        Err(e) => {
+            log::info!("Error restoring workspace: {:?}", e);
+            status_handler.set_status(DeploymentStatus::FailedPrepare);
+            status_handler.set_event_duration();
+            status_handler.set_error_text(e.to_string());
+            status_handler.send_event(handler).await;
+            status_handler.send_deployment(handler).await?;
+            status_handler.set_error_text("".to_string());
+            Err(anyhow!(
+                "Error restoring workspace of plan job {}: {}",
+                plan_job_id,
+                e
+            ))
+        }
+    }
+}
+
+/// Queues plan or apply jobs (per dependency `onOutputChange` setting) for all deployments
+/// depending on this one when the apply changed its outputs. Failures are logged but do not fail
+/// the current job.
+async fn trigger_dependent_deployments(
+    handler: &GenericCloudHandler,
+    payload: &ApiInfraPayload,
+    previous_output: &Value,
+    output: &Value,
+) {
+    let dependents = match handler
+        .get_dependents(&payload.deployment_id, &payload.environment)
+        .await
+    {
+        Ok(dependents) => dependents,
+        Err(e) => {
+            error!("Failed to get dependents to retrigger: {}", e);
+            return;
+        }
+    };
+
     // Retrigger each deployment asynchronously to run them in parallel
-    let dependent_deployment_runs = dependent_deployments.clone().into_iter().map(|dependent| {
-        let deployment_id = dependent.dependent_id.clone();
-        let environment = dependent.environment.clone();
-        async move {
-            log::info!(
-                "Deploymentid: {}, environment: {}",
-                deployment_id,
-                environment
-            );
-            let remediate = true; // Always apply remediation for dependent deployments (=> terraform apply)
-            let handler = GenericCloudHandler::default().await;
-            match driftcheck_infra(
-                &handler,
-                &deployment_id,
-                &environment,
-                remediate,
-                ExtraData::None,
-            )
-            .await
+    let triggered = dependents_to_trigger(previous_output, output, &dependents);
+    let dependent_deployment_runs = triggered
+        .into_iter()
+        .map(|(dependent, command)| async move {
+            let Some(command) = command else {
+                info!(
+                    "Outputs changed, ignoring dependent {} in {} as configured",
+                    dependent.dependent_id, dependent.environment
+                );
+                return;
+            };
+            match trigger_dependent_infra(handler, dependent, command, &payload.deployment_id).await
             {
-                Ok(_) => {
-                    info!("Successfully requested drift check");
+                Ok(job_id) => {
+                    info!(
+                        "Outputs changed, requested {} for dependent {} in {} (job_id: {})",
+                        dependent.on_output_change,
+                        dependent.dependent_id,
+                        dependent.environment,
+                        job_id
+                    );
                 }
                 Err(e) => {
-                    error!("Failed to request drift check: {}", e);
+                    error!(
+                        "Failed to retrigger dependent {} in {}: {}",
+                        dependent.dependent_id, dependent.environment, e
+                    );
                 }
             }
-        }
-    });
+        });
 
     join_all(dependent_deployment_runs).await;
-
-    info!(
-        "Successfully retriggered dependent deployments {:?}",
-        dependent_deployments
-    );
 }
 
 /// Parse the PAYLOAD env var into an ApiInfraPayload. A parse failure leaves
@@ -659,7 +881,7 @@ async fn check_dependants(
 
     if !dependants.is_empty() {
         let status = DeploymentStatus::HasDependants;
-        status_handler.set_error_text("This deployment has other deployments depending on it, and hence cannot be removed until they are removed".to_string());
+        status_handler.set_error_text("This deployment has other deployments depending on it, and hence cannot be removed until they are removed. Use `destroy --cascade` in the CLI to destroy them in dependency order".to_string());
         status_handler.set_status(status);
         status_handler.set_event_duration();
         status_handler.send_event(handler).await;
@@ -0,0 +1,120 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use env_defs::{SecretProvider, SecretRef};
+use env_utils::register_secret_value;
+use serde_json::Value;
+
+const KEY_VAULT_API_VERSION: &str = "7.4";
+
+/// Secret store the runner reads `secretRef` variables from, with the credentials of the runner
+#[async_trait]
+pub trait SecretStore: Send + Sync {
+    async fn get_secret(&self, key: &str) -> Result<String>;
+}
+
+pub struct AwsSecretsManagerStore;
+pub struct AwsSsmStore;
+pub struct AzureKeyVaultStore;
+
+#[async_trait]
+impl SecretStore for AwsSecretsManagerStore {
+    async fn get_secret(&self, key: &str) -> Result<String> {
+        let config = aws_config::load_from_env().await;
+        let client = aws_sdk_secretsmanager::Client::new(&config);
+        let resp = client.get_secret_value().secret_id(key).send().await?;
+        resp.secret_string
+            .ok_or_else(|| anyhow!("Secret {} has no string value", key))
+    }
+}
+
+#[async_trait]
+impl SecretStore for AwsSsmStore {
+    async fn get_secret(&self, key: &str) -> Result<String> {
+        let config = aws_config::load_from_env().await;
+        let client = aws_sdk_ssm::Client::new(&config);
+        let resp = client
+            .get_parameter()
+            .name(key)
+            .with_decryption(true)
+            .send()
+            .await?;
+        resp.parameter
+            .and_then(|parameter| parameter.value)
+            .ok_or_else(|| anyhow!("Parameter {} has no value", key))
+    }
+}
+
+#[async_trait]
+impl SecretStore for AzureKeyVaultStore {
+    async fn get_secret(&self, key: &str) -> Result<String> {
+        let (vault, name) = key
+            .split_once('/')
+            .ok_or_else(|| anyhow!("Key Vault secret {} is not <vault-name>/<secret-name>", key))?;
+        let credential = env_azure::get_credential().await?;
+        let token = credential
+            .get_token(&["https://vault.azure.net/.default"], None)
+            .await
+            .map_err(|e| anyhow!("Failed to get Key Vault token: {}", e))?;
+
+        // https://learn.microsoft.com/en-us/rest/api/keyvault/secrets/get-secret/get-secret
+        let url = format!(
+            "https://{}.vault.azure.net/secrets/{}?api-version={}",
+            vault, name, KEY_VAULT_API_VERSION
+        );
+        let secret: Value = reqwest::Client::new()
+            .get(&url)
+            .bearer_auth(token.token.secret())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        secret["value"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Key Vault secret {} has no value", key))
+    }
+}
+
+fn secret_store(provider: SecretProvider) -> Box<dyn SecretStore> {
+    match provider {
+        SecretProvider::AwsSecretsManager => Box::new(AwsSecretsManagerStore),
+        SecretProvider::AwsSsm => Box::new(AwsSsmStore),
+        SecretProvider::AzureKeyVault => Box::new(AzureKeyVaultStore),
+    }
+}
+
+/// The variables with each `secretRef` replaced by the value of the secret, for the tfvars of the
+/// job only. The values are registered to be masked in logs and never stored with the deployment
+pub async fn resolve_secret_refs(variables: &Value) -> Result<Value> {
+    let Some(map) = variables.as_object() else {
+        return Ok(variables.clone());
+    };
+
+    let mut resolved = map.clone();
+    for (name, value) in resolved.iter_mut() {
+        let Some(secret_ref) = SecretRef::from_value(value) else {
+            continue;
+        };
+        let secret_ref =
+            secret_ref.with_context(|| format!("Invalid secretRef for variable {}", name))?;
+        let secret = secret_store(secret_ref.provider)
+            .get_secret(&secret_ref.key)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to read secret {} from {} for variable {}",
+                    secret_ref.key, secret_ref.provider, name
+                )
+            })?;
+        register_secret_value(&secret);
+        log::info!(
+            "Resolved variable {} from {} secret {}",
+            name,
+            secret_ref.provider,
+            secret_ref.key
+        );
+        *value = Value::String(secret);
+    }
+    Ok(Value::Object(resolved))
+}
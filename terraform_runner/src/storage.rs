@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use env_common::interface::GenericCloudHandler;
+use env_defs::CloudProvider;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+// Job directories of runners that were killed before cleaning up are removed by later jobs
+const STALE_JOB_DIRECTORY_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+static PROVIDER_MIRROR_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Provider mirror on the mounted volume, if the job runs on one
+pub fn provider_mirror_override() -> Option<&'static Path> {
+    PROVIDER_MIRROR_DIR.get().map(PathBuf::as_path)
+}
+
+/// Directory of a single job on the mounted volume of the project, holding its working
+/// directory and provider mirror so concurrent jobs sharing the volume don't interfere
+pub struct JobStorage {
+    job_dir: PathBuf,
+    original_dir: PathBuf,
+}
+
+impl JobStorage {
+    /// Moves the working directory of the runner to the mounted volume configured in
+    /// `settings.runner_storage` of the project, or does nothing if none is configured
+    #[tracing::instrument(skip_all)]
+    pub async fn prepare(handler: &GenericCloudHandler) -> Result<Option<JobStorage>> {
+        let project_id = handler.get_project_id();
+        let projects = handler
+            .get_all_projects()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to read runner storage settings: {}", e))?;
+        let mount_path = projects
+            .into_iter()
+            .find(|project| project.project_id == project_id)
+            .and_then(|project| project.settings.runner_storage.mount_path);
+        let Some(mount_path) = mount_path else {
+            return Ok(None);
+        };
+
+        let job_id = handler.get_current_job_id().await?;
+        let jobs_dir = Path::new(&mount_path).join("jobs");
+        remove_stale_job_directories(&jobs_dir);
+
+        let job_dir = jobs_dir.join(sanitize_job_id(&job_id));
+        let workspace_dir = job_dir.join("workspace");
+        let mirror_dir = job_dir.join(".provider-mirror");
+        for dir in [&workspace_dir, &mirror_dir] {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create directory {:?}", dir))?;
+        }
+
+        let original_dir = std::env::current_dir()?;
+        std::env::set_current_dir(&workspace_dir)
+            .with_context(|| format!("Failed to change directory to {:?}", workspace_dir))?;
+        let _ = PROVIDER_MIRROR_DIR.set(mirror_dir);
+        log::info!("Using mounted storage at {:?} for this job", job_dir);
+
+        Ok(Some(JobStorage {
+            job_dir,
+            original_dir,
+        }))
+    }
+
+    /// Moves back to the original working directory and removes the job directory
+    pub fn cleanup(self) {
+        if let Err(e) = std::env::set_current_dir(&self.original_dir) {
+            log::warn!(
+                "Failed to change directory to {:?}: {}",
+                self.original_dir,
+                e
+            );
+        }
+        match std::fs::remove_dir_all(&self.job_dir) {
+            Ok(_) => log::info!("Removed job directory {:?}", self.job_dir),
+            Err(e) => log::warn!("Failed to remove job directory {:?}: {}", self.job_dir, e),
+        }
+    }
+}
+
+/// Job ids are ARNs in AWS and resource ids in Azure, keep only what is safe in a path segment
+fn sanitize_job_id(job_id: &str) -> String {
+    job_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn remove_stale_job_directories(jobs_dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(jobs_dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let is_stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > STALE_JOB_DIRECTORY_AGE);
+        if is_stale {
+            log::info!("Removing stale job directory {:?}", entry.path());
+            if let Err(e) = std::fs::remove_dir_all(entry.path()) {
+                log::warn!("Failed to remove {:?}: {}", entry.path(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_job_id() {
+        assert_eq!(
+            sanitize_job_id("arn:aws:ecs:eu-west-1:123456789012:task/cluster/abc123"),
+            "arn_aws_ecs_eu-west-1_123456789012_task_cluster_abc123"
+        );
+        assert_eq!(sanitize_job_id("job-1.2_3"), "job-1.2_3");
+    }
+}
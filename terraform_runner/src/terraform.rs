@@ -1,23 +1,86 @@
-use env_common::logic::insert_infra_change_record;
+use env_common::logic::{
+    insert_infra_change_record, lock_id_flag, notify_webhooks, parse_state_lock,
+};
 use env_common::DeploymentStatusHandler;
 use env_common::{interface::GenericCloudHandler, logic::upload_file_to_change_records};
 use env_defs::{
-    sanitize_resource_changes_from_plan, ApiInfraPayload, CloudProvider, DeploymentStatus,
-    InfraChangeRecord, TfLockProvider,
+    redact_plan_json, sanitize_resource_changes_from_plan, sanitize_terraform_output,
+    ApiInfraPayload, CloudProvider, DeploymentStatus, InfraChangeRecord, ResourceAction,
+    TfLockProvider, WebhookEvent,
+};
+use env_utils::{
+    get_epoch, get_extra_environment_variables, get_provider_url_key, get_timestamp,
+    mask_secret_values_in_json, merge_json_dicts,
 };
-use env_utils::{get_epoch, get_extra_environment_variables, get_provider_url_key, get_timestamp};
 use futures::stream::{self, StreamExt};
 use std::{
+    collections::HashMap,
     env,
     path::{Path, PathBuf},
 };
 use tokio::fs;
 
-use serde_json::Value;
+use serde_json::{json, Value};
 
 use anyhow::{anyhow, Context, Result};
 
-use crate::{post_webhook, run_generic_command, CommandResult};
+use crate::storage::provider_mirror_override;
+use crate::{run_generic_command, CommandResult};
+
+/// Plan or state JSON as stored with the change records, with its sensitive values redacted
+/// unless the runner is started with `PLAN_STORAGE_FULL_FIDELITY=true`, meant for dev environments
+fn plan_json_for_storage(raw_json: &str) -> String {
+    if env::var("PLAN_STORAGE_FULL_FIDELITY").is_ok_and(|v| v == "true") {
+        return raw_json.to_string();
+    }
+    match serde_json::from_str::<Value>(raw_json) {
+        Ok(content) => mask_secret_values_in_json(redact_plan_json(&content)).to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Extra environment variables of the deployment, including the ones configured on the platform.
+/// Read once per job and passed to the terraform commands that need them
+pub async fn extra_environment_variables(
+    handler: &GenericCloudHandler,
+    payload: &ApiInfraPayload,
+) -> HashMap<String, String> {
+    let platform_variables = match handler.get_environment_variables().await {
+        Ok(variables) => variables,
+        Err(e) => {
+            log::warn!("Failed to get platform environment variables: {}", e);
+            Value::Null
+        }
+    };
+    get_extra_environment_variables(payload, &platform_variables)
+}
+
+/// Sets the error text of a failed terraform command. When the command failed to acquire the
+/// state lock, the holder of the lock is put in front of it and recorded as `state_lock` in the
+/// metadata of the event
+fn set_command_error_text(
+    payload: &ApiInfraPayload,
+    status_handler: &mut DeploymentStatusHandler<'_>,
+    error_text: String,
+) {
+    match parse_state_lock(&error_text) {
+        Some(lock) => {
+            log::warn!("The state is locked by {} ({})", lock.who, lock.id);
+            let mut metadata = match status_handler.get_metadata() {
+                Value::Null => json!({}),
+                metadata => metadata.clone(),
+            };
+            merge_json_dicts(&mut metadata, &json!({ "state_lock": lock.to_json() }));
+            status_handler.set_metadata(metadata);
+            status_handler.set_error_text(format!(
+                "{}\n\n{}",
+                lock.describe(&payload.deployment_id, &payload.environment),
+                error_text
+            ));
+        }
+        None => status_handler.set_error_text(error_text),
+    }
+}
 
 #[allow(clippy::too_many_arguments)]
 #[tracing::instrument(skip_all, fields(command = %command))]
@@ -36,6 +99,7 @@ pub async fn run_terraform_command(
     environment: &str,
     max_output_lines: usize,
     extra_environment_variables: Option<&std::collections::HashMap<String, String>>,
+    targets: &[String],
 ) -> Result<CommandResult, anyhow::Error> {
     let mut exec = tokio::process::Command::new("terraform");
     exec.arg(command)
@@ -71,6 +135,10 @@ pub async fn run_terraform_command(
         exec.arg("-json");
     }
 
+    for target in targets {
+        exec.arg(format!("-target={}", target));
+    }
+
     if plan_in {
         exec.arg("planfile");
     }
@@ -123,6 +191,7 @@ pub async fn terraform_init(
         environment,
         50,
         None,
+        &[],
     )
     .await
     {
@@ -135,8 +204,10 @@ pub async fn terraform_init(
             let status = DeploymentStatus::FailedInit;
             status_handler.set_status(status);
             status_handler.set_event_duration();
+            set_command_error_text(payload, status_handler, e.to_string());
             status_handler.send_event(handler).await;
             status_handler.send_deployment(handler).await?;
+            status_handler.set_error_text("".to_string());
             Err(anyhow!("Error running terraform init: {}", e))
         }
     }
@@ -167,6 +238,7 @@ pub async fn terraform_validate(
         environment,
         50,
         None,
+        &[],
     )
     .await
     {
@@ -215,6 +287,7 @@ pub async fn terraform_graph(
         environment,
         usize::MAX,
         None,
+        &[],
     )
     .await
     {
@@ -270,6 +343,7 @@ pub async fn terraform_plan(
     payload: &ApiInfraPayload,
     handler: &GenericCloudHandler,
     status_handler: &mut DeploymentStatusHandler<'_>,
+    extra_environment_variables: &HashMap<String, String>,
 ) -> Result<String, anyhow::Error> {
     let deployment_id = &payload.deployment_id;
     let environment = &payload.environment;
@@ -293,7 +367,8 @@ pub async fn terraform_plan(
         deployment_id,
         environment,
         500,
-        Some(&get_extra_environment_variables(payload)),
+        Some(extra_environment_variables),
+        &payload.targets,
     )
     .await
     {
@@ -303,11 +378,10 @@ pub async fn terraform_plan(
         }
         Err(e) => {
             log::info!("Error running \"terraform plan\" command: {:?}", e);
-            let error_text = e.to_string();
             let status = DeploymentStatus::FailedPlan;
             status_handler.set_status(status);
             status_handler.set_event_duration();
-            status_handler.set_error_text(error_text);
+            set_command_error_text(payload, status_handler, e.to_string());
             status_handler.send_event(handler).await;
             status_handler.send_deployment(handler).await?;
             status_handler.set_error_text("".to_string());
@@ -350,6 +424,7 @@ pub async fn terraform_show(
         environment,
         5000,
         None,
+        &[],
     )
     .await
     {
@@ -380,7 +455,8 @@ pub async fn terraform_show(
                 upload_suffix
             );
 
-            match upload_file_to_change_records(handler, &output_json_key, output_json).await {
+            let stored_json = plan_json_for_storage(output_json);
+            match upload_file_to_change_records(handler, &output_json_key, &stored_json).await {
                 Ok(_) => {
                     log::info!(
                         "Successfully uploaded \"tofu {cmd}\" output file ({})",
@@ -402,36 +478,18 @@ pub async fn terraform_show(
                 status_handler.set_drift_has_occurred(drift_has_occurred);
 
                 if drift_has_occurred {
-                    for webhook in &payload.drift_detection.webhooks {
-                        match &webhook.url {
-                            Some(url) => {
-                                match post_webhook(
-                                    url,
-                                    &format!(
-                                        "Drift has occurred for {} in {}",
-                                        deployment_id, environment
-                                    ),
-                                )
-                                .await
-                                {
-                                    Ok(_) => {
-                                        log::info!("Webhook {:?} sent successfully", webhook);
-                                    }
-                                    Err(e) => {
-                                        log::info!(
-                                            "Error sending webhook: {:?} with url: {:?}",
-                                            e,
-                                            webhook
-                                        );
-                                        // Don't fail the deployment if the webhook fails
-                                    }
-                                }
-                            }
-                            None => {
-                                log::warn!("Webhook URL not provided");
-                            }
-                        }
-                    }
+                    notify_webhooks(
+                        handler,
+                        payload,
+                        WebhookEvent::DriftDetected,
+                        job_id,
+                        status_handler.get_status(),
+                        &format!(
+                            "Drift has occurred for {} in {}",
+                            deployment_id, environment
+                        ),
+                    )
+                    .await;
                 }
             }
 
@@ -456,7 +514,33 @@ pub async fn terraform_show(
                     change_type: "plan".to_string(),
                     resource_changes,
                     variables: status_handler.get_variables(),
+                    partial: !payload.targets.is_empty(),
+                    approved_by: payload.approved_by.clone(),
+                    chunks: None,
                 };
+
+                // Drift checks also get a drift record, which the deployment's drift report is read from
+                if command == "plan" && refresh_only {
+                    let drift_change_record = InfraChangeRecord {
+                        change_type: "drift".to_string(),
+                        resource_changes: infra_change_record
+                            .resource_changes
+                            .iter()
+                            .filter(|change| change.action != ResourceAction::NoOp)
+                            .cloned()
+                            .collect(),
+                        ..infra_change_record.clone()
+                    };
+                    match insert_infra_change_record(handler, drift_change_record).await {
+                        Ok(_) => {
+                            log::info!("Infra change record for drift inserted");
+                        }
+                        Err(e) => {
+                            log::info!("Error inserting drift change record: {:?}", e);
+                        }
+                    }
+                }
+
                 match insert_infra_change_record(handler, infra_change_record).await {
                     Ok(_) => {
                         log::info!("Infra change record for plan inserted");
@@ -520,7 +604,8 @@ pub async fn record_apply_destroy_changes(
         job_id,
     );
 
-    match upload_file_to_change_records(handler, &mutate_raw_json_key, &raw_plan_json).await {
+    let stored_json = plan_json_for_storage(&raw_plan_json);
+    match upload_file_to_change_records(handler, &mutate_raw_json_key, &stored_json).await {
         Ok(_) => {
             log::info!("Successfully uploaded apply/destroy output file");
         }
@@ -544,6 +629,9 @@ pub async fn record_apply_destroy_changes(
         change_type: payload.command.to_string(),
         resource_changes,
         variables: status_handler.get_variables(),
+        partial: !payload.targets.is_empty(),
+        approved_by: payload.approved_by.clone(),
+        chunks: None,
     };
 
     let _record_id = insert_infra_change_record(handler, infra_change_record)
@@ -558,6 +646,7 @@ pub async fn terraform_apply_destroy<'a>(
     payload: &'a ApiInfraPayload,
     handler: &GenericCloudHandler,
     status_handler: &mut DeploymentStatusHandler<'a>,
+    extra_environment_variables: &HashMap<String, String>,
 ) -> Result<String, anyhow::Error> {
     let cmd = &payload.command;
     let deployment_id = &payload.deployment_id;
@@ -565,6 +654,11 @@ pub async fn terraform_apply_destroy<'a>(
 
     status_handler.set_command(cmd);
 
+    // An apply of a restored plan job applies its plan file, which fails if the state changed since the plan
+    let plan_in = payload.plan_job_id.is_some();
+    // The targets of a plan file are part of the plan, and terraform rejects them alongside it
+    let targets: &[String] = if plan_in { &[] } else { &payload.targets };
+
     match run_terraform_command(
         cmd,
         false,
@@ -574,12 +668,13 @@ pub async fn terraform_apply_destroy<'a>(
         true,
         false,
         false,
-        false,
+        plan_in,
         false,
         deployment_id,
         environment,
         50,
-        Some(&get_extra_environment_variables(payload)),
+        Some(extra_environment_variables),
+        targets,
     )
     .await
     {
@@ -594,11 +689,10 @@ pub async fn terraform_apply_destroy<'a>(
         }
         Err(e) => {
             log::info!("Error running \"terraform {}\" command: {:?}", cmd, e);
-            let error_text = e.to_string();
             let status = DeploymentStatus::Error;
             status_handler.set_status(status);
             status_handler.set_event_duration();
-            status_handler.set_error_text(error_text);
+            set_command_error_text(payload, status_handler, e.to_string());
             status_handler.send_event(handler).await;
             status_handler.send_deployment(handler).await?;
             status_handler.set_error_text("".to_string());
@@ -607,22 +701,48 @@ pub async fn terraform_apply_destroy<'a>(
     }
 }
 
-fn sanitize_terraform_output(mut output: Value) -> Value {
-    if let Some(map) = output.as_object_mut() {
-        for (_, v) in map.iter_mut() {
-            if let Some(val_map) = v.as_object_mut() {
-                if let Some(sensitive) = val_map.get("sensitive") {
-                    if sensitive.as_bool().unwrap_or(false) {
-                        val_map.insert(
-                            "value".to_string(),
-                            Value::String("(output sanitized)".to_string()),
-                        );
-                    }
-                }
-            }
+/// Releases the state lock given to the force-unlock job. Requires an initialized working directory
+#[tracing::instrument(skip_all, fields(cmd = %payload.command, module = %payload.module, version = %payload.module_version))]
+pub async fn terraform_force_unlock(
+    payload: &ApiInfraPayload,
+    handler: &GenericCloudHandler,
+    status_handler: &mut DeploymentStatusHandler<'_>,
+) -> Result<(), anyhow::Error> {
+    let lock_id = lock_id_flag(payload).ok_or_else(|| anyhow!("No lock ID given to unlock"))?;
+
+    // -force skips the interactive confirmation, the CLI asks for it before starting the job
+    let mut exec = tokio::process::Command::new("terraform");
+    exec.arg("force-unlock")
+        .arg("-force")
+        .arg(lock_id)
+        .current_dir(Path::new("./"))
+        .env("TF_CLI_CONFIG_FILE", "/app/.terraformrc")
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    log::info!(
+        "Running terraform command: terraform force-unlock -force {}",
+        lock_id
+    );
+
+    match run_generic_command(&mut exec, 50, true).await {
+        Ok(_) => {
+            log::info!("Released state lock {}", lock_id);
+            status_handler.set_metadata(json!({ "released_lock_id": lock_id }));
+            Ok(())
+        }
+        Err(e) => {
+            log::info!("Error running \"terraform force-unlock\" command: {:?}", e);
+            let status = DeploymentStatus::Error;
+            status_handler.set_status(status);
+            status_handler.set_event_duration();
+            status_handler
+                .set_error_text(format!("Failed to release state lock {}: {}", lock_id, e));
+            status_handler.send_event(handler).await;
+            status_handler.send_deployment(handler).await?;
+            status_handler.set_error_text("".to_string());
+            Err(anyhow!("Error running terraform force-unlock: {}", e))
         }
     }
-    output
 }
 
 #[tracing::instrument(skip_all, fields(cmd = %payload.command, module = %payload.module, version = %payload.module_version))]
@@ -650,6 +770,7 @@ pub async fn terraform_output(
         environment,
         10000,
         None,
+        &[],
     )
     .await
     {
@@ -769,6 +890,19 @@ async fn download_all_providers(
     Ok(())
 }
 
+fn provider_mirror_dir() -> String {
+    if let Some(dir) = provider_mirror_override() {
+        dir.to_string_lossy().to_string()
+    } else if std::env::var("TEST_MODE").is_ok() {
+        env::temp_dir()
+            .join(".provider-mirror")
+            .to_string_lossy()
+            .to_string()
+    } else {
+        "/app/.provider-mirror".to_string()
+    }
+}
+
 #[tracing::instrument(skip_all, fields(provider = %tf_lock_provider.source, version = %tf_lock_provider.version))]
 async fn download_provider(
     handler: &GenericCloudHandler,
@@ -776,14 +910,7 @@ async fn download_provider(
     target: &str,
     category: &str,
 ) -> Result<()> {
-    let mirror_dir = if std::env::var("TEST_MODE").is_ok() {
-        env::temp_dir()
-            .join(".provider-mirror")
-            .to_string_lossy()
-            .to_string()
-    } else {
-        "/app/.provider-mirror".to_string()
-    };
+    let mirror_dir = provider_mirror_dir();
     let (_url, s3_key) = get_provider_url_key(tf_lock_provider, target, category).await?;
     let destination = format!("{mirror_dir}/{s3_key}",);
 
@@ -813,14 +940,7 @@ pub async fn set_up_provider_mirror(
     provider_versions: &[TfLockProvider],
     target: &str,
 ) -> Result<(), anyhow::Error> {
-    let mirror_dir = if std::env::var("TEST_MODE").is_ok() {
-        env::temp_dir()
-            .join(".provider-mirror")
-            .to_string_lossy()
-            .to_string()
-    } else {
-        "/app/.provider-mirror".to_string()
-    };
+    let mirror_dir = provider_mirror_dir();
     fs::create_dir_all(&mirror_dir).await?;
 
     let content = format!(
@@ -855,30 +975,3 @@ provider_installation {{
     download_all_providers(handler, provider_versions, target).await?;
     Ok(())
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde_json::json;
-
-    #[test]
-    fn test_sanitize_terraform_output() {
-        let output = json!({
-            "resource_name": {
-                "sensitive": false,
-                "type": "string",
-                "value": "some-name-here"
-            },
-            "secret_password": {
-                "sensitive": true,
-                "type": "string",
-                "value": "this_is_supersecret"
-            }
-        });
-
-        let sanitized = sanitize_terraform_output(output);
-
-        assert_eq!(sanitized["resource_name"]["value"], "some-name-here");
-        assert_eq!(sanitized["secret_password"]["value"], "(output sanitized)");
-    }
-}
@@ -0,0 +1,173 @@
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as base64, Engine as _};
+use env_common::interface::GenericCloudHandler;
+use env_common::logic::{download_file_from_change_records, upload_file_to_change_records};
+use env_defs::{ApiInfraPayload, CloudProvider};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::path::Path;
+
+const PLAN_OUTPUT_FILE: &str = "plan_output.txt";
+
+/// Base64 encoded 256-bit key of the runner that the cached workspace is encrypted with
+const WORKSPACE_ENCRYPTION_KEY_ENV: &str = "INFRAWEAVE_WORKSPACE_ENCRYPTION_KEY";
+
+// Provider binaries are restored from the provider mirror by `terraform init` instead,
+// keeping the cached workspace small. The tfvars are written again by the apply. The saved
+// plan embeds the same variable values, secrets included, so the workspace is encrypted
+const EXCLUDED_PATHS: &[&str] = &[
+    ".provider-mirror",
+    ".terraform/providers",
+    ".terraformrc",
+    "module.zip",
+    "terraform.tfvars.json",
+];
+
+fn workspace_key(handler: &GenericCloudHandler, payload: &ApiInfraPayload, job_id: &str) -> String {
+    format!(
+        "{}{}/{}/{}_workspace.zip",
+        handler.get_storage_basepath(),
+        payload.environment,
+        payload.deployment_id,
+        job_id
+    )
+}
+
+/// Stores the initialized workspace with its plan file, so an apply of this plan job can
+/// restore it and apply exactly the planned changes. The plan file holds every variable value
+/// of the job in cleartext, so the workspace is only stored encrypted with the key of the runner
+#[tracing::instrument(skip_all, fields(job_id = %job_id))]
+pub async fn cache_workspace(
+    handler: &GenericCloudHandler,
+    payload: &ApiInfraPayload,
+    job_id: &str,
+    plan_std_output: &str,
+) -> Result<()> {
+    let encryption_key = workspace_encryption_key()?;
+    std::fs::write(PLAN_OUTPUT_FILE, plan_std_output)?;
+    let key = workspace_key(handler, payload, job_id);
+    let workspace = seal_workspace(&encryption_key, &key, workspace_zip(Path::new("./"))?)?;
+    upload_file_to_change_records(handler, &key, &workspace).await?;
+    log::info!("Cached workspace ({} bytes) to {}", workspace.len(), key);
+    Ok(())
+}
+
+fn workspace_zip(directory: &Path) -> Result<Vec<u8>> {
+    Ok(env_utils::zip_directory(directory, EXCLUDED_PATHS)?)
+}
+
+fn workspace_encryption_key() -> Result<LessSafeKey> {
+    let encoded = std::env::var(WORKSPACE_ENCRYPTION_KEY_ENV).map_err(|_| {
+        anyhow!(
+            "{} is not set, so plan workspaces are not cached",
+            WORKSPACE_ENCRYPTION_KEY_ENV
+        )
+    })?;
+    let bytes = base64.decode(encoded.trim()).map_err(|e| {
+        anyhow!(
+            "{} is not valid base64: {}",
+            WORKSPACE_ENCRYPTION_KEY_ENV,
+            e
+        )
+    })?;
+    let key = UnboundKey::new(&AES_256_GCM, &bytes)
+        .map_err(|_| anyhow!("{} must be 32 bytes", WORKSPACE_ENCRYPTION_KEY_ENV))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Encrypts the workspace, bound to its storage key so it can't be restored as another job's
+/// workspace. The random nonce is prepended to the ciphertext
+fn seal_workspace(key: &LessSafeKey, storage_key: &str, mut workspace: Vec<u8>) -> Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow!("Failed to generate a nonce"))?;
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(storage_key.as_bytes()),
+        &mut workspace,
+    )
+    .map_err(|_| anyhow!("Failed to encrypt the workspace"))?;
+    Ok([nonce.to_vec(), workspace].concat())
+}
+
+fn open_workspace(key: &LessSafeKey, storage_key: &str, sealed: Vec<u8>) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return Err(anyhow!("The cached workspace is truncated"));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce)
+        .map_err(|_| anyhow!("The cached workspace is truncated"))?;
+    let mut ciphertext = ciphertext.to_vec();
+    let workspace = key
+        .open_in_place(nonce, Aad::from(storage_key.as_bytes()), &mut ciphertext)
+        .map_err(|_| anyhow!("Failed to decrypt the cached workspace, was the key rotated?"))?;
+    Ok(workspace.to_vec())
+}
+
+/// Restores the workspace cached by a plan job into the current directory and returns the
+/// output of its plan
+#[tracing::instrument(skip_all, fields(plan_job_id = %plan_job_id))]
+pub async fn restore_workspace(
+    handler: &GenericCloudHandler,
+    payload: &ApiInfraPayload,
+    plan_job_id: &str,
+) -> Result<String> {
+    let encryption_key = workspace_encryption_key()?;
+    let key = workspace_key(handler, payload, plan_job_id);
+    let workspace = download_file_from_change_records(handler, &key)
+        .await
+        .map_err(|e| {
+            anyhow!(
+                "No cached workspace found for plan job {}: {}",
+                plan_job_id,
+                e
+            )
+        })?;
+    let workspace = open_workspace(&encryption_key, &key, workspace)?;
+    env_utils::unzip_vec_to(&workspace, Path::new("./"))?;
+    log::info!(
+        "Restored workspace of plan job {} from {}",
+        plan_job_id,
+        key
+    );
+    Ok(std::fs::read_to_string(PLAN_OUTPUT_FILE)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_zip_excludes_tfvars() {
+        let workspace = env_utils::tempdir().unwrap();
+        std::fs::write(workspace.path().join("main.tf"), "").unwrap();
+        std::fs::write(
+            workspace.path().join("terraform.tfvars.json"),
+            r#"{"password": "secret"}"#,
+        )
+        .unwrap();
+
+        let zip = workspace_zip(workspace.path()).unwrap();
+        let restored = env_utils::tempdir().unwrap();
+        env_utils::unzip_vec_to(&zip, restored.path()).unwrap();
+        assert!(restored.path().join("main.tf").exists());
+        assert!(!restored.path().join("terraform.tfvars.json").exists());
+    }
+
+    #[test]
+    fn test_sealed_workspace_only_opens_for_its_storage_key() {
+        let key = LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &[7; 32]).unwrap());
+        let workspace = b"planfile with a password".to_vec();
+
+        let sealed =
+            seal_workspace(&key, "dev/bucket/job1_workspace.zip", workspace.clone()).unwrap();
+        assert!(!sealed
+            .windows(b"password".len())
+            .any(|window| window == b"password"));
+
+        let opened = open_workspace(&key, "dev/bucket/job1_workspace.zip", sealed.clone());
+        assert_eq!(opened.unwrap(), workspace);
+        assert!(open_workspace(&key, "dev/bucket/job2_workspace.zip", sealed).is_err());
+    }
+}
@@ -0,0 +1,123 @@
+/// AWS partition of `region`: `aws-us-gov` for the GovCloud regions, `aws-cn` for the China
+/// regions and `aws` for all other regions
+pub fn aws_partition(region: &str) -> &'static str {
+    if region.starts_with("us-gov-") {
+        "aws-us-gov"
+    } else if region.starts_with("cn-") {
+        "aws-cn"
+    } else {
+        "aws"
+    }
+}
+
+/// Domain of the service endpoints of `partition`, e.g. `amazonaws.com`
+pub fn aws_dns_suffix(partition: &str) -> &'static str {
+    match partition {
+        "aws-cn" => "amazonaws.com.cn",
+        _ => "amazonaws.com",
+    }
+}
+
+/// Regional endpoint of `service` in `region`, e.g. `https://sts.cn-north-1.amazonaws.com.cn`
+pub fn aws_endpoint(service: &str, region: &str) -> String {
+    format!(
+        "https://{}.{}.{}",
+        service,
+        region,
+        aws_dns_suffix(aws_partition(region))
+    )
+}
+
+/// ARN of a resource in the partition of `region`. `arn_region` is the region in the ARN, empty
+/// for global services such as IAM, e.g. `aws_arn("cn-north-1", "iam", "", "123", "role/x")`
+/// is `arn:aws-cn:iam::123:role/x`
+pub fn aws_arn(
+    region: &str,
+    service: &str,
+    arn_region: &str,
+    account_id: &str,
+    resource: &str,
+) -> String {
+    format!(
+        "arn:{}:{}:{}:{}:{}",
+        aws_partition(region),
+        service,
+        arn_region,
+        account_id,
+        resource
+    )
+}
+
+/// Partition, service, region, account ID and resource of an ARN in any partition
+pub fn parse_aws_arn(arn: &str) -> Option<(&str, &str, &str, &str, &str)> {
+    let mut parts = arn.splitn(6, ':');
+    if parts.next()? != "arn" {
+        return None;
+    }
+    Some((
+        parts.next()?,
+        parts.next()?,
+        parts.next()?,
+        parts.next()?,
+        parts.next()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aws_partition() {
+        assert_eq!(aws_partition("eu-west-1"), "aws");
+        assert_eq!(aws_partition("us-gov-west-1"), "aws-us-gov");
+        assert_eq!(aws_partition("cn-northwest-1"), "aws-cn");
+
+        assert_eq!(
+            aws_endpoint("sts", "us-east-1"),
+            "https://sts.us-east-1.amazonaws.com"
+        );
+        assert_eq!(
+            aws_endpoint("sts", "cn-north-1"),
+            "https://sts.cn-north-1.amazonaws.com.cn"
+        );
+        assert_eq!(
+            aws_endpoint("lambda", "us-gov-east-1"),
+            "https://lambda.us-gov-east-1.amazonaws.com"
+        );
+    }
+
+    #[test]
+    fn test_aws_arn() {
+        assert_eq!(
+            aws_arn("cn-north-1", "iam", "", "123456789012", "role/runner-prod"),
+            "arn:aws-cn:iam::123456789012:role/runner-prod"
+        );
+        assert_eq!(
+            aws_arn(
+                "us-gov-west-1",
+                "lambda",
+                "us-gov-west-1",
+                "123456789012",
+                "function:infraweave-api-prod"
+            ),
+            "arn:aws-us-gov:lambda:us-gov-west-1:123456789012:function:infraweave-api-prod"
+        );
+
+        assert_eq!(
+            parse_aws_arn("arn:aws-us-gov:sts::123456789012:assumed-role/Role/session"),
+            Some((
+                "aws-us-gov",
+                "sts",
+                "",
+                "123456789012",
+                "assumed-role/Role/session"
+            ))
+        );
+        assert_eq!(
+            parse_aws_arn("arn:aws:s3:::logs/a:b").map(|arn| arn.4),
+            Some("logs/a:b")
+        );
+        assert_eq!(parse_aws_arn("AIDAEXAMPLE"), None);
+    }
+}
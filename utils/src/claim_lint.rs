@@ -0,0 +1,466 @@
+use env_defs::ModuleResp;
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+
+use crate::{
+    convert_first_level_keys_to_snake_case, flatten_and_convert_first_level_keys_to_snake_case,
+    to_camel_case, verify_required_variables_are_set, verify_variable_existence_and_type,
+};
+
+const TOP_LEVEL_FIELDS: &[&str] = &["apiVersion", "kind", "metadata", "spec"];
+const METADATA_FIELDS: &[&str] = &["name", "namespace", "annotations", "labels"];
+const SPEC_FIELDS: &[&str] = &[
+    "moduleVersion",
+    "stackVersion",
+    "region",
+    "reference",
+    "variables",
+    "dependencies",
+    "driftDetection",
+    "varFile",
+    "targets",
+    "dependsOn",
+    "outputsTo",
+    "deletionPolicy",
+];
+
+/// Rules checked by `infraweave lint` as `(id, description)`
+pub const CLAIM_LINT_RULES: &[(&str, &str)] = &[
+    ("api-version", "apiVersion must be infraweave.io/v1"),
+    (
+        "unknown-field",
+        "Fields not known to a claim are ignored when it is applied",
+    ),
+    (
+        "missing-version",
+        "Exactly one of spec.moduleVersion and spec.stackVersion must be set",
+    ),
+    (
+        "variable-casing",
+        "Claim variables must be written in camelCase",
+    ),
+    (
+        "region-mismatch",
+        "The claim region differs from the region it is linted for",
+    ),
+    (
+        "unknown-version",
+        "The module or stack version of the claim does not exist",
+    ),
+    (
+        "module-schema",
+        "Variables must exist in the module, have the right type and include all required ones",
+    ),
+    (
+        "deprecated-version",
+        "The module or stack version of the claim is deprecated",
+    ),
+];
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Error,
+    Warning,
+}
+
+/// Problem found in a claim by one of the [`CLAIM_LINT_RULES`]
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct LintFinding {
+    pub rule: String,
+    pub severity: LintSeverity,
+    /// Dotted path to the offending field, e.g. `spec.variables.bucket_name`
+    pub path: String,
+    pub message: String,
+    /// Whether `fix_claim` resolves the finding
+    pub fixable: bool,
+}
+
+impl LintFinding {
+    fn new(rule: &str, severity: LintSeverity, path: &str, message: String) -> Self {
+        LintFinding {
+            rule: rule.to_string(),
+            severity,
+            path: path.to_string(),
+            message,
+            fixable: false,
+        }
+    }
+
+    fn fixable(mut self) -> Self {
+        self.fixable = true;
+        self
+    }
+}
+
+/// Checks the rules of a claim that don't need the module, with `expected_region` also flagging
+/// claims deploying elsewhere
+pub fn lint_claim(claim: &Value, expected_region: Option<&str>) -> Vec<LintFinding> {
+    let mut findings = vec![];
+
+    let api_version = claim["apiVersion"].as_str().unwrap_or("");
+    if api_version != "infraweave.io/v1" {
+        findings.push(LintFinding::new(
+            "api-version",
+            LintSeverity::Error,
+            "apiVersion",
+            format!(
+                "Unsupported apiVersion \"{}\", expected \"infraweave.io/v1\"",
+                api_version
+            ),
+        ));
+    }
+
+    lint_unknown_fields(claim, "", TOP_LEVEL_FIELDS, &mut findings);
+    lint_unknown_fields(
+        &claim["metadata"],
+        "metadata",
+        METADATA_FIELDS,
+        &mut findings,
+    );
+    lint_unknown_fields(&claim["spec"], "spec", SPEC_FIELDS, &mut findings);
+
+    match (
+        claim["spec"]["moduleVersion"].is_null(),
+        claim["spec"]["stackVersion"].is_null(),
+    ) {
+        (true, true) => findings.push(LintFinding::new(
+            "missing-version",
+            LintSeverity::Error,
+            "spec",
+            "Neither moduleVersion nor stackVersion are set, one should be set".to_string(),
+        )),
+        (false, false) => findings.push(LintFinding::new(
+            "missing-version",
+            LintSeverity::Error,
+            "spec",
+            "Both moduleVersion and stackVersion are set, only one should be set".to_string(),
+        )),
+        _ => {}
+    }
+
+    if let Some(variables) = claim["spec"]["variables"].as_mapping() {
+        for name in variables.keys().filter_map(Value::as_str) {
+            let camel_case = to_camel_case(name);
+            if name != camel_case {
+                let finding = LintFinding::new(
+                    "variable-casing",
+                    LintSeverity::Error,
+                    &format!("spec.variables.{}", name),
+                    format!(
+                        "Variable \"{}\" should be written as \"{}\"",
+                        name, camel_case
+                    ),
+                );
+                findings.push(if variables.contains_key(camel_case.as_str()) {
+                    finding
+                } else {
+                    finding.fixable()
+                });
+            }
+        }
+    }
+
+    if let Some(expected_region) = expected_region {
+        let regions: Vec<&str> = match &claim["spec"]["region"] {
+            Value::Sequence(regions) => regions.iter().filter_map(Value::as_str).collect(),
+            region => region.as_str().into_iter().collect(),
+        };
+        for region in regions.into_iter().filter(|r| *r != expected_region) {
+            findings.push(LintFinding::new(
+                "region-mismatch",
+                LintSeverity::Warning,
+                "spec.region",
+                format!(
+                    "Claim deploys to {} but is linted for {}",
+                    region, expected_region
+                ),
+            ));
+        }
+    }
+
+    findings
+}
+
+/// Checks the variables of a claim against the module or stack version it uses
+pub fn lint_claim_against_module(claim: &Value, module: &ModuleResp) -> Vec<LintFinding> {
+    let mut findings = vec![];
+    let version_field = if module.module_type == "stack" {
+        "spec.stackVersion"
+    } else {
+        "spec.moduleVersion"
+    };
+
+    if module.deprecated {
+        findings.push(LintFinding::new(
+            "deprecated-version",
+            LintSeverity::Warning,
+            version_field,
+            match &module.deprecated_message {
+                Some(message) => format!(
+                    "Version {} of {} is deprecated: {}",
+                    module.version, module.module, message
+                ),
+                None => format!(
+                    "Version {} of {} is deprecated",
+                    module.version, module.module
+                ),
+            },
+        ));
+    }
+
+    let provided_variables = match serde_json::to_value(&claim["spec"]["variables"]) {
+        Ok(serde_json::Value::Null) => serde_json::json!({}),
+        Ok(variables) => variables,
+        Err(e) => {
+            findings.push(LintFinding::new(
+                "module-schema",
+                LintSeverity::Error,
+                "spec.variables",
+                format!("Variables can't be read: {}", e),
+            ));
+            return findings;
+        }
+    };
+    let variables = if module.module_type == "stack" {
+        let dont_flatten: Vec<&String> = module
+            .tf_providers
+            .iter()
+            .flat_map(|p| p.tf_variables.iter().map(|v| &v.name))
+            .collect();
+        flatten_and_convert_first_level_keys_to_snake_case(&provided_variables, "", dont_flatten)
+    } else {
+        convert_first_level_keys_to_snake_case(&provided_variables)
+    };
+    if !variables.is_object() {
+        findings.push(LintFinding::new(
+            "module-schema",
+            LintSeverity::Error,
+            "spec.variables",
+            "Variables must be a mapping".to_string(),
+        ));
+        return findings;
+    }
+    for result in [
+        verify_variable_existence_and_type(module, &variables),
+        verify_required_variables_are_set(module, &variables),
+    ] {
+        if let Err(e) = result {
+            findings.push(LintFinding::new(
+                "module-schema",
+                LintSeverity::Error,
+                "spec.variables",
+                e.to_string(),
+            ));
+        }
+    }
+
+    findings
+}
+
+/// Rewrites the fixable findings of `lint_claim` in place, i.e. misspelled field names and
+/// variables not in camelCase, and returns how many fields were renamed
+pub fn fix_claim(claim: &mut Value) -> usize {
+    let mut fixed = rename_keys(claim, |key| known_field(key, TOP_LEVEL_FIELDS));
+    if let Some(metadata) = claim.get_mut("metadata") {
+        fixed += rename_keys(metadata, |key| known_field(key, METADATA_FIELDS));
+    }
+    if let Some(spec) = claim.get_mut("spec") {
+        fixed += rename_keys(spec, |key| known_field(key, SPEC_FIELDS));
+        if let Some(variables) = spec.get_mut("variables") {
+            fixed += rename_keys(variables, |key| {
+                Some(to_camel_case(key)).filter(|camel_case| camel_case != key)
+            });
+        }
+    }
+    fixed
+}
+
+fn lint_unknown_fields(
+    value: &Value,
+    path: &str,
+    known_fields: &[&str],
+    findings: &mut Vec<LintFinding>,
+) {
+    let Some(mapping) = value.as_mapping() else {
+        return;
+    };
+    for key in mapping.keys() {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        if known_fields.contains(&key) {
+            continue;
+        }
+        let field_path = if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        };
+        let finding = match known_field(key, known_fields) {
+            Some(field) if !mapping.contains_key(field.as_str()) => LintFinding::new(
+                "unknown-field",
+                LintSeverity::Warning,
+                &field_path,
+                format!("Unknown field \"{}\", did you mean \"{}\"?", key, field),
+            )
+            .fixable(),
+            _ => LintFinding::new(
+                "unknown-field",
+                LintSeverity::Warning,
+                &field_path,
+                format!("Unknown field \"{}\"", key),
+            ),
+        };
+        findings.push(finding);
+    }
+}
+
+/// Known field `key` is a casing variant of, e.g. `moduleVersion` for `module_version`
+fn known_field(key: &str, known_fields: &[&str]) -> Option<String> {
+    let camel_case = to_camel_case(key);
+    known_fields
+        .iter()
+        .find(|field| **field != key && field.eq_ignore_ascii_case(&camel_case))
+        .map(|field| field.to_string())
+}
+
+/// Renames the keys of a mapping that `rename` returns a new name for, keeping their order and
+/// leaving keys whose new name is already taken
+fn rename_keys(value: &mut Value, rename: impl Fn(&str) -> Option<String>) -> usize {
+    let Some(mapping) = value.as_mapping_mut() else {
+        return 0;
+    };
+    let mut renamed = 0;
+    let mut new_mapping = Mapping::new();
+    for (key, val) in std::mem::take(mapping) {
+        let new_key = key
+            .as_str()
+            .and_then(&rename)
+            .filter(|new_key| !new_mapping.contains_key(new_key.as_str()));
+        match new_key {
+            Some(new_key) => {
+                new_mapping.insert(Value::from(new_key), val);
+                renamed += 1;
+            }
+            None => {
+                new_mapping.insert(key, val);
+            }
+        }
+    }
+    *mapping = new_mapping;
+    renamed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim(yaml: &str) -> Value {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn rules(findings: &[LintFinding]) -> Vec<&str> {
+        findings.iter().map(|f| f.rule.as_str()).collect()
+    }
+
+    #[test]
+    fn test_lint_claim_valid() {
+        let claim = claim(
+            r#"
+apiVersion: infraweave.io/v1
+kind: S3Bucket
+metadata:
+  name: bucket
+spec:
+  moduleVersion: 0.1.0
+  region: eu-west-1
+  variables:
+    bucketName: my-bucket
+"#,
+        );
+        assert_eq!(lint_claim(&claim, Some("eu-west-1")), vec![]);
+    }
+
+    #[test]
+    fn test_lint_claim_findings() {
+        let claim = claim(
+            r#"
+apiVersion: infraweave.io/v2
+kind: S3Bucket
+metadata:
+  name: bucket
+spec:
+  module_version: 0.1.0
+  region: [eu-west-1, us-east-1]
+  colour: blue
+  variables:
+    bucket_name: my-bucket
+"#,
+        );
+        let findings = lint_claim(&claim, Some("eu-west-1"));
+        assert_eq!(
+            rules(&findings),
+            vec![
+                "api-version",
+                "unknown-field",
+                "unknown-field",
+                "missing-version",
+                "variable-casing",
+                "region-mismatch",
+            ]
+        );
+        assert_eq!(findings[1].path, "spec.module_version");
+        assert!(findings[1].fixable);
+        assert_eq!(findings[2].path, "spec.colour");
+        assert!(!findings[2].fixable);
+        assert_eq!(findings[4].path, "spec.variables.bucket_name");
+        assert!(findings[4].fixable);
+        assert_eq!(
+            findings[5].message,
+            "Claim deploys to us-east-1 but is linted for eu-west-1"
+        );
+    }
+
+    #[test]
+    fn test_fix_claim() {
+        let mut fixed = claim(
+            r#"
+apiVersion: infraweave.io/v1
+kind: S3Bucket
+metadata:
+  name: bucket
+spec:
+  module_version: 0.1.0
+  region: eu-west-1
+  variables:
+    bucket_name: my-bucket
+    tags: {}
+    enableVersioning: true
+    enable_versioning: false
+"#,
+        );
+        assert_eq!(fix_claim(&mut fixed), 2);
+        assert_eq!(fixed["spec"]["moduleVersion"], "0.1.0");
+        let variables: Vec<&str> = fixed["spec"]["variables"]
+            .as_mapping()
+            .unwrap()
+            .keys()
+            .filter_map(Value::as_str)
+            .collect();
+        // Keys keep their order and taken names are left for the user to resolve
+        assert_eq!(
+            variables,
+            vec![
+                "bucketName",
+                "tags",
+                "enableVersioning",
+                "enable_versioning"
+            ]
+        );
+
+        let findings = lint_claim(&fixed, None);
+        assert_eq!(rules(&findings), vec!["variable-casing"]);
+        assert!(!findings[0].fixable);
+    }
+}
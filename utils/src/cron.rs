@@ -0,0 +1,220 @@
+use anyhow::{anyhow, Result};
+
+const MINUTE_MILLIS: u128 = 60 * 1000;
+const HOUR_MILLIS: u128 = 60 * MINUTE_MILLIS;
+const DAY_MILLIS: u128 = 24 * HOUR_MILLIS;
+
+/// Days searched for the next match, long enough for schedules that only match on February 29
+const MAX_SEARCH_DAYS: u128 = 8 * 366;
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Cron expression with the five standard fields `minute hour day-of-month month day-of-week`,
+/// evaluated in UTC.
+///
+/// Each field is `*`, a value, a range `a-b`, or a comma-separated list of those, each optionally
+/// with a step (`*/15`, `8-18/2`). Months and days of the week can also be given by their first
+/// three letters (`jan`, `mon`), and both 0 and 7 are Sunday. Like in cron, a time matches when
+/// either the day of the month or the day of the week matches if both are restricted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days_of_month: Vec<bool>,
+    months: Vec<bool>,
+    days_of_week: Vec<bool>,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<CronSchedule> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(anyhow!(
+                "Cron expression '{}' must have 5 fields (minute hour day-of-month month day-of-week), found {}",
+                expression,
+                fields.len()
+            ));
+        }
+        let parse = |field: &str, min: u32, max: u32, names: &[&str]| {
+            parse_field(field, min, max, names)
+                .map_err(|e| anyhow!("Invalid cron expression '{}': {}", expression, e))
+        };
+
+        let mut days_of_week = parse(fields[4], 0, 7, &WEEKDAY_NAMES)?;
+        if days_of_week[7] {
+            days_of_week[0] = true;
+        }
+        days_of_week.truncate(7);
+
+        Ok(CronSchedule {
+            minutes: parse(fields[0], 0, 59, &[])?,
+            hours: parse(fields[1], 0, 23, &[])?,
+            days_of_month: parse(fields[2], 1, 31, &[])?,
+            months: parse(fields[3], 1, 12, &MONTH_NAMES)?,
+            days_of_week,
+            day_of_month_restricted: !fields[2].starts_with('*'),
+            day_of_week_restricted: !fields[4].starts_with('*'),
+        })
+    }
+
+    /// First time in epoch milliseconds after `epoch_millis` that matches the schedule, or None if
+    /// it never matches, such as on February 30
+    pub fn next_after(&self, epoch_millis: u128) -> Option<u128> {
+        let mut time = (epoch_millis / MINUTE_MILLIS + 1) * MINUTE_MILLIS;
+        let limit = time + MAX_SEARCH_DAYS * DAY_MILLIS;
+        while time < limit {
+            if !self.matches_day(time / DAY_MILLIS) {
+                time = (time / DAY_MILLIS + 1) * DAY_MILLIS;
+            } else if !self.hours[((time % DAY_MILLIS) / HOUR_MILLIS) as usize] {
+                time = (time / HOUR_MILLIS + 1) * HOUR_MILLIS;
+            } else if !self.minutes[((time % HOUR_MILLIS) / MINUTE_MILLIS) as usize] {
+                time += MINUTE_MILLIS;
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, days_since_epoch: u128) -> bool {
+        let (_, month, day) = civil_from_days(days_since_epoch);
+        // 1970-01-01 was a Thursday
+        let weekday = ((days_since_epoch + 4) % 7) as usize;
+        if !self.months[month as usize] {
+            return false;
+        }
+        let day_of_month = self.days_of_month[day as usize];
+        let day_of_week = self.days_of_week[weekday];
+        if self.day_of_month_restricted && self.day_of_week_restricted {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        }
+    }
+}
+
+/// Values of a field as flags indexed by the value, `names` map to values from `min`
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<Vec<bool>> {
+    let mut values = vec![false; max as usize + 1];
+    let parse_value = |value: &str| -> Result<u32> {
+        let lower = value.to_lowercase();
+        if let Some(index) = names.iter().position(|name| *name == lower) {
+            return Ok(min + index as u32);
+        }
+        let number: u32 = value
+            .parse()
+            .map_err(|_| anyhow!("'{}' is not a number", value))?;
+        if number < min || number > max {
+            return Err(anyhow!("{} is not between {} and {}", number, min, max));
+        }
+        Ok(number)
+    };
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| anyhow!("'{}' is not a valid step", step))?;
+                if step == 0 {
+                    return Err(anyhow!("the step of '{}' must be at least 1", part));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start)?, parse_value(end)?)
+        } else {
+            let value = parse_value(range)?;
+            // `5/15` runs from 5 to the end of the range, like `5-59/15`
+            (value, if step > 1 { max } else { value })
+        };
+        if start > end {
+            return Err(anyhow!("the range '{}' ends before it starts", range));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            values[value as usize] = true;
+        }
+    }
+    Ok(values)
+}
+
+/// Year, month and day of a number of days since 1970-01-01
+fn civil_from_days(days: u128) -> (u128, u32, u32) {
+    // Shift the epoch to 0000-03-01, so leap days are at the end of each 400 year era
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // 2024-03-15T10:07:30Z, a Friday
+    const NOW: u128 = 1_710_497_250_000;
+
+    fn timestamp(epoch_millis: u128) -> String {
+        crate::epoch_to_timestamp(epoch_millis)
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_797), (2024, 3, 15));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(10_957), (2000, 1, 1));
+    }
+
+    #[test]
+    fn test_next_after() {
+        let next = |expression: &str| {
+            CronSchedule::parse(expression)
+                .unwrap()
+                .next_after(NOW)
+                .map(timestamp)
+        };
+        assert_eq!(next("* * * * *").unwrap(), "2024-03-15T10:08:00.000Z");
+        assert_eq!(next("*/15 * * * *").unwrap(), "2024-03-15T10:15:00.000Z");
+        assert_eq!(next("0 6 * * *").unwrap(), "2024-03-16T06:00:00.000Z");
+        assert_eq!(next("30 9-17/4 * * *").unwrap(), "2024-03-15T13:30:00.000Z");
+        assert_eq!(next("0 6 * * mon-fri").unwrap(), "2024-03-18T06:00:00.000Z");
+        assert_eq!(next("0 0 1 jan,jul *").unwrap(), "2024-07-01T00:00:00.000Z");
+        assert_eq!(next("0 0 29 2 *").unwrap(), "2028-02-29T00:00:00.000Z");
+        assert_eq!(next("0 0 * * 7").unwrap(), "2024-03-17T00:00:00.000Z");
+        // Either the day of the month or the day of the week
+        assert_eq!(next("0 0 20 * sat").unwrap(), "2024-03-16T00:00:00.000Z");
+        assert_eq!(next("0 0 30 2 *"), None);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(CronSchedule::parse("0 6 * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("* * 0 * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("* 18-8 * * *").is_err());
+        assert!(CronSchedule::parse("* * * * someday").is_err());
+    }
+}
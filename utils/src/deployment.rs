@@ -1,5 +1,7 @@
-use crate::to_camel_case;
-use env_defs::{DeploymentResp, ModuleExample, ModuleResp, ModuleSpec};
+use crate::{claim_variables, is_required_variable, to_camel_case, to_snake_case};
+use std::collections::BTreeMap;
+
+use env_defs::{DeploymentResp, ModuleExample, ModuleResp, ModuleSpec, ModuleUi, TfVariable};
 
 pub fn generate_module_example_deployment(
     module: &ModuleSpec,
@@ -21,11 +23,107 @@ spec:
     ))
     .unwrap();
 
-    manifest["spec"]["variables"] = serde_yaml::to_value(module_example.variables.clone()).unwrap();
+    manifest["spec"]["variables"] = match &module.ui {
+        Some(ui) => order_example_variables(ui, &module_example.variables),
+        None => serde_yaml::to_value(module_example.variables.clone()).unwrap(),
+    };
 
     manifest
 }
 
+/// Example variables in the order of the groups of the module, without the variables hidden
+/// because the example sets the variable their `visibleWhen` depends on to another value.
+/// Keys are snake_case in `module.yaml` and camelCase once published, so both are matched
+fn order_example_variables(ui: &ModuleUi, variables: &serde_yaml::Value) -> serde_yaml::Value {
+    let serde_yaml::Value::Mapping(mapping) = variables else {
+        return variables.clone();
+    };
+    let name_of = |key: &serde_yaml::Value| to_snake_case(key.as_str().unwrap_or_default());
+    let value_of = |name: &str| {
+        mapping
+            .iter()
+            .find(|(key, _)| name_of(key) == name)
+            .and_then(|(_, value)| serde_json::to_value(value).ok())
+    };
+    let is_hidden = |name: &str| {
+        ui.visible_when.get(name).is_some_and(|condition| {
+            value_of(&condition.variable).is_some_and(|value| value != condition.equals)
+        })
+    };
+
+    let mut entries: Vec<_> = mapping
+        .iter()
+        .filter(|(key, _)| !is_hidden(&name_of(key)))
+        .collect();
+    entries.sort_by_key(|(key, _)| ui.position(&name_of(key)));
+    serde_yaml::Value::Mapping(
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+    )
+}
+
+/// Annotation set on claims fanned out from a multi-region claim, holding the original claim name
+pub const REGION_GROUP_ANNOTATION: &str = "infraweave.io/region-group";
+
+/// Expands a claim with `spec.region` set to a list of regions into one claim per region.
+///
+/// Each claim gets the region suffixed to `metadata.name`, so the deployment ids become
+/// `{module}/{name}-{region}`, and the original name in the `infraweave.io/region-group`
+/// annotation. Claims with a single region are returned unchanged.
+pub fn expand_claim_regions(
+    claim: &serde_yaml::Value,
+) -> Result<Vec<serde_yaml::Value>, anyhow::Error> {
+    let regions = match &claim["spec"]["region"] {
+        serde_yaml::Value::Sequence(regions) => regions,
+        _ => return Ok(vec![claim.clone()]),
+    };
+    let name = claim["metadata"]["name"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Claim is missing metadata.name"))?;
+    if regions.is_empty() {
+        return Err(anyhow::anyhow!("Claim {} has an empty region list", name));
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    let mut claims = Vec::with_capacity(regions.len());
+    for region in regions {
+        let region = region
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Claim {} has a region that is not a string", name))?;
+        if !seen.insert(region) {
+            return Err(anyhow::anyhow!(
+                "Claim {} lists region {} more than once",
+                name,
+                region
+            ));
+        }
+
+        let mut expanded = claim.clone();
+        expanded["spec"]["region"] = region.into();
+        expanded["metadata"]["name"] = format!("{}-{}", name, region).into();
+        if !matches!(
+            expanded["metadata"]["annotations"],
+            serde_yaml::Value::Mapping(_)
+        ) {
+            expanded["metadata"]["annotations"] =
+                serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+        }
+        expanded["metadata"]["annotations"][REGION_GROUP_ANNOTATION] = name.into();
+        claims.push(expanded);
+    }
+    Ok(claims)
+}
+
+/// Whether `deployment_id` is the deployment of `group_id` fanned out to `region`
+pub fn is_region_group_member(group_id: &str, deployment_id: &str, region: &str) -> bool {
+    deployment_id
+        .strip_prefix(group_id)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .is_some_and(|suffix| suffix == region)
+}
+
 pub fn generate_deployment_claim(deployment: &DeploymentResp, module: &ModuleResp) -> String {
     let variables = match &deployment.module_type.as_str() {
         &"stack" => deployment.variables.clone(),
@@ -77,3 +175,291 @@ metadata:
             .join("\n"),
     )
 }
+
+/// Value of `variable` in the first example of `module` that sets it
+pub fn module_example_value(module: &ModuleResp, variable: &str) -> Option<serde_yaml::Value> {
+    let key = to_camel_case(variable);
+    module
+        .manifest
+        .spec
+        .examples
+        .iter()
+        .flatten()
+        .find_map(|example| match &example.variables[key.as_str()] {
+            serde_yaml::Value::Null => None,
+            value => Some(value.clone()),
+        })
+}
+
+fn variable_comment(variable: &TfVariable) -> String {
+    let _type = match variable._type.as_str() {
+        Some(_type) => _type.to_string(),
+        None => variable._type.to_string(),
+    };
+    let mut details = vec![_type];
+    if variable.sensitive {
+        details.push("sensitive".to_string());
+    }
+    if variable.description.is_empty() {
+        format!("# ({})", details.join(", "))
+    } else {
+        format!(
+            "# {} ({})",
+            variable.description.replace('\n', " "),
+            details.join(", ")
+        )
+    }
+}
+
+/// YAML lines of `key: value`, with nested mappings and lists on their own lines
+fn yaml_entry_lines(key: &str, value: &serde_yaml::Value) -> Vec<String> {
+    let rendered = serde_yaml::to_string(value)
+        .unwrap()
+        .trim_start_matches("---\n")
+        .trim_end()
+        .to_string();
+    let nested = match value {
+        serde_yaml::Value::Mapping(m) => !m.is_empty(),
+        serde_yaml::Value::Sequence(s) => !s.is_empty(),
+        _ => false,
+    };
+    if nested {
+        std::iter::once(format!("{}:", key))
+            .chain(rendered.lines().map(|line| format!("  {}", line)))
+            .collect()
+    } else {
+        vec![format!("{}: {}", key, rendered)]
+    }
+}
+
+/// Generates a claim for `module` that is ready to apply once the required variables are set.
+///
+/// Required variables are set from `values`, or left empty when missing, and optional variables
+/// are commented out with their defaults. Each variable is preceded by its description and type.
+pub fn generate_claim_scaffold(
+    module: &ModuleResp,
+    name: &str,
+    region: &str,
+    values: &BTreeMap<String, serde_yaml::Value>,
+) -> String {
+    let version_key = if module.module_type == "stack" {
+        "stackVersion"
+    } else {
+        "moduleVersion"
+    };
+    let mut lines = vec![
+        "apiVersion: infraweave.io/v1".to_string(),
+        format!("kind: {}", module.module_name),
+        "metadata:".to_string(),
+        format!("  name: {}", name),
+        "  # namespace: default".to_string(),
+        "spec:".to_string(),
+        format!("  {}: {}", version_key, module.version),
+        format!("  region: {}", region),
+    ];
+
+    let (required, optional): (Vec<&TfVariable>, Vec<&TfVariable>) = claim_variables(module)
+        .into_iter()
+        .partition(|variable| is_required_variable(variable));
+    if required.is_empty() && optional.is_empty() {
+        lines.push("  variables: {}".to_string());
+        return lines.join("\n") + "\n";
+    }
+
+    lines.push("  variables:".to_string());
+    for variable in &required {
+        let key = to_camel_case(&variable.name);
+        let value = values
+            .get(&variable.name)
+            .cloned()
+            .unwrap_or(serde_yaml::Value::Null);
+        lines.push(format!("    {}", variable_comment(variable)));
+        lines.extend(
+            yaml_entry_lines(&key, &value)
+                .into_iter()
+                .map(|line| format!("    {}", line)),
+        );
+    }
+    for variable in &optional {
+        let key = to_camel_case(&variable.name);
+        let default = serde_yaml::to_value(variable.default.clone()).unwrap();
+        lines.push(format!("    {}", variable_comment(variable)));
+        lines.extend(
+            yaml_entry_lines(&key, &default)
+                .into_iter()
+                .map(|line| format!("    # {}", line)),
+        );
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_claim_regions() {
+        let claim: serde_yaml::Value = serde_yaml::from_str(
+            r#"
+apiVersion: infraweave.io/v1
+kind: S3Bucket
+metadata:
+  name: bucket
+spec:
+  moduleVersion: 0.1.0
+  region: [eu-west-1, us-east-1]
+  variables: {}
+"#,
+        )
+        .unwrap();
+
+        let claims = expand_claim_regions(&claim).unwrap();
+        assert_eq!(claims.len(), 2);
+        assert_eq!(claims[0]["metadata"]["name"], "bucket-eu-west-1");
+        assert_eq!(claims[0]["spec"]["region"], "eu-west-1");
+        assert_eq!(claims[1]["metadata"]["name"], "bucket-us-east-1");
+        assert_eq!(
+            claims[1]["metadata"]["annotations"][REGION_GROUP_ANNOTATION],
+            "bucket"
+        );
+
+        let mut single = claim.clone();
+        single["spec"]["region"] = "eu-west-1".into();
+        assert_eq!(expand_claim_regions(&single).unwrap(), vec![single]);
+
+        let mut duplicate = claim.clone();
+        duplicate["spec"]["region"] = serde_yaml::from_str("[eu-west-1, eu-west-1]").unwrap();
+        assert!(expand_claim_regions(&duplicate).is_err());
+    }
+
+    #[test]
+    fn test_is_region_group_member() {
+        assert!(is_region_group_member(
+            "s3bucket/bucket",
+            "s3bucket/bucket-eu-west-1",
+            "eu-west-1"
+        ));
+        assert!(!is_region_group_member(
+            "s3bucket/bucket",
+            "s3bucket/bucket-eu-west-1",
+            "us-east-1"
+        ));
+        assert!(!is_region_group_member(
+            "s3bucket/bucket",
+            "s3bucket/bucketx-eu-west-1",
+            "eu-west-1"
+        ));
+    }
+
+    #[test]
+    fn test_generate_module_example_deployment_follows_ui() {
+        let spec: ModuleSpec = serde_yaml::from_str(
+            r#"
+moduleName: S3Bucket
+version: 0.1.4
+description: S3 bucket
+reference: https://github.com/infraweave-io/modules/s3bucket
+ui:
+  groups:
+    - name: Bucket
+      variables: [bucket_name]
+    - name: Logging
+      variables: [enable_logging, log_bucket]
+  visibleWhen:
+    log_bucket:
+      variable: enable_logging
+      equals: true
+"#,
+        )
+        .unwrap();
+        let example = |variables: &str| ModuleExample {
+            name: "my-bucket".to_string(),
+            description: String::new(),
+            variables: serde_yaml::from_str(variables).unwrap(),
+        };
+        let variable_names = |claim: serde_yaml::Value| -> Vec<String> {
+            claim["spec"]["variables"]
+                .as_mapping()
+                .unwrap()
+                .keys()
+                .map(|key| key.as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let claim = generate_module_example_deployment(
+            &spec,
+            &example("{tags: {}, logBucket: logs, enableLogging: true, bucketName: my-bucket}"),
+        );
+        assert_eq!(
+            variable_names(claim),
+            vec!["bucketName", "enableLogging", "logBucket", "tags"]
+        );
+
+        let claim = generate_module_example_deployment(
+            &spec,
+            &example("{log_bucket: logs, enable_logging: false}"),
+        );
+        assert_eq!(variable_names(claim), vec!["enable_logging"]);
+    }
+
+    fn tf_variable(name: &str, default: Option<serde_json::Value>) -> TfVariable {
+        TfVariable {
+            name: name.to_string(),
+            _type: serde_json::json!("string"),
+            default,
+            description: format!("The {}", name),
+            nullable: false,
+            sensitive: false,
+            validations: vec![],
+        }
+    }
+
+    #[test]
+    fn test_generate_claim_scaffold() {
+        let mut module = ModuleResp {
+            module: "s3bucket".to_string(),
+            module_name: "S3Bucket".to_string(),
+            module_type: "module".to_string(),
+            version: "0.1.4".to_string(),
+            tf_variables: vec![
+                tf_variable("bucket_name", None),
+                tf_variable("tags", Some(serde_json::json!({"env": "dev"}))),
+            ],
+            ..Default::default()
+        };
+        module.manifest.spec.examples = Some(vec![ModuleExample {
+            name: "simple".to_string(),
+            description: "A simple bucket".to_string(),
+            variables: serde_yaml::from_str("bucketName: my-bucket").unwrap(),
+        }]);
+
+        let example = module_example_value(&module, "bucket_name").unwrap();
+        assert_eq!(example, serde_yaml::Value::from("my-bucket"));
+        assert_eq!(module_example_value(&module, "tags"), None);
+
+        let values = BTreeMap::from([("bucket_name".to_string(), example)]);
+        let claim = generate_claim_scaffold(&module, "my-bucket", "eu-west-1", &values);
+        assert_eq!(
+            claim,
+            r#"apiVersion: infraweave.io/v1
+kind: S3Bucket
+metadata:
+  name: my-bucket
+  # namespace: default
+spec:
+  moduleVersion: 0.1.4
+  region: eu-west-1
+  variables:
+    # The bucket_name (string)
+    bucketName: my-bucket
+    # The tags (string)
+    # tags:
+    #   env: dev
+"#
+        );
+
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&claim).unwrap();
+        assert_eq!(parsed["spec"]["variables"]["bucketName"], "my-bucket");
+        assert!(parsed["spec"]["variables"]["tags"].is_null());
+    }
+}
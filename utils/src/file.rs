@@ -2,7 +2,10 @@ use anyhow::{anyhow, Context};
 use base64::engine::general_purpose::STANDARD as base64;
 use base64::Engine;
 use log::info;
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::ffi::OsStr;
 use std::fs;
 use std::fs::File;
 use std::io::Cursor;
@@ -19,6 +22,45 @@ use zip::ZipWriter;
 
 const ONE_MB: u64 = 1_048_576; // 1MB in bytes
 
+/// Files with gitignore-style patterns of paths to leave out of a packaged module, provider,
+/// stack or policy. They apply to the directory they are in and its subdirectories.
+const IGNORE_FILENAMES: [&str; 2] = [".infraweaveignore", ".terraformignore"];
+
+/// Number of the largest files listed when a package exceeds the size limit
+const LARGEST_FILES_REPORTED: usize = 10;
+
+/// Files to package from `directory`, with their sizes, leaving out Terraform's local files and
+/// the paths matched by the ignore files
+fn get_package_files(directory: &Path) -> io::Result<Vec<(PathBuf, u64)>> {
+    let mut builder = ignore::WalkBuilder::new(directory);
+    builder.standard_filters(false).filter_entry(|e| {
+        let is_dir = e.file_type().is_some_and(|t| t.is_dir());
+        !should_be_excluded(e.file_name(), is_dir)
+    });
+    for filename in IGNORE_FILENAMES {
+        builder.add_custom_ignore_filename(filename);
+    }
+
+    let mut files = vec![];
+    for entry in builder.build() {
+        let entry = entry.map_err(io::Error::other)?;
+        let path = entry.into_path();
+        if path.is_file() {
+            let size = fs::metadata(&path)?.len();
+            files.push((path, size));
+        }
+    }
+    Ok(files)
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= ONE_MB {
+        format!("{:.1} MB", bytes as f64 / ONE_MB as f64)
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
 pub async fn get_zip_file(directory: &Path, manifest_yaml_path: &PathBuf) -> io::Result<Vec<u8>> {
     if !manifest_yaml_path.exists() {
         println!("Manifest yaml file does not exist in the specified directory");
@@ -28,11 +70,38 @@ pub async fn get_zip_file(directory: &Path, manifest_yaml_path: &PathBuf) -> io:
         ));
     }
     let mut buffer = Vec::new();
-    let mut total_size: u64 = 0;
 
     let bypass_file_size_check =
         std::env::var("BYPASS_FILE_SIZE_CHECK").unwrap_or("false".to_string()) != "true";
 
+    let mut files: Vec<(PathBuf, u64)> = get_package_files(directory)?
+        .into_iter()
+        .filter(|(path, _)| path != manifest_yaml_path)
+        .collect();
+    let total_size: u64 = files.iter().map(|(_, size)| size).sum();
+    info!(
+        "Packaging {} files ({}) from {}",
+        files.len(),
+        format_size(total_size),
+        directory.display()
+    );
+
+    if bypass_file_size_check && total_size > ONE_MB {
+        let mut largest = files.clone();
+        largest.sort_by_key(|(_, size)| Reverse(*size));
+        let largest = largest
+            .iter()
+            .take(LARGEST_FILES_REPORTED)
+            .map(|(path, size)| {
+                let name = path.strip_prefix(directory).unwrap_or(path);
+                format!("  {:>10}  {}", format_size(*size), name.display())
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        println!("Module directory is {}, which exceeds 1MB, aborting.\nThis typically is a sign of unwanted files in the module directory, text files should not be this large. The largest files are:\n{}\n\nExclude files by listing them in a .infraweaveignore or .terraformignore file, or remove them and retry.\n\nIf you have large files and need to publish in your module, you can by pass this check by setting the environment variable BYPASS_FILE_SIZE_CHECK to true", format_size(total_size), largest);
+        return Err(io::Error::other("ZIP file exceeds 1MB limit"));
+    }
+
     {
         let cursor = Cursor::new(&mut buffer);
         let mut zip = zip::ZipWriter::new(cursor);
@@ -41,28 +110,60 @@ pub async fn get_zip_file(directory: &Path, manifest_yaml_path: &PathBuf) -> io:
             .compression_method(zip::CompressionMethod::Stored)
             .unix_permissions(0o755);
 
-        let walker = WalkDir::new(directory)
-            .into_iter()
-            .filter_entry(|e| !should_be_excluded(e));
+        files.sort();
+        for (path, _) in &files {
+            let name = path.strip_prefix(directory).unwrap().to_str().unwrap();
+            zip.start_file(name, options)?;
+            let mut f = File::open(path)?;
+            io::copy(&mut f, &mut zip)?;
+        }
+        zip.finish()?;
+    }
+
+    Ok(buffer)
+}
+
+fn should_be_excluded(file_name: &OsStr, is_dir: bool) -> bool {
+    (is_dir && file_name == ".terraform")
+        || file_name == ".git"
+        || file_name == ".terraform-version"
+        || file_name == "terraform.tfstate"
+        || file_name == "terraform.tfstate.backup"
+        || file_name == "terraform.tfvars"
+        || file_name == "terraform.tfvars.json"
+        || file_name == "terraform.rc"
+        || file_name == ".terraformrc"
+        || file_name
+            .to_str()
+            .is_some_and(|s| s.ends_with(".auto.tfvars") || s.ends_with(".auto.tfvars.json"))
+}
+
+/// Zips all files in a directory, skipping the paths in `excluded` (relative to the directory)
+pub fn zip_directory(directory: &Path, excluded: &[&str]) -> io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    {
+        let cursor = Cursor::new(&mut buffer);
+        let mut zip = zip::ZipWriter::new(cursor);
+
+        let options = FileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored)
+            .unix_permissions(0o755);
+
+        let walker = WalkDir::new(directory).into_iter().filter_entry(|e| {
+            let relative = e.path().strip_prefix(directory).unwrap_or(e.path());
+            !excluded
+                .iter()
+                .any(|excluded| relative == Path::new(excluded))
+        });
 
         for entry in walker {
             let entry = entry?;
             let path = entry.path();
-            if path.is_file() && path != manifest_yaml_path {
+            if path.is_file() {
                 let name = path.strip_prefix(directory).unwrap().to_str().unwrap();
                 zip.start_file(name, options)?;
                 let mut f = File::open(path)?;
-                let bytes_copied = io::copy(&mut f, &mut zip)?;
-
-                total_size += bytes_copied;
-
-                if bypass_file_size_check && total_size > ONE_MB {
-                    println!("Module directory exceeds 1MB, aborting.\nThis typically is a sign of unwanted files in the module directory, text files should not be this large. Please remove files and retry.\n\nIf you have large files and need to publish in your module, you can by pass this check by setting the environment variable BYPASS_FILE_SIZE_CHECK to true");
-                    return Err(io::Error::new(
-                        io::ErrorKind::Other,
-                        "ZIP file exceeds 1MB limit",
-                    ));
-                }
+                io::copy(&mut f, &mut zip)?;
             }
         }
         zip.finish()?;
@@ -71,25 +172,6 @@ pub async fn get_zip_file(directory: &Path, manifest_yaml_path: &PathBuf) -> io:
     Ok(buffer)
 }
 
-fn should_be_excluded(entry: &walkdir::DirEntry) -> bool {
-    is_terraform_dir(entry)
-        || entry.file_name() == ".git"
-        || entry.file_name() == ".terraform-version"
-        || entry.file_name() == "terraform.tfstate"
-        || entry.file_name() == "terraform.tfstate.backup"
-        || entry.file_name() == "terraform.tfvars"
-        || entry.file_name() == "terraform.tfvars.json"
-        || entry.file_name() == "terraform.rc"
-        || entry.file_name() == ".terraformrc"
-        || entry
-            .file_name()
-            .to_str()
-            .is_some_and(|s| s.ends_with(".auto.tfvars") || s.ends_with(".auto.tfvars.json"))
-}
-fn is_terraform_dir(entry: &walkdir::DirEntry) -> bool {
-    entry.file_type().is_dir() && entry.file_name() == ".terraform"
-}
-
 pub fn get_zip_file_from_str(file_content: &str, file_name: &str) -> io::Result<Vec<u8>> {
     let mut buffer = Vec::new();
     {
@@ -115,83 +197,87 @@ pub enum ZipInput {
 }
 
 pub fn merge_zips(input: ZipInput) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-    let mut buffer = Cursor::new(Vec::new());
-
-    {
-        // Scope to close the zip writer when done
-        let mut zip_writer = ZipWriter::new(&mut buffer);
-
-        let options = FileOptions::default()
-            .compression_method(zip::CompressionMethod::Stored)
-            .unix_permissions(0o755);
-
-        match input {
-            ZipInput::WithFolders(zip_files) => {
-                // Iterate over each folder and corresponding zip file
-                for (folder, zip_file_data) in zip_files {
-                    let mut zip_archive = ZipArchive::new(Cursor::new(zip_file_data))?;
-
-                    // Iterate over the files inside each zip
-                    for i in 0..zip_archive.len() {
-                        let mut file = zip_archive.by_index(i)?;
-                        let file_name = file.name().to_string();
-
-                        // If the folder is "./" or empty string, don't prepend anything
-                        let new_file_name = if folder == "./" || folder.is_empty() {
-                            file_name
-                        } else {
-                            format!("{}/{}", folder, file_name)
-                        };
-
-                        // Read the file contents
-                        let mut file_contents = Vec::new();
-                        file.read_to_end(&mut file_contents)?;
-
-                        // Add the file to the new zip
-                        zip_writer.start_file(new_file_name, options)?;
-                        zip_writer.write_all(&file_contents)?;
-                    }
-                }
-            }
-
-            ZipInput::WithoutFolders(zip_files) => {
-                // Iterate over each zip file in the Vec
-                for zip_file_data in zip_files {
-                    let mut zip_archive = ZipArchive::new(Cursor::new(zip_file_data))?;
-
-                    // Iterate over the files inside each zip
-                    for i in 0..zip_archive.len() {
-                        let mut file = zip_archive.by_index(i)?;
-                        let file_name = file.name().to_string();
-
-                        // Since it's a Vec input, put everything in the root
-                        let new_file_name = file_name;
-
-                        // Read the file contents
-                        let mut file_contents = Vec::new();
-                        file.read_to_end(&mut file_contents)?;
+    let buffer = match input {
+        ZipInput::WithFolders(zip_files) => merge_zips_to(
+            zip_files
+                .into_iter()
+                .map(|(folder, zip_file_data)| (folder, Cursor::new(zip_file_data))),
+            Cursor::new(Vec::new()),
+        )?,
+        // Since it's a Vec input, put everything in the root
+        ZipInput::WithoutFolders(zip_files) => merge_zips_to(
+            zip_files
+                .into_iter()
+                .map(|zip_file_data| (String::new(), Cursor::new(zip_file_data))),
+            Cursor::new(Vec::new()),
+        )?,
+    };
+    Ok(buffer.into_inner())
+}
 
-                        // Add the file to the new zip
-                        zip_writer.start_file(new_file_name, options)?;
-                        zip_writer.write_all(&file_contents)?;
-                    }
-                }
-            }
+/// Merges ZIP archives into one written to `writer`, placing the files of each archive under its
+/// folder ("./" or "" for the root). Files are copied one at a time, so readers and writer can be
+/// files of any size
+pub fn merge_zips_to<R: Read + io::Seek, W: Write + io::Seek>(
+    zip_files: impl IntoIterator<Item = (String, R)>,
+    writer: W,
+) -> Result<W, anyhow::Error> {
+    let mut zip_writer = ZipWriter::new(writer);
+    let options = FileOptions::default()
+        .compression_method(zip::CompressionMethod::Stored)
+        .unix_permissions(0o755);
+
+    for (folder, reader) in zip_files {
+        let mut zip_archive = ZipArchive::new(reader)?;
+        for i in 0..zip_archive.len() {
+            let mut file = zip_archive.by_index(i)?;
+            let new_file_name = if folder == "./" || folder.is_empty() {
+                file.name().to_string()
+            } else {
+                format!("{}/{}", folder, file.name())
+            };
+            zip_writer.start_file(new_file_name, options)?;
+            io::copy(&mut file, &mut zip_writer)?;
         }
-
-        zip_writer.finish()?;
     }
 
-    Ok(buffer.into_inner())
+    Ok(zip_writer.finish()?)
 }
 
 pub async fn download_zip(url: &str, path: &Path) -> Result<(), anyhow::Error> {
     info!("Downloading ZIP file from {url} to {}", path.display());
+    let mut file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    download_to_writer(url, &mut file)
+        .await
+        .with_context(|| format!("failed downloading to {}", path.display()))?;
+    file.flush()
+        .with_context(|| format!("failed writing to {}", path.display()))?;
+    Ok(())
+}
+
+pub async fn download_zip_to_vec(url: &str) -> Result<Vec<u8>, anyhow::Error> {
+    info!("Downloading zip file from {} to vec", url);
+    let mut buffer = Vec::new();
+    download_to_writer(url, &mut buffer).await?;
+    Ok(buffer)
+}
+
+/// Streams the body of `url` into `writer` chunk by chunk and returns the number of bytes
+/// written. The timeout applies to connecting and to each read, not the whole download, so
+/// large artifacts don't time out while data keeps arriving
+pub async fn download_to_writer(url: &str, writer: &mut impl Write) -> Result<u64, anyhow::Error> {
+    // The local backend hands out file:// URLs in place of presigned URLs
+    if let Some(path) = url.strip_prefix("file://") {
+        let mut file = File::open(path).with_context(|| format!("failed to open {path}"))?;
+        return io::copy(&mut file, writer).with_context(|| format!("failed reading {path}"));
+    }
     let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(20))
+        .connect_timeout(Duration::from_secs(20))
+        .read_timeout(Duration::from_secs(20))
         .build()
         .unwrap();
-    let resp = client
+    let mut resp = client
         .get(url)
         .send()
         .await
@@ -204,24 +290,12 @@ pub async fn download_zip(url: &str, path: &Path) -> Result<(), anyhow::Error> {
         return Err(err).context("server returned an error status");
     }
 
-    let bytes = resp.bytes().await.context("failed reading body")?;
-
-    let mut file =
-        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
-    file.write_all(&bytes)
-        .with_context(|| format!("failed writing to {}", path.display()))?;
-
-    Ok(())
-}
-
-pub async fn download_zip_to_vec(url: &str) -> Result<Vec<u8>, anyhow::Error> {
-    info!("Downloading zip file from {} to vec", url);
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(20))
-        .build()
-        .unwrap();
-    let response = client.get(url).send().await?.bytes().await?;
-    Ok(response.to_vec())
+    let mut written = 0;
+    while let Some(chunk) = resp.chunk().await.context("failed reading body")? {
+        writer.write_all(&chunk)?;
+        written += chunk.len() as u64;
+    }
+    Ok(written)
 }
 
 pub fn read_file_base64(file_path: &Path) -> Result<String, anyhow::Error> {
@@ -323,6 +397,67 @@ pub fn get_file(zip_data: &[u8], filename: &str) -> Result<String, anyhow::Error
     Err(anyhow::anyhow!("No {} file found", filename))
 }
 
+/// `sha256:<hex>` digest of an artifact, the form the digests of published modules are recorded in
+pub fn artifact_digest(data: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(data))
+}
+
+/// Reads the files with the given extension in an in-memory zip, as (file name, content) pairs
+pub fn read_files_from_zip(
+    zip_data: &[u8],
+    extension: &str,
+) -> Result<Vec<(String, String)>, anyhow::Error> {
+    let mut zip = ZipArchive::new(Cursor::new(zip_data))?;
+    let mut files = vec![];
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        if file.is_dir() || Path::new(file.name()).extension() != Some(OsStr::new(extension)) {
+            continue;
+        }
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        files.push((file.name().to_string(), content));
+    }
+    Ok(files)
+}
+
+/// Reads a documentation file such as `README.md` from a module zip, matching the file name
+/// case-insensitively. The copy closest to the root wins over copies in subdirectories such as
+/// `examples/`, returns None if the zip has no such file
+pub fn get_module_doc_file(
+    zip_data: &[u8],
+    filename: &str,
+) -> Result<Option<String>, anyhow::Error> {
+    let cursor = Cursor::new(zip_data);
+    let mut zip = ZipArchive::new(cursor)?;
+
+    let mut shallowest: Option<(usize, usize)> = None;
+    for i in 0..zip.len() {
+        let file = zip.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+        let file_path = Path::new(file.name());
+        let matches = file_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.eq_ignore_ascii_case(filename));
+        let depth = file_path.components().count();
+        if matches && shallowest.is_none_or(|(_, d)| depth < d) {
+            shallowest = Some((i, depth));
+        }
+    }
+
+    match shallowest {
+        Some((index, _)) => {
+            let mut content = String::new();
+            zip.by_index(index)?.read_to_string(&mut content)?;
+            Ok(Some(content))
+        }
+        None => Ok(None),
+    }
+}
+
 pub fn store_zip_bytes(zip_data: &[u8], zip_path: &Path) -> Result<(), anyhow::Error> {
     let mut file = File::create(&zip_path)
         .with_context(|| format!("Failed to create file {}", zip_path.display()))?;
@@ -404,3 +539,108 @@ pub fn clean_root(src: &Path) -> Result<(), anyhow::Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_package_files_honors_ignore_files() {
+        let directory = crate::create_temp_dir().unwrap();
+        let files = [
+            ("main.tf", ""),
+            ("module.yaml", ""),
+            (
+                ".infraweaveignore",
+                "# Test fixtures\ntests/\n*.zip\n!keep.zip\n",
+            ),
+            ("tests/fixture.json", "{}"),
+            ("archive.zip", ""),
+            ("keep.zip", ""),
+            (".git/HEAD", ""),
+            (".terraform/modules.json", ""),
+            ("sub/.terraformignore", "local.txt\n"),
+            ("sub/local.txt", ""),
+            ("sub/vars.tf", ""),
+        ];
+        for (name, content) in files {
+            let path = directory.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        let mut packaged: Vec<String> = get_package_files(&directory)
+            .unwrap()
+            .into_iter()
+            .map(|(path, _)| {
+                path.strip_prefix(&directory)
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        packaged.sort();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            packaged,
+            vec![
+                ".infraweaveignore",
+                "keep.zip",
+                "main.tf",
+                "module.yaml",
+                "sub/.terraformignore",
+                "sub/vars.tf"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_zips_places_files_under_folders() {
+        let merged = merge_zips(ZipInput::WithFolders(HashMap::from([
+            (
+                "./".to_string(),
+                get_zip_file_from_str("root", "main.tf").unwrap(),
+            ),
+            (
+                "bucket".to_string(),
+                get_zip_file_from_str("nested", "main.tf").unwrap(),
+            ),
+        ])))
+        .unwrap();
+
+        let mut zip = ZipArchive::new(Cursor::new(merged)).unwrap();
+        let mut content = String::new();
+        zip.by_name("bucket/main.tf")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "nested");
+        assert!(zip.by_name("main.tf").is_ok());
+    }
+
+    #[test]
+    fn test_get_module_doc_file_prefers_shallowest() {
+        let directory = crate::create_temp_dir().unwrap();
+        let files = [
+            ("main.tf", ""),
+            ("S3Bucket-0.1.0/Docs/README.md", "docs"),
+            ("S3Bucket-0.1.0/examples/README.md", "example"),
+            ("S3Bucket-0.1.0/Readme.md", "# S3Bucket"),
+        ];
+        for (name, content) in files {
+            let path = directory.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let zip = zip_directory(&directory, &[]).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            get_module_doc_file(&zip, "README.md").unwrap().as_deref(),
+            Some("# S3Bucket")
+        );
+        assert_eq!(get_module_doc_file(&zip, "CHANGELOG.md").unwrap(), None);
+    }
+}
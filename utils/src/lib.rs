@@ -1,4 +1,7 @@
+mod aws_partition;
+mod claim_lint;
 pub mod config_path;
+mod cron;
 mod deployment;
 mod dir;
 mod file;
@@ -12,58 +15,85 @@ mod oci;
 #[cfg(feature = "otel")]
 pub mod otel_tracing;
 mod provider_util;
+mod sbom;
 mod schema_validation;
 mod stack;
 mod string_utils;
 mod tar;
 mod terraform;
+mod tf_validation;
 mod time;
 mod variables;
 mod versioning;
 
-pub use deployment::{generate_deployment_claim, generate_module_example_deployment};
+pub use aws_partition::{aws_arn, aws_dns_suffix, aws_endpoint, aws_partition, parse_aws_arn};
+pub use claim_lint::{
+    fix_claim, lint_claim, lint_claim_against_module, LintFinding, LintSeverity, CLAIM_LINT_RULES,
+};
+pub use cron::CronSchedule;
+pub use deployment::{
+    expand_claim_regions, generate_claim_scaffold, generate_deployment_claim,
+    generate_module_example_deployment, is_region_group_member, module_example_value,
+    REGION_GROUP_ANNOTATION,
+};
 pub use dir::create_temp_dir;
 pub use file::{
-    clean_root, copy_dir_recursive, download_zip, download_zip_to_vec, get_terraform_lockfile,
-    get_terraform_tfvars, get_zip_file, get_zip_file_from_str, merge_zips, read_file_base64,
-    read_tf_directory, read_tf_from_zip, store_zip_bytes, tempdir, unzip_file, unzip_vec_to,
-    ZipInput,
+    artifact_digest, clean_root, copy_dir_recursive, download_to_writer, download_zip,
+    download_zip_to_vec, get_module_doc_file, get_terraform_lockfile, get_terraform_tfvars,
+    get_zip_file, get_zip_file_from_str, merge_zips, merge_zips_to, read_file_base64,
+    read_files_from_zip, read_tf_directory, read_tf_from_zip, store_zip_bytes, tempdir, unzip_file,
+    unzip_vec_to, zip_directory, ZipInput,
 };
 pub use general::merge_json_dicts;
 pub use json::{
     convert_first_level_keys_to_snake_case, flatten_and_convert_first_level_keys_to_snake_case,
 };
-pub use log::sanitize_payload_for_logging;
+pub use log::{
+    mask_secret_values, mask_secret_values_in_json, register_secret_value,
+    sanitize_payload_for_logging,
+};
 pub use logging::setup_logging;
 pub use module::{
     convert_module_example_variables_to_camel_case, convert_module_example_variables_to_snake_case,
     get_providers_from_lockfile, get_tf_required_providers_from_tf_files,
     get_variables_from_tf_files, indent, validate_tf_backend_not_set,
     validate_tf_extra_environment_variables, validate_tf_required_providers_is_set,
+    PLATFORM_ENVIRONMENT_VARIABLE_PREFIX,
 };
-pub use module_diff::diff_modules;
+pub use module_diff::{diff_module_schema, diff_modules};
 pub use oci::{
-    get_module_manifest_from_oci_targz, get_module_zip_from_oci_targz, save_oci_artifacts_separate,
-    verify_oci_artifacts_offline,
+    current_oci_platform, get_module_manifest_from_oci_targz, get_module_zip_from_oci_targz,
+    get_module_zip_from_oci_targz_for_platform, get_platforms_from_oci_targz,
+    save_oci_artifacts_separate, select_platform_manifest, verify_oci_artifacts_offline,
+    verify_oci_artifacts_offline_in,
 };
 pub use provider_util::{
     _get_change_records, _get_dependents, _get_deployment, _get_deployment_and_dependents,
-    _get_deployments, _get_events, _get_module_optional, _get_modules, _get_policies, _get_policy,
+    _get_deployments, _get_events, _get_job_queue, _get_job_stats, _get_module_optional,
+    _get_modules, _get_policies, _get_policy, _get_policy_pack, _get_policy_packs,
     _get_provider_optional, _get_providers, _mutate_deployment, get_projects,
 };
+pub use sbom::{
+    generate_module_sbom, generate_provenance_attestation, ATTESTATION_MEDIA_TYPE, SBOM_MEDIA_TYPE,
+};
 pub use schema_validation::{validate_module_schema, validate_policy_schema};
-pub use stack::read_stack_directory;
+pub use stack::{generate_stack_member_claims, read_stack_directory, stack_instance_modules};
 pub use string_utils::{to_camel_case, to_snake_case};
-pub use tar::{get_diff_id_from_zip, targz_to_zip_bytes, zip_bytes_to_targz};
+pub use tar::{
+    get_diff_id_from_reader, get_diff_id_from_zip, targz_file_to_zip_file, targz_to_zip,
+    targz_to_zip_bytes, zip_bytes_to_targz, zip_file_to_targz_file, zip_to_targz,
+};
 pub use terraform::{
-    get_extra_environment_variables, get_extra_environment_variables_all, get_provider_url_key,
-    plan_get_destructive_changes, run_terraform_provider_lock, store_backend_file,
-    store_tf_vars_json, DestructiveChange,
+    get_extra_environment_variables, get_extra_environment_variables_all,
+    get_platform_environment_variables, get_provider_url_key, plan_get_destructive_changes,
+    run_terraform_provider_lock, store_backend_file, store_tf_vars_json, DestructiveChange,
 };
 pub use time::{epoch_to_timestamp, get_epoch, get_timestamp};
 pub use variables::{
-    verify_output_name_roundtrip, verify_required_variables_are_set, verify_variable_claim_casing,
-    verify_variable_existence_and_type, verify_variable_name_roundtrip,
+    claim_variables, is_required_variable, merge_tfvars_into_claim, merge_var_file_into_claim,
+    set_claim_variable_overrides, verify_output_name_roundtrip, verify_required_variables_are_set,
+    verify_variable_claim_casing, verify_variable_existence_and_type,
+    verify_variable_name_roundtrip,
 };
 pub use versioning::{
     get_version_track, semver_parse, semver_parse_without_build, zero_pad_semver,
@@ -1,7 +1,57 @@
 use serde_json::Value;
+use std::sync::Mutex;
+
+const MASKED_SECRET_VALUE: &str = "***";
+
+/// Values of secrets read in this process, masked wherever they would be logged
+static SECRET_VALUES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Masks `value` in payloads sanitized for logging and in text passed to `mask_secret_values`
+pub fn register_secret_value(value: &str) {
+    if value.is_empty() {
+        return;
+    }
+    let mut secret_values = SECRET_VALUES.lock().unwrap();
+    if !secret_values.iter().any(|v| v == value) {
+        secret_values.push(value.to_string());
+        // Longer values first, so a secret containing another one is masked as a whole
+        secret_values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+    }
+}
+
+/// `text` with each registered secret value replaced by `***`
+pub fn mask_secret_values(text: &str) -> String {
+    let secret_values = SECRET_VALUES.lock().unwrap();
+    let mut masked = text.to_string();
+    for value in secret_values.iter() {
+        if masked.contains(value.as_str()) {
+            masked = masked.replace(value.as_str(), MASKED_SECRET_VALUE);
+        }
+    }
+    masked
+}
+
+/// `value` with registered secret values masked in all of its strings
+pub fn mask_secret_values_in_json(value: Value) -> Value {
+    if SECRET_VALUES.lock().unwrap().is_empty() {
+        return value;
+    }
+    match value {
+        Value::String(s) => Value::String(mask_secret_values(&s)),
+        Value::Array(items) => {
+            Value::Array(items.into_iter().map(mask_secret_values_in_json).collect())
+        }
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| (k, mask_secret_values_in_json(v)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
 
 pub fn sanitize_payload_for_logging(payload: Value) -> Value {
-    let mut payload = payload;
+    let mut payload = mask_secret_values_in_json(payload);
 
     if let Some(event) = payload.get("event") {
         if let Some(event_str) = event.as_str() {
@@ -21,3 +71,29 @@ pub fn sanitize_payload_for_logging(payload: Value) -> Value {
 
     payload
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sanitize_payload_masks_secret_values() {
+        register_secret_value("hunter2");
+        register_secret_value("hunter2-extended");
+
+        let payload = json!({
+            "event": "insert_event",
+            "data": { "error_text": "login failed for hunter2-extended and hunter2", "count": 2 },
+        });
+
+        assert_eq!(
+            sanitize_payload_for_logging(payload),
+            json!({
+                "event": "insert_event",
+                "data": { "error_text": "login failed for *** and ***", "count": 2 },
+            })
+        );
+        assert_eq!(mask_secret_values("no secrets here"), "no secrets here");
+    }
+}
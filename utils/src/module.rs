@@ -1,5 +1,6 @@
 use env_defs::TfLockProvider;
 use env_defs::TfRequiredProvider;
+use env_defs::TfValidation;
 use env_defs::TfVariable;
 use hcl::de;
 use hcl::Expression;
@@ -107,6 +108,10 @@ pub fn validate_tf_required_providers_is_set(
     Ok(())
 }
 
+/// Prefix of the environment variables configured on the platform that are passed to every
+/// deployment as extra environment variables
+pub const PLATFORM_ENVIRONMENT_VARIABLE_PREFIX: &str = "INFRAWEAVE_PLATFORM_";
+
 #[allow(dead_code)]
 pub fn validate_tf_extra_environment_variables(
     extra_environment_variables: &[String],
@@ -147,11 +152,14 @@ pub fn validate_tf_extra_environment_variables(
             }
         }
         if tf_variable.name.starts_with("INFRAWEAVE_")
+            && !tf_variable
+                .name
+                .starts_with(PLATFORM_ENVIRONMENT_VARIABLE_PREFIX)
             && !VALID_EXTRA_ENVIRONMENT_VARIABLES.contains(&tf_variable.name.as_str())
         {
             return Err(anyhow::anyhow!(
-                "Extra environment variable {} (starting with \"INFRAWEAVE_\") is not a valid extra environment variable.\nValid extra environment variables are: {}",
-                tf_variable.name, VALID_EXTRA_ENVIRONMENT_VARIABLES.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", ")
+                "Extra environment variable {} (starting with \"INFRAWEAVE_\") is not a valid extra environment variable.\nValid extra environment variables are: {}, or any platform variable starting with {}",
+                tf_variable.name, VALID_EXTRA_ENVIRONMENT_VARIABLES.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", "), PLATFORM_ENVIRONMENT_VARIABLE_PREFIX
             ));
         }
     }
@@ -201,6 +209,7 @@ Please make any required changes terraform configuration to proceed:
 pub fn get_variables_from_tf_files(contents: &str) -> Result<Vec<TfVariable>, String> {
     let parsed_hcl: HashMap<String, serde_json::Value> =
         de::from_str(contents).map_err(|err| format!("Failed to parse HCL: {}", err))?;
+    let mut validations = get_variable_validations(contents)?;
 
     let mut variables = Vec::new();
 
@@ -249,6 +258,7 @@ pub fn get_variables_from_tf_files(contents: &str) -> Result<Vec<TfVariable>, St
                     description,
                     nullable,
                     sensitive,
+                    validations: validations.remove(var_name).unwrap_or_default(),
                 };
 
                 debug!("Parsing variable block {:?} as {:?}", var_attrs, variable);
@@ -260,6 +270,48 @@ pub fn get_variables_from_tf_files(contents: &str) -> Result<Vec<TfVariable>, St
     Ok(variables)
 }
 
+/// The `validation` blocks of each variable, keyed by variable name. The expressions are kept as
+/// HCL, since they are evaluated against the values of claims rather than when publishing
+fn get_variable_validations(contents: &str) -> Result<HashMap<String, Vec<TfValidation>>, String> {
+    let body = hcl::parse(contents).map_err(|err| format!("Failed to parse HCL: {}", err))?;
+
+    let mut validations: HashMap<String, Vec<TfValidation>> = HashMap::new();
+    for block in body.blocks().filter(|b| b.identifier() == "variable") {
+        let Some(var_name) = block.labels().first().map(|l| l.as_str().to_string()) else {
+            continue;
+        };
+        for validation in block
+            .body()
+            .blocks()
+            .filter(|b| b.identifier() == "validation")
+        {
+            let attribute = |key: &str| {
+                validation
+                    .body()
+                    .attributes()
+                    .find(|a| a.key() == key)
+                    .map(|a| a.expr())
+            };
+            let Some(condition) = attribute("condition") else {
+                continue;
+            };
+            let message = match attribute("error_message") {
+                Some(Expression::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+                None => String::new(),
+            };
+            validations
+                .entry(var_name.clone())
+                .or_default()
+                .push(TfValidation {
+                    expression: condition.to_string(),
+                    message,
+                });
+        }
+    }
+    Ok(validations)
+}
+
 #[allow(dead_code)]
 pub fn get_tf_required_providers_from_tf_files(
     contents: &str,
@@ -446,8 +498,41 @@ variable "bucket_name" {
                 description: "".to_string(),
                 nullable: true,
                 sensitive: false,
+                validations: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_variable_block_with_validations() {
+        let variables_str = r#"
+variable "bucket_name" {
+  type = string
+
+  validation {
+    condition     = can(regex("^[a-z0-9-]+$", var.bucket_name))
+    error_message = "Bucket names may only contain lowercase letters, digits and hyphens."
+  }
+
+  validation {
+    condition     = length(var.bucket_name) <= 63
+    error_message = "Bucket names must be at most 63 characters long."
+  }
+}
+"#;
+        let variable = get_variables_from_tf_files(variables_str)
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert_eq!(variable.validations.len(), 2);
+        assert_eq!(
+            variable.validations[1],
+            TfValidation {
+                expression: "length(var.bucket_name) <= 63".to_string(),
+                message: "Bucket names must be at most 63 characters long.".to_string(),
             }
         );
+        assert!(variable.validations[0].expression.starts_with("can(regex("));
     }
 
     #[test]
@@ -476,6 +561,7 @@ variable "tags" {
                 description: "".to_string(),
                 nullable: true,
                 sensitive: false,
+                validations: vec![],
             }
         );
     }
@@ -499,6 +585,7 @@ variable "tags" {
                 description: "".to_string(),
                 nullable: true,
                 sensitive: false,
+                validations: vec![],
             }
         );
     }
@@ -522,6 +609,7 @@ variable "tags" {
                 description: "".to_string(),
                 nullable: true,
                 sensitive: false,
+                validations: vec![],
             }
         );
     }
@@ -776,6 +864,7 @@ provider "registry.opentofu.org/hashicorp/kubernetes" {
                 description: "".to_string(),
                 nullable: true,
                 sensitive: false,
+                validations: vec![],
             },
             TfVariable {
                 name: "INFRAWEAVE_DEPLOYMENT_ID".to_string(),
@@ -784,6 +873,7 @@ provider "registry.opentofu.org/hashicorp/kubernetes" {
                 description: "Some description maybe".to_string(),
                 nullable: true,
                 sensitive: false,
+                validations: vec![],
             },
         ];
 
@@ -805,6 +895,7 @@ provider "registry.opentofu.org/hashicorp/kubernetes" {
                 description: "".to_string(),
                 nullable: true,
                 sensitive: false,
+                validations: vec![],
             },
             TfVariable {
                 name: "INFRAWEAVE_DEPLOYMENT_ID".to_string(),
@@ -813,6 +904,7 @@ provider "registry.opentofu.org/hashicorp/kubernetes" {
                 description: "Some description maybe".to_string(),
                 nullable: true,
                 sensitive: false,
+                validations: vec![],
             },
         ];
 
@@ -834,6 +926,7 @@ provider "registry.opentofu.org/hashicorp/kubernetes" {
                 description: "".to_string(),
                 nullable: true,
                 sensitive: false,
+                validations: vec![],
             },
             TfVariable {
                 name: "INFRAWEAVE_DEPLOYMENT_ID".to_string(),
@@ -842,6 +935,7 @@ provider "registry.opentofu.org/hashicorp/kubernetes" {
                 description: "Some description maybe".to_string(),
                 nullable: true,
                 sensitive: false,
+                validations: vec![],
             },
         ];
 
@@ -1,9 +1,14 @@
-use env_defs::{ModuleDiffAddition, ModuleDiffChange, ModuleDiffRemoval};
+use env_defs::{
+    ModuleDiffAddition, ModuleDiffChange, ModuleDiffRemoval, ModuleSchemaChange, TfOutput,
+    TfVariable,
+};
 use hcl::from_str as hcl_from_str;
 use hcl::Value as HclValue;
 use serde_json::Value as JsonValue;
 use std::vec;
 
+use crate::variables::is_required_variable;
+
 // Convert HCL value to serde_json::Value
 fn hcl_to_json(hcl_value: &HclValue) -> JsonValue {
     serde_json::to_value(hcl_value).unwrap()
@@ -129,6 +134,101 @@ fn diff_values(
     (additions, changes, removals)
 }
 
+/// Changes to the variables and outputs from `old` to `new` version of a module.
+///
+/// Removed variables, new required variables, variables that became required or changed type, and
+/// removed outputs are breaking, as claims or deployments depending on the old version may fail
+pub fn diff_module_schema(
+    old_variables: &[TfVariable],
+    new_variables: &[TfVariable],
+    old_outputs: &[TfOutput],
+    new_outputs: &[TfOutput],
+) -> Vec<ModuleSchemaChange> {
+    let variable_change = |name: &str, change: String, breaking: bool| ModuleSchemaChange {
+        kind: "variable".to_string(),
+        name: name.to_string(),
+        change,
+        breaking,
+    };
+    let mut changes = vec![];
+
+    for old in old_variables {
+        match new_variables.iter().find(|v| v.name == old.name) {
+            None => changes.push(variable_change(&old.name, "removed".to_string(), true)),
+            Some(new) => {
+                if old._type != new._type {
+                    changes.push(variable_change(
+                        &old.name,
+                        format!(
+                            "type changed from {} to {}",
+                            type_string(&old._type),
+                            type_string(&new._type)
+                        ),
+                        true,
+                    ));
+                }
+                match (is_required_variable(old), is_required_variable(new)) {
+                    (false, true) => {
+                        changes.push(variable_change(&old.name, "now required".to_string(), true))
+                    }
+                    (true, false) => changes.push(variable_change(
+                        &old.name,
+                        "now optional".to_string(),
+                        false,
+                    )),
+                    (false, false) if old.default != new.default => changes.push(variable_change(
+                        &old.name,
+                        format!(
+                            "default changed from {} to {}",
+                            old.default.clone().unwrap_or_default(),
+                            new.default.clone().unwrap_or_default()
+                        ),
+                        false,
+                    )),
+                    _ => {}
+                }
+            }
+        }
+    }
+    for new in new_variables {
+        if !old_variables.iter().any(|v| v.name == new.name) {
+            let required = is_required_variable(new);
+            let change = if required {
+                "added (required)"
+            } else {
+                "added (optional)"
+            };
+            changes.push(variable_change(&new.name, change.to_string(), required));
+        }
+    }
+
+    let output_change = |name: &str, change: &str, breaking: bool| ModuleSchemaChange {
+        kind: "output".to_string(),
+        name: name.to_string(),
+        change: change.to_string(),
+        breaking,
+    };
+    for old in old_outputs {
+        if !new_outputs.iter().any(|o| o.name == old.name) {
+            changes.push(output_change(&old.name, "removed", true));
+        }
+    }
+    for new in new_outputs {
+        if !old_outputs.iter().any(|o| o.name == new.name) {
+            changes.push(output_change(&new.name, "added", false));
+        }
+    }
+
+    changes
+}
+
+fn type_string(_type: &JsonValue) -> String {
+    match _type {
+        JsonValue::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -339,4 +439,82 @@ mod tests {
         assert_eq!(changes, expected_changes);
         assert_eq!(removals, expected_removals);
     }
+
+    fn variable(name: &str, _type: &str, default: Option<JsonValue>) -> TfVariable {
+        TfVariable {
+            name: name.to_string(),
+            _type: serde_json::json!(_type),
+            default,
+            description: "".to_string(),
+            nullable: false,
+            sensitive: false,
+            validations: vec![],
+        }
+    }
+
+    fn output(name: &str) -> TfOutput {
+        TfOutput {
+            name: name.to_string(),
+            value: "".to_string(),
+            description: "".to_string(),
+            sensitive: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_module_schema() {
+        let old_variables = vec![
+            variable("bucket_name", "string", None),
+            variable("tags", "map(string)", Some(serde_json::json!({}))),
+            variable("port", "number", Some(serde_json::json!(80))),
+            variable("legacy", "bool", Some(serde_json::json!(false))),
+        ];
+        let new_variables = vec![
+            variable("bucket_name", "string", None),
+            variable("tags", "map(string)", None),
+            variable("port", "string", Some(serde_json::json!("8080"))),
+            variable("region", "string", None),
+            variable("versioning", "bool", Some(serde_json::json!(true))),
+        ];
+        let old_outputs = vec![output("bucket_arn"), output("bucket_id")];
+        let new_outputs = vec![output("bucket_arn"), output("bucket_domain")];
+
+        let changes =
+            diff_module_schema(&old_variables, &new_variables, &old_outputs, &new_outputs);
+        let summary: Vec<(&str, &str, &str, bool)> = changes
+            .iter()
+            .map(|c| {
+                (
+                    c.kind.as_str(),
+                    c.name.as_str(),
+                    c.change.as_str(),
+                    c.breaking,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("variable", "tags", "now required", true),
+                (
+                    "variable",
+                    "port",
+                    "type changed from number to string",
+                    true
+                ),
+                (
+                    "variable",
+                    "port",
+                    "default changed from 80 to \"8080\"",
+                    false
+                ),
+                ("variable", "legacy", "removed", true),
+                ("variable", "region", "added (required)", true),
+                ("variable", "versioning", "added (optional)", false),
+                ("output", "bucket_id", "removed", true),
+                ("output", "bucket_domain", "added", false),
+            ]
+        );
+    }
 }
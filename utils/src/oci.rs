@@ -1,8 +1,9 @@
 use anyhow::{Context, Result};
 use base64::Engine;
 use env_defs::{
-    ArtifactType, Blob, IndexEntry, IndexJson, LayerDesc, LayoutFile, ModuleResp, OciArtifactSet,
-    OciManifest,
+    ArtifactType, ArtifactVerificationPolicy, Blob, IndexEntry, IndexJson, LayerDesc, LayoutFile,
+    ModuleResp, OciArtifactSet, OciManifest, OciPlatform, OCI_INDEX_MEDIA_TYPE,
+    OCI_MANIFEST_MEDIA_TYPE,
 };
 use flate2::{write::GzEncoder, Compression};
 use oci_distribution::Reference;
@@ -10,6 +11,7 @@ use regorus::Engine as RegoEngine;
 use reqwest::{header, Client};
 use sha2::Digest;
 use std::{
+    collections::{HashMap, HashSet},
     fs::File,
     io::{Cursor, Read},
     path::Path,
@@ -133,6 +135,17 @@ pub async fn save_oci_artifacts_separate(
             )
             .await?;
         }
+        ArtifactType::Sbom => {
+            let _sbom_path = fetch_and_save_sbom(
+                &client,
+                &def_headers,
+                registry,
+                repo,
+                digest_hex,
+                &artifact_path,
+            )
+            .await?;
+        }
         _ => anyhow::bail!("Unsupported artifact type for saving: {:?}", artifact_type),
     }
 
@@ -247,7 +260,8 @@ async fn create_authenticated_client(
     Ok((client, def_headers))
 }
 
-/// Save the main OCI artifact (manifest + all layers) as a tar.gz file
+/// Save the main OCI artifact (manifest + all layers) as a tar.gz file. When the artifact is a
+/// multi-platform index, the index and the manifests and layers of every platform are saved
 async fn save_main_artifact(
     manifest_bytes: &[u8],
     docker_digest: &str,
@@ -257,9 +271,6 @@ async fn save_main_artifact(
     def_headers: &header::HeaderMap,
     output_path: &str,
 ) -> Result<()> {
-    let digest_hex = docker_digest
-        .strip_prefix("sha256:")
-        .context("digest did not start with sha256:")?;
     let manifest_size = manifest_bytes.len() as u64;
 
     /* ---- start tar.gz --------------------------------------------------- */
@@ -283,20 +294,23 @@ async fn save_main_artifact(
     )?;
 
     /* ---- write manifest blob ------------------------------------------- */
-    let blob_path = format!("blobs/sha256/{}", digest_hex);
-    tar.append(
-        &header_for(&blob_path, manifest_size, EntryType::Regular),
-        Cursor::new(manifest_bytes),
-    )?;
+    append_blob(&mut tar, docker_digest, manifest_bytes)?;
 
-    // Always handle single-platform manifest
-    let manifest: OciManifest = serde_json::from_slice(manifest_bytes)?;
+    let is_index = is_index_manifest(manifest_bytes)?;
     let index_json = IndexJson {
         schema_version: 2,
+        media_type: None,
         manifests: vec![IndexEntry {
-            media_type: "application/vnd.oci.image.manifest.v1+json".into(),
+            media_type: if is_index {
+                OCI_INDEX_MEDIA_TYPE
+            } else {
+                OCI_MANIFEST_MEDIA_TYPE
+            }
+            .into(),
             digest: docker_digest.to_owned(),
             size: manifest_size,
+            platform: None,
+            annotations: None,
         }],
     };
     let idx_bytes = serde_json::to_vec(&index_json)?;
@@ -307,69 +321,175 @@ async fn save_main_artifact(
     )
     .context("Failed to append index.json")?;
 
+    if is_index {
+        let index: IndexJson = serde_json::from_slice(manifest_bytes)?;
+        let entries = platform_manifests(&index);
+        if entries.is_empty() {
+            anyhow::bail!("OCI index {} contains no platform manifests", docker_digest);
+        }
+        for entry in entries {
+            println!(
+                "🔧 Downloading manifest {} for platform {}",
+                entry.digest,
+                entry.oci_platform()
+            );
+            let url = format!(
+                "https://{}/v2/{}/manifests/{}",
+                registry, repo, entry.digest
+            );
+            let bytes = client
+                .get(&url)
+                .headers(def_headers.clone())
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            append_blob(&mut tar, &entry.digest, &bytes)?;
+            append_manifest_blobs(&mut tar, &bytes, registry, repo, client, def_headers).await?;
+        }
+    } else {
+        append_manifest_blobs(
+            &mut tar,
+            manifest_bytes,
+            registry,
+            repo,
+            client,
+            def_headers,
+        )
+        .await?;
+    }
+
+    // Finalize tar and gzip
+    tar.finish().context("failed to finish tar")?;
+    let enc = tar
+        .into_inner()
+        .context("failed to retrieve encoder after finishing tar")?;
+    enc.finish().context("failed to finish gzip encoder")?;
+
+    println!("✓ Saved main artifact to {}", output_path);
+    Ok(())
+}
+
+fn append_blob(tar: &mut Builder<GzEncoder<File>>, digest: &str, bytes: &[u8]) -> Result<()> {
+    let hex = digest
+        .strip_prefix("sha256:")
+        .context("digest did not start with sha256:")?;
+    tar.append(
+        &header_for(
+            &format!("blobs/sha256/{}", hex),
+            bytes.len() as u64,
+            EntryType::Regular,
+        ),
+        Cursor::new(bytes),
+    )?;
+    Ok(())
+}
+
+/// Download the config blob and layers of a single-platform manifest into the archive
+async fn append_manifest_blobs(
+    tar: &mut Builder<GzEncoder<File>>,
+    manifest_bytes: &[u8],
+    registry: &str,
+    repo: &str,
+    client: &Client,
+    def_headers: &header::HeaderMap,
+) -> Result<()> {
+    let manifest: OciManifest = serde_json::from_slice(manifest_bytes)?;
+    let mut blob_headers = def_headers.clone();
+    blob_headers.insert(header::ACCEPT, "*/*".parse().unwrap());
+
     // Download and add the config blob (contains annotations)
     if let Some(config_digest) = manifest.config.get("digest").and_then(|d| d.as_str()) {
-        let config_hex = config_digest.strip_prefix("sha256:").unwrap();
         let config_url = format!("https://{}/v2/{}/blobs/{}", registry, repo, config_digest);
-        let mut config_headers = def_headers.clone();
-        config_headers.insert(header::ACCEPT, "*/*".parse().unwrap());
 
         println!("🔧 Downloading config blob: {}", config_digest);
         let config_bytes = client
             .get(&config_url)
-            .headers(config_headers)
+            .headers(blob_headers.clone())
             .send()
             .await?
             .error_for_status()?
             .bytes()
             .await?;
-
-        let config_size = config_bytes.len() as u64;
-        tar.append(
-            &header_for(
-                &format!("blobs/sha256/{}", config_hex),
-                config_size,
-                EntryType::Regular,
-            ),
-            Cursor::new(&config_bytes),
-        )?;
+        append_blob(tar, config_digest, &config_bytes)?;
         println!("✓ Added config blob to archive");
     }
 
     // pull each layer so layout is self-contained
     for layer in &manifest.layers {
-        let hex = layer.digest.strip_prefix("sha256:").unwrap();
         let url = format!("https://{}/v2/{}/blobs/{}", registry, repo, layer.digest);
-        let mut blob_headers = def_headers.clone();
-        blob_headers.insert(header::ACCEPT, "*/*".parse().unwrap());
         let bytes = client
             .get(&url)
-            .headers(blob_headers)
+            .headers(blob_headers.clone())
             .send()
             .await?
             .error_for_status()?
             .bytes()
             .await?;
-        let actual_size = bytes.len() as u64;
-        tar.append(
-            &header_for(
-                &format!("blobs/sha256/{}", hex),
-                actual_size,
-                EntryType::Regular,
-            ),
-            Cursor::new(&bytes),
-        )?;
+        append_blob(tar, &layer.digest, &bytes)?;
     }
+    Ok(())
+}
 
-    // Finalize tar and gzip
-    tar.finish().context("failed to finish tar")?;
-    let enc = tar
-        .into_inner()
-        .context("failed to retrieve encoder after finishing tar")?;
-    enc.finish().context("failed to finish gzip encoder")?;
+fn is_index_manifest(manifest_bytes: &[u8]) -> Result<bool> {
+    let manifest: serde_json::Value = serde_json::from_slice(manifest_bytes)?;
+    let media_type = manifest["mediaType"].as_str().unwrap_or_default();
+    Ok(media_type == OCI_INDEX_MEDIA_TYPE
+        || media_type.contains("manifest.list")
+        || manifest.get("manifests").is_some())
+}
 
-    println!("✓ Saved main artifact to {}", output_path);
-    Ok(())
+/// Entries of an index that are platform manifests, skipping the attestation manifests
+/// buildx adds to multi-platform images
+fn platform_manifests(index: &IndexJson) -> Vec<&IndexEntry> {
+    index
+        .manifests
+        .iter()
+        .filter(|entry| {
+            let is_attestation = entry
+                .annotations
+                .as_ref()
+                .and_then(|a| a.get("vnd.docker.reference.type"))
+                .is_some_and(|t| t == "attestation-manifest");
+            let is_unknown_platform = entry.platform.as_ref().is_some_and(|p| p.os == "unknown");
+            !is_attestation && !is_unknown_platform
+        })
+        .collect()
+}
+
+/// Platform of the current process, as it appears in OCI indexes, for the given cloud provider
+pub fn current_oci_platform(cloud: Option<&str>) -> OciPlatform {
+    let architecture = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        arch => arch,
+    };
+    OciPlatform {
+        os: Some(std::env::consts::OS.to_string()),
+        architecture: Some(architecture.to_string()),
+        cloud: cloud.map(str::to_string),
+    }
+}
+
+/// Picks the manifest for `target` from the entries of a multi-platform index, preferring the
+/// most specific match, e.g. `linux/arm64 (aws)` over a generic `linux/arm64`
+pub fn select_platform_manifest<'a>(
+    entries: &[&'a IndexEntry],
+    target: &OciPlatform,
+) -> Option<&'a IndexEntry> {
+    let mut selected: Option<&'a IndexEntry> = None;
+    for entry in entries {
+        let platform = entry.oci_platform();
+        if !platform.matches(target) {
+            continue;
+        }
+        if selected.is_none_or(|s| platform.specificity() > s.oci_platform().specificity()) {
+            selected = Some(*entry);
+        }
+    }
+    selected
 }
 
 /// Fetch and save attestation as a separate tar.gz file
@@ -414,7 +534,27 @@ async fn fetch_and_save_signature(
     }
 }
 
-/// Save a blob (attestation or signature) as a tar.gz file
+/// Fetch and save SBOM as a separate tar.gz file
+async fn fetch_and_save_sbom(
+    client: &Client,
+    def_headers: &header::HeaderMap,
+    registry: &str,
+    repo: &str,
+    subject_digest: &str,
+    output_path: &str,
+) -> Result<Option<String>> {
+    if let Some(blob) = fetch_sbom_blob(client, def_headers, registry, repo, subject_digest).await?
+    {
+        save_blob_as_tar(&blob, output_path, "sbom")?;
+        println!("✓ Saved SBOM to {}", output_path);
+        Ok(Some(output_path.to_string()))
+    } else {
+        println!("ℹ️  No SBOM found");
+        Ok(None)
+    }
+}
+
+/// Save a blob (attestation, signature or SBOM) as a tar.gz file
 fn save_blob_as_tar(blob: &Blob, output_path: &str, blob_type: &str) -> Result<()> {
     let enc = GzEncoder::new(File::create(output_path)?, Compression::default());
     let mut tar = Builder::new(enc);
@@ -589,13 +729,92 @@ async fn fetch_attestation_blob(
     Ok(None)
 }
 
+/// Fetch SBOM blob from registry
+async fn fetch_sbom_blob(
+    client: &Client,
+    def_headers: &header::HeaderMap,
+    registry: &str,
+    repo: &str,
+    subject_digest: &str,
+) -> Result<Option<Blob>> {
+    let tag_pattern = format!("sha256:{}", subject_digest);
+
+    let manifest_url = format!("https://{}/v2/{}/manifests/{}", registry, repo, tag_pattern);
+
+    let resp = client
+        .get(&manifest_url)
+        .headers(def_headers.clone())
+        .send()
+        .await?;
+
+    if !resp.status().is_success() {
+        println!(
+            "🔧 Failed to fetch SBOM manifest for tag: {} (status: {})",
+            tag_pattern,
+            resp.status()
+        );
+        return Ok(None);
+    }
+
+    let manifest_bytes = resp.bytes().await?;
+    let manifest: serde_json::Value = serde_json::from_slice(&manifest_bytes)?;
+
+    for layer in manifest["layers"].as_array().into_iter().flatten() {
+        let media_type = layer["mediaType"].as_str().unwrap_or_default();
+        if media_type.contains("cyclonedx") || media_type.contains("spdx") {
+            let layer_digest = layer["digest"].as_str().unwrap();
+            let layer_size = layer["size"].as_u64().unwrap();
+
+            let blob_url = format!("https://{}/v2/{}/blobs/{}", registry, repo, layer_digest);
+            let mut blob_headers = def_headers.clone();
+            blob_headers.insert(header::ACCEPT, "*/*".parse().unwrap());
+
+            let bytes = client
+                .get(&blob_url)
+                .headers(blob_headers)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+
+            anyhow::ensure!(bytes.len() as u64 == layer_size, "SBOM size mismatch");
+
+            return Ok(Some(Blob {
+                digest: layer_digest.to_owned(),
+                content: bytes.to_vec(),
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
 /// Verify OCI artifacts offline using previously saved tar.gz files
 /// This function works with any OCI registry artifacts and does not require network access
 pub fn verify_oci_artifacts_offline(
     artifact_set: &OciArtifactSet,
     config_path: Option<&str>,
+    policy: &ArtifactVerificationPolicy,
+) -> Result<()> {
+    verify_oci_artifacts_offline_in(Path::new("."), artifact_set, config_path, policy)
+}
+
+/// Same as [verify_oci_artifacts_offline] with the tar.gz files stored in `directory`
+pub fn verify_oci_artifacts_offline_in(
+    directory: &Path,
+    artifact_set: &OciArtifactSet,
+    config_path: Option<&str>,
+    policy: &ArtifactVerificationPolicy,
 ) -> Result<()> {
     println!("🔍 Starting offline verification of OCI artifacts...");
+    policy.check(artifact_set, None)?;
+    let artifact_file = |tag: &str| {
+        directory
+            .join(format!("{}.tar.gz", tag))
+            .to_string_lossy()
+            .to_string()
+    };
 
     // Load verification configuration
     let config = if let Some(path) = config_path {
@@ -610,25 +829,33 @@ pub fn verify_oci_artifacts_offline(
     };
 
     // 1. Verify main artifact integrity
-    verify_main_artifact_offline(
-        &format!("{}.tar.gz", artifact_set.tag_main),
-        &artifact_set.digest,
-    )
-    .unwrap();
+    verify_main_artifact_offline(&artifact_file(&artifact_set.tag_main), &artifact_set.digest)
+        .unwrap();
 
     // 2. Verify attestation
     verify_attestation_offline(
-        &format!("{}.tar.gz", artifact_set.tag_attestation.as_ref().unwrap()),
+        &artifact_file(artifact_set.tag_attestation.as_ref().unwrap()),
         &artifact_set.digest,
         &config,
     )?;
 
-    // 3. Verify signature
-    verify_signature_offline(
-        &format!("{}.tar.gz", artifact_set.tag_signature.as_ref().unwrap()),
-        &artifact_set.digest,
-        &config,
-    )?;
+    // 3. Verify signature, a missing signature is only allowed if the policy allows unsigned artifacts
+    match &artifact_set.tag_signature {
+        Some(tag_signature) => {
+            verify_signature_offline(&artifact_file(tag_signature), &artifact_set.digest, &config)?
+        }
+        None => println!("ℹ️  Artifact is not signed, which the policy allows"),
+    }
+
+    // 4. Verify SBOM, which is optional since not every publisher generates one
+    if let Some(tag_sbom) = &artifact_set.tag_sbom {
+        let sbom_path = artifact_file(tag_sbom);
+        if Path::new(&sbom_path).exists() {
+            verify_sbom_offline(&sbom_path)?;
+        } else {
+            println!("ℹ️  No SBOM found at {}, skipping", sbom_path);
+        }
+    }
 
     println!("✓ Offline verification completed successfully");
     Ok(())
@@ -645,137 +872,100 @@ fn verify_main_artifact_offline(artifact_path: &str, expected_digest: &str) -> R
         anyhow::bail!("Artifact file not found: {}", artifact_path);
     }
 
-    let tar_file = File::open(artifact_path)?;
-    let decoder = flate2::read::GzDecoder::new(tar_file);
-    let mut archive = tar::Archive::new(decoder);
-
-    let mut manifest_bytes: Option<Vec<u8>> = None;
-    let mut layer_count = 0;
-    let mut config_digest: Option<String> = None;
-
-    for entry_result in archive.entries()? {
-        let mut entry = entry_result?;
-        let path = entry.path()?.to_path_buf();
-        let path_str = path.to_string_lossy();
-
-        if path_str == "index.json" {
-            let mut contents = Vec::new();
-            entry.read_to_end(&mut contents)?;
-            let index: serde_json::Value = serde_json::from_slice(&contents)?;
-            println!("✓ Found index.json");
-
-            // Verify the manifest digest in index matches expected
-            if let Some(manifests) = index["manifests"].as_array() {
-                if let Some(manifest) = manifests.first() {
-                    if let Some(digest) = manifest["digest"].as_str() {
-                        if digest != expected_digest {
-                            anyhow::bail!("Manifest digest in index.json ({}) doesn't match expected digest ({})", digest, expected_digest);
-                        }
-                        println!("✓ Manifest digest in index.json matches expected digest");
-                    }
-                }
-            }
-        } else if path_str.starts_with("blobs/sha256/") && !path_str.ends_with('/') {
-            let mut contents = Vec::new();
-            entry.read_to_end(&mut contents)?;
-            let filename = path.file_name().unwrap().to_string_lossy();
-
-            // Check if this is the manifest blob
-            let expected_hex = expected_digest
-                .strip_prefix("sha256:")
-                .unwrap_or(expected_digest);
-            if filename == expected_hex {
-                manifest_bytes = Some(contents.clone());
-                println!("✓ Found manifest blob");
-
-                // Parse manifest to get config digest
-                let manifest: OciManifest = serde_json::from_slice(&contents)?;
-                if let Some(config_dig) = manifest.config.get("digest").and_then(|d| d.as_str()) {
-                    config_digest = Some(
-                        config_dig
-                            .strip_prefix("sha256:")
-                            .unwrap_or(config_dig)
-                            .to_string(),
-                    );
-                }
-
-                // Verify manifest digest
-                let computed_digest = sha2::Sha256::digest(&contents);
-                let computed_hex = format!("{:x}", computed_digest);
-                if computed_hex != expected_hex {
-                    anyhow::bail!(
-                        "Manifest digest mismatch: expected {}, computed {}",
-                        expected_hex,
-                        computed_hex
-                    );
-                }
-                println!("✓ Manifest digest verified");
-            } else {
-                // Check if this is the config blob (don't count as layer)
-                if let Some(ref config_hex) = config_digest {
-                    if filename == *config_hex {
-                        println!("✓ Found and verified config blob");
-                        // Verify config digest
-                        let computed_digest = sha2::Sha256::digest(&contents);
-                        let computed_hex = format!("{:x}", computed_digest);
-                        if computed_hex != filename {
-                            anyhow::bail!(
-                                "Config digest mismatch for {}: computed {}",
-                                filename,
-                                computed_hex
-                            );
-                        }
-                        continue; // Skip counting this as a layer
-                    }
-                }
-
-                // This is a layer blob
-                layer_count += 1;
-                // Verify layer digest
-                let computed_digest = sha2::Sha256::digest(&contents);
-                let computed_hex = format!("{:x}", computed_digest);
-                if computed_hex != filename {
-                    anyhow::bail!(
-                        "Layer digest mismatch for {}: computed {}",
-                        filename,
-                        computed_hex
-                    );
-                }
-            }
-        }
-    }
+    let layout = read_oci_layout(artifact_path)?;
+    println!("✓ Found index.json");
 
-    if let Some(manifest_data) = manifest_bytes {
-        let manifest: OciManifest = serde_json::from_slice(&manifest_data)?;
-        println!(
-            "✓ Found {} layers in manifest, verified {} layer files",
-            manifest.layers.len(),
-            layer_count
+    // Verify the manifest digest in index matches expected
+    let root = layout
+        .index
+        .manifests
+        .first()
+        .context("index.json contains no manifests")?;
+    if root.digest != expected_digest {
+        anyhow::bail!(
+            "Manifest digest in index.json ({}) doesn't match expected digest ({})",
+            root.digest,
+            expected_digest
         );
+    }
+    println!("✓ Manifest digest in index.json matches expected digest");
 
-        if manifest.layers.len() != layer_count {
+    for (hex, contents) in &layout.blobs {
+        let computed_hex = format!("{:x}", sha2::Sha256::digest(contents));
+        if &computed_hex != hex {
             anyhow::bail!(
-                "Layer count mismatch: manifest declares {} layers but found {} layer files",
-                manifest.layers.len(),
-                layer_count
+                "Blob digest mismatch for {}: computed {}",
+                hex,
+                computed_hex
+            );
+        }
+    }
+    println!("✓ Verified digests of {} blobs", layout.blobs.len());
+
+    let root_bytes = layout.blob(expected_digest)?;
+    let mut referenced = HashSet::from([hex_of(expected_digest).to_string()]);
+    if is_index_manifest(root_bytes)? {
+        let index: IndexJson = serde_json::from_slice(root_bytes)?;
+        let entries = platform_manifests(&index);
+        if entries.is_empty() {
+            anyhow::bail!("OCI index contains no platform manifests");
+        }
+        for entry in entries {
+            verify_manifest_blobs(&layout, &entry.digest, &mut referenced)?;
+            println!(
+                "✓ Verified manifest {} for platform {}",
+                entry.digest,
+                entry.oci_platform()
             );
         }
-
-        // Perform additional integrity checks
-        verify_oci_artifact_integrity(&manifest_data, &manifest.layers, expected_digest)?;
     } else {
-        anyhow::bail!("Manifest blob not found in artifact");
+        verify_manifest_blobs(&layout, expected_digest, &mut referenced)?;
+    }
+
+    if let Some(unreferenced) = layout.blobs.keys().find(|hex| !referenced.contains(*hex)) {
+        anyhow::bail!(
+            "Artifact contains blob sha256:{} that is not referenced by any manifest",
+            unreferenced
+        );
     }
 
     println!("✓ Main artifact integrity verification completed");
     Ok(())
 }
 
+/// Verifies that the config and layers of a single-platform manifest are present in the layout
+fn verify_manifest_blobs(
+    layout: &OciLayout,
+    digest: &str,
+    referenced: &mut HashSet<String>,
+) -> Result<()> {
+    let manifest_bytes = layout.blob(digest)?;
+    let manifest: OciManifest = serde_json::from_slice(manifest_bytes)?;
+    referenced.insert(hex_of(digest).to_string());
+
+    if let Some(config_digest) = manifest.config.get("digest").and_then(|d| d.as_str()) {
+        layout.blob(config_digest)?;
+        referenced.insert(hex_of(config_digest).to_string());
+        println!("✓ Found and verified config blob");
+    }
+    for layer in &manifest.layers {
+        layout.blob(&layer.digest)?;
+        referenced.insert(hex_of(&layer.digest).to_string());
+    }
+    println!(
+        "✓ Found {} layers in manifest, all layer files present",
+        manifest.layers.len()
+    );
+
+    // Perform additional integrity checks
+    verify_oci_artifact_integrity(manifest_bytes, &manifest.layers, digest)
+}
+
 /// Verify attestation from tar.gz file
 fn verify_attestation_offline(
     attestation_path: &str,
     subject_digest: &str,
-    _config: &VerificationConfig,
+    config: &VerificationConfig,
 ) -> Result<()> {
     println!("🔍 Verifying attestation from {}", attestation_path);
 
@@ -811,7 +1001,7 @@ fn verify_attestation_offline(
         let subject_hex = subject_digest
             .strip_prefix("sha256:")
             .unwrap_or(subject_digest);
-        verify_slsa_provenance_attestation(&blob, subject_hex)?;
+        verify_slsa_provenance_attestation(&blob, subject_hex, config)?;
         println!("✓ Attestation verification completed");
     } else {
         anyhow::bail!("Incomplete attestation data in archive");
@@ -898,19 +1088,65 @@ fn verify_signature_offline(
     Ok(())
 }
 
+/// Verify SBOM from tar.gz file, checking that its content matches the stored layer digest
+fn verify_sbom_offline(sbom_path: &str) -> Result<()> {
+    println!("🔍 Verifying SBOM from {}", sbom_path);
+
+    let tar_file = File::open(sbom_path)?;
+    let decoder = flate2::read::GzDecoder::new(tar_file);
+    let mut archive = tar::Archive::new(decoder);
+
+    let mut sbom_content: Option<Vec<u8>> = None;
+    let mut stored_digest: Option<String> = None;
+
+    for entry_result in archive.entries()? {
+        let mut entry = entry_result?;
+        let path_str = entry.path()?.to_string_lossy().to_string();
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents)?;
+        if path_str == "sbom.json" {
+            sbom_content = Some(contents);
+        } else if path_str == "digest.txt" {
+            stored_digest = Some(String::from_utf8(contents)?);
+        }
+    }
+
+    let (Some(content), Some(digest)) = (sbom_content, stored_digest) else {
+        anyhow::bail!("Incomplete SBOM data in archive");
+    };
+
+    let computed = format!("sha256:{:x}", sha2::Sha256::digest(&content));
+    if computed != digest {
+        anyhow::bail!(
+            "SBOM digest mismatch: expected {}, computed {}",
+            digest,
+            computed
+        );
+    }
+    let sbom: serde_json::Value =
+        serde_json::from_slice(&content).context("Failed to parse SBOM JSON")?;
+    let components = sbom["components"].as_array().map_or(0, |c| c.len());
+    println!("✓ SBOM digest verified ({} components)", components);
+    Ok(())
+}
+
 /// Load verification configuration from environment variable or use defaults
 fn load_verification_config() -> Result<VerificationConfig> {
-    // Default policy content
-
-    // Default complete configuration JSON
-    let default_config_str = std::env::var("ATTESTATION_POLICY").unwrap();
-    let default_config = serde_json::from_str(&default_config_str).unwrap();
+    let default_config_str = std::env::var("ATTESTATION_POLICY").context(
+        "ATTESTATION_POLICY is not set and no verification configuration file was provided",
+    )?;
+    let default_config = serde_json::from_str(&default_config_str)
+        .context("Failed to parse ATTESTATION_POLICY as JSON")?;
 
     Ok(default_config)
 }
 
 /// Verifies SLSA provenance attestation content and subject matching
-fn verify_slsa_provenance_attestation(blob: &Blob, subject_digest: &str) -> Result<()> {
+fn verify_slsa_provenance_attestation(
+    blob: &Blob,
+    subject_digest: &str,
+    config: &VerificationConfig,
+) -> Result<()> {
     // Remove commented debug code and clean up verify_attestation function
     println!(
         "🔍 Verifying attestation blob {} for subject {}",
@@ -958,15 +1194,13 @@ fn verify_slsa_provenance_attestation(blob: &Blob, subject_digest: &str) -> Resu
                                 };
                                 println!("✓ SLSA provenance version: {}", version);
 
-                                let config = load_verification_config()?;
-
                                 println!("🔍 Extracting SLSA provenance information...");
 
                                 if config["policy_content"].as_str().is_some() {
                                     println!(
                                         "Using policy-based verification with embedded policy"
                                     );
-                                    verify_with_policy(&payload, &config)?;
+                                    verify_with_policy(&payload, config)?;
                                     println!("✓ Attestation verification passed!");
                                 } else {
                                     println!("No policy content provided, not performing policy-based verification");
@@ -1224,18 +1458,68 @@ pub fn verify_oci_artifact_integrity(
     println!("✓ OCI artifact integrity verification completed");
     Ok(())
 }
-struct OciArtifactData {
-    blobs: std::collections::HashMap<String, Vec<u8>>,
-    manifest: serde_json::Value,
+/// Contents of an OCI image layout stored as tar.gz
+struct OciLayout {
+    index: IndexJson,
+    blobs: HashMap<String, Vec<u8>>,
+}
+
+impl OciLayout {
+    fn blob(&self, digest: &str) -> Result<&Vec<u8>> {
+        self.blobs
+            .get(hex_of(digest))
+            .with_context(|| format!("Blob {} not found in OCI artifact", digest))
+    }
+
+    /// The single-platform manifest of the artifact, selected for `target` when the artifact is
+    /// a multi-platform index (the first platform if no target is given)
+    fn manifest(&self, target: Option<&OciPlatform>) -> Result<serde_json::Value> {
+        let root_digest = &self
+            .index
+            .manifests
+            .first()
+            .context("No manifest digest found")?
+            .digest;
+        let root_bytes = self.blob(root_digest)?;
+        if !is_index_manifest(root_bytes)? {
+            return Ok(serde_json::from_slice(root_bytes)?);
+        }
+
+        let index: IndexJson = serde_json::from_slice(root_bytes)?;
+        let entries = platform_manifests(&index);
+        let entry = match target {
+            Some(target) => select_platform_manifest(&entries, target).with_context(|| {
+                format!(
+                    "No manifest for platform {} in OCI index, available: {}",
+                    target,
+                    entries
+                        .iter()
+                        .map(|e| e.oci_platform().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?,
+            None => entries
+                .first()
+                .copied()
+                .context("OCI index contains no platform manifests")?,
+        };
+        println!("✓ Selected manifest for platform {}", entry.oci_platform());
+        Ok(serde_json::from_slice(self.blob(&entry.digest)?)?)
+    }
+}
+
+fn hex_of(digest: &str) -> &str {
+    digest.strip_prefix("sha256:").unwrap_or(digest)
 }
 
-fn parse_oci_artifact(oci_path: &str) -> Result<OciArtifactData> {
+fn read_oci_layout(oci_path: &str) -> Result<OciLayout> {
     let tar_file = File::open(oci_path).context("Failed to open OCI tar.gz file")?;
     let decoder = flate2::read::GzDecoder::new(tar_file);
     let mut archive = tar::Archive::new(decoder);
 
-    let mut index_json: Option<serde_json::Value> = None;
-    let mut blobs = std::collections::HashMap::new();
+    let mut index_json: Option<IndexJson> = None;
+    let mut blobs = HashMap::new();
 
     for entry_result in archive.entries()? {
         let mut entry = entry_result?;
@@ -1259,23 +1543,43 @@ fn parse_oci_artifact(oci_path: &str) -> Result<OciArtifactData> {
     }
 
     let index = index_json.context("index.json not found in OCI artifact")?;
-    let manifest_digest = index["manifests"][0]["digest"]
-        .as_str()
-        .context("No manifest digest found")?;
-    let manifest_hex = manifest_digest
-        .strip_prefix("sha256:")
-        .context("Invalid manifest digest format")?;
-    let manifest_content = blobs.get(manifest_hex).context("Manifest blob not found")?;
-    let manifest: serde_json::Value = serde_json::from_slice(manifest_content)?;
+    Ok(OciLayout { index, blobs })
+}
 
-    Ok(OciArtifactData { blobs, manifest })
+/// Platforms of the manifests in the artifact, empty if it is not a multi-platform index
+pub fn get_platforms_from_oci_targz(oci_path: &str) -> Result<Vec<OciPlatform>> {
+    let layout = read_oci_layout(oci_path)?;
+    let root_digest = &layout
+        .index
+        .manifests
+        .first()
+        .context("No manifest digest found")?
+        .digest;
+    let root_bytes = layout.blob(root_digest)?;
+    if !is_index_manifest(root_bytes)? {
+        return Ok(vec![]);
+    }
+    let index: IndexJson = serde_json::from_slice(root_bytes)?;
+    Ok(platform_manifests(&index)
+        .into_iter()
+        .map(IndexEntry::oci_platform)
+        .collect())
 }
 
 pub fn get_module_zip_from_oci_targz(oci_path: &str) -> Result<Vec<u8>> {
+    get_module_zip_from_oci_targz_for_platform(oci_path, None)
+}
+
+/// Module zip from the manifest for `platform` when the artifact is a multi-platform index
+pub fn get_module_zip_from_oci_targz_for_platform(
+    oci_path: &str,
+    platform: Option<&OciPlatform>,
+) -> Result<Vec<u8>> {
     println!("🔍 Extracting ZIP bytes from OCI tar.gz: {}", oci_path);
 
-    let artifact = parse_oci_artifact(oci_path)?;
-    let layers = artifact.manifest["layers"]
+    let layout = read_oci_layout(oci_path)?;
+    let manifest = layout.manifest(platform)?;
+    let layers = manifest["layers"]
         .as_array()
         .context("No layers found in manifest")?;
     if layers.is_empty() {
@@ -1290,7 +1594,7 @@ pub fn get_module_zip_from_oci_targz(oci_path: &str) -> Result<Vec<u8>> {
     let layer_hex = layer_digest
         .strip_prefix("sha256:")
         .context("Invalid layer digest format")?;
-    let layer_content = artifact
+    let layer_content = layout
         .blobs
         .get(layer_hex)
         .context("Layer blob not found")?;
@@ -1320,14 +1624,15 @@ pub fn get_module_manifest_from_oci_targz(oci_path: &str) -> Result<ModuleResp>
         oci_path
     );
 
-    let artifact = parse_oci_artifact(oci_path)?;
-    let config_digest = artifact.manifest["config"]["digest"]
+    let layout = read_oci_layout(oci_path)?;
+    let manifest = layout.manifest(None)?;
+    let config_digest = manifest["config"]["digest"]
         .as_str()
         .context("No config digest found")?;
     let config_hex = config_digest
         .strip_prefix("sha256:")
         .context("Invalid config digest format")?;
-    let config_content = artifact
+    let config_content = layout
         .blobs
         .get(config_hex)
         .context("Config blob not found")?;
@@ -1338,3 +1643,82 @@ pub fn get_module_manifest_from_oci_targz(oci_path: &str) -> Result<ModuleResp>
 
     serde_json::from_value(module_value.clone()).context("Failed to deserialize module from config")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use env_defs::{IndexPlatform, CLOUD_ANNOTATION};
+    use std::collections::BTreeMap;
+
+    fn entry(digest: &str, os_arch: Option<(&str, &str)>, cloud: Option<&str>) -> IndexEntry {
+        IndexEntry {
+            media_type: OCI_MANIFEST_MEDIA_TYPE.to_string(),
+            digest: digest.to_string(),
+            size: 0,
+            platform: os_arch.map(|(os, architecture)| IndexPlatform {
+                os: os.to_string(),
+                architecture: architecture.to_string(),
+                variant: None,
+            }),
+            annotations: cloud
+                .map(|c| BTreeMap::from([(CLOUD_ANNOTATION.to_string(), c.to_string())])),
+        }
+    }
+
+    fn target(arch: &str, cloud: &str) -> OciPlatform {
+        OciPlatform {
+            os: Some("linux".to_string()),
+            architecture: Some(arch.to_string()),
+            cloud: Some(cloud.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_select_platform_manifest_prefers_most_specific() {
+        let generic = entry("generic", None, None);
+        let arm = entry("arm", Some(("linux", "arm64")), None);
+        let arm_aws = entry("arm-aws", Some(("linux", "arm64")), Some("aws"));
+        let amd_azure = entry("amd-azure", Some(("linux", "amd64")), Some("azure"));
+        let entries = vec![&generic, &arm, &arm_aws, &amd_azure];
+
+        let selected = |t| select_platform_manifest(&entries, &t).map(|e| e.digest.clone());
+        assert_eq!(
+            selected(target("arm64", "aws")),
+            Some("arm-aws".to_string())
+        );
+        assert_eq!(selected(target("arm64", "azure")), Some("arm".to_string()));
+        assert_eq!(
+            selected(target("amd64", "azure")),
+            Some("amd-azure".to_string())
+        );
+        assert_eq!(
+            selected(target("amd64", "aws")),
+            Some("generic".to_string())
+        );
+    }
+
+    #[test]
+    fn test_select_platform_manifest_no_match() {
+        let arm = entry("arm", Some(("linux", "arm64")), None);
+        assert!(select_platform_manifest(&[&arm], &target("amd64", "aws")).is_none());
+    }
+
+    #[test]
+    fn test_platform_manifests_skips_attestations() {
+        let mut attestation = entry("att", Some(("unknown", "unknown")), None);
+        attestation.annotations = Some(BTreeMap::from([(
+            "vnd.docker.reference.type".to_string(),
+            "attestation-manifest".to_string(),
+        )]));
+        let index = IndexJson {
+            schema_version: 2,
+            media_type: Some(OCI_INDEX_MEDIA_TYPE.to_string()),
+            manifests: vec![entry("amd", Some(("linux", "amd64")), None), attestation],
+        };
+        let digests: Vec<&str> = platform_manifests(&index)
+            .iter()
+            .map(|e| e.digest.as_str())
+            .collect();
+        assert_eq!(digests, vec!["amd"]);
+    }
+}
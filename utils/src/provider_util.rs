@@ -1,7 +1,8 @@
 // Helper functions
 
 use env_defs::{
-    CloudProvider, Dependent, DeploymentResp, EventData, InfraChangeRecord, ModuleResp, PolicyResp,
+    join_change_record_parts, CloudProvider, Dependent, DeploymentResp, EventData,
+    InfraChangeRecord, JobQueueEntry, JobStats, ModuleResp, PolicyPackResp, PolicyResp,
     ProjectData, ProviderResp,
 };
 use log::info;
@@ -112,6 +113,34 @@ pub async fn _get_deployments(
     }
 }
 
+pub async fn _get_job_queue(
+    provider: &dyn CloudProvider,
+    query: Value,
+) -> Result<Vec<JobQueueEntry>, anyhow::Error> {
+    let items = provider.read_db_generic("deployments", &query).await?;
+    items
+        .into_iter()
+        .map(|item| {
+            serde_json::from_value(item)
+                .map_err(|e| anyhow::anyhow!("Failed to parse job queue entry: {}", e))
+        })
+        .collect()
+}
+
+pub async fn _get_job_stats(
+    provider: &dyn CloudProvider,
+    query: Value,
+) -> Result<Vec<JobStats>, anyhow::Error> {
+    let items = provider.read_db_generic("deployments", &query).await?;
+    items
+        .into_iter()
+        .map(|item| {
+            serde_json::from_value(item)
+                .map_err(|e| anyhow::anyhow!("Failed to parse job stats: {}", e))
+        })
+        .collect()
+}
+
 pub fn _mutate_deployment(value: &mut Vec<Value>) {
     for v in value {
         // Value is an array, loop through every element and modify the deleted field
@@ -196,13 +225,22 @@ pub async fn _get_events(
     }
 }
 
+/// Change record matching `query`, reassembled from the parts matching `parts_query` when it is
+/// stored in parts, see `split_change_record_item`
 pub async fn _get_change_records(
     provider: &dyn CloudProvider,
     query: Value,
+    parts_query: Value,
 ) -> Result<InfraChangeRecord, anyhow::Error> {
     match provider.read_db_generic("change_records", &query).await {
         Ok(change_records) => {
             if change_records.len() == 1 {
+                if change_records[0].get("chunks").is_some() {
+                    let parts = provider
+                        .read_db_generic("change_records", &parts_query)
+                        .await?;
+                    return join_change_record_parts(&change_records[0], &parts);
+                }
                 let change_record: InfraChangeRecord =
                     serde_json::from_value(change_records[0].clone())
                         .expect("Failed to parse change record");
@@ -255,6 +293,36 @@ pub async fn _get_policies(
     }
 }
 
+pub async fn _get_policy_pack(
+    provider: &dyn CloudProvider,
+    query: Value,
+) -> Result<PolicyPackResp, anyhow::Error> {
+    match provider.read_db_generic("policies", &query).await {
+        Ok(items) => match items.first() {
+            Some(item) => Ok(serde_json::from_value(item.clone())
+                .map_err(|e| anyhow::anyhow!("Failed to parse policy pack: {}", e))?),
+            None => Err(anyhow::anyhow!("No policy pack found")),
+        },
+        Err(e) => Err(e),
+    }
+}
+
+pub async fn _get_policy_packs(
+    provider: &dyn CloudProvider,
+    query: Value,
+) -> Result<Vec<PolicyPackResp>, anyhow::Error> {
+    match provider.read_db_generic("policies", &query).await {
+        Ok(items) => items
+            .into_iter()
+            .map(|item| {
+                serde_json::from_value(item)
+                    .map_err(|e| anyhow::anyhow!("Failed to parse policy pack: {}", e))
+            })
+            .collect(),
+        Err(e) => Err(e),
+    }
+}
+
 // If you need to add a field to ModuleResp, you can do it here
 fn _module_add_missing_fields(value: &mut Value) {
     if value["cpu"].is_null() {
@@ -0,0 +1,177 @@
+use base64::Engine;
+use env_defs::ModuleResp;
+use serde_json::{json, Value};
+
+pub const SBOM_MEDIA_TYPE: &str = "application/vnd.cyclonedx+json";
+pub const ATTESTATION_MEDIA_TYPE: &str = "application/vnd.dsse.envelope.v1+json";
+const IN_TOTO_PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+const BUILD_TYPE: &str = "https://infraweave.io/module-publish/v1";
+
+/// Generates a CycloneDX SBOM for a module or stack, listing the terraform providers from the
+/// lock file, the InfraWeave providers and, for stacks, the embedded module sources
+pub fn generate_module_sbom(module: &ModuleResp) -> Value {
+    let mut components: Vec<Value> = module
+        .tf_lock_providers
+        .iter()
+        .map(|provider| {
+            json!({
+                "type": "library",
+                "bom-ref": format!("provider:{}@{}", provider.source, provider.version),
+                "name": provider.source,
+                "version": provider.version,
+                "purl": format!("pkg:terraform/{}@{}", provider.source.trim_start_matches("registry.terraform.io/"), provider.version),
+            })
+        })
+        .collect();
+
+    components.extend(module.tf_providers.iter().map(|provider| {
+        json!({
+            "type": "library",
+            "bom-ref": format!("infraweave-provider:{}@{}", provider.name, provider.version),
+            "name": provider.name,
+            "version": provider.version,
+            "description": provider.description,
+            "externalReferences": [{ "type": "vcs", "url": provider.reference }],
+        })
+    }));
+
+    if let Some(stack_data) = &module.stack_data {
+        components.extend(stack_data.modules.iter().map(|stack_module| {
+            json!({
+                "type": "library",
+                "bom-ref": format!("module:{}@{}", stack_module.module, stack_module.version),
+                "name": stack_module.module,
+                "version": stack_module.version,
+                "properties": [
+                    { "name": "infraweave:track", "value": stack_module.track },
+                    { "name": "infraweave:source", "value": stack_module.s3_key },
+                ],
+            })
+        }));
+    }
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": module.timestamp,
+            "component": {
+                "type": "library",
+                "bom-ref": format!("{}:{}@{}", module.module_type, module.module, module.version),
+                "name": module.module,
+                "version": module.version,
+                "description": module.description,
+                "externalReferences": [{ "type": "vcs", "url": module.reference }],
+            },
+            "tools": [{ "vendor": "InfraWeave", "name": "infraweave" }],
+        },
+        "components": components,
+    })
+}
+
+/// Generates an unsigned SLSA v1 provenance statement for the published artifact with digest
+/// `subject_digest` (`sha256:...`), wrapped in a DSSE envelope
+pub fn generate_provenance_attestation(
+    module: &ModuleResp,
+    subject_name: &str,
+    subject_digest: &str,
+    builder_id: &str,
+) -> Value {
+    let statement = json!({
+        "_type": "https://in-toto.io/Statement/v1",
+        "subject": [{
+            "name": subject_name,
+            "digest": { "sha256": subject_digest.trim_start_matches("sha256:") },
+        }],
+        "predicateType": "https://slsa.dev/provenance/v1",
+        "predicate": {
+            "buildDefinition": {
+                "buildType": BUILD_TYPE,
+                "externalParameters": {
+                    "module": module.module,
+                    "version": module.version,
+                    "track": module.track,
+                    "reference": module.reference,
+                },
+                "resolvedDependencies": module
+                    .tf_lock_providers
+                    .iter()
+                    .map(|provider| json!({
+                        "uri": format!("pkg:terraform/{}@{}", provider.source.trim_start_matches("registry.terraform.io/"), provider.version),
+                    }))
+                    .collect::<Vec<Value>>(),
+            },
+            "runDetails": {
+                "builder": { "id": builder_id },
+                "metadata": { "startedOn": module.timestamp },
+            },
+        },
+    });
+
+    json!({
+        "payloadType": IN_TOTO_PAYLOAD_TYPE,
+        "payload": base64::engine::general_purpose::STANDARD.encode(statement.to_string()),
+        "signatures": [],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use env_defs::{ModuleStackData, StackModule, TfLockProvider};
+
+    fn module() -> ModuleResp {
+        ModuleResp {
+            module: "bucketcollection".to_string(),
+            module_type: "stack".to_string(),
+            version: "0.1.0".to_string(),
+            tf_lock_providers: vec![TfLockProvider {
+                source: "registry.terraform.io/hashicorp/aws".to_string(),
+                version: "5.81.0".to_string(),
+            }],
+            stack_data: Some(ModuleStackData {
+                modules: vec![StackModule {
+                    module: "s3bucket".to_string(),
+                    version: "0.2.1".to_string(),
+                    s3_key: "s3bucket/s3bucket-0.2.1.zip".to_string(),
+                    track: "stable".to_string(),
+                    claim: "bucket".to_string(),
+                }],
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_generate_module_sbom() {
+        let sbom = generate_module_sbom(&module());
+
+        assert_eq!(sbom["bomFormat"], "CycloneDX");
+        assert_eq!(
+            sbom["metadata"]["component"]["bom-ref"],
+            "stack:bucketcollection@0.1.0"
+        );
+        let components = sbom["components"].as_array().unwrap();
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0]["purl"], "pkg:terraform/hashicorp/aws@5.81.0");
+        assert_eq!(components[1]["name"], "s3bucket");
+    }
+
+    #[test]
+    fn test_generate_provenance_attestation() {
+        let envelope = generate_provenance_attestation(
+            &module(),
+            "ghcr.io/org/bucketcollection",
+            "sha256:abc123",
+            "https://infraweave.io/cli",
+        );
+
+        let payload = base64::engine::general_purpose::STANDARD
+            .decode(envelope["payload"].as_str().unwrap())
+            .unwrap();
+        let statement: Value = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(statement["subject"][0]["digest"]["sha256"], "abc123");
+        assert_eq!(statement["predicateType"], "https://slsa.dev/provenance/v1");
+    }
+}
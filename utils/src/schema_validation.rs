@@ -102,6 +102,11 @@ properties:
         type: string
       data:
         type: object
+      target:
+        type: string
+        enum:
+          - plan
+          - claim
     required:
       - policyName
       - version
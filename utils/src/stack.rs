@@ -1,8 +1,15 @@
-use env_defs::DeploymentManifest;
+use env_defs::{DeploymentManifest, ModuleResp, StackInstanceModule, StackInstanceOutput};
+use hcl::{Body, Expression, ObjectKey, TemplateExpr};
+use regex::Regex;
+use serde_json::Value;
+use serde_yaml::{Mapping, Value as YamlValue};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use walkdir::WalkDir;
 
+use crate::{to_camel_case, to_snake_case};
+
 /// Reads all .yaml files in a given directory and returns the deployments.
 pub fn read_stack_directory(directory: &Path) -> anyhow::Result<Vec<DeploymentManifest>> {
     let mut deployments = vec![];
@@ -36,3 +43,412 @@ pub fn read_stack_directory(directory: &Path) -> anyhow::Result<Vec<DeploymentMa
 
     anyhow::Ok(deployments)
 }
+
+/// The modules a deployment of `stack` is made of, with the outputs of the deployment mapped to
+/// the module they come from. `output` holds the outputs of the deployment as returned by
+/// `terraform output -json`.
+pub fn stack_instance_modules(stack: &ModuleResp, output: &Value) -> Vec<StackInstanceModule> {
+    let Some(stack_data) = &stack.stack_data else {
+        return vec![];
+    };
+
+    stack_data
+        .modules
+        .iter()
+        .map(|stack_module| {
+            let prefix = format!("{}__", to_snake_case(&stack_module.claim));
+            let outputs = stack
+                .tf_outputs
+                .iter()
+                .filter(|_| !stack_module.claim.is_empty())
+                .filter_map(|tf_output| {
+                    let module_output = tf_output.name.strip_prefix(&prefix)?;
+                    let value = match output.get(&tf_output.name) {
+                        Some(entry) => entry.get("value").unwrap_or(entry).clone(),
+                        None => Value::Null,
+                    };
+                    Some(StackInstanceOutput {
+                        name: tf_output.name.clone(),
+                        module_output: module_output.to_string(),
+                        value,
+                    })
+                })
+                .collect();
+            StackInstanceModule {
+                claim: stack_module.claim.clone(),
+                module: stack_module.module.clone(),
+                version: stack_module.version.clone(),
+                track: stack_module.track.clone(),
+                outputs,
+            }
+        })
+        .collect()
+}
+
+/// Module block attributes that are not inputs of the module
+const NON_INPUT_ATTRIBUTES: [&str; 3] = ["source", "providers", "depends_on"];
+
+/// Reconstructs the claims a stack was published from, one YAML document per claim, with
+/// `variables`, the variables of a deployment of the stack.
+///
+/// `members` are the claims of the stack, by name, with the module each of them deploys.
+/// `stack_main_tf` is the `main.tf` of the published stack, where references between the claims
+/// are read from. They are written back as `{{ Kind::claim::field }}`, so the claims can be put
+/// in a stack directory again, or the references replaced to deploy the claims on their own.
+pub fn generate_stack_member_claims(
+    variables: &Value,
+    members: &[(String, ModuleResp)],
+    stack_main_tf: Option<&str>,
+) -> Result<String, anyhow::Error> {
+    // Module names in main.tf and variable prefixes are the snake_case claim names
+    let claims: HashMap<String, (&str, &str)> = members
+        .iter()
+        .map(|(claim, module)| {
+            (
+                to_snake_case(claim),
+                (module.module_name.as_str(), claim.as_str()),
+            )
+        })
+        .collect();
+    let body: Option<Body> = stack_main_tf.map(hcl::parse).transpose()?;
+
+    let mut documents = vec![];
+    for (claim, module) in members {
+        let prefix = format!("{}__", to_snake_case(claim));
+        let mut claim_variables = Mapping::new();
+        for (key, value) in variables.as_object().into_iter().flatten() {
+            if let Some(input) = key.strip_prefix(&prefix) {
+                insert_claim_variable(&mut claim_variables, input, serde_yaml::to_value(value)?);
+            }
+        }
+
+        let mut depends_on = vec![];
+        let block = body.iter().flat_map(|body| body.blocks()).find(|block| {
+            block.identifier() == "module"
+                && block.labels().first().map(|label| label.as_str()) == Some(claim.as_str())
+        });
+        for attribute in block.iter().flat_map(|block| block.body().attributes()) {
+            let input = attribute.key();
+            if input == "depends_on" {
+                if let Expression::Array(modules) = attribute.expr() {
+                    depends_on.extend(modules.iter().filter_map(|module| {
+                        let path = hcl::format::to_string(module).ok()?;
+                        let (_, name) = claims.get(path.strip_prefix("module.")?)?;
+                        Some(YamlValue::from(*name))
+                    }));
+                }
+                continue;
+            }
+            if NON_INPUT_ATTRIBUTES.contains(&input) {
+                continue;
+            }
+            // Inputs set by the deployment are read from the stack's variables, see above
+            let own_variable = format!("var.{}{}", prefix, input);
+            if hcl::format::to_string(attribute.expr()).ok().as_deref() == Some(&own_variable) {
+                continue;
+            }
+            let value = claim_value(attribute.expr(), &claims)?;
+            insert_claim_variable(&mut claim_variables, input, value);
+        }
+
+        let version_key = if module.module_type == "stack" {
+            "stackVersion"
+        } else {
+            "moduleVersion"
+        };
+        let mut metadata = Mapping::new();
+        metadata.insert("name".into(), claim.as_str().into());
+        let mut spec = Mapping::new();
+        spec.insert(version_key.into(), module.version.as_str().into());
+        // The region of the claims is set by the stack deployment
+        spec.insert("region".into(), "N/A".into());
+        spec.insert("variables".into(), YamlValue::Mapping(claim_variables));
+        if !depends_on.is_empty() {
+            spec.insert("dependsOn".into(), YamlValue::Sequence(depends_on));
+        }
+        let mut document = Mapping::new();
+        document.insert("apiVersion".into(), "infraweave.io/v1".into());
+        document.insert("kind".into(), module.module_name.as_str().into());
+        document.insert("metadata".into(), YamlValue::Mapping(metadata));
+        document.insert("spec".into(), YamlValue::Mapping(spec));
+        documents.push(serde_yaml::to_string(&document)?);
+    }
+    Ok(documents.join("---\n"))
+}
+
+/// Sets an input of a stack claim, inputs of nested stacks (`<claim>__<input>`) are set as a
+/// mapping per claim
+fn insert_claim_variable(variables: &mut Mapping, input: &str, value: YamlValue) {
+    match input.split_once("__") {
+        Some((claim, nested_input)) => {
+            let key = YamlValue::from(to_camel_case(claim));
+            if !variables.get(&key).is_some_and(|v| v.is_mapping()) {
+                variables.insert(key.clone(), YamlValue::Mapping(Mapping::new()));
+            }
+            if let Some(YamlValue::Mapping(nested)) = variables.get_mut(&key) {
+                insert_claim_variable(nested, nested_input, value);
+            }
+        }
+        None => {
+            variables.insert(YamlValue::from(to_camel_case(input)), value);
+        }
+    }
+}
+
+/// Claim value of a module input set in the stack, with its references to other claims and to
+/// stack variables written as `{{ Kind::claim::field }}`
+fn claim_value(
+    expr: &Expression,
+    claims: &HashMap<String, (&str, &str)>,
+) -> Result<YamlValue, anyhow::Error> {
+    Ok(match expr {
+        Expression::Null => YamlValue::Null,
+        Expression::Bool(value) => YamlValue::Bool(*value),
+        Expression::Number(number) => serde_yaml::to_value(number)?,
+        Expression::String(value) => YamlValue::String(value.clone()),
+        Expression::Array(values) => YamlValue::Sequence(
+            values
+                .iter()
+                .map(|value| claim_value(value, claims))
+                .collect::<Result<_, _>>()?,
+        ),
+        Expression::Object(object) => {
+            let mut mapping = Mapping::new();
+            for (key, value) in object.iter() {
+                let key = match key {
+                    ObjectKey::Identifier(identifier) => identifier.to_string(),
+                    ObjectKey::Expression(Expression::String(key)) => key.clone(),
+                    ObjectKey::Expression(key) => hcl::format::to_string(key)?,
+                    key => key.to_string(),
+                };
+                mapping.insert(YamlValue::from(key), claim_value(value, claims)?);
+            }
+            YamlValue::Mapping(mapping)
+        }
+        Expression::TemplateExpr(template) => {
+            let template = match template.as_ref() {
+                TemplateExpr::QuotedString(template) => template.clone(),
+                TemplateExpr::Heredoc(heredoc) => heredoc.template.clone(),
+            };
+            let interpolation = Regex::new(r"\$\{([A-Za-z0-9_.]+)\}").unwrap();
+            let mut unresolved = None;
+            let value = interpolation.replace_all(&template, |captures: &regex::Captures| {
+                reference(&captures[1], claims).unwrap_or_else(|| {
+                    unresolved = Some(captures[0].to_string());
+                    captures[0].to_string()
+                })
+            });
+            if let Some(unresolved) = unresolved {
+                return Err(anyhow::anyhow!(
+                    "Unknown reference {} in the stack",
+                    unresolved
+                ));
+            }
+            YamlValue::String(value.into_owned())
+        }
+        _ => {
+            let path = hcl::format::to_string(expr)?;
+            YamlValue::String(
+                reference(&path, claims)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown reference {} in the stack", path))?,
+            )
+        }
+    })
+}
+
+/// Claim reference of a `module.<claim>.<output>` or `var.<claim>__<variable>` traversal
+fn reference(path: &str, claims: &HashMap<String, (&str, &str)>) -> Option<String> {
+    let (claim, field) = if let Some(module_output) = path.strip_prefix("module.") {
+        module_output.split_once('.')?
+    } else {
+        path.strip_prefix("var.")?.split_once("__")?
+    };
+    // Outputs and variables of nested stacks keep the "__" separator
+    let field = field
+        .split("__")
+        .map(to_camel_case)
+        .collect::<Vec<_>>()
+        .join("__");
+    if claim == "stack" {
+        return Some(format!("{{{{ Stack::variables::{} }}}}", field));
+    }
+    let (kind, name) = claims.get(claim)?;
+    Some(format!("{{{{ {}::{}::{} }}}}", kind, name, field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use env_defs::{ModuleStackData, StackModule, TfOutput};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn stack_module(module: &str, claim: &str) -> StackModule {
+        StackModule {
+            module: module.to_string(),
+            version: "0.1.0".to_string(),
+            s3_key: format!("{}/{}-0.1.0.zip", module, module),
+            track: "stable".to_string(),
+            claim: claim.to_string(),
+        }
+    }
+
+    fn tf_output(name: &str) -> TfOutput {
+        TfOutput {
+            name: name.to_string(),
+            description: "".to_string(),
+            value: "".to_string(),
+            sensitive: None,
+        }
+    }
+
+    #[test]
+    fn test_stack_instance_modules() {
+        let stack = ModuleResp {
+            module: "bucketcollection".to_string(),
+            module_type: "stack".to_string(),
+            stack_data: Some(ModuleStackData {
+                modules: vec![
+                    stack_module("s3bucket", "bucket1a"),
+                    stack_module("s3bucket", "Bucket2"),
+                    stack_module("vpc", ""),
+                ],
+            }),
+            tf_outputs: vec![
+                tf_output("bucket1a__bucket_arn"),
+                tf_output("bucket2__bucket_arn"),
+                tf_output("bucket2__region"),
+            ],
+            ..Default::default()
+        };
+        let output = json!({
+            "bucket1a__bucket_arn": {"sensitive": false, "type": "string", "value": "arn:aws:s3:::one"},
+        });
+
+        let modules = stack_instance_modules(&stack, &output);
+
+        assert_eq!(modules.len(), 3);
+        assert_eq!(modules[0].claim, "bucket1a");
+        assert_eq!(
+            modules[0].outputs,
+            vec![StackInstanceOutput {
+                name: "bucket1a__bucket_arn".to_string(),
+                module_output: "bucket_arn".to_string(),
+                value: json!("arn:aws:s3:::one"),
+            }]
+        );
+        let names: Vec<&str> = modules[1]
+            .outputs
+            .iter()
+            .map(|o| o.module_output.as_str())
+            .collect();
+        assert_eq!(names, vec!["bucket_arn", "region"]);
+        assert!(modules[1].outputs[0].value.is_null());
+        // Stacks published before the claim was recorded have no output mapping
+        assert!(modules[2].outputs.is_empty());
+        assert!(stack_instance_modules(&ModuleResp::default(), &output).is_empty());
+    }
+
+    const STACK_MAIN_TF: &str = r#"
+module "bucket1a" {
+  source      = "./S3Bucket-0.1.0"
+  bucket_name = var.bucket1a__bucket_name
+  tags        = var.bucket1a__tags
+}
+
+module "bucket2" {
+  source      = "./S3Bucket-0.1.0"
+  bucket_name = "${module.bucket1a.bucket_name}-after"
+  tags = {
+    Owner = var.stack__owner
+    Arn   = module.bucket1a.bucket_arn
+  }
+  depends_on = [module.bucket1a]
+}
+"#;
+
+    fn module(module_name: &str, version: &str) -> ModuleResp {
+        ModuleResp {
+            module: module_name.to_lowercase(),
+            module_name: module_name.to_string(),
+            module_type: "module".to_string(),
+            version: version.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_generate_stack_member_claims() {
+        let variables = json!({
+            "bucket1a__bucket_name": "first-bucket",
+            "bucket1a__tags": { "Team": "platform" },
+            "bucket2__enable_versioning": true,
+            "stack__owner": "platform",
+        });
+        let members = vec![
+            ("bucket1a".to_string(), module("S3Bucket", "0.1.0")),
+            ("bucket2".to_string(), module("S3Bucket", "0.1.0")),
+        ];
+
+        let claims =
+            generate_stack_member_claims(&variables, &members, Some(STACK_MAIN_TF)).unwrap();
+        assert_eq!(
+            claims,
+            r#"apiVersion: infraweave.io/v1
+kind: S3Bucket
+metadata:
+  name: bucket1a
+spec:
+  moduleVersion: 0.1.0
+  region: N/A
+  variables:
+    bucketName: first-bucket
+    tags:
+      Team: platform
+---
+apiVersion: infraweave.io/v1
+kind: S3Bucket
+metadata:
+  name: bucket2
+spec:
+  moduleVersion: 0.1.0
+  region: N/A
+  variables:
+    enableVersioning: true
+    bucketName: '{{ S3Bucket::bucket1a::bucketName }}-after'
+    tags:
+      Owner: '{{ Stack::variables::owner }}'
+      Arn: '{{ S3Bucket::bucket1a::bucketArn }}'
+  dependsOn:
+  - bucket1a
+"#
+        );
+
+        // Without main.tf only the variables of the deployment are known
+        let claims = generate_stack_member_claims(&variables, &members, None).unwrap();
+        let documents: Vec<YamlValue> = claims
+            .split("---\n")
+            .map(|document| serde_yaml::from_str(document).unwrap())
+            .collect();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(
+            documents[1]["spec"]["variables"],
+            serde_yaml::from_str::<YamlValue>("enableVersioning: true").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_insert_claim_variable_nested_stack() {
+        let mut variables = Mapping::new();
+        insert_claim_variable(&mut variables, "bucket1__bucket_name", "a".into());
+        insert_claim_variable(&mut variables, "bucket1__tags", "b".into());
+        insert_claim_variable(&mut variables, "region_name", "c".into());
+        assert_eq!(
+            YamlValue::Mapping(variables),
+            serde_yaml::from_str::<YamlValue>(
+                "bucket1:\n  bucketName: a\n  tags: b\nregionName: c"
+            )
+            .unwrap()
+        );
+    }
+}
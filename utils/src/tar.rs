@@ -1,80 +1,105 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Cursor, Read, Seek, Write};
+use std::path::Path;
+use tar::{Archive, Builder, Header};
+use zip::write::{FileOptions, ZipWriter};
+use zip::{CompressionMethod, ZipArchive};
 
 pub fn zip_bytes_to_targz(bytes: &[u8]) -> Vec<u8> {
-    // Convert ZIP bytes to a valid tar.gz: unpack ZIP, tar entries, then gzip
-    use flate2::write::GzEncoder;
-    use flate2::Compression;
-    use std::io::{Cursor, Read, Write};
-    use tar::{Builder, Header};
-    use zip::ZipArchive;
-
-    // Read ZIP archive from memory
-    let reader = Cursor::new(bytes);
-    let mut zip = ZipArchive::new(reader).expect("Failed to read ZIP archive");
-    // Prepare buffer for tar data
-    let mut tar_buf = Vec::new();
-    {
-        let mut tar = Builder::new(&mut tar_buf);
-        for i in 0..zip.len() {
-            let mut file = zip.by_index(i).expect("Failed to access ZIP entry");
-            let mut data = Vec::new();
-            file.read_to_end(&mut data)
-                .expect("Failed to read ZIP entry");
-            let mut header = Header::new_gnu();
-            header.set_path(file.name()).expect("Invalid path");
-            header.set_size(data.len() as u64);
-            header.set_cksum();
-            tar.append(&header, &data[..])
-                .expect("Failed to append to tar");
-        }
-        tar.finish().expect("Failed to finish tar archive");
-    }
-    // Gzip compress the tar data
-    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-    encoder
-        .write_all(&tar_buf)
-        .expect("Failed to write to GzEncoder");
-    encoder.finish().expect("Failed to finish GzEncoder")
+    zip_to_targz(Cursor::new(bytes), Vec::new()).expect("Failed to convert ZIP to tar.gz")
 }
 
 pub fn targz_to_zip_bytes(targz: &[u8]) -> Vec<u8> {
-    // Decompress the .tar.gz, then repackage the tar entries into a .zip archive
-    use flate2::read::GzDecoder;
-    use std::io::{Cursor, Read, Write};
-    use tar::Archive;
-    use zip::write::{FileOptions, ZipWriter};
-    use zip::CompressionMethod;
+    targz_to_zip(targz, Cursor::new(Vec::new()))
+        .expect("Failed to convert tar.gz to ZIP")
+        .into_inner()
+}
 
-    // Decode gzip into raw tar data
-    let mut decoder = GzDecoder::new(targz);
-    let mut tar_data = Vec::new();
-    decoder
-        .read_to_end(&mut tar_data)
-        .expect("Failed to decompress tar.gz");
-    // Read tar entries
-    let mut archive = Archive::new(Cursor::new(tar_data));
-    // Prepare zip writer
-    let cursor = Cursor::new(Vec::new());
-    let mut zip = ZipWriter::new(cursor);
+/// Repackages the entries of a ZIP archive as a tar.gz, one entry at a time so memory use does
+/// not grow with the size of the archive. Returns the writer once the gzip stream is finished
+pub fn zip_to_targz<R: Read + Seek, W: Write>(reader: R, writer: W) -> Result<W, anyhow::Error> {
+    let mut zip = ZipArchive::new(reader)?;
+    let mut tar = Builder::new(GzEncoder::new(writer, Compression::default()));
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        let mut header = Header::new_gnu();
+        header.set_size(file.size());
+        let name = file.name().to_string();
+        tar.append_data(&mut header, name, &mut file)?;
+    }
+    Ok(tar.into_inner()?.finish()?)
+}
+
+/// Repackages the entries of a tar.gz as a ZIP archive, one entry at a time so memory use does
+/// not grow with the size of the archive. Returns the writer once the archive is finished
+pub fn targz_to_zip<R: Read, W: Write + Seek>(reader: R, writer: W) -> Result<W, anyhow::Error> {
+    let mut archive = Archive::new(GzDecoder::new(reader));
+    let mut zip = ZipWriter::new(writer);
     let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
-    for entry in archive.entries().expect("Failed to read tar entries") {
-        let mut entry = entry.expect("Failed to access tar entry");
-        let path = entry.path().expect("Invalid entry path").into_owned();
-        let mut data = Vec::new();
-        entry
-            .read_to_end(&mut data)
-            .expect("Failed to read tar entry");
-        // Write file entry into zip
-        zip.start_file(path.to_string_lossy(), options)
-            .expect("Failed to start zip entry");
-        zip.write_all(&data).expect("Failed to write zip entry");
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        zip.start_file(path.to_string_lossy(), options)?;
+        io::copy(&mut entry, &mut zip)?;
     }
-    // Finalize zip archive and return bytes
-    let cursor = zip.finish().expect("Failed to finalize zip");
-    cursor.into_inner()
+    Ok(zip.finish()?)
+}
+
+/// Converts the ZIP file at `zip_path` to a tar.gz file at `targz_path` without loading either
+pub fn zip_file_to_targz_file(zip_path: &Path, targz_path: &Path) -> Result<(), anyhow::Error> {
+    let reader = BufReader::new(File::open(zip_path)?);
+    zip_to_targz(reader, BufWriter::new(File::create(targz_path)?))?.flush()?;
+    Ok(())
+}
+
+/// Converts the tar.gz file at `targz_path` to a ZIP file at `zip_path` without loading either
+pub fn targz_file_to_zip_file(targz_path: &Path, zip_path: &Path) -> Result<(), anyhow::Error> {
+    let reader = BufReader::new(File::open(targz_path)?);
+    targz_to_zip(reader, BufWriter::new(File::create(zip_path)?))?.flush()?;
+    Ok(())
 }
 
 pub fn get_diff_id_from_zip(zip_bytes: &[u8]) -> Result<String, anyhow::Error> {
-    let diff_id = format!("sha256:{:x}", Sha256::digest(zip_bytes));
-    Ok(diff_id)
+    get_diff_id_from_reader(zip_bytes)
+}
+
+/// Digest of the content of `reader` in the `sha256:<hex>` form of OCI diff ids
+pub fn get_diff_id_from_reader(mut reader: impl Read) -> Result<String, anyhow::Error> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut reader, &mut hasher)?;
+    Ok(format!("sha256:{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zip_targz_roundtrip_through_files() {
+        let directory = crate::create_temp_dir().unwrap();
+        let zip =
+            crate::get_zip_file_from_str("resource \"null_resource\" \"a\" {}", "main.tf").unwrap();
+        let zip_path = directory.join("module.zip");
+        std::fs::write(&zip_path, &zip).unwrap();
+
+        let targz_path = directory.join("module.tar.gz");
+        zip_file_to_targz_file(&zip_path, &targz_path).unwrap();
+        let roundtrip_path = directory.join("roundtrip.zip");
+        targz_file_to_zip_file(&targz_path, &roundtrip_path).unwrap();
+        let roundtrip = std::fs::read(&roundtrip_path).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            crate::read_tf_from_zip(&roundtrip).unwrap(),
+            "resource \"null_resource\" \"a\" {}\n"
+        );
+        assert_eq!(
+            get_diff_id_from_zip(&zip).unwrap(),
+            format!("sha256:{:x}", Sha256::digest(&zip))
+        );
+    }
 }
@@ -13,6 +13,8 @@ use serde_json::Value;
 use std::fs::{write, File};
 use uuid::Uuid;
 
+use crate::module::PLATFORM_ENVIRONMENT_VARIABLE_PREFIX;
+
 #[derive(Deserialize, Debug)]
 #[allow(dead_code)]
 struct RegistryDownloadResponse {
@@ -730,9 +732,19 @@ terraform {{
     }
 }
 
+/// Platform environment variables set in the current process, returned by the
+/// `get_environment_variables` event so that runners can pass them on
+pub fn get_platform_environment_variables() -> serde_json::Map<String, serde_json::Value> {
+    std::env::vars()
+        .filter(|(key, _)| key.starts_with(PLATFORM_ENVIRONMENT_VARIABLE_PREFIX))
+        .map(|(key, value)| (key, serde_json::Value::String(value)))
+        .collect()
+}
+
 #[rustfmt::skip]
 pub fn get_extra_environment_variables(
     payload: &ApiInfraPayload,
+    platform_variables: &serde_json::Value,
 ) -> std::collections::HashMap<String, String> {
     get_extra_environment_variables_all(
         &payload.deployment_id,
@@ -743,6 +755,7 @@ pub fn get_extra_environment_variables(
         &payload.module_track,
         &payload.drift_detection,
         &payload.extra_data,
+        platform_variables,
     )
 }
 
@@ -755,8 +768,19 @@ pub fn get_extra_environment_variables_all(
     module_track: &str,
     drift_detection: &env_defs::DriftDetection,
     extra_data: &ExtraData,
+    platform_variables: &serde_json::Value,
 ) -> std::collections::HashMap<String, String> {
     let mut env_vars = std::collections::HashMap::new();
+    if let Some(platform_variables) = platform_variables.as_object() {
+        for (key, value) in platform_variables {
+            if let (true, Some(value)) = (
+                key.starts_with(PLATFORM_ENVIRONMENT_VARIABLE_PREFIX),
+                value.as_str(),
+            ) {
+                env_vars.insert(key.clone(), value.to_string());
+            }
+        }
+    }
     env_vars.insert(
         "INFRAWEAVE_DEPLOYMENT_ID".to_string(),
         deployment_id.to_string(),
@@ -832,11 +856,40 @@ pub fn get_extra_environment_variables_all(
             );
         }
         ExtraData::GitLab(gitlab_data) => {
-            // TODO: Add more here for GitLab
+            if let Some(user) = &gitlab_data.user {
+                env_vars.insert(
+                    "INFRAWEAVE_GIT_COMMITTER_EMAIL".to_string(),
+                    user.email.clone(),
+                );
+                env_vars.insert(
+                    "INFRAWEAVE_GIT_COMMITTER_NAME".to_string(),
+                    if std::env::var("INFRAWEAVE_ALLOW_UNICODE_IN_ENV").is_ok() {
+                        user.name.clone()
+                    } else {
+                        deunicode(&user.name)
+                    },
+                );
+                env_vars.insert(
+                    "INFRAWEAVE_GIT_ACTOR_USERNAME".to_string(),
+                    user.username.clone(),
+                );
+                env_vars.insert(
+                    "INFRAWEAVE_GIT_ACTOR_PROFILE_URL".to_string(),
+                    user.profile_url.clone(),
+                );
+            }
+            env_vars.insert(
+                "INFRAWEAVE_GIT_REPOSITORY_NAME".to_string(),
+                gitlab_data.project.path_with_namespace.clone(),
+            );
             env_vars.insert(
                 "INFRAWEAVE_GIT_REPOSITORY_PATH".to_string(),
                 gitlab_data.job_details.file_path.clone(),
             );
+            env_vars.insert(
+                "INFRAWEAVE_GIT_COMMIT_SHA".to_string(),
+                gitlab_data.pipeline.sha.clone(),
+            );
         }
         ExtraData::None => {}
     };
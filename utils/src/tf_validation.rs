@@ -0,0 +1,224 @@
+use env_defs::TfVariable;
+use hcl::eval::{Context, Evaluate, FuncArgs, FuncDef, ParamType};
+use hcl::{Expression, Number, Operation, Value};
+
+/// Messages of the `validation` blocks of `variable` whose condition is false for `value`.
+///
+/// `var` holds the values of all variables of the claim, as conditions may refer to other
+/// variables. Conditions using functions or values that are not available here are skipped with
+/// a debug log, Terraform still checks those once the job runs.
+pub fn failed_validations(
+    variable: &TfVariable,
+    value: &serde_json::Value,
+    var: &serde_json::Value,
+) -> Vec<String> {
+    if variable.validations.is_empty() || value.is_null() {
+        return vec![];
+    }
+
+    let ctx = match context(var) {
+        Ok(ctx) => ctx,
+        Err(e) => {
+            log::debug!("Skipping validation of \"{}\": {}", variable.name, e);
+            return vec![];
+        }
+    };
+
+    variable
+        .validations
+        .iter()
+        .filter(|validation| match evaluate(&validation.expression, &ctx) {
+            Ok(Value::Bool(valid)) => !valid,
+            Ok(other) => {
+                log::debug!(
+                    "Skipping validation \"{}\" of \"{}\": result {} is not a bool",
+                    validation.expression,
+                    variable.name,
+                    other
+                );
+                false
+            }
+            Err(e) => {
+                log::debug!(
+                    "Skipping validation \"{}\" of \"{}\": {}",
+                    validation.expression,
+                    variable.name,
+                    e
+                );
+                false
+            }
+        })
+        .map(|validation| validation.message.clone())
+        .collect()
+}
+
+fn evaluate(expression: &str, ctx: &Context) -> Result<Value, String> {
+    let body = hcl::parse(&format!("condition = {}", expression)).map_err(|e| e.to_string())?;
+    let mut expr = body
+        .attributes()
+        .next()
+        .map(|attr| attr.expr().clone())
+        .ok_or_else(|| "empty condition".to_string())?;
+    resolve_can(&mut expr, ctx);
+    expr.evaluate(ctx).map_err(|e| e.to_string())
+}
+
+/// `can(...)` is true when its argument evaluates without error. It cannot be declared as a
+/// function since arguments are evaluated before functions are called, so it is resolved first
+fn resolve_can(expr: &mut Expression, ctx: &Context) {
+    match expr {
+        Expression::FuncCall(call) if call.name.to_string() == "can" && call.args.len() == 1 => {
+            let mut arg = call.args[0].clone();
+            resolve_can(&mut arg, ctx);
+            *expr = Expression::Bool(arg.evaluate(ctx).is_ok());
+        }
+        Expression::FuncCall(call) => {
+            call.args.iter_mut().for_each(|arg| resolve_can(arg, ctx));
+        }
+        Expression::Parenthesis(inner) => resolve_can(inner, ctx),
+        Expression::Array(items) => items.iter_mut().for_each(|item| resolve_can(item, ctx)),
+        Expression::Conditional(cond) => {
+            resolve_can(&mut cond.cond_expr, ctx);
+            resolve_can(&mut cond.true_expr, ctx);
+            resolve_can(&mut cond.false_expr, ctx);
+        }
+        Expression::Operation(op) => match op.as_mut() {
+            Operation::Unary(unary) => resolve_can(&mut unary.expr, ctx),
+            Operation::Binary(binary) => {
+                resolve_can(&mut binary.lhs_expr, ctx);
+                resolve_can(&mut binary.rhs_expr, ctx);
+            }
+        },
+        _ => {}
+    }
+}
+
+fn context(var: &serde_json::Value) -> Result<Context<'static>, String> {
+    let mut ctx = Context::new();
+    ctx.declare_var("var", hcl::to_value(var).map_err(|e| e.to_string())?);
+
+    let any = || ParamType::Any;
+    let string = || ParamType::String;
+    let bool_list = || ParamType::Array(Box::new(ParamType::Bool));
+    ctx.declare_func("length", FuncDef::builder().param(any()).build(length));
+    ctx.declare_func(
+        "contains",
+        FuncDef::builder().param(any()).param(any()).build(contains),
+    );
+    ctx.declare_func(
+        "regex",
+        FuncDef::builder().params([string(), string()]).build(regex),
+    );
+    ctx.declare_func(
+        "regexall",
+        FuncDef::builder()
+            .params([string(), string()])
+            .build(regexall),
+    );
+    ctx.declare_func(
+        "startswith",
+        FuncDef::builder()
+            .params([string(), string()])
+            .build(startswith),
+    );
+    ctx.declare_func(
+        "endswith",
+        FuncDef::builder()
+            .params([string(), string()])
+            .build(endswith),
+    );
+    ctx.declare_func("lower", FuncDef::builder().param(string()).build(lower));
+    ctx.declare_func("upper", FuncDef::builder().param(string()).build(upper));
+    ctx.declare_func(
+        "trimspace",
+        FuncDef::builder().param(string()).build(trimspace),
+    );
+    ctx.declare_func(
+        "alltrue",
+        FuncDef::builder().param(bool_list()).build(alltrue),
+    );
+    ctx.declare_func(
+        "anytrue",
+        FuncDef::builder().param(bool_list()).build(anytrue),
+    );
+    Ok(ctx)
+}
+
+fn str_arg(args: &FuncArgs, index: usize) -> &str {
+    args[index].as_str().unwrap_or_default()
+}
+
+fn length(args: FuncArgs) -> Result<Value, String> {
+    let len = match &args[0] {
+        Value::String(s) => s.chars().count(),
+        Value::Array(items) => items.len(),
+        Value::Object(map) => map.len(),
+        other => return Err(format!("length of {} is not defined", other)),
+    };
+    Ok(Value::Number(Number::from(len as u64)))
+}
+
+fn contains(args: FuncArgs) -> Result<Value, String> {
+    match &args[0] {
+        Value::Array(items) => Ok(Value::Bool(items.contains(&args[1]))),
+        other => Err(format!("contains expects a list, got {}", other)),
+    }
+}
+
+fn compile(pattern: &str) -> Result<regex::Regex, String> {
+    regex::Regex::new(pattern).map_err(|e| format!("invalid regex \"{}\": {}", pattern, e))
+}
+
+fn regex(args: FuncArgs) -> Result<Value, String> {
+    compile(str_arg(&args, 0))?
+        .find(str_arg(&args, 1))
+        .map(|m| Value::String(m.as_str().to_string()))
+        .ok_or_else(|| "pattern did not match any part of the given string".to_string())
+}
+
+fn regexall(args: FuncArgs) -> Result<Value, String> {
+    Ok(Value::Array(
+        compile(str_arg(&args, 0))?
+            .find_iter(str_arg(&args, 1))
+            .map(|m| Value::String(m.as_str().to_string()))
+            .collect(),
+    ))
+}
+
+fn startswith(args: FuncArgs) -> Result<Value, String> {
+    Ok(Value::Bool(
+        str_arg(&args, 0).starts_with(str_arg(&args, 1)),
+    ))
+}
+
+fn endswith(args: FuncArgs) -> Result<Value, String> {
+    Ok(Value::Bool(str_arg(&args, 0).ends_with(str_arg(&args, 1))))
+}
+
+fn lower(args: FuncArgs) -> Result<Value, String> {
+    Ok(Value::String(str_arg(&args, 0).to_lowercase()))
+}
+
+fn upper(args: FuncArgs) -> Result<Value, String> {
+    Ok(Value::String(str_arg(&args, 0).to_uppercase()))
+}
+
+fn trimspace(args: FuncArgs) -> Result<Value, String> {
+    Ok(Value::String(str_arg(&args, 0).trim().to_string()))
+}
+
+fn bools(args: &FuncArgs) -> impl Iterator<Item = bool> + '_ {
+    args[0]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|v| v.as_bool().unwrap_or(false))
+}
+
+fn alltrue(args: FuncArgs) -> Result<Value, String> {
+    Ok(Value::Bool(bools(&args).all(|b| b)))
+}
+
+fn anytrue(args: FuncArgs) -> Result<Value, String> {
+    Ok(Value::Bool(bools(&args).any(|b| b)))
+}
@@ -1,4 +1,6 @@
-use env_defs::{DeploymentManifest, ModuleResp};
+use env_defs::{DeploymentManifest, ModuleResp, SecretRef, TfVariable, ValueFrom};
+
+use crate::tf_validation::failed_validations;
 
 pub fn verify_variable_claim_casing(
     claim: &DeploymentManifest,
@@ -34,6 +36,16 @@ pub fn verify_variable_existence_and_type(
     let mut errors = Vec::new();
 
     let re = regex::Regex::new(r"\{\{\s*(\w+)::(\w+)::(\w+)\s*\}\}").unwrap();
+    // Values seen by validation conditions as `var`, with defaults for variables not set
+    let mut validation_vars = serde_json::Map::new();
+    for variable in claim_variables(module) {
+        if let Some(default) = &variable.default {
+            validation_vars.insert(variable.name.clone(), default.clone());
+        }
+    }
+    validation_vars.extend(variables_map.clone());
+    let validation_vars = serde_json::Value::Object(validation_vars);
+
     for (variable_key, variable_value) in variables_map {
         match module
             .tf_variables
@@ -47,6 +59,27 @@ pub fn verify_variable_existence_and_type(
             .find(|v| v.name == *variable_key)
         {
             Some(module_variable) => {
+                // Secrets are read by the runner, their values can't be checked here
+                if let Some(secret_ref) = SecretRef::from_value(variable_value) {
+                    if let Err(e) = secret_ref {
+                        errors.push(format!(
+                            "Variable \"{}\" has an invalid secretRef: {}",
+                            variable_key, e
+                        ));
+                    }
+                    continue;
+                }
+                // Outputs of other deployments are read by the runner as well
+                if let Some(value_from) = ValueFrom::from_value(variable_value) {
+                    if let Err(e) = value_from {
+                        errors.push(format!(
+                            "Variable \"{}\" has an invalid valueFrom: {}",
+                            variable_key, e
+                        ));
+                    }
+                    continue;
+                }
+
                 let variable_value_type = match variable_value {
                     serde_json::Value::String(_) => "string",
                     serde_json::Value::Number(_) => "number",
@@ -77,11 +110,7 @@ pub fn verify_variable_existence_and_type(
 
                 let is_reference = variable_value.as_str().is_some_and(|s| re.is_match(s));
 
-                if module_variable_type == "any" {
-                    continue;
-                }
-
-                if variable_value_type != module_variable_type {
+                if module_variable_type != "any" && variable_value_type != module_variable_type {
                     if is_reference {
                         log::warn!("
                             Variable \"{}\" is a reference and its type is not checked since output type of reference cannot be implied. Please ensure it matches the expected type.",
@@ -99,6 +128,15 @@ pub fn verify_variable_existence_and_type(
                             variable_key, variable_value_type, module_variable_type
                         ));
                     }
+                } else if !is_reference {
+                    for message in
+                        failed_validations(module_variable, variable_value, &validation_vars)
+                    {
+                        errors.push(format!(
+                            "Variable \"{}\" is invalid: {}",
+                            variable_key, message
+                        ));
+                    }
                 }
             }
             None => {
@@ -117,12 +155,8 @@ pub fn verify_variable_existence_and_type(
     }
 }
 
-pub fn verify_required_variables_are_set(
-    module: &ModuleResp,
-    variables: &serde_json::Value,
-) -> Result<(), anyhow::Error> {
-    let mut missing_variables = vec![];
-    let module_variables = &module.tf_variables;
+/// Variables a claim can set for `module`: its own variables followed by those of its providers
+pub fn claim_variables(module: &ModuleResp) -> Vec<&TfVariable> {
     let mut provider_variables = module
         .tf_providers
         .iter()
@@ -130,20 +164,36 @@ pub fn verify_required_variables_are_set(
         .collect::<Vec<_>>();
     provider_variables.sort_by_key(|v| &v.name);
     provider_variables.dedup_by_key(|v| &v.name);
-    let provider_variables = provider_variables;
+    module
+        .tf_variables
+        .iter()
+        .chain(provider_variables)
+        .collect()
+}
+
+/// Whether a claim must set `variable`
+pub fn is_required_variable(variable: &TfVariable) -> bool {
+    if variable.nullable && variable.default == Some(serde_json::Value::Null) {
+        // If the variable is nullable and has a default value, it is not required
+        return false;
+    }
+    if variable.default.is_some() && variable.default != Some(serde_json::Value::Null) {
+        // If the variable has a default value, it is not required anyway
+        return false;
+    }
+    // If the variable is not nullable and has no default value, it is required
+    true
+}
+
+pub fn verify_required_variables_are_set(
+    module: &ModuleResp,
+    variables: &serde_json::Value,
+) -> Result<(), anyhow::Error> {
+    let mut missing_variables = vec![];
     let variables_map = variables.as_object().unwrap();
-    for variable in module_variables.iter().chain(provider_variables) {
-        if variable.nullable && variable.default == Some(serde_json::Value::Null) {
-            // If the variable is nullable and has a default value, it is not required
-            continue;
-        }
-        if variable.default.is_some() && variable.default != Some(serde_json::Value::Null) {
-            // If the variable has a default value, it is not required anyway
-            continue;
-        }
-        // If the variable is not nullable and has no default value, it is required;
-        // Ensure the variable is set
-        if !variables_map.contains_key(variable.name.as_str()) {
+    for variable in claim_variables(module) {
+        // Ensure the required variable is set
+        if is_required_variable(variable) && !variables_map.contains_key(variable.name.as_str()) {
             missing_variables.push(variable.name.clone());
         }
     }
@@ -231,15 +281,199 @@ pub fn verify_output_name_roundtrip(
     }
 }
 
+/// Merges the variables of a Terraform variable file (tfvars) into `spec.variables` of a claim.
+///
+/// Variables already set in the claim take precedence over the ones in the variable file, and
+/// the tfvars names are converted to camelCase to match the claim. Validation against the module
+/// schema happens afterwards together with the rest of the claim variables.
+pub fn merge_tfvars_into_claim(
+    claim: &mut serde_yaml::Value,
+    tfvars: &str,
+) -> Result<(), anyhow::Error> {
+    let tfvars_value: serde_json::Value = hcl::from_str(tfvars)
+        .map_err(|e| anyhow::anyhow!("Failed to parse variable file: {}", e))?;
+    merge_variables_into_claim(claim, tfvars_value, false)
+}
+
+/// Merges a variable file into `spec.variables` of a claim like [`merge_tfvars_into_claim`].
+/// Files ending with `.yaml`, `.yml` or `.json` are read as YAML (of which JSON is a subset),
+/// any other file as tfvars.
+pub fn merge_var_file_into_claim(
+    claim: &mut serde_yaml::Value,
+    file_name: &str,
+    contents: &str,
+) -> Result<(), anyhow::Error> {
+    let extension = std::path::Path::new(file_name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+    match extension.as_deref() {
+        Some("yaml") | Some("yml") | Some("json") => {
+            let variables: serde_json::Value = serde_yaml::from_str(contents)
+                .map_err(|e| anyhow::anyhow!("Failed to parse variable file: {}", e))?;
+            merge_variables_into_claim(claim, variables, false)
+        }
+        _ => merge_tfvars_into_claim(claim, contents),
+    }
+}
+
+/// Sets `key=value` variable overrides, such as `--var` of the CLI, in `spec.variables` of a
+/// claim. Unlike variable files the overrides replace variables set in the claim. Keys are
+/// converted to camelCase, and values are read as JSON when they parse as JSON (`3`, `true`,
+/// `["a", "b"]`) and as strings otherwise.
+pub fn set_claim_variable_overrides(
+    claim: &mut serde_yaml::Value,
+    overrides: &[String],
+) -> Result<(), anyhow::Error> {
+    let mut variables = serde_json::Map::new();
+    for variable in overrides {
+        let (key, value) = variable.split_once('=').ok_or_else(|| {
+            anyhow::anyhow!("Invalid variable '{}', expected key=value", variable)
+        })?;
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Invalid variable '{}', the key is empty",
+                variable
+            ));
+        }
+        let value = serde_json::from_str(value)
+            .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+        variables.insert(key.to_string(), value);
+    }
+    merge_variables_into_claim(claim, serde_json::Value::Object(variables), true)
+}
+
+fn merge_variables_into_claim(
+    claim: &mut serde_yaml::Value,
+    new_variables: serde_json::Value,
+    overwrite: bool,
+) -> Result<(), anyhow::Error> {
+    let new_variables = match new_variables {
+        serde_json::Value::Object(new_variables) => new_variables,
+        // An empty YAML file has no variables
+        serde_json::Value::Null => return Ok(()),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Expected variable file to only contain variables"
+            ))
+        }
+    };
+
+    let spec = claim
+        .get_mut("spec")
+        .and_then(|spec| spec.as_mapping_mut())
+        .ok_or_else(|| anyhow::anyhow!("Claim is missing spec"))?;
+    let variables_key = serde_yaml::Value::from("variables");
+    if !matches!(
+        spec.get(&variables_key),
+        Some(serde_yaml::Value::Mapping(_))
+    ) {
+        spec.insert(
+            variables_key.clone(),
+            serde_yaml::Value::Mapping(serde_yaml::Mapping::new()),
+        );
+    }
+    let variables = spec
+        .get_mut(&variables_key)
+        .and_then(|variables| variables.as_mapping_mut())
+        .unwrap();
+
+    for (name, value) in new_variables {
+        let key = serde_yaml::Value::from(crate::to_camel_case(&name));
+        if overwrite || !variables.contains_key(&key) {
+            variables.insert(key, serde_yaml::to_value(value)?);
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use env_defs::{
         Metadata, ModuleManifest, ModuleSpec, ProviderManifest, ProviderMetaData, ProviderResp,
-        ProviderSpec, TfOutput, TfVariable,
+        ProviderSpec, TfOutput, TfValidation, TfVariable,
     };
     use serde_json::Value;
 
+    #[test]
+    fn test_merge_tfvars_into_claim() {
+        let mut claim: serde_yaml::Value = serde_yaml::from_str(
+            r#"
+spec:
+  region: us-west-2
+  variables:
+    bucketName: from-claim
+"#,
+        )
+        .unwrap();
+        let tfvars = r#"
+bucket_name = "from-tfvars"
+enable_acl  = true
+tags = {
+  Environment = "dev"
+}
+"#;
+
+        merge_tfvars_into_claim(&mut claim, tfvars).unwrap();
+
+        let variables = &claim["spec"]["variables"];
+        assert_eq!(variables["bucketName"], "from-claim");
+        assert_eq!(variables["enableAcl"], true);
+        assert_eq!(variables["tags"]["Environment"], "dev");
+    }
+
+    #[test]
+    fn test_merge_tfvars_into_claim_without_variables() {
+        let mut claim: serde_yaml::Value =
+            serde_yaml::from_str("spec:\n  region: us-west-2\n").unwrap();
+
+        merge_tfvars_into_claim(&mut claim, "bucket_name = \"from-tfvars\"").unwrap();
+
+        assert_eq!(claim["spec"]["variables"]["bucketName"], "from-tfvars");
+    }
+
+    #[test]
+    fn test_merge_var_file_into_claim_yaml() {
+        let mut claim: serde_yaml::Value =
+            serde_yaml::from_str("spec:\n  variables:\n    bucketName: from-claim\n").unwrap();
+        let yaml = "bucket_name: from-file\nenableAcl: true\nports: [80, 443]\n";
+
+        merge_var_file_into_claim(&mut claim, "vars.yaml", yaml).unwrap();
+
+        let variables = &claim["spec"]["variables"];
+        assert_eq!(variables["bucketName"], "from-claim");
+        assert_eq!(variables["enableAcl"], true);
+        assert_eq!(variables["ports"][1], 443);
+
+        merge_var_file_into_claim(&mut claim, "vars.tfvars", "tags = { Team = \"a\" }").unwrap();
+        assert_eq!(claim["spec"]["variables"]["tags"]["Team"], "a");
+        assert!(merge_var_file_into_claim(&mut claim, "vars.yml", "- a\n- b\n").is_err());
+    }
+
+    #[test]
+    fn test_set_claim_variable_overrides() {
+        let mut claim: serde_yaml::Value =
+            serde_yaml::from_str("spec:\n  variables:\n    bucketName: from-claim\n").unwrap();
+        let overrides = [
+            "bucket_name=from-cli".to_string(),
+            "replicas=3".to_string(),
+            "tags={\"Team\": \"a\"}".to_string(),
+            "suffix=a=b".to_string(),
+        ];
+
+        set_claim_variable_overrides(&mut claim, &overrides).unwrap();
+
+        let variables = &claim["spec"]["variables"];
+        assert_eq!(variables["bucketName"], "from-cli");
+        assert_eq!(variables["replicas"], 3);
+        assert_eq!(variables["tags"]["Team"], "a");
+        assert_eq!(variables["suffix"], "a=b");
+        assert!(set_claim_variable_overrides(&mut claim, &["replicas".to_string()]).is_err());
+        assert!(set_claim_variable_overrides(&mut claim, &["=3".to_string()]).is_err());
+    }
+
     #[test]
     fn test_variables_in_claim() {
         let module = s3bucket_module();
@@ -458,6 +692,8 @@ mod tests {
                     cpu: None,
                     memory: None,
                     providers: Vec::with_capacity(0),
+                    owners: vec![],
+                    ui: None,
                 },
             },
             tf_outputs: vec![],
@@ -468,6 +704,7 @@ mod tests {
                 description: "Configuration object".to_string(),
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             }],
             tf_extra_environment_variables: vec![],
             tf_providers: vec![],
@@ -479,6 +716,11 @@ mod tests {
             memory: "4096".to_string(),
             deprecated: false,
             deprecated_message: None,
+            readme: None,
+            changelog: None,
+            digest: None,
+            oci_reference: None,
+            yanked: None,
         };
 
         let variables = serde_json::json!({
@@ -525,6 +767,8 @@ mod tests {
                     cpu: None,
                     memory: None,
                     providers: Vec::with_capacity(0),
+                    owners: vec![],
+                    ui: None,
                 },
             },
             tf_outputs: vec![],
@@ -535,6 +779,7 @@ mod tests {
                 description: "Configuration object".to_string(),
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             }],
             tf_extra_environment_variables: vec![],
             tf_providers: vec![],
@@ -546,6 +791,11 @@ mod tests {
             memory: "4096".to_string(),
             deprecated: false,
             deprecated_message: None,
+            readme: None,
+            changelog: None,
+            digest: None,
+            oci_reference: None,
+            yanked: None,
         };
 
         let variables = serde_json::json!({
@@ -594,6 +844,8 @@ mod tests {
                     cpu: None,
                     memory: None,
                     providers: Vec::with_capacity(0),
+                    owners: vec![],
+                    ui: None,
                 },
             },
             tf_outputs: vec![],
@@ -605,6 +857,7 @@ mod tests {
                     description: "A nullable variable with a default value".to_string(),
                     nullable: true,
                     sensitive: false,
+                    validations: vec![],
                 },
                 TfVariable {
                     name: "another_var".to_string(),
@@ -613,6 +866,7 @@ mod tests {
                     description: "A required non-nullable variable".to_string(),
                     nullable: false,
                     sensitive: false,
+                    validations: vec![],
                 },
             ],
             tf_extra_environment_variables: vec![],
@@ -625,6 +879,11 @@ mod tests {
             memory: "4096".to_string(),
             deprecated: false,
             deprecated_message: None,
+            readme: None,
+            changelog: None,
+            digest: None,
+            oci_reference: None,
+            yanked: None,
         };
 
         // Test that setting a nullable variable to null is allowed
@@ -672,6 +931,8 @@ mod tests {
                     cpu: None,
                     memory: None,
                     providers: Vec::with_capacity(0),
+                    owners: vec![],
+                    ui: None,
                 },
             },
             tf_outputs: vec![],
@@ -682,6 +943,7 @@ mod tests {
                 description: "A non-nullable required variable".to_string(),
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             }],
             tf_extra_environment_variables: vec![],
             tf_providers: Vec::with_capacity(0),
@@ -693,6 +955,11 @@ mod tests {
             memory: "4096".to_string(),
             deprecated: false,
             deprecated_message: None,
+            readme: None,
+            changelog: None,
+            digest: None,
+            oci_reference: None,
+            yanked: None,
         };
 
         // Test that setting a non-nullable variable to null fails
@@ -713,6 +980,103 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_secret_ref_variables_skip_type_check() {
+        let module = s3bucket_module_with_validations();
+        let variables = serde_json::json!({
+            "bucket_name": { "secretRef": { "provider": "aws-ssm", "key": "/buckets/name" } },
+        });
+        assert!(verify_variable_existence_and_type(&module, &variables).is_ok());
+
+        let variables = serde_json::json!({
+            "bucket_name": { "secretRef": { "provider": "vault", "key": "/buckets/name" } },
+        });
+        let err = verify_variable_existence_and_type(&module, &variables)
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Variable \"bucket_name\" has an invalid secretRef"));
+    }
+
+    #[test]
+    fn test_value_from_variables_skip_type_check() {
+        let module = s3bucket_module_with_validations();
+        let variables = serde_json::json!({
+            "bucket_name": { "valueFrom": { "deployment": "naming/main", "output": "bucketName" } },
+        });
+        assert!(verify_variable_existence_and_type(&module, &variables).is_ok());
+
+        let variables = serde_json::json!({
+            "bucket_name": { "valueFrom": { "deployment": "naming/main" } },
+        });
+        let err = verify_variable_existence_and_type(&module, &variables)
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Variable \"bucket_name\" has an invalid valueFrom"));
+    }
+
+    fn s3bucket_module_with_validations() -> ModuleResp {
+        let mut module = s3bucket_module();
+        module.tf_variables[0].validations = vec![
+            TfValidation {
+                expression: r#"can(regex("^[a-z0-9-]+$", var.bucket_name))"#.to_string(),
+                message: "Bucket names may only contain lowercase letters, digits and hyphens."
+                    .to_string(),
+            },
+            TfValidation {
+                expression: "length(var.bucket_name) >= 3 && length(var.bucket_name) <= 63"
+                    .to_string(),
+                message: "Bucket names must be between 3 and 63 characters long.".to_string(),
+            },
+            TfValidation {
+                expression: r#"!contains(["reserved"], var.bucket_name)"#.to_string(),
+                message: "This bucket name is reserved.".to_string(),
+            },
+        ];
+        module
+    }
+
+    #[test]
+    fn test_variable_validations_pass() {
+        let module = s3bucket_module_with_validations();
+        let variables = serde_json::json!({ "bucket_name": "my-bucket-123" });
+
+        assert!(verify_variable_existence_and_type(&module, &variables).is_ok());
+    }
+
+    #[test]
+    fn test_variable_validations_fail_with_error_message() {
+        let module = s3bucket_module_with_validations();
+        let variables = serde_json::json!({ "bucket_name": "My_Bucket" });
+
+        let err = verify_variable_existence_and_type(&module, &variables)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "Variable \"bucket_name\" is invalid: Bucket names may only contain lowercase letters, digits and hyphens."
+        );
+
+        let variables = serde_json::json!({ "bucket_name": "reserved" });
+        let err = verify_variable_existence_and_type(&module, &variables)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("This bucket name is reserved."));
+    }
+
+    #[test]
+    fn test_variable_validations_skip_references_and_unsupported_functions() {
+        let mut module = s3bucket_module_with_validations();
+        module.tf_variables[0].validations.push(TfValidation {
+            expression: "cidrhost(var.bucket_name, 1) != \"\"".to_string(),
+            message: "Not checked before the job runs.".to_string(),
+        });
+        let variables = serde_json::json!({ "bucket_name": "{{ S3Bucket::bucket1::BucketName }}" });
+        assert!(verify_variable_existence_and_type(&module, &variables).is_ok());
+
+        let variables = serde_json::json!({ "bucket_name": "my-bucket" });
+        assert!(verify_variable_existence_and_type(&module, &variables).is_ok());
+    }
+
     fn s3bucket_module() -> ModuleResp {
         ModuleResp {
             oci_artifact_set: None,
@@ -743,6 +1107,8 @@ mod tests {
                     providers: vec![env_defs::Provider {
                         name: "aws-5-default".to_string(),
                     }],
+                    owners: vec![],
+                    ui: None,
                 },
             },
             tf_outputs: vec![
@@ -763,6 +1129,7 @@ mod tests {
                     _type: Value::String("string".to_string()),
                     nullable: false,
                     sensitive: false,
+                    validations: vec![],
                 },
                 TfVariable {
                     default: Some(serde_json::Value::Null),
@@ -771,6 +1138,7 @@ mod tests {
                     _type: Value::Bool(false),
                     nullable: false,
                     sensitive: false,
+                    validations: vec![],
                 },
                 TfVariable {
                     default: Some(serde_json::Value::Null), // This is set to null
@@ -779,6 +1147,7 @@ mod tests {
                     _type: Value::Null,
                     nullable: true,
                     sensitive: false,
+                    validations: vec![],
                 },
                 TfVariable {
                     default: None, // This is not set
@@ -787,6 +1156,7 @@ mod tests {
                     _type: Value::String("string".to_string()),
                     nullable: true,
                     sensitive: false,
+                    validations: vec![],
                 },
             ],
             tf_extra_environment_variables: vec![],
@@ -826,6 +1196,7 @@ mod tests {
                             _type: Value::String("map(string)".to_string()),
                             nullable: false,
                             sensitive: false,
+                            validations: vec![],
                         }
                     ],
                     tf_extra_environment_variables: Vec::new(),
@@ -833,6 +1204,11 @@ mod tests {
             ],
             deprecated: false,
             deprecated_message: None,
+            readme: None,
+            changelog: None,
+            digest: None,
+            oci_reference: None,
+            yanked: None,
         }
     }
 
@@ -849,6 +1225,7 @@ mod tests {
                 default: None,
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             },
             TfVariable {
                 name: "max_size".to_string(),
@@ -857,6 +1234,7 @@ mod tests {
                 default: None,
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             },
             TfVariable {
                 name: "enable_logging".to_string(),
@@ -865,6 +1243,7 @@ mod tests {
                 default: None,
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             },
         ];
 
@@ -883,6 +1262,7 @@ mod tests {
                 default: None,
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             },
             TfVariable {
                 name: "region".to_string(),
@@ -891,6 +1271,7 @@ mod tests {
                 default: None,
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             },
         ];
 
@@ -909,6 +1290,7 @@ mod tests {
                 default: None,
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             },
             TfVariable {
                 name: "bucket_v2".to_string(),
@@ -917,6 +1299,7 @@ mod tests {
                 default: None,
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             },
         ];
 
@@ -935,6 +1318,7 @@ mod tests {
             default: None,
             nullable: false,
             sensitive: false,
+            validations: vec![],
         }];
 
         let result = verify_variable_name_roundtrip(&variables);
@@ -955,6 +1339,7 @@ mod tests {
                 default: None,
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             },
             TfVariable {
                 name: "bucket_name".to_string(),
@@ -963,6 +1348,7 @@ mod tests {
                 default: None,
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             },
         ];
 
@@ -983,6 +1369,7 @@ mod tests {
             default: None,
             nullable: false,
             sensitive: false,
+            validations: vec![],
         }];
 
         let result = verify_variable_name_roundtrip(&variables);
@@ -1011,6 +1398,7 @@ mod tests {
             default: None,
             nullable: false,
             sensitive: false,
+            validations: vec![],
         }];
 
         let result = verify_variable_name_roundtrip(&variables);
@@ -1035,6 +1423,7 @@ mod tests {
             default: None,
             nullable: false,
             sensitive: false,
+            validations: vec![],
         }];
 
         let result = verify_variable_name_roundtrip(&variables);
@@ -1055,6 +1444,7 @@ mod tests {
                 default: None,
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             },
             TfVariable {
                 name: "maxSize".to_string(), // Invalid - camelCase
@@ -1063,6 +1453,7 @@ mod tests {
                 default: None,
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             },
             TfVariable {
                 name: "enable_logging".to_string(), // Valid
@@ -1071,6 +1462,7 @@ mod tests {
                 default: None,
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             },
             TfVariable {
                 name: "tag__value".to_string(), // Invalid - double underscore
@@ -1079,6 +1471,7 @@ mod tests {
                 default: None,
                 nullable: false,
                 sensitive: false,
+                validations: vec![],
             },
         ];
 